@@ -56,7 +56,7 @@ use smithay::{
             control::{connector, crtc, Device as ControlDevice, ModeTypeFlags},
             Device as _,
         },
-        input::Libinput,
+        input::{event::Event as InputEventKind, Libinput},
         nix::{fcntl::OFlag, sys::stat::dev_t},
         wayland_protocols::wp::{
             linux_dmabuf::zv1::server::zwp_linux_dmabuf_feedback_v1,
@@ -154,6 +154,9 @@ pub fn init_backend(
             if let &mut InputEvent::DeviceAdded { ref mut device } = &mut event {
                 data.state.common.config.read_device(device);
             }
+            if let InputEvent::Special(InputEventKind::TabletPad(event)) = &event {
+                data.state.process_tablet_pad_event(event);
+            }
             data.state.process_input_event(event);
             for output in data.state.common.shell.outputs() {
                 if let Err(err) = data.state.backend.kms().schedule_render(
@@ -16,7 +16,7 @@ use crate::{
         layout::{floating::SeatMoveGrabState, tiling::ANIMATION_DURATION},
         CosmicMapped, CosmicMappedRenderElement, WorkspaceRenderElement,
     },
-    state::{Common, Fps},
+    state::{Common, Fps, State},
     utils::prelude::{OutputExt, SeatExt},
     wayland::{
         handlers::{
@@ -60,6 +60,7 @@ use smithay::{
         },
     },
     desktop::layer_map_for_output,
+    input::Seat,
     output::Output,
     utils::{IsAlive, Logical, Physical, Point, Rectangle, Scale, Size},
     wayland::{
@@ -241,6 +242,41 @@ pub enum CursorMode {
     All,
 }
 
+/// Last software cursor footprint of a seat per output, in global coordinates.
+#[derive(Default)]
+struct CursorFootprints(RefCell<HashMap<String, Rectangle<i32, Logical>>>);
+
+/// Lets iced elements below the software cursor composite the area it moved over again.
+fn report_cursor_footprint<R>(
+    seat: &Seat<State>,
+    output: &Output,
+    cursor: &[CursorRenderElement<R>],
+    scale: f64,
+) where
+    R: Renderer + ImportAll + ImportMem,
+    <R as Renderer>::TextureId: 'static,
+{
+    let footprint = cursor
+        .iter()
+        .map(|element| element.geometry(scale.into()))
+        .reduce(|a, b| a.merge(b))
+        .map(|geometry| {
+            let local = geometry.to_f64().to_logical(scale).to_i32_up();
+            Rectangle::from_loc_and_size(local.loc + output.current_location(), local.size)
+        });
+
+    seat.user_data()
+        .insert_if_missing(CursorFootprints::default);
+    let Some(footprints) = seat.user_data().get::<CursorFootprints>() else { return };
+    let mut footprints = footprints.0.borrow_mut();
+    let old = match footprint {
+        Some(footprint) => footprints.insert(output.name(), footprint),
+        None => footprints.remove(&output.name()),
+    };
+    drop(footprints);
+    crate::utils::iced::cursor_moved(output, old, footprint);
+}
+
 pub fn cursor_elements<'frame, E, R>(
     renderer: &mut R,
     state: &Common,
@@ -268,18 +304,16 @@ where
         let location = pointer.current_location() - output.current_location().to_f64();
 
         if mode != CursorMode::None {
-            elements.extend(
-                cursor::draw_cursor(
-                    renderer,
-                    seat,
-                    location,
-                    scale.into(),
-                    state.clock.now(),
-                    mode != CursorMode::NotDefault,
-                )
-                .into_iter()
-                .map(E::from),
+            let cursor = cursor::draw_cursor(
+                renderer,
+                seat,
+                location,
+                scale.into(),
+                state.clock.now(),
+                mode != CursorMode::NotDefault,
             );
+            report_cursor_footprint(seat, output, &cursor, scale);
+            elements.extend(cursor.into_iter().map(E::from));
         }
 
         if let Some(wl_surface) = get_dnd_icon(seat) {
@@ -302,6 +336,19 @@ where
         }
     }
 
+    // icons aren't mapped into any space, so they have no buffer for this output's scale yet
+    for (icon, location) in crate::utils::iced::internal_drag_icons() {
+        let location =
+            (location - output.current_location().to_f64()).to_physical_precise_round(scale);
+        elements.extend(
+            icon.render_standalone(renderer.glow_renderer_mut(), location, scale.into(), 1.0)
+                .into_iter()
+                .map(CosmicWindowRenderElement::<R>::from)
+                .map(CosmicMappedRenderElement::from)
+                .map(E::from),
+        );
+    }
+
     elements
 }
 
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Live render statistics of cosmic-comp's internal elements.
+//!
+//! Connects to the socket given by `COSMIC_COMP_TELEMETRY_SOCKET` (or the first argument)
+//! and prints per-element averages once per second.
+//! See `src/utils/iced/telemetry.rs` for the record format.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Read},
+    os::unix::net::UnixStream,
+    time::{Duration, Instant},
+};
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct Stats {
+    name: String,
+    frames: u64,
+    skipped: u64,
+    update_us: u64,
+    draw_us: u64,
+    upload_us: u64,
+    size: (i32, i32),
+    /// Primitives with invalid geometry, that were dropped or clamped
+    sanitized: u64,
+}
+
+/// Fixed fields of a record, followed by the element's name.
+const HEADER_LEN: usize = 47;
+/// Names are at most `u16::MAX` bytes long, anything longer isn't a record.
+const MAX_RECORD_LEN: usize = HEADER_LEN + u16::MAX as usize;
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn bytes_at<const N: usize>(bytes: &[u8], offset: usize) -> io::Result<[u8; N]> {
+    bytes
+        .get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid("short record"))
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> io::Result<u64> {
+    bytes_at(bytes, offset).map(u64::from_le_bytes)
+}
+
+fn i32_at(bytes: &[u8], offset: usize) -> io::Result<i32> {
+    bytes_at(bytes, offset).map(i32::from_le_bytes)
+}
+
+fn read_record(stream: &mut UnixStream, stats: &mut BTreeMap<u64, Stats>) -> io::Result<()> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len < HEADER_LEN {
+        return Err(invalid("short record"));
+    }
+    if len > MAX_RECORD_LEN {
+        return Err(invalid("record too long"));
+    }
+    let mut record = vec![0u8; len];
+    stream.read_exact(&mut record)?;
+
+    let name_len = u16::from_le_bytes(bytes_at(&record, 45)?) as usize;
+    let name = record
+        .get(HEADER_LEN..HEADER_LEN + name_len)
+        .ok_or_else(|| invalid("truncated element name"))?;
+    let entry = stats.entry(u64_at(&record, 0)?).or_default();
+    entry.name = String::from_utf8_lossy(name).into_owned();
+    entry.frames += 1;
+    entry.update_us += u64_at(&record, 8)?;
+    entry.draw_us += u64_at(&record, 16)?;
+    entry.upload_us += u64_at(&record, 24)?;
+    entry.size = (i32_at(&record, 32)?, i32_at(&record, 36)?);
+    entry.skipped += (record[40] != 0) as u64;
+    entry.sanitized += u32::from_le_bytes(bytes_at(&record, 41)?) as u64;
+    Ok(())
+}
+
+fn report(stats: &BTreeMap<u64, Stats>) {
+    println!(
+        "{:<24} {:>10} {:>7} {:>7} {:>10} {:>10} {:>10} {:>9}",
+        "element", "size", "frames", "skipped", "update µs", "draw µs", "upload µs", "sanitized"
+    );
+    for (id, stats) in stats {
+        let name = if stats.name.is_empty() {
+            format!("{:#x}", id)
+        } else {
+            stats.name.clone()
+        };
+        let frames = stats.frames.max(1);
+        let drawn = stats.frames.saturating_sub(stats.skipped).max(1);
+        println!(
+            "{:<24} {:>10} {:>7} {:>7} {:>10} {:>10} {:>10} {:>9}",
+            name,
+            format!("{}x{}", stats.size.0, stats.size.1),
+            stats.frames,
+            stats.skipped,
+            stats.update_us / frames,
+            stats.draw_us / drawn,
+            stats.upload_us / frames,
+            stats.sanitized,
+        );
+    }
+    println!();
+}
+
+fn main() -> io::Result<()> {
+    let path = std::env::args_os()
+        .nth(1)
+        .or_else(|| std::env::var_os("COSMIC_COMP_TELEMETRY_SOCKET"))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "usage: cosmic-comp-mon <socket>, or set COSMIC_COMP_TELEMETRY_SOCKET",
+            )
+        })?;
+    let mut stream = UnixStream::connect(path)?;
+
+    let mut stats = BTreeMap::new();
+    let mut last_report = Instant::now();
+    loop {
+        read_record(&mut stream, &mut stats)?;
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            report(&stats);
+            stats.clear();
+            last_report = Instant::now();
+        }
+    }
+}
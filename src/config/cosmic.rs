@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Configuration in the layout of `cosmic-config`, shared with the rest of the desktop.
+//!
+//! Every key of a config is a ron file at `cosmic/{id}/v{version}/{key}`. It is read from
+//! `$XDG_CONFIG_HOME` if the user (e.g. the settings) wrote it, and from the defaults shipped
+//! by the system in `$XDG_DATA_DIRS` otherwise. Only the user layer is watched for changes.
+
+use crate::state::Data;
+use anyhow::{Context, Result};
+use inotify::{Inotify, WatchMask};
+use serde::de::DeserializeOwned;
+use smithay::reexports::calloop::{
+    generic::Generic, Interest, LoopHandle, Mode, PostAction, RegistrationToken,
+};
+use std::{io, path::PathBuf};
+use tracing::warn;
+
+#[derive(Debug, Clone)]
+pub struct CosmicConfig {
+    id: String,
+    version: u64,
+    xdg: xdg::BaseDirectories,
+}
+
+impl CosmicConfig {
+    pub fn new(id: &str, version: u64) -> Result<CosmicConfig> {
+        Ok(CosmicConfig {
+            id: id.to_string(),
+            version,
+            xdg: xdg::BaseDirectories::with_prefix("cosmic")?,
+        })
+    }
+
+    fn key_path(&self, key: &str) -> String {
+        format!("{}/v{}/{}", self.id, self.version, key)
+    }
+
+    /// Path of `key`, the user's if written, the system default otherwise.
+    fn find(&self, key: &str) -> Option<PathBuf> {
+        let path = self.key_path(key);
+        self.xdg
+            .find_config_file(&path)
+            .or_else(|| self.xdg.find_data_file(&path))
+    }
+
+    /// Reads `key`, `None` if it is neither set by the user nor by the system.
+    ///
+    /// Values failing to parse are logged and treated as unset.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let path = self.find(key)?;
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                warn!(?err, id = self.id, key, "Failed to read config value");
+                return None;
+            }
+        };
+        match ron::from_str(&content) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                warn!(?err, ?path, "Failed to parse config value");
+                None
+            }
+        }
+    }
+
+    /// Calls `callback` with the changed keys every time the user's layer changes.
+    ///
+    /// Like `cosmic-config`, this creates the user's config directory if it is missing.
+    pub fn watch<F>(
+        &self,
+        handle: &LoopHandle<'static, Data>,
+        mut callback: F,
+    ) -> Result<RegistrationToken>
+    where
+        F: FnMut(&[String]) + 'static,
+    {
+        let dir = self
+            .xdg
+            .create_config_directory(format!("{}/v{}", self.id, self.version))
+            .with_context(|| format!("Failed to create the config directory of {}", self.id))?;
+        let inotify = Inotify::init().with_context(|| "Failed to init inotify")?;
+        inotify
+            .watches()
+            .add(
+                &dir,
+                // `cosmic-config` replaces files atomically, editors may write them in place
+                WatchMask::CLOSE_WRITE
+                    | WatchMask::MOVED_TO
+                    | WatchMask::MOVED_FROM
+                    | WatchMask::DELETE,
+            )
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+
+        let mut buffer = [0; 4096];
+        handle
+            .insert_source(
+                Generic::new(inotify, Interest::READ, Mode::Level),
+                move |_, inotify, _| {
+                    let mut keys = Vec::new();
+                    loop {
+                        match inotify.read_events(&mut buffer) {
+                            Ok(events) => {
+                                for name in events.filter_map(|event| event.name) {
+                                    let key = name.to_string_lossy().into_owned();
+                                    if !keys.contains(&key) {
+                                        keys.push(key);
+                                    }
+                                }
+                            }
+                            Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                            Err(err) => return Err(err),
+                        }
+                    }
+                    if !keys.is_empty() {
+                        callback(&keys);
+                    }
+                    Ok(PostAction::Continue)
+                },
+            )
+            .map_err(|err| err.error)
+            .with_context(|| format!("Failed to init the config watcher of {}", self.id))
+    }
+}
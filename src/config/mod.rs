@@ -23,7 +23,11 @@ pub use smithay::{
 use std::{cell::RefCell, collections::HashMap, fs::OpenOptions, path::PathBuf};
 use tracing::{debug, error, info, warn};
 
+mod cosmic;
+mod theme;
 mod types;
+pub use self::cosmic::*;
+pub use self::theme::*;
 pub use self::types::*;
 
 pub struct Config {
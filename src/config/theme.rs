@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+    config::CosmicConfig,
+    state::Data,
+    utils::iced::{
+        begin_global_changes, commit_global_changes, set_default_fonts, set_default_theme,
+        FontConfig,
+    },
+};
+use cosmic::Theme;
+use serde::Deserialize;
+use smithay::reexports::calloop::LoopHandle;
+use std::{cell::RefCell, rc::Rc};
+use tracing::{info, warn};
+
+/// cosmic-config of the desktop's light or dark mode, as switched by the settings.
+const THEME_MODE: (&str, u64) = ("com.system76.CosmicTheme.Mode", 1);
+/// cosmic-config of the toolkit, holding the fonts of the desktop.
+const TOOLKIT: (&str, u64) = ("com.system76.CosmicTk", 1);
+
+/// Whether the desktop uses the light or the dark theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeMode {
+    Dark,
+    Light,
+}
+
+impl Default for ThemeMode {
+    fn default() -> Self {
+        ThemeMode::Dark
+    }
+}
+
+impl ThemeMode {
+    /// Reads the `is_dark` key of the theme mode config, dark if unset.
+    fn load(config: &CosmicConfig) -> ThemeMode {
+        match config.get::<bool>("is_dark") {
+            Some(false) => ThemeMode::Light,
+            _ => ThemeMode::Dark,
+        }
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeMode::Dark => Theme::dark(),
+            ThemeMode::Light => Theme::light(),
+        }
+    }
+}
+
+/// Font of the toolkit config, only its family is used.
+#[derive(Debug, Deserialize)]
+struct ToolkitFont {
+    family: String,
+}
+
+/// Reads the interface and monospace fonts of the toolkit config.
+fn load_fonts(config: &CosmicConfig) -> FontConfig {
+    let family = |key| config.get::<ToolkitFont>(key).map(|font| font.family);
+    FontConfig {
+        default_family: family("interface_font"),
+        monospace_family: family("monospace_font"),
+        ..FontConfig::default()
+    }
+}
+
+fn apply_fonts(fonts: FontConfig) {
+    if let Err(err) = set_default_fonts(fonts) {
+        warn!(
+            ?err,
+            "Failed to apply the font config, keeping the previous fonts."
+        );
+    }
+}
+
+struct ThemeWatcher {
+    mode_config: CosmicConfig,
+    toolkit_config: CosmicConfig,
+    /// Last applied values, `None` until applied once
+    mode: Option<ThemeMode>,
+    fonts: Option<FontConfig>,
+    mode_changed: bool,
+    fonts_changed: bool,
+    apply_scheduled: bool,
+}
+
+impl ThemeWatcher {
+    /// Applies the changed configs as one batch, so elements only relayout once.
+    fn apply(&mut self) {
+        let changes = begin_global_changes();
+        if std::mem::take(&mut self.mode_changed) {
+            let mode = ThemeMode::load(&self.mode_config);
+            if self.mode != Some(mode) {
+                info!(?mode, "Theme mode changed");
+                self.mode = Some(mode);
+                set_default_theme(mode.theme());
+            }
+        }
+        if std::mem::take(&mut self.fonts_changed) {
+            let fonts = load_fonts(&self.toolkit_config);
+            if self.fonts.as_ref() != Some(&fonts) {
+                info!(?fonts, "Font config changed");
+                self.fonts = Some(fonts.clone());
+                apply_fonts(fonts);
+            }
+        }
+        commit_global_changes(changes);
+    }
+}
+
+/// Applies the changes once all sources of the current loop iteration were dispatched,
+/// so both configs changing at once (e.g. by applying a settings profile) are one batch.
+fn schedule_apply(watcher: &Rc<RefCell<ThemeWatcher>>, handle: &LoopHandle<'static, Data>) {
+    let mut state = watcher.borrow_mut();
+    if state.apply_scheduled {
+        return;
+    }
+    state.apply_scheduled = true;
+    let watcher = watcher.clone();
+    handle.insert_idle(move |_| {
+        let mut watcher = watcher.borrow_mut();
+        watcher.apply_scheduled = false;
+        watcher.apply();
+    });
+}
+
+/// Applies the desktop's theme mode and fonts and watches their cosmic-config for changes.
+///
+/// Every change is passed to `set_default_theme` or `set_default_fonts`, which refresh all
+/// elements following the defaults (only redrawing those looking different afterwards).
+pub fn watch_theme(handle: &LoopHandle<'static, Data>) -> Result<(), anyhow::Error> {
+    let watcher = Rc::new(RefCell::new(ThemeWatcher {
+        mode_config: CosmicConfig::new(THEME_MODE.0, THEME_MODE.1)?,
+        toolkit_config: CosmicConfig::new(TOOLKIT.0, TOOLKIT.1)?,
+        mode: None,
+        fonts: None,
+        mode_changed: true,
+        fonts_changed: true,
+        apply_scheduled: false,
+    }));
+    watcher.borrow_mut().apply();
+
+    let (mode_watcher, mode_handle) = (watcher.clone(), handle.clone());
+    watcher.borrow().mode_config.watch(handle, move |keys| {
+        if keys.iter().any(|key| key == "is_dark") {
+            mode_watcher.borrow_mut().mode_changed = true;
+            schedule_apply(&mode_watcher, &mode_handle);
+        }
+    })?;
+    let (fonts_watcher, fonts_handle) = (watcher.clone(), handle.clone());
+    watcher.borrow().toolkit_config.watch(handle, move |keys| {
+        if keys
+            .iter()
+            .any(|key| key == "interface_font" || key == "monospace_font")
+        {
+            fonts_watcher.borrow_mut().fonts_changed = true;
+            schedule_apply(&fonts_watcher, &fonts_handle);
+        }
+    })?;
+    Ok(())
+}
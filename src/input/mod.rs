@@ -3,22 +3,28 @@
 use crate::{
     config::{Action, Config, KeyModifiers, WorkspaceLayout},
     shell::{
-        focus::{target::PointerFocusTarget, FocusDirection},
+        focus::{
+            target::{KeyboardFocusTarget, PointerFocusTarget},
+            FocusDirection,
+        },
         layout::{
             floating::SeatMoveGrabState,
             tiling::{Direction, FocusResult},
         },
-        OverviewMode, Workspace,
+        CosmicMapped, OverviewMode, Workspace,
     }, // shell::grabs::SeatMoveGrabState
     state::Common,
-    utils::prelude::*,
+    utils::{
+        iced::{RingEventSource, StripEventSource},
+        prelude::*,
+    },
     wayland::{handlers::screencopy::ScreencopySessions, protocols::screencopy::Session},
 };
 use cosmic_protocols::screencopy::v1::server::zcosmic_screencopy_session_v1::InputType;
 use smithay::{
     backend::input::{
-        Axis, AxisSource, Device, DeviceCapability, InputBackend, InputEvent, KeyState,
-        PointerAxisEvent,
+        AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability,
+        InputBackend, InputEvent, KeyState, PointerAxisEvent, TouchEvent, TouchSlot,
     },
     desktop::{layer_map_for_output, space::SpaceElement, WindowSurfaceType},
     input::{
@@ -27,7 +33,15 @@ use smithay::{
         Seat, SeatState,
     },
     output::Output,
-    reexports::wayland_server::DisplayHandle,
+    reexports::{
+        input::event::{
+            tablet_pad::{
+                self, RingAxisSource, StripAxisSource, TabletPadEvent, TabletPadEventTrait,
+            },
+            EventTrait,
+        },
+        wayland_server::DisplayHandle,
+    },
     utils::{Logical, Point, Rectangle, Serial, SERIAL_COUNTER},
     wayland::{
         keyboard_shortcuts_inhibit::KeyboardShortcutsInhibitorSeat, seat::WaylandFocus,
@@ -44,6 +58,24 @@ use xkbcommon::xkb::KEY_XF86Switch_VT_12;
 
 crate::utils::id_gen!(next_seat_id, SEAT_ID, SEAT_IDS);
 
+/// Repeat info of a seat's keyboard, as advertised to clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRepeat {
+    /// Delay until held keys repeat in ms
+    pub delay: i32,
+    /// Repeated keys per second
+    pub rate: i32,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        KeyRepeat {
+            delay: 200,
+            rate: 25,
+        }
+    }
+}
+
 #[repr(transparent)]
 pub struct SeatId(pub usize);
 pub struct ActiveOutput(pub RefCell<Output>);
@@ -51,6 +83,30 @@ pub struct ActiveOutput(pub RefCell<Output>);
 pub struct SupressedKeys(RefCell<Vec<u32>>);
 #[derive(Default)]
 pub struct Devices(RefCell<HashMap<String, Vec<DeviceCapability>>>);
+/// Elements touched by the seat's fingers and their global location at touch down
+#[derive(Default)]
+pub struct TouchTargets(RefCell<HashMap<TouchSlot, (CosmicMapped, Point<i32, Logical>)>>);
+
+/// Receiver of touch events, delivered to the element under the touch point at touch down.
+///
+/// smithay has no touch focus yet, see the touch events of `State::process_input_event`.
+/// Locations are relative to the target.
+pub trait TouchTarget<D> {
+    fn down(&self, seat: &Seat<D>, data: &mut D, slot: TouchSlot, location: Point<f64, Logical>);
+    fn motion(&self, seat: &Seat<D>, data: &mut D, slot: TouchSlot, location: Point<f64, Logical>);
+    fn up(&self, seat: &Seat<D>, data: &mut D, slot: TouchSlot);
+    /// Loses all touch points, e.g. once the compositor took over a gesture.
+    fn cancel(&self, seat: &Seat<D>, data: &mut D);
+}
+
+/// Receiver of tablet pad events, delivered to the keyboard focus.
+///
+/// smithay has no tablet pad focus yet, see `State::process_tablet_pad_event`.
+pub trait TabletPadTarget<D> {
+    fn pad_button(&self, seat: &Seat<D>, data: &mut D, button: u32, state: ButtonState);
+    fn pad_ring(&self, seat: &Seat<D>, data: &mut D, source: RingEventSource, degrees: f64);
+    fn pad_strip(&self, seat: &Seat<D>, data: &mut D, source: StripEventSource, position: f64);
+}
 
 impl Default for SeatId {
     fn default() -> SeatId {
@@ -126,6 +182,7 @@ pub fn add_seat(
     userdata.insert_if_missing(Devices::default);
     userdata.insert_if_missing(SupressedKeys::default);
     userdata.insert_if_missing(SeatMoveGrabState::default);
+    userdata.insert_if_missing(TouchTargets::default);
     userdata.insert_if_missing(|| ActiveOutput(RefCell::new(output.clone())));
     userdata.insert_if_missing(|| RefCell::new(CursorImageStatus::Default));
 
@@ -139,12 +196,14 @@ pub fn add_seat(
     // So instead of doing the right thing (and initialize these capabilities as matching
     // devices appear), we have to surrender to reality and just always expose a keyboard and pointer.
     let conf = config.xkb_config();
-    if let Err(err) = seat.add_keyboard((&conf).into(), 200, 25) {
+    let repeat = KeyRepeat::default();
+    userdata.insert_if_missing(|| repeat);
+    if let Err(err) = seat.add_keyboard((&conf).into(), repeat.delay, repeat.rate) {
         warn!(
             ?err,
             "Failed to load provided xkb config. Trying default...",
         );
-        seat.add_keyboard(XkbConfig::default(), 200, 25)
+        seat.add_keyboard(XkbConfig::default(), repeat.delay, repeat.rate)
             .expect("Failed to load xkb configuration files");
     }
     seat.add_pointer();
@@ -301,6 +360,14 @@ impl State {
                                                 )));
                                             }
                                         }
+                                        // overlays only get keys no global binding claimed
+                                        if state == KeyState::Pressed
+                                            && crate::utils::iced::shortcut_registry()
+                                                .route(modifiers, handle.raw_syms())
+                                        {
+                                            userdata.get::<SupressedKeys>().unwrap().add(&handle);
+                                            return FilterResult::Intercept(None);
+                                        }
                                     }
 
                                     FilterResult::Forward
@@ -326,6 +393,7 @@ impl State {
 
                         let mut position = seat.get_pointer().unwrap().current_location();
                         position += event.delta();
+                        position = crate::utils::iced::confine_pointer(seat, position);
 
                         let output = self
                             .common
@@ -421,11 +489,14 @@ impl State {
                     if devices.has_device(&device) {
                         let output = seat.active_output();
                         let geometry = output.geometry();
-                        let position = geometry.loc.to_f64()
-                            + smithay::backend::input::AbsolutePositionEvent::position_transformed(
-                                &event,
-                                geometry.size,
-                            );
+                        let position = crate::utils::iced::confine_pointer(
+                            seat,
+                            geometry.loc.to_f64()
+                                + smithay::backend::input::AbsolutePositionEvent::position_transformed(
+                                    &event,
+                                    geometry.size,
+                                ),
+                        );
                         let relative_pos = self.common.shell.map_global_to_space(position, &output);
                         let workspace = self.common.shell.active_space(&output);
                         let serial = SERIAL_COUNTER.next_serial();
@@ -649,7 +720,137 @@ impl State {
                     }
                 }
             }
-            _ => { /* TODO e.g. tablet or touch events */ }
+            InputEvent::TouchDown { event, .. } => {
+                let device = event.device();
+                for seat in self.common.seats().cloned().collect::<Vec<_>>().iter() {
+                    let userdata = seat.user_data();
+                    let devices = userdata.get::<Devices>().unwrap();
+                    if devices.has_device(&device) {
+                        let output = seat.active_output();
+                        let geometry = output.geometry();
+                        let position =
+                            geometry.loc.to_f64() + event.position_transformed(geometry.size);
+                        let relative_pos = self.common.shell.map_global_to_space(position, &output);
+                        let workspace = self.common.shell.active_space(&output);
+                        // only server-side decorations take touch input for now
+                        if let Some((PointerFocusTarget::Element(mapped), loc)) =
+                            State::surface_under(
+                                position,
+                                relative_pos,
+                                &output,
+                                geometry,
+                                &self.common.shell.override_redirect_windows,
+                                &workspace,
+                            )
+                        {
+                            let slot = event.slot();
+                            let location = position - loc.to_f64();
+                            if mapped.takes_touch(location) {
+                                TouchTarget::down(&mapped, seat, self, slot, location);
+                                let targets = userdata.get::<TouchTargets>().unwrap();
+                                targets.0.borrow_mut().insert(slot, (mapped, loc));
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+            InputEvent::TouchMotion { event, .. } => {
+                let device = event.device();
+                for seat in self.common.seats().cloned().collect::<Vec<_>>().iter() {
+                    let userdata = seat.user_data();
+                    let devices = userdata.get::<Devices>().unwrap();
+                    if devices.has_device(&device) {
+                        let output = seat.active_output();
+                        let geometry = output.geometry();
+                        let position =
+                            geometry.loc.to_f64() + event.position_transformed(geometry.size);
+                        let target = userdata
+                            .get::<TouchTargets>()
+                            .and_then(|targets| targets.0.borrow().get(&event.slot()).cloned());
+                        if let Some((mapped, loc)) = target {
+                            let location = position - loc.to_f64();
+                            TouchTarget::motion(&mapped, seat, self, event.slot(), location);
+                        }
+                        break;
+                    }
+                }
+            }
+            InputEvent::TouchUp { event, .. } => {
+                let device = event.device();
+                for seat in self.common.seats().cloned().collect::<Vec<_>>().iter() {
+                    let userdata = seat.user_data();
+                    let devices = userdata.get::<Devices>().unwrap();
+                    if devices.has_device(&device) {
+                        let target = userdata
+                            .get::<TouchTargets>()
+                            .and_then(|targets| targets.0.borrow_mut().remove(&event.slot()));
+                        if let Some((mapped, _)) = target {
+                            TouchTarget::up(&mapped, seat, self, event.slot());
+                        }
+                        break;
+                    }
+                }
+            }
+            InputEvent::TouchCancel { event, .. } => {
+                let device = event.device();
+                for seat in self.common.seats().cloned().collect::<Vec<_>>().iter() {
+                    let userdata = seat.user_data();
+                    let devices = userdata.get::<Devices>().unwrap();
+                    if devices.has_device(&device) {
+                        let targets = userdata
+                            .get::<TouchTargets>()
+                            .map(|targets| targets.0.take())
+                            .unwrap_or_default();
+                        for (mapped, _) in targets.into_values() {
+                            TouchTarget::cancel(&mapped, seat, self);
+                        }
+                        break;
+                    }
+                }
+            }
+            _ => { /* TODO e.g. tablet events */ }
+        }
+    }
+
+    /// Delivers events of tablet pads, which libinput reports as special events,
+    /// to the keyboard focus of the pad's seat.
+    pub fn process_tablet_pad_event(&mut self, event: &TabletPadEvent) {
+        let device = event.device();
+        let seat = self.common.seats().cloned().find(|seat| {
+            seat.user_data()
+                .get::<Devices>()
+                .map_or(false, |devices| devices.has_device(&device))
+        });
+        let Some(seat) = seat else { return };
+        let focus = seat
+            .get_keyboard()
+            .and_then(|keyboard| keyboard.current_focus());
+        let Some(KeyboardFocusTarget::Element(mapped)) = focus else { return };
+
+        match event {
+            TabletPadEvent::Button(event) => {
+                let state = match event.button_state() {
+                    tablet_pad::ButtonState::Pressed => ButtonState::Pressed,
+                    tablet_pad::ButtonState::Released => ButtonState::Released,
+                };
+                mapped.pad_button(&seat, self, event.button_number(), state);
+            }
+            TabletPadEvent::Ring(event) => {
+                let source = match event.source() {
+                    RingAxisSource::Finger => RingEventSource::Finger,
+                    _ => RingEventSource::Unknown,
+                };
+                mapped.pad_ring(&seat, self, source, event.position());
+            }
+            TabletPadEvent::Strip(event) => {
+                let source = match event.source() {
+                    StripAxisSource::Finger => StripEventSource::Finger,
+                    _ => StripEventSource::Unknown,
+                };
+                mapped.pad_strip(&seat, self, source, event.position());
+            }
+            _ => {}
         }
     }
 
@@ -2,14 +2,18 @@
 
 use smithay::{
     reexports::{
-        calloop::{generic::Generic, EventLoop, Interest, Mode, PostAction},
+        calloop::{
+            generic::Generic,
+            signals::{Signal, Signals},
+            EventLoop, Interest, Mode, PostAction,
+        },
         wayland_server::Display,
     },
     wayland::socket::ListeningSocketSource,
 };
 
 use anyhow::{Context, Result};
-use std::{ffi::OsString, os::unix::prelude::AsRawFd, sync::Arc};
+use std::{ffi::OsString, os::unix::prelude::AsRawFd, path::Path, sync::Arc};
 use tracing::{error, info, warn};
 
 pub mod backend;
@@ -53,6 +57,29 @@ fn main() -> Result<()> {
     }
     // potentially tell the session we are setup now
     session::setup_socket(event_loop.handle(), &state)?;
+    // reload element configurations on SIGHUP
+    event_loop
+        .handle()
+        .insert_source(Signals::new(&[Signal::SIGHUP])?, |_, _, _| {
+            info!("Received SIGHUP, reloading configuration");
+            utils::iced::reload_all_configs();
+        })
+        .with_context(|| "Failed to init the signal source.")?;
+    // follow the desktop's light or dark theme
+    if let Err(err) = config::watch_theme(&event_loop.handle()) {
+        warn!(?err, "Failed to watch the theme config");
+    }
+    // release element memory once the system runs low on memory
+    if let Err(err) = utils::iced::watch_memory_pressure(&event_loop.handle()) {
+        warn!(?err, "Failed to watch memory pressure");
+    }
+    // export element render statistics, if requested
+    if let Some(path) = std::env::var_os("COSMIC_COMP_TELEMETRY_SOCKET") {
+        if let Err(err) = utils::iced::init_telemetry_socket(&event_loop.handle(), Path::new(&path))
+        {
+            warn!(?err, "Failed to setup telemetry socket");
+        }
+    }
 
     let mut data = state::Data { display, state };
     // run the event loop
@@ -60,6 +87,7 @@ fn main() -> Result<()> {
         // shall we shut down?
         if data.state.common.shell.outputs().next().is_none() || data.state.common.should_stop {
             info!("Shutting down");
+            utils::iced::commit_pending_intents();
             data.state.common.event_loop_signal.stop();
             data.state.common.event_loop_signal.wakeup();
             return;
@@ -3,13 +3,17 @@ use crate::{
         element::{AsGlowFrame, AsGlowRenderer},
         GlMultiError, GlMultiFrame, GlMultiRenderer,
     },
+    input::{TabletPadTarget, TouchTarget},
     state::State,
-    utils::prelude::SeatExt,
+    utils::{
+        iced::{RingEventSource, StripEventSource},
+        prelude::SeatExt,
+    },
 };
 use id_tree::NodeId;
 use smithay::{
     backend::{
-        input::KeyState,
+        input::{ButtonState, KeyState, TouchSlot},
         renderer::{
             element::{
                 utils::{CropRenderElement, RelocateRenderElement, RescaleRenderElement},
@@ -126,6 +130,25 @@ impl CosmicMapped {
         }
     }
 
+    /// Whether a touch point going down at `location` hits the header of the element,
+    /// instead of the window itself.
+    pub fn takes_touch(&self, location: Point<f64, Logical>) -> bool {
+        match &self.element {
+            CosmicMappedInternal::Stack(s) => s.takes_touch(location),
+            CosmicMappedInternal::Window(w) => w.takes_touch(location),
+            _ => false,
+        }
+    }
+
+    /// Sets the global location the element is rendered at, see `IcedElement::set_location`.
+    pub fn set_location(&self, location: Point<i32, Logical>) {
+        match &self.element {
+            CosmicMappedInternal::Stack(s) => s.set_location(location),
+            CosmicMappedInternal::Window(w) => w.set_location(location),
+            _ => {}
+        }
+    }
+
     pub fn active_window(&self) -> CosmicSurface {
         match &self.element {
             CosmicMappedInternal::Stack(stack) => stack.active(),
@@ -192,7 +215,7 @@ impl CosmicMapped {
 
     pub fn has_surface(&self, surface: &WlSurface, surface_type: WindowSurfaceType) -> bool {
         self.windows().any(|(w, _)| {
-            let Some(toplevel ) = w.wl_surface() else { return false };
+            let Some(toplevel) = w.wl_surface() else { return false };
 
             if surface_type.contains(WindowSurfaceType::TOPLEVEL) {
                 if toplevel == *surface {
@@ -573,6 +596,97 @@ impl KeyboardTarget<State> for CosmicMapped {
     }
 }
 
+impl TouchTarget<State> for CosmicMapped {
+    fn down(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        slot: TouchSlot,
+        location: Point<f64, Logical>,
+    ) {
+        match &self.element {
+            CosmicMappedInternal::Stack(s) => TouchTarget::down(s, seat, data, slot, location),
+            CosmicMappedInternal::Window(w) => TouchTarget::down(w, seat, data, slot, location),
+            _ => {}
+        }
+    }
+    fn motion(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        slot: TouchSlot,
+        location: Point<f64, Logical>,
+    ) {
+        match &self.element {
+            CosmicMappedInternal::Stack(s) => TouchTarget::motion(s, seat, data, slot, location),
+            CosmicMappedInternal::Window(w) => TouchTarget::motion(w, seat, data, slot, location),
+            _ => {}
+        }
+    }
+    fn up(&self, seat: &Seat<State>, data: &mut State, slot: TouchSlot) {
+        match &self.element {
+            CosmicMappedInternal::Stack(s) => TouchTarget::up(s, seat, data, slot),
+            CosmicMappedInternal::Window(w) => TouchTarget::up(w, seat, data, slot),
+            _ => {}
+        }
+    }
+    fn cancel(&self, seat: &Seat<State>, data: &mut State) {
+        match &self.element {
+            CosmicMappedInternal::Stack(s) => TouchTarget::cancel(s, seat, data),
+            CosmicMappedInternal::Window(w) => TouchTarget::cancel(w, seat, data),
+            _ => {}
+        }
+    }
+}
+
+impl TabletPadTarget<State> for CosmicMapped {
+    fn pad_button(&self, seat: &Seat<State>, data: &mut State, button: u32, state: ButtonState) {
+        match &self.element {
+            CosmicMappedInternal::Stack(s) => {
+                TabletPadTarget::pad_button(s, seat, data, button, state)
+            }
+            CosmicMappedInternal::Window(w) => {
+                TabletPadTarget::pad_button(w, seat, data, button, state)
+            }
+            _ => {}
+        }
+    }
+    fn pad_ring(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        source: RingEventSource,
+        degrees: f64,
+    ) {
+        match &self.element {
+            CosmicMappedInternal::Stack(s) => {
+                TabletPadTarget::pad_ring(s, seat, data, source, degrees)
+            }
+            CosmicMappedInternal::Window(w) => {
+                TabletPadTarget::pad_ring(w, seat, data, source, degrees)
+            }
+            _ => {}
+        }
+    }
+    fn pad_strip(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        source: StripEventSource,
+        position: f64,
+    ) {
+        match &self.element {
+            CosmicMappedInternal::Stack(s) => {
+                TabletPadTarget::pad_strip(s, seat, data, source, position)
+            }
+            CosmicMappedInternal::Window(w) => {
+                TabletPadTarget::pad_strip(w, seat, data, source, position)
+            }
+            _ => {}
+        }
+    }
+}
+
 impl PointerTarget<State> for CosmicMapped {
     fn enter(&self, seat: &Seat<State>, data: &mut State, event: &MotionEvent) {
         self.last_cursor_position
@@ -1,6 +1,7 @@
 use crate::{
+    input::{TabletPadTarget, TouchTarget},
     state::State,
-    utils::iced::{IcedElement, Program},
+    utils::iced::{IcedElement, Program, RingEventSource, StripEventSource},
     utils::prelude::SeatExt,
     wayland::handlers::screencopy::ScreencopySessions,
 };
@@ -9,7 +10,7 @@ use cosmic::Element;
 use cosmic_protocols::screencopy::v1::server::zcosmic_screencopy_session_v1::InputType;
 use smithay::{
     backend::{
-        input::KeyState,
+        input::{ButtonState, KeyState, TouchSlot},
         renderer::{
             element::{
                 memory::MemoryRenderBufferRenderElement, surface::WaylandSurfaceRenderElement,
@@ -155,6 +156,17 @@ impl CosmicStack {
         Point::from((0, TAB_HEIGHT))
     }
 
+    /// Whether a touch point going down at `location` hits the tabs.
+    pub fn takes_touch(&self, location: Point<f64, Logical>) -> bool {
+        location.y < TAB_HEIGHT as f64
+    }
+
+    /// Sets the global location of the element, so its header can be located,
+    /// e.g. as a drop target.
+    pub fn set_location(&self, location: Point<i32, Logical>) {
+        self.0.set_location(location)
+    }
+
     pub fn set_geometry(&self, geo: Rectangle<i32, Logical>) {
         self.0.with_program(|p| {
             let loc = (geo.loc.x, geo.loc.y + TAB_HEIGHT);
@@ -404,6 +416,57 @@ impl KeyboardTarget<State> for CosmicStack {
     }
 }
 
+impl TouchTarget<State> for CosmicStack {
+    fn down(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        slot: TouchSlot,
+        location: Point<f64, Logical>,
+    ) {
+        TouchTarget::down(&self.0, seat, data, slot, location)
+    }
+    fn motion(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        slot: TouchSlot,
+        location: Point<f64, Logical>,
+    ) {
+        TouchTarget::motion(&self.0, seat, data, slot, location)
+    }
+    fn up(&self, seat: &Seat<State>, data: &mut State, slot: TouchSlot) {
+        TouchTarget::up(&self.0, seat, data, slot)
+    }
+    fn cancel(&self, seat: &Seat<State>, data: &mut State) {
+        TouchTarget::cancel(&self.0, seat, data)
+    }
+}
+
+impl TabletPadTarget<State> for CosmicStack {
+    fn pad_button(&self, seat: &Seat<State>, data: &mut State, button: u32, state: ButtonState) {
+        TabletPadTarget::pad_button(&self.0, seat, data, button, state)
+    }
+    fn pad_ring(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        source: RingEventSource,
+        degrees: f64,
+    ) {
+        TabletPadTarget::pad_ring(&self.0, seat, data, source, degrees)
+    }
+    fn pad_strip(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        source: StripEventSource,
+        position: f64,
+    ) {
+        TabletPadTarget::pad_strip(&self.0, seat, data, source, position)
+    }
+}
+
 impl PointerTarget<State> for CosmicStack {
     fn enter(&self, seat: &Seat<State>, data: &mut State, event: &MotionEvent) {
         if self.0.with_program(|p| {
@@ -3,21 +3,22 @@ use crate::{
         element::{AsGlowFrame, AsGlowRenderer},
         GlMultiError, GlMultiFrame, GlMultiRenderer,
     },
+    input::{TabletPadTarget, TouchTarget},
     shell::Shell,
     state::State,
     utils::{
-        iced::{IcedElement, Program},
+        iced::{HookPalette, IcedElement, PaletteRole, Program, RingEventSource, StripEventSource},
         prelude::SeatExt,
     },
     wayland::handlers::screencopy::ScreencopySessions,
 };
 use calloop::LoopHandle;
-use cosmic::iced_native::Command;
+use cosmic::{iced_native::Command, Theme};
 use cosmic_protocols::screencopy::v1::server::zcosmic_screencopy_session_v1::InputType;
 use iced_softbuffer::native::raqote::{DrawOptions, DrawTarget, PathBuilder, SolidSource, Source};
 use smithay::{
     backend::{
-        input::KeyState,
+        input::{ButtonState, KeyState, TouchSlot},
         renderer::{
             element::{
                 memory::MemoryRenderBufferRenderElement, surface::WaylandSurfaceRenderElement,
@@ -49,6 +50,11 @@ use std::{
 
 use super::{surface::SSD_HEIGHT, CosmicSurface};
 
+lazy_static::lazy_static! {
+    /// Palette of the default theme, under which headers keep their established colors
+    static ref DEFAULT_PALETTE: HookPalette = HookPalette::new(&Theme::dark());
+}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct CosmicWindow(IcedElement<CosmicWindowInternal>);
 
@@ -160,6 +166,18 @@ impl CosmicWindow {
             Point::from((0, 0))
         }
     }
+
+    /// Whether a touch point going down at `location` hits the header.
+    pub fn takes_touch(&self, location: Point<f64, Logical>) -> bool {
+        let has_ssd = self.0.with_program(|p| p.has_ssd());
+        has_ssd && location.y < SSD_HEIGHT as f64
+    }
+
+    /// Sets the global location of the element, so its header can be located,
+    /// e.g. as a drop target.
+    pub fn set_location(&self, location: Point<i32, Logical>) {
+        self.0.set_location(location)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -218,7 +236,7 @@ impl Program for CosmicWindowInternal {
         Command::none()
     }
 
-    fn background(&self, target: &mut DrawTarget<&mut [u32]>) {
+    fn themed_background(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
         let radius = 8.;
         let (w, h) = (target.width() as f32, target.height() as f32);
 
@@ -240,10 +258,20 @@ impl Program for CosmicWindowInternal {
             target.push_clip(&path);
         }
 
-        if self.window.is_activated() {
-            target.clear(SolidSource::from_unpremultiplied_argb(u8::MAX, 30, 30, 30));
+        let active = self.window.is_activated();
+        if *palette == *DEFAULT_PALETTE {
+            // the established header greys, the palette is only followed for other themes
+            let grey = if active { 30 } else { 39 };
+            target.clear(SolidSource::from_unpremultiplied_argb(
+                u8::MAX,
+                grey,
+                grey,
+                grey,
+            ));
+        } else if active {
+            target.clear(palette.solid(PaletteRole::Background));
         } else {
-            target.clear(SolidSource::from_unpremultiplied_argb(u8::MAX, 39, 39, 39));
+            target.clear(palette.solid(PaletteRole::Surface));
         }
 
         target.pop_clip();
@@ -281,6 +309,7 @@ impl Program for CosmicWindowInternal {
                 target.push_clip(&path);
             }
 
+            // dims with black regardless of the theme, a light background would brighten instead
             let mut options = DrawOptions::new();
             options.alpha = 0.4;
             target.fill_rect(
@@ -293,6 +322,10 @@ impl Program for CosmicWindowInternal {
             );
         }
     }
+
+    fn uses_hooks(&self) -> bool {
+        true
+    }
 }
 
 impl IsAlive for CosmicWindow {
@@ -411,6 +444,57 @@ impl KeyboardTarget<State> for CosmicWindow {
     }
 }
 
+impl TouchTarget<State> for CosmicWindow {
+    fn down(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        slot: TouchSlot,
+        location: Point<f64, Logical>,
+    ) {
+        TouchTarget::down(&self.0, seat, data, slot, location)
+    }
+    fn motion(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        slot: TouchSlot,
+        location: Point<f64, Logical>,
+    ) {
+        TouchTarget::motion(&self.0, seat, data, slot, location)
+    }
+    fn up(&self, seat: &Seat<State>, data: &mut State, slot: TouchSlot) {
+        TouchTarget::up(&self.0, seat, data, slot)
+    }
+    fn cancel(&self, seat: &Seat<State>, data: &mut State) {
+        TouchTarget::cancel(&self.0, seat, data)
+    }
+}
+
+impl TabletPadTarget<State> for CosmicWindow {
+    fn pad_button(&self, seat: &Seat<State>, data: &mut State, button: u32, state: ButtonState) {
+        TabletPadTarget::pad_button(&self.0, seat, data, button, state)
+    }
+    fn pad_ring(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        source: RingEventSource,
+        degrees: f64,
+    ) {
+        TabletPadTarget::pad_ring(&self.0, seat, data, source, degrees)
+    }
+    fn pad_strip(
+        &self,
+        seat: &Seat<State>,
+        data: &mut State,
+        source: StripEventSource,
+        position: f64,
+    ) {
+        TabletPadTarget::pad_strip(&self.0, seat, data, source, position)
+    }
+}
+
 impl PointerTarget<State> for CosmicWindow {
     fn enter(&self, seat: &Seat<State>, data: &mut State, event: &MotionEvent) {
         if self.0.with_program(|p| {
@@ -732,15 +816,13 @@ where
             )
         });
         if has_ssd {
-            elements.extend(AsRenderElements::<GlowRenderer>::render_elements::<
-                CosmicWindowRenderElement<R>,
-            >(
-                &self.0,
+            self.0.render_elements_into(
                 renderer.glow_renderer_mut(),
                 location,
                 scale,
                 alpha,
-            ))
+                &mut elements,
+            );
         }
 
         elements.into_iter().map(C::from).collect()
@@ -1,7 +1,10 @@
 use crate::{
     shell::{element::CosmicMapped, Shell, Workspace},
     state::Common,
-    utils::prelude::*,
+    utils::{
+        iced::{window_feed, FocusedWindowInfo},
+        prelude::*,
+    },
     wayland::handlers::xdg_shell::PopupGrabData,
     xwayland::XWaylandState,
 };
@@ -293,6 +296,38 @@ impl Common {
         state
             .common
             .shell
-            .update_active(seats.iter(), state.common.xwayland_state.as_mut())
+            .update_active(seats.iter(), state.common.xwayland_state.as_mut());
+        Common::publish_focused_window(state);
+    }
+
+    /// Publishes the focused window of the last active seat to `window_feed()`.
+    ///
+    /// Runs on every refresh, as titles may change at any time.
+    /// Unchanged infos are dropped by the feed.
+    fn publish_focused_window(state: &State) {
+        if state.common.seats().next().is_none() {
+            return;
+        }
+        let seat = state.common.last_active_seat();
+        let output = seat.active_output();
+        let info = state
+            .common
+            .shell
+            .active_space(&output)
+            .focus_stack
+            .get(seat)
+            .last()
+            .map(|mapped| {
+                let window = mapped.active_window();
+                FocusedWindowInfo {
+                    app_id: window.app_id(),
+                    title: window.title(),
+                    maximized: mapped.is_maximized(),
+                    fullscreen: mapped.is_fullscreen(),
+                    output: Some(output.clone()),
+                }
+            })
+            .unwrap_or_default();
+        window_feed().publish(info);
     }
 }
@@ -275,6 +275,16 @@ impl FloatingLayout {
             let output = self.space.outputs().next().unwrap().clone();
             self.map_internal(element, &output, None);
         }
+
+        for element in self.space.elements() {
+            let Some(location) = self.space.element_location(element) else { continue };
+            let outputs = self.space.outputs_for_element(element);
+            let Some(output) = outputs.first() else { continue };
+            let Some(output_geo) = self.space.output_geometry(output) else { continue };
+            element.set_location(
+                location - element.geometry().loc - output_geo.loc + output.current_location(),
+            );
+        }
     }
 
     pub fn most_overlapped_output_for_element(&self, elem: &CosmicMapped) -> Option<Output> {
@@ -1063,8 +1063,14 @@ impl TilingLayout {
             self.unmap_window_internal(&dead_window);
         }
 
-        for (_, mapped, _) in self.mapped() {
+        for (output, mapped, location) in self.mapped() {
             mapped.refresh();
+            if let Some((output_data, _)) = self.queues.get_key_value(output) {
+                mapped.set_location(
+                    location - mapped.geometry().loc - output_data.location
+                        + output.current_location(),
+                );
+            }
         }
     }
 
@@ -8,7 +8,7 @@ use crate::{
     },
     config::{Config, OutputConfig},
     shell::{layout::floating::SeatMoveGrabState, Shell},
-    utils::prelude::*,
+    utils::{iced::RefreshInfo, prelude::*},
     wayland::protocols::{
         drm::WlDrmState,
         output_configuration::OutputConfigurationState,
@@ -206,6 +206,12 @@ impl BackendData {
             let location =
                 Some(final_config.position.into()).filter(|x| *x != output.current_location());
             output.change_current_state(mode, transform, scale.map(Scale::Fractional), location);
+            if scale.is_some() {
+                crate::utils::iced::reconfigure_output(output);
+            }
+            if let Some(info) = RefreshInfo::for_output(output, final_config.vrr) {
+                crate::utils::iced::set_output_refresh_info(output, info);
+            }
         }
 
         result
@@ -390,6 +396,8 @@ impl Common {
         let time = self.clock.now();
         let throttle = Some(Duration::from_secs(1));
 
+        crate::utils::iced::frame_done(output);
+
         for seat in self.seats.iter() {
             if &seat.active_output() == output {
                 let cursor_status = seat
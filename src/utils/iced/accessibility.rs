@@ -0,0 +1,182 @@
+//! Forwards focus changes between the widgets of an element to AT-SPI2, for screen readers,
+//! see `IcedElement::register_atspi`.
+//!
+//! The element is exported as a single accessible panel. Its widgets aren't exported
+//! individually, focus moving between them is announced by `Focus` events of the panel,
+//! carrying the position of the focused widget in tab order.
+
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use tracing::warn;
+use zbus::{
+    blocking, dbus_interface,
+    names::BusName,
+    zvariant::{ObjectPath, OwnedObjectPath, Value},
+    Connection,
+};
+
+use super::focus::FocusedWidget;
+
+const ROLE_PANEL: u32 = 39;
+const STATE_ENABLED: u32 = 8;
+const STATE_FOCUSABLE: u32 = 11;
+const STATE_FOCUSED: u32 = 12;
+const STATE_EDITABLE: u32 = 7;
+
+#[derive(Debug, Default)]
+struct AccessibleState {
+    name: String,
+    focused: Option<FocusedWidget>,
+}
+
+/// `org.a11y.atspi.Accessible` of an element.
+struct Accessible(Arc<Mutex<AccessibleState>>);
+
+#[dbus_interface(name = "org.a11y.atspi.Accessible")]
+impl Accessible {
+    #[dbus_interface(property)]
+    fn name(&self) -> String {
+        self.0.lock().unwrap().name.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn description(&self) -> String {
+        match &self.0.lock().unwrap().focused {
+            Some(focused) => format!("Widget {} focused", focused.index + 1),
+            None => String::new(),
+        }
+    }
+
+    #[dbus_interface(property)]
+    fn child_count(&self) -> i32 {
+        0
+    }
+
+    fn get_role(&self) -> u32 {
+        ROLE_PANEL
+    }
+
+    fn get_role_name(&self) -> String {
+        String::from("panel")
+    }
+
+    fn get_state(&self) -> Vec<u32> {
+        state_set(self.0.lock().unwrap().focused.as_ref()).to_vec()
+    }
+
+    fn get_interfaces(&self) -> Vec<String> {
+        vec![String::from("org.a11y.atspi.Accessible")]
+    }
+}
+
+/// The AT-SPI2 state set of the element, two words of `AtspiStateType` bits.
+pub(super) fn state_set(focused: Option<&FocusedWidget>) -> [u32; 2] {
+    let mut states = (1 << STATE_ENABLED) | (1 << STATE_FOCUSABLE);
+    if let Some(focused) = focused {
+        states |= 1 << STATE_FOCUSED;
+        if focused.editable {
+            states |= 1 << STATE_EDITABLE;
+        }
+    }
+    [states, 0]
+}
+
+/// The accessible of a registered element, unexported once dropped.
+pub(super) struct AtspiBridge {
+    tx: mpsc::Sender<Option<FocusedWidget>>,
+}
+
+impl std::fmt::Debug for AtspiBridge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AtspiBridge").finish_non_exhaustive()
+    }
+}
+
+impl AtspiBridge {
+    /// Exports the accessible of an element named `name` at `path`.
+    pub fn new(
+        connection: &Connection,
+        path: &ObjectPath<'_>,
+        name: String,
+    ) -> zbus::Result<AtspiBridge> {
+        let connection = blocking::Connection::from(connection.clone());
+        let path = OwnedObjectPath::from(path.to_owned());
+        let state = Arc::new(Mutex::new(AccessibleState {
+            name,
+            focused: None,
+        }));
+        connection
+            .object_server()
+            .at(&path, Accessible(state.clone()))?;
+
+        // the bus is only talked to from a thread of its own, never from the event loop
+        let (tx, rx) = mpsc::channel::<Option<FocusedWidget>>();
+        thread::Builder::new()
+            .name(String::from("atspi-bridge"))
+            .spawn(move || {
+                for focused in rx {
+                    let was_focused = {
+                        let mut state = state.lock().unwrap();
+                        std::mem::replace(&mut state.focused, focused.clone()).is_some()
+                    };
+                    if let Err(err) = emit_focus_change(&connection, &path, was_focused, focused) {
+                        warn!(?err, "Failed to emit AT-SPI focus event");
+                    }
+                }
+                if let Err(err) = connection.object_server().remove::<Accessible, _>(&path) {
+                    warn!(?err, "Failed to unexport accessible");
+                }
+            })
+            .map_err(|err| zbus::Error::Failure(err.to_string()))?;
+
+        Ok(AtspiBridge { tx })
+    }
+
+    pub fn focus_changed(&self, focused: Option<FocusedWidget>) {
+        let _ = self.tx.send(focused);
+    }
+}
+
+fn emit_focus_change(
+    connection: &blocking::Connection,
+    path: &OwnedObjectPath,
+    was_focused: bool,
+    focused: Option<FocusedWidget>,
+) -> zbus::Result<()> {
+    let properties = HashMap::<&str, Value<'_>>::new();
+    if was_focused != focused.is_some() {
+        connection.emit_signal(
+            None::<BusName<'_>>,
+            path,
+            "org.a11y.atspi.Event.Object",
+            "StateChanged",
+            &(
+                "focused",
+                focused.is_some() as i32,
+                0i32,
+                Value::from(0i32),
+                properties.clone(),
+            ),
+        )?;
+    }
+    if let Some(focused) = focused {
+        connection.emit_signal(
+            None::<BusName<'_>>,
+            path,
+            "org.a11y.atspi.Event.Focus",
+            "Focus",
+            &(
+                "",
+                focused.index as i32,
+                0i32,
+                Value::from(0i32),
+                properties,
+            ),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,283 @@
+//! Easing curves and springs shared by the framework's animations.
+//!
+//! Animations are evaluated from the time since they started, instead of accumulating per-frame
+//! steps, so they don't depend on the frame rate. Cubic-bezier curves run for a fixed duration,
+//! springs until they settle. Running animations can be retargeted, springs keep their velocity
+//! so the motion stays continuous.
+
+use std::time::{Duration, Instant};
+
+/// Springs are settled once this close to their target, in units of the animated value
+const REST_DISTANCE: f64 = 1e-3;
+/// ... and slower than this, in units per second
+const REST_VELOCITY: f64 = 1e-2;
+/// Precision of solving bezier curves for their progress
+const BEZIER_EPSILON: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    Linear,
+    /// CSS `ease`
+    Ease,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// Control points of a CSS `cubic-bezier()`, `x1`, `y1`, `x2`, `y2`
+    CubicBezier(f32, f32, f32, f32),
+    /// Damped spring of unit mass, animations of springs ignore their duration.
+    ///
+    /// A damping of `2 * stiffness.sqrt()` is critically damped, lower values overshoot.
+    /// The initial velocity is in distances between start and target per second.
+    Spring {
+        stiffness: f32,
+        damping: f32,
+        initial_velocity: f32,
+    },
+}
+
+impl Curve {
+    /// Critically damped spring, settling quickly without overshooting.
+    pub const SPRING: Curve = Curve::Spring {
+        stiffness: 170.0,
+        damping: 26.0768,
+        initial_velocity: 0.0,
+    };
+
+    fn control_points(&self) -> Option<(f64, f64, f64, f64)> {
+        let points = match *self {
+            Curve::Linear => (0.0, 0.0, 1.0, 1.0),
+            Curve::Ease => (0.25, 0.1, 0.25, 1.0),
+            Curve::EaseIn => (0.42, 0.0, 1.0, 1.0),
+            Curve::EaseOut => (0.0, 0.0, 0.58, 1.0),
+            Curve::EaseInOut => (0.42, 0.0, 0.58, 1.0),
+            Curve::CubicBezier(x1, y1, x2, y2) => (x1 as f64, y1 as f64, x2 as f64, y2 as f64),
+            Curve::Spring { .. } => return None,
+        };
+        Some(points)
+    }
+
+    /// Eased progress at linear `progress`, springs are evaluated as if settling within one
+    /// second.
+    pub fn sample(&self, progress: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0) as f64;
+        match self.control_points() {
+            Some(points) => bezier(points, progress) as f32,
+            None => {
+                let (x, _) = self.spring_at(-1.0, 0.0, progress);
+                (1.0 + x) as f32
+            }
+        }
+    }
+
+    /// Displacement from the target and velocity of a spring `t` seconds after starting at
+    /// displacement `x0` with velocity `v0`.
+    fn spring_at(&self, x0: f64, v0: f64, t: f64) -> (f64, f64) {
+        let Curve::Spring {
+            stiffness, damping, ..
+        } = *self
+        else { return (0.0, 0.0) };
+        let omega = (stiffness.max(f32::EPSILON) as f64).sqrt();
+        let zeta = damping.max(0.0) as f64 / (2.0 * omega);
+        if (zeta - 1.0).abs() < 1e-4 {
+            // critically damped
+            let b = v0 + omega * x0;
+            let decay = (-omega * t).exp();
+            (decay * (x0 + b * t), decay * (v0 - omega * b * t))
+        } else if zeta < 1.0 {
+            // underdamped, oscillates around the target
+            let a = zeta * omega;
+            let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+            let b = (v0 + a * x0) / omega_d;
+            let (sin, cos) = (omega_d * t).sin_cos();
+            let decay = (-a * t).exp();
+            (
+                decay * (x0 * cos + b * sin),
+                decay * (v0 * cos - (a * b + x0 * omega_d) * sin),
+            )
+        } else {
+            // overdamped
+            let root = (zeta * zeta - 1.0).sqrt();
+            let r1 = -omega * (zeta - root);
+            let r2 = -omega * (zeta + root);
+            let c2 = (v0 - r1 * x0) / (r2 - r1);
+            let c1 = x0 - c2;
+            let (e1, e2) = ((r1 * t).exp(), (r2 * t).exp());
+            (c1 * e1 + c2 * e2, r1 * c1 * e1 + r2 * c2 * e2)
+        }
+    }
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Curve::EaseOut
+    }
+}
+
+/// Value of the curve through `(0, 0)`, `(x1, y1)`, `(x2, y2)`, `(1, 1)` at `x`.
+fn bezier((x1, y1, x2, y2): (f64, f64, f64, f64), x: f64) -> f64 {
+    // coefficients of the polynomial of each axis
+    let (cx, cy) = (3.0 * x1, 3.0 * y1);
+    let (bx, by) = (3.0 * (x2 - x1) - cx, 3.0 * (y2 - y1) - cy);
+    let (ax, ay) = (1.0 - cx - bx, 1.0 - cy - by);
+    let sample_x = |t: f64| ((ax * t + bx) * t + cx) * t;
+    let slope_x = |t: f64| (3.0 * ax * t + 2.0 * bx) * t + cx;
+
+    // newton's method, falling back to bisection for flat slopes
+    let mut t = x;
+    let mut solved = false;
+    for _ in 0..8 {
+        let error = sample_x(t) - x;
+        if error.abs() < BEZIER_EPSILON {
+            solved = true;
+            break;
+        }
+        let slope = slope_x(t);
+        if slope.abs() < BEZIER_EPSILON {
+            break;
+        }
+        t -= error / slope;
+    }
+    if !solved {
+        let (mut low, mut high) = (0.0, 1.0);
+        t = x;
+        while high - low > BEZIER_EPSILON {
+            if sample_x(t) < x {
+                low = t;
+            } else {
+                high = t;
+            }
+            t = (low + high) / 2.0;
+        }
+    }
+    ((ay * t + by) * t + cy) * t
+}
+
+/// A value animated along a `Curve`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Animation {
+    curve: Curve,
+    /// Duration of bezier curves for a distance of `1.0`
+    duration: Duration,
+    from: f32,
+    to: f32,
+    /// Velocity at `start` in units per second, only used by springs
+    velocity: f32,
+    start: Instant,
+}
+
+impl Animation {
+    /// Starts animating from `from` to `to` at `now`.
+    ///
+    /// Bezier curves take `duration` for distances of `1.0` or more and proportionally less
+    /// for shorter ones, so an animation reversed halfway takes half as long.
+    pub fn new(curve: Curve, duration: Duration, from: f32, to: f32, now: Instant) -> Animation {
+        let velocity = match curve {
+            Curve::Spring {
+                initial_velocity, ..
+            } => initial_velocity * (to - from),
+            _ => 0.0,
+        };
+        Animation {
+            curve,
+            duration,
+            from,
+            to,
+            velocity,
+            start: now,
+        }
+    }
+
+    pub fn curve(&self) -> Curve {
+        self.curve
+    }
+
+    pub fn target(&self) -> f32 {
+        self.to
+    }
+
+    /// Moves towards `to` from the current value at `now`.
+    ///
+    /// Springs keep their current velocity, bezier curves restart from the current value.
+    pub fn retarget(&mut self, to: f32, now: Instant) {
+        self.retarget_with(self.curve, self.duration, to, now);
+    }
+
+    /// Like `retarget`, additionally switching to another curve and duration.
+    pub fn retarget_with(&mut self, curve: Curve, duration: Duration, to: f32, now: Instant) {
+        let (value, velocity) = self.state(now);
+        *self = Animation {
+            curve,
+            duration,
+            from: value,
+            to,
+            velocity,
+            start: now,
+        };
+    }
+
+    /// Jumps to the target, completing the animation.
+    pub fn settle(&mut self, now: Instant) {
+        self.from = self.to;
+        self.velocity = 0.0;
+        self.start = now;
+    }
+
+    pub fn value(&self, now: Instant) -> f32 {
+        self.state(now).0
+    }
+
+    /// Velocity in units per second.
+    pub fn velocity(&self, now: Instant) -> f32 {
+        self.state(now).1
+    }
+
+    pub fn is_complete(&self, now: Instant) -> bool {
+        match self.curve {
+            Curve::Spring { .. } => {
+                let (x, v) = self.spring_state(now);
+                x.abs() < REST_DISTANCE && v.abs() < REST_VELOCITY
+            }
+            _ => self.progress(now) >= 1.0,
+        }
+    }
+
+    fn state(&self, now: Instant) -> (f32, f32) {
+        if let Curve::Spring { .. } = self.curve {
+            if self.is_complete(now) {
+                return (self.to, 0.0);
+            }
+            let (x, v) = self.spring_state(now);
+            return (self.to + x as f32, v as f32);
+        }
+        let progress = self.progress(now);
+        if progress >= 1.0 {
+            return (self.to, 0.0);
+        }
+        let distance = self.to - self.from;
+        let value = self.from + distance * self.curve.sample(progress);
+        // numeric derivative of the curve, scaled to units per second
+        let (low, high) = ((progress - 1e-3).max(0.0), (progress + 1e-3).min(1.0));
+        let slope = (self.curve.sample(high) - self.curve.sample(low)) / (high - low);
+        let velocity = distance * slope / self.total_duration().as_secs_f32();
+        (value, velocity)
+    }
+
+    fn spring_state(&self, now: Instant) -> (f64, f64) {
+        let t = now.saturating_duration_since(self.start).as_secs_f64();
+        self.curve
+            .spring_at((self.from - self.to) as f64, self.velocity as f64, t)
+    }
+
+    fn total_duration(&self) -> Duration {
+        self.duration.mul_f32((self.to - self.from).abs().min(1.0))
+    }
+
+    /// Linear progress of bezier curves
+    fn progress(&self, now: Instant) -> f32 {
+        let duration = self.total_duration();
+        if duration.is_zero() {
+            return 1.0;
+        }
+        (now.saturating_duration_since(self.start).as_secs_f32() / duration.as_secs_f32()).min(1.0)
+    }
+}
@@ -0,0 +1,165 @@
+//! Attention badges drawn by the element above the program's content.
+
+use cosmic::{iced_native::Color, Theme};
+use iced_softbuffer::native::raqote::{
+    DrawOptions, DrawTarget, PathBuilder, SolidSource, Source, StrokeStyle,
+};
+use smithay::utils::{Logical, Point, Rectangle};
+
+const BADGE_SIZE: f32 = 16.0;
+const DOT_SIZE: f32 = 8.0;
+const RING_WIDTH: f32 = 2.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BadgeKind {
+    /// Number, values above 99 are shown as "99"
+    Count(u32),
+    Dot,
+    /// Progress ring, `0.0..=1.0`
+    Progress(f32),
+    /// Highlights the whole anchor
+    Urgent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    /// Area the badge belongs to, badges are placed at its top-right corner
+    pub anchor: Rectangle<i32, Logical>,
+    pub kind: BadgeKind,
+}
+
+impl Badge {
+    /// Area covered by the badge.
+    pub fn bounds(&self) -> Rectangle<i32, Logical> {
+        let size = match self.kind {
+            BadgeKind::Dot => DOT_SIZE,
+            BadgeKind::Urgent => return self.anchor,
+            _ => BADGE_SIZE,
+        } as i32;
+        let corner = Point::from((self.anchor.loc.x + self.anchor.size.w, self.anchor.loc.y));
+        Rectangle::from_loc_and_size(
+            (corner.x - size / 2 - 1, corner.y - size / 2 - 1),
+            (size + 2, size + 2),
+        )
+    }
+}
+
+fn solid(color: Color) -> Source<'static> {
+    Source::Solid(SolidSource::from_unpremultiplied_argb(
+        (color.a * 255.0) as u8,
+        (color.r * 255.0) as u8,
+        (color.g * 255.0) as u8,
+        (color.b * 255.0) as u8,
+    ))
+}
+
+fn circle(target: &mut DrawTarget<&mut [u32]>, x: f32, y: f32, radius: f32, source: &Source<'_>) {
+    let mut pb = PathBuilder::new();
+    pb.arc(x, y, radius, 0.0, 2.0 * std::f32::consts::PI);
+    target.fill(&pb.finish(), source, &DrawOptions::new());
+}
+
+/// Segments of the digits 0-9 (a-g, starting at the top, clockwise, middle last).
+const SEGMENTS: [u8; 10] = [
+    0b0111111, 0b0000110, 0b1011011, 0b1001111, 0b1100110, 0b1101101, 0b1111101, 0b0000111,
+    0b1111111, 0b1101111,
+];
+
+/// Draws a seven-segment digit into the box at (`x`, `y`) of size `w`x`h`.
+fn digit(
+    target: &mut DrawTarget<&mut [u32]>,
+    value: u32,
+    (x, y, w, h): (f32, f32, f32, f32),
+    source: &Source<'_>,
+) {
+    let t = (w / 4.0).max(1.0);
+    let segments = SEGMENTS[value as usize % 10];
+    let rects = [
+        (x, y, w, t),
+        (x + w - t, y, t, h / 2.0),
+        (x + w - t, y + h / 2.0, t, h / 2.0),
+        (x, y + h - t, w, t),
+        (x, y + h / 2.0, t, h / 2.0),
+        (x, y, t, h / 2.0),
+        (x, y + (h - t) / 2.0, w, t),
+    ];
+    for (i, (rx, ry, rw, rh)) in rects.into_iter().enumerate() {
+        if segments & (1 << i) != 0 {
+            target.fill_rect(rx, ry, rw, rh, source, &DrawOptions::new());
+        }
+    }
+}
+
+/// Draws `badges` onto `target`, which is scaled by `scale` relative to logical coordinates.
+pub(super) fn draw_badges(
+    target: &mut DrawTarget<&mut [u32]>,
+    badges: &[Badge],
+    scale: f32,
+    theme: &Theme,
+) {
+    let accent = solid(theme.cosmic().accent_color().into());
+    let on_accent = solid(theme.cosmic().on_accent_color().into());
+    let urgent = solid(theme.cosmic().destructive_color().into());
+
+    for badge in badges {
+        let cx = (badge.anchor.loc.x + badge.anchor.size.w) as f32 * scale;
+        let cy = badge.anchor.loc.y as f32 * scale;
+        let radius = BADGE_SIZE / 2.0 * scale;
+
+        match badge.kind {
+            BadgeKind::Dot => circle(target, cx, cy, DOT_SIZE / 2.0 * scale, &accent),
+            BadgeKind::Count(count) => {
+                circle(target, cx, cy, radius, &accent);
+                let count = count.min(99);
+                let digits = if count >= 10 { 2 } else { 1 };
+                let (w, h) = (radius * 0.5, radius);
+                let spacing = w * 0.4;
+                let total = digits as f32 * w + (digits - 1) as f32 * spacing;
+                let (mut x, y) = (cx - total / 2.0, cy - h / 2.0);
+                for value in [count / 10, count % 10].into_iter().skip(2 - digits) {
+                    digit(target, value, (x, y, w, h), &on_accent);
+                    x += w + spacing;
+                }
+            }
+            BadgeKind::Progress(progress) => {
+                let mut pb = PathBuilder::new();
+                let start = -std::f32::consts::FRAC_PI_2;
+                pb.arc(
+                    cx,
+                    cy,
+                    radius - RING_WIDTH * scale / 2.0,
+                    start,
+                    progress.clamp(0.0, 1.0) * 2.0 * std::f32::consts::PI,
+                );
+                target.stroke(
+                    &pb.finish(),
+                    &accent,
+                    &StrokeStyle {
+                        width: RING_WIDTH * scale,
+                        ..Default::default()
+                    },
+                    &DrawOptions::new(),
+                );
+            }
+            BadgeKind::Urgent => {
+                let mut pb = PathBuilder::new();
+                let inset = RING_WIDTH * scale / 2.0;
+                pb.rect(
+                    badge.anchor.loc.x as f32 * scale + inset,
+                    badge.anchor.loc.y as f32 * scale + inset,
+                    badge.anchor.size.w as f32 * scale - 2.0 * inset,
+                    badge.anchor.size.h as f32 * scale - 2.0 * inset,
+                );
+                target.stroke(
+                    &pb.finish(),
+                    &urgent,
+                    &StrokeStyle {
+                        width: RING_WIDTH * scale,
+                        ..Default::default()
+                    },
+                    &DrawOptions::new(),
+                );
+            }
+        }
+    }
+}
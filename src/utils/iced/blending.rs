@@ -0,0 +1,342 @@
+//! Linear-light compositing of premultiplied ARGB pixels.
+//!
+//! raqote blends in whatever space the pixel values are in, which for us is sRGB.
+//! To get gamma-correct results the program primitives are rasterized with linear source
+//! colors onto a transparent layer, which is then composited onto the background and
+//! converted back to sRGB.
+//!
+//! Only solid colors of quads and text are converted, images and meshes keep their sRGB
+//! colors. Text is rasterized once into linear tiles, see `TextTiles`.
+
+use std::{
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use cosmic::iced_native::{
+    alignment::{Horizontal, Vertical},
+    Background, Color, Vector,
+};
+use iced_graphics::Primitive;
+use iced_softbuffer::{
+    native::{
+        draw_primitive,
+        raqote::{self, DrawOptions, DrawTarget, IntPoint, IntRect},
+    },
+    Backend,
+};
+
+const LINEAR_STEPS: usize = 4096;
+
+lazy_static::lazy_static! {
+    static ref SRGB_TO_LINEAR: [f32; 256] = {
+        let mut table = [0.0; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            let c = i as f32 / 255.0;
+            *value = if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            };
+        }
+        table
+    };
+    static ref LINEAR_TO_SRGB: Vec<u8> = (0..LINEAR_STEPS)
+        .map(|i| {
+            let l = i as f32 / (LINEAR_STEPS - 1) as f32;
+            let c = if l <= 0.0031308 {
+                l * 12.92
+            } else {
+                1.055 * l.powf(1.0 / 2.4) - 0.055
+            };
+            (c * 255.0).round().clamp(0.0, 255.0) as u8
+        })
+        .collect();
+}
+
+/// Frames a text tile is kept without being drawn
+const TILE_FRAMES: u64 = 8;
+
+fn to_linear(channel: u8) -> f32 {
+    SRGB_TO_LINEAR[channel as usize]
+}
+
+fn to_srgb(linear: f32) -> u8 {
+    LINEAR_TO_SRGB[(linear.clamp(0.0, 1.0) * (LINEAR_STEPS - 1) as f32).round() as usize]
+}
+
+fn unpremultiply(channel: u32, alpha: u32) -> u8 {
+    ((channel * 255 + alpha / 2) / alpha).min(255) as u8
+}
+
+/// Composites `src` (premultiplied linear ARGB) over `dst` (premultiplied sRGB ARGB, same
+/// dimensions) in linear light.
+pub fn composite_linear(dst: &mut [u32], src: &[u32]) {
+    for (d, &s) in dst.iter_mut().zip(src.iter()) {
+        let sa = s >> 24;
+        if sa == 0 {
+            continue;
+        }
+        let da = *d >> 24;
+
+        let sa_f = sa as f32 / 255.0;
+        let da_f = da as f32 / 255.0;
+        let out_a = sa_f + da_f * (1.0 - sa_f);
+
+        let mut out = ((out_a * 255.0).round() as u32) << 24;
+        for shift in [16, 8, 0] {
+            let sc = ((s >> shift) & 0xff) as f32 / 255.0;
+            let dc = if da == 0 {
+                0.0
+            } else {
+                to_linear(unpremultiply((*d >> shift) & 0xff, da)) * da_f
+            };
+            let blended = (sc + dc * (1.0 - sa_f)) / out_a;
+            let premultiplied = (to_srgb(blended) as f32 * out_a).round() as u32;
+            out |= premultiplied.min(255) << shift;
+        }
+        *d = out;
+    }
+}
+
+fn linear_channel(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_color(color: Color) -> Color {
+    Color {
+        r: linear_channel(color.r),
+        g: linear_channel(color.g),
+        b: linear_channel(color.b),
+        ..color
+    }
+}
+
+/// Returns `primitive` (not a container) with its solid colors in linear light.
+#[allow(unreachable_patterns)]
+fn linear_colors(primitive: &Primitive) -> Cow<'_, Primitive> {
+    let mut converted = match primitive {
+        Primitive::Quad { .. } | Primitive::Text { .. } => primitive.clone(),
+        _ => return Cow::Borrowed(primitive),
+    };
+    match &mut converted {
+        Primitive::Quad {
+            background,
+            border_color,
+            ..
+        } => {
+            match background {
+                Background::Color(color) => *color = linear_color(*color),
+                // gradients keep their sRGB stops
+                _ => {}
+            }
+            *border_color = linear_color(*border_color);
+        }
+        Primitive::Text { color, .. } => *color = linear_color(*color),
+        _ => {}
+    }
+    Cow::Owned(converted)
+}
+
+/// Linear-light tiles of the text of an element, reused while neither the text nor its
+/// position within a physical pixel change.
+#[derive(Debug, Default)]
+pub(super) struct TextTiles {
+    tiles: HashMap<u64, TextTile>,
+    frame: u64,
+}
+
+#[derive(Debug)]
+struct TextTile {
+    width: i32,
+    height: i32,
+    pixels: Vec<u32>,
+    last_used: u64,
+}
+
+impl TextTiles {
+    /// Drops all tiles, e.g. after the fonts changed.
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+    }
+
+    /// Drops the tiles, that weren't drawn for a few frames.
+    pub fn finish_frame(&mut self) {
+        self.frame += 1;
+        let frame = self.frame;
+        self.tiles
+            .retain(|_, tile| tile.last_used + TILE_FRAMES >= frame);
+    }
+}
+
+/// Draws primitives onto a linear-light layer.
+struct LinearPainter<'a> {
+    options: &'a DrawOptions,
+    backend: &'a mut Backend,
+    scale: f32,
+    tiles: &'a mut TextTiles,
+}
+
+impl LinearPainter<'_> {
+    /// Device position of the logical point `point` on `target`.
+    fn device(&self, target: &DrawTarget<&mut [u32]>, point: Vector) -> raqote::Point {
+        target.get_transform().transform_point(raqote::Point::new(
+            point.x * self.scale,
+            point.y * self.scale,
+        ))
+    }
+
+    fn draw(&mut self, target: &mut DrawTarget<&mut [u32]>, primitive: &Primitive, offset: Vector) {
+        match primitive {
+            Primitive::Group { primitives } => {
+                for primitive in primitives {
+                    self.draw(target, primitive, offset);
+                }
+            }
+            Primitive::Translate {
+                translation,
+                content,
+            } => self.draw(target, content, offset + *translation),
+            Primitive::Clip { bounds, content } => {
+                let min = self.device(target, offset + Vector::new(bounds.x, bounds.y));
+                let max = self.device(
+                    target,
+                    offset + Vector::new(bounds.x + bounds.width, bounds.y + bounds.height),
+                );
+                target.push_clip_rect(IntRect::new(
+                    IntPoint::new(min.x.floor() as i32, min.y.floor() as i32),
+                    IntPoint::new(max.x.ceil() as i32, max.y.ceil() as i32),
+                ));
+                self.draw(target, content, offset);
+                target.pop_clip();
+            }
+            Primitive::Cached { cache } => self.draw(target, cache, offset),
+            Primitive::Text { .. } => self.draw_text(target, primitive, offset),
+            primitive => {
+                let primitive = linear_colors(primitive);
+                if offset == Vector::new(0.0, 0.0) {
+                    draw_primitive(target, self.options, self.backend, self.scale, &primitive);
+                } else {
+                    let translated = Primitive::Translate {
+                        translation: offset,
+                        content: Box::new(primitive.into_owned()),
+                    };
+                    draw_primitive(target, self.options, self.backend, self.scale, &translated);
+                }
+            }
+        }
+    }
+
+    /// Draws the text `primitive` from its tile, rasterizing it first if needed.
+    fn draw_text(
+        &mut self,
+        target: &mut DrawTarget<&mut [u32]>,
+        primitive: &Primitive,
+        offset: Vector,
+    ) {
+        let Primitive::Text {
+            bounds,
+            size,
+            horizontal_alignment,
+            vertical_alignment,
+            ..
+        } = primitive
+        else { return };
+        let text = linear_colors(primitive);
+
+        // `bounds` are anchored at the aligned edge, glyphs may overhang them a bit
+        let margin = size / 2.0;
+        let left = margin
+            + match horizontal_alignment {
+                Horizontal::Left => 0.0,
+                Horizontal::Center => bounds.width / 2.0,
+                Horizontal::Right => bounds.width,
+            };
+        let top = margin
+            + match vertical_alignment {
+                Vertical::Top => 0.0,
+                Vertical::Center => bounds.height / 2.0,
+                Vertical::Bottom => bounds.height,
+            };
+        let anchor = self.device(target, offset + Vector::new(bounds.x, bounds.y));
+        let origin = IntPoint::new(
+            (anchor.x - left * self.scale).floor() as i32,
+            (anchor.y - top * self.scale).floor() as i32,
+        );
+        let width = ((bounds.width + 2.0 * margin) * self.scale).ceil() as i32 + 1;
+        let height = ((bounds.height + 2.0 * margin) * self.scale).ceil() as i32 + 1;
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        // anchor within the tile
+        let (x, y) = (anchor.x - origin.x as f32, anchor.y - origin.y as f32);
+
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", text).hash(&mut hasher);
+        self.scale.to_bits().hash(&mut hasher);
+        ((x * 64.0).round() as i32, (y * 64.0).round() as i32).hash(&mut hasher);
+        let key = hasher.finish();
+
+        let frame = self.tiles.frame;
+        let (options, backend, scale) = (self.options, &mut *self.backend, self.scale);
+        let tile = self.tiles.tiles.entry(key).or_insert_with(|| {
+            let mut pixels = vec![0; (width * height) as usize];
+            let mut tile = DrawTarget::from_backing(width, height, &mut pixels[..]);
+            tile.push_clip_rect(IntRect::new(
+                IntPoint::new(0, 0),
+                IntPoint::new(width, height),
+            ));
+            tile.set_transform(&raqote::Transform::translation(
+                x - bounds.x * scale,
+                y - bounds.y * scale,
+            ));
+            draw_primitive(&mut tile, options, backend, scale, &text);
+            drop(tile);
+            TextTile {
+                width,
+                height,
+                pixels,
+                last_used: frame,
+            }
+        });
+        tile.last_used = frame;
+
+        let transform = *target.get_transform();
+        target.set_transform(&raqote::Transform::identity());
+        target.draw_image_at(
+            origin.x as f32,
+            origin.y as f32,
+            &raqote::Image {
+                width: tile.width,
+                height: tile.height,
+                data: &tile.pixels,
+            },
+            &DrawOptions::new(),
+        );
+        target.set_transform(&transform);
+    }
+}
+
+/// Draws `primitive` onto the linear-light layer `target`, with its solid colors converted to
+/// linear light and text drawn from `tiles`.
+pub(super) fn draw_linear(
+    target: &mut DrawTarget<&mut [u32]>,
+    options: &DrawOptions,
+    backend: &mut Backend,
+    scale: f32,
+    primitive: &Primitive,
+    tiles: &mut TextTiles,
+) {
+    LinearPainter {
+        options,
+        backend,
+        scale,
+        tiles,
+    }
+    .draw(target, primitive, Vector::new(0.0, 0.0));
+}
@@ -0,0 +1,204 @@
+//! Per-scale render buffers of an `IcedElement`.
+//!
+//! Outputs sharing a scale also share a buffer. If those outputs render at different times,
+//! an in-place redraw may hit a buffer another output is still reading from,
+//! so such buffers get a second (back) buffer, that is rasterized into and then swapped.
+//! Elements may also use up to three buffers (the buffer age) for their own multi-buffering.
+//! Each buffer then only needs the damage accumulated since it was drawn last.
+//!
+//! Uploads are cached by smithay: `MemoryRenderBuffer` keeps the texture of every renderer it was
+//! imported into, along with the commit that renderer saw last. Rendering an unchanged buffer
+//! (e.g. a cached frame of `clean`) reuses that texture, redrawn buffers only upload the damage
+//! reported by their `render()` context. Recreating a buffer (`resize`) drops
+//! its textures, so only do so if the old content can't be kept.
+
+use std::collections::VecDeque;
+
+use smithay::{
+    backend::{allocator::Fourcc, renderer::element::memory::MemoryRenderBuffer},
+    utils::{Buffer, Logical, Rectangle, Size, Transform},
+};
+
+pub(super) struct ScaleBuffer {
+    front: MemoryRenderBuffer,
+    /// Buffers not presented, the first is drawn into next
+    back: Vec<MemoryRenderBuffer>,
+    size: Size<i32, Buffer>,
+    needs_redraw: bool,
+    /// Damage of the pending redraw, `None` if the whole buffer is damaged
+    damage: Option<Vec<Rectangle<i32, Logical>>>,
+    /// Damage of the last draws (oldest first), one per back buffer
+    history: VecDeque<Option<Vec<Rectangle<i32, Logical>>>>,
+    /// Hash of the content last drawn, for buffers redrawn only on changes
+    content_hash: Option<u64>,
+}
+
+/// Size of the buffer of an element of `size` at `scale`.
+/// Maximum number of buffers per scale.
+pub(super) const MAX_BUFFER_AGE: u8 = 3;
+
+pub(super) fn buffer_size(size: Size<i32, Logical>, scale: f64) -> Size<i32, Buffer> {
+    size.to_f64()
+        .to_buffer(scale, Transform::Normal)
+        .to_i32_round()
+}
+
+fn new_buffer(size: Size<i32, Buffer>) -> MemoryRenderBuffer {
+    MemoryRenderBuffer::new(Fourcc::Argb8888, size, 1, Transform::Normal, None)
+}
+
+impl ScaleBuffer {
+    pub fn new(size: Size<i32, Buffer>) -> ScaleBuffer {
+        ScaleBuffer {
+            front: new_buffer(size),
+            back: Vec::new(),
+            size,
+            needs_redraw: true,
+            damage: None,
+            history: VecDeque::new(),
+            content_hash: None,
+        }
+    }
+
+    /// Recreates the buffers with a new size, keeping the number of buffers.
+    pub fn resize(&mut self, size: Size<i32, Buffer>) {
+        let count = self.buffer_count();
+        *self = ScaleBuffer::new(size);
+        self.set_buffer_count(count);
+    }
+
+    pub fn size(&self) -> Size<i32, Buffer> {
+        self.size
+    }
+
+    pub fn is_double_buffered(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn buffer_count(&self) -> u8 {
+        self.back.len() as u8 + 1
+    }
+
+    /// Sets the number of buffers, between one and `MAX_BUFFER_AGE`.
+    pub fn set_buffer_count(&mut self, count: u8) {
+        let count = count.clamp(1, MAX_BUFFER_AGE);
+        if count == self.buffer_count() {
+            return;
+        }
+        self.back = (1..count).map(|_| new_buffer(self.size)).collect();
+        // new buffers are empty, so they need a full redraw
+        self.history.clear();
+    }
+
+    pub fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    /// Requests a redraw of the whole buffer.
+    pub fn mark_dirty(&mut self) {
+        self.needs_redraw = true;
+        self.damage = None;
+    }
+
+    /// Requests a redraw of the whole buffer, even if its content hash didn't change.
+    pub fn invalidate(&mut self) {
+        self.mark_dirty();
+        self.content_hash = None;
+    }
+
+    pub fn content_hash(&self) -> Option<u64> {
+        self.content_hash
+    }
+
+    pub fn set_content_hash(&mut self, hash: Option<u64>) {
+        self.content_hash = hash;
+    }
+
+    /// Clears the pending redraw without drawing, as the content didn't change.
+    pub fn discard_damage(&mut self) {
+        self.needs_redraw = false;
+        self.damage = None;
+    }
+
+    /// Requests a redraw, only damaging the given regions.
+    pub fn add_damage(&mut self, regions: &[Rectangle<i32, Logical>]) {
+        if !self.needs_redraw {
+            self.needs_redraw = true;
+            self.damage = Some(Vec::new());
+        }
+        if let Some(damage) = self.damage.as_mut() {
+            damage.extend_from_slice(regions);
+        }
+    }
+
+    /// Clears the pending redraw and returns its damage in buffer coordinates.
+    ///
+    /// The buffer drawn into next missed the draws into the other buffers,
+    /// so their damage is included as well.
+    pub fn take_damage(
+        &mut self,
+        scale: f64,
+        size: Size<i32, Logical>,
+    ) -> Vec<Rectangle<i32, Buffer>> {
+        self.needs_redraw = false;
+        let damage = self.damage.take();
+
+        let accumulated = if self.history.len() < self.back.len() {
+            None
+        } else {
+            self.history
+                .iter()
+                .chain(std::iter::once(&damage))
+                .try_fold(Vec::new(), |mut acc, damage| {
+                    acc.extend_from_slice(damage.as_deref()?);
+                    Some(acc)
+                })
+        };
+        if !self.back.is_empty() {
+            self.history.push_back(damage);
+            while self.history.len() > self.back.len() {
+                self.history.pop_front();
+            }
+        }
+
+        match accumulated {
+            Some(damage) => damage
+                .into_iter()
+                .map(|rect| {
+                    rect.to_f64()
+                        .to_buffer(scale, Transform::Normal, &size.to_f64())
+                        .to_i32_up()
+                })
+                .collect(),
+            None => vec![Rectangle::from_loc_and_size((0, 0), self.size)],
+        }
+    }
+
+    /// Damages `damage` of the presented buffer without changing its content,
+    /// so it is composited again.
+    pub fn damage_front(&mut self, damage: Vec<Rectangle<i32, Buffer>>) {
+        let _ = self
+            .front
+            .render()
+            .draw(|_| Result::<_, std::convert::Infallible>::Ok(damage));
+    }
+
+    /// The buffer to render from. Never written to while double buffered.
+    pub fn front(&self) -> &MemoryRenderBuffer {
+        &self.front
+    }
+
+    /// The buffer to rasterize into. Call `swap` once drawing is complete.
+    pub fn back_mut(&mut self) -> &mut MemoryRenderBuffer {
+        self.back.first_mut().unwrap_or(&mut self.front)
+    }
+
+    /// Presents the back buffer. The damage of the last draw is carried by the buffer itself.
+    pub fn swap(&mut self) {
+        if self.back.is_empty() {
+            return;
+        }
+        let back = self.back.remove(0);
+        self.back.push(std::mem::replace(&mut self.front, back));
+    }
+}
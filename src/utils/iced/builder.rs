@@ -0,0 +1,85 @@
+//! Builder for `IcedElement`s, making optional settings explicit.
+
+use cosmic::Theme;
+use smithay::{
+    reexports::calloop::LoopHandle,
+    utils::{Logical, Size},
+};
+
+use super::{IcedElement, Program};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum IcedElementError {
+    #[error("No loop handle was provided")]
+    MissingLoopHandle,
+    #[error("No size was provided")]
+    MissingSize,
+    #[error("Invalid element size {0:?}")]
+    InvalidSize(Size<i32, Logical>),
+}
+
+/// See `IcedElement::builder`.
+pub struct IcedElementBuilder<P: Program + Send + 'static> {
+    program: P,
+    size: Option<Size<i32, Logical>>,
+    handle: Option<LoopHandle<'static, crate::state::Data>>,
+    theme: Option<Theme>,
+    z_index: Option<u8>,
+}
+
+impl<P: Program + Send + 'static> IcedElementBuilder<P> {
+    pub(super) fn new(program: P) -> IcedElementBuilder<P> {
+        IcedElementBuilder {
+            program,
+            size: None,
+            handle: None,
+            theme: None,
+            z_index: None,
+        }
+    }
+
+    pub fn size(mut self, w: i32, h: i32) -> Self {
+        self.size = Some(Size::from((w, h)));
+        self
+    }
+
+    pub fn loop_handle(mut self, handle: LoopHandle<'static, crate::state::Data>) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Overrides the z-index declared by the program, see `IcedElement::set_z_index`.
+    pub fn z_index(mut self, z: u8) -> Self {
+        self.z_index = Some(z);
+        self
+    }
+
+    pub fn build(self) -> Result<IcedElement<P>, IcedElementError> {
+        let handle = self.handle.ok_or(IcedElementError::MissingLoopHandle)?;
+        let size = self.size.ok_or(IcedElementError::MissingSize)?;
+        if size.w < 0 || size.h < 0 {
+            return Err(IcedElementError::InvalidSize(size));
+        }
+
+        let element = IcedElement::new(self.program, size, handle);
+        if let Some(theme) = self.theme {
+            element.set_theme(theme);
+        }
+        if let Some(z) = self.z_index {
+            element.set_z_index(z);
+        }
+        Ok(element)
+    }
+}
+
+impl<P: Program + Send + 'static> IcedElement<P> {
+    /// Starts building an element for `program`, as an alternative to `IcedElement::new`.
+    pub fn builder(program: P) -> IcedElementBuilder<P> {
+        IcedElementBuilder::new(program)
+    }
+}
@@ -0,0 +1,86 @@
+//! Optional features an element supports, for call sites choosing between a feature and its fallback.
+//!
+//! Capabilities combine what a program declares via `Program::capabilities`, what is detected from
+//! its optional hooks and what the element was configured with (transitions, output offsets, ...).
+//! Call sites requiring a capability the element lacks log a single warning per mismatch, see
+//! `IcedElement::require`.
+
+use super::Program;
+
+bitflags::bitflags! {
+    /// Optional features of a program, see `Program::capabilities`.
+    pub struct ProgramCapabilities: u32 {
+        /// Implements `background` or `foreground`
+        const DRAW_HOOKS    = 1 << 0;
+        /// Implements `custom_render`
+        const CUSTOM_RENDER = 1 << 1;
+        const LAYERS        = 1 << 2;
+        const DROP_TARGET   = 1 << 3;
+        const INITIAL_FOCUS = 1 << 4;
+        const INPUT_METHOD  = 1 << 5;
+        const PROGRESSIVE   = 1 << 6;
+    }
+}
+
+bitflags::bitflags! {
+    /// Optional features of an element, see `IcedElement::capabilities`.
+    ///
+    /// Contains the bits of its program's `ProgramCapabilities`, plus those of its configuration.
+    pub struct Capabilities: u32 {
+        const DRAW_HOOKS       = ProgramCapabilities::DRAW_HOOKS.bits;
+        const CUSTOM_RENDER    = ProgramCapabilities::CUSTOM_RENDER.bits;
+        const LAYERS           = ProgramCapabilities::LAYERS.bits;
+        const DROP_TARGET      = ProgramCapabilities::DROP_TARGET.bits;
+        const INITIAL_FOCUS    = ProgramCapabilities::INITIAL_FOCUS.bits;
+        const INPUT_METHOD     = ProgramCapabilities::INPUT_METHOD.bits;
+        const PROGRESSIVE      = ProgramCapabilities::PROGRESSIVE.bits;
+
+        /// Set via `IcedElement::set_space_transition`
+        const ENTER_TRANSITION = 1 << 16;
+        const EXIT_TRANSITION  = 1 << 17;
+        /// Located on at least one output, see `IcedElement::set_location`
+        const OUTPUT_OFFSETS   = 1 << 18;
+        /// See `IcedElement::add_virtual_target`
+        const VIRTUAL_TARGETS  = 1 << 19;
+        /// See `IcedElement::new_with_pool`
+        const RENDER_POOL      = 1 << 20;
+        /// See `IcedElement::set_shell_request_handler`
+        const SHELL_REQUESTS   = 1 << 21;
+    }
+}
+
+impl From<ProgramCapabilities> for Capabilities {
+    fn from(capabilities: ProgramCapabilities) -> Capabilities {
+        Capabilities::from_bits_truncate(capabilities.bits())
+    }
+}
+
+/// Capabilities declared by `program`, plus those detected from its optional hooks.
+///
+/// Detection relies on the hooks' current state, so this is refreshed after every update.
+pub(super) fn detect<P: Program>(program: &P) -> ProgramCapabilities {
+    let mut capabilities = program.capabilities();
+    let detected = [
+        (ProgramCapabilities::DRAW_HOOKS, program.uses_hooks()),
+        (ProgramCapabilities::LAYERS, !program.layers().is_empty()),
+        (ProgramCapabilities::DROP_TARGET, program.accepts_drops()),
+        (
+            ProgramCapabilities::INITIAL_FOCUS,
+            program.initial_focus().is_some(),
+        ),
+        (
+            ProgramCapabilities::INPUT_METHOD,
+            program.wants_input_method(),
+        ),
+        (
+            ProgramCapabilities::PROGRESSIVE,
+            program.progressive().is_some(),
+        ),
+    ];
+    for (capability, supported) in detected {
+        if supported {
+            capabilities.insert(capability);
+        }
+    }
+    capabilities
+}
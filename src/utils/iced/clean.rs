@@ -0,0 +1,83 @@
+//! Reuse of an element's last frame, without locking the element, while nothing about it changed.
+//!
+//! Locking an element to change it marks it dirty (see `IcedElement::lock`), as do renders that
+//! can't show their frame again as is (transitions, layers, pending redraws, ...). Renders of
+//! clean elements only import the cached front buffer again, skipping the update and any drawing.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use smithay::{
+    backend::renderer::element::memory::MemoryRenderBuffer,
+    utils::{Logical, Rectangle, Size},
+};
+
+/// Front buffer of a clean element at one scale, with the parameters it was shown with.
+#[derive(Clone)]
+pub(super) struct CachedFrame {
+    pub scale: f64,
+    pub buffer: MemoryRenderBuffer,
+    pub src: Rectangle<f64, Logical>,
+    pub size: Size<i32, Logical>,
+}
+
+pub(super) struct CleanFrame {
+    dirty: AtomicBool,
+    /// Frames of all scales rendered since the element was last marked dirty
+    frames: Mutex<Vec<CachedFrame>>,
+}
+
+impl Default for CleanFrame {
+    fn default() -> CleanFrame {
+        CleanFrame {
+            dirty: AtomicBool::new(true),
+            frames: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl CleanFrame {
+    /// Must be called with the element locked, after it may have changed.
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Cached frame of `scale`, unless the element is dirty.
+    pub fn get(&self, scale: f64) -> Option<CachedFrame> {
+        if self.dirty.load(Ordering::Acquire) {
+            return None;
+        }
+        self.frames
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|frame| frame.scale == scale)
+            .cloned()
+    }
+
+    /// Caches the frame just rendered by a render of the locked element.
+    ///
+    /// Frames of other scales are dropped, if the element changed since they were cached.
+    pub fn store(&self, frame: CachedFrame) {
+        let mut frames = self.frames.lock().unwrap();
+        if self.dirty.swap(false, Ordering::AcqRel) {
+            frames.clear();
+        }
+        frames.retain(|cached| cached.scale != frame.scale);
+        frames.push(frame);
+    }
+}
+
+impl fmt::Debug for CleanFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CleanFrame")
+            .field("dirty", &self.dirty.load(Ordering::Relaxed))
+            .field("frames", &self.frames.lock().unwrap().len())
+            .finish()
+    }
+}
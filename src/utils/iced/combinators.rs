@@ -0,0 +1,1391 @@
+//! Combinators to compose multiple `Program`s into a single element.
+//!
+//! All combinators implement `Program` themselves and can thus be nested freely.
+//!
+//! Hooks are merged as follows:
+//! - `background`/`foreground`/`custom_render` of all visible children are drawn in order
+//!   (first/base before second/top), each child only sees its own part of the buffer for `Split`.
+//!   `uses_hooks` is set, if any child (including hidden ones) sets it.
+//! - `capabilities` of all children (including hidden ones) are combined.
+//! - `z_index` is the maximum of all children.
+//! - `scale_mode` is `CeilToInteger`, if any child requests it.
+//! - `subscriptions` of all children are combined, hidden `Conditional` programs keep theirs.
+//!   `update_interval` is the shortest interval of all children, `max_fps` the highest limit
+//!   (unlimited if any child is).
+//! - Hooks that may only produce a single value (`config_id`, `config_keys`, `on_config_changed`,
+//!   `initial_focus`, `content_state`, `progressive` and `progressive_stage`) are taken from the
+//!   first/base program, `content_state` of hidden `Conditional` programs is `Ready`.
+//! - `shortcuts` of all visible children are combined, the `shortcut_priority` is the highest of
+//!   all children.
+//! - `refresh_changed`, `fonts_changed`, `preferences_changed`, `intent_undone`, `idle` and
+//!   `resumed` are forwarded to both programs, if both react their messages are combined into
+//!   `Either::Both` and updated in order.
+//! - `scroll_regions` of `Overlaid` and visible `Conditional` programs are combined (base first),
+//!   `unmatched_scroll` and `configure_scroll_physics` are taken from the first/base program. `Split` doesn't forward them,
+//!   as its children don't know their offset within the element.
+//! - `press_regions`, `interaction_regions` and `layers` are combined like `scroll_regions`,
+//!   `optimistic_feedback` requires all children to allow it.
+//! - `secure_fields` of all (visible) children are combined, including those of `Split`.
+//! - `input_region` of `Overlaid` programs is the union of both, covering the whole element if
+//!   either does. Hidden `Conditional` programs accept no input, if they narrow their region at
+//!   all. `Split` covers the whole element.
+//! - `wants_input_method` and `is_drag_active` are set, if any (visible) child reports them.
+//! - Tablet pad input and internal drag-and-drop hooks are forwarded the same way, but to the
+//!   top program first for `Overlaid` and only to visible programs for `Conditional`.
+//!   `drag_cancelled` also reaches hidden `Conditional` programs, which may have started the drag.
+//!   `dnd_mime_types` of all (visible) children are combined in that order.
+
+use std::time::Duration;
+
+use cosmic::{
+    iced::widget::{container, Column, Row, Space},
+    iced_native::{
+        event::{self, Event},
+        layout, mouse, overlay, renderer,
+        widget::{Id, Operation, Tree},
+        Clipboard, Command, Layout, Length, Point, Rectangle, Shell, Widget,
+    },
+    Element,
+};
+use iced_softbuffer::native::raqote::{self, DrawTarget};
+use smithay::{
+    backend::input::{AxisSource, ButtonState},
+    utils::{Logical, Physical, Size},
+};
+
+use super::{
+    ContentState, DragPayload, FocusTarget, FontConfig, HookPalette, IntentId, InteractionRegion,
+    KeyPattern, LayerSpec, Preferences, Program, ProgramCapabilities, ProgressivePlan, RefreshInfo,
+    RingEventSource, ScaleMode, ScrollPhysics, ScrollRegion, ShortcutPriority, StripEventSource,
+    Subscription, UnmatchedScroll, UpdateContext,
+};
+
+/// Message type of combinators wrapping two programs.
+#[derive(Debug, Clone)]
+pub enum Either<A, B> {
+    First(A),
+    Second(B),
+    /// Messages of both programs, reacting to the same notification
+    Both(A, B),
+}
+
+impl<A, B> Either<A, B> {
+    /// Combines the reactions of both programs to a notification forwarded to each of them.
+    fn both(first: Option<A>, second: Option<B>) -> Option<Self> {
+        match (first, second) {
+            (Some(first), Some(second)) => Some(Either::Both(first, second)),
+            (first, second) => first
+                .map(Either::First)
+                .or_else(|| second.map(Either::Second)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    Horizontal,
+    Vertical,
+}
+
+/// Shows two programs side by side (`Horizontal`) or on top of each other (`Vertical`).
+pub struct Split<A: Program, B: Program> {
+    pub first: A,
+    pub second: B,
+    direction: SplitDirection,
+    /// Share of the available space given to `first`
+    ratio: f32,
+}
+
+impl<A: Program, B: Program> Split<A, B> {
+    pub fn new(first: A, second: B, direction: SplitDirection, ratio: f32) -> Self {
+        Split {
+            first,
+            second,
+            direction,
+            ratio: ratio.clamp(0.0, 1.0),
+        }
+    }
+
+    fn portions(&self) -> (u16, u16) {
+        let first = (self.ratio * 1000.0).round() as u16;
+        (first, 1000 - first)
+    }
+
+    fn split_target(
+        &self,
+        width: i32,
+        height: i32,
+    ) -> ((i32, i32, i32, i32), (i32, i32, i32, i32)) {
+        match self.direction {
+            SplitDirection::Horizontal => {
+                let first = (width as f32 * self.ratio).round() as i32;
+                ((0, 0, first, height), (first, 0, width - first, height))
+            }
+            SplitDirection::Vertical => {
+                let first = (height as f32 * self.ratio).round() as i32;
+                ((0, 0, width, first), (0, first, width, height - first))
+            }
+        }
+    }
+}
+
+fn shortest(a: Option<Duration>, b: Option<Duration>) -> Option<Duration> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn highest_fps(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+    Some(a?.max(b?))
+}
+
+/// Runs `draw` on a target of the given rectangle and composites the result onto `target`.
+pub(super) fn draw_in(
+    target: &mut DrawTarget<&mut [u32]>,
+    (x, y, width, height): (i32, i32, i32, i32),
+    draw: impl FnOnce(&mut DrawTarget<&mut [u32]>),
+) {
+    if width <= 0 || height <= 0 {
+        return;
+    }
+
+    let mut data = vec![0u32; (width * height) as usize];
+    let mut sub_target = DrawTarget::from_backing(width, height, &mut data[..]);
+    draw(&mut sub_target);
+    drop(sub_target);
+
+    target.draw_image_at(
+        x as f32,
+        y as f32,
+        &raqote::Image {
+            width,
+            height,
+            data: &data,
+        },
+        &raqote::DrawOptions::new(),
+    );
+}
+
+impl<A, B> Program for Split<A, B>
+where
+    A: Program,
+    B: Program,
+    A::Message: 'static,
+    B::Message: 'static,
+{
+    type Message = Either<A::Message, B::Message>;
+
+    fn try_clone_message(message: &Self::Message) -> Option<Self::Message> {
+        match message {
+            Either::First(message) => A::try_clone_message(message).map(Either::First),
+            Either::Second(message) => B::try_clone_message(message).map(Either::Second),
+            Either::Both(first, second) => Some(Either::Both(
+                A::try_clone_message(first)?,
+                B::try_clone_message(second)?,
+            )),
+        }
+    }
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        match message {
+            Either::First(message) => self
+                .first
+                .update_with_context(message, ctx)
+                .map(Either::First),
+            Either::Second(message) => self
+                .second
+                .update_with_context(message, ctx)
+                .map(Either::Second),
+            Either::Both(first, second) => Command::batch([
+                self.first
+                    .update_with_context(first, ctx)
+                    .map(Either::First),
+                self.second
+                    .update_with_context(second, ctx)
+                    .map(Either::Second),
+            ]),
+        }
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        let (first_portion, second_portion) = self.portions();
+        let first = self.first.view().map(Either::First);
+        let second = self.second.view().map(Either::Second);
+
+        match self.direction {
+            SplitDirection::Horizontal => Row::with_children(vec![
+                container(first)
+                    .width(Length::FillPortion(first_portion))
+                    .height(Length::Fill)
+                    .into(),
+                container(second)
+                    .width(Length::FillPortion(second_portion))
+                    .height(Length::Fill)
+                    .into(),
+            ])
+            .into(),
+            SplitDirection::Vertical => Column::with_children(vec![
+                container(first)
+                    .width(Length::Fill)
+                    .height(Length::FillPortion(first_portion))
+                    .into(),
+                container(second)
+                    .width(Length::Fill)
+                    .height(Length::FillPortion(second_portion))
+                    .into(),
+            ])
+            .into(),
+        }
+    }
+
+    fn themed_background(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        let (first, second) = self.split_target(target.width(), target.height());
+        draw_in(target, first, |target| {
+            self.first.themed_background(target, palette)
+        });
+        draw_in(target, second, |target| {
+            self.second.themed_background(target, palette)
+        });
+    }
+
+    fn themed_foreground(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        let (first, second) = self.split_target(target.width(), target.height());
+        draw_in(target, first, |target| {
+            self.first.themed_foreground(target, palette)
+        });
+        draw_in(target, second, |target| {
+            self.second.themed_foreground(target, palette)
+        });
+    }
+
+    fn custom_render(
+        &mut self,
+        target: &mut DrawTarget<&mut [u32]>,
+        _size: Size<i32, Physical>,
+        scale: f64,
+    ) {
+        let (first, second) = self.split_target(target.width(), target.height());
+        draw_in(target, first, |target| {
+            self.first
+                .custom_render(target, (first.2, first.3).into(), scale)
+        });
+        draw_in(target, second, |target| {
+            self.second
+                .custom_render(target, (second.2, second.3).into(), scale)
+        });
+    }
+
+    fn custom_render_only(&self) -> bool {
+        self.first.custom_render_only() && self.second.custom_render_only()
+    }
+
+    fn uses_hooks(&self) -> bool {
+        self.first.uses_hooks() || self.second.uses_hooks()
+    }
+    fn capabilities(&self) -> ProgramCapabilities {
+        self.first.capabilities() | self.second.capabilities()
+    }
+
+    fn z_index(&self) -> u8 {
+        self.first.z_index().max(self.second.z_index())
+    }
+
+    fn update_interval(&self) -> Option<Duration> {
+        shortest(self.first.update_interval(), self.second.update_interval())
+    }
+
+    fn max_fps(&self) -> Option<u32> {
+        highest_fps(self.first.max_fps(), self.second.max_fps())
+    }
+
+    fn shortcuts(&self) -> Vec<(KeyPattern, Self::Message)> {
+        self.first
+            .shortcuts()
+            .into_iter()
+            .map(|(pattern, message)| (pattern, Either::First(message)))
+            .chain(
+                self.second
+                    .shortcuts()
+                    .into_iter()
+                    .map(|(pattern, message)| (pattern, Either::Second(message))),
+            )
+            .collect()
+    }
+
+    fn shortcut_priority(&self) -> ShortcutPriority {
+        self.first
+            .shortcut_priority()
+            .max(self.second.shortcut_priority())
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription<Self::Message>> {
+        self.first
+            .subscriptions()
+            .into_iter()
+            .map(|s| s.map(Either::First).salted(0))
+            .chain(
+                self.second
+                    .subscriptions()
+                    .into_iter()
+                    .map(|s| s.map(Either::Second).salted(1)),
+            )
+            .collect()
+    }
+
+    fn scale_mode(&self) -> ScaleMode {
+        if self.first.scale_mode() == ScaleMode::CeilToInteger {
+            ScaleMode::CeilToInteger
+        } else {
+            self.second.scale_mode()
+        }
+    }
+
+    fn config_id(&self) -> Option<(&'static str, u64)> {
+        self.first.config_id()
+    }
+
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        self.first.initial_focus()
+    }
+
+    fn content_state(&self) -> ContentState {
+        self.first.content_state()
+    }
+
+    fn progressive(&self) -> Option<ProgressivePlan> {
+        self.first.progressive()
+    }
+
+    fn progressive_stage(&self, stage: u32) -> Option<Self::Message> {
+        self.first.progressive_stage(stage).map(Either::First)
+    }
+
+    fn config_keys(&self) -> &'static [&'static str] {
+        self.first.config_keys()
+    }
+
+    fn on_config_changed(&self, key: &str, value: Option<ron::Value>) -> Option<Self::Message> {
+        self.first.on_config_changed(key, value).map(Either::First)
+    }
+
+    fn refresh_changed(&mut self, info: &RefreshInfo) -> Option<Self::Message> {
+        Either::both(
+            self.first.refresh_changed(info),
+            self.second.refresh_changed(info),
+        )
+    }
+
+    fn fonts_changed(&self, fonts: &FontConfig) -> Option<Self::Message> {
+        Either::both(
+            self.first.fonts_changed(fonts),
+            self.second.fonts_changed(fonts),
+        )
+    }
+
+    fn preferences_changed(&self, preferences: &Preferences) -> Option<Self::Message> {
+        Either::both(
+            self.first.preferences_changed(preferences),
+            self.second.preferences_changed(preferences),
+        )
+    }
+
+    fn intent_undone(&self, intent: IntentId) -> Option<Self::Message> {
+        Either::both(
+            self.first.intent_undone(intent),
+            self.second.intent_undone(intent),
+        )
+    }
+
+    fn idle(&mut self) -> Option<Self::Message> {
+        Either::both(self.first.idle(), self.second.idle())
+    }
+
+    fn resumed(&mut self) -> Option<Self::Message> {
+        Either::both(self.first.resumed(), self.second.resumed())
+    }
+
+    fn optimistic_feedback(&self) -> bool {
+        self.first.optimistic_feedback() && self.second.optimistic_feedback()
+    }
+
+    fn wants_input_method(&self) -> bool {
+        self.first.wants_input_method() || self.second.wants_input_method()
+    }
+
+    fn secure_fields(&self) -> Vec<Id> {
+        let mut fields = self.first.secure_fields();
+        fields.extend(self.second.secure_fields());
+        fields
+    }
+
+    fn on_pad_button(&mut self, button: u32, state: ButtonState) {
+        self.first.on_pad_button(button, state);
+        self.second.on_pad_button(button, state);
+    }
+
+    fn on_pad_ring(&mut self, source: RingEventSource, degrees: f64) {
+        self.first.on_pad_ring(source, degrees);
+        self.second.on_pad_ring(source, degrees);
+    }
+
+    fn on_pad_strip(&mut self, source: StripEventSource, position: f64) {
+        self.first.on_pad_strip(source, position);
+        self.second.on_pad_strip(source, position);
+    }
+
+    fn is_drag_active(&self) -> bool {
+        self.first.is_drag_active() || self.second.is_drag_active()
+    }
+
+    fn accepts_drops(&self) -> bool {
+        self.first.accepts_drops() || self.second.accepts_drops()
+    }
+
+    fn dnd_enter(
+        &self,
+        payload: &DragPayload,
+        position: smithay::utils::Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.first
+            .dnd_enter(payload, position)
+            .map(Either::First)
+            .or_else(|| self.second.dnd_enter(payload, position).map(Either::Second))
+    }
+
+    fn dnd_mime_types(&self, position: smithay::utils::Point<f64, Logical>) -> Vec<String> {
+        let mut mime_types = self.first.dnd_mime_types(position);
+        mime_types.extend(self.second.dnd_mime_types(position));
+        mime_types
+    }
+
+    fn dnd_motion(&self, position: smithay::utils::Point<f64, Logical>) -> Option<Self::Message> {
+        self.first
+            .dnd_motion(position)
+            .map(Either::First)
+            .or_else(|| self.second.dnd_motion(position).map(Either::Second))
+    }
+
+    fn dnd_leave(&self) -> Option<Self::Message> {
+        self.first
+            .dnd_leave()
+            .map(Either::First)
+            .or_else(|| self.second.dnd_leave().map(Either::Second))
+    }
+
+    fn dnd_drop(
+        &self,
+        payload: &DragPayload,
+        position: smithay::utils::Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.first
+            .dnd_drop(payload, position)
+            .map(Either::First)
+            .or_else(|| self.second.dnd_drop(payload, position).map(Either::Second))
+    }
+
+    fn drag_cancelled(&self, payload: &DragPayload) -> Option<Self::Message> {
+        self.first
+            .drag_cancelled(payload)
+            .map(Either::First)
+            .or_else(|| self.second.drag_cancelled(payload).map(Either::Second))
+    }
+}
+
+/// Shows `top` above `base`.
+///
+/// Input is delivered to `top` first. If `top` doesn't capture an event it is forwarded to `base`,
+/// if either `pass_through` is set or the cursor is outside of `top`'s bounds.
+pub struct Overlaid<Base: Program, Top: Program> {
+    pub base: Base,
+    pub top: Top,
+    pass_through: bool,
+}
+
+impl<Base: Program, Top: Program> Overlaid<Base, Top> {
+    pub fn new(base: Base, top: Top, pass_through: bool) -> Self {
+        Overlaid {
+            base,
+            top,
+            pass_through,
+        }
+    }
+}
+
+impl<Base, Top> Program for Overlaid<Base, Top>
+where
+    Base: Program,
+    Top: Program,
+    Base::Message: 'static,
+    Top::Message: 'static,
+{
+    type Message = Either<Base::Message, Top::Message>;
+
+    fn try_clone_message(message: &Self::Message) -> Option<Self::Message> {
+        match message {
+            Either::First(message) => Base::try_clone_message(message).map(Either::First),
+            Either::Second(message) => Top::try_clone_message(message).map(Either::Second),
+            Either::Both(first, second) => Some(Either::Both(
+                Base::try_clone_message(first)?,
+                Top::try_clone_message(second)?,
+            )),
+        }
+    }
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        match message {
+            Either::First(message) => self
+                .base
+                .update_with_context(message, ctx)
+                .map(Either::First),
+            Either::Second(message) => self
+                .top
+                .update_with_context(message, ctx)
+                .map(Either::Second),
+            Either::Both(first, second) => Command::batch([
+                self.base.update_with_context(first, ctx).map(Either::First),
+                self.top
+                    .update_with_context(second, ctx)
+                    .map(Either::Second),
+            ]),
+        }
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Element::new(Stack {
+            children: [
+                self.base.view().map(Either::First),
+                self.top.view().map(Either::Second),
+            ],
+            pass_through: self.pass_through,
+        })
+    }
+
+    fn themed_background(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        self.base.themed_background(target, palette);
+        self.top.themed_background(target, palette);
+    }
+
+    fn themed_foreground(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        self.base.themed_foreground(target, palette);
+        self.top.themed_foreground(target, palette);
+    }
+
+    fn custom_render(
+        &mut self,
+        target: &mut DrawTarget<&mut [u32]>,
+        size: Size<i32, Physical>,
+        scale: f64,
+    ) {
+        self.base.custom_render(target, size, scale);
+        self.top.custom_render(target, size, scale);
+    }
+
+    fn custom_render_only(&self) -> bool {
+        self.base.custom_render_only() && self.top.custom_render_only()
+    }
+
+    fn uses_hooks(&self) -> bool {
+        self.base.uses_hooks() || self.top.uses_hooks()
+    }
+    fn capabilities(&self) -> ProgramCapabilities {
+        self.base.capabilities() | self.top.capabilities()
+    }
+
+    fn z_index(&self) -> u8 {
+        self.base.z_index().max(self.top.z_index())
+    }
+
+    fn update_interval(&self) -> Option<Duration> {
+        shortest(self.base.update_interval(), self.top.update_interval())
+    }
+
+    fn max_fps(&self) -> Option<u32> {
+        highest_fps(self.base.max_fps(), self.top.max_fps())
+    }
+
+    fn shortcuts(&self) -> Vec<(KeyPattern, Self::Message)> {
+        self.base
+            .shortcuts()
+            .into_iter()
+            .map(|(pattern, message)| (pattern, Either::First(message)))
+            .chain(
+                self.top
+                    .shortcuts()
+                    .into_iter()
+                    .map(|(pattern, message)| (pattern, Either::Second(message))),
+            )
+            .collect()
+    }
+
+    fn shortcut_priority(&self) -> ShortcutPriority {
+        self.base
+            .shortcut_priority()
+            .max(self.top.shortcut_priority())
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription<Self::Message>> {
+        self.base
+            .subscriptions()
+            .into_iter()
+            .map(|s| s.map(Either::First).salted(0))
+            .chain(
+                self.top
+                    .subscriptions()
+                    .into_iter()
+                    .map(|s| s.map(Either::Second).salted(1)),
+            )
+            .collect()
+    }
+
+    fn scale_mode(&self) -> ScaleMode {
+        if self.base.scale_mode() == ScaleMode::CeilToInteger {
+            ScaleMode::CeilToInteger
+        } else {
+            self.top.scale_mode()
+        }
+    }
+
+    fn scroll_regions(&self) -> Vec<ScrollRegion> {
+        let mut regions = self.base.scroll_regions();
+        regions.extend(self.top.scroll_regions());
+        regions
+    }
+
+    fn unmatched_scroll(&self) -> UnmatchedScroll {
+        self.base.unmatched_scroll()
+    }
+
+    fn configure_scroll_physics(&self, source: AxisSource) -> ScrollPhysics {
+        self.base.configure_scroll_physics(source)
+    }
+
+    fn config_id(&self) -> Option<(&'static str, u64)> {
+        self.base.config_id()
+    }
+
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        self.base.initial_focus()
+    }
+
+    fn content_state(&self) -> ContentState {
+        self.base.content_state()
+    }
+
+    fn progressive(&self) -> Option<ProgressivePlan> {
+        self.base.progressive()
+    }
+
+    fn progressive_stage(&self, stage: u32) -> Option<Self::Message> {
+        self.base.progressive_stage(stage).map(Either::First)
+    }
+
+    fn config_keys(&self) -> &'static [&'static str] {
+        self.base.config_keys()
+    }
+
+    fn on_config_changed(&self, key: &str, value: Option<ron::Value>) -> Option<Self::Message> {
+        self.base.on_config_changed(key, value).map(Either::First)
+    }
+
+    fn refresh_changed(&mut self, info: &RefreshInfo) -> Option<Self::Message> {
+        Either::both(
+            self.base.refresh_changed(info),
+            self.top.refresh_changed(info),
+        )
+    }
+
+    fn fonts_changed(&self, fonts: &FontConfig) -> Option<Self::Message> {
+        Either::both(
+            self.base.fonts_changed(fonts),
+            self.top.fonts_changed(fonts),
+        )
+    }
+
+    fn preferences_changed(&self, preferences: &Preferences) -> Option<Self::Message> {
+        Either::both(
+            self.base.preferences_changed(preferences),
+            self.top.preferences_changed(preferences),
+        )
+    }
+
+    fn intent_undone(&self, intent: IntentId) -> Option<Self::Message> {
+        Either::both(
+            self.base.intent_undone(intent),
+            self.top.intent_undone(intent),
+        )
+    }
+
+    fn idle(&mut self) -> Option<Self::Message> {
+        Either::both(self.base.idle(), self.top.idle())
+    }
+
+    fn resumed(&mut self) -> Option<Self::Message> {
+        Either::both(self.base.resumed(), self.top.resumed())
+    }
+
+    fn press_regions(&self) -> Vec<smithay::utils::Rectangle<i32, Logical>> {
+        let mut regions = self.base.press_regions();
+        regions.extend(self.top.press_regions());
+        regions
+    }
+
+    fn interaction_regions(&self) -> Vec<InteractionRegion> {
+        let mut regions = self.base.interaction_regions();
+        regions.extend(self.top.interaction_regions());
+        regions
+    }
+
+    fn secure_fields(&self) -> Vec<Id> {
+        let mut fields = self.base.secure_fields();
+        fields.extend(self.top.secure_fields());
+        fields
+    }
+
+    fn input_region(&self) -> Option<Vec<smithay::utils::Rectangle<i32, Logical>>> {
+        let mut regions = self.base.input_region()?;
+        regions.extend(self.top.input_region()?);
+        Some(regions)
+    }
+
+    fn layers(&self) -> Vec<LayerSpec> {
+        let mut layers = self.base.layers();
+        layers.extend(self.top.layers());
+        layers
+    }
+
+    fn optimistic_feedback(&self) -> bool {
+        self.base.optimistic_feedback() && self.top.optimistic_feedback()
+    }
+
+    fn wants_input_method(&self) -> bool {
+        self.base.wants_input_method() || self.top.wants_input_method()
+    }
+
+    fn on_pad_button(&mut self, button: u32, state: ButtonState) {
+        self.top.on_pad_button(button, state);
+        self.base.on_pad_button(button, state);
+    }
+
+    fn on_pad_ring(&mut self, source: RingEventSource, degrees: f64) {
+        self.top.on_pad_ring(source, degrees);
+        self.base.on_pad_ring(source, degrees);
+    }
+
+    fn on_pad_strip(&mut self, source: StripEventSource, position: f64) {
+        self.top.on_pad_strip(source, position);
+        self.base.on_pad_strip(source, position);
+    }
+
+    fn is_drag_active(&self) -> bool {
+        self.base.is_drag_active() || self.top.is_drag_active()
+    }
+
+    fn accepts_drops(&self) -> bool {
+        self.top.accepts_drops() || self.base.accepts_drops()
+    }
+
+    fn dnd_enter(
+        &self,
+        payload: &DragPayload,
+        position: smithay::utils::Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.top
+            .dnd_enter(payload, position)
+            .map(Either::Second)
+            .or_else(|| self.base.dnd_enter(payload, position).map(Either::First))
+    }
+
+    fn dnd_mime_types(&self, position: smithay::utils::Point<f64, Logical>) -> Vec<String> {
+        let mut mime_types = self.top.dnd_mime_types(position);
+        mime_types.extend(self.base.dnd_mime_types(position));
+        mime_types
+    }
+
+    fn dnd_motion(&self, position: smithay::utils::Point<f64, Logical>) -> Option<Self::Message> {
+        self.top
+            .dnd_motion(position)
+            .map(Either::Second)
+            .or_else(|| self.base.dnd_motion(position).map(Either::First))
+    }
+
+    fn dnd_leave(&self) -> Option<Self::Message> {
+        self.top
+            .dnd_leave()
+            .map(Either::Second)
+            .or_else(|| self.base.dnd_leave().map(Either::First))
+    }
+
+    fn dnd_drop(
+        &self,
+        payload: &DragPayload,
+        position: smithay::utils::Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.top
+            .dnd_drop(payload, position)
+            .map(Either::Second)
+            .or_else(|| self.base.dnd_drop(payload, position).map(Either::First))
+    }
+
+    fn drag_cancelled(&self, payload: &DragPayload) -> Option<Self::Message> {
+        self.top
+            .drag_cancelled(payload)
+            .map(Either::Second)
+            .or_else(|| self.base.drag_cancelled(payload).map(Either::First))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ConditionalMessage<M, S> {
+    Inner(M),
+    /// Replaces the external state the predicate is evaluated on
+    SetState(S),
+}
+
+/// Shows `program` only while `predicate` holds for the external state.
+///
+/// Messages are delivered to the program regardless of its visibility,
+/// hooks only while it is shown.
+pub struct Conditional<P: Program, S> {
+    pub program: P,
+    state: S,
+    predicate: fn(&S) -> bool,
+}
+
+impl<P: Program, S> Conditional<P, S> {
+    pub fn new(program: P, state: S, predicate: fn(&S) -> bool) -> Self {
+        Conditional {
+            program,
+            state,
+            predicate,
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn is_shown(&self) -> bool {
+        (self.predicate)(&self.state)
+    }
+}
+
+impl<P, S> Program for Conditional<P, S>
+where
+    P: Program,
+    P::Message: 'static,
+    S: std::fmt::Debug + Send + 'static,
+{
+    type Message = ConditionalMessage<P::Message, S>;
+
+    fn try_clone_message(message: &Self::Message) -> Option<Self::Message> {
+        match message {
+            ConditionalMessage::Inner(message) => {
+                P::try_clone_message(message).map(ConditionalMessage::Inner)
+            }
+            // `S` isn't required to be `Clone`
+            ConditionalMessage::SetState(_) => None,
+        }
+    }
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        match message {
+            ConditionalMessage::Inner(message) => self
+                .program
+                .update_with_context(message, ctx)
+                .map(ConditionalMessage::Inner),
+            ConditionalMessage::SetState(state) => {
+                self.state = state;
+                Command::none()
+            }
+        }
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        if self.is_shown() {
+            self.program.view().map(ConditionalMessage::Inner)
+        } else {
+            Space::new(Length::Shrink, Length::Shrink).into()
+        }
+    }
+
+    fn themed_background(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        if self.is_shown() {
+            self.program.themed_background(target, palette);
+        }
+    }
+
+    fn themed_foreground(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        if self.is_shown() {
+            self.program.themed_foreground(target, palette);
+        }
+    }
+
+    fn custom_render(
+        &mut self,
+        target: &mut DrawTarget<&mut [u32]>,
+        size: Size<i32, Physical>,
+        scale: f64,
+    ) {
+        if self.is_shown() {
+            self.program.custom_render(target, size, scale);
+        }
+    }
+
+    fn custom_render_only(&self) -> bool {
+        self.program.custom_render_only()
+    }
+
+    fn uses_hooks(&self) -> bool {
+        // hidden programs may be shown again without a theme change
+        self.program.uses_hooks()
+    }
+    fn capabilities(&self) -> ProgramCapabilities {
+        self.program.capabilities()
+    }
+
+    fn z_index(&self) -> u8 {
+        self.program.z_index()
+    }
+
+    fn update_interval(&self) -> Option<Duration> {
+        self.program.update_interval()
+    }
+
+    fn max_fps(&self) -> Option<u32> {
+        self.program.max_fps()
+    }
+
+    fn shortcuts(&self) -> Vec<(KeyPattern, Self::Message)> {
+        if !self.is_shown() {
+            return Vec::new();
+        }
+        self.program
+            .shortcuts()
+            .into_iter()
+            .map(|(pattern, message)| (pattern, ConditionalMessage::Inner(message)))
+            .collect()
+    }
+
+    fn shortcut_priority(&self) -> ShortcutPriority {
+        self.program.shortcut_priority()
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription<Self::Message>> {
+        self.program
+            .subscriptions()
+            .into_iter()
+            .map(|s| s.map(ConditionalMessage::Inner))
+            .collect()
+    }
+
+    fn scale_mode(&self) -> ScaleMode {
+        self.program.scale_mode()
+    }
+
+    fn scroll_regions(&self) -> Vec<ScrollRegion> {
+        if self.is_shown() {
+            self.program.scroll_regions()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn unmatched_scroll(&self) -> UnmatchedScroll {
+        self.program.unmatched_scroll()
+    }
+
+    fn configure_scroll_physics(&self, source: AxisSource) -> ScrollPhysics {
+        self.program.configure_scroll_physics(source)
+    }
+
+    fn config_id(&self) -> Option<(&'static str, u64)> {
+        self.program.config_id()
+    }
+
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        self.is_shown()
+            .then(|| self.program.initial_focus())
+            .flatten()
+    }
+
+    fn content_state(&self) -> ContentState {
+        if self.is_shown() {
+            self.program.content_state()
+        } else {
+            ContentState::Ready
+        }
+    }
+
+    fn progressive(&self) -> Option<ProgressivePlan> {
+        self.program.progressive()
+    }
+
+    fn progressive_stage(&self, stage: u32) -> Option<Self::Message> {
+        self.program
+            .progressive_stage(stage)
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn config_keys(&self) -> &'static [&'static str] {
+        self.program.config_keys()
+    }
+
+    fn on_config_changed(&self, key: &str, value: Option<ron::Value>) -> Option<Self::Message> {
+        self.program
+            .on_config_changed(key, value)
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn refresh_changed(&mut self, info: &RefreshInfo) -> Option<Self::Message> {
+        self.program
+            .refresh_changed(info)
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn fonts_changed(&self, fonts: &FontConfig) -> Option<Self::Message> {
+        self.program
+            .fonts_changed(fonts)
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn preferences_changed(&self, preferences: &Preferences) -> Option<Self::Message> {
+        self.program
+            .preferences_changed(preferences)
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn intent_undone(&self, intent: IntentId) -> Option<Self::Message> {
+        self.program
+            .intent_undone(intent)
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn idle(&mut self) -> Option<Self::Message> {
+        self.program.idle().map(ConditionalMessage::Inner)
+    }
+
+    fn resumed(&mut self) -> Option<Self::Message> {
+        self.program.resumed().map(ConditionalMessage::Inner)
+    }
+
+    fn press_regions(&self) -> Vec<smithay::utils::Rectangle<i32, Logical>> {
+        if self.is_shown() {
+            self.program.press_regions()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn interaction_regions(&self) -> Vec<InteractionRegion> {
+        if self.is_shown() {
+            self.program.interaction_regions()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn secure_fields(&self) -> Vec<Id> {
+        if self.is_shown() {
+            self.program.secure_fields()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn input_region(&self) -> Option<Vec<smithay::utils::Rectangle<i32, Logical>>> {
+        let regions = self.program.input_region();
+        if self.is_shown() {
+            regions
+        } else {
+            // only programs narrowing their region let input through while hidden
+            regions.map(|_| Vec::new())
+        }
+    }
+
+    fn layers(&self) -> Vec<LayerSpec> {
+        if self.is_shown() {
+            self.program.layers()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn optimistic_feedback(&self) -> bool {
+        self.program.optimistic_feedback()
+    }
+
+    fn wants_input_method(&self) -> bool {
+        self.is_shown() && self.program.wants_input_method()
+    }
+
+    fn on_pad_button(&mut self, button: u32, state: ButtonState) {
+        if self.is_shown() {
+            self.program.on_pad_button(button, state);
+        }
+    }
+
+    fn on_pad_ring(&mut self, source: RingEventSource, degrees: f64) {
+        if self.is_shown() {
+            self.program.on_pad_ring(source, degrees);
+        }
+    }
+
+    fn on_pad_strip(&mut self, source: StripEventSource, position: f64) {
+        if self.is_shown() {
+            self.program.on_pad_strip(source, position);
+        }
+    }
+
+    fn is_drag_active(&self) -> bool {
+        self.is_shown() && self.program.is_drag_active()
+    }
+
+    fn accepts_drops(&self) -> bool {
+        self.is_shown() && self.program.accepts_drops()
+    }
+
+    fn dnd_enter(
+        &self,
+        payload: &DragPayload,
+        position: smithay::utils::Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.is_shown()
+            .then(|| self.program.dnd_enter(payload, position))
+            .flatten()
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn dnd_mime_types(&self, position: smithay::utils::Point<f64, Logical>) -> Vec<String> {
+        if self.is_shown() {
+            self.program.dnd_mime_types(position)
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn dnd_motion(&self, position: smithay::utils::Point<f64, Logical>) -> Option<Self::Message> {
+        self.is_shown()
+            .then(|| self.program.dnd_motion(position))
+            .flatten()
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn dnd_leave(&self) -> Option<Self::Message> {
+        self.is_shown()
+            .then(|| self.program.dnd_leave())
+            .flatten()
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn dnd_drop(
+        &self,
+        payload: &DragPayload,
+        position: smithay::utils::Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.is_shown()
+            .then(|| self.program.dnd_drop(payload, position))
+            .flatten()
+            .map(ConditionalMessage::Inner)
+    }
+
+    fn drag_cancelled(&self, payload: &DragPayload) -> Option<Self::Message> {
+        self.program
+            .drag_cancelled(payload)
+            .map(ConditionalMessage::Inner)
+    }
+}
+
+/// Lays out two widgets on top of each other.
+struct Stack<'a, Message, Renderer> {
+    /// `[base, top]`
+    children: [cosmic::iced::Element<'a, Message, Renderer>; 2],
+    pass_through: bool,
+}
+
+/// Layouts of the base and top child, `None` if the layout doesn't belong to a `Stack`.
+fn stack_layouts(layout: Layout<'_>) -> Option<(Layout<'_>, Layout<'_>)> {
+    let mut layouts = layout.children();
+    Some((layouts.next()?, layouts.next()?))
+}
+
+fn is_input(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Mouse(_) | Event::Touch(_) | Event::Keyboard(_)
+    )
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Stack<'a, Message, Renderer>
+where
+    Renderer: cosmic::iced_native::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(Length::Fill).height(Length::Fill);
+        let children = self
+            .children
+            .iter()
+            .map(|child| child.as_widget().layout(renderer, &limits))
+            .collect();
+        layout::Node::with_children(limits.max(), children)
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        self.children.iter().map(Tree::new).collect()
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(&self.children);
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        for ((child, state), layout) in self
+            .children
+            .iter()
+            .zip(tree.children.iter())
+            .zip(layout.children())
+        {
+            child.as_widget().draw(
+                state,
+                renderer,
+                theme,
+                style,
+                layout,
+                cursor_position,
+                viewport,
+            );
+        }
+    }
+
+    fn operate(&self, tree: &mut Tree, layout: Layout<'_>, operation: &mut dyn Operation<Message>) {
+        for ((child, state), layout) in self
+            .children
+            .iter()
+            .zip(tree.children.iter_mut())
+            .zip(layout.children())
+        {
+            child.as_widget().operate(state, layout, operation);
+        }
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let Some((base_layout, top_layout)) = stack_layouts(layout) else { return event::Status::Ignored };
+        let [base, top] = &mut self.children;
+        let [base_state, top_state] = &mut tree.children[..] else { return event::Status::Ignored };
+
+        let status = top.as_widget_mut().on_event(
+            top_state,
+            event.clone(),
+            top_layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        );
+
+        let forward = !is_input(&event)
+            || (status == event::Status::Ignored
+                && (self.pass_through || !top_layout.bounds().contains(cursor_position)));
+        if forward {
+            status.merge(base.as_widget_mut().on_event(
+                base_state,
+                event,
+                base_layout,
+                cursor_position,
+                renderer,
+                clipboard,
+                shell,
+            ))
+        } else {
+            status
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let Some((base_layout, top_layout)) = stack_layouts(layout) else { return mouse::Interaction::Idle };
+        let [base_state, top_state] = &tree.children[..] else { return mouse::Interaction::Idle };
+
+        let top = self.children[1].as_widget().mouse_interaction(
+            top_state,
+            top_layout,
+            cursor_position,
+            viewport,
+            renderer,
+        );
+        if top != mouse::Interaction::Idle || !self.pass_through {
+            return top;
+        }
+        self.children[0].as_widget().mouse_interaction(
+            base_state,
+            base_layout,
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, Renderer>> {
+        overlay::from_children(&mut self.children, tree, layout, renderer)
+    }
+}
+
+impl<'a, Message, Renderer> From<Stack<'a, Message, Renderer>>
+    for cosmic::iced::Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: cosmic::iced_native::Renderer + 'a,
+{
+    fn from(stack: Stack<'a, Message, Renderer>) -> Self {
+        cosmic::iced::Element::new(stack)
+    }
+}
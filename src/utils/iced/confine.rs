@@ -0,0 +1,45 @@
+//! Compositor-side pointer confinement for drags within an element.
+//!
+//! The pointer-constraints protocol is for clients confining their own surfaces,
+//! so internal elements are confined directly in the input handling instead.
+
+use std::cell::RefCell;
+
+use smithay::{
+    input::Seat,
+    utils::{Logical, Point, Rectangle},
+};
+
+use crate::state::State;
+
+/// Global bounds to confine the pointer to, `None` once the confinement ended.
+pub(super) type Confinement = Box<dyn Fn() -> Option<Rectangle<f64, Logical>>>;
+
+#[derive(Default)]
+struct SeatConfinement(RefCell<Option<Confinement>>);
+
+pub(super) fn set(seat: &Seat<State>, confinement: Confinement) {
+    let userdata = seat.user_data();
+    userdata.insert_if_missing(SeatConfinement::default);
+    if let Some(seat_confinement) = userdata.get::<SeatConfinement>() {
+        *seat_confinement.0.borrow_mut() = Some(confinement);
+    }
+}
+
+/// Clamps a new pointer `position` of `seat` into the bounds of the element confining it, if any.
+pub fn confine_pointer(seat: &Seat<State>, position: Point<f64, Logical>) -> Point<f64, Logical> {
+    let Some(confinement) = seat.user_data().get::<SeatConfinement>() else { return position };
+    let mut confinement = confinement.0.borrow_mut();
+    let Some(bounds) = confinement.as_ref().and_then(|confinement| confinement()) else {
+        *confinement = None;
+        return position;
+    };
+
+    // keep the pointer on the element, `Rectangle::contains` excludes the far edges
+    let max = bounds.loc + bounds.size.to_point() - Point::from((1.0, 1.0));
+    (
+        position.x.max(bounds.loc.x).min(max.x.max(bounds.loc.x)),
+        position.y.max(bounds.loc.y).min(max.y.max(bounds.loc.y)),
+    )
+        .into()
+}
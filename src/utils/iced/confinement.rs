@@ -0,0 +1,184 @@
+//! Confinement of applet worker processes, see `IcedElementProxy`.
+//!
+//! Workers are confined before they are executed, as their code can't be trusted to confine
+//! itself. Landlock limits the filesystem to reading and executing `WorkerConfinement::read_only`,
+//! a seccomp filter limits syscalls to what a worker needs to talk over its socket.
+//! Both are prepared in the compositor and applied in the forked child, which must not allocate.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    os::unix::{io::RawFd, process::CommandExt},
+    path::PathBuf,
+    process::Command,
+};
+
+use landlock::{
+    path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreated, RulesetCreatedAttr,
+    RulesetStatus, ABI,
+};
+use seccompiler::{BpfProgram, SeccompAction, SeccompFilter};
+
+/// What a worker may access besides its socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerConfinement {
+    /// Directories the worker may read and execute from, e.g. its binary and shared libraries.
+    /// Missing ones are skipped.
+    pub read_only: Vec<PathBuf>,
+}
+
+impl Default for WorkerConfinement {
+    fn default() -> Self {
+        WorkerConfinement {
+            read_only: ["/usr", "/lib", "/lib64"]
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+        }
+    }
+}
+
+/// Syscalls of workers, everything else fails with `EPERM`.
+const ALLOWED_SYSCALLS: &[libc::c_long] = &[
+    // socket and process lifecycle
+    libc::SYS_read,
+    libc::SYS_write,
+    libc::SYS_readv,
+    libc::SYS_writev,
+    libc::SYS_recvfrom,
+    libc::SYS_sendto,
+    libc::SYS_recvmsg,
+    libc::SYS_sendmsg,
+    libc::SYS_close,
+    libc::SYS_ppoll,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    // loading the worker, its reachable files are limited by landlock
+    libc::SYS_execve,
+    libc::SYS_openat,
+    libc::SYS_faccessat,
+    libc::SYS_newfstatat,
+    libc::SYS_fstat,
+    libc::SYS_statx,
+    libc::SYS_lseek,
+    libc::SYS_pread64,
+    libc::SYS_readlinkat,
+    libc::SYS_getcwd,
+    libc::SYS_fcntl,
+    // memory
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mprotect,
+    libc::SYS_mremap,
+    libc::SYS_madvise,
+    libc::SYS_brk,
+    // runtime
+    libc::SYS_futex,
+    libc::SYS_rt_sigaction,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_sigaltstack,
+    libc::SYS_set_tid_address,
+    libc::SYS_set_robust_list,
+    libc::SYS_rseq,
+    libc::SYS_prlimit64,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_sched_yield,
+    libc::SYS_getpid,
+    libc::SYS_gettid,
+    libc::SYS_getrandom,
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_nanosleep,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_arch_prctl,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_access,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_open,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_stat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_readlink,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_poll,
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfinementError {
+    #[error("Failed to prepare the landlock ruleset: {0}")]
+    Landlock(#[from] landlock::RulesetError),
+    #[error("Failed to compile the seccomp filter: {0}")]
+    Seccomp(#[from] seccompiler::Error),
+    #[error("Failed to compile the seccomp filter: {0}")]
+    SeccompBackend(#[from] seccompiler::BackendError),
+}
+
+impl From<ConfinementError> for io::Error {
+    fn from(err: ConfinementError) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, err)
+    }
+}
+
+fn ruleset(confinement: &WorkerConfinement) -> Result<RulesetCreated, ConfinementError> {
+    let abi = ABI::V1;
+    let readable = confinement
+        .read_only
+        .iter()
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+    Ok(Ruleset::new()
+        .handle_access(AccessFs::from_all(abi))?
+        .create()?
+        .add_rules(path_beneath_rules(readable, AccessFs::from_read(abi)))?)
+}
+
+/// The seccomp filter of workers.
+pub(super) fn seccomp_filter() -> Result<BpfProgram, ConfinementError> {
+    let rules = ALLOWED_SYSCALLS
+        .iter()
+        .map(|syscall| (i64::from(*syscall), Vec::new()))
+        .collect::<BTreeMap<_, _>>();
+    let filter = SeccompFilter::new(
+        rules,
+        SeccompAction::Errno(libc::EPERM as u32),
+        SeccompAction::Allow,
+        std::env::consts::ARCH.try_into()?,
+    )?;
+    Ok(filter.try_into()?)
+}
+
+/// Confines the process spawned by `command` and passes `socket` on to it.
+///
+/// The worker finds the socket's fd in `WORKER_SOCKET_ENV`. Spawning fails if the kernel
+/// doesn't support landlock, instead of running the worker unconfined.
+pub(super) fn confine(
+    command: &mut Command,
+    confinement: &WorkerConfinement,
+    socket: RawFd,
+) -> Result<(), ConfinementError> {
+    let mut ruleset = Some(ruleset(confinement)?);
+    let filter = seccomp_filter()?;
+    command.env(super::proxy::WORKER_SOCKET_ENV, socket.to_string());
+
+    let denied = || io::Error::from_raw_os_error(libc::EPERM);
+    // SAFETY: the closure only does syscalls on memory prepared above, it doesn't allocate
+    unsafe {
+        command.pre_exec(move || {
+            // keep the socket across exec
+            if libc::fcntl(socket, libc::F_SETFD, 0) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let status = ruleset
+                .take()
+                .ok_or_else(denied)?
+                .restrict_self()
+                .map_err(|_| denied())?;
+            if status.ruleset == RulesetStatus::NotEnforced {
+                return Err(io::Error::from_raw_os_error(libc::ENOSYS));
+            }
+            seccompiler::apply_filter(&filter).map_err(|_| denied())
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//! Standard placeholders shown instead of a program's view, while it has nothing to show yet.
+//!
+//! Programs return a `ContentState` from `Program::content_state`, while it isn't `Ready` the
+//! element lays out and rasterizes a centered placeholder instead of the program's `view`.
+//! Updates and messages are still processed as usual, so the program can change its state.
+//! Elements crossfade between their old and new content, whenever the kind of state changes.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cosmic::{
+    iced::widget::{container, text, Column},
+    iced_native::{Alignment, Length},
+    Element,
+};
+
+use super::{
+    animation::Curve,
+    transition::{SwapKind, SwapSizing, SwapTransition},
+};
+
+const ICON_SIZE: u16 = 48;
+const TITLE_SIZE: u16 = 18;
+const SPACING: u16 = 8;
+const PADDING: u16 = 24;
+
+/// Interval between frames of the loading indicator, the element is updated at this rate.
+pub(super) const SPINNER_INTERVAL: Duration = Duration::from_millis(250);
+const SPINNER_FRAMES: [&str; 4] = ["•", "• •", "• • •", ""];
+
+/// Crossfade between placeholders and the program's view.
+pub(super) const STATE_TRANSITION: SwapTransition = SwapTransition {
+    kind: SwapKind::Crossfade,
+    sizing: SwapSizing::Align,
+    curve: Curve::EaseInOut,
+    duration: Duration::from_millis(150),
+};
+
+/// What a program currently shows, see `Program::content_state`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ContentState {
+    /// The program's own view
+    #[default]
+    Ready,
+    /// Nothing to show, e.g. no notifications or no search results
+    Empty {
+        /// Name of a themed icon
+        icon: Option<&'static str>,
+        title: String,
+        hint: Option<String>,
+    },
+    /// Content is still being loaded, shown with an animated indicator
+    Loading { message: Option<String> },
+}
+
+impl ContentState {
+    /// Whether changing from `self` to `other` switches between view and placeholders.
+    ///
+    /// Changed texts of the same kind of state are shown right away.
+    pub(super) fn is_same_kind(&self, other: &ContentState) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+fn spinner_frame() -> &'static str {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let frame = elapsed.as_millis() / SPINNER_INTERVAL.as_millis();
+    SPINNER_FRAMES[frame as usize % SPINNER_FRAMES.len()]
+}
+
+/// Placeholder of `state`, `None` for `ContentState::Ready`.
+pub(super) fn placeholder<'a, M: 'a>(state: &ContentState) -> Option<Element<'a, M>> {
+    let mut content = Column::new()
+        .spacing(SPACING)
+        .align_items(Alignment::Center);
+    match state {
+        ContentState::Ready => return None,
+        ContentState::Empty { icon, title, hint } => {
+            if let Some(icon) = icon {
+                content = content.push(cosmic::widget::icon(*icon, ICON_SIZE));
+            }
+            content = content.push(text(title).size(TITLE_SIZE));
+            if let Some(hint) = hint {
+                content = content.push(text(hint));
+            }
+        }
+        ContentState::Loading { message } => {
+            content = content.push(text(spinner_frame()).size(TITLE_SIZE));
+            if let Some(message) = message {
+                content = content.push(text(message));
+            }
+        }
+    }
+    Some(
+        container(content)
+            .padding(PADDING)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y()
+            .into(),
+    )
+}
@@ -0,0 +1,465 @@
+//! Critical elements, that fall back to a minimal built-in UI instead of disappearing,
+//! if their program fails to build or panics.
+
+use std::{
+    cell::Cell,
+    panic::{catch_unwind, AssertUnwindSafe},
+    time::Duration,
+};
+
+use cosmic::{
+    iced::widget::{button, container, text, Column, Row},
+    iced_native::{widget::Id, Command, Length},
+    Element,
+};
+use iced_softbuffer::native::raqote::DrawTarget;
+use smithay::{
+    backend::input::{AxisSource, ButtonState},
+    reexports::calloop::LoopHandle,
+    utils::{Logical, Physical, Point, Rectangle, Size},
+};
+use tracing::error;
+
+use super::{
+    ContentState, DragPayload, FocusTarget, FontConfig, HookPalette, IcedElement, IntentId,
+    InteractionRegion, KeyPattern, LayerSpec, Preferences, Program, ProgramCapabilities,
+    ProgressivePlan, RefreshInfo, RingEventSource, ScaleMode, ScrollPhysics, ScrollRegion,
+    ShellRequest, ShortcutPriority, StripEventSource, Subscription, UnmatchedScroll, UpdateContext,
+};
+
+const MAX_FALLBACK_ACTIONS: usize = 3;
+
+/// Minimal UI shown in place of a failed critical program.
+#[derive(Debug, Clone)]
+pub struct FallbackSpec {
+    pub title: String,
+    /// Labeled actions, at most three are shown
+    pub actions: Vec<(String, ShellRequest)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum CriticalMessage<M> {
+    Inner(M),
+    /// Index of the triggered fallback action
+    Fallback(usize),
+}
+
+/// Wraps the program of a critical element, see `IcedElement::new_critical`.
+pub struct Critical<P: Program> {
+    program: Option<P>,
+    poisoned: Cell<bool>,
+    fallback: FallbackSpec,
+}
+
+impl<P: Program> Critical<P> {
+    pub fn is_fallback(&self) -> bool {
+        self.program.is_none() || self.poisoned.get()
+    }
+
+    /// Runs `f` on the program, unless it failed before, poisoning it on panic.
+    /// Like `guarded`, for hooks changing the program.
+    fn guarded_mut<R>(&mut self, f: impl FnOnce(&mut P) -> R) -> Option<R> {
+        if self.poisoned.get() {
+            return None;
+        }
+        let program = self.program.as_mut()?;
+        match catch_unwind(AssertUnwindSafe(|| f(program))) {
+            Ok(result) => Some(result),
+            Err(_) => {
+                error!("Critical program panicked, switching to fallback");
+                self.poisoned.set(true);
+                None
+            }
+        }
+    }
+
+    fn guarded<R>(&self, f: impl FnOnce(&P) -> R) -> Option<R> {
+        if self.poisoned.get() {
+            return None;
+        }
+        let program = self.program.as_ref()?;
+        match catch_unwind(AssertUnwindSafe(|| f(program))) {
+            Ok(result) => Some(result),
+            Err(_) => {
+                error!("Critical program panicked, switching to fallback");
+                self.poisoned.set(true);
+                None
+            }
+        }
+    }
+
+    fn fallback_view(&self) -> Element<'_, CriticalMessage<P::Message>> {
+        let actions = self
+            .fallback
+            .actions
+            .iter()
+            .take(MAX_FALLBACK_ACTIONS)
+            .enumerate()
+            .fold(Row::new().spacing(8), |row, (i, (label, _))| {
+                row.push(button(text(label.clone())).on_press(CriticalMessage::Fallback(i)))
+            });
+
+        container(
+            Column::new()
+                .spacing(12)
+                .push(text(self.fallback.title.clone()))
+                .push(actions),
+        )
+        .padding(16)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+}
+
+impl<P> Program for Critical<P>
+where
+    P: Program,
+    P::Message: 'static,
+{
+    type Message = CriticalMessage<P::Message>;
+
+    fn try_clone_message(message: &Self::Message) -> Option<Self::Message> {
+        match message {
+            CriticalMessage::Inner(message) => {
+                P::try_clone_message(message).map(CriticalMessage::Inner)
+            }
+            CriticalMessage::Fallback(idx) => Some(CriticalMessage::Fallback(*idx)),
+        }
+    }
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        match message {
+            CriticalMessage::Inner(message) => {
+                if self.is_fallback() {
+                    return Command::none();
+                }
+                let program = self.program.as_mut().unwrap();
+                match catch_unwind(AssertUnwindSafe(|| {
+                    program.update_with_context(message, ctx)
+                })) {
+                    Ok(command) => command.map(CriticalMessage::Inner),
+                    Err(_) => {
+                        error!("Critical program panicked, switching to fallback");
+                        self.poisoned.set(true);
+                        Command::none()
+                    }
+                }
+            }
+            CriticalMessage::Fallback(i) => {
+                if let Some((_, request)) = self.fallback.actions.get(i) {
+                    ctx.request(request.clone());
+                }
+                Command::none()
+            }
+        }
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        match self.guarded(|program| program.view().map(CriticalMessage::Inner)) {
+            Some(view) => view,
+            None => self.fallback_view(),
+        }
+    }
+
+    fn themed_background(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        self.guarded(|program| program.themed_background(target, palette));
+    }
+
+    fn themed_foreground(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        self.guarded(|program| program.themed_foreground(target, palette));
+    }
+
+    fn z_index(&self) -> u8 {
+        match self.program.as_ref() {
+            Some(program) => program.z_index(),
+            None => smithay::desktop::space::RenderZindex::Overlay as u8,
+        }
+    }
+
+    fn config_id(&self) -> Option<(&'static str, u64)> {
+        self.guarded(|program| program.config_id()).flatten()
+    }
+
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        self.guarded(|program| program.initial_focus()).flatten()
+    }
+
+    fn content_state(&self) -> ContentState {
+        self.guarded(|program| program.content_state())
+            .unwrap_or_default()
+    }
+
+    fn progressive(&self) -> Option<ProgressivePlan> {
+        self.guarded(|program| program.progressive()).flatten()
+    }
+
+    fn progressive_stage(&self, stage: u32) -> Option<Self::Message> {
+        self.guarded(|program| program.progressive_stage(stage))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn config_keys(&self) -> &'static [&'static str] {
+        self.guarded(|program| program.config_keys())
+            .unwrap_or_default()
+    }
+
+    fn on_config_changed(&self, key: &str, value: Option<ron::Value>) -> Option<Self::Message> {
+        self.guarded(|program| program.on_config_changed(key, value))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn refresh_changed(&mut self, info: &RefreshInfo) -> Option<Self::Message> {
+        self.guarded_mut(|program| program.refresh_changed(info))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn fonts_changed(&self, fonts: &FontConfig) -> Option<Self::Message> {
+        self.guarded(|program| program.fonts_changed(fonts))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn preferences_changed(&self, preferences: &Preferences) -> Option<Self::Message> {
+        self.guarded(|program| program.preferences_changed(preferences))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn intent_undone(&self, intent: IntentId) -> Option<Self::Message> {
+        self.guarded(|program| program.intent_undone(intent))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn update_interval(&self) -> Option<Duration> {
+        self.guarded(|program| program.update_interval()).flatten()
+    }
+
+    fn max_fps(&self) -> Option<u32> {
+        self.guarded(|program| program.max_fps()).flatten()
+    }
+
+    fn on_pad_button(&mut self, button: u32, state: ButtonState) {
+        self.guarded_mut(|program| program.on_pad_button(button, state));
+    }
+
+    fn on_pad_ring(&mut self, source: RingEventSource, degrees: f64) {
+        self.guarded_mut(|program| program.on_pad_ring(source, degrees));
+    }
+
+    fn on_pad_strip(&mut self, source: StripEventSource, position: f64) {
+        self.guarded_mut(|program| program.on_pad_strip(source, position));
+    }
+
+    fn shortcuts(&self) -> Vec<(KeyPattern, Self::Message)> {
+        self.guarded(|program| program.shortcuts())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(pattern, message)| (pattern, CriticalMessage::Inner(message)))
+            .collect()
+    }
+
+    fn shortcut_priority(&self) -> ShortcutPriority {
+        self.guarded(|program| program.shortcut_priority())
+            .unwrap_or_default()
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription<Self::Message>> {
+        self.guarded(|program| program.subscriptions())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| s.map(CriticalMessage::Inner))
+            .collect()
+    }
+
+    fn custom_render(
+        &mut self,
+        target: &mut DrawTarget<&mut [u32]>,
+        size: Size<i32, Physical>,
+        scale: f64,
+    ) {
+        self.guarded_mut(|program| program.custom_render(target, size, scale));
+    }
+
+    fn custom_render_only(&self) -> bool {
+        self.guarded(|program| program.custom_render_only())
+            .unwrap_or(false)
+    }
+
+    fn uses_hooks(&self) -> bool {
+        self.guarded(|program| program.uses_hooks())
+            .unwrap_or(false)
+    }
+    fn capabilities(&self) -> ProgramCapabilities {
+        self.guarded(|program| program.capabilities())
+            .unwrap_or_else(ProgramCapabilities::empty)
+    }
+
+    fn scale_mode(&self) -> ScaleMode {
+        self.guarded(|program| program.scale_mode())
+            .unwrap_or_default()
+    }
+
+    fn scroll_regions(&self) -> Vec<ScrollRegion> {
+        self.guarded(|program| program.scroll_regions())
+            .unwrap_or_default()
+    }
+
+    fn unmatched_scroll(&self) -> UnmatchedScroll {
+        self.guarded(|program| program.unmatched_scroll())
+            .unwrap_or_default()
+    }
+
+    fn configure_scroll_physics(&self, source: AxisSource) -> ScrollPhysics {
+        self.guarded(|program| program.configure_scroll_physics(source))
+            .unwrap_or_else(|| ScrollPhysics::for_source(source))
+    }
+
+    fn press_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        self.guarded(|program| program.press_regions())
+            .unwrap_or_default()
+    }
+
+    fn interaction_regions(&self) -> Vec<InteractionRegion> {
+        self.guarded(|program| program.interaction_regions())
+            .unwrap_or_default()
+    }
+
+    fn secure_fields(&self) -> Vec<Id> {
+        self.guarded(|program| program.secure_fields())
+            .unwrap_or_default()
+    }
+
+    fn input_region(&self) -> Option<Vec<Rectangle<i32, Logical>>> {
+        self.guarded(|program| program.input_region()).flatten()
+    }
+
+    fn layers(&self) -> Vec<LayerSpec> {
+        self.guarded(|program| program.layers()).unwrap_or_default()
+    }
+
+    fn optimistic_feedback(&self) -> bool {
+        self.guarded(|program| program.optimistic_feedback())
+            .unwrap_or(false)
+    }
+
+    fn is_drag_active(&self) -> bool {
+        self.guarded(|program| program.is_drag_active())
+            .unwrap_or(false)
+    }
+
+    fn accepts_drops(&self) -> bool {
+        self.guarded(|program| program.accepts_drops())
+            .unwrap_or(false)
+    }
+
+    fn dnd_enter(
+        &self,
+        payload: &DragPayload,
+        position: Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.guarded(|program| program.dnd_enter(payload, position))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn dnd_mime_types(&self, position: Point<f64, Logical>) -> Vec<String> {
+        self.guarded(|program| program.dnd_mime_types(position))
+            .unwrap_or_default()
+    }
+
+    fn dnd_motion(&self, position: Point<f64, Logical>) -> Option<Self::Message> {
+        self.guarded(|program| program.dnd_motion(position))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn dnd_leave(&self) -> Option<Self::Message> {
+        self.guarded(|program| program.dnd_leave())
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn dnd_drop(
+        &self,
+        payload: &DragPayload,
+        position: Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.guarded(|program| program.dnd_drop(payload, position))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn drag_cancelled(&self, payload: &DragPayload) -> Option<Self::Message> {
+        self.guarded(|program| program.drag_cancelled(payload))
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn wants_input_method(&self) -> bool {
+        self.guarded(|program| program.wants_input_method())
+            .unwrap_or(false)
+    }
+
+    fn idle(&mut self) -> Option<Self::Message> {
+        self.guarded_mut(|program| program.idle())
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+
+    fn resumed(&mut self) -> Option<Self::Message> {
+        self.guarded_mut(|program| program.resumed())
+            .flatten()
+            .map(CriticalMessage::Inner)
+    }
+}
+
+impl<P> IcedElement<Critical<P>>
+where
+    P: Program + Send + 'static,
+    P::Message: 'static,
+{
+    /// Creates an element, that shows `fallback` if `builder` fails or panics,
+    /// or if the program panics later on.
+    pub fn new_critical(
+        builder: impl FnOnce() -> anyhow::Result<P>,
+        fallback: FallbackSpec,
+        size: impl Into<Size<i32, Logical>>,
+        handle: LoopHandle<'static, crate::state::Data>,
+    ) -> IcedElement<Critical<P>> {
+        let program = match catch_unwind(AssertUnwindSafe(builder)) {
+            Ok(Ok(program)) => Some(program),
+            Ok(Err(err)) => {
+                error!(?err, "Failed to build critical program, using fallback");
+                None
+            }
+            Err(_) => {
+                error!("Critical program panicked during construction, using fallback");
+                None
+            }
+        };
+
+        IcedElement::new(
+            Critical {
+                program,
+                poisoned: Cell::new(false),
+                fallback,
+            },
+            size,
+            handle,
+        )
+    }
+
+    /// Whether the fallback UI is shown instead of the actual program.
+    pub fn is_fallback(&self) -> bool {
+        self.with_program(|critical| critical.is_fallback())
+    }
+}
@@ -0,0 +1,47 @@
+//! Repaint of element areas a software cursor was composited over.
+//!
+//! Elements only damage what they redraw, so once a software cursor moved on, the area it covered
+//! may not be composited again from the element's buffer. The cursor path reports its footprints
+//! via `cursor_moved`, overlapped elements then damage that area of their presented buffer,
+//! without rasterizing anything. Hardware cursors never need this.
+
+use smithay::{
+    output::Output,
+    utils::{Logical, Rectangle},
+};
+
+use super::{hit, registry};
+
+/// Damages the areas of elements on `output` that the software cursor covered (`old`)
+/// or covers now (`new`), both in global coordinates.
+///
+/// Only elements with an offset on `output` are found, see `IcedElement::set_output_offset`.
+/// Areas covered by multiple elements are only damaged once.
+pub fn cursor_moved(
+    output: &Output,
+    old: Option<Rectangle<i32, Logical>>,
+    new: Option<Rectangle<i32, Logical>>,
+) {
+    // a still cursor is composited over unchanged content
+    if old == new {
+        return;
+    }
+
+    let mut claimed: Vec<Rectangle<i32, Logical>> = Vec::new();
+    for footprint in [old, new].into_iter().flatten() {
+        for (address, bbox) in hit::hit_index().overlapping(output, footprint) {
+            let Some(overlap) = bbox.intersection(footprint) else { continue };
+            let damage = overlap.subtract_rects(claimed.iter().copied());
+            if damage.is_empty() {
+                continue;
+            }
+            claimed.extend_from_slice(&damage);
+            let Some(element) = registry::element(address) else { continue };
+            let local = damage
+                .into_iter()
+                .map(|rect| Rectangle::from_loc_and_size(rect.loc - bbox.loc, rect.size))
+                .collect::<Vec<_>>();
+            element.cursor_damage(output, &local);
+        }
+    }
+}
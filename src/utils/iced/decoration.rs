@@ -0,0 +1,426 @@
+//! Compositor-drawn titlebars for programs shown as windows.
+
+use std::time::Duration;
+
+use cosmic::{
+    iced::widget::{container, Column},
+    iced_native::{widget::Id, Command, Length},
+    Element,
+};
+use iced_softbuffer::native::raqote::DrawTarget;
+use smithay::{
+    backend::input::{AxisSource, ButtonState},
+    reexports::calloop::LoopHandle,
+    utils::{Logical, Physical, Point, Rectangle, Size},
+};
+
+use super::{
+    combinators::draw_in, content_state, DragPayload, Either, FocusTarget, FontConfig, IcedElement,
+    IntentId, InteractionRegion, KeyPattern, LayerSpec, Preferences, Program, ProgramCapabilities,
+    ProgressivePlan, RefreshInfo, RingEventSource, ScaleMode, ScrollPhysics, ScrollRegion,
+    ShellRequest, ShortcutPriority, StripEventSource, Subscription, UnmatchedScroll, UpdateContext,
+};
+use crate::shell::element::surface::SSD_HEIGHT;
+
+/// Window management actions triggered by a titlebar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowAction {
+    /// Start an interactive move
+    Move,
+    Minimize,
+    Maximize,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub enum TitleBarMessage {
+    Action(WindowAction),
+    SetTitle(String),
+}
+
+/// Titlebar issuing `ShellRequest::Window` for its buttons.
+#[derive(Debug, Clone)]
+pub struct TitleBarProgram {
+    pub title: String,
+}
+
+impl Program for TitleBarProgram {
+    type Message = TitleBarMessage;
+    crate::cloneable_message!();
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        match message {
+            TitleBarMessage::Action(action) => ctx.request(ShellRequest::Window(action)),
+            TitleBarMessage::SetTitle(title) => self.title = title,
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        cosmic::widget::header_bar()
+            .title(self.title.clone())
+            .on_drag(TitleBarMessage::Action(WindowAction::Move))
+            .on_minimize(TitleBarMessage::Action(WindowAction::Minimize))
+            .on_maximize(TitleBarMessage::Action(WindowAction::Maximize))
+            .on_close(TitleBarMessage::Action(WindowAction::Close))
+            .into_element()
+    }
+}
+
+/// Shows a `TitleBarProgram` of `SSD_HEIGHT` above `content`.
+///
+/// Hooks are forwarded to `content`, with positions translated below the titlebar.
+/// The placeholder of its `content_state` replaces only the content, not the titlebar.
+/// `background` and `foreground` of `content` are not drawn, as the titlebar height in pixels
+/// is unknown there, `custom_render` is drawn below the titlebar.
+pub struct Decorated<P: Program> {
+    pub titlebar: TitleBarProgram,
+    pub content: P,
+}
+
+fn below_titlebar(position: Point<f64, Logical>) -> Point<f64, Logical> {
+    position - Point::from((0.0, SSD_HEIGHT as f64))
+}
+
+fn offset_region(region: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size(region.loc + Point::from((0, SSD_HEIGHT)), region.size)
+}
+
+impl<P> Program for Decorated<P>
+where
+    P: Program,
+    P::Message: 'static,
+{
+    type Message = Either<TitleBarMessage, P::Message>;
+
+    fn try_clone_message(message: &Self::Message) -> Option<Self::Message> {
+        match message {
+            Either::First(message) => Some(Either::First(message.clone())),
+            Either::Second(message) => P::try_clone_message(message).map(Either::Second),
+            Either::Both(first, second) => {
+                P::try_clone_message(second).map(|second| Either::Both(first.clone(), second))
+            }
+        }
+    }
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        match message {
+            Either::First(message) => self
+                .titlebar
+                .update_with_context(message, ctx)
+                .map(Either::First),
+            Either::Second(message) => self
+                .content
+                .update_with_context(message, ctx)
+                .map(Either::Second),
+            Either::Both(first, second) => Command::batch([
+                self.titlebar
+                    .update_with_context(first, ctx)
+                    .map(Either::First),
+                self.content
+                    .update_with_context(second, ctx)
+                    .map(Either::Second),
+            ]),
+        }
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Column::with_children(vec![
+            container(self.titlebar.view().map(Either::First))
+                .width(Length::Fill)
+                .height(Length::Units(SSD_HEIGHT as u16))
+                .into(),
+            // keeps the titlebar, instead of replacing the whole view
+            container(
+                content_state::placeholder(&self.content.content_state())
+                    .unwrap_or_else(|| self.content.view())
+                    .map(Either::Second),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into(),
+        ])
+        .into()
+    }
+
+    fn custom_render(
+        &mut self,
+        target: &mut DrawTarget<&mut [u32]>,
+        size: Size<i32, Physical>,
+        scale: f64,
+    ) {
+        let offset = (SSD_HEIGHT as f64 * scale).round() as i32;
+        let area = (0, offset, size.w, size.h - offset);
+        draw_in(target, area, |target| {
+            self.content
+                .custom_render(target, (area.2, area.3).into(), scale)
+        });
+    }
+
+    fn z_index(&self) -> u8 {
+        self.content.z_index()
+    }
+
+    fn capabilities(&self) -> ProgramCapabilities {
+        self.content.capabilities()
+    }
+
+    fn config_id(&self) -> Option<(&'static str, u64)> {
+        self.content.config_id()
+    }
+
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        self.content.initial_focus()
+    }
+
+    fn progressive(&self) -> Option<ProgressivePlan> {
+        self.content.progressive()
+    }
+
+    fn progressive_stage(&self, stage: u32) -> Option<Self::Message> {
+        self.content.progressive_stage(stage).map(Either::Second)
+    }
+
+    fn config_keys(&self) -> &'static [&'static str] {
+        self.content.config_keys()
+    }
+
+    fn on_config_changed(&self, key: &str, value: Option<ron::Value>) -> Option<Self::Message> {
+        self.content
+            .on_config_changed(key, value)
+            .map(Either::Second)
+    }
+
+    fn refresh_changed(&mut self, info: &RefreshInfo) -> Option<Self::Message> {
+        self.content.refresh_changed(info).map(Either::Second)
+    }
+
+    fn fonts_changed(&self, fonts: &FontConfig) -> Option<Self::Message> {
+        self.content.fonts_changed(fonts).map(Either::Second)
+    }
+
+    fn preferences_changed(&self, preferences: &Preferences) -> Option<Self::Message> {
+        self.content
+            .preferences_changed(preferences)
+            .map(Either::Second)
+    }
+
+    fn intent_undone(&self, intent: IntentId) -> Option<Self::Message> {
+        self.content.intent_undone(intent).map(Either::Second)
+    }
+
+    fn update_interval(&self) -> Option<Duration> {
+        self.content.update_interval()
+    }
+
+    fn max_fps(&self) -> Option<u32> {
+        self.content.max_fps()
+    }
+
+    fn shortcuts(&self) -> Vec<(KeyPattern, Self::Message)> {
+        self.content
+            .shortcuts()
+            .into_iter()
+            .map(|(pattern, message)| (pattern, Either::Second(message)))
+            .collect()
+    }
+
+    fn shortcut_priority(&self) -> ShortcutPriority {
+        self.content.shortcut_priority()
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription<Self::Message>> {
+        self.content
+            .subscriptions()
+            .into_iter()
+            .map(|s| s.map(Either::Second))
+            .collect()
+    }
+
+    fn scale_mode(&self) -> ScaleMode {
+        self.content.scale_mode()
+    }
+
+    fn idle(&mut self) -> Option<Self::Message> {
+        self.content.idle().map(Either::Second)
+    }
+
+    fn resumed(&mut self) -> Option<Self::Message> {
+        self.content.resumed().map(Either::Second)
+    }
+
+    fn scroll_regions(&self) -> Vec<ScrollRegion> {
+        self.content
+            .scroll_regions()
+            .into_iter()
+            .map(|region| ScrollRegion {
+                bounds: Rectangle::from_loc_and_size(
+                    region.bounds.loc + Point::from((0.0, SSD_HEIGHT as f64)),
+                    region.bounds.size,
+                ),
+            })
+            .collect()
+    }
+
+    fn unmatched_scroll(&self) -> UnmatchedScroll {
+        self.content.unmatched_scroll()
+    }
+
+    fn configure_scroll_physics(&self, source: AxisSource) -> ScrollPhysics {
+        self.content.configure_scroll_physics(source)
+    }
+
+    fn press_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        self.content
+            .press_regions()
+            .into_iter()
+            .map(offset_region)
+            .collect()
+    }
+
+    fn secure_fields(&self) -> Vec<Id> {
+        self.content.secure_fields()
+    }
+
+    fn input_region(&self) -> Option<Vec<Rectangle<i32, Logical>>> {
+        let regions = self.content.input_region()?;
+        // the titlebar always accepts input
+        let titlebar = Rectangle::from_loc_and_size((0, 0), (i32::MAX, SSD_HEIGHT));
+        Some(
+            std::iter::once(titlebar)
+                .chain(regions.into_iter().map(offset_region))
+                .collect(),
+        )
+    }
+
+    fn interaction_regions(&self) -> Vec<InteractionRegion> {
+        self.content
+            .interaction_regions()
+            .into_iter()
+            .map(|region| InteractionRegion {
+                bounds: offset_region(region.bounds),
+                ..region
+            })
+            .collect()
+    }
+
+    fn optimistic_feedback(&self) -> bool {
+        self.content.optimistic_feedback()
+    }
+
+    fn layers(&self) -> Vec<LayerSpec> {
+        self.content
+            .layers()
+            .into_iter()
+            .map(|layer| LayerSpec {
+                bounds: offset_region(layer.bounds),
+                ..layer
+            })
+            .collect()
+    }
+
+    fn is_drag_active(&self) -> bool {
+        self.content.is_drag_active()
+    }
+
+    fn accepts_drops(&self) -> bool {
+        self.content.accepts_drops()
+    }
+
+    fn dnd_enter(
+        &self,
+        payload: &DragPayload,
+        position: Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.content
+            .dnd_enter(payload, below_titlebar(position))
+            .map(Either::Second)
+    }
+
+    fn dnd_mime_types(&self, position: Point<f64, Logical>) -> Vec<String> {
+        self.content.dnd_mime_types(below_titlebar(position))
+    }
+
+    fn dnd_motion(&self, position: Point<f64, Logical>) -> Option<Self::Message> {
+        self.content
+            .dnd_motion(below_titlebar(position))
+            .map(Either::Second)
+    }
+
+    fn dnd_leave(&self) -> Option<Self::Message> {
+        self.content.dnd_leave().map(Either::Second)
+    }
+
+    fn dnd_drop(
+        &self,
+        payload: &DragPayload,
+        position: Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.content
+            .dnd_drop(payload, below_titlebar(position))
+            .map(Either::Second)
+    }
+
+    fn drag_cancelled(&self, payload: &DragPayload) -> Option<Self::Message> {
+        self.content.drag_cancelled(payload).map(Either::Second)
+    }
+
+    fn wants_input_method(&self) -> bool {
+        self.content.wants_input_method()
+    }
+
+    fn on_pad_button(&mut self, button: u32, state: ButtonState) {
+        self.content.on_pad_button(button, state);
+    }
+
+    fn on_pad_ring(&mut self, source: RingEventSource, degrees: f64) {
+        self.content.on_pad_ring(source, degrees);
+    }
+
+    fn on_pad_strip(&mut self, source: StripEventSource, position: f64) {
+        self.content.on_pad_strip(source, position);
+    }
+}
+
+/// An `IcedElement` with a compositor-drawn titlebar, presented as a single `SpaceElement`.
+pub type CosmicXdgDecorationIcedElement<P> = IcedElement<Decorated<P>>;
+
+impl<P> IcedElement<Decorated<P>>
+where
+    P: Program + Send + 'static,
+    P::Message: 'static,
+{
+    /// Creates an element showing `content` below a titlebar with the given title.
+    ///
+    /// `size` is the size of the content, the titlebar is added on top.
+    /// Titlebar buttons issue `ShellRequest::Window`, see `IcedElement::set_shell_request_handler`.
+    pub fn new_decorated(
+        content: P,
+        title: impl Into<String>,
+        size: impl Into<Size<i32, Logical>>,
+        handle: LoopHandle<'static, crate::state::Data>,
+    ) -> CosmicXdgDecorationIcedElement<P> {
+        IcedElement::new(
+            Decorated {
+                titlebar: TitleBarProgram {
+                    title: title.into(),
+                },
+                content,
+            },
+            size.into() + Size::from((0, SSD_HEIGHT)),
+            handle,
+        )
+    }
+
+    pub fn set_title(&self, title: impl Into<String>) {
+        self.queue_message(Either::First(TitleBarMessage::SetTitle(title.into())));
+    }
+}
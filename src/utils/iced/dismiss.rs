@@ -0,0 +1,84 @@
+//! Auto-dismissal of transient elements like OSDs and toasts, see `IcedElement::set_auto_dismiss`.
+//!
+//! The remaining lifetime pauses while a pointer hovers the element and restarts on interaction
+//! or changed content, as configured by the policy. Expired elements run their exit transition.
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoDismissPolicy {
+    /// Lifetime of the element
+    pub after: Duration,
+    /// Stops the lifetime while hovered, continuing with the remaining time once left
+    pub pause_on_hover: bool,
+    /// Restarts the lifetime, whenever an update changes the element's content
+    pub reset_on_update: bool,
+    /// Restarts the lifetime on button, axis and key input
+    pub reset_on_interaction: bool,
+}
+
+pub(super) struct AutoDismiss {
+    pub policy: AutoDismissPolicy,
+    deadline: Instant,
+    /// Time left while paused
+    paused: Option<Duration>,
+    on_dismissed: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl std::fmt::Debug for AutoDismiss {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AutoDismiss")
+            .field("policy", &self.policy)
+            .field("deadline", &self.deadline)
+            .field("paused", &self.paused)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AutoDismiss {
+    pub fn new(
+        policy: AutoDismissPolicy,
+        on_dismissed: Box<dyn FnOnce() + Send>,
+        now: Instant,
+    ) -> AutoDismiss {
+        AutoDismiss {
+            policy,
+            deadline: now + policy.after,
+            paused: None,
+            on_dismissed: Some(on_dismissed),
+        }
+    }
+
+    /// Restarts the full lifetime, staying paused if hovered.
+    pub fn reset(&mut self, now: Instant) {
+        match self.paused.as_mut() {
+            Some(remaining) => *remaining = self.policy.after,
+            None => self.deadline = now + self.policy.after,
+        }
+    }
+
+    /// Pauses or resumes the lifetime, returns whether it changed.
+    pub fn set_hovered(&mut self, hovered: bool, now: Instant) -> bool {
+        match (hovered && self.policy.pause_on_hover, self.paused) {
+            (true, None) => {
+                self.paused = Some(self.deadline.saturating_duration_since(now));
+                true
+            }
+            (false, Some(remaining)) => {
+                self.paused = None;
+                self.deadline = now + remaining;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Expiry of the lifetime, `None` while paused.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.paused.is_none().then_some(self.deadline)
+    }
+
+    pub fn take_on_dismissed(&mut self) -> Option<Box<dyn FnOnce() + Send>> {
+        self.on_dismissed.take()
+    }
+}
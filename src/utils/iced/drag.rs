@@ -0,0 +1,238 @@
+//! Drag-and-drop between `IcedElement`s, that never involves a client.
+//!
+//! A program starts a drag via `UpdateContext::start_internal_drag`. The element keeps the
+//! active drag and, as it holds the implicit pointer grab (the source), forwards pointer input
+//! here, which delivers `dnd_*` hooks to the drop target under the pointer.
+//! Drop targets are located via their offsets, derived from the location the shell sets with
+//! `IcedElement::set_location` or set with `IcedElement::set_output_offset`.
+
+use std::sync::{Arc, Weak};
+
+use cosmic::{
+    iced::widget::{container, text},
+    iced_native::Length,
+    Element,
+};
+use smithay::{
+    desktop::space::RenderZindex,
+    reexports::calloop::LoopHandle,
+    utils::{Logical, Point, Size},
+};
+
+use super::{registry, registry::RegisteredElement, IcedElement, Program};
+
+/// Data carried by an internal drag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DragPayload {
+    Text(String),
+    Uri(String),
+    /// Desktop entry id of an application
+    AppId(String),
+    /// Anything else, tagged by a mime type
+    Bytes {
+        mime_type: String,
+        data: Vec<u8>,
+    },
+}
+
+impl DragPayload {
+    /// Mime type of the payload, matched against `Program::dnd_mime_types`.
+    pub fn mime_type(&self) -> &str {
+        match self {
+            DragPayload::Text(_) => "text/plain;charset=utf-8",
+            DragPayload::Uri(_) => "text/uri-list",
+            DragPayload::AppId(_) => "application/x-desktop-entry-id",
+            DragPayload::Bytes { mime_type, .. } => mime_type,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DragIconSpec {
+    pub label: String,
+    pub size: Size<i32, Logical>,
+    /// Position of the pointer within the icon
+    pub hotspot: Point<i32, Logical>,
+}
+
+/// Program of the icon following the pointer during an internal drag.
+pub struct DragIcon {
+    label: String,
+}
+
+impl DragIcon {
+    /// Deterministic state for golden images, see `golden`.
+    #[cfg(any(test, feature = "test-helpers"))]
+    pub fn sample_state() -> DragIcon {
+        DragIcon {
+            label: String::from("Files"),
+        }
+    }
+}
+
+impl Program for DragIcon {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        container(text(self.label.clone()))
+            .padding(8)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y()
+            .into()
+    }
+
+    fn z_index(&self) -> u8 {
+        RenderZindex::Overlay as u8
+    }
+}
+
+/// Drag started by an element, kept by that element (the source) until it ends.
+pub(super) struct ActiveDrag {
+    payload: DragPayload,
+    icon: Option<(IcedElement<DragIcon>, Point<i32, Logical>)>,
+    target: Option<Weak<dyn RegisteredElement>>,
+    /// Mime type accepted by the target at the current location
+    accepted: Option<String>,
+    location: Option<Point<f64, Logical>>,
+}
+
+impl ActiveDrag {
+    pub fn new(
+        payload: DragPayload,
+        icon: Option<DragIconSpec>,
+        handle: &LoopHandle<'static, crate::state::Data>,
+    ) -> ActiveDrag {
+        let icon = icon.map(|spec| {
+            let element =
+                IcedElement::new(DragIcon { label: spec.label }, spec.size, handle.clone());
+            (element, spec.hotspot)
+        });
+        ActiveDrag {
+            payload,
+            icon,
+            target: None,
+            accepted: None,
+            location: None,
+        }
+    }
+
+    /// The icon of the drag and its global location.
+    pub fn icon(&self) -> Option<(IcedElement<DragIcon>, Point<f64, Logical>)> {
+        let (icon, hotspot) = self.icon.as_ref()?;
+        Some((icon.clone(), self.location? - hotspot.to_f64()))
+    }
+
+    pub fn accepted(&self) -> Option<String> {
+        self.accepted.clone()
+    }
+}
+
+fn same_element(a: &Arc<dyn RegisteredElement>, b: &Arc<dyn RegisteredElement>) -> bool {
+    Arc::as_ptr(a) as *const () == Arc::as_ptr(b) as *const ()
+}
+
+/// Drop target under the global `location` and the location relative to it.
+fn target_at(
+    location: Point<f64, Logical>,
+) -> Option<(Arc<dyn RegisteredElement>, Point<f64, Logical>)> {
+    registry::elements().into_iter().find_map(|element| {
+        let local = element.drop_target_at(location)?;
+        Some((element, local))
+    })
+}
+
+/// Whether an internal drag is currently in progress.
+pub fn is_internal_drag_active() -> bool {
+    registry::elements()
+        .iter()
+        .any(|element| element.is_dragging())
+}
+
+/// The icons of the active drags and their global locations.
+pub fn internal_drag_icons() -> Vec<(IcedElement<DragIcon>, Point<f64, Logical>)> {
+    registry::elements()
+        .iter()
+        .filter_map(|element| element.drag_icon())
+        .collect()
+}
+
+/// Mime type the drop target under the pointer accepts for an active drag, e.g. to show a copy
+/// cursor. `None` if the payload can't be dropped there.
+pub fn internal_drag_accepted() -> Option<String> {
+    registry::elements()
+        .iter()
+        .find_map(|element| element.drag_accepted())
+}
+
+/// Cancels all active internal drags, returning them to their sources.
+pub fn cancel_internal_drag() {
+    for element in registry::elements() {
+        element.cancel_drag();
+    }
+}
+
+/// Moves the `drag` to the global `location`.
+///
+/// Hooks may start new drags, so the source must not be locked while calling this.
+pub(super) fn motion(drag: &mut ActiveDrag, location: Point<f64, Logical>) {
+    drag.location = Some(location);
+    let previous = drag.target.as_ref().and_then(Weak::upgrade);
+    let target = target_at(location);
+    let entered = match (previous, target.as_ref()) {
+        (Some(previous), Some((target, local))) if same_element(&previous, target) => {
+            target.dnd_motion(*local);
+            false
+        }
+        (previous, target) => {
+            if let Some(previous) = previous {
+                previous.dnd_leave();
+            }
+            if let Some((target, local)) = target {
+                target.dnd_enter(&drag.payload, *local);
+            }
+            true
+        }
+    };
+    // the accepted type may change with the widget under the pointer, not just the target
+    drag.accepted = target
+        .as_ref()
+        .and_then(|(target, local)| target.dnd_accept(&drag.payload, *local));
+    if entered {
+        drag.target = target.map(|(target, _)| Arc::downgrade(&target));
+    }
+}
+
+/// Drops the payload of `drag` at the global `location`, or cancels the drag if there is no
+/// target.
+pub(super) fn drop_at(
+    drag: ActiveDrag,
+    source: Option<Arc<dyn RegisteredElement>>,
+    location: Point<f64, Logical>,
+) {
+    let target = target_at(location);
+    if let Some(previous) = drag.target.and_then(|previous| previous.upgrade()) {
+        if !matches!(&target, Some((target, _)) if same_element(&previous, target)) {
+            previous.dnd_leave();
+        }
+    }
+    match target {
+        Some((target, local)) => target.dnd_drop(&drag.payload, local),
+        None => {
+            if let Some(source) = source {
+                source.drag_cancelled(&drag.payload);
+            }
+        }
+    }
+}
+
+/// Cancels `drag`, returning it to its `source` (`None` once the source is gone).
+pub(super) fn cancel(drag: ActiveDrag, source: Option<Arc<dyn RegisteredElement>>) {
+    if let Some(target) = drag.target.and_then(|t| t.upgrade()) {
+        target.dnd_leave();
+    }
+    if let Some(source) = source {
+        source.drag_cancelled(&drag.payload);
+    }
+}
@@ -0,0 +1,92 @@
+//! Tracks which widget of an element's view has keyboard focus.
+//!
+//! iced keeps focus in the state of the individual widgets, so it is found by walking the
+//! widget tree with an operation. Tab and Shift+Tab move focus between widgets, unless the
+//! focused widget handles them itself.
+
+use std::sync::{Arc, Mutex};
+
+use cosmic::iced_native::{
+    event::Event,
+    keyboard::{Event as KeyboardEvent, KeyCode},
+    widget::{
+        operation::{focusable, Focusable, TextInput},
+        Id, Operation,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct FocusedWidget {
+    /// Position among the focusable widgets of the view, in tab order
+    pub index: usize,
+    pub id: Option<Id>,
+    /// Whether the widget is a text input
+    pub editable: bool,
+}
+
+/// Walks the widget tree and stores the focused widget, if any, in `found`.
+struct FindFocused {
+    count: usize,
+    found: Arc<Mutex<Option<FocusedWidget>>>,
+}
+
+impl<T> Operation<T> for FindFocused {
+    fn container(
+        &mut self,
+        _id: Option<&Id>,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation<T>),
+    ) {
+        operate_on_children(self)
+    }
+
+    fn focusable(&mut self, state: &mut dyn Focusable, id: Option<&Id>) {
+        if state.is_focused() {
+            *self.found.lock().unwrap() = Some(FocusedWidget {
+                index: self.count,
+                id: id.cloned(),
+                editable: false,
+            });
+        }
+        self.count += 1;
+    }
+
+    fn text_input(&mut self, _state: &mut dyn TextInput, id: Option<&Id>) {
+        // text inputs report themselves as focusable first
+        if let Some(focused) = self.found.lock().unwrap().as_mut() {
+            if focused.index + 1 == self.count && focused.id.as_ref() == id {
+                focused.editable = true;
+            }
+        }
+    }
+}
+
+/// An operation finding the focused widget, which can be read from the returned slot once
+/// the operation ran.
+pub(super) fn find_focused<T>() -> (Box<dyn Operation<T>>, Arc<Mutex<Option<FocusedWidget>>>) {
+    let found = Arc::new(Mutex::new(None));
+    let operation = FindFocused {
+        count: 0,
+        found: found.clone(),
+    };
+    (Box::new(operation), found)
+}
+
+/// Operations moving focus for the Tab presses among `uncaptured` events, in order.
+pub(super) fn traversal<T>(uncaptured: &[Event]) -> Vec<Box<dyn Operation<T>>> {
+    uncaptured
+        .iter()
+        .filter_map(|event| match event {
+            Event::Keyboard(KeyboardEvent::KeyPressed {
+                key_code: KeyCode::Tab,
+                modifiers,
+            }) if modifiers.shift() => {
+                Some(Box::new(focusable::focus_previous()) as Box<dyn Operation<T>>)
+            }
+            Event::Keyboard(KeyboardEvent::KeyPressed {
+                key_code: KeyCode::Tab,
+                ..
+            }) => Some(Box::new(focusable::focus_next()) as Box<dyn Operation<T>>),
+            _ => None,
+        })
+        .collect()
+}
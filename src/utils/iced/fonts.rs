@@ -0,0 +1,95 @@
+//! Font configuration of elements.
+//!
+//! The softbuffer backend lays out text with its own default font and size, which can't be
+//! configured per renderer. Elements therefore keep a `FontConfig` and pass it to their program
+//! via `Program::fonts_changed`, for widgets to use explicitly. Families are checked against
+//! the installed fonts when set, a missing family keeps the previous configuration.
+
+use std::sync::Mutex;
+
+use fontdb::{Database, Family, Query};
+use serde::{Deserialize, Serialize};
+
+use super::lock_global;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FontConfig {
+    /// Family for regular text, the backend's default if `None`
+    pub default_family: Option<String>,
+    /// Family for text needing equal advance widths, e.g. aligned numbers
+    pub monospace_family: Option<String>,
+    pub default_size: Option<u16>,
+    /// Weight (100-900) to use for the given families
+    pub weight_overrides: Vec<(String, u16)>,
+}
+
+impl FontConfig {
+    /// Weight to use for `family`, if overridden.
+    pub fn weight(&self, family: &str) -> Option<u16> {
+        self.weight_overrides
+            .iter()
+            .find(|(name, _)| name == family)
+            .map(|(_, weight)| *weight)
+    }
+
+    fn families(&self) -> impl Iterator<Item = &str> {
+        self.default_family
+            .iter()
+            .chain(self.monospace_family.iter())
+            .chain(self.weight_overrides.iter().map(|(name, _)| name))
+            .map(String::as_str)
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FontError {
+    #[error("Font family {0:?} is not installed")]
+    MissingFamily(String),
+    #[error("Invalid font weight {1} for {0:?}")]
+    InvalidWeight(String, u16),
+}
+
+lazy_static::lazy_static! {
+    static ref SYSTEM_FONTS: Database = {
+        let mut db = Database::new();
+        db.load_system_fonts();
+        db
+    };
+    static ref DEFAULT_FONTS: Mutex<FontConfig> = Mutex::new(FontConfig::default());
+}
+
+/// Checks, that all families of `config` are installed and its weights are valid.
+pub(super) fn resolve(config: &FontConfig) -> Result<(), FontError> {
+    for family in config.families() {
+        let query = Query {
+            families: &[Family::Name(family)],
+            ..Default::default()
+        };
+        if SYSTEM_FONTS.query(&query).is_none() {
+            return Err(FontError::MissingFamily(family.to_string()));
+        }
+    }
+    if let Some((family, weight)) = config
+        .weight_overrides
+        .iter()
+        .find(|(_, weight)| !(100..=900).contains(weight))
+    {
+        return Err(FontError::InvalidWeight(family.clone(), *weight));
+    }
+    Ok(())
+}
+
+pub(super) fn default_fonts() -> FontConfig {
+    lock_global(&DEFAULT_FONTS).clone()
+}
+
+/// Stores the global default, returns false if it didn't change.
+pub(super) fn store_default(config: FontConfig) -> bool {
+    let mut default = lock_global(&DEFAULT_FONTS);
+    if *default == config {
+        return false;
+    }
+    *default = config;
+    true
+}
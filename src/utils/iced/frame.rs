@@ -0,0 +1,38 @@
+//! Throttling of redraws to the rate frames are presented at.
+
+/// Defers redraws and updates of an element until the frame containing its last render was presented.
+#[derive(Debug, Default)]
+pub(super) struct FrameCallbackTracker {
+    /// A rendered frame wasn't presented yet
+    awaiting_frame: bool,
+    /// A redraw was requested while awaiting a frame
+    redraw_deferred: bool,
+}
+
+impl FrameCallbackTracker {
+    /// Called after the element was rendered into a frame.
+    pub fn rendered(&mut self) {
+        self.awaiting_frame = true;
+    }
+
+    /// Returns whether a redraw may happen right away, otherwise it is deferred until `frame_done`.
+    pub fn request_redraw(&mut self) -> bool {
+        if self.awaiting_frame {
+            self.redraw_deferred = true;
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Whether the last render wasn't presented yet, updates until then are merged.
+    pub fn is_awaiting_frame(&self) -> bool {
+        self.awaiting_frame
+    }
+
+    /// Called once a frame was presented, returns whether a deferred redraw is due.
+    pub fn frame_done(&mut self) -> bool {
+        self.awaiting_frame = false;
+        std::mem::take(&mut self.redraw_deferred)
+    }
+}
@@ -0,0 +1,320 @@
+//! Golden image matrix of registered programs, catching visual regressions of overlays.
+//!
+//! Programs are registered with `GoldenPrograms` (e.g. via `register_golden_program!`) with a
+//! factory producing deterministic sample state. `GoldenPrograms::check` renders every registered
+//! program for each theme, scale and size
+//! of a `GoldenMatrix` and compares the results against PNGs stored in a directory, writing
+//! side-by-side images (golden, actual, difference) of failing entries into another directory.
+//!
+//! Goldens are only written when blessing, e.g. with `COSMIC_COMP_BLESS_GOLDENS=1 cargo test`
+//! (see `BLESS_VAR`), otherwise missing goldens fail like differing ones. Review the written
+//! PNGs before committing them.
+//!
+//! Every entry is rendered by a fresh element, so no text cache or state is shared between
+//! entries. The element's event loop is never dispatched (see `test_helpers`), so timers and
+//! subscriptions can't change the sample state while rendering.
+
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use cosmic::Theme;
+use smithay::utils::{Logical, Size};
+
+use super::{
+    test_helpers::{IcedElementTestHarness, Snapshot},
+    IcedElement, Program,
+};
+
+/// Environment variable enabling `GoldenMatrix::bless` by default
+pub const BLESS_VAR: &str = "COSMIC_COMP_BLESS_GOLDENS";
+
+type Render = Box<dyn Fn(&Theme, Size<i32, Logical>, f64) -> Snapshot + Send>;
+
+/// Registers a program with `GoldenPrograms`, e.g.
+/// `register_golden_program!(programs, "prompt", PromptProgram::sample_state)`.
+#[macro_export]
+macro_rules! register_golden_program {
+    ($programs:expr, $name:expr, $factory:expr) => {
+        $programs.register($name, $factory)
+    };
+}
+
+/// Programs to check against their goldens, by name.
+#[derive(Default)]
+pub struct GoldenPrograms {
+    programs: Vec<(&'static str, Render)>,
+}
+
+impl GoldenPrograms {
+    pub fn new() -> GoldenPrograms {
+        GoldenPrograms::default()
+    }
+
+    /// The in-tree programs providing sample state.
+    pub fn in_tree() -> GoldenPrograms {
+        let mut programs = GoldenPrograms::new();
+        crate::register_golden_program!(
+            programs,
+            "prompt",
+            super::prompt::PromptProgram::sample_state
+        );
+        crate::register_golden_program!(programs, "drag-icon", super::drag::DragIcon::sample_state);
+        crate::register_golden_program!(programs, "titlebar", || super::TitleBarProgram {
+            title: String::from("Golden Window"),
+        });
+        programs
+    }
+
+    /// Registers a program, replacing a previous one of the same `name`.
+    pub fn register<P, F>(&mut self, name: &'static str, factory: F)
+    where
+        P: Program + Send + 'static,
+        F: Fn() -> P + Send + 'static,
+    {
+        self.register_configured(name, factory, |_| {});
+    }
+
+    /// Registers a program like `register`, whose element is set up by `configure` before
+    /// rendering, e.g. to enable element options.
+    pub fn register_configured<P, F, C>(&mut self, name: &'static str, factory: F, configure: C)
+    where
+        P: Program + Send + 'static,
+        F: Fn() -> P + Send + 'static,
+        C: Fn(&IcedElement<P>) + Send + 'static,
+    {
+        let render: Render = Box::new(move |theme, size, scale| {
+            let harness = IcedElementTestHarness::new(factory(), size);
+            harness.set_theme(theme.clone());
+            configure(harness.element());
+            harness.snapshot(scale)
+        });
+        self.programs.retain(|(n, _)| *n != name);
+        self.programs.push((name, render));
+    }
+
+    /// Renders all programs across `matrix` and compares them against the goldens in
+    /// `golden_dir`, returns all failing entries.
+    ///
+    /// Missing goldens are reported as `GoldenFailureReason::Missing`. With `GoldenMatrix::bless`,
+    /// missing and failing goldens are replaced by the renderings instead, without failing.
+    pub fn check(
+        &self,
+        matrix: &GoldenMatrix,
+        golden_dir: impl Into<PathBuf>,
+        diff_dir: impl Into<PathBuf>,
+    ) -> Vec<GoldenFailure> {
+        let (golden_dir, diff_dir) = (golden_dir.into(), diff_dir.into());
+        if matrix.bless {
+            let _ = fs::create_dir_all(&golden_dir);
+        }
+        let _ = fs::create_dir_all(&diff_dir);
+
+        let mut failures = Vec::new();
+        for (name, render) in self.programs.iter() {
+            for (theme_name, theme) in &matrix.themes {
+                for scale in &matrix.scales {
+                    for size in &matrix.sizes {
+                        let snapshot = render(theme, *size, *scale);
+                        let entry = entry_name(name, theme_name, *scale, *size);
+                        let failure =
+                            check_entry(name, entry, &snapshot, matrix, &golden_dir, &diff_dir);
+                        match failure {
+                            Some(failure) if matrix.bless => {
+                                failures.extend(bless_entry(failure, &snapshot, &golden_dir))
+                            }
+                            failure => failures.extend(failure),
+                        }
+                    }
+                }
+            }
+        }
+        failures
+    }
+}
+
+pub struct GoldenMatrix {
+    /// Named themes, the name is part of the golden file names
+    pub themes: Vec<(&'static str, Theme)>,
+    pub scales: Vec<f64>,
+    pub sizes: Vec<Size<i32, Logical>>,
+    /// Differences of single channels up to this are ignored, e.g. for antialiasing
+    pub channel_tolerance: u8,
+    /// Number of pixels allowed to differ beyond `channel_tolerance`
+    pub max_differing_pixels: usize,
+    /// Whether missing and failing goldens are replaced by the renderings, set if `BLESS_VAR` is
+    pub bless: bool,
+}
+
+impl Default for GoldenMatrix {
+    fn default() -> Self {
+        GoldenMatrix {
+            themes: vec![("dark", Theme::dark()), ("light", Theme::light())],
+            scales: vec![1.0, 1.5, 2.0],
+            sizes: vec![Size::from((420, 180)), Size::from((640, 360))],
+            channel_tolerance: 2,
+            max_differing_pixels: 0,
+            bless: std::env::var_os(BLESS_VAR).is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GoldenFailureReason {
+    /// No golden exists, see `GoldenMatrix::bless`
+    Missing,
+    SizeMismatch {
+        golden: (u32, u32),
+        actual: (u32, u32),
+    },
+    Differs {
+        pixels: usize,
+    },
+    Io(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenFailure {
+    pub program: &'static str,
+    /// File name of the golden image
+    pub entry: String,
+    pub reason: GoldenFailureReason,
+}
+
+/// Unpremultiplied RGBA8 of a snapshot, as stored in PNGs.
+fn to_rgba(snapshot: &Snapshot) -> Vec<u8> {
+    snapshot
+        .pixels
+        .iter()
+        .flat_map(|pixel| {
+            let [b, g, r, a] = pixel.to_le_bytes();
+            let unpremultiply = |c: u8| match a {
+                0 => 0,
+                a => ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8,
+            };
+            [unpremultiply(r), unpremultiply(g), unpremultiply(b), a]
+        })
+        .collect()
+}
+
+fn write_png(path: &Path, (w, h): (u32, u32), rgba: &[u8]) -> Result<(), String> {
+    let file = File::create(path).map_err(|err| err.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), w, h);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgba))
+        .map_err(|err| err.to_string())
+}
+
+fn read_png(path: &Path) -> Result<((u32, u32), Vec<u8>), String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let mut reader = png::Decoder::new(file)
+        .read_info()
+        .map_err(|err| err.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(|err| err.to_string())?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(format!("Unsupported format {:?}", info.color_type));
+    }
+    buf.truncate(info.buffer_size());
+    Ok(((info.width, info.height), buf))
+}
+
+/// Golden, actual and difference (differing pixels in red) next to each other.
+fn side_by_side(width: u32, golden: &[u8], actual: &[u8], differing: &[bool]) -> Vec<u8> {
+    let row = width as usize * 4;
+    let mut out = Vec::with_capacity(golden.len() * 3);
+    for ((golden, actual), differing) in golden
+        .chunks(row)
+        .zip(actual.chunks(row))
+        .zip(differing.chunks(width as usize))
+    {
+        out.extend_from_slice(golden);
+        out.extend_from_slice(actual);
+        for differs in differing {
+            out.extend_from_slice(if *differs {
+                &[255, 0, 0, 255]
+            } else {
+                &[0, 0, 0, 64]
+            });
+        }
+    }
+    out
+}
+
+fn check_entry(
+    program: &'static str,
+    entry: String,
+    snapshot: &Snapshot,
+    matrix: &GoldenMatrix,
+    golden_dir: &Path,
+    diff_dir: &Path,
+) -> Option<GoldenFailure> {
+    let failure = |reason| {
+        Some(GoldenFailure {
+            program,
+            entry: entry.clone(),
+            reason,
+        })
+    };
+    let size = (snapshot.size.w.max(0) as u32, snapshot.size.h.max(0) as u32);
+    let actual = to_rgba(snapshot);
+    let path = golden_dir.join(&entry);
+
+    if !path.exists() {
+        return failure(GoldenFailureReason::Missing);
+    }
+    let (golden_size, golden) = match read_png(&path) {
+        Ok(golden) => golden,
+        Err(err) => return failure(GoldenFailureReason::Io(err)),
+    };
+    if golden_size != size {
+        return failure(GoldenFailureReason::SizeMismatch {
+            golden: golden_size,
+            actual: size,
+        });
+    }
+
+    let differing = golden
+        .chunks(4)
+        .zip(actual.chunks(4))
+        .map(|(a, b)| {
+            a.iter()
+                .zip(b)
+                .any(|(a, b)| a.abs_diff(*b) > matrix.channel_tolerance)
+        })
+        .collect::<Vec<_>>();
+    let pixels = differing.iter().filter(|d| **d).count();
+    if pixels <= matrix.max_differing_pixels {
+        return None;
+    }
+
+    let diff = side_by_side(size.0, &golden, &actual, &differing);
+    if let Err(err) = write_png(&diff_dir.join(&entry), (size.0 * 3, size.1), &diff) {
+        tracing::warn!(?err, ?entry, "Failed to write golden diff");
+    }
+    failure(GoldenFailureReason::Differs { pixels })
+}
+
+/// Replaces the golden of the `failure` by `snapshot`, returns a failure if that isn't possible.
+fn bless_entry(
+    failure: GoldenFailure,
+    snapshot: &Snapshot,
+    golden_dir: &Path,
+) -> Option<GoldenFailure> {
+    let size = (snapshot.size.w.max(0) as u32, snapshot.size.h.max(0) as u32);
+    let path = golden_dir.join(&failure.entry);
+    let err = write_png(&path, size, &to_rgba(snapshot)).err()?;
+    Some(GoldenFailure {
+        reason: GoldenFailureReason::Io(err),
+        ..failure
+    })
+}
+
+fn entry_name(program: &str, theme: &str, scale: f64, size: Size<i32, Logical>) -> String {
+    format!("{}-{}-{}x-{}x{}.png", program, theme, scale, size.w, size.h)
+}
@@ -0,0 +1,117 @@
+//! Snapping of hairlines to physical pixels.
+//!
+//! One logical pixel wide separators and borders cover parts of two physical pixels at
+//! fractional scales. This moves them onto exact pixel boundaries at draw time,
+//! without affecting layout or hit testing.
+
+use std::borrow::Cow;
+
+use cosmic::iced_native::Vector;
+use iced_graphics::Primitive;
+
+/// Physical thickness of a snapped hairline at the given scale.
+fn thickness(scale: f32) -> f32 {
+    // preserve the visual weight at high scales
+    if scale >= 1.75 {
+        2.0
+    } else {
+        1.0
+    }
+}
+
+/// Snaps a logical coordinate, that is relative to `offset`, to the nearest physical pixel.
+fn snap(value: f32, offset: f32, scale: f32) -> f32 {
+    ((offset + value) * scale).round() / scale - offset
+}
+
+fn snap_quad(primitive: &Primitive, offset: Vector, scale: f32) -> Option<Primitive> {
+    let Primitive::Quad {
+        bounds,
+        border_width,
+        ..
+    } = primitive
+    else { return None };
+    let line = thickness(scale) / scale;
+    let mut new_bounds = *bounds;
+    let mut new_border_width = *border_width;
+
+    if bounds.height > 0.0 && bounds.height <= 1.0 && bounds.width > bounds.height {
+        new_bounds.y = snap(bounds.y, offset.y, scale);
+        new_bounds.height = line;
+    } else if bounds.width > 0.0 && bounds.width <= 1.0 && bounds.height > bounds.width {
+        new_bounds.x = snap(bounds.x, offset.x, scale);
+        new_bounds.width = line;
+    } else if *border_width > 0.0 && *border_width <= 1.0 {
+        let x = snap(bounds.x, offset.x, scale);
+        let y = snap(bounds.y, offset.y, scale);
+        new_bounds.width = snap(bounds.x + bounds.width, offset.x, scale) - x;
+        new_bounds.height = snap(bounds.y + bounds.height, offset.y, scale) - y;
+        new_bounds.x = x;
+        new_bounds.y = y;
+        new_border_width = line;
+    }
+
+    if new_bounds == *bounds && new_border_width == *border_width {
+        return None;
+    }
+    let mut snapped = primitive.clone();
+    if let Primitive::Quad {
+        bounds,
+        border_width,
+        ..
+    } = &mut snapped
+    {
+        *bounds = new_bounds;
+        *border_width = new_border_width;
+    }
+    Some(snapped)
+}
+
+fn snap_primitive<'a>(primitive: &'a Primitive, offset: Vector, scale: f32) -> Cow<'a, Primitive> {
+    match primitive {
+        Primitive::Group { primitives } => {
+            let snapped = primitives
+                .iter()
+                .map(|p| snap_primitive(p, offset, scale))
+                .collect::<Vec<_>>();
+            if snapped.iter().all(|p| matches!(p, Cow::Borrowed(_))) {
+                Cow::Borrowed(primitive)
+            } else {
+                Cow::Owned(Primitive::Group {
+                    primitives: snapped.into_iter().map(Cow::into_owned).collect(),
+                })
+            }
+        }
+        Primitive::Translate {
+            translation,
+            content,
+        } => match snap_primitive(content, offset + *translation, scale) {
+            Cow::Borrowed(_) => Cow::Borrowed(primitive),
+            Cow::Owned(content) => Cow::Owned(Primitive::Translate {
+                translation: *translation,
+                content: Box::new(content),
+            }),
+        },
+        Primitive::Clip { bounds, content } => match snap_primitive(content, offset, scale) {
+            Cow::Borrowed(_) => Cow::Borrowed(primitive),
+            Cow::Owned(content) => Cow::Owned(Primitive::Clip {
+                bounds: *bounds,
+                content: Box::new(content),
+            }),
+        },
+        Primitive::Cached { cache } => match snap_primitive(cache, offset, scale) {
+            Cow::Borrowed(_) => Cow::Borrowed(primitive),
+            snapped => snapped,
+        },
+        quad @ Primitive::Quad { .. } => match snap_quad(quad, offset, scale) {
+            Some(snapped) => Cow::Owned(snapped),
+            None => Cow::Borrowed(primitive),
+        },
+        _ => Cow::Borrowed(primitive),
+    }
+}
+
+/// Returns `primitive` with all hairlines snapped to physical pixels at `scale`.
+pub(super) fn snap_hairlines(primitive: &Primitive, scale: f32) -> Cow<'_, Primitive> {
+    snap_primitive(primitive, Vector::new(0.0, 0.0), scale)
+}
@@ -0,0 +1,189 @@
+//! Hit testing for input fast paths, without locking any element.
+//!
+//! Every element publishes a snapshot of the state its input region depends on,
+//! updated whenever that state changes. The pointer motion path can use these to rule out
+//! elements, instead of locking each one while it may be rendering.
+//! Snapshots never report `Outside` for a point the element's `is_in_input_region` accepts.
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, RwLock,
+};
+
+use smithay::{
+    output::Output,
+    utils::{Logical, Point, Rectangle, Size},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FastHit {
+    Outside,
+    /// The snapshot can't decide, the caller has to do the precise check
+    MaybeInside,
+    Inside,
+}
+
+#[derive(Debug)]
+pub(super) struct HitSnapshot {
+    alive: AtomicBool,
+    /// Width in the upper and height in the lower 32 bits
+    size: AtomicU64,
+    /// The program narrowed its input region, see `Program::input_region`
+    custom_region: AtomicBool,
+    /// Offsets of the element on the outputs it is mapped on, see `IcedElement::set_output_offset`.
+    /// Only written on (rare) remappings, so readers don't contend.
+    offsets: RwLock<Vec<(Output, Point<i32, Logical>)>>,
+}
+
+impl HitSnapshot {
+    pub fn new(size: Size<i32, Logical>) -> Arc<HitSnapshot> {
+        let snapshot = HitSnapshot {
+            alive: AtomicBool::new(true),
+            size: AtomicU64::new(0),
+            custom_region: AtomicBool::new(false),
+            offsets: RwLock::new(Vec::new()),
+        };
+        snapshot.set_size(size);
+        Arc::new(snapshot)
+    }
+
+    pub fn set_size(&self, size: Size<i32, Logical>) {
+        let packed = ((size.w.max(0) as u64) << 32) | size.h.max(0) as u64;
+        self.size.store(packed, Ordering::Release);
+    }
+
+    pub fn set_custom_region(&self, custom: bool) {
+        self.custom_region.store(custom, Ordering::Release);
+    }
+
+    pub fn set_offsets(&self, offsets: &[(Output, Point<i32, Logical>)]) {
+        *self.offsets.write().unwrap() = offsets.to_vec();
+    }
+
+    pub fn kill(&self) {
+        self.alive.store(false, Ordering::Release);
+    }
+
+    /// Hit test of a `point` relative to the element.
+    pub fn hit(&self, point: Point<f64, Logical>) -> FastHit {
+        if !self.alive.load(Ordering::Acquire) {
+            return FastHit::Outside;
+        }
+        let packed = self.size.load(Ordering::Acquire);
+        let (w, h) = ((packed >> 32) as f64, (packed & u32::MAX as u64) as f64);
+        if point.x >= 0.0 && point.y >= 0.0 && point.x < w && point.y < h {
+            if self.custom_region.load(Ordering::Acquire) {
+                FastHit::MaybeInside
+            } else {
+                // the input region covers the whole element
+                FastHit::Inside
+            }
+        } else {
+            FastHit::Outside
+        }
+    }
+
+    /// Global bounding box of the element on `output`, if its offset there is known.
+    fn bbox_on(&self, output: &Output) -> Option<Rectangle<i32, Logical>> {
+        if !self.alive.load(Ordering::Acquire) {
+            return None;
+        }
+        let packed = self.size.load(Ordering::Acquire);
+        let size = ((packed >> 32) as i32, (packed & u32::MAX as u64) as i32);
+        let offsets = self.offsets.read().unwrap();
+        let (_, offset) = offsets.iter().find(|(o, _)| o == output)?;
+        Some(Rectangle::from_loc_and_size(
+            output.current_location() + *offset,
+            size,
+        ))
+    }
+
+    /// Hit test of a global `point`, on any output the element is mapped on.
+    fn hit_global(&self, point: Point<f64, Logical>) -> FastHit {
+        if !self.alive.load(Ordering::Acquire) {
+            return FastHit::Outside;
+        }
+        let offsets = self.offsets.read().unwrap();
+        // the location of elements without offsets is only known to their space
+        if offsets.is_empty() {
+            return FastHit::MaybeInside;
+        }
+        offsets
+            .iter()
+            .map(|(output, offset)| {
+                let location = output.current_location() + *offset;
+                self.hit(point - location.to_f64())
+            })
+            .max()
+            .unwrap_or(FastHit::Outside)
+    }
+}
+
+/// Snapshots of all live elements, see `hit_index`.
+#[derive(Debug, Default)]
+pub struct HitIndex {
+    /// Snapshots by the address of their element
+    snapshots: RwLock<Vec<(usize, Arc<HitSnapshot>)>>,
+}
+
+impl HitIndex {
+    /// Whether the global `point` is over any element.
+    pub fn hit(&self, point: Point<f64, Logical>) -> FastHit {
+        self.snapshots
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(_, snapshot)| snapshot.hit_global(point))
+            .max()
+            .unwrap_or(FastHit::Outside)
+    }
+
+    pub(super) fn insert(&self, element: usize, snapshot: Arc<HitSnapshot>) {
+        self.snapshots.write().unwrap().push((element, snapshot));
+    }
+
+    pub(super) fn remove(&self, snapshot: &Arc<HitSnapshot>) {
+        self.snapshots
+            .write()
+            .unwrap()
+            .retain(|(_, s)| !Arc::ptr_eq(s, snapshot));
+    }
+
+    /// Elements on `output` overlapping the global `area`, with their global bounding boxes.
+    ///
+    /// Elements whose offset on `output` isn't known are skipped.
+    pub(super) fn overlapping(
+        &self,
+        output: &Output,
+        area: Rectangle<i32, Logical>,
+    ) -> Vec<(usize, Rectangle<i32, Logical>)> {
+        self.snapshots
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|(id, snapshot)| {
+                let bbox = snapshot.bbox_on(output)?;
+                bbox.overlaps(area).then(|| (*id, bbox))
+            })
+            .collect()
+    }
+
+    /// Hit test of a `point` relative to the given element.
+    pub(super) fn hit_element(&self, element: usize, point: Point<f64, Logical>) -> FastHit {
+        self.snapshots
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(id, _)| *id == element)
+            .map_or(FastHit::MaybeInside, |(_, snapshot)| snapshot.hit(point))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref HIT_INDEX: HitIndex = HitIndex::default();
+}
+
+/// Index of all live elements for lock-free hit tests, e.g. on every pointer motion.
+pub fn hit_index() -> &'static HitIndex {
+    &HIT_INDEX
+}
@@ -0,0 +1,210 @@
+//! Hover indicators of secondary seats, for multiple pointers on the same element.
+//!
+//! iced only knows a single cursor, which follows the primary seat: the seat that pressed a button
+//! last, unless one was chosen via `IcedElement::set_primary_seat`. Motion of other seats doesn't
+//! reach iced. Instead the interactive region (see `Program::press_regions`) under their cursor
+//! gets an outline and their cursor a dot, both in a color identifying the seat, drawn above the
+//! program like badges.
+
+use cosmic::iced_native::Color;
+use iced_softbuffer::native::raqote::{
+    DrawOptions, DrawTarget, PathBuilder, SolidSource, Source, StrokeStyle,
+};
+use smithay::utils::{Logical, Point, Rectangle};
+
+const DOT_SIZE: i32 = 6;
+const OUTLINE_WIDTH: f32 = 2.0;
+/// Colors of secondary seats, by seat id
+const SEAT_COLORS: [Color; 4] = [
+    Color {
+        r: 0.89,
+        g: 0.35,
+        b: 0.13,
+        a: 1.0,
+    },
+    Color {
+        r: 0.18,
+        g: 0.65,
+        b: 0.42,
+        a: 1.0,
+    },
+    Color {
+        r: 0.56,
+        g: 0.31,
+        b: 0.82,
+        a: 1.0,
+    },
+    Color {
+        r: 0.85,
+        g: 0.65,
+        b: 0.09,
+        a: 1.0,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct SeatIndicator {
+    seat: usize,
+    position: Point<f64, Logical>,
+    /// Interactive region under the cursor
+    region: Option<Rectangle<i32, Logical>>,
+}
+
+impl SeatIndicator {
+    fn dot(&self) -> Rectangle<i32, Logical> {
+        let position = self.position.to_i32_round();
+        Rectangle::from_loc_and_size(
+            (position.x - DOT_SIZE, position.y - DOT_SIZE),
+            (DOT_SIZE * 2, DOT_SIZE * 2),
+        )
+    }
+
+    /// Area covered by the indicator.
+    fn bounds(&self) -> Rectangle<i32, Logical> {
+        match self.region {
+            Some(region) => region.merge(self.dot()),
+            None => self.dot(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct SeatHovers {
+    primary: Option<usize>,
+    /// Set via `IcedElement::set_primary_seat`, presses don't change the primary seat then
+    pinned: bool,
+    indicators: Vec<SeatIndicator>,
+}
+
+impl SeatHovers {
+    pub fn primary(&self) -> Option<usize> {
+        self.primary
+    }
+
+    /// Whether input of `seat` should reach iced, the first seat becomes primary.
+    pub fn claim(&mut self, seat: usize) -> bool {
+        *self.primary.get_or_insert(seat) == seat
+    }
+
+    /// Makes `seat` primary, returns the damage of its indicator, that isn't shown anymore.
+    ///
+    /// The previous primary seat gets an indicator at `previous_position`.
+    pub fn set_primary(
+        &mut self,
+        seat: usize,
+        previous_position: Option<Point<f64, Logical>>,
+        regions: &[Rectangle<i32, Logical>],
+    ) -> Vec<Rectangle<i32, Logical>> {
+        let previous = self.primary.replace(seat);
+        let mut damage = self.left(seat);
+        if let (Some(previous), Some(position)) = (previous, previous_position) {
+            if previous != seat {
+                damage.extend(self.moved(previous, position, regions));
+            }
+        }
+        damage
+    }
+
+    pub fn pin(&mut self, pinned: bool) {
+        self.pinned = pinned;
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    /// Updates the indicator of a secondary `seat`, returns the damage.
+    pub fn moved(
+        &mut self,
+        seat: usize,
+        position: Point<f64, Logical>,
+        regions: &[Rectangle<i32, Logical>],
+    ) -> Vec<Rectangle<i32, Logical>> {
+        let indicator = SeatIndicator {
+            seat,
+            position,
+            region: regions
+                .iter()
+                .find(|region| region.to_f64().contains(position))
+                .copied(),
+        };
+        match self.indicators.iter_mut().find(|i| i.seat == seat) {
+            Some(old) if *old == indicator => Vec::new(),
+            Some(old) => {
+                let damage = vec![old.bounds(), indicator.bounds()];
+                *old = indicator;
+                damage
+            }
+            None => {
+                self.indicators.push(indicator);
+                vec![indicator.bounds()]
+            }
+        }
+    }
+
+    /// Removes the indicator of `seat`, returns the damage.
+    pub fn left(&mut self, seat: usize) -> Vec<Rectangle<i32, Logical>> {
+        let Some(i) = self.indicators.iter().position(|i| i.seat == seat) else { return Vec::new() };
+        vec![self.indicators.remove(i).bounds()]
+    }
+
+    /// The primary seat left, the next seat moving over the element becomes primary.
+    pub fn primary_left(&mut self) {
+        if !self.pinned {
+            self.primary = None;
+        }
+    }
+
+    pub fn indicators(&self) -> &[SeatIndicator] {
+        &self.indicators
+    }
+}
+
+fn solid(color: Color) -> Source<'static> {
+    Source::Solid(SolidSource::from_unpremultiplied_argb(
+        (color.a * 255.0) as u8,
+        (color.r * 255.0) as u8,
+        (color.g * 255.0) as u8,
+        (color.b * 255.0) as u8,
+    ))
+}
+
+/// Draws `indicators` onto `target`, which is scaled by `scale` relative to logical coordinates.
+pub(super) fn draw_indicators(
+    target: &mut DrawTarget<&mut [u32]>,
+    indicators: &[SeatIndicator],
+    scale: f32,
+) {
+    for indicator in indicators {
+        let source = solid(SEAT_COLORS[indicator.seat % SEAT_COLORS.len()]);
+        if let Some(region) = indicator.region {
+            let mut pb = PathBuilder::new();
+            let inset = OUTLINE_WIDTH * scale / 2.0;
+            pb.rect(
+                region.loc.x as f32 * scale + inset,
+                region.loc.y as f32 * scale + inset,
+                region.size.w as f32 * scale - 2.0 * inset,
+                region.size.h as f32 * scale - 2.0 * inset,
+            );
+            target.stroke(
+                &pb.finish(),
+                &source,
+                &StrokeStyle {
+                    width: OUTLINE_WIDTH * scale,
+                    ..Default::default()
+                },
+                &DrawOptions::new(),
+            );
+        }
+
+        let mut pb = PathBuilder::new();
+        pb.arc(
+            indicator.position.x as f32 * scale,
+            indicator.position.y as f32 * scale,
+            DOT_SIZE as f32 / 2.0 * scale,
+            0.0,
+            2.0 * std::f32::consts::PI,
+        );
+        target.fill(&pb.finish(), &source, &DrawOptions::new());
+    }
+}
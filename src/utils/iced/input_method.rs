@@ -0,0 +1,113 @@
+//! Elements acting as an input method, e.g. an on-screen keyboard.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use super::{IcedElement, Program};
+
+/// Requests of an input method, as defined by `zwp_input_method_v2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputMethodEvent {
+    CommitString(String),
+    /// Lengths in bytes around the cursor
+    DeleteSurroundingText {
+        before_length: u32,
+        after_length: u32,
+    },
+    /// Cursor positions are byte offsets into `text`, `None` hides the cursor
+    SetPreeditString {
+        text: String,
+        cursor: Option<(i32, i32)>,
+    },
+}
+
+/// Purpose of the focused text field, like the content purpose of `zwp_text_input_v3`,
+/// see `IcedElement::input_method_purpose`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentPurpose {
+    #[default]
+    Normal,
+    /// Neither shown in suggestions nor learned from
+    Password,
+}
+
+/// An `IcedElement` used as input method, receiving `InputMethodEvent`s as messages.
+pub struct InputMethodSurface<P: Program + Send + 'static> {
+    element: IcedElement<P>,
+    map: Arc<dyn Fn(InputMethodEvent) -> P::Message + Send + Sync>,
+    keyboard_grab: Arc<AtomicBool>,
+}
+
+impl<P: Program + Send + 'static> Clone for InputMethodSurface<P> {
+    fn clone(&self) -> Self {
+        InputMethodSurface {
+            element: self.element.clone(),
+            map: self.map.clone(),
+            keyboard_grab: self.keyboard_grab.clone(),
+        }
+    }
+}
+
+impl<P: Program + Send + 'static> std::fmt::Debug for InputMethodSurface<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputMethodSurface")
+            .field("keyboard_grab", &self.keyboard_grab)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: Program + Send + 'static> InputMethodSurface<P> {
+    pub fn new(
+        element: IcedElement<P>,
+        map: impl Fn(InputMethodEvent) -> P::Message + Send + Sync + 'static,
+    ) -> InputMethodSurface<P> {
+        InputMethodSurface {
+            element,
+            map: Arc::new(map),
+            keyboard_grab: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn element(&self) -> &IcedElement<P> {
+        &self.element
+    }
+
+    /// Forwards an input method request to the program.
+    pub fn handle(&self, event: InputMethodEvent) {
+        self.element.queue_message((self.map)(event));
+    }
+
+    pub fn commit_string(&self, text: impl Into<String>) {
+        self.handle(InputMethodEvent::CommitString(text.into()));
+    }
+
+    pub fn delete_surrounding_text(&self, before_length: u32, after_length: u32) {
+        self.handle(InputMethodEvent::DeleteSurroundingText {
+            before_length,
+            after_length,
+        });
+    }
+
+    pub fn set_preedit_string(&self, text: impl Into<String>, cursor: Option<(i32, i32)>) {
+        self.handle(InputMethodEvent::SetPreeditString {
+            text: text.into(),
+            cursor,
+        });
+    }
+
+    /// Enters keyboard-exclusive mode, in which all key events of the seat
+    /// are meant to be routed to the element instead of the focused client.
+    pub fn grab_keyboard(&self) {
+        self.keyboard_grab.store(true, Ordering::SeqCst);
+    }
+
+    pub fn release_keyboard(&self) {
+        self.keyboard_grab.store(false, Ordering::SeqCst);
+    }
+
+    pub fn has_keyboard_grab(&self) -> bool {
+        self.keyboard_grab.load(Ordering::SeqCst)
+    }
+}
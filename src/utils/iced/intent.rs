@@ -0,0 +1,154 @@
+//! Write-ahead log of destructive actions triggered from overlays, with an undo window.
+//!
+//! Programs record destructive actions via `UpdateContext::destructive` instead of running them.
+//! The shell is notified via the handler set with `set_intent_handler` (e.g. to show an "Undo"
+//! toast) and the action is committed once its undo window expired. `undo_last` cancels the most
+//! recent pending intent and notifies the element, that recorded it, via `Program::intent_undone`.
+//! Intents still pending on shutdown are committed, see `commit_pending_intents`.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Duration,
+};
+
+use smithay::reexports::calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle,
+};
+use tracing::{debug, warn};
+
+use super::{lock_global, registry::RegisteredElement};
+
+const DEFAULT_UNDO_WINDOW: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IntentId(u64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DestructiveIntent {
+    /// Shown to the user, e.g. "Dismissed all notifications"
+    pub description: String,
+    pub undo_window: Duration,
+}
+
+impl DestructiveIntent {
+    pub fn new(description: impl Into<String>) -> DestructiveIntent {
+        DestructiveIntent {
+            description: description.into(),
+            undo_window: DEFAULT_UNDO_WINDOW,
+        }
+    }
+
+    pub fn with_undo_window(mut self, undo_window: Duration) -> DestructiveIntent {
+        self.undo_window = undo_window;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntentEvent {
+    Pending(IntentId, DestructiveIntent),
+    Committed(IntentId),
+    Undone(IntentId),
+}
+
+pub(super) type Commit = Box<dyn FnOnce() + Send>;
+type IntentHandler = Arc<dyn Fn(IntentEvent) + Send + Sync>;
+
+struct PendingIntent {
+    id: IntentId,
+    commit: Commit,
+    element: Weak<dyn RegisteredElement>,
+}
+
+lazy_static::lazy_static! {
+    /// Oldest first
+    static ref PENDING: Mutex<Vec<PendingIntent>> = Mutex::new(Vec::new());
+    static ref HANDLER: Mutex<Option<IntentHandler>> = Mutex::new(None);
+}
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+pub(super) fn next_id() -> IntentId {
+    IntentId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Sets the handler notified about pending, committed and undone intents.
+pub fn set_intent_handler(handler: impl Fn(IntentEvent) + Send + Sync + 'static) {
+    *lock_global(&HANDLER) = Some(Arc::new(handler));
+}
+
+fn notify(event: IntentEvent) {
+    // cloned, so the handler may undo intents itself
+    let handler = lock_global(&HANDLER).clone();
+    match handler {
+        Some(handler) => handler(event),
+        None => debug!(?event, "No handler for destructive intents"),
+    }
+}
+
+fn take(id: IntentId) -> Option<PendingIntent> {
+    let mut pending = lock_global(&PENDING);
+    let i = pending.iter().position(|intent| intent.id == id)?;
+    Some(pending.remove(i))
+}
+
+fn commit(intent: PendingIntent) {
+    (intent.commit)();
+    notify(IntentEvent::Committed(intent.id));
+}
+
+pub(super) fn record(
+    id: IntentId,
+    intent: DestructiveIntent,
+    commit_fn: Commit,
+    element: Weak<dyn RegisteredElement>,
+    handle: &LoopHandle<'static, crate::state::Data>,
+) {
+    let undo_window = intent.undo_window;
+    lock_global(&PENDING).push(PendingIntent {
+        id,
+        commit: commit_fn,
+        element,
+    });
+    // undone intents are just gone once this fires
+    if let Err(err) = handle.insert_source(Timer::from_duration(undo_window), move |_, _, _| {
+        if let Some(intent) = take(id) {
+            commit(intent);
+        }
+        TimeoutAction::Drop
+    }) {
+        warn!(
+            ?err,
+            "Failed to schedule commit of destructive intent, committing now"
+        );
+        if let Some(intent) = take(id) {
+            commit(intent);
+        }
+        return;
+    }
+    notify(IntentEvent::Pending(id, intent));
+}
+
+/// Cancels the most recent pending intent, returns false if there is none.
+///
+/// Must not be called from the update of the element, that recorded the intent,
+/// as that element is notified synchronously.
+pub fn undo_last() -> bool {
+    let Some(intent) = lock_global(&PENDING).pop() else { return false };
+    if let Some(element) = intent.element.upgrade() {
+        element.intent_undone(intent.id);
+    }
+    notify(IntentEvent::Undone(intent.id));
+    true
+}
+
+/// Commits all pending intents right away, oldest first, e.g. on shutdown.
+pub fn commit_pending_intents() {
+    let pending = std::mem::take(&mut *lock_global(&PENDING));
+    for intent in pending {
+        commit(intent);
+    }
+}
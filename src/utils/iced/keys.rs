@@ -0,0 +1,184 @@
+//! Conversion of xkb keysyms into iced keyboard events.
+
+use cosmic::iced_native::keyboard::{KeyCode, Modifiers};
+use smithay::input::keyboard::{keysyms::*, KeysymHandle};
+use xkbcommon::xkb;
+
+/// Unmodified keysym of `key`, i.e. the key on the current layout.
+pub(super) fn raw_sym(key: &KeysymHandle<'_>) -> u32 {
+    key.raw_syms()
+        .first()
+        .copied()
+        .unwrap_or_else(|| key.modified_sym())
+}
+
+pub(super) fn key_code(key: &KeysymHandle<'_>) -> Option<KeyCode> {
+    keysym_to_key_code(raw_sym(key))
+}
+
+/// Whether holding the key repeats it, modifiers and locks don't.
+pub(super) fn repeats(sym: u32) -> bool {
+    !matches!(
+        sym,
+        KEY_Shift_L..=KEY_Hyper_R
+            | KEY_ISO_Lock..=KEY_ISO_Last_Group_Lock
+            | KEY_Num_Lock
+            | KEY_Mode_switch
+    )
+}
+
+/// Text typed by `key`, none for dead keys, control characters or while shortcut modifiers
+/// are held.
+pub(super) fn text(key: &KeysymHandle<'_>, modifiers: Modifiers) -> Option<char> {
+    if modifiers.control() || modifiers.logo() {
+        return None;
+    }
+    let sym = key.modified_sym();
+    // composed by the input method (or not at all), never typed on their own
+    if (KEY_dead_grave..=KEY_dead_greek).contains(&sym) {
+        return None;
+    }
+    char::from_u32(xkb::keysym_to_utf32(sym)).filter(|c| *c != '\0' && !c.is_control())
+}
+
+pub(super) fn keysym_to_key_code(sym: u32) -> Option<KeyCode> {
+    let code = match sym {
+        KEY_a | KEY_A => KeyCode::A,
+        KEY_b | KEY_B => KeyCode::B,
+        KEY_c | KEY_C => KeyCode::C,
+        KEY_d | KEY_D => KeyCode::D,
+        KEY_e | KEY_E => KeyCode::E,
+        KEY_f | KEY_F => KeyCode::F,
+        KEY_g | KEY_G => KeyCode::G,
+        KEY_h | KEY_H => KeyCode::H,
+        KEY_i | KEY_I => KeyCode::I,
+        KEY_j | KEY_J => KeyCode::J,
+        KEY_k | KEY_K => KeyCode::K,
+        KEY_l | KEY_L => KeyCode::L,
+        KEY_m | KEY_M => KeyCode::M,
+        KEY_n | KEY_N => KeyCode::N,
+        KEY_o | KEY_O => KeyCode::O,
+        KEY_p | KEY_P => KeyCode::P,
+        KEY_q | KEY_Q => KeyCode::Q,
+        KEY_r | KEY_R => KeyCode::R,
+        KEY_s | KEY_S => KeyCode::S,
+        KEY_t | KEY_T => KeyCode::T,
+        KEY_u | KEY_U => KeyCode::U,
+        KEY_v | KEY_V => KeyCode::V,
+        KEY_w | KEY_W => KeyCode::W,
+        KEY_x | KEY_X => KeyCode::X,
+        KEY_y | KEY_Y => KeyCode::Y,
+        KEY_z | KEY_Z => KeyCode::Z,
+
+        KEY_0 => KeyCode::Key0,
+        KEY_1 => KeyCode::Key1,
+        KEY_2 => KeyCode::Key2,
+        KEY_3 => KeyCode::Key3,
+        KEY_4 => KeyCode::Key4,
+        KEY_5 => KeyCode::Key5,
+        KEY_6 => KeyCode::Key6,
+        KEY_7 => KeyCode::Key7,
+        KEY_8 => KeyCode::Key8,
+        KEY_9 => KeyCode::Key9,
+
+        KEY_F1 => KeyCode::F1,
+        KEY_F2 => KeyCode::F2,
+        KEY_F3 => KeyCode::F3,
+        KEY_F4 => KeyCode::F4,
+        KEY_F5 => KeyCode::F5,
+        KEY_F6 => KeyCode::F6,
+        KEY_F7 => KeyCode::F7,
+        KEY_F8 => KeyCode::F8,
+        KEY_F9 => KeyCode::F9,
+        KEY_F10 => KeyCode::F10,
+        KEY_F11 => KeyCode::F11,
+        KEY_F12 => KeyCode::F12,
+        KEY_F13 => KeyCode::F13,
+        KEY_F14 => KeyCode::F14,
+        KEY_F15 => KeyCode::F15,
+        KEY_F16 => KeyCode::F16,
+        KEY_F17 => KeyCode::F17,
+        KEY_F18 => KeyCode::F18,
+        KEY_F19 => KeyCode::F19,
+        KEY_F20 => KeyCode::F20,
+        KEY_F21 => KeyCode::F21,
+        KEY_F22 => KeyCode::F22,
+        KEY_F23 => KeyCode::F23,
+        KEY_F24 => KeyCode::F24,
+
+        KEY_Escape => KeyCode::Escape,
+        KEY_Tab | KEY_ISO_Left_Tab => KeyCode::Tab,
+        KEY_BackSpace => KeyCode::Backspace,
+        KEY_Return => KeyCode::Enter,
+        KEY_space => KeyCode::Space,
+        KEY_Insert => KeyCode::Insert,
+        KEY_Delete => KeyCode::Delete,
+        KEY_Home => KeyCode::Home,
+        KEY_End => KeyCode::End,
+        KEY_Page_Up => KeyCode::PageUp,
+        KEY_Page_Down => KeyCode::PageDown,
+        KEY_Left => KeyCode::Left,
+        KEY_Up => KeyCode::Up,
+        KEY_Right => KeyCode::Right,
+        KEY_Down => KeyCode::Down,
+        KEY_Print => KeyCode::Snapshot,
+        KEY_Scroll_Lock => KeyCode::Scroll,
+        KEY_Pause => KeyCode::Pause,
+        KEY_Menu => KeyCode::Apps,
+        KEY_Caps_Lock => KeyCode::Capital,
+        KEY_Num_Lock => KeyCode::Numlock,
+
+        KEY_Shift_L => KeyCode::LShift,
+        KEY_Shift_R => KeyCode::RShift,
+        KEY_Control_L => KeyCode::LControl,
+        KEY_Control_R => KeyCode::RControl,
+        KEY_Alt_L => KeyCode::LAlt,
+        KEY_Alt_R | KEY_ISO_Level3_Shift => KeyCode::RAlt,
+        KEY_Super_L => KeyCode::LWin,
+        KEY_Super_R => KeyCode::RWin,
+
+        KEY_apostrophe => KeyCode::Apostrophe,
+        KEY_backslash => KeyCode::Backslash,
+        KEY_comma => KeyCode::Comma,
+        KEY_equal => KeyCode::Equals,
+        KEY_grave => KeyCode::Grave,
+        KEY_bracketleft => KeyCode::LBracket,
+        KEY_bracketright => KeyCode::RBracket,
+        KEY_minus => KeyCode::Minus,
+        KEY_period => KeyCode::Period,
+        KEY_semicolon => KeyCode::Semicolon,
+        KEY_slash => KeyCode::Slash,
+
+        KEY_KP_0 | KEY_KP_Insert => KeyCode::Numpad0,
+        KEY_KP_1 | KEY_KP_End => KeyCode::Numpad1,
+        KEY_KP_2 | KEY_KP_Down => KeyCode::Numpad2,
+        KEY_KP_3 | KEY_KP_Page_Down => KeyCode::Numpad3,
+        KEY_KP_4 | KEY_KP_Left => KeyCode::Numpad4,
+        KEY_KP_5 | KEY_KP_Begin => KeyCode::Numpad5,
+        KEY_KP_6 | KEY_KP_Right => KeyCode::Numpad6,
+        KEY_KP_7 | KEY_KP_Home => KeyCode::Numpad7,
+        KEY_KP_8 | KEY_KP_Up => KeyCode::Numpad8,
+        KEY_KP_9 | KEY_KP_Page_Up => KeyCode::Numpad9,
+        KEY_KP_Add => KeyCode::NumpadAdd,
+        KEY_KP_Subtract => KeyCode::NumpadSubtract,
+        KEY_KP_Multiply => KeyCode::NumpadMultiply,
+        KEY_KP_Divide => KeyCode::NumpadDivide,
+        KEY_KP_Decimal | KEY_KP_Delete => KeyCode::NumpadDecimal,
+        KEY_KP_Separator => KeyCode::NumpadComma,
+        KEY_KP_Enter => KeyCode::NumpadEnter,
+        KEY_KP_Equal => KeyCode::NumpadEquals,
+
+        KEY_XF86Copy => KeyCode::Copy,
+        KEY_XF86Cut => KeyCode::Cut,
+        KEY_XF86Paste => KeyCode::Paste,
+        KEY_XF86AudioMute => KeyCode::Mute,
+        KEY_XF86AudioLowerVolume => KeyCode::VolumeDown,
+        KEY_XF86AudioRaiseVolume => KeyCode::VolumeUp,
+        KEY_XF86AudioPlay => KeyCode::PlayPause,
+        KEY_XF86AudioNext => KeyCode::NextTrack,
+        KEY_XF86AudioPrev => KeyCode::PrevTrack,
+        KEY_XF86AudioStop => KeyCode::MediaStop,
+        _ => return None,
+    };
+    Some(code)
+}
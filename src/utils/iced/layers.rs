@@ -0,0 +1,215 @@
+//! Rendering of a program split into stacked layers, see `Program::layers`.
+//!
+//! Each layer gets its own buffer per scale, covering only its bounds, so a frequently changing
+//! part of a large element doesn't cause the whole element to be rasterized and imported again.
+//! Top-level primitives are routed to the first layer containing them, everything else
+//! (including `background`, `foreground`, `custom_render` and badges) is drawn into the base layer
+//! covering the whole element. Layers are redrawn, when the hash of their primitives changes.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt::{self, Write},
+    hash::{Hash, Hasher},
+};
+
+use cosmic::iced_native::{
+    alignment::{Horizontal, Vertical},
+    Rectangle as IcedRectangle,
+};
+use iced_graphics::Primitive;
+use ordered_float::OrderedFloat;
+use smithay::utils::{Logical, Rectangle};
+
+use super::{
+    buffer::{self, ScaleBuffer},
+    Program, Rasterizer, RenderError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateRate {
+    /// Rarely changes, redrawn only when its content changed
+    Static,
+    /// Changes with most updates, redrawn without comparing its content
+    Frequent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerSpec {
+    pub id: u32,
+    /// Bounds within the element, primitives not fully inside go to the next matching layer
+    pub bounds: Rectangle<i32, Logical>,
+    pub rate: UpdateRate,
+}
+
+/// Buffer of a layer at one scale.
+pub(super) struct LayerBuffer {
+    pub spec: LayerSpec,
+    pub buffer: ScaleBuffer,
+}
+
+fn union(a: IcedRectangle, b: IcedRectangle) -> IcedRectangle {
+    let x = a.x.min(b.x);
+    let y = a.y.min(b.y);
+    IcedRectangle {
+        x,
+        y,
+        width: (a.x + a.width).max(b.x + b.width) - x,
+        height: (a.y + a.height).max(b.y + b.height) - y,
+    }
+}
+
+/// Logical bounds of everything `primitive` may draw, `None` if unknown.
+fn bounds(primitive: &Primitive) -> Option<IcedRectangle> {
+    match primitive {
+        Primitive::Group { primitives } => primitives
+            .iter()
+            .map(bounds)
+            .reduce(|a, b| Some(union(a?, b?)))
+            .flatten(),
+        Primitive::Translate {
+            translation,
+            content,
+        } => bounds(content).map(|bounds| bounds + *translation),
+        Primitive::Clip { bounds, .. } => Some(*bounds),
+        Primitive::Cached { cache } => bounds(cache),
+        Primitive::Quad { bounds, .. }
+        | Primitive::Image { bounds, .. }
+        | Primitive::Svg { bounds, .. } => Some(*bounds),
+        // text is positioned by its alignment
+        Primitive::Text {
+            bounds,
+            horizontal_alignment,
+            vertical_alignment,
+            ..
+        } => {
+            let x = match horizontal_alignment {
+                Horizontal::Left => bounds.x,
+                Horizontal::Center => bounds.x - bounds.width / 2.0,
+                Horizontal::Right => bounds.x - bounds.width,
+            };
+            let y = match vertical_alignment {
+                Vertical::Top => bounds.y,
+                Vertical::Center => bounds.y - bounds.height / 2.0,
+                Vertical::Bottom => bounds.y - bounds.height,
+            };
+            Some(IcedRectangle { x, y, ..*bounds })
+        }
+        _ => None,
+    }
+}
+
+fn contains(layer: Rectangle<i32, Logical>, bounds: IcedRectangle) -> bool {
+    let layer = layer.to_f64();
+    bounds.x as f64 >= layer.loc.x
+        && bounds.y as f64 >= layer.loc.y
+        && (bounds.x + bounds.width) as f64 <= layer.loc.x + layer.size.w
+        && (bounds.y + bounds.height) as f64 <= layer.loc.y + layer.size.h
+}
+
+/// Indices of the top-level primitives of each layer, the last entry is the base layer.
+pub(super) fn route(layers: &[LayerSpec], primitives: &[Primitive]) -> Vec<Vec<usize>> {
+    let mut routes = vec![Vec::new(); layers.len() + 1];
+    for (i, primitive) in primitives.iter().enumerate() {
+        let layer = bounds(primitive)
+            .and_then(|bounds| layers.iter().position(|l| contains(l.bounds, bounds)))
+            .unwrap_or(layers.len());
+        routes[layer].push(i);
+    }
+    routes
+}
+
+struct HashWriter<'a>(&'a mut DefaultHasher);
+
+impl<'a> Write for HashWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.hash(self.0);
+        Ok(())
+    }
+}
+
+/// Hash of the given primitives and any `extra` state drawn with them.
+///
+/// Primitives don't implement `Hash`, so this hashes their debug representation,
+/// which is still a lot cheaper than rasterizing and importing a layer.
+pub(super) fn content_hash(
+    primitives: &[Primitive],
+    indices: impl IntoIterator<Item = usize>,
+    extra: &dyn fmt::Debug,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut writer = HashWriter(&mut hasher);
+    for i in indices {
+        let _ = write!(writer, "{:?}", primitives[i]);
+    }
+    let _ = write!(writer, "{:?}", extra);
+    hasher.finish()
+}
+
+/// Redraws the layers, whose content changed since their last draw at `scale`.
+///
+/// `routes` are the result of `route` for `layers`. Returns the last error, failed layers keep
+/// their previous frame.
+pub(super) fn draw<P: Program>(
+    rasterizer: &mut Rasterizer<'_, P>,
+    buffers: &mut HashMap<(u32, OrderedFloat<f64>), LayerBuffer>,
+    layers: &[LayerSpec],
+    routes: &[Vec<usize>],
+    scale: f64,
+    buffer_count: u8,
+) -> Result<(), RenderError> {
+    let (size, primitives, layer) = (rasterizer.size, rasterizer.primitives, rasterizer.layer);
+    let mut error = None;
+    for (spec, route) in layers.iter().zip(routes) {
+        let buffer_size = buffer::buffer_size(spec.bounds.size, scale);
+        if buffer_size.w <= 0 || buffer_size.h <= 0 {
+            continue;
+        }
+        let entry = buffers
+            .entry((spec.id, OrderedFloat(scale)))
+            .or_insert_with(|| LayerBuffer {
+                spec: *spec,
+                buffer: ScaleBuffer::new(buffer_size),
+            });
+        if entry.spec.bounds.size != spec.bounds.size {
+            entry.buffer.resize(buffer_size);
+        } else if entry.spec != *spec {
+            entry.buffer.invalidate();
+        }
+        entry.spec = *spec;
+        entry.buffer.set_buffer_count(buffer_count);
+
+        let hash = match spec.rate {
+            UpdateRate::Static => Some(rasterizer.renderer.with_primitives(|_, primitives| {
+                content_hash(primitives, route.iter().copied(), &())
+            })),
+            UpdateRate::Frequent => None,
+        };
+        if hash.is_some() && hash == entry.buffer.content_hash() {
+            continue;
+        }
+
+        rasterizer.size = spec.bounds.size;
+        rasterizer.primitives = Some(route);
+        rasterizer.layer = Some(spec.bounds);
+        let damage = entry.buffer.take_damage(scale, spec.bounds.size);
+        let result = entry.buffer.back_mut().render().draw(|buf| {
+            rasterizer.rasterize(buf, buffer_size, scale, None)?;
+            Ok(damage)
+        });
+        match result {
+            Ok(()) => {
+                entry.buffer.swap();
+                entry.buffer.set_content_hash(hash);
+            }
+            // the other layers are still drawn
+            Err(err) => {
+                entry.buffer.mark_dirty();
+                error = Some(err);
+            }
+        }
+    }
+    rasterizer.size = size;
+    rasterizer.primitives = primitives;
+    rasterizer.layer = layer;
+    error.map_or(Ok(()), Err)
+}
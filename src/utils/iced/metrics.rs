@@ -0,0 +1,248 @@
+//! Opt-in interaction metrics of shell overlays, for local UX research.
+//!
+//! Programs tag their interactive areas via `Program::interaction_regions`. While enabled
+//! (`enable_interaction_metrics`, requires the `interaction-metrics` feature), named elements
+//! within the `MetricScope` count clicks, hovers lasting at least `HOVER_DWELL` and scroll events
+//! per tag and day. Counts are kept in memory only, keyed by the element's name, so they survive
+//! the element being recreated, and are written out by `export_interaction_metrics` on request.
+//!
+//! Restricted elements (e.g. on the lock screen), unnamed elements and untagged areas are never
+//! counted. Exports only contain element names, tags, day buckets and counts.
+
+use smithay::utils::{Logical, Rectangle};
+
+/// Interactive area of a program, counted under `tag`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InteractionRegion {
+    /// Stable identifier of the area, e.g. "dock-launcher", never user content
+    pub tag: &'static str,
+    pub bounds: Rectangle<i32, Logical>,
+}
+
+#[cfg(feature = "interaction-metrics")]
+pub use self::recorder::*;
+
+#[cfg(feature = "interaction-metrics")]
+mod recorder {
+    use std::{
+        collections::BTreeMap,
+        fs::File,
+        io::BufWriter,
+        path::Path,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    };
+
+    use anyhow::{Context, Result};
+    use serde::Serialize;
+    use smithay::utils::{Logical, Point};
+
+    use super::{super::lock_global, InteractionRegion};
+
+    /// Hovers shorter than this aren't counted
+    pub const HOVER_DWELL: Duration = Duration::from_millis(500);
+    const EXPORT_VERSION: u32 = 1;
+
+    /// Elements participating in interaction metrics, always excluding restricted ones.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum MetricScope {
+        /// Every named element
+        AllNamed,
+        /// Elements with one of these names, see `IcedElement::set_name`
+        Named(Vec<String>),
+    }
+
+    impl MetricScope {
+        fn contains(&self, name: &str) -> bool {
+            match self {
+                MetricScope::AllNamed => true,
+                MetricScope::Named(names) => names.iter().any(|n| n == name),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(in super::super) enum Interaction {
+        /// Pointer motion, counted as a hover once the pointer dwelled in a region
+        Hover,
+        Click,
+        Scroll,
+    }
+
+    #[derive(Debug, Default, Clone, Copy, Serialize)]
+    struct Counts {
+        clicks: u64,
+        hovers: u64,
+        scrolls: u64,
+    }
+
+    #[derive(Default)]
+    struct Metrics {
+        scope: Option<MetricScope>,
+        /// Counts by day (since the unix epoch, UTC), element name and tag
+        counts: BTreeMap<(u64, String, &'static str), Counts>,
+    }
+
+    static ENABLED: AtomicBool = AtomicBool::new(false);
+
+    lazy_static::lazy_static! {
+        static ref METRICS: Mutex<Metrics> = Mutex::new(Metrics::default());
+    }
+
+    /// Starts counting interactions of the elements in `scope`.
+    pub fn enable_interaction_metrics(scope: MetricScope) {
+        lock_global(&METRICS).scope = Some(scope);
+        ENABLED.store(true, Ordering::Release);
+    }
+
+    /// Stops counting, counts gathered so far are kept until exported or cleared.
+    pub fn disable_interaction_metrics() {
+        ENABLED.store(false, Ordering::Release);
+        lock_global(&METRICS).scope = None;
+    }
+
+    pub fn clear_interaction_metrics() {
+        lock_global(&METRICS).counts.clear();
+    }
+
+    /// Fast check for input handlers, before looking at any regions.
+    pub(in super::super) fn is_enabled() -> bool {
+        ENABLED.load(Ordering::Acquire)
+    }
+
+    pub(in super::super) fn in_scope(name: &str) -> bool {
+        lock_global(&METRICS)
+            .scope
+            .as_ref()
+            .map_or(false, |scope| scope.contains(name))
+    }
+
+    fn today() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs() / 86400)
+    }
+
+    pub(in super::super) fn record(element: &str, tag: &'static str, interaction: Interaction) {
+        let mut metrics = lock_global(&METRICS);
+        let counts = metrics
+            .counts
+            .entry((today(), element.to_owned(), tag))
+            .or_default();
+        match interaction {
+            Interaction::Hover => counts.hovers += 1,
+            Interaction::Click => counts.clicks += 1,
+            Interaction::Scroll => counts.scrolls += 1,
+        }
+    }
+
+    /// Tag of the region containing `location`.
+    pub(in super::super) fn tag_at(
+        regions: &[InteractionRegion],
+        location: Point<f64, Logical>,
+    ) -> Option<&'static str> {
+        regions
+            .iter()
+            .find(|region| region.bounds.to_f64().contains(location))
+            .map(|region| region.tag)
+    }
+
+    /// Region the primary pointer of an element is in, for counting hovers.
+    #[derive(Debug, Default)]
+    pub(in super::super) struct HoverDwell {
+        current: Option<(&'static str, Instant)>,
+    }
+
+    impl HoverDwell {
+        /// The pointer moved into `tag` (`None` if outside of all regions or gone),
+        /// returns the tag of a region that was left after dwelling in it.
+        pub fn moved(&mut self, tag: Option<&'static str>) -> Option<&'static str> {
+            if self.current.map(|(current, _)| current) == tag {
+                return None;
+            }
+            let left = self.current.take();
+            self.current = tag.map(|tag| (tag, Instant::now()));
+            left.filter(|(_, since)| since.elapsed() >= HOVER_DWELL)
+                .map(|(tag, _)| tag)
+        }
+    }
+
+    /// Converts days since the unix epoch into a date.
+    fn civil_date(days: u64) -> (i64, u32, u32) {
+        // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days as i64 + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+        let year = yoe + era * 400 + (month <= 2) as i64;
+        (year, month, day)
+    }
+
+    #[derive(Serialize)]
+    struct ExportRegion {
+        tag: &'static str,
+        #[serde(flatten)]
+        counts: Counts,
+    }
+
+    #[derive(Serialize)]
+    struct ExportElement {
+        element: String,
+        regions: Vec<ExportRegion>,
+    }
+
+    #[derive(Serialize)]
+    struct ExportDay {
+        /// `YYYY-MM-DD`, UTC
+        day: String,
+        elements: Vec<ExportElement>,
+    }
+
+    #[derive(Serialize)]
+    struct Export {
+        version: u32,
+        days: Vec<ExportDay>,
+    }
+
+    /// Writes all counts gathered so far as JSON to `path`.
+    pub fn export_interaction_metrics(path: impl AsRef<Path>) -> Result<()> {
+        let mut export = Export {
+            version: EXPORT_VERSION,
+            days: Vec::new(),
+        };
+        for ((day, element, tag), counts) in lock_global(&METRICS).counts.iter() {
+            let (year, month, date) = civil_date(*day);
+            let day = format!("{:04}-{:02}-{:02}", year, month, date);
+            if export.days.last().map_or(true, |d| d.day != day) {
+                export.days.push(ExportDay {
+                    day,
+                    elements: Vec::new(),
+                });
+            }
+            let elements = &mut export.days.last_mut().unwrap().elements;
+            if elements.last().map_or(true, |e| e.element != *element) {
+                elements.push(ExportElement {
+                    element: element.clone(),
+                    regions: Vec::new(),
+                });
+            }
+            elements.last_mut().unwrap().regions.push(ExportRegion {
+                tag,
+                counts: *counts,
+            });
+        }
+
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &export)
+            .context("Failed to write interaction metrics")
+    }
+}
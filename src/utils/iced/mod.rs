@@ -0,0 +1,5389 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt,
+    future::Future,
+    hash::{Hash, Hasher},
+    panic::{catch_unwind, AssertUnwindSafe},
+    rc::Rc,
+    sync::{
+        mpsc::{Receiver, Sender},
+        Arc, Mutex, MutexGuard, PoisonError, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+pub use cosmic::Renderer as IcedRenderer;
+use cosmic::Theme;
+use cosmic::{
+    iced_native::{
+        command::Action,
+        event::Event,
+        futures::Stream,
+        keyboard::{Event as KeyboardEvent, Modifiers as IcedModifiers},
+        mouse::{Button as MouseButton, Event as MouseEvent, ScrollDelta},
+        program::Program as IcedProgram,
+        renderer::Style,
+        widget::operation::{focusable, Operation},
+        window::{Event as WindowEvent, Id},
+        Command, Debug, Point as IcedPoint, Size as IcedSize,
+    },
+    Element,
+};
+use iced_graphics::Primitive;
+use iced_softbuffer::{
+    native::{raqote::DrawTarget, *},
+    Backend,
+};
+
+use ordered_float::OrderedFloat;
+use smithay::{
+    backend::{
+        input::{AxisSource, ButtonState, KeyState, TouchSlot},
+        renderer::{
+            element::{memory::MemoryRenderBufferRenderElement, AsRenderElements},
+            ImportMem, Renderer,
+        },
+    },
+    desktop::space::{RenderZindex, SpaceElement},
+    input::{
+        keyboard::{keysyms, KeyboardTarget, KeysymHandle, ModifiersState},
+        pointer::{AxisFrame, ButtonEvent, MotionEvent, PointerTarget, RelativeMotionEvent},
+        Seat,
+    },
+    output::Output,
+    reexports::calloop::RegistrationToken,
+    reexports::calloop::{
+        self,
+        ping::{make_ping, Ping},
+        timer::{TimeoutAction, Timer},
+        LoopHandle,
+    },
+    utils::{
+        Buffer as BufferCoords, IsAlive, Logical, Physical, Point, Rectangle, Scale, Serial, Size,
+        Transform,
+    },
+};
+use tracing::{debug, error, warn};
+
+use crate::{
+    config::{CosmicConfig, KeyPattern},
+    input::{KeyRepeat, TabletPadTarget, TouchTarget},
+    state::BackendData,
+    utils::prelude::SeatExt,
+};
+
+/// Implements `Program::try_clone_message` for programs, whose `Message` is `Clone`.
+#[macro_export]
+macro_rules! cloneable_message {
+    () => {
+        fn try_clone_message(message: &Self::Message) -> Option<Self::Message> {
+            Some(Clone::clone(message))
+        }
+    };
+}
+
+#[cfg(feature = "accessibility")]
+mod accessibility;
+mod animation;
+mod badge;
+mod blending;
+mod buffer;
+mod builder;
+mod capabilities;
+mod clean;
+mod combinators;
+mod confine;
+#[cfg(feature = "applet-sandbox")]
+mod confinement;
+mod content_state;
+mod critical;
+mod cursor;
+mod decoration;
+mod dismiss;
+mod drag;
+mod focus;
+mod fonts;
+mod frame;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod golden;
+mod hairline;
+mod hit;
+mod hover;
+mod input_method;
+mod intent;
+mod keys;
+mod layers;
+mod metrics;
+mod ordering;
+mod palette;
+mod placement;
+#[cfg(feature = "power-profiles")]
+mod power_profile;
+mod preferences;
+mod press;
+mod pressure;
+mod program_state;
+mod progressive;
+mod prompt;
+#[cfg(feature = "applet-sandbox")]
+mod proxy;
+mod quota;
+mod registry;
+mod render_pool;
+mod requests;
+mod sandbox;
+mod sanitize;
+mod scale;
+mod scratch;
+mod scroll;
+mod shortcuts;
+mod subscription;
+mod telemetry;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod test_helpers;
+#[cfg(test)]
+mod tests;
+mod theme;
+mod touch;
+mod trace;
+mod transition;
+mod truncate;
+mod window_feed;
+pub use self::animation::{Animation, Curve};
+pub use self::badge::{Badge, BadgeKind};
+pub use self::builder::{IcedElementBuilder, IcedElementError};
+pub use self::capabilities::{Capabilities, ProgramCapabilities};
+pub use self::combinators::{
+    Conditional, ConditionalMessage, Either, Overlaid, Split, SplitDirection,
+};
+pub use self::confine::confine_pointer;
+#[cfg(feature = "applet-sandbox")]
+pub use self::confinement::{ConfinementError, WorkerConfinement};
+pub use self::content_state::ContentState;
+pub use self::critical::{Critical, CriticalMessage, FallbackSpec};
+pub use self::cursor::cursor_moved;
+pub use self::decoration::{
+    CosmicXdgDecorationIcedElement, Decorated, TitleBarMessage, TitleBarProgram, WindowAction,
+};
+pub use self::dismiss::AutoDismissPolicy;
+pub use self::drag::{
+    cancel_internal_drag, internal_drag_accepted, internal_drag_icons, is_internal_drag_active,
+    DragIcon, DragIconSpec, DragPayload,
+};
+pub use self::fonts::{FontConfig, FontError};
+pub use self::hit::{hit_index, FastHit, HitIndex};
+pub use self::input_method::{ContentPurpose, InputMethodEvent, InputMethodSurface};
+pub use self::intent::{
+    commit_pending_intents, set_intent_handler, undo_last, DestructiveIntent, IntentEvent, IntentId,
+};
+pub use self::layers::{LayerSpec, UpdateRate};
+pub use self::metrics::InteractionRegion;
+#[cfg(feature = "interaction-metrics")]
+pub use self::metrics::{
+    clear_interaction_metrics, disable_interaction_metrics, enable_interaction_metrics,
+    export_interaction_metrics, MetricScope,
+};
+pub use self::ordering::OrderingCorrection;
+pub use self::palette::{HookPalette, PaletteRole};
+pub use self::placement::{place_transient, Anchor, AvoidSet, PlacementPrefs};
+#[cfg(feature = "power-profiles")]
+pub use self::power_profile::{
+    PowerProfile, PowerProfileEvent, PowerProfileSource, UnknownPowerProfile,
+};
+pub use self::preferences::{AnimationPolicy, Contrast, FormatConfig, Preferences};
+pub use self::pressure::watch_memory_pressure;
+pub use self::progressive::ProgressivePlan;
+pub use self::prompt::{
+    prompt, PromptAction, PromptHandle, PromptMessage, PromptProgram, PromptResult, PromptSpec,
+};
+#[cfg(feature = "applet-sandbox")]
+pub use self::proxy::{
+    run_sandboxed_worker, IcedElementProxy, ProxyMessage, SandboxedProgram, ViewNode,
+};
+pub use self::quota::{AsyncPriority, AsyncQuotaStats};
+pub use self::requests::{RequestMeta, ShellRequest};
+pub use self::sandbox::{SandboxLimits, SandboxViolation, Sandboxed};
+pub use self::sanitize::set_sanitation_markers;
+pub use self::scale::ScaleMode;
+pub use self::scroll::{ScrollPhysics, ScrollRegion, UnmatchedScroll};
+pub use self::shortcuts::{
+    shortcut_registry, ShortcutClaim, ShortcutOwner, ShortcutPriority, ShortcutRegistry,
+};
+pub use self::subscription::Subscription;
+pub use self::telemetry::{init_telemetry_socket, FrameTelemetry};
+pub use self::trace::{Deferral, EventTrace, LatencyStats, TraceStage, TracedInput};
+pub use self::transition::{
+    SlideDirection, SwapKind, SwapSizing, SwapTransition, TransitionKind, TransitionSpec,
+};
+pub use self::truncate::{truncating_text, TruncatePolicy, TruncatingText};
+pub use self::window_feed::{window_feed, FocusedWindowInfo, WindowFeed};
+use self::{
+    blending::TextTiles,
+    buffer::ScaleBuffer,
+    clean::{CachedFrame, CleanFrame},
+    dismiss::AutoDismiss,
+    frame::FrameCallbackTracker,
+    hit::HitSnapshot,
+    hover::{SeatHovers, SeatIndicator},
+    layers::LayerBuffer,
+    ordering::PointerOrdering,
+    press::PressFeedback,
+    program_state::ProgramState,
+    progressive::ProgressiveState,
+    quota::AsyncQuota,
+    registry::{DefaultChanges, RegisteredElement},
+    render_pool::{Content, ContentJob, RenderPool},
+    requests::ShellRequestHandler,
+    sanitize::SanitizeReport,
+    scratch::FrameScratch,
+    scroll::Kinetic,
+    shortcuts::ShortcutTable,
+    touch::TouchPoints,
+    trace::EventTracer,
+    transition::{Phase, ProgramSwap, SpaceTransition, TransitionParams},
+    truncate::TruncatedText,
+};
+
+#[derive(Debug)]
+pub struct IcedElement<P: Program + Send + 'static>(
+    Arc<Mutex<IcedElementInternal<P>>>,
+    Arc<CleanFrame>,
+);
+
+// SAFETY: We cannot really be sure about the widget tree cached by `ProgramState` sadly,
+// but the rest should be fine. Render pool jobs never get the internal, only owned copies of
+// the primitives they draw (see `render_pool`).
+unsafe impl<P: Program + Send + 'static> Send for IcedElementInternal<P> {}
+
+impl<P: Program + Send + 'static> Clone for IcedElement<P> {
+    fn clone(&self) -> Self {
+        IcedElement(self.0.clone(), self.1.clone())
+    }
+}
+
+impl<P: Program + Send + 'static> PartialEq for IcedElement<P> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl<P: Program + Send + 'static> Eq for IcedElement<P> {}
+
+impl<P: Program + Send + 'static> Hash for IcedElement<P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as usize).hash(state)
+    }
+}
+
+/// Locks compositor-wide state of a module, a panic of a previous holder doesn't poison it.
+fn lock_global<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks an element to change it, so its cached frame isn't reused anymore.
+///
+/// Like `lock_global`, a panic of a previous holder (e.g. inside a program) doesn't poison it,
+/// so the element can still be torn down or replaced.
+fn lock<P: Program + Send + 'static>(
+    internal: &Mutex<IcedElementInternal<P>>,
+) -> MutexGuard<'_, IcedElementInternal<P>> {
+    let internal = internal.lock().unwrap_or_else(PoisonError::into_inner);
+    internal.clean.mark_dirty();
+    internal
+}
+
+/// Queues a render of `output`, e.g. to present a frame drawn outside of rendering.
+fn queue_render(handle: &LoopHandle<'static, crate::state::Data>, output: Output) {
+    handle.insert_idle(move |data| {
+        // headless compositors (e.g. in tests) have no backend to render with
+        if !matches!(data.state.backend, BackendData::Unset) {
+            data.state
+                .backend
+                .schedule_render(&data.state.common.event_loop_handle, &output, None);
+        }
+    });
+}
+
+impl<P: Program + Send + 'static> IcedElement<P> {
+    fn from_internal(internal: Arc<Mutex<IcedElementInternal<P>>>) -> IcedElement<P> {
+        let clean = internal.lock().unwrap().clean.clone();
+        IcedElement(internal, clean)
+    }
+
+    /// Locks the element to change it, see `lock`.
+    ///
+    /// Only renders and reads that leave the element as is lock it directly.
+    fn lock(&self) -> MutexGuard<'_, IcedElementInternal<P>> {
+        lock(&self.0)
+    }
+}
+
+/// Context passed to `Program::update_with_context`.
+pub struct UpdateContext<'a> {
+    loop_handle: &'a LoopHandle<'static, crate::state::Data>,
+    requests: &'a mut Vec<ShellRequest>,
+    badges: &'a mut Option<Vec<Badge>>,
+    drag: &'a mut Option<(DragPayload, Option<DragIconSpec>)>,
+    intents: &'a mut Vec<(IntentId, DestructiveIntent, intent::Commit)>,
+    priority: &'a mut Option<AsyncPriority>,
+}
+
+impl<'a> UpdateContext<'a> {
+    pub fn loop_handle(&self) -> &LoopHandle<'static, crate::state::Data> {
+        self.loop_handle
+    }
+
+    /// Asks the compositor to perform a `ShellRequest` once the current update cycle is done.
+    pub fn request(&mut self, request: ShellRequest) {
+        self.requests.push(request);
+    }
+
+    /// Replaces the badges of the element, see `IcedElement::set_badges`.
+    pub fn set_badges(&mut self, badges: Vec<Badge>) {
+        *self.badges = Some(badges);
+    }
+
+    /// Starts a drag to other `IcedElement`s, usually in response to a press and motion.
+    ///
+    /// Drop targets receive the `dnd_*` hooks, if the drag isn't dropped on any target,
+    /// `Program::drag_cancelled` is called.
+    pub fn start_internal_drag(&mut self, payload: DragPayload, icon: Option<DragIconSpec>) {
+        *self.drag = Some((payload, icon));
+    }
+
+    /// Records a destructive action, that is only committed if it isn't undone
+    /// within the undo window of `intent`, see `undo_last`.
+    ///
+    /// The program should apply the action optimistically
+    /// and restore its state in `Program::intent_undone`.
+    pub fn destructive(
+        &mut self,
+        intent: DestructiveIntent,
+        commit: impl FnOnce() + Send + 'static,
+    ) -> IntentId {
+        let id = intent::next_id();
+        self.intents.push((id, intent, Box::new(commit)));
+        id
+    }
+
+    /// Runs `future` like `Command::perform`, but queued with `priority` once the element
+    /// exceeds its async quota, see `IcedElement::set_async_quota`.
+    ///
+    /// Return the command from `update`. Futures of the same update all share the highest
+    /// priority requested during it, plain commands are queued with `AsyncPriority::Normal`.
+    pub fn spawn_prioritized<T, M>(
+        &mut self,
+        priority: AsyncPriority,
+        future: impl Future<Output = T> + Send + 'static,
+        f: impl FnOnce(T) -> M + Send + 'static,
+    ) -> Command<M> {
+        *self.priority = Some(self.priority.map_or(priority, |p| p.max(priority)));
+        Command::perform(future, f)
+    }
+}
+
+/// Delivers messages to an element from any thread, see `IcedElement::message_sender`.
+pub struct MessageSender<Message> {
+    tx: Sender<Message>,
+    wakeup: Ping,
+}
+
+impl<Message> fmt::Debug for MessageSender<Message> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageSender")
+            .field("tx", &self.tx)
+            .field("wakeup", &"...")
+            .finish()
+    }
+}
+
+impl<Message> Clone for MessageSender<Message> {
+    fn clone(&self) -> Self {
+        MessageSender {
+            tx: self.tx.clone(),
+            wakeup: self.wakeup.clone(),
+        }
+    }
+}
+
+impl<Message> MessageSender<Message> {
+    /// Returns false, if the element is gone.
+    pub fn send(&self, message: Message) -> bool {
+        if self.tx.send(message).is_err() {
+            return false;
+        }
+        self.wakeup.ping();
+        true
+    }
+}
+
+pub trait Program {
+    type Message: std::fmt::Debug + Send;
+
+    /// Copies a message for features that need to deliver it more than once or keep it around.
+    ///
+    /// `Message` isn't required to be `Clone`, so this returns `None` by default and these
+    /// features fall back to delivering messages once. Use `cloneable_message!()` to implement
+    /// it for cloneable messages.
+    fn try_clone_message(message: &Self::Message) -> Option<Self::Message> {
+        let _ = message;
+        None
+    }
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        loop_handle: &LoopHandle<'static, crate::state::Data>,
+    ) -> Command<Self::Message> {
+        let _ = (message, loop_handle);
+        Command::none()
+    }
+    /// Like `update`, with access to the element through `ctx`, e.g. to request shell actions.
+    ///
+    /// Calls `update` by default.
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        self.update(message, ctx.loop_handle())
+    }
+    fn view(&self) -> Element<'_, Self::Message>;
+    /// Shows a standard placeholder instead of `view`, while not `Ready`.
+    ///
+    /// Queried after every update, see `ContentState`.
+    fn content_state(&self) -> ContentState {
+        ContentState::Ready
+    }
+
+    fn background(&self, target: &mut DrawTarget<&mut [u32]>) {
+        let _ = target;
+    }
+    fn foreground(&self, target: &mut DrawTarget<&mut [u32]>) {
+        let _ = target;
+    }
+    /// Draws below the widgets, with colors from the element's theme in `palette`.
+    ///
+    /// Calls `background` by default.
+    fn themed_background(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        let _ = palette;
+        self.background(target)
+    }
+    /// Draws above the widgets, see `themed_background`.
+    ///
+    /// Calls `foreground` by default.
+    fn themed_foreground(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        let _ = palette;
+        self.foreground(target)
+    }
+    /// Whether `themed_background` or `themed_foreground` are implemented, so their output depends
+    /// on the theme.
+    ///
+    /// Only elements of programs returning `true` are redrawn, when their theme changes without
+    /// affecting the widgets.
+    fn uses_hooks(&self) -> bool {
+        false
+    }
+    /// Optional features supported beyond those detected from the other hooks,
+    /// see `IcedElement::capabilities`.
+    fn capabilities(&self) -> ProgramCapabilities {
+        ProgramCapabilities::empty()
+    }
+
+    /// Default z-index of the element, unless overriden via `IcedElement::set_z_index`.
+    fn z_index(&self) -> u8 {
+        RenderZindex::Shell as u8
+    }
+
+    /// cosmic-config id and version of the configuration this program reads.
+    fn config_id(&self) -> Option<(&'static str, u64)> {
+        None
+    }
+    /// Keys of the configuration passed to `on_config_changed` on reload
+    /// and whenever the user changes them.
+    fn config_keys(&self) -> &'static [&'static str] {
+        &[]
+    }
+    fn on_config_changed(&self, key: &str, value: Option<ron::Value>) -> Option<Self::Message> {
+        let _ = (key, value);
+        None
+    }
+
+    /// Called whenever the refresh rate of the fastest output the element is on changes.
+    fn refresh_changed(&mut self, info: &RefreshInfo) -> Option<Self::Message> {
+        let _ = info;
+        None
+    }
+
+    /// Event sources the program currently wants to receive messages from.
+    ///
+    /// Queried after every update, subscriptions are started and stopped
+    /// as they appear in or vanish from the returned list.
+    fn subscriptions(&self) -> Vec<Subscription<Self::Message>> {
+        Vec::new()
+    }
+
+    /// Key combinations the program currently handles, even without keyboard focus.
+    ///
+    /// Queried after every update, see `ShortcutRegistry` for how conflicts are resolved.
+    fn shortcuts(&self) -> Vec<(KeyPattern, Self::Message)> {
+        Vec::new()
+    }
+    /// Priority of the program's `shortcuts`, activated elements use at least `Focused`.
+    fn shortcut_priority(&self) -> ShortcutPriority {
+        ShortcutPriority::Ambient
+    }
+
+    /// Interval to redraw the element at, for programs polling their state (e.g. clocks).
+    ///
+    /// Queried once the element is created and after every tick, `None` stops the polling.
+    fn update_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Highest rate the element is redrawn at, `None` redraws with every frame of its outputs.
+    ///
+    /// In between, the previous buffer is shown.
+    fn max_fps(&self) -> Option<u32> {
+        None
+    }
+
+    /// Low-level drawing into the element's buffer, called after `foreground`.
+    ///
+    /// `size` is the size of `target`, `scale` the scale it is rendered at. Like the other hooks
+    /// changing the program, e.g. `idle`, it may keep caches across frames.
+    fn custom_render(
+        &mut self,
+        target: &mut DrawTarget<&mut [u32]>,
+        size: Size<i32, Physical>,
+        scale: f64,
+    ) {
+        let _ = (target, size, scale);
+    }
+    /// Skips drawing the widgets returned by `view`, leaving the buffer to `background`,
+    /// `foreground` and `custom_render`. Input is still handled by the widgets.
+    fn custom_render_only(&self) -> bool {
+        false
+    }
+
+    /// How the element is rasterized on outputs with fractional scales.
+    fn scale_mode(&self) -> ScaleMode {
+        ScaleMode::Exact
+    }
+
+    /// Called once the element wasn't interacted with or rendered for the duration
+    /// set via `IcedElement::set_idle_notify`.
+    ///
+    /// Expensive internal state may be dropped here and rebuilt in `resumed`.
+    fn idle(&mut self) -> Option<Self::Message> {
+        None
+    }
+    /// Called on the first interaction or render after `idle`, before the triggering event is processed.
+    fn resumed(&mut self) -> Option<Self::Message> {
+        None
+    }
+
+    /// Scrollable areas, wheel events are delivered at the exact position of the pointer
+    /// at the time of the event, so it always scrolls the region it is over.
+    fn scroll_regions(&self) -> Vec<ScrollRegion> {
+        Vec::new()
+    }
+    /// Routing of wheel events outside of all `scroll_regions`.
+    fn unmatched_scroll(&self) -> UnmatchedScroll {
+        UnmatchedScroll::Pass
+    }
+    /// How wheel events of `source` are delivered, e.g. to scroll by lines for touchpads too.
+    fn configure_scroll_physics(&self, source: AxisSource) -> ScrollPhysics {
+        ScrollPhysics::for_source(source)
+    }
+
+    /// Interactive areas, e.g. buttons, that show a pressed tint right away when pressed,
+    /// until the program drew its own pressed state or the press was released.
+    fn press_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        Vec::new()
+    }
+    /// Tagged areas counted by interaction metrics, see `metrics`. Untagged areas are never
+    /// counted.
+    fn interaction_regions(&self) -> Vec<InteractionRegion> {
+        Vec::new()
+    }
+    /// Ids of password `text_input`s, e.g. of the lock screen or authentication prompts.
+    ///
+    /// While one of them has focus, however it got there, key input stays out of the element's
+    /// optional capture facilities, see `IcedElement::is_secure_entry_active`. Programs should
+    /// keep the typed value in a `zeroize::Zeroizing<String>`, the element keeps no copy of it.
+    fn secure_fields(&self) -> Vec<cosmic::iced_native::widget::Id> {
+        Vec::new()
+    }
+    /// Areas accepting pointer input, e.g. to let clicks on transparent corners or shadows pass
+    /// through to the windows below. `None` accepts input on the whole element.
+    fn input_region(&self) -> Option<Vec<Rectangle<i32, Logical>>> {
+        None
+    }
+    /// Allows the element to draw optimistic feedback for `press_regions`.
+    fn optimistic_feedback(&self) -> bool {
+        true
+    }
+
+    /// Parts of the element rendered into their own buffers, stacked above the rest
+    /// in declaration order. Purely a rendering optimization, input isn't affected.
+    ///
+    /// `background`, `foreground`, `custom_render` and badges always end up in the base layer.
+    /// Layers are only redrawn, when their primitives changed.
+    fn layers(&self) -> Vec<LayerSpec> {
+        Vec::new()
+    }
+
+    /// Whether a drag within the program (e.g. reordering a list) is in progress.
+    ///
+    /// Polled on pointer motion to end `IcedElement::confine_pointer_during_drag`.
+    fn is_drag_active(&self) -> bool {
+        false
+    }
+
+    /// Whether the element is a drop target for internal drags, see `UpdateContext::start_internal_drag`.
+    ///
+    /// Drop targets are only found on outputs the element's offset is known for,
+    /// see `IcedElement::set_location`.
+    fn accepts_drops(&self) -> bool {
+        false
+    }
+    fn dnd_enter(
+        &self,
+        payload: &DragPayload,
+        position: Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        let _ = (payload, position);
+        None
+    }
+    /// Mime types accepted for drops at `position`, most preferred first, `"*"` accepts any.
+    ///
+    /// Queried on every motion of a drag over the element, see `internal_drag_accepted`.
+    fn dnd_mime_types(&self, position: Point<f64, Logical>) -> Vec<String> {
+        let _ = position;
+        vec![String::from("*")]
+    }
+    fn dnd_motion(&self, position: Point<f64, Logical>) -> Option<Self::Message> {
+        let _ = position;
+        None
+    }
+    fn dnd_leave(&self) -> Option<Self::Message> {
+        None
+    }
+    fn dnd_drop(
+        &self,
+        payload: &DragPayload,
+        position: Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        let _ = (payload, position);
+        None
+    }
+    /// Called on the source of an internal drag, that was cancelled or not dropped on any target.
+    fn drag_cancelled(&self, payload: &DragPayload) -> Option<Self::Message> {
+        let _ = payload;
+        None
+    }
+
+    /// Widget to focus right after the first layout, before any input is delivered.
+    ///
+    /// Applied again once the program was replaced.
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        None
+    }
+
+    /// Loads the content in stages, for programs whose full first layout is expensive.
+    ///
+    /// Queried once the element is created or its program replaced, see `ProgressivePlan`.
+    fn progressive(&self) -> Option<ProgressivePlan> {
+        None
+    }
+    /// Called once `stage` of the `progressive` plan should be loaded, starting at 1
+    /// as the first stage is shown right away.
+    fn progressive_stage(&self, stage: u32) -> Option<Self::Message> {
+        let _ = stage;
+        None
+    }
+
+    /// Whether the program contains editable text, that needs an input method while focused.
+    fn wants_input_method(&self) -> bool {
+        false
+    }
+
+    /// Called when a destructive intent recorded via `UpdateContext::destructive` was undone,
+    /// to restore the state from before the action.
+    fn intent_undone(&self, intent: IntentId) -> Option<Self::Message> {
+        let _ = intent;
+        None
+    }
+
+    /// Called when the fonts of the element changed, see `IcedElement::set_fonts`.
+    ///
+    /// Also called after creation, if the default fonts were changed from the backend's defaults.
+    /// Iced's text widgets don't pick these up on their own, so widgets have to use them explicitly.
+    fn fonts_changed(&self, fonts: &FontConfig) -> Option<Self::Message> {
+        let _ = fonts;
+        None
+    }
+
+    /// Called when the desktop's preferences changed, see `set_default_text_scale` and friends.
+    ///
+    /// Also called after creation, if they were changed from the defaults. Several preferences
+    /// changed at once (see `begin_global_changes`) are passed in a single call.
+    fn preferences_changed(&self, preferences: &Preferences) -> Option<Self::Message> {
+        let _ = preferences;
+        None
+    }
+
+    /// Called for hardware buttons of a tablet pad, while the element has keyboard focus.
+    ///
+    /// The view is rebuilt afterwards, like after `update`.
+    fn on_pad_button(&mut self, button: u32, state: ButtonState) {
+        let _ = (button, state);
+    }
+    /// Called for ring movements of a tablet pad, `degrees` is the absolute position of the ring.
+    fn on_pad_ring(&mut self, source: RingEventSource, degrees: f64) {
+        let _ = (source, degrees);
+    }
+    /// Called for strip movements of a tablet pad, `position` is normalized to `0.0..=1.0`.
+    fn on_pad_strip(&mut self, source: StripEventSource, position: f64) {
+        let _ = (source, position);
+    }
+}
+
+/// Changes applied atomically by `IcedElement::reconfigure`.
+#[derive(Debug, Clone, Default)]
+pub struct Reconfigure {
+    pub size: Option<Size<i32, Logical>>,
+    /// Scales to keep buffers for, e.g. the new scales of the outputs the element is shown on.
+    /// Buffers for virtual targets are always kept.
+    pub scales: Option<Vec<f64>>,
+}
+
+/// Widget focused once an element is created, see `Program::initial_focus`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FocusTarget {
+    Widget(cosmic::iced_native::widget::Id),
+    FirstFocusable,
+}
+
+/// Identifies a virtual output (e.g. a screencast of a region), that has no `Output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VirtualTargetId(pub u64);
+
+#[derive(Debug, Clone)]
+struct VirtualTarget {
+    id: VirtualTargetId,
+    /// Area of the virtual output in the global compositor space
+    geometry: Rectangle<i32, Logical>,
+    scale: f64,
+}
+
+/// Source of a tablet pad ring event, as reported by libinput.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RingEventSource {
+    Unknown,
+    Finger,
+}
+
+/// Source of a tablet pad strip event, as reported by libinput.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StripEventSource {
+    Unknown,
+    Finger,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemoryPressureLevel {
+    /// Releases memory reused across frames
+    Moderate,
+    /// Additionally releases the buffers of all scales but the active output's
+    Critical,
+}
+
+/// Failure to rasterize an element, its buffer keeps the previous frame.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum RenderError {
+    #[error("Buffer of {actual} bytes is too small for {expected} bytes")]
+    BufferTooSmall { expected: usize, actual: usize },
+    /// Drawing panicked, e.g. raqote drawing out of bounds
+    #[error("Rasterizing panicked: {0}")]
+    Panicked(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshInfo {
+    /// Refresh interval of the output's current mode
+    pub interval: Duration,
+    /// If adaptive sync is enabled, `interval` is only the lower bound between frames
+    pub vrr: bool,
+}
+
+impl RefreshInfo {
+    pub fn for_output(output: &Output, vrr: bool) -> Option<RefreshInfo> {
+        let refresh = output.current_mode()?.refresh;
+        (refresh > 0).then(|| RefreshInfo {
+            interval: Duration::from_nanos(1_000_000_000_000 / refresh as u64),
+            vrr,
+        })
+    }
+}
+
+struct ProgramWrapper<P: Program>(
+    P,
+    LoopHandle<'static, crate::state::Data>,
+    RefCell<Vec<ShellRequest>>,
+    RefCell<Option<Vec<Badge>>>,
+    RefCell<Option<(DragPayload, Option<DragIconSpec>)>>,
+    RefCell<Vec<(IntentId, DestructiveIntent, intent::Commit)>>,
+    RefCell<Option<AsyncPriority>>,
+);
+impl<P: Program> IcedProgram for ProgramWrapper<P> {
+    type Message = <P as Program>::Message;
+    type Renderer = IcedRenderer;
+
+    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        let mut ctx = UpdateContext {
+            loop_handle: &self.1,
+            requests: self.2.get_mut(),
+            badges: self.3.get_mut(),
+            drag: self.4.get_mut(),
+            intents: self.5.get_mut(),
+            priority: self.6.get_mut(),
+        };
+        self.0.update_with_context(message, &mut ctx)
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        content_state::placeholder(&self.0.content_state()).unwrap_or_else(|| self.0.view())
+    }
+}
+
+struct IcedElementInternal<P: Program + Send + 'static> {
+    // draw buffer
+    outputs: Vec<Output>,
+    refresh_info: Vec<(Output, RefreshInfo)>,
+    buffers: HashMap<OrderedFloat<f64>, ScaleBuffer>,
+    frame_tracker: FrameCallbackTracker,
+    upload_failures: u32,
+    badges: Vec<Badge>,
+    double_buffered: bool,
+    buffer_age: u8,
+    scratch: FrameScratch,
+    layer_buffers: HashMap<(u32, OrderedFloat<f64>), LayerBuffer>,
+    last_inconsistency: Option<Instant>,
+    /// Primitives dropped or clamped in the last drawn frame, see `sanitize`
+    sanitized: u32,
+    last_sanitation_warning: Option<Instant>,
+    last_render_error: Option<Instant>,
+    /// Time the update before the current render took, for telemetry
+    render_update_duration: Duration,
+    #[cfg(feature = "interaction-metrics")]
+    hover_dwell: metrics::HoverDwell,
+    /// Texts truncated by the last layout pass, see `full_text_at`
+    truncated_texts: Vec<TruncatedText>,
+
+    // state
+    size: Size<i32, Logical>,
+    cursor_pos: Option<Point<f64, Logical>>,
+    pointer_ordering: PointerOrdering,
+    seat_hovers: SeatHovers,
+    active_output: Option<Output>,
+    /// Global location, as last set by the shell via `IcedElement::set_location`
+    location: Option<Point<i32, Logical>>,
+    output_offsets: Vec<(Output, Point<i32, Logical>)>,
+    hit: Arc<HitSnapshot>,
+    /// `Program::input_region` as of the last update
+    input_region: Option<Vec<Rectangle<i32, Logical>>>,
+    /// Internal drag started by the program, see `UpdateContext::start_internal_drag`
+    drag: Option<drag::ActiveDrag>,
+    /// Reserved area, if placed via `IcedElement::place_transient`
+    transient_reservation: Option<u64>,
+    activated: bool,
+    /// See `shortcuts::next_mapping`, `None` while not on any output
+    mapped_at: Option<u64>,
+    virtual_targets: Vec<VirtualTarget>,
+    z_index: Option<u8>,
+    linear_blending: bool,
+    hairline_snapping: bool,
+    input_method_active: bool,
+    /// Last modifiers received via `KeyboardTarget::modifiers`
+    modifiers: IcedModifiers,
+    /// Raw keysyms of the keys pressed while focused, released once focus leaves
+    held_keys: HashSet<u32>,
+    /// Raw keysym of the repeating key and its timer
+    key_repeat: Option<(u32, RegistrationToken)>,
+    touch: TouchPoints,
+    /// A field of `Program::secure_fields` has focus
+    secure_entry: bool,
+    /// Focused widget, as of the last update of `secure_entry`
+    secure_focus: Option<focus::FocusedWidget>,
+    press_feedback: Option<PressFeedback>,
+    enter_transition: Option<TransitionSpec>,
+    exit_transition: Option<TransitionSpec>,
+    transition: Option<SpaceTransition>,
+    swap: Option<ProgramSwap>,
+    fonts: FontConfig,
+    /// Set via `IcedElement::set_fonts`, ignoring the default fonts
+    fonts_overridden: bool,
+    preferences: Preferences,
+
+    // shell requests
+    name: Option<String>,
+    restricted: bool,
+    last_serial: Option<Serial>,
+    request_handler: Option<ShellRequestHandler>,
+
+    // iced
+    theme: Theme,
+    /// Set via `IcedElement::set_theme`, instead of following the default theme
+    theme_overridden: bool,
+    /// `theme` for program hooks
+    palette: HookPalette,
+    renderer: IcedRenderer,
+    state: ProgramState<ProgramWrapper<P>>,
+    debug: Debug,
+
+    // futures
+    handle: LoopHandle<'static, crate::state::Data>,
+    futures: AsyncQuota<<P as Program>::Message>,
+    executor_token: Option<RegistrationToken>,
+    rx: Receiver<<P as Program>::Message>,
+    tx: Sender<<P as Program>::Message>,
+    /// Wakes the element up, once messages arrived via `rx`
+    wakeup: Ping,
+    wakeup_token: Option<RegistrationToken>,
+    update_pending: bool,
+    deferred_update: Option<RegistrationToken>,
+    /// Updates not yet caught up with, see `IcedElementInternal::sync_program`
+    sync_pending: bool,
+    /// The program reacted since the view was last hashed
+    view_outdated: bool,
+    /// Last frame, reused while the element isn't changed, shared with `IcedElement`
+    clean: Arc<CleanFrame>,
+
+    // subscriptions
+    self_ref: Weak<Mutex<IcedElementInternal<P>>>,
+    subscriptions: Vec<(u64, Option<RegistrationToken>)>,
+
+    // idle notification
+    last_activity: Instant,
+    idle_after: Option<Duration>,
+    idle_timer: Option<RegistrationToken>,
+    /// Source of the last axis frame, which only the first frame of a scroll may report
+    last_axis_source: Option<AxisSource>,
+    kinetic: Kinetic,
+    kinetic_timer: Option<RegistrationToken>,
+    is_idle: bool,
+
+    update_timer: Option<RegistrationToken>,
+    /// Watches the program's `config_id`
+    config_watcher: Option<RegistrationToken>,
+    /// State shown by the last update, see `Program::content_state`
+    content_state: ContentState,
+    /// Animates the loading indicator
+    content_timer: Option<RegistrationToken>,
+    auto_dismiss: Option<AutoDismiss>,
+    dismiss_timer: Option<RegistrationToken>,
+
+    render_pool: Option<RenderPool>,
+    /// A job of `render_pool` draws the content of the next frame
+    render_job_pending: bool,
+
+    // set for `Sandboxed` programs
+    sandbox: Option<SandboxLimits>,
+    draw_throttled_until: Option<Instant>,
+
+    /// Last draw per scale, for `Program::max_fps`
+    last_rendered_at: HashMap<OrderedFloat<f64>, Instant>,
+    /// Scales whose buffers were released under memory pressure, recreated once rendered again
+    released_scales: Vec<OrderedFloat<f64>>,
+    /// Stages still to load, see `Program::progressive`
+    progressive: Option<ProgressiveState>,
+    /// Refreshed once per frame after updates, see `capabilities::detect`
+    program_capabilities: ProgramCapabilities,
+    /// Mismatches already warned about, see `IcedElement::require`
+    capability_warnings: HashSet<(&'static str, Capabilities)>,
+    traces: EventTracer,
+    /// Hash of the primitives of the last update, see `IcedElementInternal::view_changed`
+    view_hash: Option<u64>,
+    /// Updates not redrawn, as their view was identical to the previous one
+    redundant_updates: u64,
+    /// Sources attached to the element, see `IcedElement::attach_stream`
+    attached_sources: Vec<RegistrationToken>,
+
+    // accessibility
+    #[cfg(feature = "accessibility")]
+    atspi: Option<accessibility::AtspiBridge>,
+    #[cfg(feature = "accessibility")]
+    focused_widget: Option<focus::FocusedWidget>,
+}
+
+/// Consecutive failed uploads after which rasterizing again is given up on
+const MAX_UPLOAD_FAILURES: u32 = 3;
+/// Minimum interval between logs of inconsistent element state in release builds
+const INCONSISTENCY_LOG_INTERVAL: Duration = Duration::from_secs(10);
+const SANITATION_LOG_INTERVAL: Duration = Duration::from_secs(10);
+const RENDER_ERROR_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Counts a failed upload of an element's buffer, returns whether to rasterize it again.
+///
+/// After `MAX_UPLOAD_FAILURES` consecutive failures only the upload is retried.
+fn upload_failed(failures: &mut u32, scale: f64, err: &impl fmt::Debug) -> bool {
+    *failures += 1;
+    if *failures < MAX_UPLOAD_FAILURES {
+        warn!(?err, scale, "Failed to upload element buffer, retrying");
+        return true;
+    }
+    if *failures == MAX_UPLOAD_FAILURES {
+        error!(
+            ?err,
+            scale, "Failed to upload element buffer repeatedly, element will not be shown"
+        );
+    }
+    false
+}
+
+/// Quiet period after the last input event before a deferred update is run
+const DEFERRED_UPDATE_DELAY: Duration = Duration::from_millis(8);
+
+impl<P: Program + Send + 'static> fmt::Debug for IcedElementInternal<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IcedElementInternal")
+            .field("buffers", &"...")
+            .field("double_buffered", &self.double_buffered)
+            .field("buffer_age", &self.buffer_age)
+            .field("dragging", &self.drag.is_some())
+            .field("scratch", &"...")
+            .field("layer_buffers", &"...")
+            .field("last_inconsistency", &self.last_inconsistency)
+            .field("frame_tracker", &self.frame_tracker)
+            .field("upload_failures", &self.upload_failures)
+            .field("badges", &self.badges)
+            .field("refresh_info", &self.refresh_info)
+            .field("size", &self.size)
+            .field("cursor_pos", &self.cursor_pos)
+            .field("pointer_ordering", &self.pointer_ordering)
+            .field("active_output", &self.active_output)
+            .field("location", &self.location)
+            .field("output_offsets", &self.output_offsets)
+            .field("hit", &self.hit)
+            .field("transient_reservation", &self.transient_reservation)
+            .field("virtual_targets", &self.virtual_targets)
+            .field("z_index", &self.z_index)
+            .field("linear_blending", &self.linear_blending)
+            .field("hairline_snapping", &self.hairline_snapping)
+            .field("input_method_active", &self.input_method_active)
+            .field("modifiers", &self.modifiers)
+            .field(
+                "held_keys",
+                if self.secure_entry {
+                    &"<redacted>" as &dyn fmt::Debug
+                } else {
+                    &self.held_keys
+                },
+            )
+            .field(
+                "key_repeat",
+                if self.secure_entry {
+                    &"<redacted>" as &dyn fmt::Debug
+                } else {
+                    &self.key_repeat
+                },
+            )
+            .field("touch", &self.touch)
+            .field("secure_entry", &self.secure_entry)
+            .field("press_feedback", &self.press_feedback)
+            .field("enter_transition", &self.enter_transition)
+            .field("exit_transition", &self.exit_transition)
+            .field("transition", &self.transition)
+            .field("swap", &self.swap)
+            .field("fonts", &self.fonts)
+            .field("fonts_overridden", &self.fonts_overridden)
+            .field("preferences", &self.preferences)
+            .field("name", &self.name)
+            .field("restricted", &self.restricted)
+            .field("last_serial", &self.last_serial)
+            .field("request_handler", &self.request_handler.is_some())
+            .field("theme", &self.theme)
+            .field("theme_overridden", &self.theme_overridden)
+            .field("renderer", &"...")
+            .field("state", &"...")
+            .field("debug", &self.debug)
+            .field("handle", &self.handle)
+            .field("futures", &self.futures)
+            .field("executor_token", &self.executor_token)
+            .field("rx", &self.rx)
+            .field("tx", &self.tx)
+            .field("wakeup_token", &self.wakeup_token)
+            .field("update_pending", &self.update_pending)
+            .field("deferred_update", &self.deferred_update)
+            .field("sync_pending", &self.sync_pending)
+            .field("subscriptions", &self.subscriptions)
+            .field("last_activity", &self.last_activity)
+            .field("idle_after", &self.idle_after)
+            .field("idle_timer", &self.idle_timer)
+            .field("is_idle", &self.is_idle)
+            .field("update_timer", &self.update_timer)
+            .field("content_state", &self.content_state)
+            .field("content_timer", &self.content_timer)
+            .field("auto_dismiss", &self.auto_dismiss)
+            .field("dismiss_timer", &self.dismiss_timer)
+            .field("render_pool", &self.render_pool)
+            .field("render_job_pending", &self.render_job_pending)
+            .field("sandbox", &self.sandbox)
+            .field("draw_throttled_until", &self.draw_throttled_until)
+            .field("last_rendered_at", &self.last_rendered_at)
+            .field("capabilities", &self.capabilities())
+            .field("input_latency", &self.traces.latency())
+            .field("attached_sources", &self.attached_sources)
+            .finish()
+    }
+}
+
+impl<P: Program + Send + 'static> Drop for IcedElementInternal<P> {
+    fn drop(&mut self) {
+        self.futures.close();
+        if let Some(token) = self.executor_token.take() {
+            self.handle.remove(token);
+        }
+        if let Some(token) = self.deferred_update.take() {
+            self.handle.remove(token);
+        }
+        if let Some(token) = self.idle_timer.take() {
+            self.handle.remove(token);
+        }
+        if let Some(token) = self.kinetic_timer.take() {
+            self.handle.remove(token);
+        }
+        if let Some(token) = self.update_timer.take() {
+            self.handle.remove(token);
+        }
+        if let Some(token) = self.config_watcher.take() {
+            self.handle.remove(token);
+        }
+        if let Some(token) = self.content_timer.take() {
+            self.handle.remove(token);
+        }
+        if let Some(token) = self.dismiss_timer.take() {
+            self.handle.remove(token);
+        }
+        if let Some((_, token)) = self.key_repeat.take() {
+            self.handle.remove(token);
+        }
+        if let Some(token) = self.wakeup_token.take() {
+            self.handle.remove(token);
+        }
+        if let Some(pool) = self.render_pool.take() {
+            self.handle.remove(pool.token);
+        }
+        for (_, token) in self.subscriptions.drain(..) {
+            if let Some(token) = token {
+                self.handle.remove(token);
+            }
+        }
+        self.hit.kill();
+        hit::hit_index().remove(&self.hit);
+        if let Some(id) = self.transient_reservation.take() {
+            placement::release(id);
+        }
+        shortcuts::shortcut_registry().remove(self.self_ref.as_ptr() as usize);
+        if let Some(active) = self.drag.take() {
+            drag::cancel(active, None);
+        }
+        for token in self.attached_sources.drain(..) {
+            self.handle.remove(token);
+        }
+    }
+}
+
+impl<P: Program + Send + 'static> IcedElement<P> {
+    pub fn new(
+        program: P,
+        size: impl Into<Size<i32, Logical>>,
+        handle: LoopHandle<'static, crate::state::Data>,
+    ) -> IcedElement<P> {
+        let size = size.into();
+        let mut renderer = IcedRenderer::new(Backend::new());
+        let mut debug = Debug::new();
+
+        let state = ProgramState::new(
+            ProgramWrapper(
+                program,
+                handle.clone(),
+                RefCell::new(Vec::new()),
+                RefCell::new(None),
+                RefCell::new(None),
+                RefCell::new(Vec::new()),
+                RefCell::new(None),
+            ),
+            IcedSize::new(size.w as f32, size.h as f32),
+            &mut renderer,
+            &mut debug,
+        );
+
+        let (executor, scheduler) = calloop::futures::executor().expect("Out of file descriptors");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (wakeup, wakeup_source) = make_ping().expect("Out of file descriptors");
+        let executor_token = {
+            let (tx, wakeup) = (tx.clone(), wakeup.clone());
+            handle
+                .insert_source(executor, move |message, _, _| {
+                    let _ = tx.send(message);
+                    wakeup.ping();
+                })
+                .ok()
+        };
+
+        let mut internal = IcedElementInternal {
+            outputs: Vec::new(),
+            refresh_info: Vec::new(),
+            buffers: HashMap::new(),
+            double_buffered: false,
+            buffer_age: 1,
+            scratch: FrameScratch::default(),
+            layer_buffers: HashMap::new(),
+            last_inconsistency: None,
+            sanitized: 0,
+            last_sanitation_warning: None,
+            last_render_error: None,
+            render_update_duration: Duration::ZERO,
+            #[cfg(feature = "interaction-metrics")]
+            hover_dwell: metrics::HoverDwell::default(),
+            truncated_texts: Vec::new(),
+            frame_tracker: FrameCallbackTracker::default(),
+            upload_failures: 0,
+            badges: Vec::new(),
+            size,
+            cursor_pos: None,
+            pointer_ordering: PointerOrdering::default(),
+            seat_hovers: SeatHovers::default(),
+            active_output: None,
+            location: None,
+            output_offsets: Vec::new(),
+            hit: HitSnapshot::new(size),
+            input_region: None,
+            drag: None,
+            transient_reservation: None,
+            activated: false,
+            mapped_at: None,
+            virtual_targets: Vec::new(),
+            z_index: None,
+            linear_blending: false,
+            hairline_snapping: true,
+            input_method_active: false,
+            modifiers: IcedModifiers::empty(),
+            held_keys: HashSet::new(),
+            key_repeat: None,
+            touch: TouchPoints::default(),
+            secure_entry: false,
+            secure_focus: None,
+            press_feedback: None,
+            enter_transition: None,
+            exit_transition: None,
+            transition: None,
+            swap: None,
+            fonts: fonts::default_fonts(),
+            fonts_overridden: false,
+            preferences: preferences::default_preferences(),
+            name: None,
+            restricted: false,
+            last_serial: None,
+            request_handler: None,
+            palette: HookPalette::new(&theme::default_theme()),
+            theme: theme::default_theme(),
+            theme_overridden: false,
+            renderer,
+            state,
+            debug,
+            handle,
+            futures: AsyncQuota::new(scheduler),
+            executor_token,
+            rx,
+            tx,
+            wakeup,
+            wakeup_token: None,
+            update_pending: false,
+            deferred_update: None,
+            sync_pending: false,
+            view_outdated: false,
+            clean: Arc::default(),
+            self_ref: Weak::new(),
+            subscriptions: Vec::new(),
+            last_activity: Instant::now(),
+            idle_after: None,
+            idle_timer: None,
+            last_axis_source: None,
+            kinetic: Kinetic::default(),
+            kinetic_timer: None,
+            is_idle: false,
+            update_timer: None,
+            config_watcher: None,
+            content_state: ContentState::Ready,
+            content_timer: None,
+            auto_dismiss: None,
+            dismiss_timer: None,
+            render_pool: None,
+            render_job_pending: false,
+            sandbox: None,
+            draw_throttled_until: None,
+            last_rendered_at: HashMap::new(),
+            released_scales: Vec::new(),
+            progressive: None,
+            program_capabilities: ProgramCapabilities::empty(),
+            capability_warnings: HashSet::new(),
+            traces: EventTracer::default(),
+            view_hash: None,
+            redundant_updates: 0,
+            attached_sources: Vec::new(),
+            #[cfg(feature = "accessibility")]
+            atspi: None,
+            #[cfg(feature = "accessibility")]
+            focused_widget: None,
+        };
+        let _ = internal.update(true);
+        if internal.fonts != FontConfig::default() {
+            let fonts = internal.fonts.clone();
+            internal.dispatch_hook(|program| program.fonts_changed(&fonts));
+        }
+        if internal.preferences != Preferences::default() {
+            let preferences = internal.preferences.clone();
+            internal.dispatch_hook(|program| program.preferences_changed(&preferences));
+        }
+        internal.apply_initial_focus();
+
+        let internal = Arc::new(Mutex::new(internal));
+        {
+            let mut internal_ref = internal.lock().unwrap();
+            internal_ref.self_ref = Arc::downgrade(&internal);
+            internal_ref.sync_subscriptions();
+            internal_ref.sync_config_watcher();
+            internal_ref.sync_shortcuts();
+            internal_ref.start_progressive();
+            internal_ref.sync_content_state();
+
+            let element = Arc::downgrade(&internal);
+            match internal_ref
+                .handle
+                .insert_source(wakeup_source, move |_, _, _| {
+                    if let Some(internal) = element.upgrade() {
+                        let _ = lock(&internal).update(false);
+                    }
+                }) {
+                Ok(token) => internal_ref.wakeup_token = Some(token),
+                Err(err) => warn!(?err, "Failed to register wakeup of element"),
+            }
+        }
+        registry::register(Arc::downgrade(&internal) as Weak<dyn RegisteredElement>);
+        let hit = internal.lock().unwrap().hit.clone();
+        hit::hit_index().insert(Arc::as_ptr(&internal) as usize, hit);
+        let element = IcedElement::from_internal(internal);
+        element.start_update_timer();
+        element
+    }
+
+    fn start_update_timer(&self) {
+        let mut internal = self.lock();
+        let Some(interval) = internal.state.program().0.update_interval() else { return };
+
+        let element = Arc::downgrade(&self.0);
+        match internal
+            .handle
+            .insert_source(Timer::from_duration(interval), move |_, _, _| {
+                let Some(internal) = element.upgrade() else { return TimeoutAction::Drop };
+                let element = IcedElement::from_internal(internal);
+                element.force_update();
+
+                let mut internal = element.lock();
+                match internal.state.program().0.update_interval() {
+                    Some(interval) => TimeoutAction::ToDuration(interval),
+                    None => {
+                        internal.update_timer = None;
+                        TimeoutAction::Drop
+                    }
+                }
+            }) {
+            Ok(token) => internal.update_timer = Some(token),
+            Err(err) => warn!(?err, "Failed to schedule periodic updates"),
+        }
+    }
+
+    /// Creates an element, whose primitives are rasterized on `pool` instead of the main thread.
+    ///
+    /// Rendering presents the previous frame right away, while the pool draws the next one.
+    /// Once the job finished, the element draws its hooks and overlays on top and queues a render
+    /// of its outputs to present it. Elements with layers are drawn on the main thread.
+    pub fn new_with_pool(
+        program: P,
+        size: impl Into<Size<i32, Logical>>,
+        handle: LoopHandle<'static, crate::state::Data>,
+        pool: Arc<rayon::ThreadPool>,
+    ) -> IcedElement<P> {
+        let element = IcedElement::new(program, size, handle.clone());
+        let (done, finished) = calloop::channel::channel();
+        let weak = Arc::downgrade(&element.0);
+        match handle.insert_source(finished, move |event, _, _| {
+            let calloop::channel::Event::Msg(content) = event else { return };
+            if let Some(internal) = weak.upgrade() {
+                lock(&internal).finish_pool_draw(content);
+            }
+        }) {
+            Ok(token) => element.lock().render_pool = Some(RenderPool::new(pool, done, token)),
+            Err(err) => warn!(?err, "Failed to register render pool of element"),
+        }
+        element
+    }
+
+    /// Replaces the program, keeping the element's size, outputs and settings.
+    pub fn replace_program(&self, program: P) {
+        let mut internal = self.lock();
+        internal.swap = None;
+        internal.replace_program(program);
+        for buffer in internal.buffers.values_mut() {
+            buffer.invalidate();
+        }
+    }
+
+    /// Replaces the program like `replace_program`, transitioning from the last frame of the
+    /// old program to the new one.
+    ///
+    /// The old frame is kept until the transition completed, so the new program is only
+    /// rasterized once. Input goes to the new program right away. Resize the element afterwards
+    /// to fit the new program, `SwapTransition::sizing` decides how the old frame adapts.
+    pub fn replace_program_with_transition(&self, program: P, transition: SwapTransition) {
+        let mut internal = self.lock();
+        internal.transition_from_current(transition);
+        internal.replace_program(program);
+    }
+
+    pub fn with_program<R>(&self, func: impl FnOnce(&P) -> R) -> R {
+        let internal = self.0.lock().unwrap();
+        func(&internal.state.program().0)
+    }
+
+    pub fn loop_handle(&self) -> LoopHandle<'static, crate::state::Data> {
+        self.0.lock().unwrap().handle.clone()
+    }
+
+    pub fn resize(&self, size: Size<i32, Logical>) {
+        self.reconfigure(Reconfigure {
+            size: Some(size),
+            ..Default::default()
+        });
+    }
+
+    /// Applies multiple changes at once, so no frame is ever rendered with only some of them applied.
+    pub fn reconfigure(&self, changes: Reconfigure) {
+        let mut internal = self.lock();
+        internal.reconfigure(changes);
+        internal.repair_inconsistencies();
+    }
+
+    fn with_program_mut(&self, f: impl FnOnce(&mut P)) {
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        f(&mut internal.state.program_mut().0);
+        let _ = internal.update(true);
+    }
+
+    /// Presses a finger of `slot` at `location`, relative to the element.
+    ///
+    /// smithay has no touch focus yet, so touch input is delivered through `TouchTarget`
+    /// by the code hit testing the touch point.
+    ///
+    /// Like with iced's own shells, the cursor follows the finger, widgets like buttons check
+    /// the cursor position once the finger is lifted.
+    pub fn touch_down(&self, slot: TouchSlot, location: Point<f64, Logical>) {
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        internal.reset_dismiss(|policy| policy.reset_on_interaction);
+        internal.cursor_pos = Some(location);
+        for event in internal.touch.down(slot, location) {
+            internal.state.queue_event(Event::Touch(event));
+        }
+        let _ = internal.update(true);
+    }
+
+    /// Moves the finger of `slot` to `location`, relative to the element, e.g. to pan scrollables.
+    pub fn touch_motion(&self, slot: TouchSlot, location: Point<f64, Logical>) {
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        let Some(event) = internal.touch.motion(slot, location) else { return };
+        internal.cursor_pos = Some(location);
+        internal.state.queue_event(Event::Touch(event));
+        self.defer_update(&mut internal);
+    }
+
+    pub fn touch_up(&self, slot: TouchSlot) {
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        let Some(event) = internal.touch.up(slot) else { return };
+        internal.state.queue_event(Event::Touch(event));
+        let _ = internal.update(true);
+    }
+
+    /// Loses all fingers, e.g. once the compositor took over a gesture,
+    /// so no widget stays pressed.
+    pub fn touch_cancel(&self) {
+        let mut internal = self.lock();
+        let events = internal.touch.cancel();
+        if events.is_empty() {
+            return;
+        }
+        for event in events {
+            internal.state.queue_event(Event::Touch(event));
+        }
+        let _ = internal.update(true);
+    }
+
+    fn dispatch_hook(&self, hook: impl FnOnce(&P) -> Option<P::Message>) {
+        self.lock().dispatch_hook(hook);
+    }
+
+    /// Returns a handle to deliver messages from other threads, e.g. from tokio tasks.
+    ///
+    /// Sending wakes the element up right away, instead of waiting for its next update.
+    pub fn message_sender(&self) -> MessageSender<P::Message> {
+        let internal = self.0.lock().unwrap();
+        MessageSender {
+            tx: internal.tx.clone(),
+            wakeup: internal.wakeup.clone(),
+        }
+    }
+
+    /// Delivers a message to the program, as if it was produced by the program itself.
+    pub fn queue_message(&self, message: P::Message) {
+        let mut internal = self.lock();
+        internal.state.queue_message(message);
+        // messages to shown elements arrive in bursts (e.g. from other elements), lay them out once
+        if internal.outputs.is_empty() {
+            let _ = internal.update(true);
+        } else {
+            internal.schedule_update();
+        }
+    }
+
+    /// Overrides the theme of the element, instead of following the default theme.
+    pub fn set_theme(&self, theme: Theme) {
+        let mut internal = self.lock();
+        internal.theme_overridden = true;
+        // widgets take their colors from the theme as well, not only the hooks
+        for buffer in internal.buffers.values_mut() {
+            buffer.mark_dirty();
+        }
+        internal.apply_theme(theme);
+    }
+
+    /// Follows the default theme again (see `set_default_theme`), undoing `set_theme`.
+    ///
+    /// Only redraws, if the element looks different with the default theme.
+    pub fn refresh_theme(&self) {
+        let mut internal = self.lock();
+        internal.theme_overridden = false;
+        internal.follow_default_theme();
+    }
+
+    /// Delivers the active power profile and its changes to the program, until the element
+    /// is dropped.
+    #[cfg(feature = "power-profiles")]
+    pub fn attach_power_profile_source(
+        &self,
+        source: &PowerProfileSource,
+        map: impl Fn(PowerProfileEvent) -> P::Message + Send + Sync + 'static,
+    ) {
+        use cosmic::iced_native::futures::StreamExt;
+        self.attach_stream(source.events().map(map));
+    }
+
+    /// Forwards every item of `stream` as a message, for as long as the element lives.
+    #[cfg_attr(not(feature = "power-profiles"), allow(dead_code))]
+    fn attach_stream(&self, stream: impl Stream<Item = P::Message> + Send + 'static) {
+        use cosmic::iced_native::futures::StreamExt;
+
+        let (executor, scheduler) = match calloop::futures::executor::<()>() {
+            Ok(executor) => executor,
+            Err(err) => {
+                warn!(?err, "Failed to attach stream");
+                return;
+            }
+        };
+        let element = Arc::downgrade(&self.0);
+        let mut stream = stream.boxed();
+        let _ = scheduler.schedule(async move {
+            while let Some(message) = stream.next().await {
+                match element.upgrade() {
+                    Some(internal) => IcedElement(internal).queue_message(message),
+                    None => break,
+                }
+            }
+        });
+        let mut internal = self.0.lock().unwrap();
+        let token = internal
+            .handle
+            .insert_source(executor, |_, _, _| {})
+            .map_err(|err| warn!(?err.error, "Failed to attach stream"))
+            .ok();
+        internal.attached_sources.extend(token);
+    }
+
+    /// Exports the element as an AT-SPI2 accessible at `path` of `connection`, announcing
+    /// focus moving between its widgets to screen readers.
+    ///
+    /// The accessible is unexported once the element is dropped. Registering again replaces
+    /// the previous accessible.
+    #[cfg(feature = "accessibility")]
+    pub fn register_atspi(
+        &self,
+        connection: &zbus::Connection,
+        path: &zbus::zvariant::ObjectPath<'_>,
+    ) -> zbus::Result<()> {
+        let mut internal = self.0.lock().unwrap();
+        let name = internal.name.clone().unwrap_or_default();
+        internal.atspi = Some(accessibility::AtspiBridge::new(connection, path, name)?);
+        // announce the current focus to the new accessible
+        internal.focused_widget = None;
+        internal.sync_focus();
+        Ok(())
+    }
+
+    pub fn force_update(&self) {
+        let mut internal = self.lock();
+        for buffer in internal.buffers.values_mut() {
+            buffer.mark_dirty();
+        }
+        internal.update(true);
+    }
+
+    /// Full text of a `truncating_text` at `location` (relative to the element), if it was
+    /// truncated, e.g. for a tooltip.
+    pub fn full_text_at(&self, location: Point<f64, Logical>) -> Option<String> {
+        let internal = self.0.lock().unwrap();
+        internal
+            .truncated_texts
+            .iter()
+            .find(|text| text.bounds.to_f64().contains(location))
+            .map(|text| text.full.clone())
+    }
+
+    /// Sets the name reported alongside `ShellRequest`s of this element.
+    pub fn set_name(&self, name: impl Into<String>) {
+        let mut internal = self.lock();
+        internal.name = Some(name.into());
+        internal.sync_shortcuts();
+    }
+
+    /// Restricted elements may only issue `ShellRequest`s allowed by
+    /// `ShellRequest::allowed_when_restricted`.
+    ///
+    /// Their `Program::shortcuts` are ignored.
+    pub fn set_restricted(&self, restricted: bool) {
+        let mut internal = self.lock();
+        internal.restricted = restricted;
+        internal.sync_shortcuts();
+    }
+
+    /// Delivers the focused window of `window_feed()` to the program, mapped by `map`.
+    ///
+    /// The current value is delivered right away, followed by one message per distinct change.
+    /// Restricted elements only receive `FocusedWindowInfo::redacted`.
+    /// The binding ends, when the element is dropped.
+    pub fn bind_window_feed(
+        &self,
+        map: impl Fn(&FocusedWindowInfo) -> P::Message + Send + Sync + 'static,
+    ) {
+        let element = Arc::downgrade(&self.0);
+        let last = Mutex::new(None::<FocusedWindowInfo>);
+        window_feed().bind(Arc::new(move |info| {
+            let Some(element) = element.upgrade() else { return false };
+            let mut internal = lock(&element);
+            let info = if internal.restricted {
+                info.redacted()
+            } else {
+                info.clone()
+            };
+            let mut last = last.lock().unwrap();
+            if last.as_ref() != Some(&info) {
+                internal.state.queue_message(map(&info));
+                let _ = internal.update(true);
+                *last = Some(info);
+            }
+            true
+        }));
+    }
+
+    /// Limits how many futures of the program's commands may run at once, further ones are queued
+    /// up to `max_queued`, dropping the oldest queued ones beyond that.
+    ///
+    /// Defaults to 64 running and 256 queued futures.
+    pub fn set_async_quota(&self, max_concurrent: usize, max_queued: usize) {
+        let internal = self.0.lock().unwrap();
+        internal
+            .futures
+            .set_limits(max_concurrent, max_queued, internal.name.as_deref());
+    }
+
+    /// Running, queued and dropped futures of the program, see `set_async_quota`.
+    pub fn async_stats(&self) -> AsyncQuotaStats {
+        self.0.lock().unwrap().futures.stats()
+    }
+
+    /// Updates that weren't redrawn, because the program's view didn't change.
+    pub fn redundant_updates(&self) -> u64 {
+        self.0.lock().unwrap().redundant_updates
+    }
+
+    /// Up to `n` of the most recent input events shown by the element, oldest first.
+    ///
+    /// Each trace has the time the event was queued, updated, rasterized and shown,
+    /// see `trace` for how stages are attributed.
+    pub fn trace_recent(&self, n: usize) -> Vec<EventTrace> {
+        self.0.lock().unwrap().traces.recent(n)
+    }
+
+    /// Whether a password field (see `Program::secure_fields`) has focus.
+    pub fn is_secure_entry_active(&self) -> bool {
+        self.0.lock().unwrap().secure_entry
+    }
+
+    /// Content purpose of the focused field, to be passed on to input methods, so composing
+    /// engines neither show nor learn from passwords.
+    pub fn input_method_purpose(&self) -> ContentPurpose {
+        if self.0.lock().unwrap().secure_entry {
+            ContentPurpose::Password
+        } else {
+            ContentPurpose::Normal
+        }
+    }
+
+    /// Distribution of the input-to-import latency of the recently shown input events.
+    pub fn input_latency(&self) -> LatencyStats {
+        self.0.lock().unwrap().traces.latency()
+    }
+
+    /// Optional features of the element, from its program and configuration.
+    ///
+    /// Always reflects the current state, call sites should check this instead of relying on
+    /// features silently doing nothing.
+    pub fn capabilities(&self) -> Capabilities {
+        self.0.lock().unwrap().capabilities()
+    }
+
+    /// Whether the element has all `required` capabilities, for call sites that can't work
+    /// without them and fall back otherwise.
+    ///
+    /// Missing capabilities are logged once per `consumer` (e.g. `"modal stack"`) and mismatch.
+    pub fn require(&self, required: Capabilities, consumer: &'static str) -> bool {
+        self.0.lock().unwrap().require(required, consumer)
+    }
+
+    /// Registers the handler receiving `ShellRequest`s emitted by the program.
+    ///
+    /// The handler is called while the element is locked, so it must not call back into it.
+    pub fn set_shell_request_handler(
+        &self,
+        handler: impl FnMut(ShellRequest, RequestMeta) + Send + 'static,
+    ) {
+        self.lock().request_handler = Some(Box::new(handler));
+    }
+
+    /// Overrides the z-index declared by the program.
+    pub fn set_z_index(&self, z: u8) {
+        self.lock().z_index = Some(z);
+    }
+
+    /// Restores the z-index declared by the program.
+    pub fn reset_z_index(&self) {
+        self.lock().z_index = None;
+    }
+
+    /// Updates the refresh information of an output, the element is shown on.
+    pub fn set_refresh_info(&self, output: &Output, info: RefreshInfo) {
+        self.lock().set_refresh_info(output, info);
+    }
+
+    /// Refresh information of the fastest output the element is currently on.
+    pub fn refresh_info(&self) -> Option<RefreshInfo> {
+        self.0.lock().unwrap().fastest_refresh()
+    }
+
+    /// Re-reads all watched configuration keys and passes them to the program.
+    ///
+    /// Values set by the user take precedence over the system defaults, see `CosmicConfig`.
+    pub fn reload_config(&self) {
+        self.lock().reload_config();
+    }
+
+    /// Enables gamma-correct blending of the program's content onto its background.
+    ///
+    /// This is considerably more expensive and thus disabled by default.
+    pub fn set_linear_blending(&self, linear: bool) {
+        let mut internal = self.lock();
+        if internal.linear_blending == linear {
+            return;
+        }
+        internal.linear_blending = linear;
+        for buffer in internal.buffers.values_mut() {
+            buffer.mark_dirty();
+        }
+    }
+
+    /// Overrides the fonts of the element, instead of following the default fonts.
+    ///
+    /// Fails and keeps the previous fonts, if a family isn't installed.
+    pub fn set_fonts(&self, fonts: FontConfig) -> Result<(), FontError> {
+        let mut internal = self.lock();
+        if let Err(err) = fonts::resolve(&fonts) {
+            warn!(?err, element = ?internal.name, "Failed to set fonts");
+            return Err(err);
+        }
+        internal.fonts_overridden = true;
+        internal.apply_fonts(fonts);
+        Ok(())
+    }
+
+    /// Follows the default fonts again, undoing `set_fonts`.
+    pub fn reset_fonts(&self) {
+        let mut internal = self.lock();
+        internal.fonts_overridden = false;
+        internal.apply_fonts(fonts::default_fonts());
+    }
+
+    pub fn fonts(&self) -> FontConfig {
+        self.0.lock().unwrap().fonts.clone()
+    }
+
+    /// Snaps separators and borders of up to one logical pixel to physical pixels.
+    ///
+    /// Only affects drawing, enabled by default.
+    pub fn set_hairline_snapping(&self, snapping: bool) {
+        let mut internal = self.lock();
+        if internal.hairline_snapping == snapping {
+            return;
+        }
+        internal.hairline_snapping = snapping;
+        for buffer in internal.buffers.values_mut() {
+            buffer.mark_dirty();
+        }
+    }
+
+    /// Sets the global location of the element, done by the shell whenever it is mapped or moved.
+    ///
+    /// The offsets on the outputs the element is on are derived from it,
+    /// replacing offsets set via `set_output_offset`.
+    pub fn set_location(&self, location: Point<i32, Logical>) {
+        let offsets = {
+            let internal = self.0.lock().unwrap();
+            let offsets = internal
+                .outputs
+                .iter()
+                .map(|output| (output.clone(), location - output.current_location()))
+                .collect::<Vec<_>>();
+            if internal.location == Some(location) && internal.output_offsets == offsets {
+                return;
+            }
+            offsets
+        };
+        let mut internal = self.lock();
+        internal.location = Some(location);
+        internal.output_offsets = offsets;
+        internal.hit.set_offsets(&internal.output_offsets);
+    }
+
+    /// Sets the location of the element relative to the given output.
+    ///
+    /// Needed for elements mapped at different locations on multiple outputs (e.g. spanning panels),
+    /// so pointer positions can be translated relative to the mapping on the output the pointer is on.
+    pub fn set_output_offset(&self, output: &Output, offset: Point<i32, Logical>) {
+        let mut internal = self.lock();
+        internal.output_offsets.retain(|(o, _)| o != output);
+        internal.output_offsets.push((output.clone(), offset));
+        internal.hit.set_offsets(&internal.output_offsets);
+    }
+
+    /// Places the element as a transient, reserving its area until it is dropped
+    /// or `release_transient` is called. Returns the location to map it at.
+    ///
+    /// Placing it again replaces its previous reservation.
+    pub fn place_transient(&self, prefs: &PlacementPrefs) -> Point<i32, Logical> {
+        let mut internal = self.lock();
+        if let Some(id) = internal.transient_reservation.take() {
+            placement::release(id);
+        }
+        let (id, location) = placement::reserve(internal.size, prefs);
+        internal.transient_reservation = Some(id);
+        location
+    }
+
+    /// Releases the area reserved by `place_transient`, e.g. once the element is unmapped.
+    pub fn release_transient(&self) {
+        if let Some(id) = self.lock().transient_reservation.take() {
+            placement::release(id);
+        }
+    }
+
+    /// Hit test of a `point` relative to the element, without locking it.
+    ///
+    /// Meant for hot input paths, `MaybeInside` requires the precise `is_in_input_region`.
+    pub fn fast_hit(&self, point: Point<f64, Logical>) -> FastHit {
+        hit::hit_index().hit_element(Arc::as_ptr(&self.0) as usize, point)
+    }
+
+    /// Sets the transitions played when the element enters or leaves a space.
+    ///
+    /// The enter transition starts automatically once the element enters its first output.
+    pub fn set_space_transition(
+        &self,
+        enter: Option<TransitionSpec>,
+        exit: Option<TransitionSpec>,
+    ) {
+        let mut internal = self.lock();
+        internal.enter_transition = enter;
+        internal.exit_transition = exit;
+    }
+
+    /// Starts the enter transition, smoothly reversing a running exit transition.
+    ///
+    /// `direction` overrides the direction of slide transitions, e.g. to match a workspace switch.
+    pub fn begin_enter_transition(&self, direction: Option<SlideDirection>) {
+        let mut internal = self.lock();
+        let Some(spec) = internal.enter_transition else { return };
+        internal.start_transition(Phase::Enter, spec, direction, None);
+    }
+
+    /// Dismisses the element once `policy.after` passed without it being cleared or replaced,
+    /// e.g. for OSDs and toasts.
+    ///
+    /// Dismissing runs the exit transition and calls `on_dismissed` once the element may be
+    /// unmapped, like `begin_exit_transition`.
+    pub fn set_auto_dismiss(
+        &self,
+        policy: AutoDismissPolicy,
+        on_dismissed: impl FnOnce() + Send + 'static,
+    ) {
+        let mut internal = self.lock();
+        let now = Instant::now();
+        let mut dismiss = AutoDismiss::new(policy, Box::new(on_dismissed), now);
+        dismiss.set_hovered(internal.pointer_ordering.is_hovered(), now);
+        internal.auto_dismiss = Some(dismiss);
+        internal.arm_dismiss_timer();
+    }
+
+    pub fn clear_auto_dismiss(&self) {
+        let mut internal = self.lock();
+        internal.auto_dismiss = None;
+        internal.arm_dismiss_timer();
+    }
+
+    /// Starts the exit transition, smoothly reversing a running enter transition.
+    ///
+    /// `on_complete` is called from the event loop once the transition finished
+    /// and the element may be unmapped, or right away without an exit transition.
+    pub fn begin_exit_transition(
+        &self,
+        direction: Option<SlideDirection>,
+        on_complete: impl FnOnce() + Send + 'static,
+    ) {
+        let mut internal = self.lock();
+        // closed manually, the lifetime doesn't matter anymore
+        internal.auto_dismiss = None;
+        internal.arm_dismiss_timer();
+        match internal.exit_transition {
+            Some(spec) => {
+                internal.start_transition(Phase::Exit, spec, direction, Some(Box::new(on_complete)))
+            }
+            None => {
+                drop(internal);
+                on_complete();
+            }
+        }
+    }
+
+    /// Drives the running transition externally instead of by its duration,
+    /// e.g. by the progress of a workspace switch. `1.0` completes the transition.
+    pub fn set_transition_progress(&self, progress: f32) {
+        let mut internal = self.lock();
+        let Some(transition) = internal.transition.as_mut() else { return };
+        transition.set_progress(progress);
+        internal.finish_transition();
+    }
+
+    /// Bounding box of the element in coordinates local to `output`.
+    ///
+    /// Uses the offset on `output`, if set via `set_output_offset`, otherwise the global location
+    /// minus the location of `output`. Elements never located are at the global origin.
+    pub fn bounding_box_in_output(&self, output: &Output) -> Rectangle<i32, Logical> {
+        let internal = self.0.lock().unwrap();
+        let offset = internal
+            .output_offsets
+            .iter()
+            .find(|(o, _)| o == output)
+            .map(|(_, offset)| *offset)
+            .unwrap_or_else(|| internal.location.unwrap_or_default() - output.geometry().loc);
+        Rectangle::from_loc_and_size(offset, internal.size)
+    }
+
+    pub fn active_output(&self) -> Option<Output> {
+        self.0.lock().unwrap().active_output.clone()
+    }
+
+    /// Last loaded stage of the program's `ProgressivePlan`, `None` once all stages are loaded.
+    pub fn progressive_stage(&self) -> Option<u32> {
+        self.0
+            .lock()
+            .unwrap()
+            .progressive
+            .as_ref()
+            .map(|progressive| progressive.stage)
+    }
+
+    /// Keeps the pointer of `seat` within the element, until the program reports
+    /// the drag as ended via `Program::is_drag_active`.
+    ///
+    /// Returns false, if the element has no location on the active output of `seat`.
+    pub fn confine_pointer_during_drag(&self, seat: &Seat<crate::state::State>) -> bool {
+        let output = seat.active_output();
+        if !self
+            .0
+            .lock()
+            .unwrap()
+            .output_offsets
+            .iter()
+            .any(|(o, _)| *o == output)
+        {
+            return false;
+        }
+
+        let element = Arc::downgrade(&self.0);
+        confine::set(
+            seat,
+            Box::new(move || {
+                let internal = element.upgrade()?;
+                let internal = internal.lock().unwrap();
+                if !internal.state.program().0.is_drag_active() {
+                    return None;
+                }
+                let (_, offset) = internal.output_offsets.iter().find(|(o, _)| *o == output)?;
+                Some(Rectangle::from_loc_and_size(
+                    (output.current_location() + *offset).to_f64(),
+                    internal.size.to_f64(),
+                ))
+            }),
+        );
+        true
+    }
+
+    /// Provides the last known position of `seat`, relative to the element.
+    ///
+    /// Used instead of the seat's pointer position, if a button or axis event of `seat`
+    /// arrives before its enter, see `set_ordering_correction`.
+    pub fn pointer_position_hint(
+        &self,
+        seat: &Seat<crate::state::State>,
+        position: Point<f64, Logical>,
+    ) {
+        let mut internal = self.lock();
+        internal.pointer_ordering.hint(seat.id(), position);
+    }
+
+    /// How pointer events arriving without a preceding enter are corrected.
+    pub fn set_ordering_correction(&self, correction: OrderingCorrection) {
+        self.lock().pointer_ordering.mode = correction;
+    }
+
+    /// Number of pointer events, that arrived without a preceding enter and were corrected.
+    pub fn ordering_corrections(&self) -> u64 {
+        self.0.lock().unwrap().pointer_ordering.corrections
+    }
+
+    /// Whether an input method should currently be enabled for the element,
+    /// i.e. it has keyboard focus and the program `wants_input_method`.
+    pub fn input_method_active(&self) -> bool {
+        self.0.lock().unwrap().input_method_active
+    }
+
+    /// Registers a virtual output the element is shown on.
+    ///
+    /// Virtual targets get their own buffer, if their scale differs from all outputs,
+    /// but never route any input to the element.
+    pub fn add_virtual_target(
+        &self,
+        target: VirtualTargetId,
+        geometry: Rectangle<i32, Logical>,
+        scale: f64,
+    ) {
+        let mut internal = self.lock();
+        internal.virtual_targets.retain(|t| t.id != target);
+        internal.virtual_targets.push(VirtualTarget {
+            id: target,
+            geometry,
+            scale,
+        });
+        internal.refresh_buffers();
+        internal.repair_inconsistencies();
+    }
+
+    pub fn remove_virtual_target(&self, target: VirtualTargetId) {
+        let mut internal = self.lock();
+        internal.virtual_targets.retain(|t| t.id != target);
+        internal.refresh_buffers();
+        internal.repair_inconsistencies();
+    }
+
+    /// Renders the element for a virtual target.
+    ///
+    /// `location` is the position of the element in the global compositor space,
+    /// the returned elements are positioned relative to the virtual output.
+    pub fn render_for_virtual<R>(
+        &self,
+        renderer: &mut R,
+        target: VirtualTargetId,
+        location: Point<i32, Logical>,
+    ) -> Vec<MemoryRenderBufferRenderElement<R>>
+    where
+        R: Renderer + ImportMem,
+        <R as Renderer>::TextureId: 'static,
+    {
+        let mut internal = self.0.lock().unwrap();
+        if !internal.require(Capabilities::VIRTUAL_TARGETS, "virtual render") {
+            return Vec::new();
+        }
+        let Some((geometry, scale)) = internal
+            .virtual_targets
+            .iter()
+            .find(|t| t.id == target)
+            .map(|t| (t.geometry, t.scale))
+        else { return Vec::new() };
+        drop(internal);
+
+        AsRenderElements::<R>::render_elements(
+            self,
+            renderer,
+            (location - geometry.loc).to_physical_precise_round(scale),
+            Scale::from(scale),
+            1.0,
+        )
+    }
+
+    /// Rasterizes the element for a virtual target into CPU memory, e.g. for screencasts.
+    ///
+    /// Returns the buffer size and its ARGB8888 (premultiplied) pixels row by row,
+    /// `None` if `target` isn't registered via `add_virtual_target` or rasterizing failed.
+    pub fn capture_virtual(
+        &self,
+        target: VirtualTargetId,
+    ) -> Option<(Size<i32, BufferCoords>, Vec<u32>)> {
+        let mut internal = self.lock();
+        if !internal.require(Capabilities::VIRTUAL_TARGETS, "virtual capture") {
+            return None;
+        }
+        let scale = internal
+            .virtual_targets
+            .iter()
+            .find(|t| t.id == target)?
+            .scale;
+        let _ = internal.update(false);
+        let size = buffer::buffer_size(internal.size, scale);
+        let mut pixels = vec![0u32; (size.w.max(0) * size.h.max(0)) as usize];
+        if !pixels.is_empty() {
+            let result = internal.rasterizer().rasterize(
+                bytemuck::cast_slice_mut(&mut pixels),
+                size,
+                scale,
+                None,
+            );
+            if let Err(err) = result {
+                internal.log_render_error(&err);
+                return None;
+            }
+        }
+        Some((size, pixels))
+    }
+
+    /// Sets the attention badges drawn above the program's content.
+    ///
+    /// Changing badges only damages the areas of the old and new badges.
+    pub fn set_badges(&self, badges: Vec<Badge>) {
+        self.lock().set_badges(badges);
+    }
+
+    /// Notifies the program via `Program::idle` once the element wasn't interacted with
+    /// or rendered for `after`. `None` disables idle notifications.
+    pub fn set_idle_notify(&self, after: Option<Duration>) {
+        let mut internal = self.lock();
+        internal.idle_after = after;
+        if let Some(token) = internal.idle_timer.take() {
+            internal.handle.remove(token);
+        }
+        if after.is_some() {
+            self.arm_idle_timer(&mut internal);
+        }
+    }
+
+    /// Pins the seat iced's cursor follows, other seats only get hover indicators and can't
+    /// interact with the element. `None` lets the seat that pressed a button last be primary again.
+    pub fn set_primary_seat(&self, seat: Option<&Seat<crate::state::State>>) {
+        let mut internal = self.lock();
+        internal.seat_hovers.pin(false);
+        if let Some(seat) = seat {
+            let fallback = internal.cursor_pos.unwrap_or_default();
+            let location = internal.cursor_position(seat, fallback);
+            internal.route_seat(seat, location, true);
+            internal.seat_hovers.pin(true);
+            let _ = internal.update(true);
+        }
+    }
+
+    /// Forces double buffering for all scales.
+    ///
+    /// By default only buffers shared by multiple outputs are double buffered.
+    pub fn set_double_buffered(&self, double_buffered: bool) {
+        let mut internal = self.lock();
+        internal.double_buffered = double_buffered;
+        internal.update_double_buffering();
+        internal.repair_inconsistencies();
+    }
+
+    /// Sets the number of buffers per scale (1 to 3), recreating the buffers.
+    ///
+    /// With more buffers, a redraw doesn't have to wait for outputs still reading the
+    /// previous frame, while only the damage since a buffer's last draw is rasterized and uploaded.
+    /// Buffers shared by multiple outputs or `set_double_buffered` always use at least two.
+    pub fn set_buffer_age(&self, age: u8) {
+        if !(1..=buffer::MAX_BUFFER_AGE).contains(&age) {
+            warn!(
+                age,
+                "Invalid buffer age, expected 1 to {}",
+                buffer::MAX_BUFFER_AGE
+            );
+            return;
+        }
+        let mut internal = self.lock();
+        internal.buffer_age = age;
+        internal.update_double_buffering();
+        internal.repair_inconsistencies();
+    }
+
+    /// Renders the element without it being mapped into any `Space`.
+    ///
+    /// Elements not living in a `Space` never receive `output_enter`,
+    /// so the buffer for the requested scale is created on demand.
+    pub fn render_standalone<R>(
+        &self,
+        renderer: &mut R,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        alpha: f32,
+    ) -> Vec<MemoryRenderBufferRenderElement<R>>
+    where
+        R: Renderer + ImportMem,
+        <R as Renderer>::TextureId: 'static,
+    {
+        {
+            let mut internal = self.lock();
+            let buffer_size = buffer::buffer_size(internal.size, scale.x);
+            internal
+                .buffers
+                .entry(OrderedFloat(scale.x))
+                .or_insert_with(|| ScaleBuffer::new(buffer_size));
+            internal.repair_inconsistencies();
+        }
+        AsRenderElements::<R>::render_elements(self, renderer, location, scale, alpha)
+    }
+}
+
+impl<P: Program + Send + 'static> IcedElement<P> {
+    fn registered(&self) -> Arc<dyn RegisteredElement> {
+        self.0.clone()
+    }
+
+    /// Moves the active drag of the element to the global `location`,
+    /// returns false if there is none.
+    fn drag_motion(&self, location: Point<f64, Logical>) -> bool {
+        let Some(mut active) = self.lock().drag.take() else { return false };
+        // hooks may reach this element, so it must not be locked meanwhile
+        drag::motion(&mut active, location);
+        let mut internal = self.lock();
+        if internal.drag.is_none() {
+            internal.drag = Some(active);
+        }
+        true
+    }
+
+    /// Drops the active drag of the element at the global `location`,
+    /// returns false if there is none.
+    fn drag_drop(&self, location: Point<f64, Logical>) -> bool {
+        let Some(active) = self.lock().drag.take() else { return false };
+        drag::drop_at(active, Some(self.registered()), location);
+        true
+    }
+
+    /// Cancels the active drag of the element, returns false if there is none.
+    fn drag_cancel(&self) -> bool {
+        let Some(active) = self.lock().drag.take() else { return false };
+        drag::cancel(active, Some(self.registered()));
+        true
+    }
+
+    /// Records an interaction or render, resuming the program if it was idle.
+    fn mark_active(&self, internal: &mut IcedElementInternal<P>) {
+        internal.last_activity = Instant::now();
+        if internal.is_idle {
+            internal.is_idle = false;
+            if let Some(message) = internal.state.program_mut().0.resumed() {
+                internal.state.queue_message(message);
+                // process before any pending event
+                let _ = internal.update(true);
+            }
+        }
+        if internal.idle_after.is_some() && internal.idle_timer.is_none() {
+            self.arm_idle_timer(internal);
+        }
+    }
+
+    fn arm_idle_timer(&self, internal: &mut IcedElementInternal<P>) {
+        let Some(after) = internal.idle_after else { return };
+        let deadline = internal.last_activity + after;
+
+        let element = Arc::downgrade(&self.0);
+        match internal
+            .handle
+            .insert_source(Timer::from_deadline(deadline), move |_, _, _| {
+                let Some(internal) = element.upgrade() else { return TimeoutAction::Drop };
+                let mut internal = lock(&internal);
+                let Some(after) = internal.idle_after else {
+                    internal.idle_timer = None;
+                    return TimeoutAction::Drop;
+                };
+
+                let idle_for = internal.last_activity.elapsed();
+                if idle_for < after {
+                    return TimeoutAction::ToDuration(after - idle_for);
+                }
+
+                // rearmed by the next activity
+                internal.idle_timer = None;
+                if !internal.is_idle {
+                    internal.is_idle = true;
+                    if let Some(message) = internal.state.program_mut().0.idle() {
+                        internal.state.queue_message(message);
+                        let _ = internal.update(true);
+                    }
+                }
+                TimeoutAction::Drop
+            }) {
+            Ok(token) => internal.idle_timer = Some(token),
+            Err(err) => warn!(?err, "Failed to schedule idle notification"),
+        }
+    }
+
+    /// Schedules an update once no further input arrived for `DEFERRED_UPDATE_DELAY`.
+    ///
+    /// Used by high-frequency input handlers to coalesce bursts of events into a single update.
+    /// Every call resets the timer, any immediate update in the meantime cancels it.
+    fn defer_update(&self, internal: &mut IcedElementInternal<P>) {
+        if let Some(token) = internal.deferred_update.take() {
+            internal.handle.remove(token);
+        }
+
+        let element = Arc::downgrade(&self.0);
+        match internal.handle.insert_source(
+            Timer::from_duration(DEFERRED_UPDATE_DELAY),
+            move |_, _, _| {
+                if let Some(internal) = element.upgrade() {
+                    let mut internal = lock(&internal);
+                    internal.deferred_update = None;
+                    if internal.update_pending {
+                        let _ = internal.update(true);
+                    }
+                }
+                TimeoutAction::Drop
+            },
+        ) {
+            Ok(token) => {
+                internal.update_pending = true;
+                internal.deferred_update = Some(token);
+                internal.traces.update_deferred(Deferral::Coalesced);
+            }
+            Err(err) => {
+                warn!(?err, "Failed to schedule deferred update");
+                let _ = internal.update(true);
+            }
+        }
+    }
+}
+
+impl<P: Program + Send + 'static> RegisteredElement for Mutex<IcedElementInternal<P>> {
+    fn reload_config(&self) {
+        lock(self).reload_config();
+    }
+
+    fn set_refresh_info(&self, output: &Output, info: RefreshInfo) {
+        let mut internal = lock(self);
+        if internal.outputs.contains(output) {
+            internal.set_refresh_info(output, info);
+        }
+    }
+
+    fn frame_done(&self, output: &Output) {
+        // called every frame, the element is only marked dirty if anything was deferred
+        let mut internal = self.lock().unwrap_or_else(PoisonError::into_inner);
+        if internal.outputs.contains(output) {
+            let _ = internal.frame_done(Some(output));
+        }
+    }
+
+    fn drop_target_at(&self, location: Point<f64, Logical>) -> Option<Point<f64, Logical>> {
+        let mut internal = self.lock().unwrap();
+        if !internal.capabilities().contains(Capabilities::DROP_TARGET)
+            || !internal.require(Capabilities::OUTPUT_OFFSETS, "dnd coordinator")
+        {
+            return None;
+        }
+        internal.output_offsets.iter().find_map(|(output, offset)| {
+            let origin = (output.current_location() + *offset).to_f64();
+            Rectangle::from_loc_and_size(origin, internal.size.to_f64())
+                .contains(location)
+                .then(|| location - origin)
+        })
+    }
+
+    fn dnd_enter(&self, payload: &DragPayload, location: Point<f64, Logical>) {
+        lock(self).dispatch_hook(|program| program.dnd_enter(payload, location));
+    }
+
+    fn dnd_accept(&self, payload: &DragPayload, location: Point<f64, Logical>) -> Option<String> {
+        let mime_type = payload.mime_type();
+        self.lock()
+            .unwrap()
+            .state
+            .program()
+            .0
+            .dnd_mime_types(location)
+            .into_iter()
+            .find(|accepted| accepted == "*" || accepted == mime_type)
+            .map(|_| mime_type.to_owned())
+    }
+
+    fn dnd_motion(&self, location: Point<f64, Logical>) {
+        lock(self).dispatch_hook(|program| program.dnd_motion(location));
+    }
+
+    fn dnd_leave(&self) {
+        lock(self).dispatch_hook(|program| program.dnd_leave());
+    }
+
+    fn dnd_drop(&self, payload: &DragPayload, location: Point<f64, Logical>) {
+        lock(self).dispatch_hook(|program| program.dnd_drop(payload, location));
+    }
+
+    fn drag_cancelled(&self, payload: &DragPayload) {
+        lock(self).dispatch_hook(|program| program.drag_cancelled(payload));
+    }
+
+    fn is_dragging(&self) -> bool {
+        self.lock().unwrap().drag.is_some()
+    }
+
+    fn drag_icon(&self) -> Option<(IcedElement<DragIcon>, Point<f64, Logical>)> {
+        self.lock().unwrap().drag.as_ref()?.icon()
+    }
+
+    fn drag_accepted(&self) -> Option<String> {
+        self.lock().unwrap().drag.as_ref()?.accepted()
+    }
+
+    fn cancel_drag(&self) {
+        let (active, source) = {
+            let mut internal = self.lock().unwrap();
+            (internal.drag.take(), internal.self_ref.upgrade())
+        };
+        if let Some(active) = active {
+            let source = source.map(|source| source as Arc<dyn RegisteredElement>);
+            drag::cancel(active, source);
+        }
+    }
+
+    fn cursor_damage(&self, output: &Output, damage: &[Rectangle<i32, Logical>]) {
+        // marks the element dirty, clean renders would skip the damage
+        let mut internal = lock(self);
+        let scale = output.current_scale().fractional_scale();
+        let size = internal.size.to_f64();
+        let Some(buffer) = internal.buffers.get_mut(&OrderedFloat(scale)) else { return };
+        buffer.damage_front(
+            damage
+                .iter()
+                .map(|rect| {
+                    rect.to_f64()
+                        .to_buffer(scale, Transform::Normal, &size)
+                        .to_i32_up()
+                })
+                .collect(),
+        );
+    }
+
+    fn output_reconfigured(&self, output: &Output) {
+        let mut internal = lock(self);
+        if internal.outputs.contains(output) {
+            let scales = internal
+                .outputs
+                .iter()
+                .map(|o| o.current_scale().fractional_scale())
+                .collect();
+            internal.reconfigure(Reconfigure {
+                scales: Some(scales),
+                ..Default::default()
+            });
+        }
+    }
+
+    fn intent_undone(&self, intent: IntentId) {
+        lock(self).dispatch_hook(|program| program.intent_undone(intent));
+    }
+
+    fn trigger_shortcut(&self, pattern: &KeyPattern) -> bool {
+        let mut internal = lock(self);
+        if internal.restricted {
+            return false;
+        }
+        let Some(message) = internal
+            .state
+            .program()
+            .0
+            .shortcuts()
+            .into_iter()
+            .find_map(|(p, message)| (p == *pattern).then_some(message))
+        else { return false };
+        internal.state.queue_message(message);
+        let _ = internal.update(true);
+        true
+    }
+
+    fn memory_pressure(&self, level: MemoryPressureLevel) {
+        lock(self).release_memory(level);
+    }
+
+    fn defaults_changed(&self, changes: DefaultChanges) {
+        let mut internal = lock(self);
+        let mut changed = false;
+        if changes.contains(DefaultChanges::FONTS) && !internal.fonts_overridden {
+            changed |= internal.switch_fonts(fonts::default_fonts());
+        }
+        if changes.contains(DefaultChanges::THEME) && !internal.theme_overridden {
+            changed |= internal.switch_to_default_theme();
+        }
+        if changes.intersects(DefaultChanges::PREFERENCES) {
+            changed |= internal.switch_preferences(preferences::default_preferences());
+        }
+        if changed {
+            let _ = internal.update(true);
+        }
+    }
+}
+
+/// Notifies every live `IcedElement` shown on `output` about a changed refresh rate.
+pub fn set_output_refresh_info(output: &Output, info: RefreshInfo) {
+    for element in registry::elements() {
+        element.set_refresh_info(output, info);
+    }
+}
+
+/// Notifies all elements shown on `output` that a frame was presented.
+///
+/// Called for every frame of every output, so only the elements mapped on `output` are visited.
+pub fn frame_done(output: &Output) {
+    for element in registry::elements_on(output) {
+        element.frame_done(output);
+    }
+}
+
+/// Reconfigures all elements shown on `output` after its configuration (e.g. the scale) was
+/// applied, so the new buffers are ready before the next frame.
+///
+/// Goes through `IcedElement::reconfigure`, so no frame is rendered with a partially applied
+/// configuration.
+pub fn reconfigure_output(output: &Output) {
+    for element in registry::elements() {
+        element.output_reconfigured(output);
+    }
+}
+
+/// Sets the default fonts (e.g. from the user settings) of all elements, that didn't override them.
+///
+/// Fails and keeps the previous defaults, if a family isn't installed.
+pub fn set_default_fonts(fonts: FontConfig) -> Result<(), FontError> {
+    fonts::resolve(&fonts)?;
+    if !fonts::store_default(fonts) {
+        return Ok(());
+    }
+    registry::defaults_changed(DefaultChanges::FONTS);
+    Ok(())
+}
+
+/// Sets the default theme of all elements, that didn't override it, e.g. once the desktop
+/// switched between light and dark.
+pub fn set_default_theme(theme: Theme) {
+    if !theme::store_default(theme) {
+        return;
+    }
+    registry::defaults_changed(DefaultChanges::THEME);
+}
+
+/// Sets the factor for all text sizes of all elements, e.g. `1.25` for larger text.
+///
+/// Non-positive or non-finite factors are ignored.
+pub fn set_default_text_scale(scale: f32) {
+    if !scale.is_finite() || scale <= 0.0 {
+        warn!(scale, "Ignoring invalid text scale.");
+        return;
+    }
+    if preferences::store_default(|p| &mut p.text_scale, scale) {
+        registry::defaults_changed(DefaultChanges::TEXT_SCALE);
+    }
+}
+
+/// Sets how much all elements animate.
+pub fn set_default_animation_policy(policy: AnimationPolicy) {
+    if preferences::store_default(|p| &mut p.animations, policy) {
+        registry::defaults_changed(DefaultChanges::ANIMATIONS);
+    }
+}
+
+/// Sets the contrast of all elements.
+pub fn set_default_contrast(contrast: Contrast) {
+    if preferences::store_default(|p| &mut p.contrast, contrast) {
+        registry::defaults_changed(DefaultChanges::CONTRAST);
+    }
+}
+
+/// Sets how all elements format numbers, dates and times.
+pub fn set_default_format(format: FormatConfig) {
+    if preferences::store_default(|p| &mut p.format, format) {
+        registry::defaults_changed(DefaultChanges::FORMAT);
+    }
+}
+
+/// Keeps changed defaults from reaching elements until dropped, see `begin_global_changes`.
+#[must_use = "changes are applied as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct GlobalChanges {
+    _private: (),
+}
+
+impl Drop for GlobalChanges {
+    fn drop(&mut self) {
+        registry::end_batch();
+    }
+}
+
+/// Starts a batch of changes to the defaults, e.g. while applying a settings profile.
+///
+/// `set_default_fonts`, `set_default_theme` and the setters of the `Preferences` only record
+/// their changes while the returned guard is alive. Once it is dropped (or passed to
+/// `commit_global_changes`), every element switches to all changed defaults at once with a single
+/// relayout, instead of once per change, and its program gets a single `preferences_changed` call.
+/// Elements looking the same afterwards aren't redrawn. Nested batches are committed with the
+/// outermost one.
+pub fn begin_global_changes() -> GlobalChanges {
+    registry::begin_batch();
+    GlobalChanges { _private: () }
+}
+
+/// Ends a batch started by `begin_global_changes`, same as dropping its guard.
+pub fn commit_global_changes(changes: GlobalChanges) {
+    drop(changes);
+}
+
+/// Reloads the configuration of every live `IcedElement`.
+pub fn reload_all_configs() {
+    for element in registry::elements() {
+        element.reload_config();
+    }
+}
+
+/// Releases memory of all live `IcedElement`s, e.g. once the system runs low on memory.
+///
+/// Released buffers are recreated (and fully redrawn) the next time they are rendered.
+pub fn on_memory_pressure(level: MemoryPressureLevel) {
+    for element in registry::elements() {
+        element.memory_pressure(level);
+    }
+}
+
+impl<P: Program + Send + 'static> IcedElementInternal<P> {
+    fn fastest_refresh(&self) -> Option<RefreshInfo> {
+        self.refresh_info
+            .iter()
+            .map(|(_, info)| *info)
+            .min_by_key(|info| info.interval)
+    }
+
+    fn set_refresh_info(&mut self, output: &Output, info: RefreshInfo) {
+        let previous = self.fastest_refresh();
+        match self.refresh_info.iter_mut().find(|(o, _)| o == output) {
+            Some((_, old)) => *old = info,
+            None => self.refresh_info.push((output.clone(), info)),
+        }
+        self.refresh_changed(previous);
+    }
+
+    fn refresh_changed(&mut self, previous: Option<RefreshInfo>) {
+        let Some(current) = self.fastest_refresh() else { return };
+        if previous == Some(current) {
+            return;
+        }
+        if let Some(message) = self.state.program_mut().0.refresh_changed(&current) {
+            self.state.queue_message(message);
+            let _ = self.update(true);
+        }
+    }
+
+    fn dispatch_requests(&mut self) {
+        let requests = self.state.program().2.take();
+        if requests.is_empty() {
+            return;
+        }
+
+        let meta = RequestMeta {
+            serial: self.last_serial,
+            element: self.name.clone(),
+        };
+        for request in requests {
+            if self.restricted && !request.allowed_when_restricted() {
+                debug!(?request, element = ?self.name, "Dropping request of restricted element");
+                continue;
+            }
+            match self.request_handler.as_mut() {
+                Some(handler) => handler(request, meta.clone()),
+                None => debug!(?request, element = ?self.name, "No handler for shell request"),
+            }
+        }
+    }
+
+    fn reload_config(&mut self) {
+        let keys = self.state.program().0.config_keys();
+        self.config_changed(keys);
+    }
+
+    /// Passes the current values of `keys` to the program, ignoring keys it doesn't watch.
+    fn config_changed<K: AsRef<str>>(&mut self, keys: &[K]) {
+        let program = &self.state.program().0;
+        let Some((id, version)) = program.config_id() else { return };
+        let config = match CosmicConfig::new(id, version) {
+            Ok(config) => config,
+            Err(err) => {
+                warn!(?err, id, "Failed to open config");
+                return;
+            }
+        };
+
+        let messages = program
+            .config_keys()
+            .iter()
+            .filter(|key| keys.iter().any(|changed| changed.as_ref() == **key))
+            .filter_map(|key| program.on_config_changed(key, config.get::<ron::Value>(key)))
+            .collect::<Vec<_>>();
+
+        if !messages.is_empty() {
+            for message in messages {
+                self.state.queue_message(message);
+            }
+            let _ = self.update(true);
+        }
+    }
+
+    /// Watches the program's config for changes, replacing the watcher of a previous program.
+    fn sync_config_watcher(&mut self) {
+        if let Some(token) = self.config_watcher.take() {
+            self.handle.remove(token);
+        }
+        let Some((id, version)) = self.state.program().0.config_id() else { return };
+        let element = self.self_ref.clone();
+        let watcher = CosmicConfig::new(id, version).and_then(|config| {
+            config.watch(&self.handle, move |keys| {
+                if let Some(internal) = element.upgrade() {
+                    lock(&internal).config_changed(keys);
+                }
+            })
+        });
+        match watcher {
+            Ok(token) => self.config_watcher = Some(token),
+            Err(err) => warn!(?err, id, "Failed to watch config"),
+        }
+    }
+
+    /// Tracks the output the pointer is on and translates `location` to be relative
+    /// to the element's mapping on that output, if known.
+    fn cursor_position(
+        &mut self,
+        seat: &Seat<crate::state::State>,
+        location: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        let output = seat.active_output();
+        let position = self
+            .output_offsets
+            .iter()
+            .find(|(o, _)| o == &output)
+            .and_then(|(_, offset)| {
+                let global = seat.get_pointer()?.current_location();
+                Some(global - output.current_location().to_f64() - offset.to_f64())
+            })
+            .unwrap_or(location);
+        self.active_output = Some(output);
+        position
+    }
+
+    /// Synthesizes the enter of `seat` before an `event` that arrived without one,
+    /// at the hinted or otherwise current position of its pointer.
+    fn synthesize_enter(&mut self, seat: &Seat<crate::state::State>, event: &'static str) {
+        self.pointer_ordering.correct(seat.id(), event);
+        let location = match self.pointer_ordering.take_hint(seat.id()) {
+            Some(hint) => hint,
+            None => {
+                let fallback = self.cursor_pos.unwrap_or_default();
+                self.cursor_position(seat, fallback)
+            }
+        };
+        self.state
+            .queue_event(Event::Mouse(MouseEvent::CursorEntered));
+        self.queue_cursor(location);
+        self.cursor_pos = Some(location);
+        for event in self.pointer_ordering.enter(seat.id()) {
+            self.queue_pointer_event(event);
+        }
+    }
+
+    /// Queues a pointer `event`, starting the press feedback of left clicks.
+    fn queue_pointer_event(&mut self, event: Event) {
+        match &event {
+            Event::Mouse(MouseEvent::ButtonPressed(MouseButton::Left)) => {
+                self.start_press_feedback()
+            }
+            Event::Mouse(MouseEvent::ButtonReleased(MouseButton::Left)) => {
+                self.fade_press_feedback()
+            }
+            _ => {}
+        }
+        self.state.queue_event(event);
+    }
+
+    fn queue_cursor(&mut self, location: Point<f64, Logical>) {
+        let position = IcedPoint::new(location.x as f32, location.y as f32);
+        self.state
+            .queue_event(Event::Mouse(MouseEvent::CursorMoved { position }));
+    }
+
+    /// Queues a wheel event at `location`, returns false if it was dropped, see `scroll::route`.
+    fn queue_scroll(&mut self, location: Point<f64, Logical>, delta: ScrollDelta) -> bool {
+        let program = &self.state.program().0;
+        let Some(target) = scroll::route(
+            &program.scroll_regions(),
+            program.unmatched_scroll(),
+            location,
+        ) else { return false };
+        self.queue_cursor(target);
+        self.state
+            .queue_event(Event::Mouse(MouseEvent::WheelScrolled { delta }));
+        if target != location {
+            self.queue_cursor(location);
+        }
+        true
+    }
+
+    fn start_kinetic(&mut self) {
+        let element = self.self_ref.clone();
+        match self.handle.insert_source(
+            Timer::from_duration(scroll::KINETIC_INTERVAL),
+            move |_, _, _| {
+                let Some(internal) = element.upgrade() else { return TimeoutAction::Drop };
+                let mut internal = lock(&internal);
+                if internal.kinetic_step() {
+                    TimeoutAction::ToDuration(scroll::KINETIC_INTERVAL)
+                } else {
+                    internal.kinetic_timer = None;
+                    TimeoutAction::Drop
+                }
+            },
+        ) {
+            Ok(token) => self.kinetic_timer = Some(token),
+            Err(err) => warn!(?err, "Failed to start momentum scrolling"),
+        }
+    }
+
+    /// Scrolls by the next momentum delta, returns false once the momentum is used up.
+    fn kinetic_step(&mut self) -> bool {
+        let Some(location) = self.cursor_pos else {
+            self.kinetic.stop();
+            return false;
+        };
+        let step = self.kinetic.step(scroll::KINETIC_INTERVAL);
+        let delta = match step {
+            Some((x, y)) => ScrollDelta::Pixels {
+                x: x as f32,
+                y: y as f32,
+            },
+            // lets widgets snap, like a stop without momentum
+            None => ScrollDelta::Lines { x: 0.0, y: 0.0 },
+        };
+        self.queue_scroll(location, delta);
+        let _ = self.update(true);
+        step.is_some()
+    }
+
+    /// Whether pointer input of `seat` at `location` should reach iced, otherwise only its
+    /// hover indicator is updated.
+    ///
+    /// With `take_over` (e.g. on a press) `seat` becomes the primary seat, unless it is pinned.
+    fn route_seat(
+        &mut self,
+        seat: &Seat<crate::state::State>,
+        location: Point<f64, Logical>,
+        take_over: bool,
+    ) -> bool {
+        let id = seat.id();
+        if self.seat_hovers.claim(id) {
+            let damage = self.seat_hovers.left(id);
+            self.damage_overlays(&damage);
+            return true;
+        }
+
+        let regions = self.state.program().0.press_regions();
+        if take_over && !self.seat_hovers.is_pinned() {
+            let damage = self.seat_hovers.set_primary(id, self.cursor_pos, &regions);
+            self.damage_overlays(&damage);
+            // iced's cursor jumps over to the new primary seat
+            self.queue_cursor(location);
+            self.cursor_pos = Some(location);
+            return true;
+        }
+        let damage = self.seat_hovers.moved(id, location, &regions);
+        self.damage_overlays(&damage);
+        false
+    }
+
+    /// Redraws the given regions of overlays drawn above the program, e.g. hover indicators.
+    fn damage_overlays(&mut self, damage: &[Rectangle<i32, Logical>]) {
+        if !damage.is_empty() && self.frame_tracker.request_redraw() {
+            for buffer in self.buffers.values_mut() {
+                buffer.add_damage(damage);
+            }
+        }
+    }
+
+    fn start_transition(
+        &mut self,
+        phase: Phase,
+        mut spec: TransitionSpec,
+        direction: Option<SlideDirection>,
+        on_complete: Option<Box<dyn FnOnce() + Send>>,
+    ) {
+        if let (TransitionKind::Slide { direction: d, .. }, Some(direction)) =
+            (&mut spec.kind, direction)
+        {
+            *d = direction;
+        }
+        let previous = self.transition.take();
+        self.transition = Some(SpaceTransition::new(phase, spec, previous, on_complete));
+        self.finish_transition();
+    }
+
+    /// Advances the running transition and returns its render parameters.
+    fn tick_transition(&mut self, now: Instant) -> TransitionParams {
+        let Some(transition) = self.transition.as_mut() else { return TransitionParams::default() };
+        transition.tick(now);
+        let params = transition.params();
+        self.finish_transition();
+        params
+    }
+
+    /// Drops the transition once complete, scheduling its completion callback.
+    fn finish_transition(&mut self) {
+        if !self
+            .transition
+            .as_ref()
+            .map_or(false, SpaceTransition::is_complete)
+        {
+            return;
+        }
+        let mut transition = self.transition.take().unwrap();
+        if let Some(on_complete) = transition.take_on_complete() {
+            self.handle.insert_idle(move |_| on_complete());
+        }
+        // an exited element stays hidden until it is unmapped or entered again
+        if transition.phase == Phase::Exit {
+            self.transition = Some(transition);
+        }
+    }
+
+    /// Switches to `fonts`, relayouting and redrawing everything.
+    fn apply_fonts(&mut self, fonts: FontConfig) {
+        if self.switch_fonts(fonts) {
+            let _ = self.update(true);
+        }
+    }
+
+    /// Switches to `fonts` without updating, returns whether they changed.
+    fn switch_fonts(&mut self, fonts: FontConfig) -> bool {
+        if self.fonts == fonts {
+            return false;
+        }
+        self.fonts = fonts;
+        // drops the text caches
+        self.renderer = IcedRenderer::new(Backend::new());
+        self.scratch.text_tiles.clear();
+        for buffer in self.buffers.values_mut() {
+            buffer.mark_dirty();
+        }
+        self.layer_buffers.clear();
+        if let Some(message) = self.state.program().0.fonts_changed(&self.fonts) {
+            self.state.queue_message(message);
+        }
+        true
+    }
+
+    /// Switches to `preferences` without updating, returns whether the program reacted.
+    fn switch_preferences(&mut self, preferences: Preferences) -> bool {
+        if self.preferences == preferences {
+            return false;
+        }
+        self.preferences = preferences;
+        let program = &self.state.program().0;
+        let Some(message) = program.preferences_changed(&self.preferences) else { return false };
+        self.state.queue_message(message);
+        true
+    }
+
+    fn dispatch_hook(&mut self, hook: impl FnOnce(&P) -> Option<P::Message>) {
+        if let Some(message) = hook(&self.state.program().0) {
+            self.state.queue_message(message);
+            let _ = self.update(true);
+        }
+    }
+
+    fn damage_press_feedback(&mut self) {
+        let Some(feedback) = self.press_feedback else { return };
+        for buffer in self.buffers.values_mut() {
+            buffer.add_damage(&[feedback.bounds]);
+        }
+    }
+
+    /// Shows optimistic feedback, if the pointer is on one of the program's `press_regions`.
+    fn start_press_feedback(&mut self) {
+        let program = &self.state.program().0;
+        let Some(cursor_pos) = self.cursor_pos else { return };
+        if !program.optimistic_feedback() {
+            return;
+        }
+        let Some(bounds) = program
+            .press_regions()
+            .into_iter()
+            .find(|region| region.to_f64().contains(cursor_pos))
+        else { return };
+        self.damage_press_feedback();
+        self.press_feedback = Some(PressFeedback::new(bounds));
+        self.damage_press_feedback();
+    }
+
+    fn fade_press_feedback(&mut self) {
+        let Some(feedback) = self.press_feedback.as_mut() else { return };
+        if !feedback.is_fading() {
+            feedback.fade();
+            self.damage_press_feedback();
+        }
+    }
+
+    fn replace_program(&mut self, program: P) {
+        self.state = ProgramState::new(
+            ProgramWrapper(
+                program,
+                self.handle.clone(),
+                RefCell::new(Vec::new()),
+                RefCell::new(None),
+                RefCell::new(None),
+                RefCell::new(Vec::new()),
+                RefCell::new(None),
+            ),
+            IcedSize::new(self.size.w as f32, self.size.h as f32),
+            &mut self.renderer,
+            &mut self.debug,
+        );
+        self.state.set_redact_messages(self.secure_entry);
+        self.layer_buffers.clear();
+        let _ = self.update(true);
+        self.apply_initial_focus();
+        self.sync_subscriptions();
+        self.sync_config_watcher();
+        self.start_progressive();
+    }
+
+    /// Keeps the current frame around and transitions from it to the next one, see `ProgramSwap`.
+    fn transition_from_current(&mut self, transition: SwapTransition) {
+        let old_buffers = self
+            .buffers
+            .iter_mut()
+            .map(|(scale, buffer)| {
+                let old = buffer.front().clone();
+                // the new content must not draw into the captured buffer
+                buffer.resize(buffer.size());
+                (**scale, old)
+            })
+            .collect();
+        self.swap = Some(ProgramSwap::new(transition, self.size, old_buffers));
+    }
+
+    /// Crossfades to the placeholder or view of a changed `Program::content_state`
+    /// and animates the loading indicator.
+    fn sync_content_state(&mut self) {
+        let state = self.state.program().0.content_state();
+        if !state.is_same_kind(&self.content_state) && !self.outputs.is_empty() {
+            self.transition_from_current(content_state::STATE_TRANSITION);
+        }
+        let loading = matches!(state, ContentState::Loading { .. });
+        self.content_state = state;
+
+        if !loading {
+            if let Some(token) = self.content_timer.take() {
+                self.handle.remove(token);
+            }
+            return;
+        }
+        // not yet fully constructed, or already animating
+        if self.self_ref.strong_count() == 0 || self.content_timer.is_some() {
+            return;
+        }
+        let element = self.self_ref.clone();
+        match self.handle.insert_source(
+            Timer::from_duration(content_state::SPINNER_INTERVAL),
+            move |_, _, _| {
+                if let Some(internal) = element.upgrade() {
+                    let mut internal = lock(&internal);
+                    internal.content_timer = None;
+                    // lays out the next frame of the indicator and rearms the timer
+                    let _ = internal.update(true);
+                }
+                TimeoutAction::Drop
+            },
+        ) {
+            Ok(token) => self.content_timer = Some(token),
+            Err(err) => warn!(?err, "Failed to animate loading indicator"),
+        }
+    }
+
+    /// Pauses or resumes the lifetime of `auto_dismiss`, as pointers enter or leave.
+    fn sync_dismiss_hover(&mut self) {
+        let hovered = self.pointer_ordering.is_hovered();
+        let Some(dismiss) = self.auto_dismiss.as_mut() else { return };
+        if dismiss.set_hovered(hovered, Instant::now()) {
+            self.arm_dismiss_timer();
+        }
+    }
+
+    /// Restarts the lifetime of `auto_dismiss`, if `reset` applies to its policy.
+    fn reset_dismiss(&mut self, reset: impl FnOnce(&AutoDismissPolicy) -> bool) {
+        let Some(dismiss) = self.auto_dismiss.as_mut() else { return };
+        if reset(&dismiss.policy) {
+            dismiss.reset(Instant::now());
+            self.arm_dismiss_timer();
+        }
+    }
+
+    /// Rearms the timer dismissing the element, removing it while paused or cleared.
+    fn arm_dismiss_timer(&mut self) {
+        if let Some(token) = self.dismiss_timer.take() {
+            self.handle.remove(token);
+        }
+        let Some(deadline) = self.auto_dismiss.as_ref().and_then(AutoDismiss::deadline) else { return };
+        let element = self.self_ref.clone();
+        match self
+            .handle
+            .insert_source(Timer::from_deadline(deadline), move |_, _, _| {
+                let Some(internal) = element.upgrade() else { return TimeoutAction::Drop };
+                let on_dismissed = {
+                    let mut internal = lock(&internal);
+                    internal.dismiss_timer = None;
+                    let Some(mut dismiss) = internal.auto_dismiss.take() else { return TimeoutAction::Drop };
+                    let on_dismissed = dismiss.take_on_dismissed();
+                    match internal.exit_transition {
+                        Some(spec) => {
+                            internal.start_transition(Phase::Exit, spec, None, on_dismissed);
+                            None
+                        }
+                        None => on_dismissed,
+                    }
+                };
+                if let Some(on_dismissed) = on_dismissed {
+                    on_dismissed();
+                }
+                TimeoutAction::Drop
+            }) {
+            Ok(token) => self.dismiss_timer = Some(token),
+            Err(err) => warn!(?err, "Failed to schedule auto-dismissal"),
+        }
+    }
+
+    /// Schedules the stages after the first one, if the program is progressive.
+    fn start_progressive(&mut self) {
+        let plan = self.state.program().0.progressive();
+        self.progressive = plan.and_then(ProgressiveState::new);
+        self.schedule_progressive_stage();
+    }
+
+    /// Loads the next stage from an idle callback, so pending input is processed first.
+    fn schedule_progressive_stage(&mut self) {
+        let Some(progressive) = self.progressive.as_mut() else { return };
+        let element = self.self_ref.clone();
+        progressive.idle = Some(self.handle.insert_idle(move |_| {
+            if let Some(internal) = element.upgrade() {
+                lock(&internal).load_progressive_stage();
+            }
+        }));
+    }
+
+    fn load_progressive_stage(&mut self) {
+        let Some(progressive) = self.progressive.as_mut() else { return };
+        // already ran, nothing to cancel
+        progressive.idle = None;
+        progressive.stage += 1;
+        let (stage, budget) = (progressive.stage, progressive.plan.stage_budget);
+        let complete = progressive.is_complete();
+
+        let start = Instant::now();
+        self.dispatch_hook(|program| program.progressive_stage(stage));
+        let elapsed = start.elapsed();
+        if elapsed > budget {
+            warn!(
+                element = ?self.name,
+                stage,
+                ?elapsed,
+                ?budget,
+                "Progressive stage exceeded its budget, it should be split further"
+            );
+        }
+
+        if complete {
+            self.progressive = None;
+        } else {
+            self.schedule_progressive_stage();
+        }
+    }
+
+    /// Starts new and stops vanished subscriptions of the program.
+    fn sync_subscriptions(&mut self) {
+        // not yet fully constructed
+        if self.self_ref.strong_count() == 0 {
+            return;
+        }
+
+        let subscriptions = self.state.program().0.subscriptions();
+        let handle = &self.handle;
+        self.subscriptions.retain(|(id, token)| {
+            let keep = subscriptions.iter().any(|s| s.id() == *id);
+            if let (false, Some(token)) = (keep, token) {
+                handle.remove(*token);
+            }
+            keep
+        });
+
+        for subscription in subscriptions {
+            let id = subscription.id();
+            if self.subscriptions.iter().any(|(running, _)| *running == id) {
+                continue;
+            }
+            let element = self.self_ref.clone();
+            let token = subscription.start(
+                &self.handle,
+                Rc::new(move |message| {
+                    if let Some(internal) = element.upgrade() {
+                        IcedElement::from_internal(internal).queue_message(message);
+                    }
+                }),
+            );
+            self.subscriptions.push((id, token));
+        }
+    }
+
+    /// Publishes the program's shortcuts and the state they are routed by.
+    fn sync_shortcuts(&mut self) {
+        // not yet fully constructed
+        if self.self_ref.strong_count() == 0 {
+            return;
+        }
+
+        let program = &self.state.program().0;
+        let mut priority = program.shortcut_priority();
+        if self.activated {
+            priority = priority.max(ShortcutPriority::Focused);
+        }
+        let table = ShortcutTable {
+            element: self.self_ref.clone() as Weak<dyn RegisteredElement>,
+            name: self.name.clone(),
+            priority,
+            patterns: program
+                .shortcuts()
+                .into_iter()
+                .map(|(pattern, _)| pattern)
+                .collect(),
+            mapped_at: self.mapped_at,
+            restricted: self.restricted,
+        };
+        shortcuts::shortcut_registry().set_table(self.self_ref.as_ptr() as usize, table);
+    }
+
+    fn reconfigure(&mut self, changes: Reconfigure) {
+        let size = match self.sandbox.as_ref() {
+            Some(limits) => changes.size.map(|size| limits.clamp_size(size)),
+            None => changes.size,
+        };
+        let resized = size.map_or(false, |size| size != self.size);
+        if let Some(size) = size {
+            self.size = size;
+            self.hit.set_size(size);
+            if let Some(id) = self.transient_reservation {
+                placement::resize(id, size);
+            }
+        }
+
+        let scales_changed = match changes.scales {
+            Some(scales) => self.set_buffer_scales(scales),
+            None => false,
+        };
+
+        if resized {
+            for (scale, buffer) in self.buffers.iter_mut() {
+                buffer.resize(buffer::buffer_size(self.size, **scale));
+            }
+        }
+        if scales_changed {
+            self.update_double_buffering();
+        }
+        if resized {
+            // held keys may target widgets that were moved away by the resize
+            self.release_held_keys();
+        }
+        if resized || scales_changed {
+            let _ = self.update(true);
+        }
+    }
+
+    /// Repeats the `events` of the key `sym` with the repeat info of the seat's keyboard,
+    /// until released.
+    ///
+    /// Replaces the repeat of any previously pressed key, like clients do.
+    fn start_key_repeat(&mut self, repeat: KeyRepeat, sym: u32, mut events: Vec<KeyboardEvent>) {
+        self.stop_key_repeat();
+        // typed characters are neither repeated into secure fields nor kept around for it
+        if self.secure_entry {
+            events.retain(|event| !matches!(event, KeyboardEvent::CharacterReceived(_)));
+        }
+        if repeat.rate <= 0 || events.is_empty() {
+            return;
+        }
+        let delay = Duration::from_millis(repeat.delay.max(0) as u64);
+        let interval = Duration::from_secs(1) / repeat.rate as u32;
+        let element = self.self_ref.clone();
+        match self
+            .handle
+            .insert_source(Timer::from_duration(delay), move |_, _, _| {
+                let Some(internal) = element.upgrade() else { return TimeoutAction::Drop };
+                let mut internal = lock(&internal);
+                for event in &events {
+                    internal.state.queue_event(Event::Keyboard(event.clone()));
+                }
+                let _ = internal.update(true);
+                TimeoutAction::ToDuration(interval)
+            }) {
+            Ok(token) => self.key_repeat = Some((sym, token)),
+            Err(err) => warn!(?err, "Failed to start key repeat"),
+        }
+    }
+
+    fn stop_key_repeat(&mut self) {
+        if let Some((_, token)) = self.key_repeat.take() {
+            self.handle.remove(token);
+        }
+    }
+
+    /// Starts or ends secure entry, once focus moved to or away from a field of
+    /// `Program::secure_fields`, by a press, a key or the program itself.
+    ///
+    /// Unless `force`d, nothing changes while the same widget stays focused, so secure entry
+    /// ended by keyboard focus leaving the element isn't started again by the next update.
+    fn sync_secure_entry(&mut self, force: bool) {
+        let fields = self.state.program().0.secure_fields();
+        // saves walking the widget tree for most programs
+        if fields.is_empty() && !self.secure_entry {
+            return;
+        }
+        let focused = self.find_focused();
+        if !force && focused == self.secure_focus {
+            return;
+        }
+        let secure = focused.as_ref().map_or(false, |focused| {
+            focused.editable && focused.id.as_ref().map_or(false, |id| fields.contains(id))
+        });
+        self.secure_focus = focused;
+        self.set_secure_entry(secure);
+    }
+
+    fn set_secure_entry(&mut self, secure: bool) {
+        if self.secure_entry != secure {
+            // repeats of keys pressed in another field
+            self.stop_key_repeat();
+        }
+        self.secure_entry = secure;
+        self.traces.set_suspended(secure);
+        self.state.set_redact_messages(secure);
+    }
+
+    /// Presses the keys (raw keysyms) already held once keyboard focus entered,
+    /// returns whether any of them wasn't held before.
+    fn seed_held_keys(&mut self, syms: impl IntoIterator<Item = u32>) -> bool {
+        let modifiers = self.modifiers;
+        let mut pressed = false;
+        for sym in syms {
+            if !self.held_keys.insert(sym) {
+                continue;
+            }
+            let Some(key_code) = keys::keysym_to_key_code(sym) else { continue };
+            self.state
+                .queue_event(Event::Keyboard(KeyboardEvent::KeyPressed {
+                    key_code,
+                    modifiers,
+                }));
+            pressed = true;
+        }
+        pressed
+    }
+
+    /// Queues releases of all keys pressed while focused.
+    fn release_held_keys(&mut self) {
+        self.stop_key_repeat();
+        let modifiers = self.modifiers;
+        for sym in self.held_keys.drain() {
+            let Some(key_code) = keys::keysym_to_key_code(sym) else { continue };
+            self.state
+                .queue_event(Event::Keyboard(KeyboardEvent::KeyReleased {
+                    key_code,
+                    modifiers,
+                }));
+        }
+    }
+
+    fn set_badges(&mut self, badges: Vec<Badge>) {
+        if self.badges == badges {
+            return;
+        }
+
+        let damage = self
+            .badges
+            .iter()
+            .chain(badges.iter())
+            .map(Badge::bounds)
+            .collect::<Vec<_>>();
+        self.badges = badges;
+        if self.frame_tracker.request_redraw() {
+            for buffer in self.buffers.values_mut() {
+                buffer.add_damage(&damage);
+            }
+        }
+    }
+
+    /// Applies what was deferred until the frame was shown on `presented`, and queues another
+    /// render of it, if that left the element to redraw. Returns whether a render was queued.
+    fn frame_done(&mut self, presented: Option<&Output>) -> bool {
+        let redraw_due = self.frame_tracker.frame_done();
+        if redraw_due {
+            self.clean.mark_dirty();
+            for buffer in self.buffers.values_mut() {
+                buffer.mark_dirty();
+            }
+        }
+        let update_due = self.update_pending;
+        if update_due {
+            self.clean.mark_dirty();
+            let _ = self.update(true);
+        }
+        self.sync_program();
+        // nothing else renders the output again for redraws that were deferred
+        let Some(output) = presented else { return false };
+        let dirty = self.buffers.values().any(|buffer| buffer.needs_redraw());
+        if !redraw_due && !(update_due && dirty) {
+            return false;
+        }
+        queue_render(&self.handle, output.clone());
+        true
+    }
+
+    /// Creates buffers for all scales of outputs and virtual targets and drops unused ones.
+    fn refresh_buffers(&mut self) {
+        let scales = self
+            .outputs
+            .iter()
+            .map(|o| o.current_scale().fractional_scale())
+            .collect();
+        self.set_buffer_scales(scales);
+        self.update_double_buffering();
+    }
+
+    /// Keeps buffers for exactly `scales` (and the virtual targets), returns whether any buffer
+    /// was dropped or created.
+    fn set_buffer_scales(&mut self, scales: Vec<f64>) -> bool {
+        let scales = scales
+            .into_iter()
+            .map(OrderedFloat)
+            .chain(
+                self.virtual_targets
+                    .iter()
+                    .map(|target| OrderedFloat(target.scale)),
+            )
+            .collect::<Vec<_>>();
+
+        let previous = self.buffers.len();
+        self.buffers.retain(|scale, _| scales.contains(scale));
+        let mut changed = self.buffers.len() != previous;
+        self.released_scales.retain(|scale| scales.contains(scale));
+        self.layer_buffers
+            .retain(|(_, scale), _| scales.contains(scale));
+        self.last_rendered_at
+            .retain(|scale, _| scales.contains(scale));
+        for scale in scales {
+            // released buffers are only recreated once rendered again
+            if !self.buffers.contains_key(&scale) && !self.released_scales.contains(&scale) {
+                let buffer_size = buffer::buffer_size(self.size, *scale);
+                self.buffers.insert(scale, ScaleBuffer::new(buffer_size));
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn release_memory(&mut self, level: MemoryPressureLevel) {
+        self.scratch = FrameScratch::default();
+        if level < MemoryPressureLevel::Critical {
+            return;
+        }
+
+        let active = self
+            .active_output
+            .as_ref()
+            .map(|output| OrderedFloat(output.current_scale().fractional_scale()));
+        let released = self
+            .buffers
+            .keys()
+            .filter(|scale| Some(**scale) != active)
+            .copied()
+            .collect::<Vec<_>>();
+        for scale in &released {
+            self.buffers.remove(scale);
+            self.last_rendered_at.remove(scale);
+        }
+        self.layer_buffers
+            .retain(|(_, scale), _| Some(*scale) == active);
+        debug!(element = ?self.name, ?released, "Released buffers under memory pressure");
+        self.released_scales.extend(released);
+    }
+
+    /// Rasterizes the new buffer of `scale` right away, so the first frame showing it isn't blank.
+    ///
+    /// Elements with a render pool draw it on the pool, like any other redraw.
+    fn prewarm(&mut self, scale: f64) {
+        let size = self
+            .size
+            .to_f64()
+            .to_buffer(scale, Transform::Normal)
+            .to_i32_round();
+        if size.w <= 0 || size.h <= 0 {
+            return;
+        }
+        let layers = self.state.program().0.layers();
+        // only one job may be in flight, the next render draws the buffer instead
+        if self.render_job_pending || self.start_pool_draw(scale, &layers) {
+            return;
+        }
+        let _ = self.draw(scale, size, &layers, None, None);
+    }
+
+    /// Hands drawing the primitives of the buffer of `scale` to the render pool, returns false if
+    /// the element has to draw it itself (no pool, layers or nothing but custom rendering).
+    fn start_pool_draw(&mut self, scale: f64, layers: &[LayerSpec]) -> bool {
+        let Some(pool) = self.render_pool.as_ref() else { return false };
+        let program = &self.state.program().0;
+        if !layers.is_empty() || program.custom_render_only() {
+            return false;
+        }
+        let options = ContentOptions {
+            size: self.size,
+            render_scale: program.scale_mode().render_scale(scale),
+            linear_blending: self.linear_blending,
+            hairline_snapping: self.hairline_snapping,
+        };
+        let size = self.size;
+        let (primitives, hash) = self.renderer.with_primitives(|_, primitives| {
+            let hash = layers::content_hash(primitives, 0..primitives.len(), &size);
+            (primitives.to_vec(), hash)
+        });
+        pool.spawn(ContentJob {
+            primitives,
+            scale,
+            options,
+            hash,
+        });
+        self.render_job_pending = true;
+        self.traces.raster_deferred(Deferral::RenderPool);
+        true
+    }
+
+    /// Draws the frame of `content` drawn by the render pool, with the hooks and overlays on top,
+    /// and queues a render of the element's outputs to present it.
+    fn finish_pool_draw(&mut self, content: Content) {
+        self.render_job_pending = false;
+        let scale = content.scale;
+        let size = self
+            .size
+            .to_f64()
+            .to_buffer(scale, Transform::Normal)
+            .to_i32_round();
+        let layers = self.state.program().0.layers();
+        let press_feedback = self
+            .press_feedback
+            .and_then(|feedback| Some((feedback, feedback.alpha(Instant::now())?)));
+        let current_size = self.size;
+        let current = self.renderer.with_primitives(|_, primitives| {
+            layers::content_hash(primitives, 0..primitives.len(), &current_size)
+        });
+        let stale = current != content.hash;
+
+        if size.w > 0
+            && size.h > 0
+            && layers.is_empty()
+            && self
+                .draw(scale, size, &layers, press_feedback, Some(content))
+                .is_ok()
+        {
+            if stale {
+                // the program changed while the job was drawing
+                if let Some(buffer) = self.buffers.get_mut(&OrderedFloat(scale)) {
+                    buffer.mark_dirty();
+                }
+            }
+        } else if let Some(buffer) = self.buffers.get_mut(&OrderedFloat(scale)) {
+            buffer.mark_dirty();
+        }
+        for output in &self.outputs {
+            queue_render(&self.handle, output.clone());
+        }
+    }
+
+    /// Recreates the buffer of `scale`, if it was released under memory pressure.
+    fn restore_buffer(&mut self, scale: f64) {
+        let Some(i) = self.released_scales.iter().position(|s| **s == scale) else { return };
+        self.released_scales.swap_remove(i);
+        let buffer_size = buffer::buffer_size(self.size, scale);
+        self.buffers
+            .entry(OrderedFloat(scale))
+            .or_insert_with(|| ScaleBuffer::new(buffer_size));
+        self.update_double_buffering();
+    }
+
+    /// Rasterizes the pending redraw of the buffer of `scale`, returns the time it took.
+    ///
+    /// The primitives are taken from `content` drawn by the render pool, if it is given and still
+    /// matches the buffer. Failures are logged here, the buffer then keeps its previous frame and
+    /// stays dirty.
+    fn draw(
+        &mut self,
+        scale: f64,
+        size: Size<i32, BufferCoords>,
+        layers: &[LayerSpec],
+        press_feedback: Option<(PressFeedback, f32)>,
+        mut content: Option<Content>,
+    ) -> Result<Duration, RenderError> {
+        let Some(buffer) = self.buffers.get_mut(&OrderedFloat(scale)) else { return Ok(Duration::ZERO) };
+        let draw_start = Instant::now();
+        // the pool already sanitized the primitives
+        let sanitation = content
+            .as_mut()
+            .map(|content| std::mem::take(&mut content.sanitation))
+            .unwrap_or_default();
+        // not `rasterizer()`, as `buffer` still borrows `self.buffers`
+        let mut rasterizer = Rasterizer {
+            renderer: &mut self.renderer,
+            state: &mut self.state,
+            scratch: &mut self.scratch,
+            badges: &self.badges,
+            hovers: self.seat_hovers.indicators(),
+            theme: &self.theme,
+            palette: &self.palette,
+            size: self.size,
+            linear_blending: self.linear_blending,
+            hairline_snapping: self.hairline_snapping,
+            primitives: None,
+            layer: None,
+            content: content
+                .as_ref()
+                .map(|content| (content.render_size, &content.pixels[..])),
+            sanitation,
+        };
+
+        let routes = (!layers.is_empty()).then(|| {
+            rasterizer
+                .renderer
+                .with_primitives(|_, primitives| layers::route(layers, primitives))
+        });
+        // only layered elements skip unchanged content, for others every redraw is a change
+        let base_hash = routes.as_ref().map(|routes| {
+            let overlays = (rasterizer.badges, rasterizer.hovers, press_feedback);
+            rasterizer.renderer.with_primitives(|_, primitives| {
+                layers::content_hash(primitives, &routes[layers.len()], &overlays)
+            })
+        });
+        if base_hash.is_some() && base_hash == buffer.content_hash() {
+            // keeps the damage history of the other buffers intact
+            buffer.discard_damage();
+        } else {
+            let damage = buffer.take_damage(scale, self.size);
+            rasterizer.primitives = routes.as_ref().map(|routes| &routes[layers.len()][..]);
+            let result = buffer.back_mut().render().draw(|buf| {
+                rasterizer.rasterize(buf, size, scale, press_feedback)?;
+                Ok(damage)
+            });
+            if let Err(err) = result {
+                buffer.mark_dirty();
+                self.log_render_error(&err);
+                return Err(err);
+            }
+            buffer.swap();
+            buffer.set_content_hash(base_hash);
+        }
+        let layers_result = match routes.as_ref() {
+            Some(routes) => layers::draw(
+                &mut rasterizer,
+                &mut self.layer_buffers,
+                layers,
+                routes,
+                scale,
+                buffer.buffer_count(),
+            ),
+            None => Ok(()),
+        };
+        if let Err(err) = layers_result {
+            self.log_render_error(&err);
+            return Err(err);
+        }
+        let sanitation = std::mem::take(&mut rasterizer.sanitation);
+        self.sanitized = sanitation.sanitized();
+        if self.sanitized > 0 {
+            self.warn_sanitized(&sanitation);
+        }
+        self.last_rendered_at
+            .insert(OrderedFloat(scale), draw_start);
+        let draw_duration = draw_start.elapsed();
+        if let Some(limits) = self.sandbox.as_ref() {
+            self.draw_throttled_until = limits.throttle(draw_duration);
+        }
+        // elements not mapped on any output never see a frame
+        if !self.outputs.is_empty() {
+            self.frame_tracker.rendered();
+        }
+        self.traces.rasterized(Instant::now());
+        Ok(draw_duration)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let mut capabilities = Capabilities::from(self.program_capabilities);
+        let configured = [
+            (
+                Capabilities::ENTER_TRANSITION,
+                self.enter_transition.is_some(),
+            ),
+            (
+                Capabilities::EXIT_TRANSITION,
+                self.exit_transition.is_some(),
+            ),
+            (
+                Capabilities::OUTPUT_OFFSETS,
+                !self.output_offsets.is_empty(),
+            ),
+            (
+                Capabilities::VIRTUAL_TARGETS,
+                !self.virtual_targets.is_empty(),
+            ),
+            (Capabilities::RENDER_POOL, self.render_pool.is_some()),
+            (Capabilities::SHELL_REQUESTS, self.request_handler.is_some()),
+        ];
+        for (capability, configured) in configured {
+            if configured {
+                capabilities.insert(capability);
+            }
+        }
+        capabilities
+    }
+
+    fn require(&mut self, required: Capabilities, consumer: &'static str) -> bool {
+        let missing = required - self.capabilities();
+        if missing.is_empty() {
+            return true;
+        }
+        if self.capability_warnings.insert((consumer, missing)) {
+            warn!(
+                element = ?self.name,
+                consumer,
+                ?missing,
+                "Element lacks capabilities required by the caller, falling back"
+            );
+        }
+        false
+    }
+
+    fn log_render_error(&mut self, err: &RenderError) {
+        if self
+            .last_render_error
+            .map_or(false, |last| last.elapsed() < RENDER_ERROR_LOG_INTERVAL)
+        {
+            return;
+        }
+        error!(element = ?self.name, %err, "Failed to rasterize element");
+        self.last_render_error = Some(Instant::now());
+    }
+
+    fn warn_sanitized(&mut self, report: &SanitizeReport) {
+        if self
+            .last_sanitation_warning
+            .map_or(false, |last| last.elapsed() < SANITATION_LOG_INTERVAL)
+        {
+            return;
+        }
+        warn!(
+            element = ?self.name,
+            dropped = report.dropped,
+            clamped = report.clamped,
+            "Program produced primitives with invalid geometry, check its layout math"
+        );
+        self.last_sanitation_warning = Some(Instant::now());
+    }
+
+    /// Whether the primitives of the last update differ from those of the one before.
+    ///
+    /// Many messages only change the program's bookkeeping, redrawing for them would produce
+    /// identical pixels. Forced redraws (theme, scale, damage) mark the buffers directly and
+    /// aren't affected by this.
+    fn view_changed(&mut self) -> bool {
+        // hooks and custom renders draw program state not captured by the primitives
+        if self
+            .program_capabilities
+            .intersects(ProgramCapabilities::DRAW_HOOKS | ProgramCapabilities::CUSTOM_RENDER)
+        {
+            self.view_hash = None;
+            return true;
+        }
+        let size = self.size;
+        let hash = self.renderer.with_primitives(|_, primitives| {
+            layers::content_hash(primitives, 0..primitives.len(), &size)
+        });
+        if self.view_hash.replace(hash) == Some(hash) {
+            self.redundant_updates += 1;
+            false
+        } else {
+            true
+        }
+    }
+
+    fn follow_default_theme(&mut self) {
+        if self.switch_to_default_theme() {
+            let _ = self.update(true);
+        }
+    }
+
+    /// Switches to the default theme without updating, returns whether the colors changed.
+    fn switch_to_default_theme(&mut self) -> bool {
+        let theme = theme::default_theme();
+        if theme::same_colors(&theme, &self.theme) {
+            return false;
+        }
+        for buffer in self.buffers.values_mut() {
+            buffer.mark_dirty();
+        }
+        self.switch_theme(theme);
+        true
+    }
+
+    /// Switches to `theme`, redrawing hooks only for programs using them.
+    fn apply_theme(&mut self, theme: Theme) {
+        self.switch_theme(theme);
+        let _ = self.update(true);
+    }
+
+    fn switch_theme(&mut self, theme: Theme) {
+        let palette = HookPalette::new(&theme);
+        self.theme = theme;
+        if palette != self.palette {
+            self.palette = palette;
+            if self.state.program().0.uses_hooks() && self.frame_tracker.request_redraw() {
+                for buffer in self.buffers.values_mut() {
+                    buffer.mark_dirty();
+                }
+            }
+        }
+    }
+
+    fn rasterizer(&mut self) -> Rasterizer<'_, P> {
+        Rasterizer {
+            renderer: &mut self.renderer,
+            state: &mut self.state,
+            scratch: &mut self.scratch,
+            badges: &self.badges,
+            hovers: self.seat_hovers.indicators(),
+            theme: &self.theme,
+            palette: &self.palette,
+            size: self.size,
+            linear_blending: self.linear_blending,
+            hairline_snapping: self.hairline_snapping,
+            primitives: None,
+            layer: None,
+            content: None,
+            sanitation: SanitizeReport::default(),
+        }
+    }
+
+    /// Whether multiple outputs or virtual targets read from the buffer of `scale`.
+    fn is_shared(&self, scale: f64) -> bool {
+        self.outputs
+            .iter()
+            .filter(|o| o.current_scale().fractional_scale() == scale)
+            .count()
+            + self
+                .virtual_targets
+                .iter()
+                .filter(|t| t.scale == scale)
+                .count()
+            > 1
+    }
+
+    fn update_double_buffering(&mut self) {
+        // taken out for `is_shared`, without allocating on every refresh
+        let mut buffers = std::mem::take(&mut self.buffers);
+        for (scale, buffer) in buffers.iter_mut() {
+            buffer.set_buffer_count(if self.double_buffered || self.is_shared(**scale) {
+                self.buffer_age.max(2)
+            } else {
+                self.buffer_age
+            });
+        }
+        self.buffers = buffers;
+    }
+
+    /// Checks the invariants between size, buffers, outputs and cursor state.
+    ///
+    /// Outputs without a buffer for their scale are not checked, as `Reconfigure::scales`
+    /// may drop those ahead of the output's scale change, until the next `refresh`.
+    /// Violations are logged (rate limited) and repaired by recreating the offending buffers.
+    fn repair_inconsistencies(&mut self) {
+        let mut violations = Vec::new();
+        for (scale, buffer) in self.buffers.iter() {
+            let expected = buffer::buffer_size(self.size, **scale);
+            if buffer.size() != expected {
+                violations.push(format!(
+                    "buffer for scale {} has size {:?}, expected {:?}",
+                    scale,
+                    buffer.size(),
+                    expected
+                ));
+            }
+            if (self.double_buffered || self.is_shared(**scale)) && !buffer.is_double_buffered() {
+                violations.push(format!(
+                    "buffer for scale {} is read by multiple outputs, but single buffered",
+                    scale
+                ));
+            }
+            if buffer.buffer_count() < self.buffer_age {
+                violations.push(format!(
+                    "buffer for scale {} has {} buffers, expected at least {}",
+                    scale,
+                    buffer.buffer_count(),
+                    self.buffer_age
+                ));
+            }
+        }
+        if let Some(pos) = self.cursor_pos {
+            if !pos.x.is_finite() || !pos.y.is_finite() {
+                violations.push(format!("cursor position {:?} is not finite", pos));
+            }
+        }
+        if violations.is_empty() {
+            return;
+        }
+
+        if self
+            .last_inconsistency
+            .map_or(true, |last| last.elapsed() >= INCONSISTENCY_LOG_INTERVAL)
+        {
+            error!(element = ?self.name, ?violations, "Inconsistent element state, repairing");
+            self.last_inconsistency = Some(Instant::now());
+        }
+        let size = self.size;
+        for (scale, buffer) in self.buffers.iter_mut() {
+            let expected = buffer::buffer_size(size, **scale);
+            if buffer.size() != expected {
+                buffer.resize(expected);
+            }
+        }
+        self.update_double_buffering();
+        if self
+            .cursor_pos
+            .map_or(false, |pos| !pos.x.is_finite() || !pos.y.is_finite())
+        {
+            self.cursor_pos = None;
+        }
+    }
+
+    /// Counts an interaction of the primary pointer at `location` (`None` once it left).
+    #[cfg(feature = "interaction-metrics")]
+    fn record_interaction(
+        &mut self,
+        location: Option<Point<f64, Logical>>,
+        interaction: metrics::Interaction,
+    ) {
+        if !metrics::is_enabled() || self.restricted || self.secure_entry {
+            return;
+        }
+        let Some(name) = self.name.as_deref() else { return };
+        if !metrics::in_scope(name) {
+            return;
+        }
+        let regions = self.state.program().0.interaction_regions();
+        let tag = location.and_then(|location| metrics::tag_at(&regions, location));
+        if interaction == metrics::Interaction::Hover {
+            if let Some(tag) = self.hover_dwell.moved(tag) {
+                metrics::record(name, tag, interaction);
+            }
+        } else if let Some(tag) = tag {
+            metrics::record(name, tag, interaction);
+        }
+    }
+
+    /// Size and cursor position for a layout pass, with values iced can't lay out replaced.
+    fn layout_inputs(&mut self) -> (IcedSize, IcedPoint) {
+        let outside = IcedPoint::new(-1.0, -1.0);
+        if self.size.w < 0 || self.size.h < 0 {
+            warn!(element = ?self.name, size = ?self.size, "Negative element size, laying out as empty");
+        }
+        let size = IcedSize::new(self.size.w.max(0) as f32, self.size.h.max(0) as f32);
+        let Some(pos) = self.cursor_pos else { return (size, outside) };
+        // f64 positions may also overflow f32
+        let cursor = IcedPoint::new(pos.x as f32, pos.y as f32);
+        if cursor.x.is_finite() && cursor.y.is_finite() {
+            return (size, cursor);
+        }
+        warn!(element = ?self.name, ?pos, "Cursor position is not finite, laying out without cursor");
+        self.cursor_pos = None;
+        (size, outside)
+    }
+
+    /// Focuses the widget of `Program::initial_focus`, once the view was laid out.
+    fn apply_initial_focus(&mut self) {
+        let Some(target) = self.state.program().0.initial_focus() else { return };
+        let operation: Box<dyn Operation<P::Message>> = match target {
+            FocusTarget::Widget(id) => Box::new(focusable::focus(id)),
+            FocusTarget::FirstFocusable => Box::new(focusable::focus_next()),
+        };
+        let bounds = IcedSize::new(self.size.w as f32, self.size.h as f32);
+        self.state
+            .operate(&mut self.renderer, [operation], bounds, &mut self.debug);
+        // redraws the focus ring
+        let _ = self.update(true);
+    }
+
+    /// The widget of the view having keyboard focus, if any.
+    fn find_focused(&mut self) -> Option<focus::FocusedWidget> {
+        let (operation, found) = focus::find_focused();
+        let bounds = IcedSize::new(self.size.w as f32, self.size.h as f32);
+        self.state
+            .operate(&mut self.renderer, [operation], bounds, &mut self.debug);
+        let mut focused = found.lock().unwrap();
+        focused.take()
+    }
+
+    /// Announces focus moving between widgets to the registered accessible, if any.
+    #[cfg(feature = "accessibility")]
+    fn sync_focus(&mut self) {
+        if self.atspi.is_none() {
+            return;
+        }
+        let focused = self.find_focused();
+        if focused == self.focused_widget {
+            return;
+        }
+        self.focused_widget = focused.clone();
+        if let Some(atspi) = &self.atspi {
+            atspi.focus_changed(focused);
+        }
+    }
+
+    fn update(&mut self, mut force: bool) -> Vec<Action<<P as Program>::Message>> {
+        if self.update_pending {
+            self.update_pending = false;
+            force = true;
+            if let Some(token) = self.deferred_update.take() {
+                self.handle.remove(token);
+            }
+        }
+
+        while let Ok(message) = self.rx.try_recv() {
+            self.state.queue_message(message);
+            force = true;
+        }
+
+        if !force {
+            return Vec::new();
+        }
+
+        // merge updates arriving faster than frames are presented into one layout pass
+        if self.frame_tracker.is_awaiting_frame() {
+            self.update_pending = true;
+            self.traces.update_deferred(Deferral::AwaitingFrame);
+            return Vec::new();
+        }
+
+        let (size, cursor) = self.layout_inputs();
+        let ((uncaptured, actions), truncated_texts) = truncate::collect(|| {
+            let (uncaptured, command) = self.state.update(
+                size,
+                cursor,
+                &mut self.renderer,
+                &self.theme,
+                &Style {
+                    text_color: self.theme.cosmic().on_bg_color().into(),
+                },
+                &mut cosmic::iced_native::clipboard::Null,
+                &mut self.debug,
+            );
+            (uncaptured, command.map(|command| command.actions()))
+        });
+        self.truncated_texts = truncated_texts;
+        self.traces.updated(Instant::now());
+        let traversal = focus::traversal(&uncaptured);
+        if !traversal.is_empty() {
+            let bounds = IcedSize::new(self.size.w as f32, self.size.h as f32);
+            self.state
+                .operate(&mut self.renderer, traversal, bounds, &mut self.debug);
+            // redraws the focus ring
+            self.schedule_update();
+        }
+        self.sync_secure_entry(false);
+        self.sync_content_state();
+
+        // the program reacted, its own pressed state takes over
+        if actions.is_some() {
+            self.fade_press_feedback();
+            self.view_outdated = true;
+        }
+        self.sync_pending = true;
+        self.dispatch_requests();
+        for (id, intent, commit) in self.state.program().5.take() {
+            let element = self.self_ref.clone() as Weak<dyn RegisteredElement>;
+            intent::record(id, intent, commit, element, &self.handle);
+        }
+        if let Some(badges) = self.state.program().3.take() {
+            self.set_badges(badges);
+        }
+        if let Some((payload, icon)) = self.state.program().4.take() {
+            if self.drag.is_some() {
+                warn!("Internal drag already in progress, ignoring new drag");
+            } else {
+                self.drag = Some(drag::ActiveDrag::new(payload, icon, &self.handle));
+            }
+        }
+        // elements not shown on any output never see a frame
+        if self.outputs.is_empty() {
+            self.sync_program();
+        }
+        let priority = self.state.program().6.take().unwrap_or_default();
+        // the common case, don't go through the actions at all
+        let Some(actions) = actions else { return Vec::new() };
+        // collecting reuses the allocation of `actions`
+        actions
+            .into_iter()
+            .filter_map(|action| {
+                if let Action::Future(future) = action {
+                    self.futures
+                        .schedule(future, priority, self.name.as_deref());
+                    None
+                } else {
+                    Some(action)
+                }
+            })
+            .collect::<Vec<_>>()
+    }
+
+    /// Catches up with all updates since the last call, at most once per frame for elements
+    /// shown on outputs.
+    ///
+    /// Hashing the view, syncing subscriptions and shortcuts and querying the capabilities and
+    /// input region are too expensive to repeat for every update of a burst.
+    fn sync_program(&mut self) {
+        if !std::mem::take(&mut self.sync_pending) {
+            return;
+        }
+        let changed = std::mem::take(&mut self.view_outdated) && self.view_changed();
+        if changed {
+            self.reset_dismiss(|policy| policy.reset_on_update);
+        }
+        if changed && self.frame_tracker.request_redraw() {
+            for buffer in self.buffers.values_mut() {
+                buffer.mark_dirty();
+            }
+        }
+        self.sync_subscriptions();
+        self.sync_shortcuts();
+        #[cfg(feature = "accessibility")]
+        self.sync_focus();
+        self.program_capabilities = capabilities::detect(&self.state.program().0);
+        self.input_region = self.state.program().0.input_region();
+        self.hit.set_custom_region(self.input_region.is_some());
+    }
+
+    /// Updates once the event loop is dispatched next, merging all updates requested until then
+    /// into a single layout pass.
+    fn schedule_update(&mut self) {
+        self.update_pending = true;
+        if self.deferred_update.is_some() {
+            return;
+        }
+        let element = self.self_ref.clone();
+        match self
+            .handle
+            .insert_source(Timer::immediate(), move |_, _, _| {
+                if let Some(internal) = element.upgrade() {
+                    let mut internal = lock(&internal);
+                    internal.deferred_update = None;
+                    if internal.update_pending {
+                        let _ = internal.update(true);
+                    }
+                }
+                TimeoutAction::Drop
+            }) {
+            Ok(token) => {
+                self.deferred_update = Some(token);
+                self.traces.update_deferred(Deferral::Coalesced);
+            }
+            Err(err) => {
+                warn!(?err, "Failed to schedule update");
+                let _ = self.update(true);
+            }
+        }
+    }
+}
+
+impl<P: Program + Send + 'static> PointerTarget<crate::state::State> for IcedElement<P> {
+    fn enter(
+        &self,
+        seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        event: &MotionEvent,
+    ) {
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        let location = internal.cursor_position(seat, event.location);
+        let deferred = internal.pointer_ordering.enter(seat.id());
+        internal.sync_dismiss_hover();
+        if !internal.route_seat(seat, location, !deferred.is_empty()) {
+            return;
+        }
+        internal
+            .state
+            .queue_event(Event::Mouse(MouseEvent::CursorEntered));
+        let position = IcedPoint::new(location.x as f32, location.y as f32);
+        internal
+            .state
+            .queue_event(Event::Mouse(MouseEvent::CursorMoved { position }));
+        internal.cursor_pos = Some(location);
+        #[cfg(feature = "interaction-metrics")]
+        internal.record_interaction(Some(location), metrics::Interaction::Hover);
+        for event in deferred {
+            internal.queue_pointer_event(event);
+        }
+        let _ = internal.update(true);
+    }
+
+    fn motion(
+        &self,
+        seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        event: &MotionEvent,
+    ) {
+        // the drag source keeps the implicit grab and moves the drag instead
+        if let Some(pointer) = seat.get_pointer() {
+            if self.drag_motion(pointer.current_location()) {
+                return;
+            }
+        }
+
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        let entered = internal.pointer_ordering.is_entered(seat.id());
+        if !entered {
+            internal.pointer_ordering.correct(seat.id(), "motion");
+        }
+        let location = internal.cursor_position(seat, event.location);
+        let deferred = internal.pointer_ordering.enter(seat.id());
+        internal.sync_dismiss_hover();
+        if !internal.route_seat(seat, location, !deferred.is_empty()) {
+            return;
+        }
+        if !entered {
+            internal
+                .state
+                .queue_event(Event::Mouse(MouseEvent::CursorEntered));
+        }
+        let position = IcedPoint::new(location.x as f32, location.y as f32);
+        internal
+            .state
+            .queue_event(Event::Mouse(MouseEvent::CursorMoved { position }));
+        internal.traces.queued(TracedInput::Motion, None);
+        internal.cursor_pos = Some(location);
+        #[cfg(feature = "interaction-metrics")]
+        internal.record_interaction(Some(location), metrics::Interaction::Hover);
+        if !deferred.is_empty() {
+            // deferred buttons shouldn't wait for the motion to settle
+            for event in deferred {
+                internal.queue_pointer_event(event);
+            }
+            let _ = internal.update(true);
+        } else {
+            self.defer_update(&mut internal);
+        }
+    }
+
+    fn relative_motion(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        _event: &RelativeMotionEvent,
+    ) {
+    }
+
+    fn button(
+        &self,
+        seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        event: &ButtonEvent,
+    ) {
+        let mut internal = self.lock();
+        let dragging = internal.drag.is_some();
+        self.mark_active(&mut internal);
+        internal.reset_dismiss(|policy| policy.reset_on_interaction);
+        internal.last_serial = Some(event.serial);
+        let button = match event.button {
+            0x110 => MouseButton::Left,
+            0x111 => MouseButton::Right,
+            0x112 => MouseButton::Middle,
+            x => MouseButton::Other(x as u8),
+        };
+        let button_event = Event::Mouse(match event.state {
+            ButtonState::Pressed => MouseEvent::ButtonPressed(button),
+            ButtonState::Released => MouseEvent::ButtonReleased(button),
+        });
+        if !dragging && internal.pointer_ordering.is_entered(seat.id()) {
+            let fallback = internal.cursor_pos.unwrap_or_default();
+            let location = internal.cursor_position(seat, fallback);
+            if !internal.route_seat(seat, location, event.state == ButtonState::Pressed) {
+                return;
+            }
+        }
+        if !internal.pointer_ordering.is_entered(seat.id()) {
+            if internal.pointer_ordering.mode == OrderingCorrection::Defer {
+                internal.pointer_ordering.correct(seat.id(), "button");
+                internal.pointer_ordering.defer(seat.id(), button_event);
+                return;
+            }
+            internal.synthesize_enter(seat, "button");
+        }
+        internal.queue_pointer_event(button_event);
+        internal
+            .traces
+            .queued(TracedInput::Button, Some(event.serial));
+        #[cfg(feature = "interaction-metrics")]
+        if event.state == ButtonState::Pressed {
+            let location = internal.cursor_pos;
+            internal.record_interaction(location, metrics::Interaction::Click);
+        }
+        let _ = internal.update(true);
+
+        if dragging && event.state == ButtonState::Released {
+            drop(internal);
+            if let Some(pointer) = seat.get_pointer() {
+                self.drag_drop(pointer.current_location());
+            }
+        }
+    }
+
+    fn axis(
+        &self,
+        seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        frame: AxisFrame,
+    ) {
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        internal.reset_dismiss(|policy| policy.reset_on_interaction);
+        if !internal.pointer_ordering.is_entered(seat.id()) {
+            internal.synthesize_enter(seat, "axis");
+        }
+
+        // the last queued motion may be stale, so resolve the target with the current position
+        let fallback = internal.cursor_pos.unwrap_or_default();
+        let location = internal.cursor_position(seat, fallback);
+        if let Some(source) = frame.source {
+            internal.last_axis_source = Some(source);
+        }
+        let physics = match internal.last_axis_source {
+            Some(source) => internal.state.program().0.configure_scroll_physics(source),
+            None if frame.discrete.is_some() => ScrollPhysics::Discrete,
+            None => ScrollPhysics::Pixels,
+        };
+
+        // any new scroll takes over from momentum scrolling
+        if let Some(token) = internal.kinetic_timer.take() {
+            internal.handle.remove(token);
+        }
+        if let ScrollPhysics::Momentum { deceleration } = physics {
+            if !scroll::is_stop(&frame) {
+                internal.kinetic.record(frame.time, frame.axis);
+            } else if internal.kinetic.release(deceleration) {
+                // the stop is delivered once the momentum is used up
+                internal.cursor_pos = Some(location);
+                internal.start_kinetic();
+                return;
+            }
+        }
+
+        if !internal.queue_scroll(location, scroll::delta(&frame, physics)) {
+            return;
+        }
+        internal.traces.queued(TracedInput::Axis, None);
+        internal.cursor_pos = Some(location);
+        #[cfg(feature = "interaction-metrics")]
+        if !scroll::is_stop(&frame) {
+            internal.record_interaction(Some(location), metrics::Interaction::Scroll);
+        }
+        self.defer_update(&mut internal);
+    }
+
+    fn leave(
+        &self,
+        seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        _serial: Serial,
+        _time: u32,
+    ) {
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        internal.pointer_ordering.leave(seat.id());
+        internal.sync_dismiss_hover();
+        if internal.seat_hovers.primary() != Some(seat.id()) {
+            let damage = internal.seat_hovers.left(seat.id());
+            internal.damage_overlays(&damage);
+            return;
+        }
+        internal.seat_hovers.primary_left();
+        #[cfg(feature = "interaction-metrics")]
+        internal.record_interaction(None, metrics::Interaction::Hover);
+        internal
+            .state
+            .queue_event(Event::Mouse(MouseEvent::CursorLeft));
+        let _ = internal.update(true);
+    }
+}
+
+impl<P: Program + Send + 'static> KeyboardTarget<crate::state::State> for IcedElement<P> {
+    fn enter(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        held: Vec<KeysymHandle<'_>>,
+        _serial: Serial,
+    ) {
+        let mut internal = self.lock();
+        internal.input_method_active = internal.capabilities().contains(Capabilities::INPUT_METHOD);
+        // a password field may have kept its focus while the element didn't have keyboard focus
+        internal.sync_secure_entry(true);
+        // keys held while focus moved here, e.g. the modifiers of a window switcher
+        if internal.seed_held_keys(held.iter().map(keys::raw_sym)) {
+            self.mark_active(&mut internal);
+            let _ = internal.update(true);
+        }
+    }
+
+    fn leave(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        _serial: Serial,
+    ) {
+        let mut internal = self.lock();
+        internal.input_method_active = false;
+        internal.set_secure_entry(false);
+        internal.release_held_keys();
+        internal.modifiers = IcedModifiers::empty();
+        internal
+            .state
+            .queue_event(Event::Keyboard(KeyboardEvent::ModifiersChanged(
+                IcedModifiers::empty(),
+            )));
+        let _ = internal.update(true);
+    }
+
+    fn key(
+        &self,
+        seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        key: KeysymHandle<'_>,
+        state: KeyState,
+        _serial: Serial,
+        _time: u32,
+    ) {
+        if state == KeyState::Pressed
+            && key.modified_sym() == keysyms::KEY_Escape
+            && self.drag_cancel()
+        {
+            return;
+        }
+
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        internal.reset_dismiss(|policy| policy.reset_on_interaction);
+        let modifiers = internal.modifiers;
+        let sym = keys::raw_sym(&key);
+        let key_code = keys::key_code(&key);
+        match state {
+            KeyState::Pressed => {
+                internal.held_keys.insert(sym);
+                // keys without a code on this layout (e.g. umlauts) may still type text
+                let events = key_code
+                    .map(|key_code| KeyboardEvent::KeyPressed {
+                        key_code,
+                        modifiers,
+                    })
+                    .into_iter()
+                    .chain(keys::text(&key, modifiers).map(KeyboardEvent::CharacterReceived))
+                    .collect::<Vec<_>>();
+                for event in &events {
+                    internal.state.queue_event(Event::Keyboard(event.clone()));
+                }
+                if keys::repeats(sym) && !events.is_empty() {
+                    let repeat = seat
+                        .user_data()
+                        .get::<KeyRepeat>()
+                        .copied()
+                        .unwrap_or_default();
+                    internal.start_key_repeat(repeat, sym, events);
+                }
+            }
+            KeyState::Released => {
+                internal.held_keys.remove(&sym);
+                if matches!(internal.key_repeat, Some((repeating, _)) if repeating == sym) {
+                    internal.stop_key_repeat();
+                }
+                if let Some(key_code) = key_code {
+                    internal
+                        .state
+                        .queue_event(Event::Keyboard(KeyboardEvent::KeyReleased {
+                            key_code,
+                            modifiers,
+                        }));
+                }
+            }
+        }
+        let _ = internal.update(true);
+    }
+
+    fn modifiers(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        modifiers: ModifiersState,
+        _serial: Serial,
+    ) {
+        let mut internal = self.lock();
+        self.mark_active(&mut internal);
+        let mut mods = IcedModifiers::empty();
+        if modifiers.shift {
+            mods.insert(IcedModifiers::SHIFT);
+        }
+        if modifiers.alt {
+            mods.insert(IcedModifiers::ALT);
+        }
+        if modifiers.ctrl {
+            mods.insert(IcedModifiers::CTRL);
+        }
+        if modifiers.logo {
+            mods.insert(IcedModifiers::LOGO);
+        }
+        internal.modifiers = mods;
+        internal
+            .state
+            .queue_event(Event::Keyboard(KeyboardEvent::ModifiersChanged(mods)));
+        self.defer_update(&mut internal);
+    }
+}
+
+impl<P: Program + Send + 'static> IsAlive for IcedElement<P> {
+    fn alive(&self) -> bool {
+        true
+    }
+}
+
+impl<P: Program + Send + 'static> SpaceElement for IcedElement<P> {
+    fn bbox(&self) -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size((0, 0), self.0.lock().unwrap().size)
+    }
+
+    fn is_in_input_region(&self, point: &Point<f64, Logical>) -> bool {
+        let internal = self.0.lock().unwrap();
+        match internal.input_region.as_ref() {
+            Some(regions) => regions
+                .iter()
+                .any(|region| region.to_f64().contains(*point)),
+            None => true,
+        }
+    }
+
+    fn set_activate(&self, activated: bool) {
+        let mut internal = self.lock();
+        internal.activated = activated;
+        internal.state.queue_event(Event::Window(
+            Id::MAIN,
+            if activated {
+                WindowEvent::Focused
+            } else {
+                WindowEvent::Unfocused
+            },
+        ));
+        let _ = internal.update(true); // TODO
+    }
+
+    fn output_enter(&self, output: &Output, _overlap: Rectangle<i32, Logical>) {
+        let mut internal = self.lock();
+        let scale = output.current_scale().fractional_scale();
+        if !internal.buffers.contains_key(&OrderedFloat(scale)) {
+            let buffer_size = buffer::buffer_size(internal.size, scale);
+            internal
+                .buffers
+                .insert(OrderedFloat(scale), ScaleBuffer::new(buffer_size));
+            internal.prewarm(scale);
+        }
+        if internal.outputs.is_empty() && internal.transition.is_none() {
+            if let Some(spec) = internal.enter_transition {
+                internal.start_transition(Phase::Enter, spec, None, None);
+            }
+        }
+        if internal.outputs.is_empty() {
+            internal.mapped_at = Some(shortcuts::next_mapping());
+            internal.sync_shortcuts();
+        }
+        internal.outputs.push(output.clone());
+        let element = internal.self_ref.clone() as Weak<dyn RegisteredElement>;
+        registry::entered_output(output, element);
+        if let Some(location) = internal.location {
+            internal.output_offsets.retain(|(o, _)| o != output);
+            internal
+                .output_offsets
+                .push((output.clone(), location - output.current_location()));
+            internal.hit.set_offsets(&internal.output_offsets);
+        }
+        internal.update_double_buffering();
+        internal.repair_inconsistencies();
+        if !internal.refresh_info.iter().any(|(o, _)| o == output) {
+            if let Some(info) = RefreshInfo::for_output(output, false) {
+                internal.set_refresh_info(output, info);
+            }
+        }
+    }
+
+    fn output_leave(&self, output: &Output) {
+        {
+            let mut internal = self.lock();
+            let previous = internal.fastest_refresh();
+            internal.outputs.retain(|o| o != output);
+            let element = internal.self_ref.clone() as Weak<dyn RegisteredElement>;
+            registry::left_output(output, &element);
+            internal.refresh_info.retain(|(o, _)| o != output);
+            internal.output_offsets.retain(|(o, _)| o != output);
+            internal.hit.set_offsets(&internal.output_offsets);
+            if internal.outputs.is_empty() {
+                let _ = internal.frame_done(None);
+                internal.mapped_at = None;
+                internal.sync_shortcuts();
+            }
+            if internal.active_output.as_ref() == Some(output) {
+                internal.active_output = None;
+            }
+            internal.refresh_changed(previous);
+        }
+        self.refresh();
+    }
+
+    fn z_index(&self) -> u8 {
+        let internal = self.0.lock().unwrap();
+        internal
+            .z_index
+            .unwrap_or_else(|| internal.state.program().0.z_index())
+    }
+
+    fn refresh(&self) {
+        // called every loop iteration, only creates and drops buffers of other scales
+        let mut internal = self.0.lock().unwrap();
+        internal.refresh_buffers();
+        internal.repair_inconsistencies();
+    }
+}
+
+/// Parts of an `IcedElementInternal` needed to rasterize it, borrowed apart from its buffers.
+struct Rasterizer<'a, P: Program> {
+    renderer: &'a mut IcedRenderer,
+    state: &'a mut State<ProgramWrapper<P>>,
+    scratch: &'a mut FrameScratch,
+    badges: &'a [Badge],
+    /// Hover indicators of secondary seats
+    hovers: &'a [SeatIndicator],
+    theme: &'a Theme,
+    palette: &'a HookPalette,
+    /// Logical size of the drawn area
+    size: Size<i32, Logical>,
+    linear_blending: bool,
+    hairline_snapping: bool,
+    /// Indices of the top-level primitives to draw, all if `None`
+    primitives: Option<&'a [usize]>,
+    /// Bounds of the drawn layer, the whole element (including program hooks and overlays) if `None`
+    layer: Option<Rectangle<i32, Logical>>,
+    /// Primitives drawn by the render pool (render size, pixels), drawn instead of the primitives
+    content: Option<(Size<i32, BufferCoords>, &'a [u32])>,
+    /// Primitives with broken geometry, across all rasterized buffers
+    sanitation: SanitizeReport,
+}
+
+/// How the primitives of an element are drawn, see `draw_primitives`.
+#[derive(Debug, Clone, Copy)]
+struct ContentOptions {
+    /// Logical size of the element
+    size: Size<i32, Logical>,
+    render_scale: f64,
+    linear_blending: bool,
+    hairline_snapping: bool,
+}
+
+/// Draws `primitives` onto `target`, sanitized and with hairlines snapped as configured.
+///
+/// With linear blending, `target` is a linear-light layer and text is drawn from `text_tiles`.
+fn draw_primitives<'p>(
+    target: &mut DrawTarget<&mut [u32]>,
+    backend: &mut Backend,
+    primitives: impl IntoIterator<Item = &'p Primitive>,
+    options: ContentOptions,
+    text_tiles: &mut TextTiles,
+    report: &mut SanitizeReport,
+) {
+    let draw_options = raqote::DrawOptions {
+        // Default to antialiasing off for now
+        antialias: raqote::AntialiasMode::None,
+        ..Default::default()
+    };
+    let scale = options.render_scale as f32;
+    let limit = sanitize::limit(options.size.w, options.size.h);
+    for primitive in primitives {
+        let Some(primitive) = sanitize::sanitize(primitive, limit, report) else { continue };
+        let snapped = options
+            .hairline_snapping
+            .then(|| hairline::snap_hairlines(&primitive, scale));
+        let primitive = snapped.as_deref().unwrap_or(&primitive);
+        if options.linear_blending {
+            blending::draw_linear(target, &draw_options, backend, scale, primitive, text_tiles);
+        } else {
+            draw_primitive(target, &draw_options, backend, scale, primitive);
+        }
+    }
+}
+
+impl<'a, P: Program> Rasterizer<'a, P> {
+    /// Draws the program into `buf` of `size` (ARGB8888) for `scale`.
+    ///
+    /// `buf` is left in an unspecified state on errors.
+    fn rasterize(
+        &mut self,
+        buf: &mut [u8],
+        size: Size<i32, BufferCoords>,
+        scale: f64,
+        press_feedback: Option<(PressFeedback, f32)>,
+    ) -> Result<(), RenderError> {
+        let expected = (size.w.max(0) * size.h.max(0) * 4) as usize;
+        if buf.len() < expected {
+            return Err(RenderError::BufferTooSmall {
+                expected,
+                actual: buf.len(),
+            });
+        }
+        catch_unwind(AssertUnwindSafe(|| {
+            self.rasterize_unchecked(buf, size, scale, press_feedback)
+        }))
+        .map_err(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            RenderError::Panicked(message)
+        })
+    }
+
+    fn rasterize_unchecked(
+        &mut self,
+        buf: &mut [u8],
+        size: Size<i32, BufferCoords>,
+        scale: f64,
+        press_feedback: Option<(PressFeedback, f32)>,
+    ) {
+        let render_scale = self.state.program().0.scale_mode().render_scale(scale);
+        let render_size = self
+            .size
+            .to_f64()
+            .to_buffer(render_scale, Transform::Normal)
+            .to_i32_round();
+        let pixels = (render_size.w * render_size.h) as usize;
+        let FrameScratch {
+            hires,
+            layer,
+            text_tiles,
+        } = &mut *self.scratch;
+        let mut hires = (render_size != size).then(|| scratch::cleared(hires, pixels));
+
+        let mut target = raqote::DrawTarget::from_backing(
+            render_size.w,
+            render_size.h,
+            match hires.as_deref_mut() {
+                Some(hires) => hires,
+                None => bytemuck::cast_slice_mut::<_, u32>(buf),
+            },
+        );
+
+        target.clear(raqote::SolidSource::from_unpremultiplied_argb(0, 0, 0, 0));
+        let program = &self.state.program().0;
+        if self.layer.is_none() {
+            program.themed_background(&mut target, self.palette);
+        }
+
+        let options = ContentOptions {
+            size: self.size,
+            render_scale,
+            linear_blending: self.linear_blending,
+            hairline_snapping: self.hairline_snapping,
+        };
+        let mut draw_content = |target: &mut DrawTarget<&mut [u32]>| {
+            // Having at least one clip fixes some font rendering issues
+            target.push_clip_rect(raqote::IntRect::new(
+                raqote::IntPoint::new(0, 0),
+                raqote::IntPoint::new(render_size.w, render_size.h),
+            ));
+            if let Some(layer) = self.layer {
+                target.set_transform(&raqote::Transform::translation(
+                    -layer.loc.x as f32 * render_scale as f32,
+                    -layer.loc.y as f32 * render_scale as f32,
+                ));
+            }
+
+            let filter = self.primitives;
+            let report = &mut self.sanitation;
+            self.renderer.with_primitives(|backend, primitives| {
+                let primitives = primitives
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| filter.map_or(true, |filter| filter.contains(i)))
+                    .map(|(_, primitive)| primitive);
+                draw_primitives(target, backend, primitives, options, text_tiles, report);
+            });
+            if !report.markers.is_empty() {
+                if sanitize::markers_enabled() {
+                    sanitize::draw_markers(target, &report.markers, render_scale as f32);
+                }
+                report.markers.clear();
+            }
+        };
+
+        if !program.custom_render_only() {
+            if let Some((_, content)) = self.content.filter(|(size, _)| *size == render_size) {
+                if self.linear_blending {
+                    blending::composite_linear(target.get_data_mut(), content);
+                } else {
+                    target.draw_image_at(
+                        0.0,
+                        0.0,
+                        &raqote::Image {
+                            width: render_size.w,
+                            height: render_size.h,
+                            data: content,
+                        },
+                        &raqote::DrawOptions::new(),
+                    );
+                }
+            } else if self.linear_blending {
+                let layer = scratch::cleared(layer, pixels);
+                let mut layer_target =
+                    raqote::DrawTarget::from_backing(render_size.w, render_size.h, &mut *layer);
+                draw_content(&mut layer_target);
+                drop(layer_target);
+                text_tiles.finish_frame();
+                blending::composite_linear(target.get_data_mut(), layer);
+            } else {
+                draw_content(&mut target);
+            }
+        }
+
+        if self.layer.is_none() {
+            program.themed_foreground(&mut target, self.palette);
+            self.state.program_mut().0.custom_render(
+                &mut target,
+                (render_size.w, render_size.h).into(),
+                render_scale,
+            );
+            if let Some((feedback, alpha)) = press_feedback.as_ref() {
+                press::draw_press_feedback(
+                    &mut target,
+                    feedback,
+                    *alpha,
+                    render_scale as f32,
+                    self.theme,
+                );
+            }
+            badge::draw_badges(&mut target, self.badges, render_scale as f32, self.theme);
+            hover::draw_indicators(&mut target, self.hovers, render_scale as f32);
+        }
+        drop(target);
+
+        if let Some(hires) = hires {
+            scale::downsample_bilinear(
+                &hires,
+                render_size,
+                bytemuck::cast_slice_mut::<_, u32>(buf),
+                size,
+            );
+        }
+    }
+}
+
+impl<P: Program + Send + 'static> IcedElement<P> {
+    /// Like `AsRenderElements::render_elements`, but appends to `out`,
+    /// so callers can reuse their `Vec` across frames.
+    pub fn render_elements_into<R, C>(
+        &self,
+        renderer: &mut R,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        alpha: f32,
+        out: &mut Vec<C>,
+    ) where
+        R: Renderer + ImportMem,
+        <R as Renderer>::TextureId: 'static,
+        C: From<MemoryRenderBufferRenderElement<R>>,
+    {
+        if let Some(frame) = self.1.get(scale.x) {
+            match MemoryRenderBufferRenderElement::from_buffer(
+                renderer,
+                location.to_f64(),
+                &frame.buffer,
+                Some(alpha),
+                Some(frame.src),
+                Some(frame.size),
+            ) {
+                Ok(element) => {
+                    out.push(C::from(element));
+                    return;
+                }
+                // the regular path retries and handles upload failures
+                Err(_) => {}
+            }
+        }
+        let mut internal = self.0.lock().unwrap();
+        self.update_for_render(&mut internal);
+        self.render_updated_into(&mut internal, renderer, location, scale, alpha, out);
+    }
+
+    /// Renders multiple elements sharing state (e.g. a panel and its applet popup), so they
+    /// show the same state of it.
+    ///
+    /// All elements are updated before any of them is rendered, so messages arriving while
+    /// rendering only show up in the next batch, for all elements alike.
+    pub fn render_batch<R>(
+        elements: &[&IcedElement<P>],
+        renderer: &mut R,
+        locations: &[Point<i32, Physical>],
+        scale: Scale<f64>,
+        alpha: f32,
+    ) -> Vec<MemoryRenderBufferRenderElement<R>>
+    where
+        R: Renderer + ImportMem,
+        <R as Renderer>::TextureId: 'static,
+    {
+        debug_assert_eq!(elements.len(), locations.len());
+        for element in elements {
+            element.update_for_render(&mut element.0.lock().unwrap());
+        }
+        let mut out = Vec::new();
+        for (element, location) in elements.iter().zip(locations) {
+            let mut internal = element.0.lock().unwrap();
+            element.render_updated_into(&mut internal, renderer, *location, scale, alpha, &mut out);
+        }
+        out
+    }
+
+    /// Applies pending updates ahead of rendering.
+    fn update_for_render(&self, internal: &mut IcedElementInternal<P>) {
+        self.mark_active(internal);
+        let update_start = Instant::now();
+        let _ = internal.update(false); // TODO
+        internal.render_update_duration = update_start.elapsed();
+    }
+
+    fn render_updated_into<R, C>(
+        &self,
+        internal: &mut IcedElementInternal<P>,
+        renderer: &mut R,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        alpha: f32,
+        out: &mut Vec<C>,
+    ) where
+        R: Renderer + ImportMem,
+        <R as Renderer>::TextureId: 'static,
+        C: From<MemoryRenderBufferRenderElement<R>>,
+    {
+        let now = Instant::now();
+        let transition = internal.tick_transition(now);
+        if internal
+            .swap
+            .as_ref()
+            .map_or(false, |swap| swap.is_complete(now))
+        {
+            // releases the old program's buffers
+            internal.swap = None;
+        }
+        let (swap_old, swap_new) = internal
+            .swap
+            .as_ref()
+            .map(|swap| swap.params(now, internal.size))
+            .unwrap_or_default();
+        if internal
+            .transition
+            .as_ref()
+            .map_or(false, |t| t.phase == Phase::Exit && t.is_complete())
+        {
+            return;
+        }
+
+        // keep redrawing fading press feedback until it is gone
+        let press_feedback = internal
+            .press_feedback
+            .and_then(|feedback| Some((feedback, feedback.alpha(now)?)));
+        match press_feedback {
+            Some((feedback, _)) if feedback.is_fading() => internal.damage_press_feedback(),
+            Some(_) => {}
+            None => {
+                internal.damage_press_feedback();
+                internal.press_feedback = None;
+            }
+        }
+
+        internal.restore_buffer(scale.x);
+        internal.sync_program();
+
+        // makes partial borrows easier
+        let internal_ref = &mut *internal;
+        if let Some(buffer) = internal_ref.buffers.get_mut(&OrderedFloat(scale.x)) {
+            let size = internal_ref
+                .size
+                .to_f64()
+                .to_buffer(scale.x, Transform::Normal)
+                .to_i32_round();
+
+            // very small elements at small fractional scales may round down to nothing,
+            // which raqote can't draw into
+            if size.w <= 0 || size.h <= 0 {
+                return;
+            }
+
+            let layers = internal_ref.state.program().0.layers();
+            internal_ref
+                .layer_buffers
+                .retain(|(id, _), _| layers.iter().any(|layer| layer.id == *id));
+
+            // over-budget sandboxed elements keep their previous buffer for a while
+            let throttled = internal_ref
+                .draw_throttled_until
+                .map_or(false, |until| Instant::now() < until);
+            // as do programs limiting their frame rate
+            let rate_limited = match (
+                internal_ref.state.program().0.max_fps(),
+                internal_ref.last_rendered_at.get(&OrderedFloat(scale.x)),
+            ) {
+                (Some(fps), Some(last)) if fps > 0 => last.elapsed() < Duration::from_secs(1) / fps,
+                _ => false,
+            };
+            let skipped = !buffer.needs_redraw()
+                || throttled
+                || rate_limited
+                || internal_ref.render_job_pending;
+            if buffer.needs_redraw() {
+                let deferral = if throttled {
+                    Some(Deferral::Throttled)
+                } else if rate_limited {
+                    Some(Deferral::RateLimited)
+                } else if internal_ref.render_job_pending {
+                    Some(Deferral::RenderPool)
+                } else {
+                    None
+                };
+                if let Some(deferral) = deferral {
+                    internal_ref.traces.raster_deferred(deferral);
+                }
+            }
+            let mut draw_duration = Duration::ZERO;
+            // the pool presents the previous frame, until the job finished
+            if !skipped && !internal_ref.start_pool_draw(scale.x, &layers) {
+                // nothing is shown rather than a partially drawn buffer
+                match internal_ref.draw(scale.x, size, &layers, press_feedback, None) {
+                    Ok(duration) => draw_duration = duration,
+                    Err(_) => return,
+                }
+            }
+            let Some(buffer) = internal_ref.buffers.get_mut(&OrderedFloat(scale.x)) else { return };
+
+            // transitions only change where and how the existing buffer is shown
+            let logical_size = internal_ref.size.to_f64();
+            let transition_size = logical_size.upscale(transition.scale);
+            let transition_offset = transition.offset
+                + (logical_size.to_point() - transition_size.to_point()).downscale(2.0);
+            let element_location =
+                location.to_f64() + (transition_offset + swap_new.offset).to_physical(scale);
+            let content_alpha = alpha * transition.alpha * swap_new.alpha;
+            let upload_start = Instant::now();
+
+            // the last frame of a replaced program stays on top, until its transition completed
+            if let Some(swap) = internal_ref.swap.as_ref() {
+                if let Some((_, old)) = swap.old_buffers.iter().find(|(s, _)| *s == scale.x) {
+                    let old_size = match swap.spec.sizing {
+                        SwapSizing::Align => swap.old_size.to_f64(),
+                        SwapSizing::Stretch => logical_size,
+                    };
+                    match MemoryRenderBufferRenderElement::from_buffer(
+                        renderer,
+                        location.to_f64()
+                            + (transition_offset + swap_old.offset).to_physical(scale),
+                        old,
+                        Some(alpha * transition.alpha * swap_old.alpha),
+                        Some(Rectangle::from_loc_and_size(
+                            (0., 0.),
+                            buffer::buffer_size(swap.old_size, scale.x)
+                                .to_f64()
+                                .to_logical(1.0, Transform::Normal),
+                        )),
+                        Some(old_size.upscale(transition.scale).to_i32_round()),
+                    ) {
+                        Ok(element) => out.push(C::from(element)),
+                        Err(err) => warn!(?err, "Failed to upload buffer of replaced program"),
+                    }
+                }
+            }
+
+            // layers are stacked above the base, topmost first
+            for spec in layers.iter().rev() {
+                let key = (spec.id, OrderedFloat(scale.x));
+                let Some(layer) = internal_ref.layer_buffers.get_mut(&key) else { continue };
+                match MemoryRenderBufferRenderElement::from_buffer(
+                    renderer,
+                    element_location
+                        + spec
+                            .bounds
+                            .loc
+                            .to_f64()
+                            .upscale(transition.scale)
+                            .to_physical(scale),
+                    layer.buffer.front(),
+                    Some(content_alpha),
+                    Some(Rectangle::from_loc_and_size(
+                        (0., 0.),
+                        layer
+                            .buffer
+                            .size()
+                            .to_f64()
+                            .to_logical(1.0, Transform::Normal),
+                    )),
+                    Some(
+                        spec.bounds
+                            .size
+                            .to_f64()
+                            .upscale(transition.scale)
+                            .to_i32_round(),
+                    ),
+                ) {
+                    Ok(element) => out.push(C::from(element)),
+                    Err(err) => {
+                        warn!(
+                            ?err,
+                            layer = spec.id,
+                            "Failed to upload layer buffer, retrying"
+                        );
+                        layer.buffer.invalidate();
+                        // layers are only redrawn alongside the base
+                        buffer.mark_dirty();
+                    }
+                }
+            }
+
+            let src = Rectangle::from_loc_and_size(
+                (0., 0.),
+                size.to_f64().to_logical(1.0, Transform::Normal),
+            );
+            match MemoryRenderBufferRenderElement::from_buffer(
+                renderer,
+                element_location,
+                buffer.front(),
+                Some(content_alpha),
+                Some(src),
+                Some(transition_size.to_i32_round()),
+            ) {
+                Ok(element) => {
+                    internal_ref.upload_failures = 0;
+                    out.push(C::from(element));
+                    // events whose draw is still pending are shown by a later frame
+                    if !buffer.needs_redraw() && !internal_ref.render_job_pending {
+                        internal_ref.traces.imported(Instant::now());
+                    }
+                    // the next render may show the same buffer as is, unless anything changes
+                    let reusable = layers.is_empty()
+                        && internal_ref.transition.is_none()
+                        && internal_ref.swap.is_none()
+                        && internal_ref.press_feedback.is_none()
+                        && internal_ref.idle_after.is_none()
+                        && !internal_ref.render_job_pending
+                        && !internal_ref.update_pending
+                        && !buffer.needs_redraw()
+                        && internal_ref.state.is_queue_empty();
+                    if reusable {
+                        internal_ref.clean.store(CachedFrame {
+                            scale: scale.x,
+                            buffer: buffer.front().clone(),
+                            src,
+                            size: transition_size.to_i32_round(),
+                        });
+                    } else {
+                        internal_ref.clean.mark_dirty();
+                    }
+                }
+                Err(err) => {
+                    if upload_failed(&mut internal_ref.upload_failures, scale.x, &err) {
+                        // start over with a fresh buffer on the next frame
+                        buffer.invalidate();
+                    }
+                }
+            }
+
+            // frame timing during secure entry reveals what is typed
+            if telemetry::is_enabled() && !internal_ref.secure_entry {
+                telemetry::record(FrameTelemetry {
+                    element_id: Arc::as_ptr(&self.0) as u64,
+                    name: internal_ref.name.clone(),
+                    update: internal_ref.render_update_duration,
+                    draw: draw_duration,
+                    upload: upload_start.elapsed(),
+                    buffer_size: size,
+                    skipped,
+                    sanitized: if skipped { 0 } else { internal_ref.sanitized },
+                });
+            }
+        }
+    }
+}
+
+impl<P, R> AsRenderElements<R> for IcedElement<P>
+where
+    P: Program + Send + 'static,
+    R: Renderer + ImportMem,
+    <R as Renderer>::TextureId: 'static,
+{
+    type RenderElement = MemoryRenderBufferRenderElement<R>;
+
+    fn render_elements<C: From<Self::RenderElement>>(
+        &self,
+        renderer: &mut R,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        alpha: f32,
+    ) -> Vec<C> {
+        let mut elements = Vec::new();
+        self.render_elements_into(renderer, location, scale, alpha, &mut elements);
+        elements
+    }
+}
+
+impl<P: Program + Send + 'static> TouchTarget<crate::state::State> for IcedElement<P> {
+    fn down(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        slot: TouchSlot,
+        location: Point<f64, Logical>,
+    ) {
+        self.touch_down(slot, location);
+    }
+
+    fn motion(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        slot: TouchSlot,
+        location: Point<f64, Logical>,
+    ) {
+        self.touch_motion(slot, location);
+    }
+
+    fn up(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        slot: TouchSlot,
+    ) {
+        self.touch_up(slot);
+    }
+
+    fn cancel(&self, _seat: &Seat<crate::state::State>, _data: &mut crate::state::State) {
+        self.touch_cancel();
+    }
+}
+
+impl<P: Program + Send + 'static> TabletPadTarget<crate::state::State> for IcedElement<P> {
+    fn pad_button(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        button: u32,
+        state: ButtonState,
+    ) {
+        self.with_program_mut(|program| program.on_pad_button(button, state));
+    }
+
+    fn pad_ring(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        source: RingEventSource,
+        degrees: f64,
+    ) {
+        self.with_program_mut(|program| program.on_pad_ring(source, degrees));
+    }
+
+    fn pad_strip(
+        &self,
+        _seat: &Seat<crate::state::State>,
+        _data: &mut crate::state::State,
+        source: StripEventSource,
+        position: f64,
+    ) {
+        self.with_program_mut(|program| program.on_pad_strip(source, position));
+    }
+}
@@ -0,0 +1,97 @@
+//! Defensive handling of pointer events delivered out of order.
+//!
+//! After focus changes the compositor may deliver a button or axis event to an element
+//! before its enter and motion, which iced would then process at a stale cursor position.
+//! Whether a seat entered the element is tracked per seat, events arriving without an enter
+//! are corrected as configured by `OrderingCorrection`. Corrections are logged and counted,
+//! so the upstream ordering bugs remain visible.
+
+use cosmic::iced_native::event::Event;
+use smithay::utils::{Logical, Point};
+use tracing::debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrderingCorrection {
+    /// Synthesize the missing enter and motion at the last known position of the seat
+    #[default]
+    Synthesize,
+    /// Hold back button events until the next motion of the seat, axis events are always
+    /// corrected by synthesizing
+    Defer,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct PointerOrdering {
+    pub mode: OrderingCorrection,
+    /// Ids of the seats, that entered since their last leave
+    entered: Vec<usize>,
+    /// Element local positions provided via `IcedElement::pointer_position_hint`
+    hints: Vec<(usize, Point<f64, Logical>)>,
+    deferred: Vec<(usize, Event)>,
+    pub corrections: u64,
+}
+
+impl PointerOrdering {
+    pub fn is_entered(&self, seat: usize) -> bool {
+        self.entered.contains(&seat)
+    }
+
+    /// Whether any seat entered the element.
+    pub fn is_hovered(&self) -> bool {
+        !self.entered.is_empty()
+    }
+
+    /// Marks `seat` as entered, returning the events deferred until then.
+    pub fn enter(&mut self, seat: usize) -> Vec<Event> {
+        if !self.is_entered(seat) {
+            self.entered.push(seat);
+        }
+        // superseded by the actual position
+        self.hints.retain(|(id, _)| *id != seat);
+        let (deferred, rest) = std::mem::take(&mut self.deferred)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(id, _)| *id == seat);
+        self.deferred = rest;
+        deferred.into_iter().map(|(_, event)| event).collect()
+    }
+
+    pub fn leave(&mut self, seat: usize) {
+        self.entered.retain(|id| *id != seat);
+        let before = self.deferred.len();
+        self.deferred.retain(|(id, _)| *id != seat);
+        if self.deferred.len() != before {
+            debug!(
+                seat,
+                dropped = before - self.deferred.len(),
+                "Dropping deferred pointer events of a seat, that left before moving"
+            );
+        }
+    }
+
+    pub fn hint(&mut self, seat: usize, position: Point<f64, Logical>) {
+        match self.hints.iter_mut().find(|(id, _)| *id == seat) {
+            Some((_, hint)) => *hint = position,
+            None => self.hints.push((seat, position)),
+        }
+    }
+
+    pub fn take_hint(&mut self, seat: usize) -> Option<Point<f64, Logical>> {
+        let i = self.hints.iter().position(|(id, _)| *id == seat)?;
+        Some(self.hints.remove(i).1)
+    }
+
+    pub fn defer(&mut self, seat: usize, event: Event) {
+        self.deferred.push((seat, event));
+    }
+
+    /// Records a correction of an `event` of `seat`, that arrived without an enter.
+    pub fn correct(&mut self, seat: usize, event: &'static str) {
+        self.corrections += 1;
+        debug!(
+            seat,
+            event,
+            corrections = self.corrections,
+            "Pointer event arrived without an enter, correcting"
+        );
+    }
+}
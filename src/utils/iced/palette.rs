@@ -0,0 +1,87 @@
+//! Theme colors for the raqote drawing hooks of programs.
+//!
+//! `Program::themed_background` and `Program::themed_foreground` draw outside of iced's styling,
+//! so instead of hardcoding colors they get a `HookPalette` of the element's current theme.
+//! Elements rebuild it whenever their theme changes, see `Program::uses_hooks`.
+
+use cosmic::{iced_native::Color, Theme};
+use iced_softbuffer::native::raqote::{SolidSource, Source};
+
+/// Semantic colors of a `HookPalette`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaletteRole {
+    /// Base color of the element
+    Background,
+    /// Containers drawn on top of the background, e.g. headers or cards
+    Surface,
+    /// Content drawn on top of a surface
+    OnSurface,
+    Accent,
+    Divider,
+    Warning,
+    Error,
+}
+
+impl PaletteRole {
+    const ALL: [PaletteRole; 7] = [
+        PaletteRole::Background,
+        PaletteRole::Surface,
+        PaletteRole::OnSurface,
+        PaletteRole::Accent,
+        PaletteRole::Divider,
+        PaletteRole::Warning,
+        PaletteRole::Error,
+    ];
+}
+
+/// Colors of a theme, ready to be used as raqote sources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookPalette {
+    colors: [Color; PaletteRole::ALL.len()],
+}
+
+impl HookPalette {
+    pub fn new(theme: &Theme) -> HookPalette {
+        let cosmic = theme.cosmic();
+        HookPalette {
+            colors: PaletteRole::ALL.map(|role| match role {
+                PaletteRole::Background => cosmic.bg_color().into(),
+                PaletteRole::Surface => cosmic.primary_container_color().into(),
+                PaletteRole::OnSurface => cosmic.on_primary_container_color().into(),
+                PaletteRole::Accent => cosmic.accent_color().into(),
+                PaletteRole::Divider => cosmic.bg_divider().into(),
+                PaletteRole::Warning => cosmic.warning_color().into(),
+                PaletteRole::Error => cosmic.destructive_color().into(),
+            }),
+        }
+    }
+
+    pub fn color(&self, role: PaletteRole) -> Color {
+        self.colors[role as usize]
+    }
+
+    /// Premultiplied color of `role`, e.g. for `DrawTarget::clear`.
+    pub fn solid(&self, role: PaletteRole) -> SolidSource {
+        self.solid_with_alpha(role, 1.0)
+    }
+
+    pub fn source(&self, role: PaletteRole) -> Source<'static> {
+        Source::Solid(self.solid(role))
+    }
+
+    /// Color of `role` with its alpha multiplied by `alpha`, premultiplied.
+    pub fn with_alpha(&self, role: PaletteRole, alpha: f32) -> Source<'static> {
+        Source::Solid(self.solid_with_alpha(role, alpha))
+    }
+
+    fn solid_with_alpha(&self, role: PaletteRole, alpha: f32) -> SolidSource {
+        let color = self.color(role);
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        SolidSource::from_unpremultiplied_argb(
+            channel(color.a * alpha),
+            channel(color.r),
+            channel(color.g),
+            channel(color.b),
+        )
+    }
+}
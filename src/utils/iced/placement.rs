@@ -0,0 +1,193 @@
+//! Placement of transient elements (OSDs, notifications, prompts).
+//!
+//! Transients appearing at the same time would otherwise stack on the same default position,
+//! or cover the caret of the window the user is typing in. Positions are tried per anchor in
+//! preference order, nudging away from the anchored edge by `PlacementPrefs::step`, and the first
+//! position not overlapping anything to avoid wins. If there is none, the least overlapping one
+//! is used. Elements placed via `IcedElement::place_transient` reserve their rectangle until
+//! they are dropped, so later transients flow around them.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use smithay::utils::{Logical, Point, Rectangle, Size};
+
+use super::lock_global;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AvoidSet {
+    /// Avoid the reservations of other transients
+    pub transients: bool,
+    pub focused_window: Option<Rectangle<i32, Logical>>,
+    /// Caret of the focused window, if known
+    pub caret: Option<Rectangle<i32, Logical>>,
+    pub other: Vec<Rectangle<i32, Logical>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacementPrefs {
+    /// Area to place within, e.g. the non-exclusive zone of an output
+    pub area: Rectangle<i32, Logical>,
+    /// Tried in order, the first is preferred
+    pub anchor_candidates: Vec<Anchor>,
+    pub avoid: AvoidSet,
+    /// Distance between positions tried for each anchor
+    pub step: i32,
+}
+
+lazy_static::lazy_static! {
+    static ref RESERVATIONS: Mutex<Vec<(u64, Rectangle<i32, Logical>)>> = Mutex::new(Vec::new());
+}
+static NEXT_RESERVATION: AtomicU64 = AtomicU64::new(0);
+
+fn anchor_location(
+    anchor: Anchor,
+    area: Rectangle<i32, Logical>,
+    size: Size<i32, Logical>,
+) -> Point<i32, Logical> {
+    let left = area.loc.x;
+    let center_x = area.loc.x + (area.size.w - size.w) / 2;
+    let right = area.loc.x + area.size.w - size.w;
+    let top = area.loc.y;
+    let center_y = area.loc.y + (area.size.h - size.h) / 2;
+    let bottom = area.loc.y + area.size.h - size.h;
+    match anchor {
+        Anchor::TopLeft => (left, top),
+        Anchor::Top => (center_x, top),
+        Anchor::TopRight => (right, top),
+        Anchor::Left => (left, center_y),
+        Anchor::Center => (center_x, center_y),
+        Anchor::Right => (right, center_y),
+        Anchor::BottomLeft => (left, bottom),
+        Anchor::Bottom => (center_x, bottom),
+        Anchor::BottomRight => (right, bottom),
+    }
+    .into()
+}
+
+/// Positions to try for `anchor`, starting at the anchor and moving away from its edge.
+fn candidates(
+    anchor: Anchor,
+    area: Rectangle<i32, Logical>,
+    size: Size<i32, Logical>,
+    step: i32,
+) -> Vec<Point<i32, Logical>> {
+    let start = anchor_location(anchor, area, size);
+    let step = step.max(1);
+    // vertically centered anchors move both ways, alternating
+    let directions: &[i32] = match anchor {
+        Anchor::TopLeft | Anchor::Top | Anchor::TopRight => &[1],
+        Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => &[-1],
+        Anchor::Left | Anchor::Center | Anchor::Right => &[1, -1],
+    };
+    let (min_y, max_y) = (area.loc.y, area.loc.y + area.size.h - size.h);
+
+    let mut positions = vec![start];
+    for k in 1.. {
+        let mut moved = false;
+        for direction in directions {
+            let y = start.y + direction * step * k;
+            if y >= min_y && y <= max_y {
+                positions.push((start.x, y).into());
+                moved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+    positions
+}
+
+fn overlap(a: Rectangle<i32, Logical>, b: Rectangle<i32, Logical>) -> i64 {
+    let w = (a.loc.x + a.size.w).min(b.loc.x + b.size.w) - a.loc.x.max(b.loc.x);
+    let h = (a.loc.y + a.size.h).min(b.loc.y + b.size.h) - a.loc.y.max(b.loc.y);
+    if w <= 0 || h <= 0 {
+        0
+    } else {
+        w as i64 * h as i64
+    }
+}
+
+fn place(
+    size: Size<i32, Logical>,
+    prefs: &PlacementPrefs,
+    reservations: &[(u64, Rectangle<i32, Logical>)],
+) -> Point<i32, Logical> {
+    let avoid = prefs
+        .avoid
+        .focused_window
+        .iter()
+        .chain(prefs.avoid.caret.iter())
+        .chain(prefs.avoid.other.iter())
+        .copied()
+        .chain(
+            reservations
+                .iter()
+                .filter(|_| prefs.avoid.transients)
+                .map(|(_, rect)| *rect),
+        )
+        .collect::<Vec<_>>();
+
+    let mut best: Option<(i64, Point<i32, Logical>)> = None;
+    for anchor in &prefs.anchor_candidates {
+        for position in candidates(*anchor, prefs.area, size, prefs.step) {
+            let rect = Rectangle::from_loc_and_size(position, size);
+            let overlapping = avoid.iter().map(|avoid| overlap(rect, *avoid)).sum::<i64>();
+            if overlapping == 0 {
+                return position;
+            }
+            // earlier candidates win ties, which keeps results stable
+            if best.map_or(true, |(least, _)| overlapping < least) {
+                best = Some((overlapping, position));
+            }
+        }
+    }
+    best.map_or(prefs.area.loc, |(_, position)| position)
+}
+
+/// Location for a transient of `size`, see the module documentation.
+///
+/// Doesn't reserve the returned rectangle, see `IcedElement::place_transient` for that.
+pub fn place_transient(size: Size<i32, Logical>, prefs: &PlacementPrefs) -> Point<i32, Logical> {
+    place(size, prefs, &lock_global(&RESERVATIONS))
+}
+
+/// Places a transient and reserves its rectangle, returns the reservation and location.
+pub(super) fn reserve(
+    size: Size<i32, Logical>,
+    prefs: &PlacementPrefs,
+) -> (u64, Point<i32, Logical>) {
+    let mut reservations = lock_global(&RESERVATIONS);
+    let location = place(size, prefs, &reservations);
+    let id = NEXT_RESERVATION.fetch_add(1, Ordering::Relaxed);
+    reservations.push((id, Rectangle::from_loc_and_size(location, size)));
+    (id, location)
+}
+
+/// Updates the size of a reservation, e.g. after its element was resized.
+pub(super) fn resize(id: u64, size: Size<i32, Logical>) {
+    let mut reservations = lock_global(&RESERVATIONS);
+    if let Some((_, rect)) = reservations.iter_mut().find(|(i, _)| *i == id) {
+        rect.size = size;
+    }
+}
+
+pub(super) fn release(id: u64) {
+    lock_global(&RESERVATIONS).retain(|(i, _)| *i != id);
+}
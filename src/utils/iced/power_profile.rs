@@ -0,0 +1,143 @@
+//! The active power profile of `power-profiles-daemon`, for applets showing or switching it,
+//! see `IcedElement::attach_power_profile_source`.
+
+use std::{fmt, str::FromStr};
+
+use cosmic::iced_native::futures::{future, stream, Stream, StreamExt};
+use tracing::warn;
+use zbus::{dbus_proxy, Connection};
+
+#[dbus_proxy(
+    interface = "net.hadess.PowerProfiles",
+    default_service = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles"
+)]
+trait PowerProfiles {
+    #[dbus_proxy(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+    #[dbus_proxy(property)]
+    fn set_active_profile(&self, profile: &str) -> zbus::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerProfile {
+    PowerSaver,
+    Balanced,
+    Performance,
+}
+
+impl PowerProfile {
+    /// The name of the profile on the bus.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PowerProfile::PowerSaver => "power-saver",
+            PowerProfile::Balanced => "balanced",
+            PowerProfile::Performance => "performance",
+        }
+    }
+}
+
+impl fmt::Display for PowerProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Unknown power profile: {0}")]
+pub struct UnknownPowerProfile(pub String);
+
+impl FromStr for PowerProfile {
+    type Err = UnknownPowerProfile;
+
+    fn from_str(profile: &str) -> Result<Self, Self::Err> {
+        match profile {
+            "power-saver" => Ok(PowerProfile::PowerSaver),
+            "balanced" => Ok(PowerProfile::Balanced),
+            "performance" => Ok(PowerProfile::Performance),
+            unknown => Err(UnknownPowerProfile(unknown.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerProfileEvent {
+    /// The active profile, delivered once on attaching and on every change.
+    ActiveProfile(PowerProfile),
+}
+
+/// A client of `net.hadess.PowerProfiles` on the system bus.
+#[derive(Clone)]
+pub struct PowerProfileSource {
+    proxy: PowerProfilesProxy<'static>,
+}
+
+impl fmt::Debug for PowerProfileSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PowerProfileSource").finish_non_exhaustive()
+    }
+}
+
+impl PowerProfileSource {
+    /// Connects to the daemon on the system bus.
+    pub async fn new() -> zbus::Result<PowerProfileSource> {
+        let connection = Connection::system().await?;
+        PowerProfileSource::with_connection(&connection).await
+    }
+
+    /// Connects to the daemon on an existing bus connection.
+    pub async fn with_connection(connection: &Connection) -> zbus::Result<PowerProfileSource> {
+        Ok(PowerProfileSource {
+            proxy: PowerProfilesProxy::new(connection).await?,
+        })
+    }
+
+    pub async fn active_profile(&self) -> zbus::Result<PowerProfile> {
+        let profile = self.proxy.active_profile().await?;
+        profile
+            .parse()
+            .map_err(|err: UnknownPowerProfile| zbus::Error::Failure(err.to_string()))
+    }
+
+    /// Switches the profile, attached elements get the change as `PowerProfileEvent`.
+    pub async fn set_profile(&self, profile: PowerProfile) -> zbus::Result<()> {
+        self.proxy.set_active_profile(profile.as_str()).await
+    }
+
+    /// The current profile followed by every change, unknown profiles are skipped.
+    pub(super) fn events(&self) -> impl Stream<Item = PowerProfileEvent> + Send + 'static {
+        let proxy = self.proxy.clone();
+        let mut last = None;
+        stream::once(async move {
+            // subscribe before reading, to not miss a change in between
+            let changes = proxy.receive_active_profile_changed().await;
+            let current = proxy.active_profile().await;
+            let changes = changes.then(|change| async move { change.get().await });
+            stream::once(future::ready(current)).chain(changes)
+        })
+        .flatten()
+        .filter_map(move |profile| {
+            let event = match profile {
+                Ok(profile) => parse_event(&profile),
+                Err(err) => {
+                    warn!(?err, "Failed to read the active power profile");
+                    None
+                }
+            };
+            // the change stream may start with the value just read
+            let event = event.filter(|event| last.replace(*event) != Some(*event));
+            future::ready(event)
+        })
+    }
+}
+
+/// Maps a profile name of the bus to an event, if it is known.
+pub(super) fn parse_event(profile: &str) -> Option<PowerProfileEvent> {
+    match profile.parse() {
+        Ok(profile) => Some(PowerProfileEvent::ActiveProfile(profile)),
+        Err(err) => {
+            warn!(%err, "Ignoring power profile");
+            None
+        }
+    }
+}
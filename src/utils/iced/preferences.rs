@@ -0,0 +1,76 @@
+//! Desktop-wide preferences of all `IcedElement`s besides fonts and theme.
+//!
+//! Iced's widgets don't know about any of them, so elements pass them to their program via
+//! `Program::preferences_changed`, for widgets to use explicitly. Unlike fonts and themes,
+//! preferences can't be overridden per element.
+
+use std::sync::Mutex;
+
+use super::lock_global;
+
+/// How much programs should animate, e.g. reduced for users sensitive to motion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnimationPolicy {
+    #[default]
+    Full,
+    /// Only short fades instead of movement
+    Reduced,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Contrast {
+    #[default]
+    Normal,
+    High,
+}
+
+/// Formatting of numbers, dates and times.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatConfig {
+    /// Locale like `de-DE`, the one of the environment if `None`
+    pub locale: Option<String>,
+    pub clock_24h: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preferences {
+    /// Factor for all text sizes, `1.0` by default
+    pub text_scale: f32,
+    pub animations: AnimationPolicy,
+    pub contrast: Contrast,
+    pub format: FormatConfig,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            text_scale: 1.0,
+            animations: AnimationPolicy::default(),
+            contrast: Contrast::default(),
+            format: FormatConfig::default(),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_PREFERENCES: Mutex<Preferences> = Mutex::new(Preferences::default());
+}
+
+pub(super) fn default_preferences() -> Preferences {
+    lock_global(&DEFAULT_PREFERENCES).clone()
+}
+
+/// Stores `value` in the `field` of the global default, returns false if it didn't change.
+pub(super) fn store_default<T: PartialEq>(
+    field: impl FnOnce(&mut Preferences) -> &mut T,
+    value: T,
+) -> bool {
+    let mut default = lock_global(&DEFAULT_PREFERENCES);
+    let field = field(&mut default);
+    if *field == value {
+        return false;
+    }
+    *field = value;
+    true
+}
@@ -0,0 +1,71 @@
+//! Optimistic press feedback, drawn before the program itself reacted to a press.
+
+use std::time::{Duration, Instant};
+
+use cosmic::Theme;
+use iced_softbuffer::native::raqote::{DrawOptions, DrawTarget, SolidSource, Source};
+use smithay::utils::{Logical, Rectangle};
+
+use super::animation::{Animation, Curve};
+
+const FADE_DURATION: Duration = Duration::from_millis(50);
+const TINT_ALPHA: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PressFeedback {
+    pub bounds: Rectangle<i32, Logical>,
+    fading: Option<Animation>,
+}
+
+impl PressFeedback {
+    pub fn new(bounds: Rectangle<i32, Logical>) -> PressFeedback {
+        PressFeedback {
+            bounds,
+            fading: None,
+        }
+    }
+
+    /// Starts fading out, e.g. on release or once the program drew its own pressed state.
+    pub fn fade(&mut self) {
+        self.fading.get_or_insert_with(|| {
+            Animation::new(Curve::EaseOut, FADE_DURATION, 1.0, 0.0, Instant::now())
+        });
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.fading.is_some()
+    }
+
+    /// Opacity at `now`, `None` once faded out completely.
+    pub fn alpha(&self, now: Instant) -> Option<f32> {
+        match self.fading {
+            None => Some(1.0),
+            Some(fading) => (!fading.is_complete(now)).then(|| fading.value(now)),
+        }
+    }
+}
+
+/// Tints the pressed area of `feedback` onto `target`, which is scaled by `scale`.
+pub(super) fn draw_press_feedback(
+    target: &mut DrawTarget<&mut [u32]>,
+    feedback: &PressFeedback,
+    alpha: f32,
+    scale: f32,
+    theme: &Theme,
+) {
+    let color: cosmic::iced_native::Color = theme.cosmic().on_bg_color().into();
+    let bounds = feedback.bounds;
+    target.fill_rect(
+        bounds.loc.x as f32 * scale,
+        bounds.loc.y as f32 * scale,
+        bounds.size.w as f32 * scale,
+        bounds.size.h as f32 * scale,
+        &Source::Solid(SolidSource::from_unpremultiplied_argb(
+            (TINT_ALPHA * alpha * 255.0) as u8,
+            (color.r * 255.0) as u8,
+            (color.g * 255.0) as u8,
+            (color.b * 255.0) as u8,
+        )),
+        &DrawOptions::new(),
+    );
+}
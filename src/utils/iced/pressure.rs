@@ -0,0 +1,71 @@
+//! Watches the kernel's memory pressure stall information (`/proc/pressure/memory`) and
+//! releases element memory, once tasks start stalling on memory.
+
+use super::{on_memory_pressure, MemoryPressureLevel};
+use anyhow::{Context, Result};
+use smithay::reexports::calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle, RegistrationToken,
+};
+use std::{path::Path, time::Duration};
+use tracing::{debug, warn};
+
+const PRESSURE_PATH: &str = "/proc/pressure/memory";
+/// How often the pressure stall information is read.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Percentage of the last 10 seconds some tasks stalled on memory, to release reused memory.
+const MODERATE_THRESHOLD: f32 = 10.0;
+/// Percentage of the last 10 seconds all tasks stalled on memory, to release all but the
+/// active buffers.
+const CRITICAL_THRESHOLD: f32 = 5.0;
+
+/// Maps the content of `/proc/pressure/memory` to a pressure level, if any.
+pub(super) fn parse_pressure(content: &str) -> Option<MemoryPressureLevel> {
+    let avg10 = |kind: &str| -> Option<f32> {
+        let line = content
+            .lines()
+            .find(|line| line.split_whitespace().next() == Some(kind))?;
+        line.split_whitespace()
+            .find_map(|field| field.strip_prefix("avg10="))?
+            .parse()
+            .ok()
+    };
+    if avg10("full").map_or(false, |full| full >= CRITICAL_THRESHOLD) {
+        Some(MemoryPressureLevel::Critical)
+    } else if avg10("some").map_or(false, |some| some >= MODERATE_THRESHOLD) {
+        Some(MemoryPressureLevel::Moderate)
+    } else {
+        None
+    }
+}
+
+/// Polls the memory pressure of the system and calls `on_memory_pressure` every time it rises.
+///
+/// Fails if the kernel doesn't provide pressure stall information.
+pub fn watch_memory_pressure(
+    handle: &LoopHandle<'static, crate::state::Data>,
+) -> Result<RegistrationToken> {
+    let path = Path::new(PRESSURE_PATH);
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut level = None;
+    handle
+        .insert_source(Timer::from_duration(POLL_INTERVAL), move |_, _, _| {
+            let new_level = match std::fs::read_to_string(path) {
+                Ok(content) => parse_pressure(&content),
+                Err(err) => {
+                    warn!(?err, "Failed to read memory pressure, stop watching it.");
+                    return TimeoutAction::Drop;
+                }
+            };
+            if new_level > level {
+                debug!(?new_level, "Memory pressure rose");
+                if let Some(new_level) = new_level {
+                    on_memory_pressure(new_level);
+                }
+            }
+            level = new_level;
+            TimeoutAction::ToDuration(POLL_INTERVAL)
+        })
+        .map_err(|err| anyhow::anyhow!("Failed to init the memory pressure watcher: {}", err.error))
+}
@@ -0,0 +1,218 @@
+//! State of a program's user interface, like `iced_native::program::State`.
+//!
+//! Additionally gives mutable access to the program and runs widget operations (e.g. focusing a
+//! widget) on the cached widget tree, which iced's version doesn't expose. Layout passes are
+//! counted, so callers can verify that updates are merged.
+
+use cosmic::iced_native::{
+    clipboard::Clipboard,
+    command::Command,
+    event::{self, Event},
+    mouse,
+    program::Program,
+    renderer,
+    user_interface::{self, UserInterface},
+    widget::operation::{Operation, Outcome},
+    Debug, Point, Size,
+};
+
+type Theme<P> = <<P as Program>::Renderer as cosmic::iced_native::Renderer>::Theme;
+
+pub(super) struct ProgramState<P: Program> {
+    program: P,
+    cache: Option<user_interface::Cache>,
+    queued_events: Vec<Event>,
+    queued_messages: Vec<P::Message>,
+    mouse_interaction: mouse::Interaction,
+    /// Layout passes since creation
+    layouts: u64,
+    /// Keeps messages out of the log of `Debug`, e.g. while a password is typed
+    redact_messages: bool,
+}
+
+impl<P: Program> ProgramState<P> {
+    pub fn new(
+        mut program: P,
+        bounds: Size,
+        renderer: &mut P::Renderer,
+        debug: &mut Debug,
+    ) -> ProgramState<P> {
+        let mut layouts = 0;
+        let user_interface = build_user_interface(
+            &mut program,
+            user_interface::Cache::new(),
+            renderer,
+            bounds,
+            debug,
+            &mut layouts,
+        );
+        let cache = Some(user_interface.into_cache());
+        ProgramState {
+            program,
+            cache,
+            queued_events: Vec::new(),
+            queued_messages: Vec::new(),
+            mouse_interaction: mouse::Interaction::Idle,
+            layouts,
+            redact_messages: false,
+        }
+    }
+
+    pub fn program(&self) -> &P {
+        &self.program
+    }
+
+    /// Changes the program outside of `update`, the next update rebuilds its view.
+    pub fn program_mut(&mut self) -> &mut P {
+        &mut self.program
+    }
+
+    pub fn set_redact_messages(&mut self, redact: bool) {
+        self.redact_messages = redact;
+    }
+
+    pub fn queue_event(&mut self, event: Event) {
+        self.queued_events.push(event);
+    }
+
+    pub fn queue_message(&mut self, message: P::Message) {
+        self.queued_messages.push(message);
+    }
+
+    pub fn is_queue_empty(&self) -> bool {
+        self.queued_events.is_empty() && self.queued_messages.is_empty()
+    }
+
+    pub fn mouse_interaction(&self) -> mouse::Interaction {
+        self.mouse_interaction
+    }
+
+    pub fn layouts(&self) -> u64 {
+        self.layouts
+    }
+
+    /// Processes the queued events and messages, returns the events no widget captured and the
+    /// commands of the program, if it received any messages.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        bounds: Size,
+        cursor_position: Point,
+        renderer: &mut P::Renderer,
+        theme: &Theme<P>,
+        style: &renderer::Style,
+        clipboard: &mut dyn Clipboard,
+        debug: &mut Debug,
+    ) -> (Vec<Event>, Option<Command<P::Message>>) {
+        let mut user_interface = build_user_interface(
+            &mut self.program,
+            self.cache.take().unwrap_or_else(user_interface::Cache::new),
+            renderer,
+            bounds,
+            debug,
+            &mut self.layouts,
+        );
+
+        debug.event_processing_started();
+        let mut messages = Vec::new();
+        let (_, event_statuses) = user_interface.update(
+            &self.queued_events,
+            cursor_position,
+            renderer,
+            clipboard,
+            &mut messages,
+        );
+        let uncaptured_events = self
+            .queued_events
+            .drain(..)
+            .zip(event_statuses)
+            .filter_map(|(event, status)| matches!(status, event::Status::Ignored).then_some(event))
+            .collect();
+        messages.append(&mut self.queued_messages);
+        debug.event_processing_finished();
+
+        if messages.is_empty() {
+            debug.draw_started();
+            self.mouse_interaction = user_interface.draw(renderer, theme, style, cursor_position);
+            debug.draw_finished();
+            self.cache = Some(user_interface.into_cache());
+            return (uncaptured_events, None);
+        }
+
+        // the view changes with the messages, so it is built again
+        let cache = user_interface.into_cache();
+        let commands = Command::batch(messages.into_iter().map(|message| {
+            if !self.redact_messages {
+                debug.log_message(&message);
+            }
+            debug.update_started();
+            let command = self.program.update(message);
+            debug.update_finished();
+            command
+        }));
+        let mut user_interface = build_user_interface(
+            &mut self.program,
+            cache,
+            renderer,
+            bounds,
+            debug,
+            &mut self.layouts,
+        );
+        debug.draw_started();
+        self.mouse_interaction = user_interface.draw(renderer, theme, style, cursor_position);
+        debug.draw_finished();
+        self.cache = Some(user_interface.into_cache());
+
+        (uncaptured_events, Some(commands))
+    }
+
+    /// Runs `operations` on the widget tree, messages they produce are queued for the next
+    /// update.
+    pub fn operate(
+        &mut self,
+        renderer: &mut P::Renderer,
+        operations: impl IntoIterator<Item = Box<dyn Operation<P::Message>>>,
+        bounds: Size,
+        debug: &mut Debug,
+    ) {
+        let mut user_interface = build_user_interface(
+            &mut self.program,
+            self.cache.take().unwrap_or_else(user_interface::Cache::new),
+            renderer,
+            bounds,
+            debug,
+            &mut self.layouts,
+        );
+        for operation in operations {
+            let mut current = Some(operation);
+            while let Some(mut operation) = current.take() {
+                user_interface.operate(renderer, operation.as_mut());
+                match operation.finish() {
+                    Outcome::None => {}
+                    Outcome::Some(message) => self.queued_messages.push(message),
+                    Outcome::Chain(next) => current = Some(next),
+                }
+            }
+        }
+        self.cache = Some(user_interface.into_cache());
+    }
+}
+
+fn build_user_interface<'a, P: Program>(
+    program: &'a mut P,
+    cache: user_interface::Cache,
+    renderer: &mut P::Renderer,
+    size: Size,
+    debug: &mut Debug,
+    layouts: &mut u64,
+) -> UserInterface<'a, P::Message, P::Renderer> {
+    debug.view_started();
+    let view = program.view();
+    debug.view_finished();
+
+    debug.layout_started();
+    let user_interface = UserInterface::build(view, size, cache, renderer);
+    debug.layout_finished();
+    *layouts += 1;
+    user_interface
+}
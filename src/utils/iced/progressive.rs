@@ -0,0 +1,64 @@
+//! Progressive first rendering of programs, whose full first layout is expensive.
+//!
+//! Programs returning a `ProgressivePlan` from `Program::progressive` start out with their first
+//! stage (which has to be cheap) and are shown right away. Every further stage is loaded from
+//! an idle callback of the event loop, one stage per loop iteration, so input arriving in between
+//! is processed before the next stage. Programs learn about loaded stages via
+//! `Program::progressive_stage`, e.g. to replace skeleton placeholders.
+
+use std::time::Duration;
+
+use smithay::reexports::calloop::Idle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressivePlan {
+    /// Number of stages including the first one, which is shown right away
+    pub stages: u32,
+    /// Stages taking longer to load than this are logged, and should be split further
+    pub stage_budget: Duration,
+}
+
+impl ProgressivePlan {
+    pub fn new(stages: u32) -> ProgressivePlan {
+        ProgressivePlan {
+            stages,
+            stage_budget: Duration::from_millis(4),
+        }
+    }
+
+    pub fn with_stage_budget(mut self, stage_budget: Duration) -> ProgressivePlan {
+        self.stage_budget = stage_budget;
+        self
+    }
+}
+
+pub(super) struct ProgressiveState {
+    pub plan: ProgressivePlan,
+    /// Last loaded stage
+    pub stage: u32,
+    /// Loads the next stage
+    pub idle: Option<Idle<'static>>,
+}
+
+impl ProgressiveState {
+    /// State of a program, that just showed its first stage, `None` if there is nothing to load.
+    pub fn new(plan: ProgressivePlan) -> Option<ProgressiveState> {
+        (plan.stages > 1).then(|| ProgressiveState {
+            plan,
+            stage: 0,
+            idle: None,
+        })
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.stage + 1 >= self.plan.stages
+    }
+}
+
+impl Drop for ProgressiveState {
+    fn drop(&mut self) {
+        if let Some(idle) = self.idle.take() {
+            idle.cancel();
+        }
+    }
+}
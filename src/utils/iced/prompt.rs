@@ -0,0 +1,280 @@
+//! Reusable confirm/cancel prompt.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use cosmic::{
+    iced::widget::{button, container, horizontal_space, text, Column, Row},
+    iced_native::{Command, Length},
+    Element,
+};
+use smithay::reexports::calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle,
+};
+
+use super::{IcedElement, Program, ShellRequest, UpdateContext};
+
+const PROMPT_SIZE: (i32, i32) = (420, 180);
+const TICK: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptAction {
+    Confirm,
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptResult {
+    Confirmed,
+    Cancelled,
+    /// The timeout expired, the timeout action was applied
+    TimedOut(PromptAction),
+}
+
+#[derive(Debug, Clone)]
+pub struct PromptSpec {
+    pub title: String,
+    pub body: String,
+    pub confirm_label: String,
+    pub cancel_label: String,
+    /// Action triggered by accelerators (Enter)
+    pub default_action: PromptAction,
+    /// Resolves the prompt after the given duration, if no choice was made
+    pub timeout: Option<(Duration, PromptAction)>,
+}
+
+#[derive(Default)]
+struct Resolution {
+    result: Option<PromptResult>,
+    callback: Option<Box<dyn FnOnce(PromptResult) + Send>>,
+    waker: Option<Waker>,
+}
+
+/// Shared between `PromptProgram` and `PromptHandle`, guarantees a single resolution.
+#[derive(Clone, Default)]
+struct Resolver(Arc<Mutex<Resolution>>);
+
+impl Resolver {
+    /// Returns false, if the prompt was already resolved.
+    fn resolve(&self, result: PromptResult) -> bool {
+        let mut resolution = self.0.lock().unwrap();
+        if resolution.result.is_some() {
+            return false;
+        }
+        resolution.result = Some(result);
+        let callback = resolution.callback.take();
+        let waker = resolution.waker.take();
+        drop(resolution);
+
+        if let Some(callback) = callback {
+            callback(result);
+        }
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        true
+    }
+
+    fn is_resolved(&self) -> bool {
+        self.0.lock().unwrap().result.is_some()
+    }
+}
+
+/// Resolves once the prompt was answered, timed out or closed.
+///
+/// Can be awaited or used with a callback via `on_resolved`.
+pub struct PromptHandle(Resolver);
+
+impl PromptHandle {
+    pub fn result(&self) -> Option<PromptResult> {
+        self.0 .0.lock().unwrap().result
+    }
+
+    /// Calls `callback` once the prompt is resolved, or immediately if it already is.
+    pub fn on_resolved(self, callback: impl FnOnce(PromptResult) + Send + 'static) {
+        let mut resolution = self.0 .0.lock().unwrap();
+        match resolution.result {
+            Some(result) => {
+                drop(resolution);
+                callback(result);
+            }
+            None => resolution.callback = Some(Box::new(callback)),
+        }
+    }
+}
+
+impl Future for PromptHandle {
+    type Output = PromptResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut resolution = self.0 .0.lock().unwrap();
+        match resolution.result {
+            Some(result) => Poll::Ready(result),
+            None => {
+                resolution.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PromptMessage {
+    Confirm,
+    Cancel,
+    /// Triggers the default action
+    Accept,
+    Tick,
+}
+
+pub struct PromptProgram {
+    spec: PromptSpec,
+    remaining: Option<Duration>,
+    resolver: Resolver,
+}
+
+impl PromptProgram {
+    /// Deterministic state for golden images, see `golden`.
+    #[cfg(any(test, feature = "test-helpers"))]
+    pub fn sample_state() -> PromptProgram {
+        PromptProgram {
+            spec: PromptSpec {
+                title: String::from("Keep these display settings?"),
+                body: String::from("Reverting in 10 seconds."),
+                confirm_label: String::from("Keep"),
+                cancel_label: String::from("Revert"),
+                default_action: PromptAction::Cancel,
+                timeout: Some((Duration::from_secs(10), PromptAction::Cancel)),
+            },
+            remaining: Some(Duration::from_secs(10)),
+            resolver: Resolver::default(),
+        }
+    }
+
+    fn resolve(&self, result: PromptResult, ctx: &mut UpdateContext<'_>) {
+        if self.resolver.resolve(result) {
+            ctx.request(ShellRequest::CloseElement);
+        }
+    }
+}
+
+impl Drop for PromptProgram {
+    fn drop(&mut self) {
+        // closed externally
+        self.resolver.resolve(PromptResult::Cancelled);
+    }
+}
+
+impl Program for PromptProgram {
+    type Message = PromptMessage;
+    crate::cloneable_message!();
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        match message {
+            PromptMessage::Confirm => self.resolve(PromptResult::Confirmed, ctx),
+            PromptMessage::Cancel => self.resolve(PromptResult::Cancelled, ctx),
+            PromptMessage::Accept => self.resolve(
+                match self.spec.default_action {
+                    PromptAction::Confirm => PromptResult::Confirmed,
+                    PromptAction::Cancel => PromptResult::Cancelled,
+                },
+                ctx,
+            ),
+            PromptMessage::Tick => {
+                if let Some(remaining) = self.remaining.as_mut() {
+                    *remaining = remaining.saturating_sub(TICK);
+                    if remaining.is_zero() {
+                        let action = self.spec.timeout.map(|(_, action)| action).unwrap();
+                        self.resolve(PromptResult::TimedOut(action), ctx);
+                    }
+                }
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        let mut content = Column::new()
+            .spacing(12)
+            .push(text(self.spec.title.clone()).size(20))
+            .push(text(self.spec.body.clone()));
+
+        if let Some(remaining) = self.remaining {
+            content = content.push(text(format!(
+                "{} in {} seconds",
+                match self.spec.timeout.map(|(_, action)| action) {
+                    Some(PromptAction::Confirm) => &self.spec.confirm_label,
+                    _ => &self.spec.cancel_label,
+                },
+                remaining.as_secs()
+            )));
+        }
+
+        content = content.push(
+            Row::new()
+                .spacing(8)
+                .push(horizontal_space(Length::Fill))
+                .push(button(text(self.spec.cancel_label.clone())).on_press(PromptMessage::Cancel))
+                .push(
+                    button(text(self.spec.confirm_label.clone())).on_press(PromptMessage::Confirm),
+                ),
+        );
+
+        container(content)
+            .padding(16)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+/// Creates a confirm/cancel prompt.
+///
+/// The element issues `ShellRequest::CloseElement` once resolved, the caller is expected to
+/// unmap it in its request handler. Dropping the element without an answer resolves the
+/// handle as `PromptResult::Cancelled`.
+pub fn prompt(
+    spec: PromptSpec,
+    handle: LoopHandle<'static, crate::state::Data>,
+) -> (IcedElement<PromptProgram>, PromptHandle) {
+    let resolver = Resolver::default();
+    let timeout = spec.timeout.map(|(duration, _)| duration);
+    let program = PromptProgram {
+        spec,
+        // round up to full seconds for the countdown
+        remaining: timeout
+            .map(|t| Duration::from_secs(t.as_secs() + (t.subsec_nanos() > 0) as u64)),
+        resolver: resolver.clone(),
+    };
+    let element = IcedElement::new(program, PROMPT_SIZE, handle.clone());
+
+    if timeout.is_some() {
+        let weak = Arc::downgrade(&element.0);
+        let tick_resolver = resolver.clone();
+        if let Err(err) =
+            handle.insert_source(Timer::from_duration(TICK), move |_, _, _| {
+                match weak.upgrade() {
+                    Some(internal) if !tick_resolver.is_resolved() => {
+                        IcedElement::from_internal(internal).queue_message(PromptMessage::Tick);
+                        TimeoutAction::ToDuration(TICK)
+                    }
+                    _ => TimeoutAction::Drop,
+                }
+            })
+        {
+            tracing::warn!(?err, "Failed to start prompt timeout");
+        }
+    }
+
+    (element, PromptHandle(resolver))
+}
@@ -0,0 +1,418 @@
+//! Applets running in confined worker processes, see `IcedElementProxy`.
+//!
+//! The worker runs the applet's `SandboxedProgram` and describes its view as a tree of
+//! `ViewNode`s, which the compositor renders. The two talk over a Unix socket in frames of
+//! a `u32` length (little endian) followed by that many bytes of JSON:
+//! - the worker sends a `ViewNode` tree initially and after every update changing it,
+//! - the compositor sends the messages of widgets the user interacted with.
+//!
+//! Workers sending malformed or oversized frames, or not keeping up with messages,
+//! are killed and the applet is replaced by a placeholder.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    marker::PhantomData,
+    os::unix::{
+        io::{AsRawFd, FromRawFd, RawFd},
+        net::UnixStream,
+    },
+    process::{Child, Command as ProcessCommand},
+    sync::{Arc, Mutex},
+};
+
+use cosmic::{
+    iced::widget::{button, container, text, Column, Row, Space},
+    iced_native::{Command, Length},
+    Element,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use smithay::reexports::calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction};
+use tracing::warn;
+
+use super::{
+    confinement::{confine, WorkerConfinement},
+    Program, Subscription,
+};
+
+/// Environment variable holding the fd of the worker's socket.
+pub(super) const WORKER_SOCKET_ENV: &str = "COSMIC_APPLET_SOCKET";
+/// Frames beyond this size are rejected, views are expected to be small.
+pub(super) const MAX_FRAME_LEN: usize = 1 << 20;
+
+/// A serializable description of a view, rendered by the compositor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ViewNode<M> {
+    Text {
+        content: String,
+        size: Option<u16>,
+    },
+    Button {
+        child: Box<ViewNode<M>>,
+        on_press: Option<M>,
+    },
+    Column {
+        children: Vec<ViewNode<M>>,
+        spacing: u16,
+        padding: u16,
+    },
+    Row {
+        children: Vec<ViewNode<M>>,
+        spacing: u16,
+        padding: u16,
+    },
+    Container {
+        child: Box<ViewNode<M>>,
+        padding: u16,
+    },
+    Space {
+        width: u16,
+        height: u16,
+    },
+}
+
+impl<M: Clone + 'static> ViewNode<M> {
+    pub fn to_element(&self) -> Element<'_, M> {
+        match self {
+            ViewNode::Text { content, size } => {
+                let text = text(content);
+                match size {
+                    Some(size) => text.size(*size).into(),
+                    None => text.into(),
+                }
+            }
+            ViewNode::Button { child, on_press } => {
+                let button = button(child.to_element());
+                match on_press {
+                    Some(message) => button.on_press(message.clone()).into(),
+                    None => button.into(),
+                }
+            }
+            ViewNode::Column {
+                children,
+                spacing,
+                padding,
+            } => Column::with_children(children.iter().map(ViewNode::to_element).collect())
+                .spacing(*spacing)
+                .padding(*padding)
+                .into(),
+            ViewNode::Row {
+                children,
+                spacing,
+                padding,
+            } => Row::with_children(children.iter().map(ViewNode::to_element).collect())
+                .spacing(*spacing)
+                .padding(*padding)
+                .into(),
+            ViewNode::Container { child, padding } => {
+                container(child.to_element()).padding(*padding).into()
+            }
+            ViewNode::Space { width, height } => {
+                Space::new(Length::Units(*width), Length::Units(*height)).into()
+            }
+        }
+    }
+}
+
+/// The program of an applet, run by its worker process, see `run_sandboxed_worker`.
+pub trait SandboxedProgram {
+    type Message: fmt::Debug + Clone + Send + Serialize + DeserializeOwned + 'static;
+
+    fn update(&mut self, message: Self::Message);
+    fn view(&self) -> ViewNode<Self::Message>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(super) enum FrameError {
+    #[error("Frame of {0} bytes exceeds the limit")]
+    TooLarge(usize),
+    #[error("Malformed frame: {0}")]
+    Malformed(#[from] serde_json::Error),
+}
+
+pub(super) fn encode_frame(value: &impl Serialize) -> Result<Vec<u8>, FrameError> {
+    let json = serde_json::to_vec(value)?;
+    if json.len() > MAX_FRAME_LEN {
+        return Err(FrameError::TooLarge(json.len()));
+    }
+    let mut frame = Vec::with_capacity(4 + json.len());
+    frame.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&json);
+    Ok(frame)
+}
+
+/// Splits bytes read from a non-blocking socket into frames.
+#[derive(Debug, Default)]
+pub(super) struct FrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl FrameDecoder {
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The next complete frame, if any.
+    pub fn next_frame<T: DeserializeOwned>(&mut self) -> Option<Result<T, FrameError>> {
+        let len = u32::from_le_bytes(self.buffer.get(..4)?.try_into().unwrap()) as usize;
+        if len > MAX_FRAME_LEN {
+            return Some(Err(FrameError::TooLarge(len)));
+        }
+        let frame = self.buffer.get(4..4 + len)?;
+        let value = serde_json::from_slice(frame).map_err(FrameError::from);
+        self.buffer.drain(..4 + len);
+        Some(value)
+    }
+}
+
+/// Reads a frame from a blocking socket, `None` once it is closed.
+fn read_frame<T: DeserializeOwned>(socket: &mut impl Read) -> io::Result<Option<T>> {
+    let mut len = [0u8; 4];
+    match socket.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            FrameError::TooLarge(len),
+        ));
+    }
+    let mut frame = vec![0; len];
+    socket.read_exact(&mut frame)?;
+    serde_json::from_slice(&frame)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn invalid_data(err: FrameError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Runs `program` in a worker launched by `IcedElementProxy::spawn`, until the compositor
+/// closes the socket.
+pub fn run_sandboxed_worker<P: SandboxedProgram>(program: P) -> io::Result<()> {
+    let fd: RawFd = std::env::var(WORKER_SOCKET_ENV)
+        .ok()
+        .and_then(|fd| fd.parse().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Not launched by IcedElementProxy::spawn",
+            )
+        })?;
+    // SAFETY: the fd was passed on by the compositor, for this process only
+    let socket = unsafe { UnixStream::from_raw_fd(fd) };
+    serve(program, socket)
+}
+
+/// Sends the view of `program` and applies messages received over `socket`.
+pub(super) fn serve<P: SandboxedProgram>(mut program: P, mut socket: UnixStream) -> io::Result<()> {
+    let mut last_view = None;
+    loop {
+        let view = encode_frame(&program.view()).map_err(invalid_data)?;
+        if last_view.as_ref() != Some(&view) {
+            socket.write_all(&view)?;
+            last_view = Some(view);
+        }
+        let Some(message) = read_frame(&mut socket)? else { return Ok(()) };
+        program.update(message);
+    }
+}
+
+#[derive(Debug)]
+pub enum ProxyMessage<M> {
+    /// A new view of the worker
+    View(ViewNode<M>),
+    /// A widget was interacted with, forwarded to the worker
+    Message(M),
+    /// The worker closed its socket or was killed
+    Exited,
+}
+
+/// The compositor side of an applet running in a confined worker process.
+///
+/// Use it as the program of an `IcedElement`, ideally wrapped in `Sandboxed` to also limit
+/// the views the worker may send.
+pub struct IcedElementProxy<P: SandboxedProgram> {
+    socket: UnixStream,
+    /// Taken by the subscription reading from the worker
+    reader: Arc<Mutex<Option<UnixStream>>>,
+    child: Option<Child>,
+    view: Option<ViewNode<P::Message>>,
+    exited: bool,
+    _program: PhantomData<fn() -> P>,
+}
+
+impl<P: SandboxedProgram> fmt::Debug for IcedElementProxy<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IcedElementProxy")
+            .field("child", &self.child)
+            .field("exited", &self.exited)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: SandboxedProgram> IcedElementProxy<P> {
+    /// Launches `command` as a confined worker, which is expected to call
+    /// `run_sandboxed_worker`.
+    ///
+    /// Fails if the worker can't be confined, e.g. on kernels without landlock.
+    pub fn spawn(
+        mut command: ProcessCommand,
+        confinement: &WorkerConfinement,
+    ) -> io::Result<IcedElementProxy<P>> {
+        let (socket, worker_socket) = UnixStream::pair()?;
+        confine(&mut command, confinement, worker_socket.as_raw_fd())?;
+        let child = command.spawn()?;
+        drop(worker_socket);
+        let mut proxy = IcedElementProxy::with_socket(socket)?;
+        proxy.child = Some(child);
+        Ok(proxy)
+    }
+
+    /// A proxy of a worker on the other end of `socket`, which is already confined.
+    pub(super) fn with_socket(socket: UnixStream) -> io::Result<IcedElementProxy<P>> {
+        socket.set_nonblocking(true)?;
+        let reader = socket.try_clone()?;
+        Ok(IcedElementProxy {
+            socket,
+            reader: Arc::new(Mutex::new(Some(reader))),
+            child: None,
+            view: None,
+            exited: false,
+            _program: PhantomData,
+        })
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// The last view sent by the worker.
+    pub fn current_view(&self) -> Option<&ViewNode<P::Message>> {
+        self.view.as_ref()
+    }
+
+    fn forward(&mut self, message: &P::Message) {
+        let result = encode_frame(message)
+            .map_err(invalid_data)
+            .and_then(|frame| self.socket.write_all(&frame));
+        if let Err(err) = result {
+            // `WouldBlock` means the worker doesn't keep up with its messages
+            warn!(
+                ?err,
+                "Failed to forward message to applet worker, stopping it"
+            );
+            self.stop();
+        }
+    }
+
+    fn stop(&mut self) {
+        self.exited = true;
+        self.view = None;
+        let _ = self.socket.shutdown(std::net::Shutdown::Both);
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl<P: SandboxedProgram> Drop for IcedElementProxy<P> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Reads the views sent by a worker and delivers them to its proxy.
+fn read_views<M: DeserializeOwned + 'static>(
+    handle: &LoopHandle<'static, crate::state::Data>,
+    reader: UnixStream,
+    sink: super::subscription::Sink<ProxyMessage<M>>,
+) -> Option<smithay::reexports::calloop::RegistrationToken> {
+    let mut decoder = FrameDecoder::default();
+    handle
+        .insert_source(
+            Generic::new(reader, Interest::READ, Mode::Level),
+            move |_, reader, _| {
+                let mut chunk = [0u8; 4096];
+                loop {
+                    match reader.read(&mut chunk) {
+                        Ok(0) => {
+                            sink(ProxyMessage::Exited);
+                            return Ok(PostAction::Remove);
+                        }
+                        Ok(read) => decoder.push(&chunk[..read]),
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                        Err(err) => {
+                            warn!(?err, "Failed to read from applet worker");
+                            sink(ProxyMessage::Exited);
+                            return Ok(PostAction::Remove);
+                        }
+                    }
+                }
+                while let Some(view) = decoder.next_frame() {
+                    match view {
+                        Ok(view) => sink(ProxyMessage::View(view)),
+                        Err(err) => {
+                            warn!(?err, "Applet worker sent an invalid view");
+                            sink(ProxyMessage::Exited);
+                            return Ok(PostAction::Remove);
+                        }
+                    }
+                }
+                Ok(PostAction::Continue)
+            },
+        )
+        .map_err(|err| warn!(?err.error, "Failed to read from applet worker"))
+        .ok()
+}
+
+impl<P: SandboxedProgram> Program for IcedElementProxy<P> {
+    type Message = ProxyMessage<P::Message>;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, crate::state::Data>,
+    ) -> Command<Self::Message> {
+        match message {
+            ProxyMessage::View(view) if !self.exited => self.view = Some(view),
+            ProxyMessage::View(_) => {}
+            ProxyMessage::Message(message) if !self.exited => self.forward(&message),
+            ProxyMessage::Message(_) => {}
+            ProxyMessage::Exited => self.stop(),
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        if self.exited {
+            return container(text("This applet stopped"))
+                .padding(16)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into();
+        }
+        match &self.view {
+            Some(view) => view.to_element().map(ProxyMessage::Message),
+            None => Space::new(Length::Shrink, Length::Shrink).into(),
+        }
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription<Self::Message>> {
+        let reader = self.reader.clone();
+        vec![Subscription::source(
+            "applet-worker",
+            move |handle, sink| {
+                let reader = reader.lock().unwrap().take()?;
+                read_views(handle, reader, sink)
+            },
+        )]
+    }
+}
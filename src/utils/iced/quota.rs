@@ -0,0 +1,211 @@
+//! Per-element limits on concurrently running futures of `Command`s.
+//!
+//! Futures beyond `max_concurrent` are queued, highest priority first and in order of scheduling
+//! within a priority. Once more than `max_queued` are waiting, the oldest future of the lowest
+//! priority is dropped without ever being polled. Elements staying below their quota only pay for
+//! a short lock when scheduling and completing futures.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex, Weak},
+};
+
+use calloop::futures::Scheduler;
+use cosmic::iced_native::futures::future::BoxFuture;
+use tracing::warn;
+
+pub(super) const DEFAULT_MAX_CONCURRENT: usize = 64;
+pub(super) const DEFAULT_MAX_QUEUED: usize = 256;
+
+/// Order in which queued futures are started, see `UpdateContext::spawn_prioritized`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum AsyncPriority {
+    Low,
+    /// Futures of plain `Command`s
+    #[default]
+    Normal,
+    High,
+}
+
+/// Futures of an element, see `IcedElement::async_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AsyncQuotaStats {
+    pub running: usize,
+    pub queued: usize,
+    /// Futures dropped from the queue over the element's lifetime
+    pub dropped: u64,
+}
+
+struct Queued<M> {
+    priority: AsyncPriority,
+    seq: u64,
+    future: BoxFuture<'static, M>,
+}
+
+struct QuotaState<M> {
+    scheduler: Scheduler<M>,
+    max_concurrent: usize,
+    max_queued: usize,
+    running: usize,
+    queue: Vec<Queued<M>>,
+    next_seq: u64,
+    dropped: u64,
+    closed: bool,
+}
+
+impl<M> QuotaState<M> {
+    /// Takes the next queued future, if another one may run.
+    fn next(&mut self) -> Option<BoxFuture<'static, M>> {
+        if self.closed || self.running >= self.max_concurrent {
+            return None;
+        }
+        let (i, _) = self
+            .queue
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, queued)| (queued.priority, std::cmp::Reverse(queued.seq)))?;
+        self.running += 1;
+        Some(self.queue.remove(i).future)
+    }
+
+    /// Drops queued futures exceeding `max_queued`, returns how many.
+    fn trim(&mut self) -> usize {
+        let mut dropped = 0;
+        while self.queue.len() > self.max_queued {
+            let Some((i, _)) = self
+                .queue
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, queued)| (queued.priority, queued.seq))
+            else { break };
+            self.queue.remove(i);
+            dropped += 1;
+        }
+        self.dropped += dropped as u64;
+        dropped
+    }
+}
+
+/// Frees the slot of a running future, once it completed or was dropped.
+struct Permit<M: Send + 'static>(Weak<Mutex<QuotaState<M>>>);
+
+impl<M: Send + 'static> Drop for Permit<M> {
+    fn drop(&mut self) {
+        let Some(state) = self.0.upgrade() else { return };
+        let next = {
+            let mut inner = state.lock().unwrap();
+            inner.running -= 1;
+            inner.next()
+        };
+        if let Some(future) = next {
+            start(&state, future);
+        }
+    }
+}
+
+/// Hands `future` to the executor, its slot has to be taken already.
+fn start<M: Send + 'static>(state: &Arc<Mutex<QuotaState<M>>>, future: BoxFuture<'static, M>) {
+    let permit = Permit(Arc::downgrade(state));
+    // not scheduled under the lock, a destroyed executor drops the future (and permit) right away
+    let scheduler = state.lock().unwrap().scheduler.clone();
+    let _ = scheduler.schedule(async move {
+        let _permit = permit;
+        future.await
+    });
+}
+
+pub(super) struct AsyncQuota<M: Send + 'static>(Arc<Mutex<QuotaState<M>>>);
+
+impl<M: Send + 'static> AsyncQuota<M> {
+    pub fn new(scheduler: Scheduler<M>) -> AsyncQuota<M> {
+        AsyncQuota(Arc::new(Mutex::new(QuotaState {
+            scheduler,
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            max_queued: DEFAULT_MAX_QUEUED,
+            running: 0,
+            queue: Vec::new(),
+            next_seq: 0,
+            dropped: 0,
+            closed: false,
+        })))
+    }
+
+    /// Applies new limits, starting or dropping queued futures accordingly.
+    pub fn set_limits(&self, max_concurrent: usize, max_queued: usize, element: Option<&str>) {
+        let (dropped, next) = {
+            let mut inner = self.0.lock().unwrap();
+            inner.max_concurrent = max_concurrent.max(1);
+            inner.max_queued = max_queued;
+            let dropped = inner.trim();
+            (
+                dropped,
+                std::iter::from_fn(|| inner.next()).collect::<Vec<_>>(),
+            )
+        };
+        if dropped > 0 {
+            warn!(
+                ?element,
+                dropped, "Dropped queued futures exceeding the new async quota"
+            );
+        }
+        for future in next {
+            start(&self.0, future);
+        }
+    }
+
+    pub fn schedule(
+        &self,
+        future: BoxFuture<'static, M>,
+        priority: AsyncPriority,
+        element: Option<&str>,
+    ) {
+        let (dropped, queued) = {
+            let mut inner = self.0.lock().unwrap();
+            if inner.closed {
+                return;
+            }
+            if inner.running < inner.max_concurrent {
+                inner.running += 1;
+                drop(inner);
+                start(&self.0, future);
+                return;
+            }
+            let seq = inner.next_seq;
+            inner.next_seq += 1;
+            inner.queue.push(Queued {
+                priority,
+                seq,
+                future,
+            });
+            (inner.trim(), inner.queue.len())
+        };
+        for _ in 0..dropped {
+            warn!(
+                ?element,
+                queued, "Async quota exceeded, dropped the oldest queued future"
+            );
+        }
+    }
+
+    /// Drops all queued futures, running ones are left to complete.
+    pub fn close(&self) {
+        let mut inner = self.0.lock().unwrap();
+        inner.closed = true;
+        inner.queue.clear();
+    }
+
+    pub fn stats(&self) -> AsyncQuotaStats {
+        let inner = self.0.lock().unwrap();
+        AsyncQuotaStats {
+            running: inner.running,
+            queued: inner.queue.len(),
+            dropped: inner.dropped,
+        }
+    }
+}
+
+impl<M: Send + 'static> fmt::Debug for AsyncQuota<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AsyncQuota").field(&self.stats()).finish()
+    }
+}
@@ -0,0 +1,182 @@
+//! Global registry of all live `IcedElement`s.
+//!
+//! Used for compositor-wide notifications, which need to reach every element
+//! regardless of where (or if) it is currently mapped.
+
+use smithay::{
+    output::Output,
+    utils::{Logical, Point, Rectangle},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+use super::{
+    lock_global, DragIcon, DragPayload, IcedElement, IntentId, MemoryPressureLevel, RefreshInfo,
+};
+use crate::config::KeyPattern;
+
+pub(super) trait RegisteredElement: Send + Sync {
+    fn reload_config(&self);
+    fn set_refresh_info(&self, output: &Output, info: RefreshInfo);
+    fn frame_done(&self, output: &Output);
+    fn output_reconfigured(&self, output: &Output);
+    /// Switches to the changed defaults at once, with a single relayout.
+    fn defaults_changed(&self, changes: DefaultChanges);
+    fn memory_pressure(&self, level: MemoryPressureLevel);
+    fn intent_undone(&self, intent: IntentId);
+    /// Delivers the message of a shortcut, returns false if the program doesn't claim it anymore.
+    fn trigger_shortcut(&self, pattern: &KeyPattern) -> bool;
+
+    /// Location relative to the element, if it accepts drops at the global `location`.
+    fn drop_target_at(&self, location: Point<f64, Logical>) -> Option<Point<f64, Logical>>;
+    fn dnd_enter(&self, payload: &DragPayload, location: Point<f64, Logical>);
+    /// Mime type accepted for `payload` at `location`, see `Program::dnd_mime_types`.
+    fn dnd_accept(&self, payload: &DragPayload, location: Point<f64, Logical>) -> Option<String>;
+    fn dnd_motion(&self, location: Point<f64, Logical>);
+    fn dnd_leave(&self);
+    fn dnd_drop(&self, payload: &DragPayload, location: Point<f64, Logical>);
+    fn drag_cancelled(&self, payload: &DragPayload);
+    /// Whether the element is the source of an active internal drag.
+    fn is_dragging(&self) -> bool;
+    /// Icon of the element's active drag and its global location.
+    fn drag_icon(&self) -> Option<(IcedElement<DragIcon>, Point<f64, Logical>)>;
+    /// Mime type accepted for the element's active drag, see `internal_drag_accepted`.
+    fn drag_accepted(&self) -> Option<String>;
+    fn cancel_drag(&self);
+
+    /// Areas (relative to the element) on `output` to composite again, without redrawing them.
+    fn cursor_damage(&self, output: &Output, damage: &[Rectangle<i32, Logical>]);
+}
+
+bitflags::bitflags! {
+    /// Global defaults changed since elements were last notified.
+    #[derive(Default)]
+    pub(super) struct DefaultChanges: u32 {
+        const FONTS = 1 << 0;
+        const THEME = 1 << 1;
+        const TEXT_SCALE = 1 << 2;
+        const ANIMATIONS = 1 << 3;
+        const CONTRAST = 1 << 4;
+        const FORMAT = 1 << 5;
+        /// Any of the `Preferences`
+        const PREFERENCES = Self::TEXT_SCALE.bits
+            | Self::ANIMATIONS.bits
+            | Self::CONTRAST.bits
+            | Self::FORMAT.bits;
+    }
+}
+
+/// Changes recorded while `depth` guards of `begin_global_changes` are alive.
+#[derive(Debug, Default)]
+pub(super) struct Batch {
+    depth: usize,
+    pending: DefaultChanges,
+}
+
+impl Batch {
+    pub(super) fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Records `changes` while a batch is running, returns the changes to notify now otherwise.
+    pub(super) fn record(&mut self, changes: DefaultChanges) -> Option<DefaultChanges> {
+        if self.depth > 0 {
+            self.pending |= changes;
+            None
+        } else {
+            Some(changes)
+        }
+    }
+
+    /// Ends a batch, returns the recorded changes once the outermost one ended.
+    pub(super) fn end(&mut self) -> Option<DefaultChanges> {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth > 0 {
+            return None;
+        }
+        let changes = std::mem::replace(&mut self.pending, DefaultChanges::empty());
+        (!changes.is_empty()).then_some(changes)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref ELEMENTS: Mutex<Vec<Weak<dyn RegisteredElement>>> = Mutex::new(Vec::new());
+    static ref BATCH: Mutex<Batch> = Mutex::new(Batch::default());
+    /// Elements by the outputs they are shown on, for notifications of a single output
+    static ref MAPPED: Mutex<HashMap<Output, Vec<Weak<dyn RegisteredElement>>>> =
+        Mutex::new(HashMap::new());
+}
+
+fn address(element: &Weak<dyn RegisteredElement>) -> usize {
+    element.as_ptr() as *const () as usize
+}
+
+pub(super) fn register(element: Weak<dyn RegisteredElement>) {
+    let mut elements = lock_global(&ELEMENTS);
+    elements.retain(|e| e.strong_count() > 0);
+    elements.push(element);
+}
+
+/// The live element at `address`, the address of its `Mutex`.
+pub(super) fn element(address: usize) -> Option<Arc<dyn RegisteredElement>> {
+    lock_global(&ELEMENTS)
+        .iter()
+        .find(|e| self::address(e) == address)
+        .and_then(Weak::upgrade)
+}
+
+/// Returns strong references to all live elements.
+///
+/// The registry lock is released before returning, so callers may freely lock the elements.
+pub(super) fn elements() -> Vec<Arc<dyn RegisteredElement>> {
+    let mut elements = lock_global(&ELEMENTS);
+    elements.retain(|e| e.strong_count() > 0);
+    elements.iter().filter_map(Weak::upgrade).collect()
+}
+
+pub(super) fn entered_output(output: &Output, element: Weak<dyn RegisteredElement>) {
+    let mut mapped = lock_global(&MAPPED);
+    let elements = mapped.entry(output.clone()).or_default();
+    elements.retain(|e| e.strong_count() > 0 && address(e) != address(&element));
+    elements.push(element);
+}
+
+pub(super) fn left_output(output: &Output, element: &Weak<dyn RegisteredElement>) {
+    let mut mapped = lock_global(&MAPPED);
+    let Some(elements) = mapped.get_mut(output) else { return };
+    elements.retain(|e| e.strong_count() > 0 && address(e) != address(element));
+    if elements.is_empty() {
+        // doesn't keep removed outputs alive
+        mapped.remove(output);
+    }
+}
+
+/// Returns strong references to the live elements shown on `output`, see `elements`.
+pub(super) fn elements_on(output: &Output) -> Vec<Arc<dyn RegisteredElement>> {
+    let mut mapped = lock_global(&MAPPED);
+    let Some(elements) = mapped.get_mut(output) else { return Vec::new() };
+    elements.retain(|e| e.strong_count() > 0);
+    elements.iter().filter_map(Weak::upgrade).collect()
+}
+
+/// Notifies all elements about changed defaults, or records them until the outermost batch ends.
+pub(super) fn defaults_changed(changes: DefaultChanges) {
+    let Some(changes) = lock_global(&BATCH).record(changes) else { return };
+    for element in elements() {
+        element.defaults_changed(changes);
+    }
+}
+
+pub(super) fn begin_batch() {
+    lock_global(&BATCH).begin();
+}
+
+/// Ends a batch, notifying all elements about the recorded changes once the outermost one ended.
+pub(super) fn end_batch() {
+    let changes = lock_global(&BATCH).end();
+    if let Some(changes) = changes {
+        defaults_changed(changes);
+    }
+}
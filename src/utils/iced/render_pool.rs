@@ -0,0 +1,123 @@
+//! Rasterization of element content on a thread pool, see `IcedElement::new_with_pool`.
+//!
+//! Jobs only get owned copies of what they draw (the primitives, sizes and draw options), the
+//! element itself never leaves the main thread. Each pool thread keeps its own text caches.
+//! Drawn content is sent back through a calloop channel, the element then draws its hooks and
+//! overlays on top of it (see `IcedElementInternal::finish_pool_draw`).
+
+use std::{cell::RefCell, fmt, sync::Arc};
+
+use iced_graphics::Primitive;
+use iced_softbuffer::{native::raqote, Backend};
+use rayon::ThreadPool;
+use smithay::{
+    reexports::calloop::{channel::Sender, RegistrationToken},
+    utils::{Buffer as BufferCoords, Size, Transform},
+};
+
+use super::{blending::TextTiles, draw_primitives, sanitize, ContentOptions, SanitizeReport};
+
+thread_local! {
+    /// Text caches of the pool thread
+    static CACHES: RefCell<(Backend, TextTiles)> = RefCell::new((Backend::new(), TextTiles::default()));
+}
+
+/// Primitives of an element to draw for the buffer of `scale`.
+pub(super) struct ContentJob {
+    pub primitives: Vec<Primitive>,
+    pub scale: f64,
+    pub options: ContentOptions,
+    /// `layers::content_hash` of `primitives`
+    pub hash: u64,
+}
+
+/// Primitives drawn by a `ContentJob`.
+pub(super) struct Content {
+    pub scale: f64,
+    pub render_size: Size<i32, BufferCoords>,
+    /// Premultiplied ARGB of `render_size`, in linear light with linear blending
+    pub pixels: Vec<u32>,
+    pub hash: u64,
+    pub sanitation: SanitizeReport,
+}
+
+impl ContentJob {
+    fn draw(self) -> Content {
+        let render_size = self
+            .options
+            .size
+            .to_f64()
+            .to_buffer(self.options.render_scale, Transform::Normal)
+            .to_i32_round();
+        let mut pixels = vec![0; (render_size.w.max(0) * render_size.h.max(0)) as usize];
+        let mut sanitation = SanitizeReport::default();
+        if !pixels.is_empty() {
+            let mut target =
+                raqote::DrawTarget::from_backing(render_size.w, render_size.h, &mut pixels[..]);
+            // see `Rasterizer::rasterize_unchecked`
+            target.push_clip_rect(raqote::IntRect::new(
+                raqote::IntPoint::new(0, 0),
+                raqote::IntPoint::new(render_size.w, render_size.h),
+            ));
+            CACHES.with(|caches| {
+                let (backend, text_tiles) = &mut *caches.borrow_mut();
+                draw_primitives(
+                    &mut target,
+                    backend,
+                    &self.primitives,
+                    self.options,
+                    text_tiles,
+                    &mut sanitation,
+                );
+                if self.options.linear_blending {
+                    text_tiles.finish_frame();
+                }
+            });
+            if !sanitation.markers.is_empty() {
+                if sanitize::markers_enabled() {
+                    let scale = self.options.render_scale as f32;
+                    sanitize::draw_markers(&mut target, &sanitation.markers, scale);
+                }
+                sanitation.markers.clear();
+            }
+        }
+        Content {
+            scale: self.scale,
+            render_size,
+            pixels,
+            hash: self.hash,
+            sanitation,
+        }
+    }
+}
+
+/// Render pool of an element, with the channel its jobs send their content back through.
+pub(super) struct RenderPool {
+    pool: Arc<ThreadPool>,
+    done: Sender<Content>,
+    /// Source of the receiving end of `done`
+    pub token: RegistrationToken,
+}
+
+impl fmt::Debug for RenderPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderPool")
+            .field("threads", &self.pool.current_num_threads())
+            .field("token", &self.token)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RenderPool {
+    pub fn new(pool: Arc<ThreadPool>, done: Sender<Content>, token: RegistrationToken) -> Self {
+        RenderPool { pool, done, token }
+    }
+
+    /// Draws `job` on the pool and sends its content back, unless the element was dropped.
+    pub fn spawn(&self, job: ContentJob) {
+        let done = self.done.clone();
+        self.pool.spawn(move || {
+            let _ = done.send(job.draw());
+        });
+    }
+}
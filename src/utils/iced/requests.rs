@@ -0,0 +1,44 @@
+use smithay::utils::{Logical, Point, Serial};
+
+use super::WindowAction;
+use crate::shell::CosmicSurface;
+
+/// Actions a `Program` may ask the compositor to perform.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ShellRequest {
+    SwitchWorkspace(usize),
+    FocusWindow(CosmicSurface),
+    Spawn(String),
+    /// Move the requesting element to the given location
+    MoveElement(Point<i32, Logical>),
+    SetOutputEnabled {
+        output: String,
+        enabled: bool,
+    },
+    /// Unmap the requesting element
+    CloseElement,
+    /// Window management action from a titlebar, see `Decorated`
+    Window(WindowAction),
+}
+
+impl ShellRequest {
+    /// Whether elements in restricted mode (e.g. on the lock screen) may issue this request.
+    pub fn allowed_when_restricted(&self) -> bool {
+        matches!(
+            self,
+            ShellRequest::MoveElement(_) | ShellRequest::CloseElement
+        )
+    }
+}
+
+/// Information about the origin of a `ShellRequest`.
+#[derive(Debug, Clone)]
+pub struct RequestMeta {
+    /// Serial of the last input event the element received before the request
+    pub serial: Option<Serial>,
+    /// Name of the requesting element, if set
+    pub element: Option<String>,
+}
+
+pub(super) type ShellRequestHandler = Box<dyn FnMut(ShellRequest, RequestMeta) + Send>;
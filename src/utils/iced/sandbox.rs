@@ -0,0 +1,502 @@
+//! Sandboxed elements, whose programs are driven by untrusted configuration.
+//!
+//! A pathological configuration (deeply nested layouts, huge sizes, message floods)
+//! must not be able to freeze the compositor. Limits are enforced as follows:
+//! - views with more than `max_widgets` widgets and updates taking longer than
+//!   `max_update_time` (or panicking) degrade the element to a placeholder permanently,
+//!   as iced can't abort an update half-way,
+//! - sizes are clamped to `max_size`,
+//! - frames taking longer than `max_draw_time` to rasterize keep the previous buffer
+//!   for a while, the slower the frame the longer,
+//! - messages exceeding `max_messages_per_second` are dropped.
+//!
+//! Violations are logged and can be queried via `IcedElement::sandbox_violations`.
+
+use std::{
+    cell::{Cell, RefCell},
+    panic::{catch_unwind, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+use cosmic::{
+    iced::widget::{container, text},
+    iced_native::{
+        widget::{Id, Tree},
+        Command, Length,
+    },
+    Element,
+};
+use iced_softbuffer::native::raqote::DrawTarget;
+use smithay::{
+    backend::input::{AxisSource, ButtonState},
+    reexports::calloop::LoopHandle,
+    utils::{Logical, Physical, Point, Rectangle, Size},
+};
+use tracing::warn;
+
+use super::{
+    ContentState, DragPayload, FocusTarget, FontConfig, HookPalette, IcedElement, IntentId,
+    InteractionRegion, KeyPattern, LayerSpec, Preferences, Program, ProgramCapabilities,
+    ProgressivePlan, RefreshInfo, RingEventSource, ScaleMode, ScrollPhysics, ScrollRegion,
+    ShortcutPriority, StripEventSource, Subscription, UnmatchedScroll, UpdateContext,
+};
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+/// Frames over budget skip redraws for this multiple of their draw time
+const DRAW_THROTTLE_FACTOR: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SandboxLimits {
+    pub max_widgets: usize,
+    pub max_update_time: Duration,
+    pub max_size: Size<i32, Logical>,
+    pub max_draw_time: Duration,
+    pub max_messages_per_second: u32,
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        SandboxLimits {
+            max_widgets: 2000,
+            max_update_time: Duration::from_millis(16),
+            max_size: Size::from((4096, 4096)),
+            max_draw_time: Duration::from_millis(16),
+            max_messages_per_second: 240,
+        }
+    }
+}
+
+impl SandboxLimits {
+    pub(super) fn clamp_size(&self, size: Size<i32, Logical>) -> Size<i32, Logical> {
+        let clamped = Size::from((size.w.min(self.max_size.w), size.h.min(self.max_size.h)));
+        if clamped != size {
+            warn!(
+                ?size,
+                ?clamped,
+                "Sandboxed element requested an oversized size"
+            );
+        }
+        clamped
+    }
+
+    /// Until when redraws are skipped after a frame took `draw_time`, if it was over budget.
+    pub(super) fn throttle(&self, draw_time: Duration) -> Option<Instant> {
+        if draw_time <= self.max_draw_time {
+            return None;
+        }
+        warn!(
+            ?draw_time,
+            "Sandboxed element exceeded its draw time, throttling"
+        );
+        Some(Instant::now() + draw_time * DRAW_THROTTLE_FACTOR)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxViolation {
+    /// Number of widgets of the rejected view
+    TooManyWidgets(usize),
+    SlowUpdate(Duration),
+    Panicked,
+    MessageFlood,
+}
+
+/// Wraps the program of a sandboxed element, see `IcedElement::new_sandboxed`.
+pub struct Sandboxed<P: Program> {
+    program: P,
+    limits: SandboxLimits,
+    degraded: Cell<bool>,
+    violations: RefCell<Vec<SandboxViolation>>,
+    /// Start of the current rate window and the messages received within
+    rate: Cell<(Instant, u32)>,
+}
+
+fn count_widgets(tree: &Tree) -> usize {
+    1 + tree.children.iter().map(count_widgets).sum::<usize>()
+}
+
+impl<P: Program> Sandboxed<P> {
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.get()
+    }
+
+    fn report(&self, violation: SandboxViolation) {
+        warn!(?violation, "Sandboxed program exceeded its limits");
+        self.violations.borrow_mut().push(violation);
+    }
+
+    fn degrade(&self, violation: SandboxViolation) {
+        self.report(violation);
+        self.degraded.set(true);
+    }
+
+    /// Counts a message against the rate limit, returns false if it should be dropped.
+    fn admit_message(&self) -> bool {
+        let (start, count) = self.rate.get();
+        let now = Instant::now();
+        let (start, count) = if now.duration_since(start) >= RATE_WINDOW {
+            (now, 0)
+        } else {
+            (start, count)
+        };
+        self.rate.set((start, count.saturating_add(1)));
+        if count < self.limits.max_messages_per_second {
+            return true;
+        }
+        // once per window
+        if count == self.limits.max_messages_per_second {
+            self.report(SandboxViolation::MessageFlood);
+        }
+        false
+    }
+
+    /// Runs a hook producing a message, unless the program is degraded.
+    fn hook(&self, f: impl FnOnce(&P) -> Option<P::Message>) -> Option<P::Message> {
+        if self.is_degraded() {
+            return None;
+        }
+        f(&self.program)
+    }
+
+    /// Like `hook`, for hooks changing the program.
+    fn hook_mut(&mut self, f: impl FnOnce(&mut P) -> Option<P::Message>) -> Option<P::Message> {
+        if self.is_degraded() {
+            return None;
+        }
+        f(&mut self.program)
+    }
+
+    fn placeholder(&self) -> Element<'_, P::Message> {
+        container(text(
+            "This widget was disabled, as it exceeded its resource limits",
+        ))
+        .padding(16)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+}
+
+impl<P> Program for Sandboxed<P>
+where
+    P: Program,
+    P::Message: 'static,
+{
+    type Message = P::Message;
+
+    fn try_clone_message(message: &Self::Message) -> Option<Self::Message> {
+        P::try_clone_message(message)
+    }
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        if self.is_degraded() || !self.admit_message() {
+            return Command::none();
+        }
+
+        let start = Instant::now();
+        let program = &mut self.program;
+        match catch_unwind(AssertUnwindSafe(|| {
+            program.update_with_context(message, ctx)
+        })) {
+            Ok(command) => {
+                let elapsed = start.elapsed();
+                if elapsed > self.limits.max_update_time {
+                    self.degrade(SandboxViolation::SlowUpdate(elapsed));
+                    return Command::none();
+                }
+                command
+            }
+            Err(_) => {
+                self.degrade(SandboxViolation::Panicked);
+                Command::none()
+            }
+        }
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        if self.is_degraded() {
+            return self.placeholder();
+        }
+
+        let element = self.program.view();
+        let widgets = count_widgets(&Tree::new(element.as_widget()));
+        if widgets > self.limits.max_widgets {
+            self.degrade(SandboxViolation::TooManyWidgets(widgets));
+            return self.placeholder();
+        }
+        element
+    }
+
+    fn themed_background(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        if !self.is_degraded() {
+            self.program.themed_background(target, palette)
+        }
+    }
+
+    fn themed_foreground(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        if !self.is_degraded() {
+            self.program.themed_foreground(target, palette)
+        }
+    }
+
+    fn custom_render(
+        &mut self,
+        target: &mut DrawTarget<&mut [u32]>,
+        size: Size<i32, Physical>,
+        scale: f64,
+    ) {
+        if !self.is_degraded() {
+            self.program.custom_render(target, size, scale)
+        }
+    }
+
+    fn custom_render_only(&self) -> bool {
+        !self.is_degraded() && self.program.custom_render_only()
+    }
+
+    fn uses_hooks(&self) -> bool {
+        !self.is_degraded() && self.program.uses_hooks()
+    }
+    fn capabilities(&self) -> ProgramCapabilities {
+        if self.is_degraded() {
+            return ProgramCapabilities::empty();
+        }
+        self.program.capabilities()
+    }
+
+    fn z_index(&self) -> u8 {
+        self.program.z_index()
+    }
+
+    fn config_id(&self) -> Option<(&'static str, u64)> {
+        self.program.config_id()
+    }
+
+    fn config_keys(&self) -> &'static [&'static str] {
+        self.program.config_keys()
+    }
+
+    fn on_config_changed(&self, key: &str, value: Option<ron::Value>) -> Option<Self::Message> {
+        self.hook(|program| program.on_config_changed(key, value))
+    }
+
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        self.program.initial_focus()
+    }
+
+    fn content_state(&self) -> ContentState {
+        self.program.content_state()
+    }
+
+    fn progressive(&self) -> Option<ProgressivePlan> {
+        self.program.progressive()
+    }
+
+    fn progressive_stage(&self, stage: u32) -> Option<Self::Message> {
+        self.hook(|program| program.progressive_stage(stage))
+    }
+
+    fn refresh_changed(&mut self, info: &RefreshInfo) -> Option<Self::Message> {
+        self.hook_mut(|program| program.refresh_changed(info))
+    }
+
+    fn fonts_changed(&self, fonts: &FontConfig) -> Option<Self::Message> {
+        self.hook(|program| program.fonts_changed(fonts))
+    }
+
+    fn preferences_changed(&self, preferences: &Preferences) -> Option<Self::Message> {
+        self.hook(|program| program.preferences_changed(preferences))
+    }
+
+    fn intent_undone(&self, intent: IntentId) -> Option<Self::Message> {
+        self.hook(|program| program.intent_undone(intent))
+    }
+
+    fn update_interval(&self) -> Option<Duration> {
+        if self.is_degraded() {
+            return None;
+        }
+        self.program.update_interval()
+    }
+
+    fn max_fps(&self) -> Option<u32> {
+        self.program.max_fps()
+    }
+
+    fn shortcuts(&self) -> Vec<(KeyPattern, Self::Message)> {
+        if self.is_degraded() {
+            return Vec::new();
+        }
+        self.program.shortcuts()
+    }
+
+    fn shortcut_priority(&self) -> ShortcutPriority {
+        self.program.shortcut_priority()
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription<Self::Message>> {
+        if self.is_degraded() {
+            return Vec::new();
+        }
+        self.program.subscriptions()
+    }
+
+    fn scale_mode(&self) -> ScaleMode {
+        self.program.scale_mode()
+    }
+
+    fn idle(&mut self) -> Option<Self::Message> {
+        self.hook_mut(|program| program.idle())
+    }
+
+    fn resumed(&mut self) -> Option<Self::Message> {
+        self.hook_mut(|program| program.resumed())
+    }
+
+    fn scroll_regions(&self) -> Vec<ScrollRegion> {
+        self.program.scroll_regions()
+    }
+
+    fn unmatched_scroll(&self) -> UnmatchedScroll {
+        self.program.unmatched_scroll()
+    }
+
+    fn configure_scroll_physics(&self, source: AxisSource) -> ScrollPhysics {
+        self.program.configure_scroll_physics(source)
+    }
+
+    fn press_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        self.program.press_regions()
+    }
+
+    fn interaction_regions(&self) -> Vec<InteractionRegion> {
+        self.program.interaction_regions()
+    }
+
+    fn secure_fields(&self) -> Vec<Id> {
+        self.program.secure_fields()
+    }
+
+    fn input_region(&self) -> Option<Vec<Rectangle<i32, Logical>>> {
+        self.program.input_region()
+    }
+
+    fn optimistic_feedback(&self) -> bool {
+        self.program.optimistic_feedback()
+    }
+
+    fn layers(&self) -> Vec<LayerSpec> {
+        if self.is_degraded() {
+            return Vec::new();
+        }
+        self.program.layers()
+    }
+
+    fn is_drag_active(&self) -> bool {
+        !self.is_degraded() && self.program.is_drag_active()
+    }
+
+    fn accepts_drops(&self) -> bool {
+        !self.is_degraded() && self.program.accepts_drops()
+    }
+
+    fn dnd_enter(
+        &self,
+        payload: &DragPayload,
+        position: Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.hook(|program| program.dnd_enter(payload, position))
+    }
+
+    fn dnd_mime_types(&self, position: Point<f64, Logical>) -> Vec<String> {
+        if self.is_degraded() {
+            Vec::new()
+        } else {
+            self.program.dnd_mime_types(position)
+        }
+    }
+
+    fn dnd_motion(&self, position: Point<f64, Logical>) -> Option<Self::Message> {
+        self.hook(|program| program.dnd_motion(position))
+    }
+
+    fn dnd_leave(&self) -> Option<Self::Message> {
+        self.hook(|program| program.dnd_leave())
+    }
+
+    fn dnd_drop(
+        &self,
+        payload: &DragPayload,
+        position: Point<f64, Logical>,
+    ) -> Option<Self::Message> {
+        self.hook(|program| program.dnd_drop(payload, position))
+    }
+
+    fn drag_cancelled(&self, payload: &DragPayload) -> Option<Self::Message> {
+        self.hook(|program| program.drag_cancelled(payload))
+    }
+
+    fn wants_input_method(&self) -> bool {
+        !self.is_degraded() && self.program.wants_input_method()
+    }
+
+    fn on_pad_button(&mut self, button: u32, state: ButtonState) {
+        if !self.is_degraded() {
+            self.program.on_pad_button(button, state);
+        }
+    }
+
+    fn on_pad_ring(&mut self, source: RingEventSource, degrees: f64) {
+        if !self.is_degraded() {
+            self.program.on_pad_ring(source, degrees);
+        }
+    }
+
+    fn on_pad_strip(&mut self, source: StripEventSource, position: f64) {
+        if !self.is_degraded() {
+            self.program.on_pad_strip(source, position);
+        }
+    }
+}
+
+impl<P> IcedElement<Sandboxed<P>>
+where
+    P: Program + Send + 'static,
+    P::Message: 'static,
+{
+    /// Creates an element running `program` within `limits`, see the module documentation.
+    pub fn new_sandboxed(
+        program: P,
+        limits: SandboxLimits,
+        size: impl Into<Size<i32, Logical>>,
+        handle: LoopHandle<'static, crate::state::Data>,
+    ) -> IcedElement<Sandboxed<P>> {
+        let element = IcedElement::new(
+            Sandboxed {
+                program,
+                limits,
+                degraded: Cell::new(false),
+                violations: RefCell::new(Vec::new()),
+                rate: Cell::new((Instant::now(), 0)),
+            },
+            limits.clamp_size(size.into()),
+            handle,
+        );
+        element.lock().sandbox = Some(limits);
+        element
+    }
+
+    /// Whether the program was replaced by a placeholder for exceeding its limits.
+    pub fn is_degraded(&self) -> bool {
+        self.with_program(|sandboxed| sandboxed.is_degraded())
+    }
+
+    /// All limit violations of the program so far, oldest first.
+    pub fn sandbox_violations(&self) -> Vec<SandboxViolation> {
+        self.with_program(|sandboxed| sandboxed.violations.borrow().clone())
+    }
+}
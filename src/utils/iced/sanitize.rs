@@ -0,0 +1,273 @@
+//! Sanitation of primitive geometry before it reaches raqote.
+//!
+//! Layout math of programs may produce NaN, infinite or absurdly large bounds (e.g. dividing
+//! by a zero width), which raqote turns into garbage across the whole buffer or a stuck clip.
+//! Primitives with such bounds, translations or text sizes are dropped, slightly negative sizes
+//! (e.g. from rounding) are clamped to zero. Meshes and other primitives without bounds are
+//! passed through unchecked.
+//!
+//! Valid primitive streams only pay for a walk over their primitives, nothing is cloned unless
+//! a primitive has to be repaired.
+
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use cosmic::iced_native::{Point as IcedPoint, Rectangle as IcedRectangle, Vector};
+use iced_graphics::Primitive;
+use iced_softbuffer::native::raqote::{
+    DrawOptions, DrawTarget, PathBuilder, SolidSource, Source, StrokeStyle,
+};
+
+/// Values beyond this multiple of the element's larger dimension are considered absurd
+const LIMIT_FACTOR: f32 = 16.0;
+const MARKER_SIZE: f32 = 8.0;
+
+static MARKERS: AtomicBool = AtomicBool::new(cfg!(debug_assertions));
+
+/// Draws a magenta marker where a primitive was dropped, enabled by default in debug builds.
+pub fn set_sanitation_markers(enabled: bool) {
+    MARKERS.store(enabled, Ordering::Relaxed);
+}
+
+pub(super) fn markers_enabled() -> bool {
+    MARKERS.load(Ordering::Relaxed)
+}
+
+/// Largest sane coordinate or size for an element of the given logical size.
+pub(super) fn limit(width: i32, height: i32) -> f32 {
+    width.max(height).max(1) as f32 * LIMIT_FACTOR
+}
+
+#[derive(Debug, Default)]
+pub(super) struct SanitizeReport {
+    pub dropped: u32,
+    pub clamped: u32,
+    /// Logical positions of dropped primitives
+    pub markers: Vec<IcedPoint>,
+}
+
+impl SanitizeReport {
+    pub fn sanitized(&self) -> u32 {
+        self.dropped + self.clamped
+    }
+}
+
+fn is_sane(value: f32, limit: f32) -> bool {
+    // false for NaN and infinities
+    value.abs() <= limit
+}
+
+fn is_valid_bounds(bounds: &IcedRectangle, limit: f32) -> bool {
+    is_sane(bounds.x, limit)
+        && is_sane(bounds.y, limit)
+        && is_sane(bounds.width, limit)
+        && is_sane(bounds.height, limit)
+        && bounds.width >= 0.0
+        && bounds.height >= 0.0
+}
+
+fn is_valid(primitive: &Primitive, limit: f32) -> bool {
+    match primitive {
+        Primitive::Group { primitives } => primitives.iter().all(|p| is_valid(p, limit)),
+        Primitive::Translate {
+            translation,
+            content,
+        } => {
+            is_sane(translation.x, limit)
+                && is_sane(translation.y, limit)
+                && is_valid(content, limit)
+        }
+        Primitive::Clip { bounds, content } => {
+            is_valid_bounds(bounds, limit) && is_valid(content, limit)
+        }
+        Primitive::Cached { cache } => is_valid(cache, limit),
+        Primitive::Quad {
+            bounds,
+            border_width,
+            ..
+        } => {
+            is_valid_bounds(bounds, limit) && is_sane(*border_width, limit) && *border_width >= 0.0
+        }
+        Primitive::Text { bounds, size, .. } => {
+            is_valid_bounds(bounds, limit) && is_sane(*size, limit) && *size >= 0.0
+        }
+        Primitive::Image { bounds, .. } | Primitive::Svg { bounds, .. } => {
+            is_valid_bounds(bounds, limit)
+        }
+        _ => true,
+    }
+}
+
+/// `bounds` with negative sizes clamped to zero, `None` if they can't be drawn.
+fn repair_bounds(
+    bounds: &IcedRectangle,
+    offset: Vector,
+    limit: f32,
+    report: &mut SanitizeReport,
+) -> Option<IcedRectangle> {
+    if !(is_sane(bounds.x, limit)
+        && is_sane(bounds.y, limit)
+        && is_sane(bounds.width, limit)
+        && is_sane(bounds.height, limit))
+    {
+        report.dropped += 1;
+        report.markers.push(marker_position(bounds, offset));
+        return None;
+    }
+    if bounds.width < 0.0 || bounds.height < 0.0 {
+        report.clamped += 1;
+    }
+    Some(IcedRectangle {
+        width: bounds.width.max(0.0),
+        height: bounds.height.max(0.0),
+        ..*bounds
+    })
+}
+
+/// Position of a dropped primitive, as close to its origin as its bounds allow.
+fn marker_position(bounds: &IcedRectangle, offset: Vector) -> IcedPoint {
+    let coordinate = |value: f32| if value.is_finite() { value } else { 0.0 };
+    IcedPoint::new(
+        offset.x + coordinate(bounds.x),
+        offset.y + coordinate(bounds.y),
+    )
+}
+
+fn repair_length(value: f32, limit: f32, report: &mut SanitizeReport) -> Option<f32> {
+    if !is_sane(value, limit) {
+        return None;
+    }
+    if value < 0.0 {
+        report.clamped += 1;
+    }
+    Some(value.max(0.0))
+}
+
+fn repair(
+    primitive: &Primitive,
+    offset: Vector,
+    limit: f32,
+    report: &mut SanitizeReport,
+) -> Option<Primitive> {
+    if is_valid(primitive, limit) {
+        return Some(primitive.clone());
+    }
+    match primitive {
+        Primitive::Group { primitives } => Some(Primitive::Group {
+            primitives: primitives
+                .iter()
+                .filter_map(|p| repair(p, offset, limit, report))
+                .collect(),
+        }),
+        Primitive::Translate {
+            translation,
+            content,
+        } => {
+            if !is_sane(translation.x, limit) || !is_sane(translation.y, limit) {
+                report.dropped += 1;
+                report.markers.push(IcedPoint::new(offset.x, offset.y));
+                return None;
+            }
+            Some(Primitive::Translate {
+                translation: *translation,
+                content: Box::new(repair(content, offset + *translation, limit, report)?),
+            })
+        }
+        Primitive::Clip { bounds, content } => Some(Primitive::Clip {
+            bounds: repair_bounds(bounds, offset, limit, report)?,
+            content: Box::new(repair(content, offset, limit, report)?),
+        }),
+        Primitive::Cached { cache } => Some(Primitive::Cached {
+            cache: Arc::new(repair(cache, offset, limit, report)?),
+        }),
+        Primitive::Quad {
+            bounds,
+            border_width,
+            ..
+        } => {
+            let new_bounds = repair_bounds(bounds, offset, limit, report)?;
+            let Some(new_border_width) = repair_length(*border_width, limit, report) else {
+                report.dropped += 1;
+                report.markers.push(marker_position(bounds, offset));
+                return None;
+            };
+            let mut repaired = primitive.clone();
+            if let Primitive::Quad {
+                bounds,
+                border_width,
+                ..
+            } = &mut repaired
+            {
+                *bounds = new_bounds;
+                *border_width = new_border_width;
+            }
+            Some(repaired)
+        }
+        Primitive::Text { bounds, size, .. } => {
+            let new_bounds = repair_bounds(bounds, offset, limit, report)?;
+            let Some(new_size) = repair_length(*size, limit, report) else {
+                report.dropped += 1;
+                report.markers.push(marker_position(bounds, offset));
+                return None;
+            };
+            let mut repaired = primitive.clone();
+            if let Primitive::Text { bounds, size, .. } = &mut repaired {
+                *bounds = new_bounds;
+                *size = new_size;
+            }
+            Some(repaired)
+        }
+        Primitive::Image { bounds, .. } | Primitive::Svg { bounds, .. } => {
+            let new_bounds = repair_bounds(bounds, offset, limit, report)?;
+            let mut repaired = primitive.clone();
+            if let Primitive::Image { bounds, .. } | Primitive::Svg { bounds, .. } = &mut repaired {
+                *bounds = new_bounds;
+            }
+            Some(repaired)
+        }
+        primitive => Some(primitive.clone()),
+    }
+}
+
+/// `primitive` with all geometry drawable, `None` if nothing of it is.
+///
+/// Dropped and clamped primitives are counted in `report`.
+pub(super) fn sanitize<'a>(
+    primitive: &'a Primitive,
+    limit: f32,
+    report: &mut SanitizeReport,
+) -> Option<Cow<'a, Primitive>> {
+    if is_valid(primitive, limit) {
+        return Some(Cow::Borrowed(primitive));
+    }
+    repair(primitive, Vector::new(0.0, 0.0), limit, report).map(Cow::Owned)
+}
+
+/// Draws a cross at each of `markers` onto `target`, which is scaled by `scale` relative to
+/// logical coordinates.
+pub(super) fn draw_markers(target: &mut DrawTarget<&mut [u32]>, markers: &[IcedPoint], scale: f32) {
+    let source = Source::Solid(SolidSource::from_unpremultiplied_argb(255, 255, 0, 255));
+    let half = MARKER_SIZE / 2.0 * scale;
+    for marker in markers {
+        let (x, y) = (marker.x * scale, marker.y * scale);
+        let mut pb = PathBuilder::new();
+        pb.move_to(x - half, y - half);
+        pb.line_to(x + half, y + half);
+        pb.move_to(x + half, y - half);
+        pb.line_to(x - half, y + half);
+        target.stroke(
+            &pb.finish(),
+            &source,
+            &StrokeStyle {
+                width: 2.0 * scale,
+                ..Default::default()
+            },
+            &DrawOptions::new(),
+        );
+    }
+}
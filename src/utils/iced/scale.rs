@@ -0,0 +1,62 @@
+//! Rendering at integer scales for fractional outputs.
+
+use smithay::utils::{Buffer, Size};
+
+/// How an element is rasterized for fractional scales.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleMode {
+    /// Render at the exact scale of the output
+    #[default]
+    Exact,
+    /// Render at the next integer scale and downsample,
+    /// avoiding blurry content caused by non-integer pixel boundaries.
+    CeilToInteger,
+}
+
+impl ScaleMode {
+    /// Scale to rasterize at for the given output scale.
+    pub fn render_scale(&self, scale: f64) -> f64 {
+        match self {
+            ScaleMode::Exact => scale,
+            ScaleMode::CeilToInteger => scale.ceil(),
+        }
+    }
+}
+
+/// Bilinearly resamples premultiplied ARGB pixels of `src` into `dst`.
+pub fn downsample_bilinear(
+    src: &[u32],
+    src_size: Size<i32, Buffer>,
+    dst: &mut [u32],
+    dst_size: Size<i32, Buffer>,
+) {
+    let (sw, sh) = (src_size.w as usize, src_size.h as usize);
+    let x_ratio = src_size.w as f32 / dst_size.w as f32;
+    let y_ratio = src_size.h as f32 / dst_size.h as f32;
+
+    for y in 0..dst_size.h as usize {
+        // sample at pixel centers
+        let sy = ((y as f32 + 0.5) * y_ratio - 0.5).clamp(0.0, (sh - 1) as f32);
+        let (y0, fy) = (sy as usize, sy.fract());
+        let y1 = (y0 + 1).min(sh - 1);
+
+        for x in 0..dst_size.w as usize {
+            let sx = ((x as f32 + 0.5) * x_ratio - 0.5).clamp(0.0, (sw - 1) as f32);
+            let (x0, fx) = (sx as usize, sx.fract());
+            let x1 = (x0 + 1).min(sw - 1);
+
+            let (p00, p10) = (src[y0 * sw + x0], src[y0 * sw + x1]);
+            let (p01, p11) = (src[y1 * sw + x0], src[y1 * sw + x1]);
+
+            let mut out = 0u32;
+            for shift in [24, 16, 8, 0] {
+                let c = |p: u32| ((p >> shift) & 0xff) as f32;
+                let top = c(p00) * (1.0 - fx) + c(p10) * fx;
+                let bottom = c(p01) * (1.0 - fx) + c(p11) * fx;
+                let value = (top * (1.0 - fy) + bottom * fy).round() as u32;
+                out |= value.min(255) << shift;
+            }
+            dst[y * dst_size.w as usize + x] = out;
+        }
+    }
+}
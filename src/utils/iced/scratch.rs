@@ -0,0 +1,21 @@
+//! Per-element memory reused across frames, so redraws don't go through the allocator.
+
+use super::blending::TextTiles;
+
+/// Temporary pixel buffers and caches of the draw path. Never freed between frames.
+#[derive(Debug, Default)]
+pub(super) struct FrameScratch {
+    /// Target of `ScaleMode::CeilToInteger` before downsampling
+    pub hires: Vec<u32>,
+    /// Content layer for linear blending
+    pub layer: Vec<u32>,
+    /// Text of the content layer for linear blending, kept across frames
+    pub text_tiles: TextTiles,
+}
+
+/// Resizes `buffer` to `len` transparent pixels, keeping its allocation.
+pub(super) fn cleared(buffer: &mut Vec<u32>, len: usize) -> &mut [u32] {
+    buffer.clear();
+    buffer.resize(len, 0);
+    &mut buffer[..]
+}
@@ -0,0 +1,174 @@
+//! Routing of wheel events to one of multiple scrollable areas of a program,
+//! and their physics per axis source.
+
+use std::{collections::VecDeque, time::Duration};
+
+use cosmic::iced_native::mouse::ScrollDelta;
+use smithay::{
+    backend::input::AxisSource,
+    input::pointer::AxisFrame,
+    utils::{Logical, Point, Rectangle},
+};
+
+/// Pixels per line for wheels without discrete steps
+const PIXELS_PER_LINE: f64 = 15.0;
+/// Deltas considered for the velocity once the fingers are lifted, in ms
+const VELOCITY_WINDOW: u32 = 100;
+/// Momentum scrolling stops below this velocity, in logical pixels per second
+const MIN_VELOCITY: f64 = 50.0;
+/// Interval of momentum scrolling deltas
+pub(super) const KINETIC_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Area of a scrollable, see `Program::scroll_regions`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollRegion {
+    pub bounds: Rectangle<f64, Logical>,
+}
+
+/// What happens to wheel events outside of all scroll regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnmatchedScroll {
+    /// Deliver at the cursor position and let iced decide
+    #[default]
+    Pass,
+    /// Deliver to the region at the given index
+    Region(usize),
+    /// Discard the event
+    Drop,
+}
+
+/// How scrolling of an axis source is delivered, see `Program::configure_scroll_physics`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollPhysics {
+    /// Whole lines, e.g. per wheel detent
+    Discrete,
+    /// Pixels as reported by the device
+    Pixels,
+    /// Pixels, continued by decelerating deltas once the fingers are lifted
+    Momentum {
+        /// In logical pixels per second squared
+        deceleration: f64,
+    },
+}
+
+impl ScrollPhysics {
+    /// Physics of sources, programs don't configure otherwise.
+    pub fn for_source(source: AxisSource) -> ScrollPhysics {
+        match source {
+            AxisSource::Wheel | AxisSource::WheelTilt => ScrollPhysics::Discrete,
+            AxisSource::Finger => ScrollPhysics::Momentum {
+                deceleration: 2500.0,
+            },
+            AxisSource::Continuous => ScrollPhysics::Pixels,
+        }
+    }
+}
+
+/// Whether `frame` only stops scrolling, e.g. as the fingers were lifted.
+pub(super) fn is_stop(frame: &AxisFrame) -> bool {
+    (frame.stop.0 || frame.stop.1) && frame.axis == (0.0, 0.0)
+}
+
+/// Delta of a wheel event for `frame`.
+///
+/// iced has no dedicated stop event, so frames only stopping (kinetic) scrolling are delivered
+/// as empty `Lines`, which widgets snapping their scroll position react to.
+pub(super) fn delta(frame: &AxisFrame, physics: ScrollPhysics) -> ScrollDelta {
+    match (physics, frame.discrete) {
+        (ScrollPhysics::Discrete, Some(discrete)) => ScrollDelta::Lines {
+            x: discrete.0 as f32,
+            y: discrete.1 as f32,
+        },
+        (ScrollPhysics::Discrete, None) => ScrollDelta::Lines {
+            x: (frame.axis.0 / PIXELS_PER_LINE) as f32,
+            y: (frame.axis.1 / PIXELS_PER_LINE) as f32,
+        },
+        _ if is_stop(frame) => ScrollDelta::Lines { x: 0.0, y: 0.0 },
+        _ => ScrollDelta::Pixels {
+            x: frame.axis.0 as f32,
+            y: frame.axis.1 as f32,
+        },
+    }
+}
+
+/// Momentum scrolling after the fingers were lifted, see `ScrollPhysics::Momentum`.
+#[derive(Debug, Default)]
+pub(super) struct Kinetic {
+    /// Recent deltas of the ongoing scroll, with their time in ms
+    samples: VecDeque<(u32, (f64, f64))>,
+    /// In logical pixels per second, while coasting
+    velocity: (f64, f64),
+    deceleration: f64,
+}
+
+impl Kinetic {
+    pub fn record(&mut self, time: u32, delta: (f64, f64)) {
+        self.velocity = (0.0, 0.0);
+        self.samples.push_back((time, delta));
+        while self
+            .samples
+            .front()
+            .map_or(false, |(t, _)| time.wrapping_sub(*t) > VELOCITY_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The fingers were lifted, returns whether momentum scrolling starts.
+    pub fn release(&mut self, deceleration: f64) -> bool {
+        let samples = std::mem::take(&mut self.samples);
+        let (Some((first, _)), Some((last, _))) = (samples.front(), samples.back()) else { return false };
+        // a single sample still spans about one event interval
+        let span = (last.wrapping_sub(*first)).max(8) as f64 / 1000.0;
+        let (x, y) = samples
+            .iter()
+            .fold((0.0, 0.0), |(x, y), (_, (dx, dy))| (x + dx, y + dy));
+        self.velocity = (x / span, y / span);
+        self.deceleration = deceleration;
+        x.hypot(y) / span >= MIN_VELOCITY
+    }
+
+    pub fn stop(&mut self) {
+        self.samples.clear();
+        self.velocity = (0.0, 0.0);
+    }
+
+    /// Advances the momentum by `dt`, returns the delta to scroll or `None` once it stopped.
+    pub fn step(&mut self, dt: Duration) -> Option<(f64, f64)> {
+        let speed = self.velocity.0.hypot(self.velocity.1);
+        if speed < MIN_VELOCITY {
+            self.stop();
+            return None;
+        }
+        let dt = dt.as_secs_f64();
+        let next = (speed - self.deceleration * dt).max(0.0);
+        // distance at the average speed over `dt`
+        let factor = (speed + next) / 2.0 / speed * dt;
+        let delta = (self.velocity.0 * factor, self.velocity.1 * factor);
+        self.velocity = (
+            self.velocity.0 * next / speed,
+            self.velocity.1 * next / speed,
+        );
+        Some(delta)
+    }
+}
+
+/// Position a wheel event at `position` has to be delivered at,
+/// or `None` if it should be dropped.
+pub(super) fn route(
+    regions: &[ScrollRegion],
+    unmatched: UnmatchedScroll,
+    position: Point<f64, Logical>,
+) -> Option<Point<f64, Logical>> {
+    if regions.is_empty() || regions.iter().any(|r| r.bounds.contains(position)) {
+        return Some(position);
+    }
+    match unmatched {
+        UnmatchedScroll::Pass => Some(position),
+        UnmatchedScroll::Region(idx) => {
+            let bounds = regions.get(idx)?.bounds;
+            Some(bounds.loc + bounds.size.downscale(2.0).to_point())
+        }
+        UnmatchedScroll::Drop => None,
+    }
+}
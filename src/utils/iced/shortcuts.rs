@@ -0,0 +1,227 @@
+//! Keyboard shortcuts of elements, routed once none of the compositor's global bindings matched.
+//!
+//! Elements publish the shortcuts of their program (`Program::shortcuts`) after every update.
+//! A pressed combination goes to the claimant of the highest `ShortcutPriority`, that is mapped
+//! and not restricted, ties are won by the most recently mapped element. Claims of equal priority
+//! are logged once per combination and pair of elements.
+//!
+//! Each element's table is replaced as a whole, so old and new bindings of an element are
+//! never live at the same time.
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, Weak,
+    },
+};
+
+use smithay::input::keyboard::{Keysym, ModifiersState};
+use tracing::debug;
+
+use super::registry::RegisteredElement;
+use crate::config::KeyPattern;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum ShortcutPriority {
+    /// Shortcuts of elements shown alongside others, e.g. OSDs
+    #[default]
+    Ambient,
+    /// Used for activated elements, regardless of the priority of their program
+    Focused,
+    /// Shortcuts of elements blocking other interaction, e.g. prompts
+    Modal,
+}
+
+/// Claims of an element, see `ShortcutRegistry::dump`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortcutClaim {
+    pub element: String,
+    pub priority: ShortcutPriority,
+}
+
+/// Owner of a combination, see `ShortcutRegistry::dump`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortcutOwner {
+    pub pattern: KeyPattern,
+    pub owner: ShortcutClaim,
+    /// Other claimants, which would get the combination without the owner, in routing order
+    pub shadowed: Vec<ShortcutClaim>,
+}
+
+/// Everything the registry knows about an element, replaced as a whole.
+pub(super) struct ShortcutTable {
+    pub element: Weak<dyn RegisteredElement>,
+    pub name: Option<String>,
+    pub priority: ShortcutPriority,
+    pub patterns: Vec<KeyPattern>,
+    /// See `next_mapping`, `None` while unmapped
+    pub mapped_at: Option<u64>,
+    pub restricted: bool,
+}
+
+impl ShortcutTable {
+    fn routable(&self) -> bool {
+        self.mapped_at.is_some() && !self.restricted
+    }
+
+    fn claim(&self, id: usize) -> ShortcutClaim {
+        ShortcutClaim {
+            element: self
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("element@{:x}", id)),
+            priority: self.priority,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Tables {
+    /// Tables by the address of their element
+    tables: Vec<(usize, ShortcutTable)>,
+    /// Combinations and pairs of elements (lower address first) already logged as conflicting
+    conflicts: HashSet<(KeyPattern, usize, usize)>,
+}
+
+impl Tables {
+    /// Claimants of `pattern`, in routing order.
+    fn claimants<'a>(
+        &'a self,
+        pattern: &'a KeyPattern,
+    ) -> impl Iterator<Item = &'a (usize, ShortcutTable)> + 'a {
+        let mut claimants = self
+            .tables
+            .iter()
+            .filter(|(_, table)| table.routable() && table.patterns.contains(pattern))
+            .collect::<Vec<_>>();
+        claimants.sort_by_key(|(_, table)| std::cmp::Reverse((table.priority, table.mapped_at)));
+        claimants.into_iter()
+    }
+
+    fn log_conflicts(&mut self, id: usize) {
+        let Some((_, table)) = self.tables.iter().find(|(i, _)| *i == id) else { return };
+        for pattern in &table.patterns {
+            for (other_id, other) in &self.tables {
+                if *other_id == id
+                    || other.priority != table.priority
+                    || !other.patterns.contains(pattern)
+                {
+                    continue;
+                }
+                let key = (pattern.clone(), id.min(*other_id), id.max(*other_id));
+                if self.conflicts.insert(key) {
+                    debug!(
+                        ?pattern,
+                        first = ?table.name,
+                        second = ?other.name,
+                        priority = ?table.priority,
+                        "Elements of equal priority claim the same shortcut, the most recently mapped wins"
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Shortcuts of all live elements, see `shortcut_registry`.
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    tables: Mutex<Tables>,
+}
+
+impl ShortcutRegistry {
+    /// Delivers a pressed combination to its owner, returns false if no element took it.
+    ///
+    /// Falls through to the next claimant, if the owner's program doesn't claim the combination
+    /// anymore (e.g. as it changed since publishing its table).
+    pub fn route(&self, modifiers: &ModifiersState, syms: &[Keysym]) -> bool {
+        let candidates = {
+            let tables = self.tables.lock().unwrap();
+            let mut candidates = Vec::new();
+            for (_, table) in &tables.tables {
+                for pattern in &table.patterns {
+                    if pattern.modifiers == *modifiers
+                        && syms.contains(&pattern.key)
+                        && !candidates.iter().any(|(p, _)| p == pattern)
+                    {
+                        let elements = tables
+                            .claimants(pattern)
+                            .filter_map(|(_, table)| table.element.upgrade())
+                            .collect::<Vec<_>>();
+                        candidates.push((pattern.clone(), elements));
+                    }
+                }
+            }
+            candidates
+        };
+        // elements are only locked once the registry isn't anymore, as they update their tables
+        candidates.into_iter().any(|(pattern, elements)| {
+            elements
+                .into_iter()
+                .any(|element| element.trigger_shortcut(&pattern))
+        })
+    }
+
+    /// Owners of all combinations claimed by mapped and unrestricted elements, e.g. for the
+    /// debug overlay.
+    pub fn dump(&self) -> Vec<ShortcutOwner> {
+        let tables = self.tables.lock().unwrap();
+        let mut patterns = Vec::<&KeyPattern>::new();
+        for (_, table) in tables.tables.iter().filter(|(_, table)| table.routable()) {
+            for pattern in &table.patterns {
+                if !patterns.contains(&pattern) {
+                    patterns.push(pattern);
+                }
+            }
+        }
+        patterns
+            .into_iter()
+            .filter_map(|pattern| {
+                let mut claimants = tables
+                    .claimants(pattern)
+                    .map(|(id, table)| table.claim(*id));
+                let owner = claimants.next()?;
+                Some(ShortcutOwner {
+                    pattern: pattern.clone(),
+                    owner,
+                    shadowed: claimants.collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// Replaces the table of `element`, tables without shortcuts are removed.
+    pub(super) fn set_table(&self, element: usize, table: ShortcutTable) {
+        let mut tables = self.tables.lock().unwrap();
+        tables.tables.retain(|(id, _)| *id != element);
+        if table.patterns.is_empty() {
+            return;
+        }
+        tables.tables.push((element, table));
+        tables.log_conflicts(element);
+    }
+
+    pub(super) fn remove(&self, element: usize) {
+        let mut tables = self.tables.lock().unwrap();
+        tables.tables.retain(|(id, _)| *id != element);
+        tables
+            .conflicts
+            .retain(|(_, a, b)| *a != element && *b != element);
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SHORTCUT_REGISTRY: ShortcutRegistry = ShortcutRegistry::default();
+}
+static NEXT_MAPPING: AtomicU64 = AtomicU64::new(0);
+
+/// Registry of the shortcuts of all live elements.
+pub fn shortcut_registry() -> &'static ShortcutRegistry {
+    &SHORTCUT_REGISTRY
+}
+
+/// Increasing value for `ShortcutTable::mapped_at`, later mappings win ties.
+pub(super) fn next_mapping() -> u64 {
+    NEXT_MAPPING.fetch_add(1, Ordering::Relaxed)
+}
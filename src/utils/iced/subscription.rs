@@ -0,0 +1,139 @@
+//! Declarative event sources of a `Program`, see `Program::subscriptions`.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    rc::Rc,
+    time::Duration,
+};
+
+use cosmic::iced_native::futures::{stream::BoxStream, Stream, StreamExt};
+use smithay::reexports::calloop::{
+    self,
+    timer::{TimeoutAction, Timer},
+    LoopHandle, RegistrationToken,
+};
+use tracing::warn;
+
+pub type Sink<M> = Rc<dyn Fn(M)>;
+type StartFn<M> =
+    Box<dyn FnOnce(&LoopHandle<'static, crate::state::Data>, Sink<M>) -> Option<RegistrationToken>>;
+
+enum Kind<M> {
+    Interval(Duration, Box<dyn FnMut() -> M>),
+    Stream(BoxStream<'static, M>),
+    Source(StartFn<M>),
+}
+
+/// An ongoing source of messages.
+///
+/// Subscriptions are identified by the key given on creation. They are started once
+/// returned by `Program::subscriptions` and stopped once they are not returned anymore.
+pub struct Subscription<M> {
+    id: u64,
+    kind: Kind<M>,
+}
+
+fn id(key: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<M: 'static> Subscription<M> {
+    /// Produces a message every `every`.
+    pub fn interval(
+        key: impl Hash,
+        every: Duration,
+        message: impl FnMut() -> M + 'static,
+    ) -> Subscription<M> {
+        Subscription {
+            id: id(key),
+            kind: Kind::Interval(every, Box::new(message)),
+        }
+    }
+
+    /// Forwards every item of `stream` as a message.
+    pub fn stream(
+        key: impl Hash,
+        stream: impl Stream<Item = M> + Send + 'static,
+    ) -> Subscription<M> {
+        Subscription {
+            id: id(key),
+            kind: Kind::Stream(stream.boxed()),
+        }
+    }
+
+    /// Starts a custom calloop event source.
+    ///
+    /// `start` gets passed a sink to deliver messages to and returns the token of the inserted source,
+    /// which is removed once the subscription is stopped.
+    /// The sink must only be called from the source's callback, not from `start` itself.
+    pub fn source<F>(key: impl Hash, start: F) -> Subscription<M>
+    where
+        F: FnOnce(&LoopHandle<'static, crate::state::Data>, Sink<M>) -> Option<RegistrationToken>
+            + 'static,
+    {
+        Subscription {
+            id: id(key),
+            kind: Kind::Source(Box::new(start)),
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Converts the messages produced by the subscription.
+    pub fn map<N: 'static>(self, f: impl Fn(M) -> N + Send + Sync + 'static) -> Subscription<N> {
+        let kind = match self.kind {
+            Kind::Interval(every, mut message) => {
+                Kind::Interval(every, Box::new(move || f(message())))
+            }
+            Kind::Stream(stream) => Kind::Stream(stream.map(f).boxed()),
+            Kind::Source(start) => Kind::Source(Box::new(move |handle, sink: Sink<N>| {
+                start(handle, Rc::new(move |message| sink(f(message))))
+            })),
+        };
+        Subscription { id: self.id, kind }
+    }
+
+    /// Distinguishes subscriptions with equal keys, e.g. of different programs of a combinator.
+    pub(super) fn salted(mut self, salt: u64) -> Subscription<M> {
+        self.id = id((self.id, salt));
+        self
+    }
+
+    pub(super) fn start(
+        self,
+        handle: &LoopHandle<'static, crate::state::Data>,
+        sink: Sink<M>,
+    ) -> Option<RegistrationToken> {
+        match self.kind {
+            Kind::Interval(every, mut message) => handle
+                .insert_source(Timer::from_duration(every), move |_, _, _| {
+                    sink(message());
+                    TimeoutAction::ToDuration(every)
+                })
+                .map_err(|err| warn!(?err, "Failed to start interval subscription"))
+                .ok(),
+            Kind::Stream(mut stream) => {
+                let (executor, scheduler) = calloop::futures::executor::<()>()
+                    .map_err(|err| warn!(?err, "Failed to start stream subscription"))
+                    .ok()?;
+                scheduler
+                    .schedule(async move {
+                        while let Some(message) = stream.next().await {
+                            sink(message);
+                        }
+                    })
+                    .ok()?;
+                handle
+                    .insert_source(executor, |_, _, _| {})
+                    .map_err(|err| warn!(?err, "Failed to start stream subscription"))
+                    .ok()
+            }
+            Kind::Source(start) => start(handle, sink),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Per-frame render statistics of `IcedElement`s, exported over a Unix socket.
+//!
+//! Statistics are only collected while at least one client is connected.
+//! Clients receive a stream of records, each encoded as (all integers little endian):
+//!
+//! | type  | field                                      |
+//! |-------|--------------------------------------------|
+//! | `u32` | length of the remaining record in bytes    |
+//! | `u64` | element id, stable for the element's life  |
+//! | `u64` | update duration in µs                      |
+//! | `u64` | draw duration in µs                        |
+//! | `u64` | buffer upload duration in µs               |
+//! | `i32` | buffer width                               |
+//! | `i32` | buffer height                              |
+//! | `u8`  | 1 if the frame was skipped (no redraw)     |
+//! | `u32` | primitives with invalid geometry dropped or clamped |
+//! | `u16` | length of the element name                 |
+//! | `[u8]`| element name (utf-8), see `IcedElement::set_name` |
+//!
+//! Clients not keeping up with the stream are disconnected.
+
+use std::{
+    cell::RefCell,
+    io::Write,
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use smithay::{
+    reexports::calloop::{
+        generic::Generic,
+        timer::{TimeoutAction, Timer},
+        Interest, LoopHandle, Mode, PostAction,
+    },
+    utils::{Buffer, Size},
+};
+use tracing::{debug, warn};
+
+use super::lock_global;
+
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameTelemetry {
+    pub element_id: u64,
+    pub name: Option<String>,
+    pub update: Duration,
+    pub draw: Duration,
+    pub upload: Duration,
+    pub buffer_size: Size<i32, Buffer>,
+    /// The buffer was up to date and not redrawn
+    pub skipped: bool,
+    /// Primitives with invalid geometry, that were dropped or clamped
+    pub sanitized: u32,
+}
+
+impl FrameTelemetry {
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let name = self.name.as_deref().unwrap_or_default().as_bytes();
+        let name = &name[..name.len().min(u16::MAX as usize)];
+        let len = 8 * 4 + 4 * 2 + 1 + 4 + 2 + name.len();
+        out.extend_from_slice(&(len as u32).to_le_bytes());
+        out.extend_from_slice(&self.element_id.to_le_bytes());
+        for duration in [self.update, self.draw, self.upload] {
+            out.extend_from_slice(&(duration.as_micros() as u64).to_le_bytes());
+        }
+        out.extend_from_slice(&self.buffer_size.w.to_le_bytes());
+        out.extend_from_slice(&self.buffer_size.h.to_le_bytes());
+        out.push(self.skipped as u8);
+        out.extend_from_slice(&self.sanitized.to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(name);
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref RECORDS: Mutex<Vec<FrameTelemetry>> = Mutex::new(Vec::new());
+}
+
+/// Whether statistics should be collected, i.e. any client is connected.
+pub(super) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub(super) fn record(telemetry: FrameTelemetry) {
+    if is_enabled() {
+        lock_global(&RECORDS).push(telemetry);
+    }
+}
+
+/// Listens for telemetry clients on `path`, e.g. `cosmic-comp-mon`.
+pub fn init_telemetry_socket(
+    handle: &LoopHandle<'static, crate::state::Data>,
+    path: &Path,
+) -> Result<()> {
+    // remove a stale socket of a previous instance
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to bind telemetry socket at {}", path.display()))?;
+    listener.set_nonblocking(true)?;
+
+    let clients = Rc::new(RefCell::new(Vec::<UnixStream>::new()));
+    let accepted = clients.clone();
+    handle
+        .insert_source(
+            Generic::new(listener, Interest::READ, Mode::Level),
+            move |_, listener, _| {
+                while let Ok((stream, _)) = listener.accept() {
+                    if let Err(err) = stream.set_nonblocking(true) {
+                        warn!(?err, "Failed to setup telemetry client");
+                        continue;
+                    }
+                    debug!("Telemetry client connected");
+                    accepted.borrow_mut().push(stream);
+                    ENABLED.store(true, Ordering::Relaxed);
+                }
+                Ok(PostAction::Continue)
+            },
+        )
+        .map_err(|err| err.error)
+        .with_context(|| "Failed to init the telemetry socket source")?;
+
+    let mut buffer = Vec::new();
+    handle
+        .insert_source(Timer::from_duration(FLUSH_INTERVAL), move |_, _, _| {
+            let records = std::mem::take(&mut *lock_global(&RECORDS));
+            buffer.clear();
+            for record in &records {
+                record.encode(&mut buffer);
+            }
+            let mut clients = clients.borrow_mut();
+            if !buffer.is_empty() {
+                clients.retain_mut(|client| match client.write_all(&buffer) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        debug!(?err, "Telemetry client disconnected");
+                        false
+                    }
+                });
+            }
+            ENABLED.store(!clients.is_empty(), Ordering::Relaxed);
+            TimeoutAction::ToDuration(FLUSH_INTERVAL)
+        })
+        .map_err(|err| err.error)
+        .with_context(|| "Failed to init the telemetry timer")?;
+
+    Ok(())
+}
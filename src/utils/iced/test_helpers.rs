@@ -0,0 +1,469 @@
+//! Drives `IcedElement`s in tests of programs.
+//!
+//! `IcedElementTestHarness` runs an element without a compositor. Input is delivered to the
+//! program directly, bypassing seats and focus handling. The element's event loop is never
+//! dispatched, so subscriptions, futures and timers of the element don't run, messages have to
+//! be delivered via `IcedElement::queue_message`.
+//!
+//! `HeadlessCompositor` runs elements in a `Space` on a headless output instead, with input
+//! delivered through the element's `PointerTarget` and `KeyboardTarget` implementations
+//! by a test seat, and an event loop that may be dispatched.
+
+use std::{
+    ffi::OsString,
+    time::{Duration, Instant},
+};
+
+use cosmic::iced_native::{
+    event::Event,
+    keyboard::{Event as KeyboardEvent, KeyCode, Modifiers},
+    mouse::{Button as MouseButton, Event as MouseEvent},
+    Point as IcedPoint,
+};
+use cosmic::{iced::widget::text, Element, Theme};
+use smithay::{
+    backend::input::{ButtonState, TouchSlot},
+    desktop::Space,
+    input::{
+        keyboard::KeyboardTarget,
+        pointer::{AxisFrame, ButtonEvent, MotionEvent, PointerTarget},
+        Seat,
+    },
+    output::{Mode, Output, PhysicalProperties, Scale, Subpixel},
+    reexports::{
+        calloop::{EventLoop, LoopHandle},
+        wayland_server::Display,
+    },
+    utils::{Buffer, Logical, Physical, Point, Size, Transform, SERIAL_COUNTER},
+};
+
+use super::{buffer, IcedElement, Program, RingEventSource};
+use crate::{
+    input::{TabletPadTarget, TouchTarget},
+    state::{Data, State},
+};
+
+/// Rasterized content of an element, ARGB8888 (premultiplied) row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub size: Size<i32, Buffer>,
+    pub pixels: Vec<u32>,
+    /// Primitives with invalid geometry, that were dropped or clamped
+    pub sanitized: u32,
+}
+
+impl Snapshot {
+    pub fn pixel(&self, x: i32, y: i32) -> Option<u32> {
+        if x < 0 || y < 0 || x >= self.size.w || y >= self.size.h {
+            return None;
+        }
+        self.pixels.get((y * self.size.w + x) as usize).copied()
+    }
+}
+
+/// Static text, drawn through widgets only (without hooks), for tests not about the content.
+pub struct Label;
+
+impl Program for Label {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Label").into()
+    }
+}
+
+pub struct IcedElementTestHarness<P: Program + Send + 'static> {
+    element: IcedElement<P>,
+    // keeps the element's sources alive
+    _event_loop: EventLoop<'static, crate::state::Data>,
+}
+
+impl<P: Program + Send + 'static> IcedElementTestHarness<P> {
+    pub fn new(program: P, size: impl Into<Size<i32, Logical>>) -> IcedElementTestHarness<P> {
+        let event_loop = EventLoop::try_new().expect("Failed to create event loop");
+        let element = IcedElement::new(program, size, event_loop.handle());
+        IcedElementTestHarness {
+            element,
+            _event_loop: event_loop,
+        }
+    }
+
+    pub fn element(&self) -> &IcedElement<P> {
+        &self.element
+    }
+
+    /// Renders the program with `theme` from now on.
+    pub fn set_theme(&self, theme: Theme) {
+        self.element.set_theme(theme);
+    }
+
+    /// Moves the cursor to `point` and clicks the left mouse button.
+    pub fn click_at(&self, point: impl Into<Point<f64, Logical>>) {
+        let point = point.into();
+        let mut internal = self.element.lock();
+        internal.cursor_pos = Some(point);
+        for event in [
+            MouseEvent::CursorMoved {
+                position: IcedPoint::new(point.x as f32, point.y as f32),
+            },
+            MouseEvent::ButtonPressed(MouseButton::Left),
+            MouseEvent::ButtonReleased(MouseButton::Left),
+        ] {
+            internal.state.queue_event(Event::Mouse(event));
+        }
+        let _ = internal.update(true);
+    }
+
+    /// Presses and releases `key` with `modifiers` held.
+    pub fn key_press(&self, key: KeyCode, modifiers: Modifiers) {
+        let mut internal = self.element.lock();
+        internal
+            .state
+            .queue_event(Event::Keyboard(KeyboardEvent::KeyPressed {
+                key_code: key,
+                modifiers,
+            }));
+        internal
+            .state
+            .queue_event(Event::Keyboard(KeyboardEvent::KeyReleased {
+                key_code: key,
+                modifiers,
+            }));
+        let _ = internal.update(true);
+    }
+
+    /// Types `text` into the focused widget.
+    pub fn type_text(&self, text: &str) {
+        let mut internal = self.element.lock();
+        for c in text.chars() {
+            internal
+                .state
+                .queue_event(Event::Keyboard(KeyboardEvent::CharacterReceived(c)));
+        }
+        let _ = internal.update(true);
+    }
+
+    /// Lets `duration` pass and applies everything that became due,
+    /// i.e. queued messages and time based state like transitions and press feedback.
+    pub fn tick(&self, duration: Duration) {
+        std::thread::sleep(duration);
+        let mut internal = self.element.lock();
+        let _ = internal.frame_done(None);
+        let _ = internal.update(true);
+    }
+
+    /// Rasterizes the current state of the program at `scale`.
+    pub fn snapshot(&self, scale: f64) -> Snapshot {
+        snapshot(&self.element, scale)
+    }
+}
+
+fn snapshot<P: Program + Send + 'static>(element: &IcedElement<P>, scale: f64) -> Snapshot {
+    let mut internal = element.lock();
+    let _ = internal.update(true);
+    internal.sync_program();
+    let size = buffer::buffer_size(internal.size, scale);
+    let mut pixels = vec![0u32; (size.w.max(0) * size.h.max(0)) as usize];
+    let mut sanitized = 0;
+    if !pixels.is_empty() {
+        let mut rasterizer = internal.rasterizer();
+        rasterizer
+            .rasterize(bytemuck::cast_slice_mut(&mut pixels), size, scale, None)
+            .expect("Failed to rasterize program");
+        sanitized = rasterizer.sanitation.sanitized();
+    }
+    Snapshot {
+        size,
+        pixels,
+        sanitized,
+    }
+}
+
+/// Compositor state with a single headless output and seat, for integration tests of elements.
+///
+/// Pointer locations are local to the element receiving them. Frames are only rasterized into
+/// `Snapshot`s, as there is no renderer to import buffers into.
+pub struct HeadlessCompositor<P: Program + Send + 'static> {
+    event_loop: EventLoop<'static, Data>,
+    data: Data,
+    seat: Seat<State>,
+    output: Output,
+    space: Space<IcedElement<P>>,
+    start: Instant,
+}
+
+impl<P: Program + Send + 'static> HeadlessCompositor<P> {
+    pub fn new(output_size: impl Into<Size<i32, Physical>>, scale: f64) -> HeadlessCompositor<P> {
+        let event_loop = EventLoop::try_new().expect("Failed to create event loop");
+        let display = Display::<State>::new().expect("Failed to create display");
+        let mut state = State::new(
+            &display.handle(),
+            OsString::from("headless"),
+            event_loop.handle(),
+            event_loop.get_signal(),
+        );
+
+        let mode = Mode {
+            size: output_size.into(),
+            refresh: 60_000,
+        };
+        let output = Output::new(
+            String::from("HEADLESS-1"),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: String::from("COSMIC"),
+                model: String::from("Headless"),
+            },
+        );
+        output.add_mode(mode);
+        output.set_preferred(mode);
+        output.change_current_state(
+            Some(mode),
+            Some(Transform::Normal),
+            Some(Scale::Fractional(scale)),
+            Some((0, 0).into()),
+        );
+
+        let seat = crate::input::add_seat(
+            &display.handle(),
+            &mut state.common.seat_state,
+            &output,
+            &state.common.config,
+            String::from("headless"),
+        );
+        state.common.add_seat(seat.clone());
+
+        let mut space = Space::default();
+        space.map_output(&output, (0, 0));
+
+        HeadlessCompositor {
+            event_loop,
+            data: Data { display, state },
+            seat,
+            output,
+            space,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    pub fn seat(&self) -> &Seat<State> {
+        &self.seat
+    }
+
+    pub fn space(&self) -> &Space<IcedElement<P>> {
+        &self.space
+    }
+
+    pub fn handle(&self) -> LoopHandle<'static, Data> {
+        self.event_loop.handle()
+    }
+
+    /// Creates an element of `program` and maps it at `location`, entering the output
+    /// if they overlap. Like the shell, the location is also set on the element.
+    pub fn insert(
+        &mut self,
+        program: P,
+        size: impl Into<Size<i32, Logical>>,
+        location: impl Into<Point<i32, Logical>>,
+    ) -> IcedElement<P> {
+        let location = location.into();
+        let element = IcedElement::new(program, size, self.event_loop.handle());
+        self.space.map_element(element.clone(), location, false);
+        self.space.refresh();
+        element.set_location(location);
+        element
+    }
+
+    /// Unmaps `element`, leaving its outputs.
+    pub fn remove(&mut self, element: &IcedElement<P>) {
+        self.space.unmap_elem(element);
+        self.space.refresh();
+    }
+
+    /// Dispatches the event loop once, running due timers, futures and subscriptions.
+    pub fn dispatch(&mut self, timeout: Duration) {
+        self.event_loop
+            .dispatch(Some(timeout), &mut self.data)
+            .expect("Failed to dispatch event loop");
+    }
+
+    /// Presents a frame, i.e. sends frame callbacks to all mapped elements.
+    pub fn frame(&mut self) {
+        for element in self.space.elements() {
+            let _ = element.lock().frame_done(Some(&self.output));
+        }
+    }
+
+    /// Presents a frame and dispatches the event loop once, so the elements apply all pending
+    /// updates, e.g. of `IcedElement::queue_message`.
+    pub fn settle(&mut self) {
+        self.frame();
+        self.dispatch(Duration::ZERO);
+    }
+
+    /// Rasterizes the current state of `element` at the output's scale.
+    pub fn snapshot(&self, element: &IcedElement<P>) -> Snapshot {
+        snapshot(element, self.output.current_scale().fractional_scale())
+    }
+
+    pub fn pointer_enter(
+        &mut self,
+        element: &IcedElement<P>,
+        location: impl Into<Point<f64, Logical>>,
+    ) {
+        let location = location.into();
+        self.warp_pointer(element, location);
+        let event = self.motion_event(location);
+        PointerTarget::enter(element, &self.seat, &mut self.data.state, &event);
+    }
+
+    pub fn pointer_motion(
+        &mut self,
+        element: &IcedElement<P>,
+        location: impl Into<Point<f64, Logical>>,
+    ) {
+        let location = location.into();
+        self.warp_pointer(element, location);
+        let event = self.motion_event(location);
+        PointerTarget::motion(element, &self.seat, &mut self.data.state, &event);
+    }
+
+    /// Presses or releases `button`, e.g. `0x110` (`BTN_LEFT`).
+    pub fn pointer_button(&mut self, element: &IcedElement<P>, button: u32, state: ButtonState) {
+        let event = ButtonEvent {
+            serial: SERIAL_COUNTER.next_serial(),
+            time: self.time(),
+            button,
+            state,
+        };
+        PointerTarget::button(element, &self.seat, &mut self.data.state, &event);
+    }
+
+    /// Presses and releases the left button.
+    pub fn click(&mut self, element: &IcedElement<P>) {
+        self.pointer_button(element, 0x110, ButtonState::Pressed);
+        self.pointer_button(element, 0x110, ButtonState::Released);
+    }
+
+    /// Scrolls by `frame`, whose time is kept, as momentum scrolling depends on it.
+    pub fn pointer_axis(&mut self, element: &IcedElement<P>, frame: AxisFrame) {
+        PointerTarget::axis(element, &self.seat, &mut self.data.state, frame);
+    }
+
+    pub fn pointer_leave(&mut self, element: &IcedElement<P>) {
+        let time = self.time();
+        PointerTarget::leave(
+            element,
+            &self.seat,
+            &mut self.data.state,
+            SERIAL_COUNTER.next_serial(),
+            time,
+        );
+    }
+
+    /// Presses or releases a button of a tablet pad, as delivered to the keyboard focus.
+    pub fn pad_button(&mut self, element: &IcedElement<P>, button: u32, state: ButtonState) {
+        TabletPadTarget::pad_button(element, &self.seat, &mut self.data.state, button, state);
+    }
+
+    /// Moves the ring of a tablet pad to `degrees`.
+    pub fn pad_ring(&mut self, element: &IcedElement<P>, degrees: f64) {
+        TabletPadTarget::pad_ring(
+            element,
+            &self.seat,
+            &mut self.data.state,
+            RingEventSource::Finger,
+            degrees,
+        );
+    }
+
+    /// Presses a finger of `slot` at `location`, relative to `element`.
+    pub fn touch_down(
+        &mut self,
+        element: &IcedElement<P>,
+        slot: TouchSlot,
+        location: impl Into<Point<f64, Logical>>,
+    ) {
+        TouchTarget::down(
+            element,
+            &self.seat,
+            &mut self.data.state,
+            slot,
+            location.into(),
+        );
+    }
+
+    pub fn touch_up(&mut self, element: &IcedElement<P>, slot: TouchSlot) {
+        TouchTarget::up(element, &self.seat, &mut self.data.state, slot);
+    }
+
+    pub fn keyboard_enter(&mut self, element: &IcedElement<P>) {
+        KeyboardTarget::enter(
+            element,
+            &self.seat,
+            &mut self.data.state,
+            Vec::new(),
+            SERIAL_COUNTER.next_serial(),
+        );
+    }
+
+    /// Presses and releases `key` with `modifiers` held.
+    ///
+    /// Delivered to the program directly, the seat's keyboard would need the raw keycodes of
+    /// its keymap instead.
+    pub fn key_press(&mut self, element: &IcedElement<P>, key: KeyCode, modifiers: Modifiers) {
+        let mut internal = element.lock();
+        for event in [
+            KeyboardEvent::KeyPressed {
+                key_code: key,
+                modifiers,
+            },
+            KeyboardEvent::KeyReleased {
+                key_code: key,
+                modifiers,
+            },
+        ] {
+            internal.state.queue_event(Event::Keyboard(event));
+        }
+        let _ = internal.update(true);
+    }
+
+    pub fn keyboard_leave(&mut self, element: &IcedElement<P>) {
+        KeyboardTarget::leave(
+            element,
+            &self.seat,
+            &mut self.data.state,
+            SERIAL_COUNTER.next_serial(),
+        );
+    }
+
+    /// Moves the pointer of the seat to `location` within `element`, without focusing anything.
+    ///
+    /// Elements on an output translate pointer positions from the seat's pointer location,
+    /// see `IcedElement::set_output_offset`.
+    fn warp_pointer(&mut self, element: &IcedElement<P>, location: Point<f64, Logical>) {
+        let origin = self.space.element_location(element).unwrap_or_default();
+        let event = self.motion_event(origin.to_f64() + location);
+        if let Some(pointer) = self.seat.get_pointer() {
+            pointer.motion(&mut self.data.state, None, &event);
+        }
+    }
+
+    fn motion_event(&self, location: Point<f64, Logical>) -> MotionEvent {
+        MotionEvent {
+            location,
+            serial: SERIAL_COUNTER.next_serial(),
+            time: self.time(),
+        }
+    }
+
+    /// Milliseconds since creation, for event timestamps.
+    fn time(&self) -> u32 {
+        self.start.elapsed().as_millis() as u32
+    }
+}
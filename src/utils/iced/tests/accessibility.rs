@@ -0,0 +1,35 @@
+use crate::utils::iced::{accessibility::state_set, focus::FocusedWidget};
+
+const ENABLED: u32 = 1 << 8;
+const FOCUSABLE: u32 = 1 << 11;
+const FOCUSED: u32 = 1 << 12;
+const EDITABLE: u32 = 1 << 7;
+
+fn focused(editable: bool) -> FocusedWidget {
+    FocusedWidget {
+        index: 0,
+        id: None,
+        editable,
+    }
+}
+
+#[test]
+fn unfocused_elements_are_focusable() {
+    assert_eq!(state_set(None), [ENABLED | FOCUSABLE, 0]);
+}
+
+#[test]
+fn focused_widgets_focus_the_element() {
+    assert_eq!(
+        state_set(Some(&focused(false))),
+        [ENABLED | FOCUSABLE | FOCUSED, 0]
+    );
+}
+
+#[test]
+fn focused_text_inputs_are_editable() {
+    assert_eq!(
+        state_set(Some(&focused(true))),
+        [ENABLED | FOCUSABLE | FOCUSED | EDITABLE, 0]
+    );
+}
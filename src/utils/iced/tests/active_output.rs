@@ -0,0 +1,48 @@
+use smithay::desktop::space::SpaceElement;
+
+use crate::utils::iced::{
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement,
+};
+
+fn cursor(element: &IcedElement<Label>) -> Option<(f64, f64)> {
+    element
+        .0
+        .lock()
+        .unwrap()
+        .cursor_pos
+        .map(|pos| (pos.x, pos.y))
+}
+
+#[test]
+fn pointer_sets_the_active_output() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (50, 20));
+    assert_eq!(element.active_output(), None);
+
+    compositor.pointer_enter(&element, (10.0, 5.0));
+    assert_eq!(element.active_output().as_ref(), Some(compositor.output()));
+    assert_eq!(cursor(&element), Some((10.0, 5.0)));
+}
+
+#[test]
+fn pointer_is_translated_by_the_offset_on_the_active_output() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (50, 20));
+    compositor.pointer_enter(&element, (10.0, 5.0));
+
+    // e.g. a panel spanning outputs, mapped further left on this one
+    element.set_output_offset(compositor.output(), (40, 20).into());
+    compositor.pointer_motion(&element, (10.0, 5.0));
+    assert_eq!(cursor(&element), Some((20.0, 5.0)));
+}
+
+#[test]
+fn leaving_the_active_output_clears_it() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (50, 20));
+    compositor.pointer_enter(&element, (10.0, 5.0));
+
+    element.output_leave(compositor.output());
+    assert_eq!(element.active_output(), None);
+}
@@ -0,0 +1,70 @@
+use smithay::{
+    backend::renderer::{element::memory::MemoryRenderBufferRenderElement, test::DummyRenderer},
+    utils::Scale,
+};
+
+use crate::utils::iced::{
+    scratch::cleared,
+    test_helpers::{HeadlessCompositor, IcedElementTestHarness, Label},
+    IcedElement,
+};
+
+fn layer(element: &IcedElement<Label>) -> (*const u32, usize) {
+    let internal = element.0.lock().unwrap();
+    (
+        internal.scratch.layer.as_ptr(),
+        internal.scratch.layer.capacity(),
+    )
+}
+
+#[test]
+fn cleared_buffers_keep_their_allocation() {
+    let mut buffer = vec![0xffu32; 64];
+    let ptr = buffer.as_ptr();
+
+    let pixels = cleared(&mut buffer, 32);
+    assert!(pixels.iter().all(|pixel| *pixel == 0));
+    assert_eq!(pixels.len(), 32);
+    assert_eq!(buffer.as_ptr(), ptr);
+
+    buffer.iter_mut().for_each(|pixel| *pixel = 0xff);
+    assert!(cleared(&mut buffer, 64).iter().all(|pixel| *pixel == 0));
+}
+
+#[test]
+fn redraws_reuse_the_blending_layer() {
+    let harness = IcedElementTestHarness::new(Label, (100, 50));
+    harness.element().set_linear_blending(true);
+
+    let first = harness.snapshot(1.0);
+    let allocation = layer(harness.element());
+    assert_eq!(allocation.1, 100 * 50);
+
+    let second = harness.snapshot(1.0);
+    assert_eq!(layer(harness.element()), allocation);
+    assert_eq!(first.pixels, second.pixels);
+}
+
+#[test]
+fn render_elements_are_appended() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+    let mut renderer = DummyRenderer::new();
+
+    let mut elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> = Vec::with_capacity(4);
+    element.render_elements_into(
+        &mut renderer,
+        (0, 0).into(),
+        Scale::from(1.0),
+        1.0,
+        &mut elements,
+    );
+    element.render_elements_into(
+        &mut renderer,
+        (200, 0).into(),
+        Scale::from(1.0),
+        1.0,
+        &mut elements,
+    );
+    assert_eq!(elements.len(), 2);
+}
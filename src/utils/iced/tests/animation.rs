@@ -0,0 +1,167 @@
+use std::time::{Duration, Instant};
+
+use cosmic::{iced::widget::text, Element};
+use iced_softbuffer::native::raqote::{
+    DrawOptions, DrawTarget, PathBuilder, SolidSource, Source, StrokeStyle,
+};
+
+use super::{assert_goldens, single_golden};
+use crate::utils::iced::{golden::GoldenPrograms, Animation, Curve, Program};
+
+const DURATION: Duration = Duration::from_millis(100);
+const BEZIERS: [Curve; 6] = [
+    Curve::Linear,
+    Curve::Ease,
+    Curve::EaseIn,
+    Curve::EaseOut,
+    Curve::EaseInOut,
+    Curve::CubicBezier(0.1, 0.8, 0.2, 1.0),
+];
+const UNDERDAMPED: Curve = Curve::Spring {
+    stiffness: 200.0,
+    damping: 10.0,
+    initial_velocity: 0.0,
+};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn beziers_run_from_start_to_end() {
+    for curve in BEZIERS {
+        assert!(curve.sample(0.0).abs() < 1e-4, "{:?}", curve);
+        assert!((curve.sample(1.0) - 1.0).abs() < 1e-4, "{:?}", curve);
+    }
+    assert!((Curve::Linear.sample(0.3) - 0.3).abs() < 1e-4);
+    assert!(Curve::EaseIn.sample(0.5) < 0.5);
+    assert!(Curve::EaseOut.sample(0.5) > 0.5);
+    assert!((Curve::EaseInOut.sample(0.5) - 0.5).abs() < 1e-3);
+    // out of range progress is clamped
+    assert_eq!(Curve::Ease.sample(2.0), Curve::Ease.sample(1.0));
+}
+
+#[test]
+fn bezier_animations_take_their_duration() {
+    let start = Instant::now();
+    let animation = Animation::new(Curve::Linear, DURATION, 10.0, 20.0, start);
+    assert_eq!(animation.value(start), 10.0);
+    assert!((animation.value(start + ms(50)) - 15.0).abs() < 1e-3);
+    assert!(!animation.is_complete(start + ms(50)));
+    assert_eq!(animation.value(start + ms(100)), 20.0);
+    assert!(animation.is_complete(start + ms(100)));
+    assert_eq!(animation.velocity(start + ms(100)), 0.0);
+}
+
+#[test]
+fn short_distances_take_proportionally_less() {
+    let start = Instant::now();
+    let animation = Animation::new(Curve::EaseOut, DURATION, 0.0, 0.5, start);
+    assert!(!animation.is_complete(start + ms(40)));
+    assert!(animation.is_complete(start + ms(50)));
+}
+
+#[test]
+fn retargeted_beziers_continue_from_the_current_value() {
+    let start = Instant::now();
+    let mut animation = Animation::new(Curve::Linear, DURATION, 0.0, 1.0, start);
+    let now = start + ms(50);
+    let value = animation.value(now);
+    animation.retarget(0.0, now);
+    assert_eq!(animation.target(), 0.0);
+    assert!((animation.value(now) - value).abs() < 1e-4);
+    // half the distance back
+    assert!(animation.is_complete(now + ms(50)));
+}
+
+#[test]
+fn critically_damped_springs_settle_without_overshooting() {
+    let start = Instant::now();
+    let animation = Animation::new(Curve::SPRING, DURATION, 0.0, 1.0, start);
+    let mut previous = 0.0;
+    for step in 1..=200 {
+        let value = animation.value(start + ms(step * 10));
+        assert!(value <= 1.0 + 1e-4, "overshot to {}", value);
+        assert!(value >= previous - 1e-4);
+        previous = value;
+    }
+    // springs ignore the duration
+    assert!(!animation.is_complete(start + DURATION));
+    assert!(animation.is_complete(start + Duration::from_secs(2)));
+    assert_eq!(animation.value(start + Duration::from_secs(2)), 1.0);
+}
+
+#[test]
+fn underdamped_springs_overshoot() {
+    let start = Instant::now();
+    let animation = Animation::new(UNDERDAMPED, DURATION, 0.0, 1.0, start);
+    let peak = (1..100)
+        .map(|step| animation.value(start + ms(step * 10)))
+        .fold(0.0, f32::max);
+    assert!(peak > 1.0);
+}
+
+#[test]
+fn retargeted_springs_keep_their_velocity() {
+    let start = Instant::now();
+    let mut animation = Animation::new(Curve::SPRING, DURATION, 0.0, 1.0, start);
+    let now = start + ms(100);
+    let (value, velocity) = (animation.value(now), animation.velocity(now));
+    assert!(velocity > 0.0);
+    animation.retarget(0.0, now);
+    assert!((animation.value(now) - value).abs() < 1e-4);
+    assert!((animation.velocity(now) - velocity).abs() < 1e-3);
+}
+
+#[test]
+fn settled_animations_jump_to_their_target() {
+    let start = Instant::now();
+    let mut animation = Animation::new(UNDERDAMPED, DURATION, 0.0, 1.0, start);
+    animation.settle(start + ms(10));
+    assert!(animation.is_complete(start + ms(10)));
+    assert_eq!(animation.value(start + ms(10)), 1.0);
+}
+
+/// Plots the eased progress of its curves, one above the other.
+struct Plot(Vec<Curve>);
+
+impl Program for Plot {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("").into()
+    }
+
+    fn background(&self, target: &mut DrawTarget<&mut [u32]>) {
+        let (w, h) = (target.width() as f32, target.height() as f32);
+        let row = h / self.0.len() as f32;
+        let source = Source::Solid(SolidSource::from_unpremultiplied_argb(255, 255, 255, 255));
+        for (i, curve) in self.0.iter().enumerate() {
+            // springs overshoot, so leave some headroom
+            let (top, height) = (row * i as f32 + row * 0.2, row * 0.6);
+            let mut pb = PathBuilder::new();
+            pb.move_to(0.0, top + height);
+            for x in 1..=w as u32 {
+                let progress = x as f32 / w;
+                pb.line_to(x as f32, top + height * (1.0 - curve.sample(progress)));
+            }
+            target.stroke(
+                &pb.finish(),
+                &source,
+                &StrokeStyle {
+                    width: 2.0,
+                    ..Default::default()
+                },
+                &DrawOptions::new(),
+            );
+        }
+    }
+}
+
+#[test]
+fn curves_golden() {
+    let mut programs = GoldenPrograms::new();
+    programs.register("curves-bezier", || Plot(BEZIERS.to_vec()));
+    programs.register("curves-spring", || Plot(vec![Curve::SPRING, UNDERDAMPED]));
+    assert_goldens(&programs, &single_golden(1.0, (200, 240)));
+}
@@ -0,0 +1,141 @@
+use std::{future, time::Duration};
+
+use calloop::{futures::executor, EventLoop};
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+
+use crate::utils::iced::{
+    quota::{AsyncQuota, DEFAULT_MAX_CONCURRENT, DEFAULT_MAX_QUEUED},
+    test_helpers::HeadlessCompositor,
+    AsyncPriority, AsyncQuotaStats, Program, UpdateContext,
+};
+
+/// A quota on an event loop collecting the outputs of its futures in order of completion.
+fn quota() -> (EventLoop<'static, Vec<u32>>, AsyncQuota<u32>) {
+    let event_loop = EventLoop::try_new().unwrap();
+    let (executor, scheduler) = executor::<u32>().unwrap();
+    event_loop
+        .handle()
+        .insert_source(executor, |output, _, outputs: &mut Vec<u32>| {
+            outputs.push(output)
+        })
+        .unwrap();
+    (event_loop, AsyncQuota::new(scheduler))
+}
+
+fn ready(output: u32) -> future::Ready<u32> {
+    future::ready(output)
+}
+
+fn stats(running: usize, queued: usize, dropped: u64) -> AsyncQuotaStats {
+    AsyncQuotaStats {
+        running,
+        queued,
+        dropped,
+    }
+}
+
+#[test]
+fn futures_below_the_quota_start_right_away() {
+    let (mut event_loop, quota) = quota();
+    for output in 0..3 {
+        quota.schedule(Box::pin(ready(output)), AsyncPriority::Normal, None);
+    }
+    assert_eq!(quota.stats(), stats(3, 0, 0));
+
+    let mut outputs = Vec::new();
+    event_loop.dispatch(Duration::ZERO, &mut outputs).unwrap();
+    assert_eq!(outputs, vec![0, 1, 2]);
+    assert_eq!(quota.stats(), stats(0, 0, 0));
+}
+
+#[test]
+fn queued_futures_start_by_priority_then_in_order() {
+    let (mut event_loop, quota) = quota();
+    quota.set_limits(1, DEFAULT_MAX_QUEUED, None);
+    quota.schedule(Box::pin(ready(0)), AsyncPriority::Normal, None);
+    quota.schedule(Box::pin(ready(1)), AsyncPriority::Low, None);
+    quota.schedule(Box::pin(ready(2)), AsyncPriority::Normal, None);
+    quota.schedule(Box::pin(ready(3)), AsyncPriority::High, None);
+    quota.schedule(Box::pin(ready(4)), AsyncPriority::Normal, None);
+    assert_eq!(quota.stats(), stats(1, 4, 0));
+
+    let mut outputs = Vec::new();
+    for _ in 0..10 {
+        event_loop.dispatch(Duration::ZERO, &mut outputs).unwrap();
+    }
+    assert_eq!(outputs, vec![0, 3, 2, 4, 1]);
+    assert_eq!(quota.stats(), stats(0, 0, 0));
+}
+
+#[test]
+fn overflowing_queues_drop_the_oldest_of_the_lowest_priority() {
+    let (_event_loop, quota) = quota();
+    quota.set_limits(1, 2, None);
+    quota.schedule(Box::pin(future::pending()), AsyncPriority::Normal, None);
+    quota.schedule(Box::pin(ready(1)), AsyncPriority::Normal, None);
+    quota.schedule(Box::pin(ready(2)), AsyncPriority::Low, None);
+    quota.schedule(Box::pin(ready(3)), AsyncPriority::Low, None);
+    assert_eq!(quota.stats(), stats(1, 2, 1));
+
+    // lowering the limit drops as well
+    quota.set_limits(1, 0, None);
+    assert_eq!(quota.stats(), stats(1, 0, 3));
+}
+
+#[test]
+fn raising_the_limit_starts_queued_futures() {
+    let (_event_loop, quota) = quota();
+    quota.set_limits(1, DEFAULT_MAX_QUEUED, None);
+    for _ in 0..3 {
+        quota.schedule(Box::pin(future::pending()), AsyncPriority::Normal, None);
+    }
+    assert_eq!(quota.stats(), stats(1, 2, 0));
+    quota.set_limits(DEFAULT_MAX_CONCURRENT, DEFAULT_MAX_QUEUED, None);
+    assert_eq!(quota.stats(), stats(3, 0, 0));
+}
+
+#[test]
+fn closing_drops_queued_futures() {
+    let (_event_loop, quota) = quota();
+    quota.set_limits(1, DEFAULT_MAX_QUEUED, None);
+    quota.schedule(Box::pin(future::pending()), AsyncPriority::Normal, None);
+    quota.schedule(Box::pin(ready(1)), AsyncPriority::Normal, None);
+    quota.close();
+    quota.schedule(Box::pin(ready(2)), AsyncPriority::Normal, None);
+    assert_eq!(quota.stats(), stats(1, 0, 0));
+}
+
+/// Waits forever on every message, with the message as priority.
+struct Waiter;
+
+impl Program for Waiter {
+    type Message = AsyncPriority;
+
+    fn update_with_context(
+        &mut self,
+        priority: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        ctx.spawn_prioritized(priority, future::pending::<()>(), move |_| priority)
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Waiting").into()
+    }
+}
+
+#[test]
+fn elements_apply_their_quota_to_commands() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Waiter, (100, 40), (0, 0));
+    element.set_async_quota(1, 1);
+    for priority in [
+        AsyncPriority::Normal,
+        AsyncPriority::Low,
+        AsyncPriority::High,
+    ] {
+        element.queue_message(priority);
+        compositor.settle();
+    }
+    assert_eq!(element.async_stats(), stats(1, 1, 1));
+}
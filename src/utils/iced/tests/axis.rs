@@ -0,0 +1,210 @@
+use std::time::{Duration, Instant};
+
+use cosmic::{iced::widget::text, iced_native::mouse::ScrollDelta, Element};
+use smithay::{
+    backend::input::{Axis, AxisSource},
+    input::pointer::AxisFrame,
+};
+
+use crate::utils::iced::{
+    scroll::{delta, is_stop, Kinetic, ScrollPhysics, KINETIC_INTERVAL},
+    test_helpers::HeadlessCompositor,
+    IcedElement, Program,
+};
+
+fn finger(x: f64, y: f64) -> AxisFrame {
+    AxisFrame::new(0)
+        .source(AxisSource::Finger)
+        .value(Axis::Horizontal, x)
+        .value(Axis::Vertical, y)
+}
+
+fn lifted() -> AxisFrame {
+    AxisFrame::new(0)
+        .source(AxisSource::Finger)
+        .stop(Axis::Horizontal)
+        .stop(Axis::Vertical)
+}
+
+#[test]
+fn stop_frames_are_empty_lines() {
+    assert!(is_stop(&lifted()));
+    assert_eq!(
+        delta(&lifted(), ScrollPhysics::Pixels),
+        ScrollDelta::Lines { x: 0.0, y: 0.0 }
+    );
+}
+
+#[test]
+fn stopping_a_single_axis_is_a_stop() {
+    let frame = AxisFrame::new(0)
+        .source(AxisSource::Finger)
+        .stop(Axis::Vertical);
+    assert!(is_stop(&frame));
+}
+
+#[test]
+fn frames_with_movement_arent_stops() {
+    let frame = finger(0.0, 4.0).stop(Axis::Horizontal);
+    assert!(!is_stop(&frame));
+    assert_eq!(
+        delta(&frame, ScrollPhysics::Pixels),
+        ScrollDelta::Pixels { x: 0.0, y: 4.0 }
+    );
+    assert!(!is_stop(&finger(0.0, 0.0)), "empty frames don't stop");
+}
+
+#[test]
+fn wheels_scroll_by_lines() {
+    let frame = AxisFrame::new(0)
+        .source(AxisSource::Wheel)
+        .value(Axis::Vertical, 15.0)
+        .discrete(Axis::Vertical, 1);
+    assert_eq!(
+        delta(&frame, ScrollPhysics::Discrete),
+        ScrollDelta::Lines { x: 0.0, y: 1.0 }
+    );
+    // continuous wheels without detents
+    let frame = AxisFrame::new(0).value(Axis::Vertical, 30.0);
+    assert_eq!(
+        delta(&frame, ScrollPhysics::Discrete),
+        ScrollDelta::Lines { x: 0.0, y: 2.0 }
+    );
+    assert_eq!(
+        delta(&frame, ScrollPhysics::Pixels),
+        ScrollDelta::Pixels { x: 0.0, y: 30.0 }
+    );
+}
+
+#[test]
+fn physics_depend_on_the_source() {
+    assert_eq!(
+        ScrollPhysics::for_source(AxisSource::Wheel),
+        ScrollPhysics::Discrete
+    );
+    assert_eq!(
+        ScrollPhysics::for_source(AxisSource::WheelTilt),
+        ScrollPhysics::Discrete
+    );
+    assert_eq!(
+        ScrollPhysics::for_source(AxisSource::Continuous),
+        ScrollPhysics::Pixels
+    );
+    assert!(matches!(
+        ScrollPhysics::for_source(AxisSource::Finger),
+        ScrollPhysics::Momentum { .. }
+    ));
+}
+
+#[test]
+fn momentum_needs_a_fast_release() {
+    let mut kinetic = Kinetic::default();
+    assert!(!kinetic.release(2500.0), "nothing was scrolled");
+
+    kinetic.record(0, (0.0, 0.1));
+    kinetic.record(90, (0.0, 0.1));
+    assert!(!kinetic.release(2500.0));
+
+    kinetic.record(0, (0.0, 20.0));
+    kinetic.record(8, (0.0, 20.0));
+    assert!(kinetic.release(2500.0));
+}
+
+#[test]
+fn only_recent_deltas_count() {
+    let mut kinetic = Kinetic::default();
+    kinetic.record(0, (0.0, 1000.0));
+    kinetic.record(200, (0.0, 0.1));
+    assert!(!kinetic.release(2500.0));
+}
+
+#[test]
+fn momentum_decelerates_until_it_stops() {
+    let mut kinetic = Kinetic::default();
+    kinetic.record(0, (0.0, 20.0));
+    kinetic.record(8, (0.0, 20.0));
+    kinetic.record(16, (0.0, 20.0));
+    assert!(kinetic.release(2500.0));
+
+    let mut deltas = Vec::new();
+    while let Some((x, y)) = kinetic.step(KINETIC_INTERVAL) {
+        assert_eq!(x, 0.0);
+        deltas.push(y);
+        assert!(deltas.len() < 1000, "momentum didn't stop");
+    }
+    assert!(deltas.windows(2).all(|w| w[1] < w[0]));
+    // v² / 2a, with the initial velocity of 3750 px/s
+    let distance: f64 = deltas.iter().sum();
+    assert!((distance - 2812.5).abs() < 50.0, "{}", distance);
+}
+
+#[test]
+fn stopped_momentum_is_gone() {
+    let mut kinetic = Kinetic::default();
+    kinetic.record(0, (0.0, 20.0));
+    kinetic.record(8, (0.0, 20.0));
+    assert!(kinetic.release(2500.0));
+    kinetic.stop();
+    assert_eq!(kinetic.step(KINETIC_INTERVAL), None);
+}
+
+/// Coasts for a fraction of a second only, unless it configured other physics.
+struct Scrollable(Option<ScrollPhysics>);
+
+impl Program for Scrollable {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Scrollable").into()
+    }
+
+    fn configure_scroll_physics(&self, source: AxisSource) -> ScrollPhysics {
+        self.0.unwrap_or(match source {
+            AxisSource::Finger => ScrollPhysics::Momentum {
+                deceleration: 100_000.0,
+            },
+            source => ScrollPhysics::for_source(source),
+        })
+    }
+}
+
+fn kinetic_timer(element: &IcedElement<Scrollable>) -> bool {
+    element.0.lock().unwrap().kinetic_timer.is_some()
+}
+
+fn swipe(compositor: &mut HeadlessCompositor<Scrollable>, element: &IcedElement<Scrollable>) {
+    compositor.pointer_enter(element, (50.0, 20.0));
+    for time in [0, 8, 16] {
+        let frame = AxisFrame::new(time)
+            .source(AxisSource::Finger)
+            .value(Axis::Vertical, 20.0);
+        compositor.pointer_axis(element, frame);
+    }
+    let mut lifted = lifted();
+    lifted.time = 16;
+    compositor.pointer_axis(element, lifted);
+}
+
+#[test]
+fn lifted_fingers_coast() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Scrollable(None), (100, 40), (0, 0));
+
+    swipe(&mut compositor, &element);
+    assert!(kinetic_timer(&element));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while kinetic_timer(&element) {
+        assert!(Instant::now() < deadline, "momentum didn't stop");
+        compositor.dispatch(KINETIC_INTERVAL);
+    }
+}
+
+#[test]
+fn programs_may_opt_out_of_momentum() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Scrollable(Some(ScrollPhysics::Pixels)), (100, 40), (0, 0));
+
+    swipe(&mut compositor, &element);
+    assert!(!kinetic_timer(&element));
+}
@@ -0,0 +1,84 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::utils::Rectangle;
+
+use crate::utils::iced::{
+    test_helpers::IcedElementTestHarness, Badge, BadgeKind, Program, UpdateContext,
+};
+
+/// Sets the badges it receives.
+struct Launcher;
+
+impl Program for Launcher {
+    type Message = Vec<Badge>;
+
+    fn update_with_context(
+        &mut self,
+        badges: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        ctx.set_badges(badges);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("").into()
+    }
+}
+
+fn badge(kind: BadgeKind) -> Badge {
+    Badge {
+        anchor: Rectangle::from_loc_and_size((10, 20), (40, 30)),
+        kind,
+    }
+}
+
+#[test]
+fn badges_are_centered_on_the_top_right_corner() {
+    assert_eq!(
+        badge(BadgeKind::Count(3)).bounds(),
+        Rectangle::from_loc_and_size((41, 11), (18, 18))
+    );
+    assert_eq!(
+        badge(BadgeKind::Dot).bounds(),
+        Rectangle::from_loc_and_size((45, 15), (10, 10))
+    );
+}
+
+#[test]
+fn urgent_badges_cover_the_anchor() {
+    let urgent = badge(BadgeKind::Urgent);
+    assert_eq!(urgent.bounds(), urgent.anchor);
+}
+
+#[test]
+fn badges_are_drawn_above_the_content() {
+    let harness = IcedElementTestHarness::new(Launcher, (100, 60));
+    let before = harness.snapshot(1.0);
+
+    harness.element().set_badges(vec![badge(BadgeKind::Dot)]);
+    let after = harness.snapshot(1.0);
+    assert_ne!(after.pixel(50, 20), before.pixel(50, 20));
+    assert_eq!(
+        after.pixel(10, 55),
+        before.pixel(10, 55),
+        "only the badge is drawn"
+    );
+}
+
+#[test]
+fn programs_set_badges_from_updates() {
+    let harness = IcedElementTestHarness::new(Launcher, (100, 60));
+    let plain = harness.snapshot(1.0).pixel(50, 20);
+
+    harness
+        .element()
+        .queue_message(vec![badge(BadgeKind::Count(120))]);
+    assert_eq!(
+        harness.element().0.lock().unwrap().badges,
+        vec![badge(BadgeKind::Count(120))]
+    );
+    assert_ne!(harness.snapshot(1.0).pixel(50, 20), plain);
+
+    harness.element().queue_message(Vec::new());
+    assert_eq!(harness.snapshot(1.0).pixel(50, 20), plain);
+}
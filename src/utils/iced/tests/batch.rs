@@ -0,0 +1,64 @@
+use crate::utils::iced::registry::{Batch, DefaultChanges};
+
+#[test]
+fn changes_outside_of_batches_are_notified_right_away() {
+    let mut batch = Batch::default();
+    assert_eq!(
+        batch.record(DefaultChanges::THEME),
+        Some(DefaultChanges::THEME)
+    );
+}
+
+#[test]
+fn batched_changes_are_notified_once_at_the_end() {
+    let mut batch = Batch::default();
+    batch.begin();
+    assert_eq!(batch.record(DefaultChanges::THEME), None);
+    assert_eq!(batch.record(DefaultChanges::FONTS), None);
+    assert_eq!(batch.record(DefaultChanges::THEME), None);
+    assert_eq!(
+        batch.end(),
+        Some(DefaultChanges::THEME | DefaultChanges::FONTS)
+    );
+    assert_eq!(batch.end(), None, "changes are only notified once");
+}
+
+#[test]
+fn nested_batches_are_committed_with_the_outermost_one() {
+    let mut batch = Batch::default();
+    batch.begin();
+    batch.begin();
+    assert_eq!(batch.record(DefaultChanges::FONTS), None);
+    assert_eq!(batch.end(), None);
+    assert_eq!(batch.record(DefaultChanges::THEME), None);
+    assert_eq!(
+        batch.end(),
+        Some(DefaultChanges::FONTS | DefaultChanges::THEME)
+    );
+}
+
+#[test]
+fn batches_without_changes_notify_nothing() {
+    let mut batch = Batch::default();
+    batch.begin();
+    assert_eq!(batch.end(), None);
+}
+
+#[test]
+fn all_changed_preferences_are_notified_together() {
+    let mut batch = Batch::default();
+    batch.begin();
+    for changes in [
+        DefaultChanges::THEME,
+        DefaultChanges::TEXT_SCALE,
+        DefaultChanges::ANIMATIONS,
+        DefaultChanges::FONTS,
+        DefaultChanges::CONTRAST,
+        DefaultChanges::FORMAT,
+    ] {
+        assert_eq!(batch.record(changes), None);
+    }
+    let changes = batch.end().unwrap();
+    assert!(changes.contains(DefaultChanges::PREFERENCES));
+    assert!(changes.contains(DefaultChanges::THEME | DefaultChanges::FONTS));
+}
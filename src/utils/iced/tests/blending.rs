@@ -0,0 +1,213 @@
+use std::time::Instant;
+
+use cosmic::{
+    iced::widget::text,
+    iced_native::{
+        layout, renderer, widget::Tree, Color, Layout, Length, Point, Rectangle as IcedRectangle,
+        Renderer, Widget,
+    },
+    Element,
+};
+use iced_softbuffer::native::raqote::{DrawOptions, DrawTarget, SolidSource, Source};
+
+use super::{assert_goldens, single_golden};
+use crate::utils::iced::{
+    blending::composite_linear, golden::GoldenPrograms, test_helpers::IcedElementTestHarness,
+    Program,
+};
+
+const OPAQUE_BLACK: u32 = 0xff00_0000;
+const HALF_WHITE: u32 = 0x8080_8080;
+
+/// sRGB encoding of the linear intensity `linear`, scaled to 8 bit.
+fn srgb(linear: f64) -> f64 {
+    let encoded = if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    encoded * 255.0
+}
+
+/// Asserts that the color channels of `pixel` are within 1 LSB of `expected`.
+fn assert_channels(pixel: u32, expected: f64) {
+    let [blue, green, red, alpha] = pixel.to_le_bytes();
+    assert_eq!(alpha, 255);
+    for channel in [red, green, blue] {
+        assert!(
+            (channel as f64 - expected).abs() <= 1.0,
+            "{} instead of {}",
+            channel,
+            expected
+        );
+    }
+}
+
+#[test]
+fn opaque_content_replaces_the_background() {
+    let mut dst = [OPAQUE_BLACK];
+    composite_linear(&mut dst, &[0xffff_ffff]);
+    assert_eq!(dst, [0xffff_ffff]);
+}
+
+#[test]
+fn transparent_content_keeps_the_background() {
+    let mut dst = [OPAQUE_BLACK];
+    composite_linear(&mut dst, &[0]);
+    assert_eq!(dst, [OPAQUE_BLACK]);
+}
+
+#[test]
+fn translucent_content_is_blended_in_linear_light() {
+    let mut dst = [OPAQUE_BLACK];
+    composite_linear(&mut dst, &[HALF_WHITE]);
+    // half the light of white is a lot brighter than the sRGB midpoint of 128
+    assert_channels(dst[0], srgb(128.0 / 255.0));
+}
+
+/// Covers the element with 50% white.
+struct Veil;
+
+impl Widget<(), cosmic::Renderer> for Veil {
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(&self, _renderer: &cosmic::Renderer, limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(limits.max())
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut cosmic::Renderer,
+        _theme: &<cosmic::Renderer as Renderer>::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &IcedRectangle,
+    ) {
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds: layout.bounds(),
+                border_radius: 0.0.into(),
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            },
+            Color::from_rgba(1.0, 1.0, 1.0, 0.5),
+        );
+    }
+}
+
+/// Fills `target` with the opaque color `(r, g, b)`.
+fn fill(target: &mut DrawTarget<&mut [u32]>, (r, g, b): (u8, u8, u8)) {
+    let (w, h) = (target.width() as f32, target.height() as f32);
+    target.fill_rect(
+        0.0,
+        0.0,
+        w,
+        h,
+        &Source::Solid(SolidSource::from_unpremultiplied_argb(255, r, g, b)),
+        &DrawOptions::new(),
+    );
+}
+
+/// 50% white over black.
+struct HalfWhite;
+
+impl Program for HalfWhite {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Element::new(Veil)
+    }
+
+    fn background(&self, target: &mut DrawTarget<&mut [u32]>) {
+        fill(target, (0, 0, 0));
+    }
+}
+
+#[test]
+fn elements_blend_half_white_over_black_in_linear_light() {
+    let harness = IcedElementTestHarness::new(HalfWhite, (20, 20));
+    harness.element().set_linear_blending(true);
+    let snapshot = harness.snapshot(1.0);
+    assert_channels(snapshot.pixel(10, 10).unwrap(), srgb(0.5));
+}
+
+#[test]
+fn disabled_linear_blending_keeps_the_legacy_output() {
+    let legacy = IcedElementTestHarness::new(HalfWhite, (20, 20)).snapshot(1.0);
+    assert_channels(legacy.pixel(10, 10).unwrap(), 127.5);
+
+    let harness = IcedElementTestHarness::new(HalfWhite, (20, 20));
+    harness.element().set_linear_blending(true);
+    let _ = harness.snapshot(1.0);
+    harness.element().set_linear_blending(false);
+    assert_eq!(harness.snapshot(1.0), legacy);
+}
+
+/// White text on a dark blue background, whose antialiased edges depend on the blending.
+struct Caption;
+
+impl Program for Caption {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Linear light").size(32).into()
+    }
+
+    fn background(&self, target: &mut DrawTarget<&mut [u32]>) {
+        fill(target, (16, 32, 96));
+    }
+}
+
+#[test]
+fn cached_text_tiles_render_like_the_first_frame() {
+    let harness = IcedElementTestHarness::new(Caption, (240, 60));
+    harness.element().set_linear_blending(true);
+    let first = harness.snapshot(1.0);
+    assert_eq!(harness.snapshot(1.0), first);
+}
+
+#[test]
+fn linear_blending_golden() {
+    let mut programs = GoldenPrograms::new();
+    programs.register_configured(
+        "linear-blending",
+        || Caption,
+        |element| element.set_linear_blending(true),
+    );
+    programs.register("srgb-blending", || Caption);
+    assert_goldens(&programs, &single_golden(1.0, (240, 60)));
+}
+
+/// Mean time of rasterizing `Caption` at 2x.
+fn mean_frame_time(linear: bool) -> f64 {
+    const FRAMES: u32 = 200;
+    let harness = IcedElementTestHarness::new(Caption, (240, 60));
+    harness.element().set_linear_blending(linear);
+    let _ = harness.snapshot(2.0);
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        let _ = harness.snapshot(2.0);
+    }
+    start.elapsed().as_secs_f64() / FRAMES as f64
+}
+
+#[test]
+#[ignore = "benchmark, run with `cargo test --release -- --ignored --nocapture blending`"]
+fn linear_blending_overhead() {
+    let legacy = mean_frame_time(false);
+    let linear = mean_frame_time(true);
+    println!(
+        "sRGB: {:.1}µs, linear: {:.1}µs per frame ({:+.0}%)",
+        legacy * 1e6,
+        linear * 1e6,
+        (linear / legacy - 1.0) * 100.0
+    );
+}
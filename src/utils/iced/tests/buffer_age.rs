@@ -0,0 +1,113 @@
+use smithay::utils::{Buffer, Logical, Rectangle};
+
+use crate::utils::iced::{
+    buffer::ScaleBuffer,
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement,
+};
+
+fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size((x, y), (w, h))
+}
+
+fn buffer_rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Buffer> {
+    Rectangle::from_loc_and_size((x, y), (w, h))
+}
+
+/// Draws with `damage` into the next buffer, returns the damage redrawn.
+fn draw(buffer: &mut ScaleBuffer, damage: Rectangle<i32, Logical>) -> Vec<Rectangle<i32, Buffer>> {
+    buffer.add_damage(&[damage]);
+    let damage = buffer.take_damage(1.0, (100, 100).into());
+    buffer.swap();
+    damage
+}
+
+fn counts(element: &IcedElement<Label>) -> Vec<u8> {
+    let internal = element.0.lock().unwrap();
+    internal
+        .buffers
+        .values()
+        .map(ScaleBuffer::buffer_count)
+        .collect()
+}
+
+#[test]
+fn single_buffers_only_redraw_their_damage() {
+    let mut buffer = ScaleBuffer::new((100, 100).into());
+    buffer.take_damage(1.0, (100, 100).into());
+
+    assert_eq!(
+        draw(&mut buffer, rect(0, 0, 10, 10)),
+        vec![buffer_rect(0, 0, 10, 10)]
+    );
+}
+
+#[test]
+fn buffers_redraw_the_damage_they_missed() {
+    let mut buffer = ScaleBuffer::new((100, 100).into());
+    buffer.set_buffer_count(3);
+    let full = vec![buffer_rect(0, 0, 100, 100)];
+
+    // the initial draw and the two after it can't be tracked
+    buffer.take_damage(1.0, (100, 100).into());
+    buffer.swap();
+    assert_eq!(draw(&mut buffer, rect(0, 0, 10, 10)), full);
+    assert_eq!(draw(&mut buffer, rect(10, 0, 10, 10)), full);
+    // missed the two draws into the other buffers
+    assert_eq!(
+        draw(&mut buffer, rect(20, 0, 10, 10)),
+        vec![
+            buffer_rect(0, 0, 10, 10),
+            buffer_rect(10, 0, 10, 10),
+            buffer_rect(20, 0, 10, 10),
+        ]
+    );
+    assert_eq!(
+        draw(&mut buffer, rect(30, 0, 10, 10)),
+        vec![
+            buffer_rect(10, 0, 10, 10),
+            buffer_rect(20, 0, 10, 10),
+            buffer_rect(30, 0, 10, 10),
+        ]
+    );
+
+    // a full redraw in between is missed as well
+    buffer.mark_dirty();
+    buffer.take_damage(1.0, (100, 100).into());
+    buffer.swap();
+    assert_eq!(draw(&mut buffer, rect(0, 0, 10, 10)), full);
+}
+
+#[test]
+fn buffer_count_is_clamped() {
+    let mut buffer = ScaleBuffer::new((10, 10).into());
+    buffer.set_buffer_count(0);
+    assert_eq!(buffer.buffer_count(), 1);
+    buffer.set_buffer_count(5);
+    assert_eq!(buffer.buffer_count(), 3);
+}
+
+#[test]
+fn buffer_age_sets_the_buffers_per_scale() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    assert_eq!(counts(&element), vec![1]);
+
+    element.set_buffer_age(3);
+    assert_eq!(counts(&element), vec![3]);
+
+    // invalid ages are ignored
+    element.set_buffer_age(4);
+    element.set_buffer_age(0);
+    assert_eq!(counts(&element), vec![3]);
+}
+
+#[test]
+fn double_buffered_elements_keep_two_buffers() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    element.set_double_buffered(true);
+
+    element.set_buffer_age(1);
+    assert_eq!(counts(&element), vec![2]);
+}
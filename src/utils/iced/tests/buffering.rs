@@ -0,0 +1,108 @@
+use smithay::{
+    desktop::space::SpaceElement,
+    output::{Mode, Output, PhysicalProperties, Subpixel},
+    utils::{Scale, Transform},
+};
+
+use crate::utils::iced::{
+    buffer::ScaleBuffer,
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement,
+};
+
+fn output(name: &str, scale: f64) -> Output {
+    let output = Output::new(
+        name.to_string(),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: String::from("COSMIC"),
+            model: String::from("Test"),
+        },
+    );
+    let mode = Mode {
+        size: (1920, 1080).into(),
+        refresh: 60_000,
+    };
+    output.add_mode(mode);
+    output.change_current_state(
+        Some(mode),
+        Some(Transform::Normal),
+        Some(Scale::Fractional(scale)),
+        None,
+    );
+    output
+}
+
+/// Buffers per scale, ordered by scale.
+fn buffer_counts(element: &IcedElement<Label>) -> Vec<(f64, u8)> {
+    let internal = element.0.lock().unwrap();
+    let mut counts = internal
+        .buffers
+        .iter()
+        .map(|(scale, buffer)| (scale.0, buffer.buffer_count()))
+        .collect::<Vec<_>>();
+    counts.sort_by(|a, b| a.0.total_cmp(&b.0));
+    counts
+}
+
+#[test]
+fn buffer_of_a_single_output_is_single_buffered() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+    assert_eq!(buffer_counts(&element), vec![(1.0, 1)]);
+}
+
+#[test]
+fn outputs_sharing_a_scale_get_a_back_buffer() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+    let second = output("TEST-2", 1.0);
+
+    element.output_enter(&second, element.bbox());
+    assert_eq!(buffer_counts(&element), vec![(1.0, 2)]);
+
+    element.output_leave(&second);
+    assert_eq!(buffer_counts(&element), vec![(1.0, 1)]);
+}
+
+#[test]
+fn outputs_of_other_scales_keep_single_buffers() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+
+    element.output_enter(&output("TEST-2", 2.0), element.bbox());
+    assert_eq!(buffer_counts(&element), vec![(1.0, 1), (2.0, 1)]);
+}
+
+#[test]
+fn double_buffering_can_be_forced() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+
+    element.set_double_buffered(true);
+    assert_eq!(buffer_counts(&element), vec![(1.0, 2)]);
+    element.set_double_buffered(false);
+    assert_eq!(buffer_counts(&element), vec![(1.0, 1)]);
+}
+
+#[test]
+fn buffer_count_is_clamped() {
+    let mut buffer = ScaleBuffer::new((10, 10).into());
+    buffer.set_buffer_count(0);
+    assert_eq!(buffer.buffer_count(), 1);
+    assert!(!buffer.is_double_buffered());
+    buffer.set_buffer_count(10);
+    assert_eq!(buffer.buffer_count(), 3);
+    assert!(buffer.is_double_buffered());
+}
+
+#[test]
+fn resizing_keeps_the_back_buffers() {
+    let mut buffer = ScaleBuffer::new((10, 10).into());
+    buffer.set_buffer_count(2);
+    buffer.resize((20, 20).into());
+    assert_eq!(buffer.size(), (20, 20).into());
+    assert_eq!(buffer.buffer_count(), 2);
+    assert!(buffer.needs_redraw());
+}
@@ -0,0 +1,57 @@
+use cosmic::Theme;
+use smithay::{desktop::space::SpaceElement, utils::Size};
+
+use crate::utils::iced::{
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement, IcedElementError,
+};
+
+#[test]
+fn loop_handle_and_size_are_required() {
+    let compositor = HeadlessCompositor::<Label>::new((400, 200), 1.0);
+
+    let err = IcedElement::builder(Label)
+        .size(100, 40)
+        .build()
+        .unwrap_err();
+    assert_eq!(err, IcedElementError::MissingLoopHandle);
+    let err = IcedElement::builder(Label)
+        .loop_handle(compositor.handle())
+        .build()
+        .unwrap_err();
+    assert_eq!(err, IcedElementError::MissingSize);
+}
+
+#[test]
+fn negative_sizes_are_rejected() {
+    let compositor = HeadlessCompositor::<Label>::new((400, 200), 1.0);
+
+    let err = IcedElement::builder(Label)
+        .size(-1, 40)
+        .loop_handle(compositor.handle())
+        .build()
+        .unwrap_err();
+    assert_eq!(err, IcedElementError::InvalidSize(Size::from((-1, 40))));
+}
+
+#[test]
+fn optional_settings_are_applied() {
+    let compositor = HeadlessCompositor::<Label>::new((400, 200), 1.0);
+
+    let built = IcedElement::builder(Label)
+        .size(100, 40)
+        .loop_handle(compositor.handle())
+        .theme(Theme::light())
+        .z_index(42)
+        .build()
+        .unwrap();
+    assert_eq!(built.z_index(), 42);
+
+    let themed = IcedElement::new(Label, (100, 40), compositor.handle());
+    themed.set_theme(Theme::light());
+    let default = IcedElement::new(Label, (100, 40), compositor.handle());
+    let snapshot = compositor.snapshot(&built);
+    assert_eq!(snapshot.size, (100, 40).into());
+    assert_eq!(snapshot.pixels, compositor.snapshot(&themed).pixels);
+    assert_ne!(snapshot.pixels, compositor.snapshot(&default).pixels);
+}
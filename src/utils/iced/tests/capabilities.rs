@@ -0,0 +1,89 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        capabilities::detect,
+        test_helpers::{HeadlessCompositor, Label},
+        Capabilities, Overlaid, Program, ProgramCapabilities,
+    },
+};
+
+/// Declares custom rendering, and accepts drops once told so.
+struct Target {
+    drops: bool,
+}
+
+impl Program for Target {
+    type Message = bool;
+
+    fn update(&mut self, drops: bool, _: &LoopHandle<'static, Data>) -> Command<bool> {
+        self.drops = drops;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Target").into()
+    }
+
+    fn accepts_drops(&self) -> bool {
+        self.drops
+    }
+
+    fn capabilities(&self) -> ProgramCapabilities {
+        ProgramCapabilities::CUSTOM_RENDER
+    }
+}
+
+#[test]
+fn hooks_are_detected_besides_declared_capabilities() {
+    assert_eq!(detect(&Label), ProgramCapabilities::empty());
+    assert_eq!(
+        detect(&Target { drops: false }),
+        ProgramCapabilities::CUSTOM_RENDER
+    );
+    assert_eq!(
+        detect(&Target { drops: true }),
+        ProgramCapabilities::CUSTOM_RENDER | ProgramCapabilities::DROP_TARGET
+    );
+}
+
+#[test]
+fn combinators_combine_capabilities() {
+    let overlaid = Overlaid::new(Label, Target { drops: true }, false);
+    assert_eq!(overlaid.capabilities(), ProgramCapabilities::CUSTOM_RENDER);
+    assert!(detect(&overlaid).contains(ProgramCapabilities::DROP_TARGET));
+}
+
+#[test]
+fn element_capabilities_follow_the_program_and_configuration() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Target { drops: false }, (100, 40), (0, 0));
+    compositor.settle();
+    let capabilities = element.capabilities();
+    assert!(capabilities.contains(Capabilities::CUSTOM_RENDER | Capabilities::OUTPUT_OFFSETS));
+    assert!(!capabilities.contains(Capabilities::DROP_TARGET));
+    assert!(!capabilities.contains(Capabilities::SHELL_REQUESTS));
+
+    element.queue_message(true);
+    compositor.settle();
+    element.set_shell_request_handler(|_, _| {});
+    let capabilities = element.capabilities();
+    assert!(capabilities.contains(Capabilities::DROP_TARGET | Capabilities::SHELL_REQUESTS));
+}
+
+#[test]
+fn missing_capabilities_fail_requirements() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    assert!(element.require(Capabilities::OUTPUT_OFFSETS, "test"));
+    assert!(!element.require(Capabilities::VIRTUAL_TARGETS, "test"));
+    // warned once, still failing
+    assert!(!element.require(Capabilities::VIRTUAL_TARGETS, "test"));
+    assert!(!element.require(
+        Capabilities::OUTPUT_OFFSETS | Capabilities::RENDER_POOL,
+        "test"
+    ));
+    assert_eq!(element.0.lock().unwrap().capability_warnings.len(), 2);
+}
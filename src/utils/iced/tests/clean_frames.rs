@@ -0,0 +1,94 @@
+use std::{sync::mpsc, thread, time::Duration};
+
+use cosmic::Theme;
+use smithay::{
+    backend::{
+        allocator::Fourcc,
+        renderer::{
+            element::memory::{MemoryRenderBuffer, MemoryRenderBufferRenderElement},
+            test::DummyRenderer,
+        },
+    },
+    utils::{Rectangle, Scale, Transform},
+};
+
+use crate::utils::iced::{
+    clean::{CachedFrame, CleanFrame},
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement,
+};
+
+fn frame(scale: f64) -> CachedFrame {
+    CachedFrame {
+        scale,
+        buffer: MemoryRenderBuffer::new(Fourcc::Argb8888, (10, 10), 1, Transform::Normal, None),
+        src: Rectangle::from_loc_and_size((0.0, 0.0), (10.0, 10.0)),
+        size: (10, 10).into(),
+    }
+}
+
+fn render(element: &IcedElement<Label>) -> usize {
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> = element.render_elements(
+        &mut DummyRenderer::new(),
+        (0, 0).into(),
+        Scale::from(1.0),
+        1.0,
+    );
+    elements.len()
+}
+
+fn is_cached(element: &IcedElement<Label>) -> bool {
+    element.1.get(1.0).is_some()
+}
+
+#[test]
+fn frames_are_reused_until_marked_dirty() {
+    let clean = CleanFrame::default();
+    assert!(clean.get(1.0).is_none(), "new elements are dirty");
+
+    clean.store(frame(1.0));
+    clean.store(frame(2.0));
+    assert_eq!(clean.get(1.0).map(|frame| frame.scale), Some(1.0));
+    assert_eq!(clean.get(2.0).map(|frame| frame.scale), Some(2.0));
+    assert!(clean.get(1.5).is_none());
+
+    clean.mark_dirty();
+    assert!(clean.get(2.0).is_none());
+    // the frame of 2.0 is outdated
+    clean.store(frame(1.0));
+    assert!(clean.get(1.0).is_some());
+    assert!(clean.get(2.0).is_none());
+}
+
+#[test]
+fn changing_an_element_drops_its_frame() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    assert_eq!(render(&element), 1);
+    assert!(is_cached(&element));
+
+    element.set_theme(Theme::light());
+    assert!(!is_cached(&element));
+    compositor.frame();
+    assert_eq!(render(&element), 1);
+    assert!(is_cached(&element));
+}
+
+#[test]
+fn clean_frames_render_without_locking() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    assert_eq!(render(&element), 1);
+    assert!(is_cached(&element));
+
+    let guard = element.0.lock().unwrap();
+    let (tx, rx) = mpsc::channel();
+    let renderer = {
+        let element = element.clone();
+        thread::spawn(move || tx.send(render(&element)).unwrap())
+    };
+    let rendered = rx.recv_timeout(Duration::from_secs(5));
+    drop(guard);
+    assert_eq!(rendered, Ok(1));
+    renderer.join().unwrap();
+}
@@ -0,0 +1,74 @@
+use cosmic::{iced::widget::text, Element};
+
+use crate::utils::iced::{
+    Conditional, ConditionalMessage, Critical, CriticalMessage, Either, Program, Split,
+    TitleBarMessage, TitleBarProgram, WindowAction,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+struct Cloneable(u32);
+
+struct WithClone;
+
+impl Program for WithClone {
+    type Message = Cloneable;
+    crate::cloneable_message!();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Clone").into()
+    }
+}
+
+#[derive(Debug)]
+struct Unique;
+
+struct WithoutClone;
+
+impl Program for WithoutClone {
+    type Message = Unique;
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Unique").into()
+    }
+}
+
+#[test]
+fn messages_are_only_cloned_when_opted_in() {
+    assert_eq!(
+        WithClone::try_clone_message(&Cloneable(3)),
+        Some(Cloneable(3))
+    );
+    assert!(WithoutClone::try_clone_message(&Unique).is_none());
+    assert!(matches!(
+        TitleBarProgram::try_clone_message(&TitleBarMessage::Action(WindowAction::Close)),
+        Some(TitleBarMessage::Action(WindowAction::Close))
+    ));
+}
+
+#[test]
+fn combinators_clone_per_child() {
+    type Both = Split<WithClone, WithoutClone>;
+    assert!(matches!(
+        Both::try_clone_message(&Either::First(Cloneable(1))),
+        Some(Either::First(Cloneable(1)))
+    ));
+    assert!(Both::try_clone_message(&Either::Second(Unique)).is_none());
+
+    type Shown = Conditional<WithClone, bool>;
+    assert!(matches!(
+        Shown::try_clone_message(&ConditionalMessage::Inner(Cloneable(2))),
+        Some(ConditionalMessage::Inner(Cloneable(2)))
+    ));
+    // the state isn't required to be `Clone`
+    assert!(Shown::try_clone_message(&ConditionalMessage::SetState(true)).is_none());
+}
+
+#[test]
+fn fallback_actions_of_critical_programs_are_cloneable() {
+    type Guarded = Critical<WithoutClone>;
+    assert!(matches!(
+        Guarded::try_clone_message(&CriticalMessage::Fallback(1)),
+        Some(CriticalMessage::Fallback(1))
+    ));
+    assert!(Guarded::try_clone_message(&CriticalMessage::Inner(Unique)).is_none());
+}
@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+use cosmic::{
+    iced::{
+        widget::{button, text},
+        Length,
+    },
+    iced_native::Command,
+    Element,
+};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        test_helpers::IcedElementTestHarness, Conditional, ConditionalMessage, Either, FontConfig,
+        Overlaid, Preferences, Program, RefreshInfo, Split, SplitDirection,
+    },
+};
+
+#[derive(Debug, Clone)]
+struct Pressed;
+
+/// A button filling the element, counting its presses.
+#[derive(Default)]
+struct Counter {
+    presses: usize,
+}
+
+impl Program for Counter {
+    type Message = Pressed;
+
+    fn update(
+        &mut self,
+        _message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        self.presses += 1;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        button(text("Press"))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .on_press(Pressed)
+            .into()
+    }
+}
+
+/// A short label in the top left corner, which doesn't capture input.
+struct Label;
+
+impl Program for Label {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Hi").into()
+    }
+}
+
+/// Reacts to every change of the environment.
+struct Listener;
+
+impl Program for Listener {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Listening").into()
+    }
+
+    fn refresh_changed(&mut self, _info: &RefreshInfo) -> Option<Self::Message> {
+        Some(())
+    }
+
+    fn fonts_changed(&self, _fonts: &FontConfig) -> Option<Self::Message> {
+        Some(())
+    }
+
+    fn preferences_changed(&self, _preferences: &Preferences) -> Option<Self::Message> {
+        Some(())
+    }
+}
+
+fn split_presses(harness: &IcedElementTestHarness<Split<Counter, Counter>>) -> (usize, usize) {
+    harness
+        .element()
+        .with_program(|p| (p.first.presses, p.second.presses))
+}
+
+#[test]
+fn horizontal_split_routes_clicks_to_each_half() {
+    let split = Split::new(
+        Counter::default(),
+        Counter::default(),
+        SplitDirection::Horizontal,
+        0.5,
+    );
+    let harness = IcedElementTestHarness::new(split, (200, 40));
+
+    harness.click_at((50.0, 20.0));
+    assert_eq!(split_presses(&harness), (1, 0));
+    harness.click_at((150.0, 20.0));
+    assert_eq!(split_presses(&harness), (1, 1));
+}
+
+#[test]
+fn vertical_split_follows_the_ratio() {
+    let split = Split::new(
+        Counter::default(),
+        Counter::default(),
+        SplitDirection::Vertical,
+        0.25,
+    );
+    let harness = IcedElementTestHarness::new(split, (100, 80));
+
+    harness.click_at((50.0, 10.0));
+    assert_eq!(split_presses(&harness), (1, 0));
+    // below a quarter of the height
+    harness.click_at((50.0, 30.0));
+    assert_eq!(split_presses(&harness), (1, 1));
+}
+
+#[test]
+fn split_clamps_the_ratio() {
+    let split = Split::new(
+        Counter::default(),
+        Counter::default(),
+        SplitDirection::Horizontal,
+        3.0,
+    );
+    let harness = IcedElementTestHarness::new(split, (100, 40));
+
+    harness.click_at((95.0, 20.0));
+    assert_eq!(split_presses(&harness), (1, 0));
+}
+
+#[test]
+fn split_delivers_messages_by_side() {
+    let split = Split::new(
+        Counter::default(),
+        Counter::default(),
+        SplitDirection::Horizontal,
+        0.5,
+    );
+    let harness = IcedElementTestHarness::new(split, (200, 40));
+
+    harness.element().queue_message(Either::Second(Pressed));
+    assert_eq!(split_presses(&harness), (0, 1));
+}
+
+#[test]
+fn overlay_captures_input_before_base() {
+    let overlaid = Overlaid::new(Counter::default(), Counter::default(), true);
+    let harness = IcedElementTestHarness::new(overlaid, (100, 40));
+
+    harness.click_at((50.0, 20.0));
+    let presses = harness
+        .element()
+        .with_program(|p| (p.base.presses, p.top.presses));
+    assert_eq!(presses, (0, 1));
+}
+
+#[test]
+fn uncaptured_input_outside_of_top_reaches_base() {
+    let overlaid = Overlaid::new(Counter::default(), Label, false);
+    let harness = IcedElementTestHarness::new(overlaid, (100, 40));
+
+    harness.click_at((90.0, 35.0));
+    assert_eq!(harness.element().with_program(|p| p.base.presses), 1);
+}
+
+#[test]
+fn uncaptured_input_inside_of_top_only_passes_through_if_enabled() {
+    let blocking =
+        IcedElementTestHarness::new(Overlaid::new(Counter::default(), Label, false), (100, 40));
+    blocking.click_at((2.0, 2.0));
+    assert_eq!(blocking.element().with_program(|p| p.base.presses), 0);
+
+    let passing =
+        IcedElementTestHarness::new(Overlaid::new(Counter::default(), Label, true), (100, 40));
+    passing.click_at((2.0, 2.0));
+    assert_eq!(passing.element().with_program(|p| p.base.presses), 1);
+}
+
+fn shown(visible: &bool) -> bool {
+    *visible
+}
+
+#[test]
+fn conditional_program_only_gets_input_while_shown() {
+    let conditional = Conditional::new(Counter::default(), false, shown);
+    let harness = IcedElementTestHarness::new(conditional, (100, 40));
+
+    harness.click_at((50.0, 20.0));
+    assert_eq!(harness.element().with_program(|p| p.program.presses), 0);
+
+    harness
+        .element()
+        .queue_message(ConditionalMessage::SetState(true));
+    assert!(harness.element().with_program(|p| p.is_shown()));
+    harness.click_at((50.0, 20.0));
+    assert_eq!(harness.element().with_program(|p| p.program.presses), 1);
+}
+
+#[test]
+fn hidden_conditional_program_still_gets_messages() {
+    let conditional = Conditional::new(Counter::default(), false, shown);
+    let harness = IcedElementTestHarness::new(conditional, (100, 40));
+
+    harness
+        .element()
+        .queue_message(ConditionalMessage::Inner(Pressed));
+    assert_eq!(harness.element().with_program(|p| p.program.presses), 1);
+    assert!(!harness.element().with_program(|p| *p.state()));
+}
+
+#[test]
+fn changes_are_forwarded_to_both_programs() {
+    let info = RefreshInfo {
+        interval: Duration::from_millis(16),
+        vrr: false,
+    };
+    let mut split = Split::new(Listener, Listener, SplitDirection::Horizontal, 0.5);
+    assert!(matches!(
+        split.refresh_changed(&info),
+        Some(Either::Both((), ()))
+    ));
+    assert!(matches!(
+        split.fonts_changed(&FontConfig::default()),
+        Some(Either::Both((), ()))
+    ));
+    assert!(matches!(
+        split.preferences_changed(&Preferences::default()),
+        Some(Either::Both((), ()))
+    ));
+
+    let mut overlaid = Overlaid::new(Label, Listener, false);
+    assert!(matches!(
+        overlaid.refresh_changed(&info),
+        Some(Either::Second(()))
+    ));
+    assert!(matches!(
+        overlaid.preferences_changed(&Preferences::default()),
+        Some(Either::Second(()))
+    ));
+}
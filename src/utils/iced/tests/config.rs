@@ -0,0 +1,161 @@
+use std::{fs, time::Duration};
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        reload_all_configs,
+        test_helpers::{HeadlessCompositor, IcedElementTestHarness},
+        Program,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Message {
+    Changed(String, bool),
+}
+
+/// Records the configuration keys it was passed.
+#[derive(Default)]
+struct Applet {
+    config_id: Option<(&'static str, u64)>,
+    changes: Vec<Message>,
+}
+
+impl Program for Applet {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _: &LoopHandle<'static, Data>,
+    ) -> Command<Message> {
+        self.changes.push(message);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(self.changes.len().to_string()).into()
+    }
+
+    fn config_id(&self) -> Option<(&'static str, u64)> {
+        self.config_id
+    }
+
+    fn config_keys(&self) -> &'static [&'static str] {
+        &["show-seconds", "format"]
+    }
+
+    fn on_config_changed(&self, key: &str, value: Option<ron::Value>) -> Option<Self::Message> {
+        Some(Message::Changed(key.to_string(), value.is_some()))
+    }
+}
+
+#[test]
+fn reload_passes_every_key_to_the_program() {
+    let harness = IcedElementTestHarness::new(
+        Applet {
+            // never written, so every key is reported as unset
+            config_id: Some(("com.system76.CosmicCompTestMissingConfig", 1)),
+            ..Applet::default()
+        },
+        (100, 20),
+    );
+    harness.element().reload_config();
+    // other tests may reload all elements concurrently
+    assert_eq!(
+        harness.element().with_program(|p| p.changes[..2].to_vec()),
+        vec![
+            Message::Changed(String::from("show-seconds"), false),
+            Message::Changed(String::from("format"), false),
+        ]
+    );
+}
+
+#[test]
+fn reload_reaches_all_live_elements() {
+    let harness = IcedElementTestHarness::new(
+        Applet {
+            config_id: Some(("com.system76.CosmicCompTestMissingConfig", 1)),
+            ..Applet::default()
+        },
+        (100, 20),
+    );
+    reload_all_configs();
+    assert!(harness.element().with_program(|p| p.changes.len()) >= 2);
+}
+
+#[test]
+fn programs_without_config_arent_reloaded() {
+    let harness = IcedElementTestHarness::new(Applet::default(), (100, 20));
+    harness.element().reload_config();
+    assert!(harness.element().with_program(|p| p.changes.is_empty()));
+}
+
+/// A config id no other test (or run) uses.
+fn unique_id(name: &str) -> &'static str {
+    Box::leak(
+        format!("com.system76.CosmicCompTest{}-{}", name, std::process::id()).into_boxed_str(),
+    )
+}
+
+#[test]
+fn reload_falls_back_to_system_defaults() {
+    let id = unique_id("SystemConfig");
+    let system = std::env::temp_dir().join(format!("cosmic-comp-config-{}", std::process::id()));
+    let dir = system.join(format!("cosmic/{}/v1", id));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("format"), "\"%H:%M\"").unwrap();
+    let data_dirs = std::env::var("XDG_DATA_DIRS").unwrap_or_default();
+    std::env::set_var(
+        "XDG_DATA_DIRS",
+        format!("{}:{}", system.display(), data_dirs),
+    );
+
+    let harness = IcedElementTestHarness::new(
+        Applet {
+            config_id: Some((id, 1)),
+            ..Applet::default()
+        },
+        (100, 20),
+    );
+    harness.element().reload_config();
+    let _ = fs::remove_dir_all(&system);
+    assert_eq!(
+        harness.element().with_program(|p| p.changes[..2].to_vec()),
+        vec![
+            Message::Changed(String::from("show-seconds"), false),
+            Message::Changed(String::from("format"), true),
+        ]
+    );
+}
+
+#[test]
+fn user_changes_are_delivered() {
+    let id = unique_id("WatchedConfig");
+    let mut compositor = HeadlessCompositor::new((200, 100), 1.0);
+    let element = compositor.insert(
+        Applet {
+            config_id: Some((id, 1)),
+            ..Applet::default()
+        },
+        (100, 20),
+        (0, 0),
+    );
+
+    let xdg = xdg::BaseDirectories::with_prefix("cosmic").unwrap();
+    let path = xdg
+        .place_config_file(format!("{}/v1/show-seconds", id))
+        .unwrap();
+    fs::write(&path, "true").unwrap();
+    compositor.dispatch(Duration::from_millis(100));
+    let _ = fs::remove_dir_all(path.parent().unwrap().parent().unwrap());
+
+    // only the written key is passed on
+    assert_eq!(
+        element.with_program(|p| p.changes.clone()),
+        vec![Message::Changed(String::from("show-seconds"), true)]
+    );
+}
@@ -0,0 +1,68 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        confine_pointer,
+        test_helpers::{HeadlessCompositor, IcedElementTestHarness},
+        Program,
+    },
+};
+
+#[derive(Debug, Clone)]
+enum Message {
+    DragEnded,
+}
+
+/// List that is reordered by dragging.
+struct List {
+    dragging: bool,
+}
+
+impl Program for List {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _: &LoopHandle<'static, Data>,
+    ) -> Command<Message> {
+        let Message::DragEnded = message;
+        self.dragging = false;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("List").into()
+    }
+
+    fn is_drag_active(&self) -> bool {
+        self.dragging
+    }
+}
+
+#[test]
+fn pointer_stays_within_located_element_until_the_drag_ended() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(List { dragging: true }, (100, 50), (200, 20));
+    assert!(element.confine_pointer_during_drag(compositor.seat()));
+
+    let confined = confine_pointer(compositor.seat(), (10.0, 150.0).into());
+    assert_eq!(confined, (200.0, 69.0).into());
+
+    element.queue_message(Message::DragEnded);
+    compositor.settle();
+    let released = confine_pointer(compositor.seat(), (10.0, 150.0).into());
+    assert_eq!(released, (10.0, 150.0).into());
+}
+
+#[test]
+fn unlocated_element_doesnt_confine() {
+    let compositor = HeadlessCompositor::<List>::new((400, 200), 1.0);
+    let harness = IcedElementTestHarness::new(List { dragging: true }, (100, 50));
+    assert!(!harness
+        .element()
+        .confine_pointer_during_drag(compositor.seat()));
+}
@@ -0,0 +1,27 @@
+use crate::utils::iced::{
+    buffer,
+    test_helpers::{HeadlessCompositor, Label},
+};
+
+#[test]
+fn inconsistent_state_is_repaired_without_panicking() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+    compositor.settle();
+
+    {
+        let mut internal = element.0.lock().unwrap();
+        internal.size = (120, 60).into();
+        internal.cursor_pos = Some((f64::NAN, 10.0).into());
+    }
+    element.set_double_buffered(false);
+
+    let internal = element.0.lock().unwrap();
+    assert_eq!(internal.cursor_pos, None);
+    for (scale, buffer) in internal.buffers.iter() {
+        assert_eq!(
+            buffer.size(),
+            buffer::buffer_size((120, 60).into(), **scale)
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        content_state::placeholder, test_helpers::HeadlessCompositor, ContentState, IcedElement,
+        Program,
+    },
+};
+
+/// Shows the content state it was last told to.
+struct Results(ContentState);
+
+impl Program for Results {
+    type Message = ContentState;
+
+    fn update(
+        &mut self,
+        state: ContentState,
+        _: &LoopHandle<'static, Data>,
+    ) -> Command<ContentState> {
+        self.0 = state;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Results").into()
+    }
+
+    fn content_state(&self) -> ContentState {
+        self.0.clone()
+    }
+}
+
+fn empty(title: &str) -> ContentState {
+    ContentState::Empty {
+        icon: None,
+        title: title.to_owned(),
+        hint: None,
+    }
+}
+
+fn loading() -> ContentState {
+    ContentState::Loading { message: None }
+}
+
+fn is_animating(element: &IcedElement<Results>) -> bool {
+    element.0.lock().unwrap().content_timer.is_some()
+}
+
+fn is_crossfading(element: &IcedElement<Results>) -> bool {
+    element.0.lock().unwrap().swap.is_some()
+}
+
+#[test]
+fn kinds_of_states() {
+    assert!(empty("a").is_same_kind(&empty("b")));
+    assert!(!empty("a").is_same_kind(&loading()));
+    assert!(!ContentState::Ready.is_same_kind(&loading()));
+    assert!(placeholder::<()>(&ContentState::Ready).is_none());
+    assert!(placeholder::<()>(&empty("Nothing here")).is_some());
+    assert!(placeholder::<()>(&loading()).is_some());
+}
+
+#[test]
+fn placeholders_replace_the_view() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let ready = compositor.insert(Results(ContentState::Ready), (200, 100), (0, 0));
+    let empty = compositor.insert(Results(empty("No results")), (200, 100), (0, 100));
+    assert_ne!(
+        compositor.snapshot(&ready).pixels,
+        compositor.snapshot(&empty).pixels
+    );
+}
+
+#[test]
+fn loading_states_are_animated() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Results(ContentState::Ready), (200, 100), (0, 0));
+    assert!(!is_animating(&element));
+
+    element.queue_message(loading());
+    compositor.settle();
+    assert!(is_animating(&element));
+
+    element.queue_message(empty("No results"));
+    compositor.settle();
+    assert!(!is_animating(&element));
+}
+
+#[test]
+fn changing_the_kind_of_state_crossfades() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Results(empty("No results")), (200, 100), (0, 0));
+    compositor.settle();
+    assert!(!is_crossfading(&element));
+
+    element.queue_message(empty("Still no results"));
+    compositor.settle();
+    assert!(!is_crossfading(&element), "same kind of state");
+
+    element.queue_message(ContentState::Ready);
+    compositor.settle();
+    assert!(is_crossfading(&element));
+}
@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::{EventLoop, LoopHandle};
+
+use crate::{
+    state::Data,
+    utils::iced::{Critical, CriticalMessage, FallbackSpec, IcedElement, Program, ShellRequest},
+};
+
+#[derive(Debug, Clone)]
+enum Message {
+    Increment,
+    Crash,
+}
+
+#[derive(Default)]
+struct Panel {
+    count: usize,
+    crash_view: bool,
+}
+
+impl Program for Panel {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        match message {
+            Message::Increment => self.count += 1,
+            Message::Crash => panic!("panel crashed"),
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        if self.crash_view {
+            panic!("panel view crashed");
+        }
+        text(self.count.to_string()).into()
+    }
+}
+
+fn fallback() -> FallbackSpec {
+    FallbackSpec {
+        title: String::from("The panel stopped working"),
+        actions: vec![
+            (
+                String::from("Restart"),
+                ShellRequest::Spawn(String::from("cosmic-panel")),
+            ),
+            (String::from("Hide"), ShellRequest::CloseElement),
+        ],
+    }
+}
+
+fn critical(
+    event_loop: &EventLoop<'static, Data>,
+    builder: impl FnOnce() -> anyhow::Result<Panel>,
+) -> IcedElement<Critical<Panel>> {
+    IcedElement::new_critical(builder, fallback(), (200, 100), event_loop.handle())
+}
+
+#[test]
+fn working_program_is_shown() {
+    let event_loop = EventLoop::try_new().unwrap();
+    let element = critical(&event_loop, || Ok(Panel::default()));
+
+    element.queue_message(CriticalMessage::Inner(Message::Increment));
+    assert!(!element.is_fallback());
+}
+
+#[test]
+fn failed_builder_shows_the_fallback() {
+    let event_loop = EventLoop::try_new().unwrap();
+    let element = critical(&event_loop, || Err(anyhow!("missing config")));
+    assert!(element.is_fallback());
+}
+
+#[test]
+fn panicking_builder_shows_the_fallback() {
+    let event_loop = EventLoop::try_new().unwrap();
+    let element = critical(&event_loop, || panic!("panel failed to start"));
+    assert!(element.is_fallback());
+}
+
+#[test]
+fn panicking_update_switches_to_the_fallback() {
+    let event_loop = EventLoop::try_new().unwrap();
+    let element = critical(&event_loop, || Ok(Panel::default()));
+
+    element.queue_message(CriticalMessage::Inner(Message::Crash));
+    assert!(element.is_fallback());
+    // the poisoned program doesn't get messages anymore
+    element.queue_message(CriticalMessage::Inner(Message::Increment));
+    assert!(element.is_fallback());
+}
+
+#[test]
+fn panicking_view_switches_to_the_fallback() {
+    let event_loop = EventLoop::try_new().unwrap();
+    let element = critical(&event_loop, || {
+        Ok(Panel {
+            crash_view: true,
+            ..Panel::default()
+        })
+    });
+    assert!(element.is_fallback());
+}
+
+#[test]
+fn fallback_actions_issue_their_requests() {
+    let event_loop = EventLoop::try_new().unwrap();
+    let element = critical(&event_loop, || Err(anyhow!("missing config")));
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let sink = requests.clone();
+    element.set_shell_request_handler(move |request, _| sink.lock().unwrap().push(request));
+
+    element.queue_message(CriticalMessage::Fallback(1));
+    // out of range actions are ignored
+    element.queue_message(CriticalMessage::Fallback(5));
+    assert!(matches!(
+        requests.lock().unwrap()[..],
+        [ShellRequest::CloseElement]
+    ));
+}
+
+#[test]
+fn elements_survive_a_panic_of_an_unguarded_program() {
+    let event_loop = EventLoop::try_new().unwrap();
+    let element = IcedElement::new(Panel::default(), (100, 30), event_loop.handle());
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        element.queue_message(Message::Crash)
+    }));
+    assert!(result.is_err());
+    // the lock isn't poisoned, the element keeps working and can be dropped
+    element.queue_message(Message::Increment);
+    assert_eq!(element.lock().state.program().0.count, 1);
+    drop(element);
+}
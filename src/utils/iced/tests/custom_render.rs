@@ -0,0 +1,97 @@
+use std::sync::{Arc, Mutex};
+
+use cosmic::{iced::widget::text, Element};
+use iced_softbuffer::native::raqote::{DrawOptions, DrawTarget, SolidSource, Source};
+use smithay::utils::{Physical, Size};
+
+use crate::utils::iced::{test_helpers::IcedElementTestHarness, Program};
+
+const RED: u32 = 0xffff0000;
+const BLUE: u32 = 0xff0000ff;
+
+fn fill(target: &mut DrawTarget<&mut [u32]>, (x, y, w, h): (f32, f32, f32, f32), color: u32) {
+    let [b, g, r, a] = color.to_le_bytes();
+    target.fill_rect(
+        x,
+        y,
+        w,
+        h,
+        &Source::Solid(SolidSource::from_unpremultiplied_argb(a, r, g, b)),
+        &DrawOptions::new(),
+    );
+}
+
+/// Paints the left half red in `foreground` and the left quarter blue in `custom_render`.
+#[derive(Default)]
+struct Gauge {
+    only_custom: bool,
+    rendered: Arc<Mutex<Vec<(Size<i32, Physical>, f64)>>>,
+}
+
+impl Program for Gauge {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Gauge gauge gauge gauge").size(32).into()
+    }
+
+    fn foreground(&self, target: &mut DrawTarget<&mut [u32]>) {
+        let (w, h) = (target.width() as f32, target.height() as f32);
+        fill(target, (0.0, 0.0, w / 2.0, h), RED);
+    }
+
+    fn custom_render(
+        &mut self,
+        target: &mut DrawTarget<&mut [u32]>,
+        size: Size<i32, Physical>,
+        scale: f64,
+    ) {
+        self.rendered.lock().unwrap().push((size, scale));
+        fill(target, (0.0, 0.0, size.w as f32 / 4.0, size.h as f32), BLUE);
+    }
+
+    fn custom_render_only(&self) -> bool {
+        self.only_custom
+    }
+}
+
+#[test]
+fn custom_render_gets_the_buffer_size_and_scale() {
+    let gauge = Gauge::default();
+    let rendered = gauge.rendered.clone();
+    let harness = IcedElementTestHarness::new(gauge, (100, 40));
+
+    let snapshot = harness.snapshot(2.0);
+    assert_eq!(
+        rendered.lock().unwrap().last(),
+        Some(&(Size::from((200, 80)), 2.0))
+    );
+    assert_eq!(snapshot.size, Size::from((200, 80)));
+}
+
+#[test]
+fn custom_render_draws_after_foreground() {
+    let harness = IcedElementTestHarness::new(Gauge::default(), (100, 40));
+    let snapshot = harness.snapshot(1.0);
+    assert_eq!(snapshot.pixel(10, 20), Some(BLUE));
+    assert_eq!(snapshot.pixel(40, 20), Some(RED));
+}
+
+#[test]
+fn custom_render_only_skips_the_widgets() {
+    let harness = IcedElementTestHarness::new(
+        Gauge {
+            only_custom: true,
+            ..Gauge::default()
+        },
+        (200, 40),
+    );
+    let snapshot = harness.snapshot(1.0);
+    assert!(
+        snapshot.pixels[..]
+            .chunks(200)
+            .all(|row| row[100..].iter().all(|pixel| *pixel == 0)),
+        "the text isn't drawn"
+    );
+    assert_eq!(snapshot.pixel(10, 20), Some(BLUE));
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use smithay::backend::input::ButtonState;
+
+use crate::utils::iced::{
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement, DEFERRED_UPDATE_DELAY,
+};
+
+fn layouts(element: &IcedElement<Label>) -> u64 {
+    element.0.lock().unwrap().state.layouts()
+}
+
+fn update_pending(element: &IcedElement<Label>) -> bool {
+    element.0.lock().unwrap().update_pending
+}
+
+/// An element outside of the output, so updates don't wait for frames.
+fn setup() -> (HeadlessCompositor<Label>, IcedElement<Label>) {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (1000, 1000));
+    compositor.pointer_enter(&element, (1.0, 1.0));
+    compositor.dispatch(Duration::ZERO);
+    (compositor, element)
+}
+
+fn motion_burst(compositor: &mut HeadlessCompositor<Label>, element: &IcedElement<Label>) {
+    for x in 2..12 {
+        compositor.pointer_motion(element, (f64::from(x), 10.0));
+    }
+}
+
+#[test]
+fn motion_burst_is_laid_out_once_it_settled() {
+    let (mut compositor, element) = setup();
+    let before = layouts(&element);
+
+    motion_burst(&mut compositor, &element);
+    assert_eq!(layouts(&element), before, "motion is deferred");
+    assert!(update_pending(&element));
+
+    std::thread::sleep(DEFERRED_UPDATE_DELAY * 2);
+    compositor.dispatch(Duration::ZERO);
+    assert_eq!(layouts(&element), before + 1);
+    assert!(!update_pending(&element));
+}
+
+#[test]
+fn buttons_flush_deferred_motion() {
+    let (mut compositor, element) = setup();
+    let before = layouts(&element);
+
+    motion_burst(&mut compositor, &element);
+    compositor.pointer_button(&element, 0x110, ButtonState::Pressed);
+
+    assert!(
+        layouts(&element) > before,
+        "the press is applied right away"
+    );
+}
@@ -0,0 +1,136 @@
+use std::sync::{Arc, Mutex};
+
+use cosmic::{iced::widget::text, Element};
+use smithay::{
+    reexports::calloop::EventLoop,
+    utils::{Logical, Point, Rectangle},
+};
+
+use crate::{
+    shell::element::surface::SSD_HEIGHT,
+    state::Data,
+    utils::iced::{
+        test_helpers::IcedElementTestHarness, Decorated, Either, IcedElement, Program,
+        ShellRequest, TitleBarMessage, TitleBarProgram, WindowAction,
+    },
+};
+
+/// Content reporting drop positions and a press region at its origin.
+struct Content;
+
+impl Program for Content {
+    type Message = Point<f64, Logical>;
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Content").into()
+    }
+
+    fn press_regions(&self) -> Vec<Rectangle<i32, Logical>> {
+        vec![Rectangle::from_loc_and_size((0, 0), (10, 10))]
+    }
+
+    fn dnd_motion(&self, position: Point<f64, Logical>) -> Option<Self::Message> {
+        Some(position)
+    }
+}
+
+fn decorated() -> Decorated<Content> {
+    Decorated {
+        titlebar: TitleBarProgram {
+            title: String::from("Title"),
+        },
+        content: Content,
+    }
+}
+
+#[test]
+fn titlebar_is_added_on_top_of_the_content() {
+    let event_loop = EventLoop::<Data>::try_new().unwrap();
+    let element = IcedElement::new_decorated(Content, "Title", (200, 100), event_loop.handle());
+
+    assert_eq!(
+        element.0.lock().unwrap().size,
+        (200, 100 + SSD_HEIGHT).into()
+    );
+}
+
+#[test]
+fn titlebar_actions_become_window_requests() {
+    let harness = IcedElementTestHarness::new(decorated(), (200, 100));
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    harness
+        .element()
+        .set_shell_request_handler(move |request, _| sink.lock().unwrap().push(request));
+
+    for action in [WindowAction::Maximize, WindowAction::Close] {
+        harness
+            .element()
+            .queue_message(Either::First(TitleBarMessage::Action(action)));
+    }
+
+    let received = received.lock().unwrap();
+    assert!(matches!(
+        received[..],
+        [
+            ShellRequest::Window(WindowAction::Maximize),
+            ShellRequest::Window(WindowAction::Close)
+        ]
+    ));
+}
+
+#[test]
+fn title_can_be_changed() {
+    let event_loop = EventLoop::<Data>::try_new().unwrap();
+    let element = IcedElement::new_decorated(Content, "Title", (200, 100), event_loop.handle());
+
+    element.set_title("Renamed");
+    assert_eq!(
+        element.with_program(|p| p.titlebar.title.clone()),
+        "Renamed"
+    );
+}
+
+#[test]
+fn content_positions_are_below_the_titlebar() {
+    let program = decorated();
+
+    assert_eq!(
+        program.press_regions(),
+        vec![Rectangle::from_loc_and_size((0, SSD_HEIGHT), (10, 10))]
+    );
+    let Some(Either::Second(position)) =
+        program.dnd_motion(Point::from((5.0, SSD_HEIGHT as f64 + 5.0)))
+    else {
+        panic!("Drop motion wasn't forwarded to the content");
+    };
+    assert_eq!(position, Point::from((5.0, 5.0)));
+}
+
+#[test]
+fn titlebar_accepts_input_of_narrowed_content() {
+    struct Narrow;
+
+    impl Program for Narrow {
+        type Message = ();
+
+        fn view(&self) -> Element<'_, Self::Message> {
+            text("Narrow").into()
+        }
+
+        fn input_region(&self) -> Option<Vec<Rectangle<i32, Logical>>> {
+            Some(Vec::new())
+        }
+    }
+
+    let program = Decorated {
+        titlebar: TitleBarProgram {
+            title: String::new(),
+        },
+        content: Narrow,
+    };
+    let region = program.input_region().unwrap();
+    assert_eq!(region.len(), 1);
+    assert!(region[0].contains((100, SSD_HEIGHT / 2)));
+    assert!(!region[0].contains((100, SSD_HEIGHT)));
+}
@@ -0,0 +1,139 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use cosmic::{iced::widget::text, Element};
+
+use crate::utils::iced::{
+    dismiss::AutoDismiss, test_helpers::HeadlessCompositor, AutoDismissPolicy, IcedElement, Program,
+};
+
+const LIFETIME: Duration = Duration::from_millis(30);
+
+struct Toast;
+
+impl Program for Toast {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Toast").into()
+    }
+}
+
+fn policy(pause_on_hover: bool) -> AutoDismissPolicy {
+    AutoDismissPolicy {
+        after: LIFETIME,
+        pause_on_hover,
+        reset_on_update: false,
+        reset_on_interaction: false,
+    }
+}
+
+/// Sets `policy` on `element`, returning whether it was dismissed yet.
+fn auto_dismiss(element: &IcedElement<Toast>, policy: AutoDismissPolicy) -> Arc<AtomicBool> {
+    let dismissed = Arc::new(AtomicBool::new(false));
+    let flag = dismissed.clone();
+    element.set_auto_dismiss(policy, move || flag.store(true, Ordering::SeqCst));
+    dismissed
+}
+
+/// Dispatches the event loop until `duration` passed.
+fn run_for(compositor: &mut HeadlessCompositor<Toast>, duration: Duration) {
+    let end = Instant::now() + duration;
+    while let Some(left) = end.checked_duration_since(Instant::now()) {
+        compositor.dispatch(left);
+    }
+}
+
+#[test]
+fn hovering_pauses_the_lifetime() {
+    let now = Instant::now();
+    let mut dismiss = AutoDismiss::new(policy(true), Box::new(|| {}), now);
+    assert_eq!(dismiss.deadline(), Some(now + LIFETIME));
+
+    let hovered_at = now + Duration::from_millis(10);
+    assert!(dismiss.set_hovered(true, hovered_at));
+    assert!(!dismiss.set_hovered(true, hovered_at));
+    assert_eq!(dismiss.deadline(), None);
+
+    let left_at = now + Duration::from_secs(5);
+    assert!(dismiss.set_hovered(false, left_at));
+    assert_eq!(
+        dismiss.deadline(),
+        Some(left_at + LIFETIME - Duration::from_millis(10))
+    );
+}
+
+#[test]
+fn hovering_is_ignored_without_pausing() {
+    let now = Instant::now();
+    let mut dismiss = AutoDismiss::new(policy(false), Box::new(|| {}), now);
+    assert!(!dismiss.set_hovered(true, now));
+    assert_eq!(dismiss.deadline(), Some(now + LIFETIME));
+}
+
+#[test]
+fn resetting_restarts_the_full_lifetime() {
+    let now = Instant::now();
+    let mut dismiss = AutoDismiss::new(policy(true), Box::new(|| {}), now);
+    let later = now + Duration::from_millis(20);
+    dismiss.reset(later);
+    assert_eq!(dismiss.deadline(), Some(later + LIFETIME));
+
+    // stays paused, but with the full lifetime left
+    dismiss.set_hovered(true, later + Duration::from_millis(25));
+    let left_at = later + Duration::from_secs(1);
+    dismiss.reset(left_at);
+    assert_eq!(dismiss.deadline(), None);
+    dismiss.set_hovered(false, left_at);
+    assert_eq!(dismiss.deadline(), Some(left_at + LIFETIME));
+}
+
+#[test]
+fn expired_elements_are_dismissed() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Toast, (100, 40), (0, 0));
+    let dismissed = auto_dismiss(&element, policy(false));
+    assert!(!dismissed.load(Ordering::SeqCst));
+
+    run_for(&mut compositor, LIFETIME * 2);
+    assert!(dismissed.load(Ordering::SeqCst));
+}
+
+#[test]
+fn hovered_elements_stay_until_left() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Toast, (100, 40), (0, 0));
+    compositor.pointer_enter(&element, (10.0, 10.0));
+    let dismissed = auto_dismiss(&element, policy(true));
+
+    run_for(&mut compositor, LIFETIME * 2);
+    assert!(!dismissed.load(Ordering::SeqCst));
+
+    compositor.pointer_leave(&element);
+    run_for(&mut compositor, LIFETIME * 2);
+    assert!(dismissed.load(Ordering::SeqCst));
+}
+
+#[test]
+fn cleared_or_closed_elements_are_not_dismissed() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let cleared = compositor.insert(Toast, (100, 40), (0, 0));
+    let closed = compositor.insert(Toast, (100, 40), (0, 100));
+    let cleared_dismissed = auto_dismiss(&cleared, policy(false));
+    let closed_dismissed = auto_dismiss(&closed, policy(false));
+
+    cleared.clear_auto_dismiss();
+    let on_complete = Arc::new(AtomicBool::new(false));
+    let flag = on_complete.clone();
+    closed.begin_exit_transition(None, move || flag.store(true, Ordering::SeqCst));
+
+    run_for(&mut compositor, LIFETIME * 2);
+    assert!(!cleared_dismissed.load(Ordering::SeqCst));
+    assert!(!closed_dismissed.load(Ordering::SeqCst));
+    assert!(on_complete.load(Ordering::SeqCst));
+}
@@ -0,0 +1,160 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::utils::iced::{
+    test_helpers::HeadlessCompositor, DragPayload, IcedElement, Program, UpdateContext,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Message {
+    StartDrag,
+    Hook(Hook),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Hook {
+    Enter,
+    Leave,
+    Drop(DragPayload, Point<f64, Logical>),
+    Cancelled(DragPayload),
+}
+
+/// Records the drag hooks it receives.
+struct Tile {
+    name: &'static str,
+    hooks: Vec<Hook>,
+}
+
+impl Tile {
+    fn new(name: &'static str) -> Tile {
+        Tile {
+            name,
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl Program for Tile {
+    type Message = Message;
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Message> {
+        match message {
+            Message::StartDrag => {
+                ctx.start_internal_drag(DragPayload::Text(self.name.to_owned()), None)
+            }
+            Message::Hook(hook) => self.hooks.push(hook),
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(self.name).into()
+    }
+
+    fn accepts_drops(&self) -> bool {
+        true
+    }
+
+    fn dnd_enter(&self, _: &DragPayload, _: Point<f64, Logical>) -> Option<Message> {
+        Some(Message::Hook(Hook::Enter))
+    }
+
+    fn dnd_leave(&self) -> Option<Message> {
+        Some(Message::Hook(Hook::Leave))
+    }
+
+    fn dnd_drop(&self, payload: &DragPayload, position: Point<f64, Logical>) -> Option<Message> {
+        Some(Message::Hook(Hook::Drop(payload.clone(), position)))
+    }
+
+    fn drag_cancelled(&self, payload: &DragPayload) -> Option<Message> {
+        Some(Message::Hook(Hook::Cancelled(payload.clone())))
+    }
+}
+
+fn hooks(element: &IcedElement<Tile>) -> Vec<Hook> {
+    element.with_program(|p| p.hooks.clone())
+}
+
+fn is_dragging(element: &IcedElement<Tile>) -> bool {
+    element.0.lock().unwrap().drag.is_some()
+}
+
+/// Source at (0, 0) and target at (200, 0), both 100x50 and located by the compositor.
+fn setup() -> (
+    HeadlessCompositor<Tile>,
+    IcedElement<Tile>,
+    IcedElement<Tile>,
+) {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let source = compositor.insert(Tile::new("source"), (100, 50), (0, 0));
+    let target = compositor.insert(Tile::new("target"), (100, 50), (200, 0));
+    source.queue_message(Message::StartDrag);
+    compositor.settle();
+    assert!(is_dragging(&source));
+    (compositor, source, target)
+}
+
+#[test]
+fn drop_reaches_located_target() {
+    let (_compositor, source, target) = setup();
+
+    assert!(source.drag_motion((250.0, 25.0).into()));
+    assert!(source.drag_drop((260.0, 30.0).into()));
+
+    assert!(!is_dragging(&source));
+    assert_eq!(
+        hooks(&target),
+        vec![
+            Hook::Enter,
+            Hook::Drop(
+                DragPayload::Text(String::from("source")),
+                (60.0, 30.0).into()
+            ),
+        ]
+    );
+    assert!(hooks(&source).is_empty());
+}
+
+#[test]
+fn drop_outside_of_targets_returns_to_source() {
+    let (_compositor, source, target) = setup();
+
+    assert!(source.drag_motion((250.0, 25.0).into()));
+    assert!(source.drag_drop((350.0, 150.0).into()));
+
+    assert_eq!(hooks(&target), vec![Hook::Enter, Hook::Leave]);
+    assert_eq!(
+        hooks(&source),
+        vec![Hook::Cancelled(DragPayload::Text(String::from("source")))]
+    );
+}
+
+#[test]
+fn cancelled_drag_returns_to_source() {
+    let (_compositor, source, target) = setup();
+
+    assert!(source.drag_motion((250.0, 25.0).into()));
+    assert!(source.drag_cancel());
+
+    assert!(!is_dragging(&source));
+    assert_eq!(hooks(&target), vec![Hook::Enter, Hook::Leave]);
+    assert_eq!(
+        hooks(&source),
+        vec![Hook::Cancelled(DragPayload::Text(String::from("source")))]
+    );
+}
+
+#[test]
+fn bounding_box_is_local_to_the_output() {
+    let mut compositor = HeadlessCompositor::<Tile>::new((400, 200), 1.0);
+    let element = compositor.insert(Tile::new("tile"), (100, 50), (200, 20));
+    assert_eq!(
+        element.bounding_box_in_output(compositor.output()),
+        Rectangle::from_loc_and_size((200, 20), (100, 50))
+    );
+}
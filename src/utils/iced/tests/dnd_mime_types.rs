@@ -0,0 +1,90 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::utils::{Logical, Point};
+
+use crate::utils::iced::{
+    test_helpers::HeadlessCompositor, DragPayload, IcedElement, Program, UpdateContext,
+};
+
+#[derive(Debug, Clone)]
+struct StartDrag;
+
+/// Drags plain text, and accepts uris on its left half and plain text on its right half.
+struct Shelf;
+
+impl Program for Shelf {
+    type Message = StartDrag;
+
+    fn update_with_context(
+        &mut self,
+        _: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        ctx.start_internal_drag(DragPayload::Text(String::from("note")), None);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Shelf").into()
+    }
+
+    fn accepts_drops(&self) -> bool {
+        true
+    }
+
+    fn dnd_mime_types(&self, position: Point<f64, Logical>) -> Vec<String> {
+        if position.x < 200.0 {
+            vec![String::from("text/uri-list")]
+        } else {
+            vec![
+                String::from("text/uri-list"),
+                String::from("text/plain;charset=utf-8"),
+            ]
+        }
+    }
+}
+
+fn accepted(source: &IcedElement<Shelf>) -> Option<String> {
+    source.0.lock().unwrap().drag.as_ref()?.accepted()
+}
+
+#[test]
+fn payloads_have_mime_types() {
+    assert_eq!(
+        DragPayload::Text(String::new()).mime_type(),
+        "text/plain;charset=utf-8"
+    );
+    assert_eq!(DragPayload::Uri(String::new()).mime_type(), "text/uri-list");
+    assert_eq!(
+        DragPayload::AppId(String::new()).mime_type(),
+        "application/x-desktop-entry-id"
+    );
+    assert_eq!(
+        DragPayload::Bytes {
+            mime_type: String::from("image/png"),
+            data: Vec::new(),
+        }
+        .mime_type(),
+        "image/png"
+    );
+}
+
+#[test]
+fn accepted_type_follows_the_position_in_the_target() {
+    let mut compositor = HeadlessCompositor::new((400, 400), 1.0);
+    // away from the elements of the other drag tests, whose drop targets are global
+    let source = compositor.insert(Shelf, (100, 50), (0, 300));
+    let _target = compositor.insert(Shelf, (400, 50), (0, 350));
+    source.queue_message(StartDrag);
+    compositor.settle();
+
+    assert!(source.drag_motion((100.0, 375.0).into()));
+    assert_eq!(accepted(&source), None, "left half takes uris only");
+    assert!(source.drag_motion((300.0, 375.0).into()));
+    assert_eq!(
+        accepted(&source).as_deref(),
+        Some("text/plain;charset=utf-8")
+    );
+    assert!(source.drag_motion((300.0, 250.0).into()));
+    assert_eq!(accepted(&source), None, "no target");
+    assert!(source.drag_cancel());
+}
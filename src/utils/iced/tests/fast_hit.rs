@@ -0,0 +1,138 @@
+use cosmic::{iced::widget::text, Element};
+use smithay::{
+    desktop::space::SpaceElement,
+    output::{Mode, Output, PhysicalProperties, Subpixel},
+    utils::{Logical, Point, Rectangle},
+};
+
+use crate::utils::iced::{
+    hit::HitSnapshot, test_helpers::HeadlessCompositor, FastHit, HitIndex, IcedElement, Program,
+};
+
+/// Accepts input on its left half only, if `narrowed`.
+struct Label {
+    narrowed: bool,
+}
+
+impl Program for Label {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Label").into()
+    }
+
+    fn input_region(&self) -> Option<Vec<Rectangle<i32, Logical>>> {
+        self.narrowed
+            .then(|| vec![Rectangle::from_loc_and_size((0, 0), (50, 40))])
+    }
+}
+
+fn output_at(location: (i32, i32)) -> Output {
+    let output = Output::new(
+        String::from("TEST-2"),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: String::from("COSMIC"),
+            model: String::from("Test"),
+        },
+    );
+    let mode = Mode {
+        size: (1920, 1080).into(),
+        refresh: 60_000,
+    };
+    output.add_mode(mode);
+    output.change_current_state(Some(mode), None, None, Some(location.into()));
+    output
+}
+
+fn point(x: f64, y: f64) -> Point<f64, Logical> {
+    Point::from((x, y))
+}
+
+#[test]
+fn snapshot_hits_its_bounds() {
+    let snapshot = HitSnapshot::new((100, 40).into());
+    assert_eq!(snapshot.hit(point(0.0, 0.0)), FastHit::Inside);
+    assert_eq!(snapshot.hit(point(99.5, 39.5)), FastHit::Inside);
+    assert_eq!(snapshot.hit(point(100.0, 20.0)), FastHit::Outside);
+    assert_eq!(snapshot.hit(point(-0.5, 20.0)), FastHit::Outside);
+
+    snapshot.set_size((200, 40).into());
+    assert_eq!(snapshot.hit(point(150.0, 20.0)), FastHit::Inside);
+
+    // only the precise check knows custom regions
+    snapshot.set_custom_region(true);
+    assert_eq!(snapshot.hit(point(150.0, 20.0)), FastHit::MaybeInside);
+    assert_eq!(snapshot.hit(point(250.0, 20.0)), FastHit::Outside);
+
+    snapshot.kill();
+    assert_eq!(snapshot.hit(point(10.0, 10.0)), FastHit::Outside);
+}
+
+#[test]
+fn index_hits_global_points() {
+    let index = HitIndex::default();
+    assert_eq!(index.hit(point(10.0, 10.0)), FastHit::Outside);
+
+    let snapshot = HitSnapshot::new((100, 40).into());
+    index.insert(1, snapshot.clone());
+    assert_eq!(
+        index.hit(point(5000.0, 5000.0)),
+        FastHit::MaybeInside,
+        "without offsets only the space knows the location"
+    );
+
+    let output = output_at((1920, 0));
+    snapshot.set_offsets(&[(output.clone(), Point::from((10, 20)))]);
+    assert_eq!(index.hit(point(1930.0, 20.0)), FastHit::Inside);
+    assert_eq!(index.hit(point(10.0, 20.0)), FastHit::Outside);
+    assert_eq!(
+        index.overlapping(&output, Rectangle::from_loc_and_size((1900, 0), (50, 50))),
+        vec![(1, Rectangle::from_loc_and_size((1930, 20), (100, 40)))]
+    );
+
+    index.remove(&snapshot);
+    assert_eq!(index.hit(point(1930.0, 20.0)), FastHit::Outside);
+}
+
+#[test]
+fn fast_hit_doesnt_lock_the_element() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label { narrowed: false }, (100, 40), (0, 0));
+
+    let _guard = element.0.lock().unwrap();
+    assert_eq!(element.fast_hit(point(10.0, 10.0)), FastHit::Inside);
+    assert_eq!(element.fast_hit(point(110.0, 10.0)), FastHit::Outside);
+}
+
+#[test]
+fn fast_hit_follows_resizes() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label { narrowed: false }, (100, 40), (0, 0));
+
+    element.resize((200, 40).into());
+    assert_eq!(element.fast_hit(point(150.0, 10.0)), FastHit::Inside);
+}
+
+fn assert_no_false_negatives(element: &IcedElement<Label>) {
+    for x in (-10..120).step_by(5) {
+        for y in (-10..60).step_by(5) {
+            let point = point(x as f64, y as f64);
+            if element.is_in_input_region(&point) {
+                assert_ne!(element.fast_hit(point), FastHit::Outside, "at {:?}", point);
+            }
+        }
+    }
+}
+
+#[test]
+fn fast_hit_never_rules_out_accepted_points() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let full = compositor.insert(Label { narrowed: false }, (100, 40), (0, 0));
+    let narrowed = compositor.insert(Label { narrowed: true }, (100, 40), (0, 100));
+
+    assert_no_false_negatives(&full);
+    assert_no_false_negatives(&narrowed);
+    assert_eq!(narrowed.fast_hit(point(75.0, 20.0)), FastHit::MaybeInside);
+}
@@ -0,0 +1,161 @@
+use cosmic::{
+    iced::widget::{text_input, Column},
+    iced_native::{widget, Command},
+    Element,
+};
+
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        focus::FocusedWidget, test_helpers::IcedElementTestHarness, FocusTarget, Program,
+    },
+};
+
+const QUERY: &str = "query";
+
+#[derive(Debug, Clone)]
+enum Message {
+    Query(String),
+}
+
+/// Search field of a launcher
+struct Launcher {
+    query: String,
+    focus: bool,
+}
+
+impl Launcher {
+    fn new(focus: bool) -> Launcher {
+        Launcher {
+            query: String::new(),
+            focus,
+        }
+    }
+}
+
+impl Program for Launcher {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        let Message::Query(query) = message;
+        self.query = query;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text_input("Search", &self.query, Message::Query)
+            .id(text_input::Id::new(QUERY))
+            .into()
+    }
+
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        self.focus
+            .then(|| FocusTarget::Widget(widget::Id::new(QUERY)))
+    }
+}
+
+#[test]
+fn typed_text_reaches_initially_focused_field() {
+    let harness = IcedElementTestHarness::new(Launcher::new(true), (400, 40));
+    harness.type_text("ab");
+    assert_eq!(harness.element().with_program(|p| p.query.clone()), "ab");
+}
+
+#[test]
+fn unfocused_field_ignores_typed_text() {
+    let harness = IcedElementTestHarness::new(Launcher::new(false), (400, 40));
+    harness.type_text("ab");
+    assert_eq!(harness.element().with_program(|p| p.query.clone()), "");
+}
+
+#[test]
+fn first_focusable_widget_is_focused() {
+    struct FirstFocusable(Launcher);
+    impl Program for FirstFocusable {
+        type Message = Message;
+        fn update(
+            &mut self,
+            message: Self::Message,
+            loop_handle: &LoopHandle<'static, Data>,
+        ) -> Command<Self::Message> {
+            self.0.update(message, loop_handle)
+        }
+        fn view(&self) -> Element<'_, Self::Message> {
+            self.0.view()
+        }
+        fn initial_focus(&self) -> Option<FocusTarget> {
+            Some(FocusTarget::FirstFocusable)
+        }
+    }
+
+    let harness = IcedElementTestHarness::new(FirstFocusable(Launcher::new(false)), (400, 40));
+    harness.type_text("x");
+    assert_eq!(harness.element().with_program(|p| p.0.query.clone()), "x");
+}
+
+#[test]
+fn initial_focus_is_applied_again_after_replacing_the_program() {
+    let harness = IcedElementTestHarness::new(Launcher::new(true), (400, 40));
+    harness.type_text("a");
+    harness.element().replace_program(Launcher::new(true));
+    harness.type_text("b");
+    assert_eq!(harness.element().with_program(|p| p.query.clone()), "b");
+}
+
+#[test]
+fn focused_widget_is_found() {
+    let harness = IcedElementTestHarness::new(Launcher::new(true), (400, 40));
+    let focused = harness.element().0.lock().unwrap().find_focused();
+    assert_eq!(
+        focused,
+        Some(FocusedWidget {
+            index: 0,
+            id: Some(widget::Id::new(QUERY)),
+            editable: true,
+        })
+    );
+}
+
+#[test]
+fn no_widget_is_found_without_focus() {
+    let harness = IcedElementTestHarness::new(Launcher::new(false), (400, 40));
+    assert_eq!(harness.element().0.lock().unwrap().find_focused(), None);
+}
+
+#[test]
+fn focused_widget_is_found_by_tab_order() {
+    const PASSWORD: &str = "password";
+    struct Login(Launcher);
+    impl Program for Login {
+        type Message = Message;
+        fn update(
+            &mut self,
+            message: Self::Message,
+            loop_handle: &LoopHandle<'static, Data>,
+        ) -> Command<Self::Message> {
+            self.0.update(message, loop_handle)
+        }
+        fn view(&self) -> Element<'_, Self::Message> {
+            Column::with_children(vec![
+                self.0.view(),
+                text_input("Password", "", Message::Query)
+                    .id(text_input::Id::new(PASSWORD))
+                    .into(),
+            ])
+            .into()
+        }
+        fn initial_focus(&self) -> Option<FocusTarget> {
+            Some(FocusTarget::Widget(widget::Id::new(PASSWORD)))
+        }
+    }
+
+    let harness = IcedElementTestHarness::new(Login(Launcher::new(false)), (400, 80));
+    let focused = harness.element().0.lock().unwrap().find_focused();
+    assert_eq!(focused.map(|focused| focused.index), Some(1));
+}
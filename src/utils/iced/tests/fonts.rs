@@ -0,0 +1,25 @@
+use crate::utils::iced::{set_default_fonts, FontConfig, FontError};
+
+#[test]
+fn font_config_reads_partial_configs() {
+    let fonts: FontConfig = ron::de::from_str("(default_size: Some(14))").unwrap();
+    assert_eq!(
+        fonts,
+        FontConfig {
+            default_size: Some(14),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn missing_default_family_is_rejected() {
+    let fonts = FontConfig {
+        default_family: Some("No Such Family 1234".into()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        set_default_fonts(fonts),
+        Err(FontError::MissingFamily(family)) if family == "No Such Family 1234"
+    ));
+}
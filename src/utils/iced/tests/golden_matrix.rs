@@ -0,0 +1,191 @@
+use std::{fs, path::PathBuf};
+
+use cosmic::{iced::widget::text, Element};
+use smithay::utils::Size;
+
+use super::single_golden;
+use crate::utils::iced::{
+    golden::{GoldenFailureReason, GoldenMatrix, GoldenPrograms},
+    Program,
+};
+
+struct Label(&'static str);
+
+impl Program for Label {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(self.0).into()
+    }
+}
+
+/// An empty directory for the goldens or diffs of a single test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cosmic-comp-golden-matrix-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn label(text: &'static str) -> GoldenPrograms {
+    let mut programs = GoldenPrograms::new();
+    programs.register("label", move || Label(text));
+    programs
+}
+
+/// `single_golden`, only comparing regardless of `golden::BLESS_VAR`.
+fn checking(scale: f64, size: (i32, i32)) -> GoldenMatrix {
+    GoldenMatrix {
+        bless: false,
+        ..single_golden(scale, size)
+    }
+}
+
+/// `single_golden`, writing missing and failing goldens.
+fn blessing(scale: f64, size: (i32, i32)) -> GoldenMatrix {
+    GoldenMatrix {
+        bless: true,
+        ..single_golden(scale, size)
+    }
+}
+
+#[test]
+fn missing_goldens_fail_without_being_written() {
+    let (goldens, diffs) = (temp_dir("missing"), temp_dir("missing-diffs"));
+    let matrix = checking(1.0, (100, 40));
+
+    for _ in 0..2 {
+        let failures = label("Golden").check(&matrix, &goldens, &diffs);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].program, "label");
+        assert_eq!(failures[0].entry, "label-dark-1x-100x40.png");
+        assert_eq!(failures[0].reason, GoldenFailureReason::Missing);
+        assert!(!goldens.join("label-dark-1x-100x40.png").exists());
+    }
+}
+
+#[test]
+fn blessing_writes_missing_and_failing_goldens() {
+    let (goldens, diffs) = (temp_dir("bless"), temp_dir("bless-diffs"));
+    let matrix = checking(1.0, (100, 40));
+
+    assert!(label("Golden")
+        .check(&blessing(1.0, (100, 40)), &goldens, &diffs)
+        .is_empty());
+    assert!(goldens.join("label-dark-1x-100x40.png").exists());
+    assert!(label("Golden").check(&matrix, &goldens, &diffs).is_empty());
+
+    assert!(label("Changed")
+        .check(&blessing(1.0, (100, 40)), &goldens, &diffs)
+        .is_empty());
+    assert!(label("Changed").check(&matrix, &goldens, &diffs).is_empty());
+    assert_eq!(label("Golden").check(&matrix, &goldens, &diffs).len(), 1);
+}
+
+#[test]
+fn differences_are_written_side_by_side() {
+    let (goldens, diffs) = (temp_dir("differs"), temp_dir("differs-diffs"));
+    label("Golden").check(&blessing(1.0, (100, 40)), &goldens, &diffs);
+
+    let failures = label("Changed").check(&checking(1.0, (100, 40)), &goldens, &diffs);
+    assert_eq!(failures.len(), 1);
+    assert!(matches!(
+        failures[0].reason,
+        GoldenFailureReason::Differs { pixels } if pixels > 0
+    ));
+    let diff = fs::File::open(diffs.join(&failures[0].entry)).unwrap();
+    let reader = png::Decoder::new(diff).read_info().unwrap();
+    let info = reader.info();
+    assert_eq!((info.width, info.height), (300, 40));
+
+    // tolerated, if enough pixels may differ
+    let lenient = GoldenMatrix {
+        max_differing_pixels: 100 * 40,
+        ..checking(1.0, (100, 40))
+    };
+    assert!(label("Changed")
+        .check(&lenient, &goldens, &diffs)
+        .is_empty());
+}
+
+#[test]
+fn size_mismatches_are_reported() {
+    let (goldens, diffs) = (temp_dir("size"), temp_dir("size-diffs"));
+    label("Golden").check(&blessing(1.0, (100, 40)), &goldens, &diffs);
+    fs::copy(
+        goldens.join("label-dark-1x-100x40.png"),
+        goldens.join("label-dark-1x-120x40.png"),
+    )
+    .unwrap();
+
+    let failures = label("Golden").check(&checking(1.0, (120, 40)), &goldens, &diffs);
+    assert_eq!(
+        failures[0].reason,
+        GoldenFailureReason::SizeMismatch {
+            golden: (100, 40),
+            actual: (120, 40),
+        }
+    );
+}
+
+#[test]
+fn every_entry_of_the_matrix_is_rendered() {
+    let (goldens, diffs) = (temp_dir("matrix"), temp_dir("matrix-diffs"));
+    let matrix = GoldenMatrix {
+        scales: vec![1.0, 1.5],
+        sizes: vec![Size::from((100, 40)), Size::from((50, 20))],
+        bless: false,
+        ..GoldenMatrix::default()
+    };
+
+    let mut entries = label("Golden")
+        .check(&matrix, &goldens, &diffs)
+        .into_iter()
+        .map(|failure| failure.entry)
+        .collect::<Vec<_>>();
+    entries.sort();
+    assert_eq!(
+        entries,
+        vec![
+            "label-dark-1.5x-100x40.png",
+            "label-dark-1.5x-50x20.png",
+            "label-dark-1x-100x40.png",
+            "label-dark-1x-50x20.png",
+            "label-light-1.5x-100x40.png",
+            "label-light-1.5x-50x20.png",
+            "label-light-1x-100x40.png",
+            "label-light-1x-50x20.png",
+        ]
+    );
+}
+
+#[test]
+fn registering_a_name_again_replaces_the_program() {
+    let (goldens, diffs) = (temp_dir("replace"), temp_dir("replace-diffs"));
+    let mut programs = label("Golden");
+    programs.register("label", || Label("Replaced"));
+
+    assert!(programs
+        .check(&blessing(1.0, (100, 40)), &goldens, &diffs)
+        .is_empty());
+    let entries = fs::read_dir(&goldens).unwrap().count();
+    assert_eq!(entries, 1, "only one program is registered");
+    assert!(label("Replaced")
+        .check(&checking(1.0, (100, 40)), &goldens, &diffs)
+        .is_empty());
+}
+
+#[test]
+fn in_tree_programs_render_sample_state() {
+    let (goldens, diffs) = (temp_dir("in-tree"), temp_dir("in-tree-diffs"));
+    let mut programs = GoldenPrograms::in_tree()
+        .check(&checking(1.0, (420, 180)), &goldens, &diffs)
+        .into_iter()
+        .map(|failure| failure.program)
+        .collect::<Vec<_>>();
+    programs.sort();
+    assert_eq!(programs, vec!["drag-icon", "prompt", "titlebar"]);
+}
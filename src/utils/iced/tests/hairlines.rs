@@ -0,0 +1,154 @@
+use cosmic::{
+    iced::widget::{horizontal_rule, horizontal_space, vertical_rule, vertical_space, Column, Row},
+    iced_native::Length,
+    Element,
+};
+
+use super::{assert_goldens, single_golden};
+use crate::utils::iced::{
+    golden::{GoldenMatrix, GoldenPrograms},
+    test_helpers::IcedElementTestHarness,
+    Program,
+};
+
+/// A horizontal separator 10 logical pixels from the top.
+struct Separator;
+
+impl Program for Separator {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Column::with_children(vec![
+            vertical_space(Length::Units(10)).into(),
+            horizontal_rule(1).into(),
+        ])
+        .into()
+    }
+}
+
+/// A vertical separator 10 logical pixels from the left.
+struct Divider;
+
+impl Program for Divider {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Row::with_children(vec![
+            horizontal_space(Length::Units(10)).into(),
+            vertical_rule(1).into(),
+        ])
+        .into()
+    }
+}
+
+/// Rows of column `x` that differ from the top left pixel.
+fn painted_rows<P: Program + Send + 'static>(
+    harness: &IcedElementTestHarness<P>,
+    scale: f64,
+    x: i32,
+) -> Vec<i32> {
+    let snapshot = harness.snapshot(scale);
+    let empty = snapshot.pixel(0, 0);
+    (0..snapshot.size.h)
+        .filter(|y| snapshot.pixel(x, *y) != empty)
+        .collect()
+}
+
+/// Columns of row `y` that differ from the top left pixel.
+fn painted_columns<P: Program + Send + 'static>(
+    harness: &IcedElementTestHarness<P>,
+    scale: f64,
+    y: i32,
+) -> Vec<i32> {
+    let snapshot = harness.snapshot(scale);
+    let empty = snapshot.pixel(0, 0);
+    (0..snapshot.size.w)
+        .filter(|x| snapshot.pixel(*x, y) != empty)
+        .collect()
+}
+
+#[test]
+fn hairlines_stay_on_their_pixel_row_at_integer_scales() {
+    let harness = IcedElementTestHarness::new(Separator, (100, 20));
+    assert_eq!(painted_rows(&harness, 1.0, 50), vec![10]);
+}
+
+#[test]
+fn hairlines_cover_a_single_pixel_row_at_fractional_scales() {
+    let harness = IcedElementTestHarness::new(Separator, (100, 20));
+    // 12.5 physical pixels from the top, rounded to 13
+    assert_eq!(painted_rows(&harness, 1.25, 50), vec![13]);
+    assert_eq!(painted_rows(&harness, 1.5, 50), vec![15]);
+}
+
+#[test]
+fn unsnapped_hairlines_are_blurred_across_rows() {
+    let harness = IcedElementTestHarness::new(Separator, (100, 20));
+    harness.element().set_hairline_snapping(false);
+    assert_eq!(painted_rows(&harness, 1.5, 50), vec![15, 16]);
+}
+
+#[test]
+fn hairlines_keep_their_weight_at_high_scales() {
+    let harness = IcedElementTestHarness::new(Separator, (100, 20));
+    assert_eq!(painted_rows(&harness, 2.0, 50), vec![20, 21]);
+}
+
+#[test]
+fn vertical_hairlines_are_snapped_to_pixel_columns() {
+    let harness = IcedElementTestHarness::new(Divider, (20, 100));
+    assert_eq!(painted_columns(&harness, 1.0, 50), vec![10]);
+    assert_eq!(painted_columns(&harness, 1.25, 50), vec![13]);
+    assert_eq!(painted_columns(&harness, 1.5, 50), vec![15]);
+    assert_eq!(painted_columns(&harness, 2.0, 50), vec![20, 21]);
+}
+
+#[test]
+fn unsnapped_vertical_hairlines_are_blurred_across_columns() {
+    let harness = IcedElementTestHarness::new(Divider, (20, 100));
+    harness.element().set_hairline_snapping(false);
+    assert_eq!(painted_columns(&harness, 1.5, 50), vec![15, 16]);
+}
+
+/// Separators in both directions, at offsets that fall between physical pixels at 1.25 and 1.5.
+struct Grid;
+
+impl Program for Grid {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        let row = || {
+            Row::with_children(vec![
+                horizontal_space(Length::Units(15)).into(),
+                vertical_rule(1).into(),
+                horizontal_space(Length::Units(15)).into(),
+                vertical_rule(1).into(),
+            ])
+            .height(Length::Units(15))
+            .into()
+        };
+        Column::with_children(vec![
+            row(),
+            horizontal_rule(1).into(),
+            row(),
+            horizontal_rule(1).into(),
+        ])
+        .into()
+    }
+}
+
+#[test]
+fn hairlines_golden() {
+    let mut programs = GoldenPrograms::new();
+    programs.register("hairlines", || Grid);
+    programs.register_configured(
+        "hairlines-unsnapped",
+        || Grid,
+        |element| element.set_hairline_snapping(false),
+    );
+    let matrix = GoldenMatrix {
+        scales: vec![1.0, 1.25, 1.5, 2.0],
+        ..single_golden(1.5, (64, 64))
+    };
+    assert_goldens(&programs, &matrix);
+}
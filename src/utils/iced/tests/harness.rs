@@ -0,0 +1,126 @@
+use cosmic::{
+    iced::{
+        widget::{button, text, text_input, Column},
+        Length,
+    },
+    iced_native::{
+        keyboard::{KeyCode, Modifiers},
+        Command,
+    },
+    Element, Theme,
+};
+use smithay::reexports::calloop::LoopHandle;
+
+use super::{assert_goldens, single_golden};
+use crate::{
+    state::Data,
+    utils::iced::{golden::GoldenPrograms, test_helpers::IcedElementTestHarness, Program},
+};
+
+#[derive(Debug, Clone)]
+enum Message {
+    Increment,
+    Input(String),
+    Submit,
+}
+
+/// A counter button above a text field.
+#[derive(Default)]
+struct Counter {
+    count: usize,
+    input: String,
+    submitted: Vec<String>,
+}
+
+impl Program for Counter {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        match message {
+            Message::Increment => self.count += 1,
+            Message::Input(input) => self.input = input,
+            Message::Submit => self.submitted.push(std::mem::take(&mut self.input)),
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Column::with_children(vec![
+            button(text(self.count.to_string()))
+                .width(Length::Fill)
+                .height(Length::Units(40))
+                .on_press(Message::Increment)
+                .into(),
+            text_input("Name", &self.input, Message::Input)
+                .width(Length::Fill)
+                .on_submit(Message::Submit)
+                .into(),
+        ])
+        .into()
+    }
+}
+
+fn harness() -> IcedElementTestHarness<Counter> {
+    IcedElementTestHarness::new(Counter::default(), (200, 80))
+}
+
+#[test]
+fn clicks_reach_the_widget_under_the_cursor() {
+    let harness = harness();
+
+    harness.click_at((100.0, 20.0));
+    harness.click_at((100.0, 20.0));
+    assert_eq!(harness.element().with_program(|p| p.count), 2);
+
+    // below the button
+    harness.click_at((100.0, 60.0));
+    assert_eq!(harness.element().with_program(|p| p.count), 2);
+}
+
+#[test]
+fn typed_text_reaches_the_focused_field() {
+    let harness = harness();
+
+    harness.click_at((100.0, 60.0));
+    harness.type_text("cosmic");
+    assert_eq!(
+        harness.element().with_program(|p| p.input.clone()),
+        "cosmic"
+    );
+
+    harness.key_press(KeyCode::Enter, Modifiers::empty());
+    harness.element().with_program(|p| {
+        assert!(p.input.is_empty());
+        assert_eq!(p.submitted, vec![String::from("cosmic")]);
+    });
+}
+
+#[test]
+fn snapshots_follow_the_state_and_theme() {
+    let harness = harness();
+    let initial = harness.snapshot(1.0);
+    assert_eq!(initial.size, (200, 80).into());
+    assert_eq!(harness.snapshot(2.0).size, (400, 160).into());
+
+    harness.click_at((100.0, 20.0));
+    let clicked = harness.snapshot(1.0);
+    assert_ne!(initial.pixels, clicked.pixels);
+
+    harness.set_theme(Theme::light());
+    assert_ne!(harness.snapshot(1.0).pixels, clicked.pixels);
+}
+
+#[test]
+fn counter_golden() {
+    let mut programs = GoldenPrograms::new();
+    programs.register("harness-counter", || Counter {
+        count: 3,
+        input: String::from("cosmic"),
+        submitted: Vec::new(),
+    });
+    assert_goldens(&programs, &single_golden(1.0, (200, 80)));
+}
@@ -0,0 +1,99 @@
+use cosmic::{
+    iced::{
+        widget::{button, text},
+        Length,
+    },
+    iced_native::Command,
+    Element,
+};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{test_helpers::HeadlessCompositor, IcedElement, Program},
+};
+
+#[derive(Debug, Clone)]
+enum Message {
+    Increment,
+    /// Increments from a future
+    Later,
+}
+
+/// A counter button filling the element.
+#[derive(Default)]
+struct Counter(usize);
+
+impl Program for Counter {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        match message {
+            Message::Increment => {
+                self.0 += 1;
+                Command::none()
+            }
+            Message::Later => Command::perform(async {}, |_| Message::Increment),
+        }
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        button(text(self.0.to_string()))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .on_press(Message::Increment)
+            .into()
+    }
+}
+
+fn count(element: &IcedElement<Counter>) -> usize {
+    element.with_program(|counter| counter.0)
+}
+
+#[test]
+fn inserted_elements_enter_the_output() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 2.0);
+    let element = compositor.insert(Counter::default(), (100, 40), (10, 20));
+    assert_eq!(
+        compositor.space().outputs_for_element(&element),
+        vec![compositor.output().clone()]
+    );
+    assert_eq!(compositor.snapshot(&element).size, (200, 80).into());
+
+    compositor.remove(&element);
+    assert!(compositor.space().outputs_for_element(&element).is_empty());
+    assert_eq!(compositor.space().elements().count(), 0);
+}
+
+#[test]
+fn clicks_go_through_the_seat() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (100, 40), (0, 0));
+    let before = compositor.snapshot(&element);
+
+    compositor.pointer_enter(&element, (50.0, 20.0));
+    compositor.click(&element);
+    compositor.settle();
+    assert_eq!(count(&element), 1);
+    assert_ne!(compositor.snapshot(&element).pixels, before.pixels);
+
+    compositor.pointer_leave(&element);
+    compositor.click(&element);
+    compositor.settle();
+    assert_eq!(count(&element), 1, "the pointer left");
+}
+
+#[test]
+fn futures_run_when_dispatching() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (100, 40), (0, 0));
+    element.queue_message(Message::Later);
+    for _ in 0..3 {
+        compositor.settle();
+    }
+    assert_eq!(count(&element), 1);
+}
@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        test_helpers::HeadlessCompositor, Either, IcedElement, Program, Split, SplitDirection,
+    },
+};
+
+const IDLE_AFTER: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Notification {
+    Idle,
+    Resumed,
+}
+
+/// Records its idle notifications.
+#[derive(Default)]
+struct Applet {
+    notifications: Vec<Notification>,
+}
+
+impl Program for Applet {
+    type Message = Notification;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        self.notifications.push(message);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Applet").into()
+    }
+
+    fn idle(&mut self) -> Option<Self::Message> {
+        Some(Notification::Idle)
+    }
+
+    fn resumed(&mut self) -> Option<Self::Message> {
+        Some(Notification::Resumed)
+    }
+}
+
+fn notifications(element: &IcedElement<Applet>) -> Vec<Notification> {
+    element.with_program(|p| p.notifications.clone())
+}
+
+/// Lets the element become idle and runs the idle timer.
+fn wait_idle<P: Program + Send + 'static>(compositor: &mut HeadlessCompositor<P>) {
+    std::thread::sleep(IDLE_AFTER * 2);
+    compositor.dispatch(Duration::ZERO);
+    compositor.settle();
+}
+
+#[test]
+fn idle_is_notified_once() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Applet::default(), (100, 50), (0, 0));
+    element.set_idle_notify(Some(IDLE_AFTER));
+
+    wait_idle(&mut compositor);
+    assert_eq!(notifications(&element), vec![Notification::Idle]);
+    wait_idle(&mut compositor);
+    assert_eq!(notifications(&element), vec![Notification::Idle]);
+}
+
+#[test]
+fn interaction_resumes_idle_elements() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Applet::default(), (100, 50), (0, 0));
+    element.set_idle_notify(Some(IDLE_AFTER));
+    wait_idle(&mut compositor);
+
+    compositor.pointer_enter(&element, (10.0, 10.0));
+    compositor.settle();
+    assert_eq!(
+        notifications(&element),
+        vec![Notification::Idle, Notification::Resumed]
+    );
+
+    // the timer is armed again
+    wait_idle(&mut compositor);
+    assert_eq!(
+        notifications(&element),
+        vec![
+            Notification::Idle,
+            Notification::Resumed,
+            Notification::Idle
+        ]
+    );
+}
+
+#[test]
+fn activity_postpones_idle() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Applet::default(), (100, 50), (0, 0));
+    element.set_idle_notify(Some(Duration::from_secs(60)));
+
+    compositor.pointer_enter(&element, (10.0, 10.0));
+    compositor.dispatch(Duration::ZERO);
+    assert!(notifications(&element).is_empty());
+}
+
+#[test]
+fn idle_notifications_can_be_disabled() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Applet::default(), (100, 50), (0, 0));
+    element.set_idle_notify(Some(IDLE_AFTER));
+    element.set_idle_notify(None);
+
+    wait_idle(&mut compositor);
+    assert!(notifications(&element).is_empty());
+}
+
+#[test]
+fn split_notifies_both_programs() {
+    let mut split = Split::new(
+        Applet::default(),
+        Applet::default(),
+        SplitDirection::Horizontal,
+        0.5,
+    );
+    assert!(matches!(
+        split.idle(),
+        Some(Either::Both(Notification::Idle, Notification::Idle))
+    ));
+    assert!(matches!(
+        split.resumed(),
+        Some(Either::Both(Notification::Resumed, Notification::Resumed))
+    ));
+}
+
+#[test]
+fn both_programs_of_idle_splits_are_updated() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let split = Split::new(
+        Applet::default(),
+        Applet::default(),
+        SplitDirection::Horizontal,
+        0.5,
+    );
+    let element = compositor.insert(split, (200, 50), (0, 0));
+    element.set_idle_notify(Some(IDLE_AFTER));
+
+    wait_idle(&mut compositor);
+    let (first, second) = element.with_program(|p| {
+        (
+            p.first.notifications.clone(),
+            p.second.notifications.clone(),
+        )
+    });
+    assert_eq!(first, vec![Notification::Idle]);
+    assert_eq!(second, vec![Notification::Idle]);
+}
@@ -0,0 +1,60 @@
+use cosmic::{iced::widget::text, Element};
+
+use crate::utils::iced::{
+    test_helpers::HeadlessCompositor, Conditional, Program, Split, SplitDirection,
+};
+
+/// A label, which may claim to contain editable text.
+struct Editable(bool);
+
+impl Program for Editable {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Editable").into()
+    }
+
+    fn wants_input_method(&self) -> bool {
+        self.0
+    }
+}
+
+#[test]
+fn input_method_is_active_while_focused() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Editable(true), (100, 40), (0, 0));
+    assert!(!element.input_method_active());
+
+    compositor.keyboard_enter(&element);
+    assert!(element.input_method_active());
+
+    compositor.keyboard_leave(&element);
+    assert!(!element.input_method_active());
+}
+
+#[test]
+fn programs_without_text_get_no_input_method() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Editable(false), (100, 40), (0, 0));
+
+    compositor.keyboard_enter(&element);
+    assert!(!element.input_method_active());
+}
+
+#[test]
+fn combinators_want_an_input_method_if_any_child_does() {
+    let split = |first, second| {
+        Split::new(
+            Editable(first),
+            Editable(second),
+            SplitDirection::Vertical,
+            0.5,
+        )
+    };
+    assert!(split(false, true).wants_input_method());
+    assert!(split(true, false).wants_input_method());
+    assert!(!split(false, false).wants_input_method());
+
+    assert!(Conditional::new(Editable(true), true, |shown| *shown).wants_input_method());
+    assert!(!Conditional::new(Editable(true), false, |shown| *shown).wants_input_method());
+}
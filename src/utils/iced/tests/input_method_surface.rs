@@ -0,0 +1,75 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        test_helpers::IcedElementTestHarness, InputMethodEvent, InputMethodSurface, Program,
+    },
+};
+
+/// Records the requests of its input method.
+#[derive(Default)]
+struct Keyboard {
+    events: Vec<InputMethodEvent>,
+}
+
+impl Program for Keyboard {
+    type Message = InputMethodEvent;
+
+    fn update(
+        &mut self,
+        event: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        self.events.push(event);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Keyboard").into()
+    }
+}
+
+fn surface(harness: &IcedElementTestHarness<Keyboard>) -> InputMethodSurface<Keyboard> {
+    InputMethodSurface::new(harness.element().clone(), |event| event)
+}
+
+#[test]
+fn requests_are_delivered_as_messages() {
+    let harness = IcedElementTestHarness::new(Keyboard::default(), (400, 200));
+    let surface = surface(&harness);
+
+    surface.set_preedit_string("ab", Some((2, 2)));
+    surface.commit_string("abc");
+    surface.delete_surrounding_text(1, 0);
+
+    assert_eq!(
+        harness.element().with_program(|p| p.events.clone()),
+        vec![
+            InputMethodEvent::SetPreeditString {
+                text: String::from("ab"),
+                cursor: Some((2, 2)),
+            },
+            InputMethodEvent::CommitString(String::from("abc")),
+            InputMethodEvent::DeleteSurroundingText {
+                before_length: 1,
+                after_length: 0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn keyboard_grab_is_shared_between_clones() {
+    let harness = IcedElementTestHarness::new(Keyboard::default(), (400, 200));
+    let surface = surface(&harness);
+    let clone = surface.clone();
+    assert!(!surface.has_keyboard_grab());
+
+    surface.grab_keyboard();
+    assert!(clone.has_keyboard_grab());
+
+    clone.release_keyboard();
+    assert!(!surface.has_keyboard_grab());
+}
@@ -0,0 +1,96 @@
+use cosmic::{iced::widget::text, Element};
+use smithay::{
+    desktop::space::SpaceElement,
+    utils::{Logical, Point, Rectangle},
+};
+
+use crate::utils::iced::{
+    test_helpers::IcedElementTestHarness, Conditional, ConditionalMessage, Overlaid, Program,
+};
+
+/// Accepts input within `region` only, or everywhere without one.
+struct Card {
+    region: Option<Rectangle<i32, Logical>>,
+}
+
+impl Card {
+    fn narrowed(x: i32, w: i32) -> Card {
+        Card {
+            region: Some(Rectangle::from_loc_and_size((x, 0), (w, 40))),
+        }
+    }
+
+    fn full() -> Card {
+        Card { region: None }
+    }
+}
+
+impl Program for Card {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Card").into()
+    }
+
+    fn input_region(&self) -> Option<Vec<Rectangle<i32, Logical>>> {
+        self.region.map(|region| vec![region])
+    }
+}
+
+fn point(x: f64, y: f64) -> Point<f64, Logical> {
+    Point::from((x, y))
+}
+
+fn shown(visible: &bool) -> bool {
+    *visible
+}
+
+#[test]
+fn elements_accept_input_everywhere_by_default() {
+    let harness = IcedElementTestHarness::new(Card::full(), (100, 40));
+    let element = harness.element();
+    assert!(element.is_in_input_region(&point(0.0, 0.0)));
+    assert!(element.is_in_input_region(&point(99.5, 39.5)));
+}
+
+#[test]
+fn narrowed_regions_let_input_pass_through() {
+    let harness = IcedElementTestHarness::new(Card::narrowed(10, 80), (100, 40));
+    let element = harness.element();
+    assert!(element.is_in_input_region(&point(10.0, 20.0)));
+    assert!(element.is_in_input_region(&point(89.5, 20.0)));
+    assert!(!element.is_in_input_region(&point(5.0, 20.0)));
+    assert!(!element.is_in_input_region(&point(90.0, 20.0)));
+}
+
+#[test]
+fn overlaid_regions_are_united() {
+    let overlaid = Overlaid::new(Card::narrowed(0, 20), Card::narrowed(80, 20), false);
+    let region = overlaid.input_region().unwrap();
+    assert_eq!(region.len(), 2);
+
+    let harness = IcedElementTestHarness::new(overlaid, (100, 40));
+    let element = harness.element();
+    assert!(element.is_in_input_region(&point(10.0, 20.0)));
+    assert!(element.is_in_input_region(&point(90.0, 20.0)));
+    assert!(!element.is_in_input_region(&point(50.0, 20.0)));
+
+    // either covering the whole element covers the union
+    let overlaid = Overlaid::new(Card::narrowed(0, 20), Card::full(), false);
+    assert_eq!(overlaid.input_region(), None);
+}
+
+#[test]
+fn hidden_conditional_programs_accept_no_input() {
+    let conditional = Conditional::new(Card::narrowed(0, 100), false, shown);
+    let harness = IcedElementTestHarness::new(conditional, (100, 40));
+    let element = harness.element();
+    assert!(!element.is_in_input_region(&point(50.0, 20.0)));
+
+    element.queue_message(ConditionalMessage::SetState(true));
+    assert!(element.is_in_input_region(&point(50.0, 20.0)));
+
+    // without narrowing, the whole element keeps accepting input
+    let conditional = Conditional::new(Card::full(), false, shown);
+    assert_eq!(conditional.input_region(), None);
+}
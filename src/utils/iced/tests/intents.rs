@@ -0,0 +1,140 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+
+use crate::utils::iced::{
+    commit_pending_intents, set_intent_handler, test_helpers::HeadlessCompositor, undo_last,
+    DestructiveIntent, IntentEvent, IntentId, Program, UpdateContext,
+};
+
+#[derive(Debug, Clone)]
+enum Message {
+    DismissAll(Duration),
+    Restore,
+}
+
+/// Dismisses its notifications optimistically, until the dismissal was committed.
+struct Notifications {
+    count: usize,
+    committed: Arc<AtomicUsize>,
+}
+
+impl Program for Notifications {
+    type Message = Message;
+
+    fn update_with_context(
+        &mut self,
+        message: Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Message> {
+        match message {
+            Message::DismissAll(undo_window) => {
+                self.count = 0;
+                let committed = self.committed.clone();
+                ctx.destructive(
+                    DestructiveIntent::new("Dismissed all notifications")
+                        .with_undo_window(undo_window),
+                    move || {
+                        committed.fetch_add(1, Ordering::SeqCst);
+                    },
+                );
+            }
+            Message::Restore => self.count = 3,
+        }
+        Command::none()
+    }
+
+    fn intent_undone(&self, _: IntentId) -> Option<Message> {
+        Some(Message::Restore)
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(format!("{} notifications", self.count)).into()
+    }
+}
+
+fn pending_id(events: &Mutex<Vec<IntentEvent>>) -> IntentId {
+    match events.lock().unwrap().last() {
+        Some(IntentEvent::Pending(id, intent)) => {
+            assert_eq!(intent.description, "Dismissed all notifications");
+            *id
+        }
+        event => panic!("expected a pending intent, got {:?}", event),
+    }
+}
+
+// the log and its handler are global, so a single test covers them
+#[test]
+fn destructive_intents_are_committed_unless_undone() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    {
+        let events = events.clone();
+        set_intent_handler(move |event| events.lock().unwrap().push(event));
+    }
+    let committed = Arc::new(AtomicUsize::new(0));
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(
+        Notifications {
+            count: 3,
+            committed: committed.clone(),
+        },
+        (200, 40),
+        (0, 0),
+    );
+    let short = Duration::from_millis(50);
+
+    // undone within the window
+    element.queue_message(Message::DismissAll(short));
+    compositor.settle();
+    let id = pending_id(&events);
+    assert_eq!(
+        element.with_program(|p| p.count),
+        0,
+        "applied optimistically"
+    );
+    assert!(undo_last());
+    assert_eq!(
+        element.with_program(|p| p.count),
+        3,
+        "restored by the program"
+    );
+    assert_eq!(
+        events.lock().unwrap().last(),
+        Some(&IntentEvent::Undone(id))
+    );
+
+    let deadline = Instant::now() + short * 3;
+    while Instant::now() < deadline {
+        compositor.dispatch(short);
+    }
+    assert_eq!(committed.load(Ordering::SeqCst), 0);
+    assert!(!undo_last(), "nothing is pending");
+
+    // committed once the window expired
+    element.queue_message(Message::DismissAll(short));
+    compositor.settle();
+    let id = pending_id(&events);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while committed.load(Ordering::SeqCst) == 0 {
+        assert!(Instant::now() < deadline, "intent wasn't committed");
+        compositor.dispatch(short);
+    }
+    assert_eq!(
+        events.lock().unwrap().last(),
+        Some(&IntentEvent::Committed(id))
+    );
+    assert!(!undo_last());
+
+    // committed right away on shutdown
+    element.queue_message(Message::DismissAll(Duration::from_secs(60)));
+    compositor.settle();
+    commit_pending_intents();
+    assert_eq!(committed.load(Ordering::SeqCst), 2);
+    assert!(!undo_last());
+}
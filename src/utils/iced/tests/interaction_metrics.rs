@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use cosmic::{iced::widget::text, Element};
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::utils::iced::{
+    clear_interaction_metrics, disable_interaction_metrics, enable_interaction_metrics,
+    export_interaction_metrics,
+    metrics::{tag_at, HoverDwell, HOVER_DWELL},
+    test_helpers::HeadlessCompositor,
+    InteractionRegion, MetricScope, Program,
+};
+
+/// A dock with a launcher on its left half.
+struct Dock;
+
+impl Program for Dock {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Dock").into()
+    }
+
+    fn interaction_regions(&self) -> Vec<InteractionRegion> {
+        vec![InteractionRegion {
+            tag: "launcher",
+            bounds: Rectangle::from_loc_and_size((0, 0), (50, 40)),
+        }]
+    }
+}
+
+fn point(x: f64, y: f64) -> Point<f64, Logical> {
+    Point::from((x, y))
+}
+
+#[test]
+fn regions_are_found_by_location() {
+    let regions = Dock.interaction_regions();
+    assert_eq!(tag_at(&regions, point(10.0, 10.0)), Some("launcher"));
+    assert_eq!(tag_at(&regions, point(60.0, 10.0)), None);
+}
+
+#[test]
+fn hovers_count_after_dwelling() {
+    let mut dwell = HoverDwell::default();
+    assert_eq!(dwell.moved(Some("launcher")), None);
+    assert_eq!(dwell.moved(None), None, "left right away");
+
+    dwell.moved(Some("launcher"));
+    std::thread::sleep(HOVER_DWELL + Duration::from_millis(20));
+    assert_eq!(dwell.moved(Some("launcher")), None, "still inside");
+    assert_eq!(dwell.moved(None), Some("launcher"));
+}
+
+fn clicks(export: &serde_json::Value, element: &str) -> Option<u64> {
+    export["days"]
+        .as_array()?
+        .iter()
+        .flat_map(|day| day["elements"].as_array().unwrap())
+        .filter(|e| e["element"] == element)
+        .flat_map(|e| e["regions"].as_array().unwrap())
+        .find(|region| region["tag"] == "launcher")
+        .map(|region| region["clicks"].as_u64().unwrap())
+}
+
+// the metrics are global, so a single test records them
+#[test]
+fn clicks_of_named_elements_in_scope_are_counted() {
+    clear_interaction_metrics();
+    enable_interaction_metrics(MetricScope::Named(vec![
+        String::from("dock"),
+        String::from("lock-dock"),
+    ]));
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+
+    let dock = compositor.insert(Dock, (100, 40), (0, 0));
+    dock.set_name("dock");
+    compositor.pointer_enter(&dock, (10.0, 10.0));
+    compositor.click(&dock);
+    compositor.pointer_motion(&dock, (60.0, 10.0));
+    compositor.click(&dock);
+
+    let locked = compositor.insert(Dock, (100, 40), (0, 50));
+    locked.set_name("lock-dock");
+    locked.set_restricted(true);
+    compositor.pointer_enter(&locked, (10.0, 10.0));
+    compositor.click(&locked);
+
+    let unnamed = compositor.insert(Dock, (100, 40), (0, 100));
+    compositor.pointer_enter(&unnamed, (10.0, 10.0));
+    compositor.click(&unnamed);
+
+    let path = std::env::temp_dir().join(format!(
+        "cosmic-comp-interaction-metrics-{}.json",
+        std::process::id()
+    ));
+    export_interaction_metrics(&path).unwrap();
+    let export: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(export["version"], 1);
+    assert_eq!(clicks(&export, "dock"), Some(1), "outside of all regions");
+    assert_eq!(clicks(&export, "lock-dock"), None);
+
+    disable_interaction_metrics();
+    compositor.pointer_enter(&dock, (10.0, 10.0));
+    compositor.click(&dock);
+    export_interaction_metrics(&path).unwrap();
+    let export: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(clicks(&export, "dock"), Some(1), "disabled");
+    clear_interaction_metrics();
+}
@@ -0,0 +1,328 @@
+use std::time::Duration;
+
+use cosmic::{
+    iced::widget::text_input,
+    iced_native::{
+        event::{self, Event},
+        keyboard::{Event as KeyboardEvent, KeyCode, Modifiers},
+        layout, renderer,
+        widget::{self, Tree},
+        Clipboard, Command, Layout, Length, Point, Rectangle as IcedRectangle, Renderer, Shell,
+        Widget,
+    },
+    Element,
+};
+use smithay::{
+    input::keyboard::keysyms::{
+        KEY_Caps_Lock, KEY_Control_R, KEY_KP_Enter, KEY_Num_Lock, KEY_Return, KEY_Shift_L, KEY_Up,
+        KEY_a, KEY_udiaeresis, KEY_1, KEY_A, KEY_F1,
+    },
+    reexports::calloop::LoopHandle,
+};
+
+use crate::{
+    input::KeyRepeat,
+    state::Data,
+    utils::iced::{
+        keys::{keysym_to_key_code, repeats},
+        test_helpers::HeadlessCompositor,
+        FocusTarget, Program,
+    },
+};
+
+#[derive(Debug, Clone)]
+enum Message {
+    Password(String),
+}
+
+const PASSWORD: &str = "password";
+
+/// A focused text input, that is a password field if `secure`.
+struct Login {
+    password: String,
+    secure: bool,
+}
+
+impl Program for Login {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        let Message::Password(password) = message;
+        self.password = password;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text_input("Password", &self.password, Message::Password)
+            .id(text_input::Id::new(PASSWORD))
+            .into()
+    }
+
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        Some(FocusTarget::FirstFocusable)
+    }
+
+    fn secure_fields(&self) -> Vec<widget::Id> {
+        if self.secure {
+            vec![widget::Id::new(PASSWORD)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Holds `a` for a few repeats and returns the typed password.
+fn hold_a(secure: bool) -> String {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let login = Login {
+        password: String::new(),
+        secure,
+    };
+    let element = compositor.insert(login, (200, 40), (0, 0));
+    compositor.settle();
+    assert_eq!(element.is_secure_entry_active(), secure);
+
+    {
+        let mut internal = element.0.lock().unwrap();
+        internal.start_key_repeat(
+            KeyRepeat {
+                delay: 0,
+                rate: 1000,
+            },
+            0x61,
+            vec![KeyboardEvent::CharacterReceived('a')],
+        );
+    }
+    for _ in 0..3 {
+        compositor.dispatch(Duration::from_millis(5));
+    }
+    element.0.lock().unwrap().stop_key_repeat();
+    element.with_program(|p| p.password.clone())
+}
+
+#[test]
+fn held_key_repeats_typed_characters() {
+    let password = hold_a(false);
+    assert!(!password.is_empty());
+    assert!(password.chars().all(|c| c == 'a'));
+}
+
+#[test]
+fn held_key_doesnt_repeat_characters_into_secure_fields() {
+    assert_eq!(hold_a(true), "");
+}
+
+/// Publishes every key press and release it receives.
+struct KeyRecorder;
+
+impl Widget<KeyEvent, cosmic::Renderer> for KeyRecorder {
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(&self, _renderer: &cosmic::Renderer, limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(limits.max())
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        _renderer: &mut cosmic::Renderer,
+        _theme: &<cosmic::Renderer as Renderer>::Theme,
+        _style: &renderer::Style,
+        _layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &IcedRectangle,
+    ) {
+    }
+
+    fn on_event(
+        &mut self,
+        _tree: &mut Tree,
+        event: Event,
+        _layout: Layout<'_>,
+        _cursor_position: Point,
+        _renderer: &cosmic::Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, KeyEvent>,
+    ) -> event::Status {
+        match event {
+            Event::Keyboard(KeyboardEvent::KeyPressed { key_code, .. }) => {
+                shell.publish(KeyEvent::Pressed(key_code))
+            }
+            Event::Keyboard(KeyboardEvent::KeyReleased { key_code, .. }) => {
+                shell.publish(KeyEvent::Released(key_code))
+            }
+            _ => return event::Status::Ignored,
+        }
+        event::Status::Captured
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum KeyEvent {
+    Pressed(KeyCode),
+    Released(KeyCode),
+}
+
+#[derive(Default)]
+struct Keys {
+    events: Vec<KeyEvent>,
+}
+
+impl Program for Keys {
+    type Message = KeyEvent;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        self.events.push(message);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Element::new(KeyRecorder)
+    }
+}
+
+#[test]
+fn keys_held_on_enter_are_pressed_and_released_on_leave() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Keys::default(), (200, 40), (0, 0));
+    compositor.settle();
+
+    {
+        let mut internal = element.0.lock().unwrap();
+        // held twice, e.g. by two keyboards
+        assert!(internal.seed_held_keys([KEY_Up, KEY_a, KEY_Up]));
+        let _ = internal.update(true);
+    }
+    assert_eq!(
+        element.with_program(|p| p.events.clone()),
+        vec![
+            KeyEvent::Pressed(KeyCode::Up),
+            KeyEvent::Pressed(KeyCode::A)
+        ]
+    );
+
+    compositor.keyboard_leave(&element);
+    compositor.settle();
+    let mut released = element.with_program(|p| p.events[2..].to_vec());
+    released.sort_by_key(|event| format!("{:?}", event));
+    assert_eq!(
+        released,
+        vec![
+            KeyEvent::Released(KeyCode::A),
+            KeyEvent::Released(KeyCode::Up)
+        ]
+    );
+    assert!(element.0.lock().unwrap().held_keys.is_empty());
+}
+
+#[test]
+fn keys_already_held_arent_pressed_again() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Keys::default(), (200, 40), (0, 0));
+    compositor.settle();
+
+    let mut internal = element.0.lock().unwrap();
+    assert!(internal.seed_held_keys([KEY_a]));
+    assert!(!internal.seed_held_keys([KEY_a]));
+}
+
+#[test]
+fn keysyms_map_to_the_keys_of_the_layout() {
+    assert_eq!(keysym_to_key_code(KEY_a), Some(KeyCode::A));
+    assert_eq!(keysym_to_key_code(KEY_A), Some(KeyCode::A), "shifted");
+    assert_eq!(keysym_to_key_code(KEY_1), Some(KeyCode::Key1));
+    assert_eq!(keysym_to_key_code(KEY_F1), Some(KeyCode::F1));
+    assert_eq!(keysym_to_key_code(KEY_Return), Some(KeyCode::Enter));
+    assert_eq!(keysym_to_key_code(KEY_KP_Enter), Some(KeyCode::NumpadEnter));
+    assert_eq!(keysym_to_key_code(KEY_Up), Some(KeyCode::Up));
+    // typed as text only
+    assert_eq!(keysym_to_key_code(KEY_udiaeresis), None);
+}
+
+#[test]
+fn modifiers_and_locks_dont_repeat() {
+    assert!(repeats(KEY_a));
+    assert!(repeats(KEY_Up));
+    assert!(!repeats(KEY_Shift_L));
+    assert!(!repeats(KEY_Control_R));
+    assert!(!repeats(KEY_Caps_Lock));
+    assert!(!repeats(KEY_Num_Lock));
+}
+
+#[test]
+fn resizing_releases_held_keys() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Keys::default(), (200, 40), (0, 0));
+    compositor.settle();
+
+    // as if pressed via `KeyboardTarget::key`
+    element.lock().held_keys.insert(KEY_a);
+    element.resize((300, 40).into());
+    compositor.settle();
+    assert_eq!(
+        element.with_program(|p| p.events.clone()),
+        vec![KeyEvent::Released(KeyCode::A)]
+    );
+    assert!(element.0.lock().unwrap().held_keys.is_empty());
+}
+
+#[test]
+fn leaving_resets_modifiers_and_repeats() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Keys::default(), (200, 40), (0, 0));
+    compositor.settle();
+
+    {
+        let mut internal = element.lock();
+        internal.modifiers = Modifiers::SHIFT;
+        internal.held_keys.insert(KEY_Up);
+        internal.start_key_repeat(
+            KeyRepeat {
+                delay: 0,
+                rate: 1000,
+            },
+            KEY_Up,
+            vec![KeyboardEvent::KeyPressed {
+                key_code: KeyCode::Up,
+                modifiers: Modifiers::SHIFT,
+            }],
+        );
+    }
+    compositor.keyboard_leave(&element);
+    let internal = element.0.lock().unwrap();
+    assert_eq!(internal.modifiers, Modifiers::empty());
+    assert!(internal.key_repeat.is_none());
+    assert!(internal.held_keys.is_empty());
+}
+
+#[test]
+fn held_keys_without_key_codes_are_tracked_silently() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Keys::default(), (200, 40), (0, 0));
+    compositor.settle();
+
+    {
+        let mut internal = element.lock();
+        // typed as text only, there is nothing to press
+        assert!(!internal.seed_held_keys([KEY_udiaeresis]));
+        assert!(internal.held_keys.contains(&KEY_udiaeresis));
+    }
+    compositor.keyboard_leave(&element);
+    compositor.settle();
+    assert!(element.with_program(|p| p.events.is_empty()));
+    assert!(element.0.lock().unwrap().held_keys.is_empty());
+}
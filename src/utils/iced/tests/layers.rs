@@ -0,0 +1,169 @@
+use cosmic::{
+    iced::{
+        widget::{text, Column},
+        Length,
+    },
+    iced_native::{Command, Rectangle as IcedRectangle},
+    Element,
+};
+use iced_graphics::Primitive;
+use ordered_float::OrderedFloat;
+use smithay::{
+    backend::renderer::{
+        element::{memory::MemoryRenderBufferRenderElement, AsRenderElements},
+        test::DummyRenderer,
+    },
+    reexports::calloop::LoopHandle,
+    utils::{Rectangle, Scale},
+};
+
+use super::{assert_goldens, single_golden};
+use crate::{
+    state::Data,
+    utils::iced::{
+        golden::GoldenPrograms,
+        layers::{content_hash, route},
+        test_helpers::HeadlessCompositor,
+        IcedElement, LayerSpec, Program, UpdateRate,
+    },
+};
+
+#[derive(Debug, Clone)]
+struct Tick;
+
+/// A static title above a frequently changing clock, each in its own layer.
+#[derive(Default)]
+struct Clock {
+    seconds: u32,
+}
+
+impl Program for Clock {
+    type Message = Tick;
+
+    fn update(&mut self, _: Self::Message, _: &LoopHandle<'static, Data>) -> Command<Tick> {
+        self.seconds += 1;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Column::with_children(vec![
+            text("Clock").height(Length::Units(20)).into(),
+            text(format!("00:{:02}", self.seconds))
+                .height(Length::Units(20))
+                .into(),
+        ])
+        .into()
+    }
+
+    fn layers(&self) -> Vec<LayerSpec> {
+        vec![
+            layer(1, (0, 0, 200, 20), UpdateRate::Static),
+            layer(2, (0, 20, 200, 20), UpdateRate::Frequent),
+        ]
+    }
+}
+
+fn layer(id: u32, (x, y, w, h): (i32, i32, i32, i32), rate: UpdateRate) -> LayerSpec {
+    LayerSpec {
+        id,
+        bounds: Rectangle::from_loc_and_size((x, y), (w, h)),
+        rate,
+    }
+}
+
+/// An invisible primitive covering the given bounds.
+fn clip(x: f32, y: f32, width: f32, height: f32) -> Primitive {
+    Primitive::Clip {
+        bounds: IcedRectangle {
+            x,
+            y,
+            width,
+            height,
+        },
+        content: Box::new(Primitive::None),
+    }
+}
+
+fn render(element: &IcedElement<Clock>, scale: f64) -> usize {
+    let mut renderer = DummyRenderer::new();
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_elements(&mut renderer, (0, 0).into(), Scale::from(scale), 1.0);
+    elements.len()
+}
+
+fn layer_hash(element: &IcedElement<Clock>, id: u32, scale: f64) -> Option<u64> {
+    element
+        .0
+        .lock()
+        .unwrap()
+        .layer_buffers
+        .get(&(id, OrderedFloat(scale)))
+        .and_then(|layer| layer.buffer.content_hash())
+}
+
+#[test]
+fn primitives_go_to_the_first_layer_containing_them() {
+    let layers = [
+        layer(1, (0, 0, 100, 50), UpdateRate::Static),
+        layer(2, (0, 0, 200, 100), UpdateRate::Frequent),
+    ];
+    let primitives = [
+        clip(10.0, 10.0, 20.0, 20.0),
+        clip(90.0, 10.0, 20.0, 20.0),
+        clip(190.0, 90.0, 20.0, 20.0),
+        // unknown bounds
+        Primitive::None,
+    ];
+
+    assert_eq!(
+        route(&layers, &primitives),
+        vec![vec![0], vec![1], vec![2, 3]]
+    );
+}
+
+#[test]
+fn content_hash_covers_the_routed_primitives() {
+    let primitives = [clip(0.0, 0.0, 10.0, 10.0), clip(0.0, 0.0, 20.0, 20.0)];
+
+    let first = content_hash(&primitives, [0], &());
+    assert_eq!(first, content_hash(&primitives, [0], &()));
+    assert_ne!(first, content_hash(&primitives, [1], &()));
+    assert_ne!(first, content_hash(&primitives, [0], &1));
+}
+
+#[test]
+fn layers_are_rendered_above_the_base() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 2.0);
+    let element = compositor.insert(Clock::default(), (200, 40), (0, 0));
+
+    // both layers and the base
+    assert_eq!(render(&element, 2.0), 3);
+    let internal = element.0.lock().unwrap();
+    for id in [1, 2] {
+        let layer = &internal.layer_buffers[&(id, OrderedFloat(2.0))];
+        assert_eq!(layer.buffer.size(), (400, 40).into());
+    }
+}
+
+#[test]
+fn only_changed_static_layers_are_redrawn() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Clock::default(), (200, 40), (0, 0));
+    render(&element, 1.0);
+    let title = layer_hash(&element, 1, 1.0);
+    assert!(title.is_some());
+    // frequent layers are redrawn without comparing
+    assert_eq!(layer_hash(&element, 2, 1.0), None);
+
+    element.queue_message(Tick);
+    compositor.settle();
+    render(&element, 1.0);
+    assert_eq!(layer_hash(&element, 1, 1.0), title);
+}
+
+#[test]
+fn layers_golden() {
+    let mut programs = GoldenPrograms::new();
+    programs.register("layers", || Clock { seconds: 42 });
+    assert_goldens(&programs, &single_golden(1.0, (200, 40)));
+}
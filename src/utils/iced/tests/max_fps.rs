@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use cosmic::{iced::widget::text, Element};
+use ordered_float::OrderedFloat;
+use smithay::{
+    backend::renderer::{element::memory::MemoryRenderBufferRenderElement, test::DummyRenderer},
+    utils::Scale,
+};
+
+use crate::utils::iced::{
+    test_helpers::HeadlessCompositor, IcedElement, Overlaid, Program, Split, SplitDirection,
+};
+
+struct Limited(Option<u32>);
+
+impl Program for Limited {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Limited").into()
+    }
+
+    fn max_fps(&self) -> Option<u32> {
+        self.0
+    }
+}
+
+fn render(element: &IcedElement<Limited>) {
+    let mut renderer = DummyRenderer::new();
+    let _: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_elements(&mut renderer, (0, 0).into(), Scale::from(1.0), 1.0);
+}
+
+fn invalidate(element: &IcedElement<Limited>) {
+    for buffer in element.lock().buffers.values_mut() {
+        buffer.mark_dirty();
+    }
+}
+
+fn needs_redraw(element: &IcedElement<Limited>) -> bool {
+    element.0.lock().unwrap().buffers[&OrderedFloat(1.0)].needs_redraw()
+}
+
+#[test]
+fn redraws_are_skipped_until_the_next_frame_is_due() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Limited(Some(10)), (100, 50), (0, 0));
+    render(&element);
+    assert!(!needs_redraw(&element));
+
+    invalidate(&element);
+    render(&element);
+    assert!(needs_redraw(&element), "the previous buffer is shown");
+
+    std::thread::sleep(Duration::from_millis(120));
+    render(&element);
+    assert!(!needs_redraw(&element));
+}
+
+#[test]
+fn unlimited_programs_redraw_with_every_frame() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Limited(None), (100, 50), (0, 0));
+    render(&element);
+
+    invalidate(&element);
+    render(&element);
+    assert!(!needs_redraw(&element));
+}
+
+#[test]
+fn zero_fps_doesnt_limit() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Limited(Some(0)), (100, 50), (0, 0));
+    render(&element);
+
+    invalidate(&element);
+    render(&element);
+    assert!(!needs_redraw(&element));
+}
+
+#[test]
+fn last_draw_is_tracked_per_scale() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Limited(Some(1)), (100, 50), (0, 0));
+    render(&element);
+
+    let internal = element.0.lock().unwrap();
+    assert_eq!(
+        internal.last_rendered_at.keys().collect::<Vec<_>>(),
+        vec![&OrderedFloat(1.0)]
+    );
+}
+
+#[test]
+fn combinators_use_the_highest_limit() {
+    let split = Split::new(
+        Limited(Some(10)),
+        Limited(Some(30)),
+        SplitDirection::Horizontal,
+        0.5,
+    );
+    assert_eq!(split.max_fps(), Some(30));
+    let overlaid = Overlaid::new(Limited(Some(10)), Limited(None), false);
+    assert_eq!(overlaid.max_fps(), None, "unlimited if any child is");
+}
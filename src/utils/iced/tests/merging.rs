@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{test_helpers::HeadlessCompositor, IcedElement, Program},
+};
+
+#[derive(Debug, Clone)]
+struct Increment;
+
+#[derive(Default)]
+struct Counter {
+    count: usize,
+}
+
+impl Program for Counter {
+    type Message = Increment;
+
+    fn update(&mut self, _: Self::Message, _: &LoopHandle<'static, Data>) -> Command<Increment> {
+        self.count += 1;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(self.count.to_string()).into()
+    }
+}
+
+fn layouts(element: &IcedElement<Counter>) -> u64 {
+    element.0.lock().unwrap().state.layouts()
+}
+
+/// Pretends the element was rendered into a frame, that isn't presented yet.
+fn render(element: &IcedElement<Counter>) {
+    element.0.lock().unwrap().frame_tracker.rendered();
+}
+
+#[test]
+fn updates_wait_for_the_last_frame() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (100, 50), (0, 0));
+    compositor.settle();
+    let before = layouts(&element);
+
+    render(&element);
+    for _ in 0..3 {
+        element.queue_message(Increment);
+        compositor.dispatch(Duration::ZERO);
+    }
+    assert_eq!(layouts(&element), before);
+    assert_eq!(element.with_program(|p| p.count), 0);
+    assert!(element.0.lock().unwrap().update_pending);
+
+    // the whole burst is applied in a single pass, once presented
+    compositor.frame();
+    assert_eq!(layouts(&element), before + 1);
+    assert_eq!(element.with_program(|p| p.count), 3);
+    assert!(!element.0.lock().unwrap().update_pending);
+}
+
+#[test]
+fn presented_elements_update_right_away() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (100, 50), (0, 0));
+    compositor.settle();
+
+    render(&element);
+    compositor.frame();
+    element.queue_message(Increment);
+    compositor.dispatch(Duration::ZERO);
+    assert_eq!(element.with_program(|p| p.count), 1);
+    assert!(!element.0.lock().unwrap().update_pending);
+}
+
+#[test]
+fn deferred_redraws_queue_a_render_once_presented() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (100, 50), (0, 0));
+    compositor.settle();
+    let output = compositor.output().clone();
+
+    render(&element);
+    assert!(!element.0.lock().unwrap().frame_tracker.request_redraw());
+    assert!(element.0.lock().unwrap().frame_done(Some(&output)));
+    // nothing left to redraw
+    assert!(!element.0.lock().unwrap().frame_done(Some(&output)));
+}
+
+#[test]
+fn merged_updates_queue_a_render_once_presented() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (100, 50), (0, 0));
+    compositor.settle();
+    let output = compositor.output().clone();
+
+    render(&element);
+    element.queue_message(Increment);
+    compositor.dispatch(Duration::ZERO);
+    assert!(element.0.lock().unwrap().frame_done(Some(&output)));
+    assert_eq!(element.with_program(|p| p.count), 1);
+}
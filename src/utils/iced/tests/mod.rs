@@ -0,0 +1,130 @@
+//! Tests of `IcedElement`, driven through `test_helpers`.
+
+#[cfg(feature = "accessibility")]
+mod accessibility;
+mod active_output;
+mod allocations;
+mod animation;
+mod async_quota;
+mod axis;
+mod badges;
+mod batch;
+mod blending;
+mod buffer_age;
+mod buffering;
+mod builder;
+mod capabilities;
+mod clean_frames;
+mod clone_message;
+mod combinators;
+mod config;
+mod confine;
+mod consistency;
+mod content_state;
+mod critical;
+mod custom_render;
+mod debounce;
+mod decoration;
+mod dismiss;
+mod dnd;
+mod dnd_mime_types;
+mod fast_hit;
+mod focus;
+mod fonts;
+mod golden_matrix;
+mod hairlines;
+mod harness;
+mod headless;
+mod idle;
+mod input_method;
+mod input_method_surface;
+mod input_region;
+mod intents;
+#[cfg(feature = "interaction-metrics")]
+mod interaction_metrics;
+mod keys;
+mod layers;
+mod max_fps;
+mod merging;
+mod ordering;
+mod output_bounds;
+mod output_scale;
+mod pacing;
+mod palette;
+mod placement;
+mod polling;
+#[cfg(feature = "power-profiles")]
+mod power_profile;
+mod preferences;
+mod press;
+mod pressure;
+mod prewarm;
+mod program_swap;
+mod progressive;
+mod prompt;
+#[cfg(feature = "applet-sandbox")]
+mod proxy;
+mod reconfigure;
+mod redundant_updates;
+mod refresh;
+mod render_errors;
+mod render_pool;
+mod render_update;
+mod requests;
+mod sandbox;
+mod sanitize;
+mod scale;
+mod scale_mode;
+mod scroll;
+mod seat_hovers;
+mod secure;
+mod shortcuts;
+mod software_cursor;
+mod standalone;
+mod subscriptions;
+mod tablet_pad;
+mod telemetry;
+mod texture_reuse;
+mod theme;
+mod touch;
+mod traces;
+mod transitions;
+mod truncation;
+mod upload;
+mod virtual_targets;
+mod wakeup;
+mod window_feed;
+mod z_index;
+
+use cosmic::Theme;
+use smithay::utils::Size;
+
+use super::golden::{GoldenMatrix, GoldenPrograms};
+
+/// Goldens of the tests, see `assert_goldens`.
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/utils/iced/tests/goldens");
+
+/// A matrix of a single entry, the dark theme at `scale` and `size`.
+fn single_golden(scale: f64, size: (i32, i32)) -> GoldenMatrix {
+    GoldenMatrix {
+        themes: vec![("dark", Theme::dark())],
+        scales: vec![scale],
+        sizes: vec![Size::from(size)],
+        ..GoldenMatrix::default()
+    }
+}
+
+/// Checks `programs` against their goldens in `GOLDEN_DIR`.
+///
+/// Missing goldens fail the test, bless them (see `golden::BLESS_VAR`), review and commit them.
+/// Differences are written next to each other into the temporary directory.
+fn assert_goldens(programs: &GoldenPrograms, matrix: &GoldenMatrix) {
+    let diff_dir = std::env::temp_dir().join("cosmic-comp-golden-diffs");
+    let failures = programs.check(matrix, GOLDEN_DIR, &diff_dir);
+    assert!(
+        failures.is_empty(),
+        "Goldens failed, differences are in {}: {:#?}",
+        diff_dir.display(),
+        failures
+    );
+}
@@ -0,0 +1,112 @@
+use cosmic::{
+    iced::{
+        widget::{button, horizontal_space, text, Row},
+        Length,
+    },
+    iced_native::Command,
+    Element,
+};
+use smithay::{backend::input::ButtonState, reexports::calloop::LoopHandle};
+
+use crate::{
+    state::Data,
+    utils::iced::{test_helpers::HeadlessCompositor, OrderingCorrection, Program},
+};
+
+#[derive(Debug, Clone)]
+struct Pressed;
+
+#[derive(Default)]
+struct Button {
+    presses: usize,
+    /// Only covers the right half of the element
+    right_half: bool,
+}
+
+impl Program for Button {
+    type Message = Pressed;
+
+    fn update(&mut self, _: Self::Message, _: &LoopHandle<'static, Data>) -> Command<Pressed> {
+        self.presses += 1;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        let button = button(text("Press"))
+            .on_press(Pressed)
+            .width(Length::Fill)
+            .height(Length::Fill);
+        if self.right_half {
+            Row::with_children(vec![horizontal_space(Length::Fill).into(), button.into()]).into()
+        } else {
+            button.into()
+        }
+    }
+}
+
+#[test]
+fn click_without_enter_is_corrected() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Button::default(), (100, 50), (0, 0));
+    compositor.settle();
+
+    compositor.click(&element);
+    compositor.settle();
+
+    assert_eq!(element.ordering_corrections(), 1);
+    assert_eq!(element.with_program(|p| p.presses), 1);
+}
+
+#[test]
+fn click_after_enter_isnt_corrected() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Button::default(), (100, 50), (0, 0));
+    compositor.settle();
+
+    compositor.pointer_enter(&element, (10.0, 10.0));
+    compositor.click(&element);
+    compositor.settle();
+
+    assert_eq!(element.ordering_corrections(), 0);
+    assert_eq!(element.with_program(|p| p.presses), 1);
+}
+
+#[test]
+fn deferred_click_is_delivered_with_the_next_motion() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Button::default(), (100, 50), (0, 0));
+    element.set_ordering_correction(OrderingCorrection::Defer);
+    compositor.settle();
+
+    compositor.pointer_button(&element, 0x110, ButtonState::Pressed);
+    compositor.pointer_button(&element, 0x110, ButtonState::Released);
+    compositor.settle();
+    assert_eq!(element.with_program(|p| p.presses), 0);
+
+    compositor.pointer_motion(&element, (10.0, 10.0));
+    compositor.settle();
+    assert_eq!(element.with_program(|p| p.presses), 1);
+}
+
+#[test]
+fn click_without_enter_lands_at_the_hinted_position() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let program = Button {
+        right_half: true,
+        ..Button::default()
+    };
+    let element = compositor.insert(program, (200, 50), (0, 0));
+    compositor.settle();
+
+    // the seat's pointer is still at the origin, left of the button
+    compositor.click(&element);
+    compositor.settle();
+    assert_eq!(element.with_program(|p| p.presses), 0);
+
+    compositor.pointer_leave(&element);
+    element.pointer_position_hint(compositor.seat(), (150.0, 25.0).into());
+    compositor.click(&element);
+    compositor.settle();
+    assert_eq!(element.ordering_corrections(), 2);
+    assert_eq!(element.with_program(|p| p.presses), 1);
+}
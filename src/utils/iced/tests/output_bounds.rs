@@ -0,0 +1,73 @@
+use smithay::{
+    output::{Mode, Output, PhysicalProperties, Subpixel},
+    reexports::calloop::EventLoop,
+    utils::Rectangle,
+};
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        test_helpers::{HeadlessCompositor, Label},
+        IcedElement,
+    },
+};
+
+/// An output of 1920x1080 at `location`, not known to the compositor.
+fn output_at(location: (i32, i32)) -> Output {
+    let output = Output::new(
+        String::from("TEST-2"),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: String::from("COSMIC"),
+            model: String::from("Test"),
+        },
+    );
+    let mode = Mode {
+        size: (1920, 1080).into(),
+        refresh: 60_000,
+    };
+    output.add_mode(mode);
+    output.change_current_state(Some(mode), None, None, Some(location.into()));
+    output
+}
+
+#[test]
+fn bounding_box_is_local_to_the_output() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (30, 20));
+
+    assert_eq!(
+        element.bounding_box_in_output(compositor.output()),
+        Rectangle::from_loc_and_size((30, 20), (100, 40))
+    );
+    // derived from the global location for outputs the element isn't on
+    assert_eq!(
+        element.bounding_box_in_output(&output_at((-1920, 0))),
+        Rectangle::from_loc_and_size((1950, 20), (100, 40))
+    );
+}
+
+#[test]
+fn output_offsets_take_precedence() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (30, 20));
+    let other = output_at((400, 0));
+
+    element.set_output_offset(&other, (0, 1040).into());
+    assert_eq!(
+        element.bounding_box_in_output(&other),
+        Rectangle::from_loc_and_size((0, 1040), (100, 40))
+    );
+}
+
+#[test]
+fn unlocated_elements_are_at_the_global_origin() {
+    let event_loop = EventLoop::<Data>::try_new().unwrap();
+    let element = IcedElement::new(Label, (100, 40), event_loop.handle());
+
+    assert_eq!(
+        element.bounding_box_in_output(&output_at((400, 0))),
+        Rectangle::from_loc_and_size((-400, 0), (100, 40))
+    );
+}
@@ -0,0 +1,70 @@
+use smithay::output::{Mode, Output, PhysicalProperties, Scale, Subpixel};
+
+use crate::utils::iced::{
+    reconfigure_output,
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement,
+};
+
+fn set_scale(output: &Output, scale: f64) {
+    output.change_current_state(None, None, Some(Scale::Fractional(scale)), None);
+    reconfigure_output(output);
+}
+
+fn scales(element: &IcedElement<Label>) -> Vec<f64> {
+    let internal = element.0.lock().unwrap();
+    let mut scales = internal
+        .buffers
+        .keys()
+        .map(|scale| scale.0)
+        .collect::<Vec<_>>();
+    scales.sort_by(f64::total_cmp);
+    scales
+}
+
+#[test]
+fn new_scale_is_ready_before_the_next_frame() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+
+    set_scale(compositor.output(), 1.25);
+    assert_eq!(scales(&element), vec![1.25]);
+    let internal = element.0.lock().unwrap();
+    let buffer = &internal.buffers.values().next().unwrap();
+    assert_eq!(buffer.size(), (125, 63).into());
+    assert!(buffer.needs_redraw());
+}
+
+#[test]
+fn snapshot_follows_the_new_scale() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+
+    set_scale(compositor.output(), 2.0);
+    assert_eq!(compositor.snapshot(&element).size, (200, 100).into());
+}
+
+#[test]
+fn elements_on_other_outputs_are_untouched() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+
+    let other = Output::new(
+        String::from("TEST-2"),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: String::from("COSMIC"),
+            model: String::from("Test"),
+        },
+    );
+    let mode = Mode {
+        size: (1920, 1080).into(),
+        refresh: 60_000,
+    };
+    other.add_mode(mode);
+    other.change_current_state(Some(mode), None, None, None);
+    set_scale(&other, 3.0);
+
+    assert_eq!(scales(&element), vec![1.0]);
+}
@@ -0,0 +1,101 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{frame_done, registry, test_helpers::HeadlessCompositor, IcedElement, Program},
+};
+
+#[derive(Debug, Clone)]
+struct Increment;
+
+#[derive(Default)]
+struct Counter {
+    count: usize,
+}
+
+impl Program for Counter {
+    type Message = Increment;
+
+    fn update(&mut self, _: Self::Message, _: &LoopHandle<'static, Data>) -> Command<Increment> {
+        self.count += 1;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(self.count.to_string()).into()
+    }
+}
+
+fn layouts(element: &IcedElement<Counter>) -> u64 {
+    element.0.lock().unwrap().state.layouts()
+}
+
+/// Layout passes needed to deliver `messages` to a shown element at once.
+fn layouts_for(
+    compositor: &mut HeadlessCompositor<Counter>,
+    element: &IcedElement<Counter>,
+    messages: usize,
+) -> u64 {
+    let before = layouts(element);
+    for _ in 0..messages {
+        element.queue_message(Increment);
+    }
+    compositor.settle();
+    layouts(element) - before
+}
+
+#[test]
+fn burst_of_messages_is_laid_out_once() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (100, 50), (0, 0));
+    compositor.settle();
+
+    let single = layouts_for(&mut compositor, &element, 1);
+    let burst = layouts_for(&mut compositor, &element, 10);
+    assert_eq!(burst, single, "a burst costs as much as a single message");
+    assert_eq!(element.with_program(|p| p.count), 11);
+}
+
+#[test]
+fn burst_of_messages_is_synced_once() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (100, 50), (0, 0));
+    compositor.settle();
+
+    for _ in 0..10 {
+        element.queue_message(Increment);
+    }
+    assert!(
+        !element.0.lock().unwrap().sync_pending,
+        "nothing is laid out before the event loop is dispatched"
+    );
+    compositor.dispatch(std::time::Duration::ZERO);
+    assert!(
+        element.0.lock().unwrap().sync_pending,
+        "shown elements catch up with the program once per frame"
+    );
+    compositor.frame();
+    assert!(!element.0.lock().unwrap().sync_pending);
+}
+
+#[test]
+fn frames_reach_the_elements_on_their_output() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let shown = compositor.insert(Counter::default(), (100, 50), (0, 0));
+    let removed = compositor.insert(Counter::default(), (100, 50), (0, 100));
+    compositor.settle();
+    assert_eq!(registry::elements_on(compositor.output()).len(), 2);
+
+    compositor.remove(&removed);
+    assert_eq!(registry::elements_on(compositor.output()).len(), 1);
+
+    shown.queue_message(Increment);
+    compositor.dispatch(std::time::Duration::ZERO);
+    assert!(shown.0.lock().unwrap().sync_pending);
+    frame_done(compositor.output());
+    assert!(!shown.0.lock().unwrap().sync_pending);
+
+    compositor.remove(&shown);
+    assert!(registry::elements_on(compositor.output()).is_empty());
+}
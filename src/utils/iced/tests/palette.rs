@@ -0,0 +1,73 @@
+use cosmic::{iced::widget::text, Element, Theme};
+use iced_softbuffer::native::raqote::{DrawOptions, DrawTarget, SolidSource, Source};
+
+use crate::utils::iced::{test_helpers::IcedElementTestHarness, HookPalette, PaletteRole, Program};
+
+/// Fills its background with the accent color of its theme.
+struct Accented;
+
+impl Program for Accented {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("").into()
+    }
+
+    fn themed_background(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        let (w, h) = (target.width() as f32, target.height() as f32);
+        target.fill_rect(
+            0.0,
+            0.0,
+            w,
+            h,
+            &palette.source(PaletteRole::Accent),
+            &DrawOptions::new(),
+        );
+    }
+
+    fn uses_hooks(&self) -> bool {
+        true
+    }
+}
+
+fn argb(source: SolidSource) -> u32 {
+    u32::from_be_bytes([source.a, source.r, source.g, source.b])
+}
+
+#[test]
+fn palettes_follow_their_theme() {
+    let dark = HookPalette::new(&Theme::dark());
+    assert_eq!(dark, HookPalette::new(&Theme::dark()));
+    assert_ne!(dark, HookPalette::new(&Theme::light()));
+    assert_ne!(
+        dark.color(PaletteRole::Background),
+        dark.color(PaletteRole::Accent)
+    );
+}
+
+#[test]
+fn alpha_variants_are_premultiplied() {
+    let palette = HookPalette::new(&Theme::dark());
+    let solid = palette.solid(PaletteRole::Error);
+    assert_eq!(solid.a, 255);
+    let Source::Solid(half) = palette.with_alpha(PaletteRole::Error, 0.5) else {
+        panic!("not a solid source");
+    };
+    assert_eq!(half.a, 128);
+    assert!(half.r <= solid.r / 2 + 1 && half.r + 1 >= solid.r / 2);
+    let Source::Solid(none) = palette.with_alpha(PaletteRole::Error, 0.0) else {
+        panic!("not a solid source");
+    };
+    assert_eq!(argb(none), 0);
+}
+
+#[test]
+fn hooks_draw_with_the_current_theme() {
+    let harness = IcedElementTestHarness::new(Accented, (20, 20));
+    let dark = HookPalette::new(&Theme::dark()).solid(PaletteRole::Accent);
+    assert_eq!(harness.snapshot(1.0).pixel(10, 10), Some(argb(dark)));
+
+    harness.set_theme(Theme::light());
+    let light = HookPalette::new(&Theme::light()).solid(PaletteRole::Accent);
+    assert_eq!(harness.snapshot(1.0).pixel(10, 10), Some(argb(light)));
+}
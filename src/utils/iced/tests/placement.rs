@@ -0,0 +1,151 @@
+use smithay::utils::{Logical, Rectangle};
+
+use crate::utils::iced::{
+    place_transient,
+    test_helpers::{IcedElementTestHarness, Label},
+    Anchor, AvoidSet, PlacementPrefs,
+};
+
+fn prefs(anchors: Vec<Anchor>, avoid: AvoidSet) -> PlacementPrefs {
+    PlacementPrefs {
+        area: Rectangle::from_loc_and_size((0, 0), (1000, 800)),
+        anchor_candidates: anchors,
+        avoid,
+        step: 50,
+    }
+}
+
+fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size((x, y), (w, h))
+}
+
+#[test]
+fn unobstructed_transient_is_placed_at_its_anchor() {
+    let prefs = prefs(vec![Anchor::Bottom], AvoidSet::default());
+    assert_eq!(
+        place_transient((200, 100).into(), &prefs),
+        (400, 700).into()
+    );
+}
+
+#[test]
+fn transient_moves_away_from_its_edge_to_avoid_the_caret() {
+    let avoid = AvoidSet {
+        caret: Some(rect(450, 720, 2, 20)),
+        ..Default::default()
+    };
+    let prefs = prefs(vec![Anchor::Bottom], avoid);
+    assert_eq!(
+        place_transient((200, 100).into(), &prefs),
+        (400, 600).into()
+    );
+}
+
+#[test]
+fn transients_flow_around_transients_already_shown() {
+    let shown = place_transient(
+        (200, 100).into(),
+        &prefs(vec![Anchor::TopRight], AvoidSet::default()),
+    );
+    let avoid = AvoidSet {
+        other: vec![Rectangle::from_loc_and_size(shown, (200, 100))],
+        ..Default::default()
+    };
+    let second = place_transient((200, 100).into(), &prefs(vec![Anchor::TopRight], avoid));
+    assert_eq!(shown, (800, 0).into());
+    assert_eq!(second, (800, 100).into());
+}
+
+#[test]
+fn later_anchor_is_used_if_the_preferred_one_is_covered() {
+    let avoid = AvoidSet {
+        focused_window: Some(rect(300, 0, 400, 800)),
+        ..Default::default()
+    };
+    let prefs = prefs(vec![Anchor::Top, Anchor::TopRight], avoid);
+    assert_eq!(place_transient((200, 100).into(), &prefs), (800, 0).into());
+}
+
+#[test]
+fn least_overlapping_position_is_used_if_nothing_is_free() {
+    let avoid = AvoidSet {
+        focused_window: Some(rect(0, 0, 1000, 800)),
+        other: vec![rect(0, 0, 1000, 100)],
+        ..Default::default()
+    };
+    let prefs = prefs(vec![Anchor::Top], avoid);
+    assert_eq!(
+        place_transient((200, 100).into(), &prefs),
+        (400, 100).into()
+    );
+}
+
+fn disjoint(a: Rectangle<i32, Logical>, b: Rectangle<i32, Logical>) -> bool {
+    a.loc.x + a.size.w <= b.loc.x
+        || b.loc.x + b.size.w <= a.loc.x
+        || a.loc.y + a.size.h <= b.loc.y
+        || b.loc.y + b.size.h <= a.loc.y
+}
+
+/// Prefers the top right corner of an area at `x`, avoiding the reservations of other
+/// transients. Tests reserving areas use distinct `x`, as reservations are process-wide.
+fn reserving_prefs(x: i32) -> PlacementPrefs {
+    PlacementPrefs {
+        area: Rectangle::from_loc_and_size((x, 0), (1000, 800)),
+        anchor_candidates: vec![Anchor::TopRight],
+        avoid: AvoidSet {
+            transients: true,
+            ..Default::default()
+        },
+        step: 50,
+    }
+}
+
+#[test]
+fn placed_transients_dont_overlap() {
+    let prefs = reserving_prefs(10_000);
+    let transients = (0..3)
+        .map(|_| IcedElementTestHarness::new(Label, (200, 100)))
+        .collect::<Vec<_>>();
+    let rects = transients
+        .iter()
+        .map(|t| Rectangle::from_loc_and_size(t.element().place_transient(&prefs), (200, 100)))
+        .collect::<Vec<_>>();
+
+    assert_eq!(rects[0].loc, (10_800, 0).into());
+    for (i, a) in rects.iter().enumerate() {
+        for b in &rects[i + 1..] {
+            assert!(disjoint(*a, *b), "{:?} overlaps {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn closing_a_transient_frees_its_slot() {
+    let prefs = reserving_prefs(20_000);
+    let first = IcedElementTestHarness::new(Label, (200, 100));
+    let second = IcedElementTestHarness::new(Label, (200, 100));
+    assert_eq!(first.element().place_transient(&prefs), (20_800, 0).into());
+    assert_eq!(
+        second.element().place_transient(&prefs),
+        (20_800, 100).into()
+    );
+
+    drop(first);
+    let next = IcedElementTestHarness::new(Label, (200, 100));
+    assert_eq!(next.element().place_transient(&prefs), (20_800, 0).into());
+}
+
+#[test]
+fn reservations_follow_resizes() {
+    let prefs = reserving_prefs(30_000);
+    let first = IcedElementTestHarness::new(Label, (200, 100));
+    assert_eq!(first.element().place_transient(&prefs), (30_800, 0).into());
+    first.element().resize((200, 200).into());
+
+    let second = IcedElementTestHarness::new(Label, (200, 100));
+    assert_eq!(
+        second.element().place_transient(&prefs),
+        (30_800, 200).into()
+    );
+}
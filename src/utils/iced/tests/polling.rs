@@ -0,0 +1,88 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use cosmic::{iced::widget::text, Element};
+
+use crate::utils::iced::{
+    test_helpers::HeadlessCompositor, IcedElement, Overlaid, Program, Split, SplitDirection,
+};
+
+/// Polls at an interval, that can be changed from the outside.
+#[derive(Clone, Default)]
+struct Poller {
+    interval: Arc<Mutex<Option<Duration>>>,
+}
+
+impl Poller {
+    fn every(interval: Option<Duration>) -> Poller {
+        Poller {
+            interval: Arc::new(Mutex::new(interval)),
+        }
+    }
+}
+
+impl Program for Poller {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Poller").into()
+    }
+
+    fn update_interval(&self) -> Option<Duration> {
+        *self.interval.lock().unwrap()
+    }
+}
+
+fn layouts(element: &IcedElement<Poller>) -> u64 {
+    element.0.lock().unwrap().state.layouts()
+}
+
+fn run_for(compositor: &mut HeadlessCompositor<Poller>, duration: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        compositor.frame();
+        compositor.dispatch(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn polling_programs_are_updated_periodically_until_they_stop() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let poller = Poller::every(Some(Duration::from_millis(10)));
+    let element = compositor.insert(poller.clone(), (100, 40), (0, 0));
+    assert!(element.0.lock().unwrap().update_timer.is_some());
+    compositor.settle();
+
+    let before = layouts(&element);
+    run_for(&mut compositor, Duration::from_millis(100));
+    assert!(layouts(&element) > before);
+
+    *poller.interval.lock().unwrap() = None;
+    run_for(&mut compositor, Duration::from_millis(30));
+    assert!(element.0.lock().unwrap().update_timer.is_none());
+    let stopped = layouts(&element);
+    run_for(&mut compositor, Duration::from_millis(50));
+    assert_eq!(layouts(&element), stopped);
+}
+
+#[test]
+fn programs_without_interval_have_no_timer() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Poller::every(None), (100, 40), (0, 0));
+    assert!(element.0.lock().unwrap().update_timer.is_none());
+}
+
+#[test]
+fn combinators_poll_at_the_shortest_interval() {
+    let fast = || Poller::every(Some(Duration::from_millis(10)));
+    let slow = || Poller::every(Some(Duration::from_secs(1)));
+
+    let split = Split::new(slow(), fast(), SplitDirection::Horizontal, 0.5);
+    assert_eq!(split.update_interval(), Some(Duration::from_millis(10)));
+    let overlaid = Overlaid::new(Poller::every(None), slow(), false);
+    assert_eq!(overlaid.update_interval(), Some(Duration::from_secs(1)));
+    let neither = Overlaid::new(Poller::every(None), Poller::every(None), false);
+    assert_eq!(neither.update_interval(), None);
+}
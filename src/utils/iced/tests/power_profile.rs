@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use cosmic::{
+    iced::widget::text,
+    iced_native::{futures::stream, Command},
+    Element,
+};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        power_profile::parse_event, test_helpers::HeadlessCompositor, PowerProfile,
+        PowerProfileEvent, Program,
+    },
+};
+
+#[test]
+fn profiles_round_trip_through_their_bus_names() {
+    for profile in [
+        PowerProfile::PowerSaver,
+        PowerProfile::Balanced,
+        PowerProfile::Performance,
+    ] {
+        assert_eq!(profile.as_str().parse(), Ok(profile));
+    }
+}
+
+#[test]
+fn unknown_profiles_are_skipped() {
+    assert_eq!(
+        parse_event("performance"),
+        Some(PowerProfileEvent::ActiveProfile(PowerProfile::Performance))
+    );
+    assert_eq!(parse_event("turbo"), None);
+    assert_eq!(parse_event(""), None);
+}
+
+#[derive(Default)]
+struct Profiles {
+    seen: Vec<PowerProfile>,
+}
+
+impl Program for Profiles {
+    type Message = PowerProfileEvent;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        let PowerProfileEvent::ActiveProfile(profile) = message;
+        self.seen.push(profile);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(format!("{:?}", self.seen.last())).into()
+    }
+}
+
+#[test]
+fn attached_events_reach_the_program() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Profiles::default(), (200, 40), (0, 0));
+    compositor.settle();
+
+    element.attach_stream(stream::iter(
+        ["balanced", "power-saver", "turbo"]
+            .into_iter()
+            .filter_map(parse_event),
+    ));
+    for _ in 0..3 {
+        compositor.dispatch(Duration::from_millis(5));
+    }
+    compositor.settle();
+
+    assert_eq!(element.0.lock().unwrap().attached_sources.len(), 1);
+    assert_eq!(
+        element.with_program(|p| p.seen.clone()),
+        vec![PowerProfile::Balanced, PowerProfile::PowerSaver]
+    );
+}
@@ -0,0 +1,205 @@
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use iced_softbuffer::native::raqote::DrawTarget;
+use smithay::{
+    backend::renderer::{element::memory::MemoryRenderBufferRenderElement, test::DummyRenderer},
+    reexports::calloop::LoopHandle,
+    utils::Scale,
+};
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        begin_global_changes, commit_global_changes, set_default_animation_policy,
+        set_default_contrast, set_default_format, set_default_text_scale,
+        test_helpers::HeadlessCompositor, AnimationPolicy, Contrast, FormatConfig, IcedElement,
+        Preferences, Program,
+    },
+};
+
+/// Shows its text at the preferred scale, counting updates, views and rasterizations.
+#[derive(Default)]
+struct Scaled {
+    preferences: Preferences,
+    /// Ignores the preferences, like programs with fixed-size text
+    fixed: bool,
+    updates: AtomicUsize,
+    views: AtomicUsize,
+    draws: AtomicUsize,
+}
+
+impl Scaled {
+    fn fixed() -> Scaled {
+        Scaled {
+            fixed: true,
+            ..Scaled::default()
+        }
+    }
+}
+
+impl Program for Scaled {
+    type Message = Preferences;
+
+    fn update(
+        &mut self,
+        preferences: Preferences,
+        _: &LoopHandle<'static, Data>,
+    ) -> Command<Preferences> {
+        self.updates.fetch_add(1, Ordering::SeqCst);
+        self.preferences = preferences;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        self.views.fetch_add(1, Ordering::SeqCst);
+        let size = (16.0 * self.preferences.text_scale) as u16;
+        let clock = if self.preferences.format.clock_24h {
+            "13:00"
+        } else {
+            "1:00 PM"
+        };
+        text(format!("{:?} {}", self.preferences.contrast, clock))
+            .size(size)
+            .into()
+    }
+
+    fn background(&self, _: &mut DrawTarget<&mut [u32]>) {
+        self.draws.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn preferences_changed(&self, preferences: &Preferences) -> Option<Self::Message> {
+        (!self.fixed).then(|| preferences.clone())
+    }
+}
+
+/// Counts of (updates, views, rasterizations) since the last call.
+fn take_counts(element: &IcedElement<Scaled>) -> (usize, usize, usize) {
+    element.with_program(|program| {
+        (
+            program.updates.swap(0, Ordering::SeqCst),
+            program.views.swap(0, Ordering::SeqCst),
+            program.draws.swap(0, Ordering::SeqCst),
+        )
+    })
+}
+
+/// Applies pending updates and renders every element once.
+fn render(compositor: &mut HeadlessCompositor<Scaled>, elements: &[IcedElement<Scaled>]) {
+    compositor.settle();
+    let mut renderer = DummyRenderer::new();
+    for element in elements {
+        let _: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+            element.render_elements(&mut renderer, (0, 0).into(), Scale::from(1.0), 1.0);
+    }
+}
+
+fn apply_profile() {
+    set_default_text_scale(1.5);
+    set_default_animation_policy(AnimationPolicy::Reduced);
+    set_default_contrast(Contrast::High);
+    set_default_format(FormatConfig {
+        locale: None,
+        clock_24h: true,
+    });
+    // the last change of a preference wins
+    set_default_text_scale(1.25);
+}
+
+fn reset_profile() {
+    set_default_text_scale(1.0);
+    set_default_animation_policy(AnimationPolicy::default());
+    set_default_contrast(Contrast::default());
+    set_default_format(FormatConfig::default());
+}
+
+// Changes the global preferences, which other tests don't react to, so they may run in parallel.
+#[test]
+fn batched_preferences_are_applied_at_once() {
+    let mut compositor = HeadlessCompositor::new((1000, 200), 1.0);
+    let elements = (0..10)
+        .map(|i| {
+            let program = if i % 2 == 0 {
+                Scaled::default()
+            } else {
+                Scaled::fixed()
+            };
+            compositor.insert(program, (100, 40), (i * 100, 0))
+        })
+        .collect::<Vec<_>>();
+    let (following, fixed): (Vec<_>, Vec<_>) = elements
+        .iter()
+        .partition(|element| !element.with_program(|p| p.fixed));
+    render(&mut compositor, &elements);
+
+    // the cost of a single change, to compare the batch against
+    take_counts(following[0]);
+    set_default_contrast(Contrast::High);
+    render(&mut compositor, &elements);
+    let single = take_counts(following[0]);
+    assert_eq!(single.0, 1);
+    assert_eq!(single.2, 1);
+    set_default_contrast(Contrast::Normal);
+    render(&mut compositor, &elements);
+    for element in &elements {
+        take_counts(element);
+    }
+
+    let changes = begin_global_changes();
+    apply_profile();
+    compositor.settle();
+    for element in &following {
+        assert_eq!(
+            element.with_program(|p| p.preferences.clone()),
+            Preferences::default(),
+            "changes are held back until committed"
+        );
+    }
+    commit_global_changes(changes);
+    render(&mut compositor, &elements);
+
+    let expected = Preferences {
+        text_scale: 1.25,
+        animations: AnimationPolicy::Reduced,
+        contrast: Contrast::High,
+        format: FormatConfig {
+            locale: None,
+            clock_24h: true,
+        },
+    };
+    for element in &following {
+        assert_eq!(element.with_program(|p| p.preferences.clone()), expected);
+        assert_eq!(take_counts(element), single);
+    }
+    for element in &fixed {
+        assert_eq!(take_counts(element), (0, 0, 0));
+    }
+    let batched = compositor.snapshot(following[0]);
+
+    // the individual path ends up looking the same
+    reset_profile();
+    render(&mut compositor, &elements);
+    take_counts(following[0]);
+    apply_profile();
+    render(&mut compositor, &elements);
+    assert_eq!(take_counts(following[0]).0, 5);
+    assert_eq!(compositor.snapshot(following[0]), batched);
+
+    // a panic within the batch still commits it
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _changes = begin_global_changes();
+        set_default_contrast(Contrast::Normal);
+        panic!("failed to apply the profile");
+    }));
+    assert!(result.is_err());
+    render(&mut compositor, &elements);
+    assert_eq!(
+        following[0].with_program(|p| p.preferences.contrast),
+        Contrast::Normal
+    );
+
+    reset_profile();
+}
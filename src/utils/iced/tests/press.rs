@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use cosmic::{iced::widget::text, Element, Theme};
+use iced_softbuffer::native::raqote::DrawTarget;
+use smithay::{backend::input::ButtonState, utils::Rectangle};
+
+use crate::utils::iced::{
+    press::{draw_press_feedback, PressFeedback},
+    test_helpers::HeadlessCompositor,
+    IcedElement, Program,
+};
+
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+
+/// A label with a single press region in its top left corner.
+struct Pressable {
+    optimistic: bool,
+}
+
+impl Program for Pressable {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Press").into()
+    }
+
+    fn press_regions(&self) -> Vec<Rectangle<i32, smithay::utils::Logical>> {
+        vec![Rectangle::from_loc_and_size((0, 0), (50, 20))]
+    }
+
+    fn optimistic_feedback(&self) -> bool {
+        self.optimistic
+    }
+}
+
+fn feedback(element: &IcedElement<Pressable>) -> Option<PressFeedback> {
+    element.0.lock().unwrap().press_feedback
+}
+
+fn setup(optimistic: bool) -> (HeadlessCompositor<Pressable>, IcedElement<Pressable>) {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Pressable { optimistic }, (100, 40), (0, 0));
+    (compositor, element)
+}
+
+#[test]
+fn left_press_on_a_region_starts_feedback() {
+    let (mut compositor, element) = setup(true);
+
+    compositor.pointer_enter(&element, (10.0, 10.0));
+    compositor.pointer_button(&element, BTN_LEFT, ButtonState::Pressed);
+    let pressed = feedback(&element).expect("No press feedback");
+    assert_eq!(
+        pressed.bounds,
+        Rectangle::from_loc_and_size((0, 0), (50, 20))
+    );
+    assert!(!pressed.is_fading());
+
+    compositor.pointer_button(&element, BTN_LEFT, ButtonState::Released);
+    assert!(feedback(&element).unwrap().is_fading());
+}
+
+#[test]
+fn presses_elsewhere_show_no_feedback() {
+    let (mut compositor, element) = setup(true);
+
+    compositor.pointer_enter(&element, (80.0, 30.0));
+    compositor.pointer_button(&element, BTN_LEFT, ButtonState::Pressed);
+    assert!(feedback(&element).is_none());
+    compositor.pointer_button(&element, BTN_LEFT, ButtonState::Released);
+
+    compositor.pointer_motion(&element, (10.0, 10.0));
+    compositor.pointer_button(&element, BTN_RIGHT, ButtonState::Pressed);
+    assert!(feedback(&element).is_none());
+}
+
+#[test]
+fn programs_can_opt_out() {
+    let (mut compositor, element) = setup(false);
+
+    compositor.pointer_enter(&element, (10.0, 10.0));
+    compositor.pointer_button(&element, BTN_LEFT, ButtonState::Pressed);
+    assert!(feedback(&element).is_none());
+}
+
+#[test]
+fn feedback_fades_out() {
+    let mut feedback = PressFeedback::new(Rectangle::from_loc_and_size((0, 0), (10, 10)));
+    let start = Instant::now();
+    assert_eq!(feedback.alpha(start), Some(1.0));
+
+    feedback.fade();
+    let alpha = feedback
+        .alpha(Instant::now() + Duration::from_millis(25))
+        .unwrap();
+    assert!(alpha > 0.0 && alpha < 1.0);
+    assert_eq!(
+        feedback.alpha(Instant::now() + Duration::from_millis(60)),
+        None
+    );
+}
+
+#[test]
+fn feedback_only_tints_its_bounds() {
+    let feedback = PressFeedback::new(Rectangle::from_loc_and_size((0, 0), (5, 5)));
+    let mut pixels = vec![0u32; 20 * 20];
+    let mut target = DrawTarget::from_backing(20, 20, &mut pixels[..]);
+    draw_press_feedback(&mut target, &feedback, 1.0, 2.0, &Theme::dark());
+    drop(target);
+
+    // bounds are scaled with the target
+    assert_ne!(pixels[9 * 20 + 9], 0);
+    assert_eq!(pixels[10 * 20 + 10], 0);
+    assert_eq!(pixels[19 * 20 + 19], 0);
+}
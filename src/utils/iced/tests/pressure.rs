@@ -0,0 +1,28 @@
+use crate::utils::iced::{pressure::parse_pressure, MemoryPressureLevel};
+
+#[test]
+fn no_stalls_are_no_pressure() {
+    let content = "some avg10=0.00 avg60=0.00 avg300=0.00 total=1234\n\
+                   full avg10=0.00 avg60=0.00 avg300=0.00 total=567\n";
+    assert_eq!(parse_pressure(content), None);
+}
+
+#[test]
+fn some_stalls_are_moderate_pressure() {
+    let content = "some avg10=12.50 avg60=3.00 avg300=1.00 total=1234\n\
+                   full avg10=1.00 avg60=0.20 avg300=0.00 total=567\n";
+    assert_eq!(parse_pressure(content), Some(MemoryPressureLevel::Moderate));
+}
+
+#[test]
+fn full_stalls_are_critical_pressure() {
+    let content = "some avg10=40.00 avg60=20.00 avg300=5.00 total=1234\n\
+                   full avg10=8.00 avg60=2.00 avg300=0.50 total=567\n";
+    assert_eq!(parse_pressure(content), Some(MemoryPressureLevel::Critical));
+}
+
+#[test]
+fn malformed_content_is_no_pressure() {
+    assert_eq!(parse_pressure(""), None);
+    assert_eq!(parse_pressure("some avg10=lots\n"), None);
+}
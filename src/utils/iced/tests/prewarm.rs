@@ -0,0 +1,62 @@
+use ordered_float::OrderedFloat;
+use smithay::{
+    desktop::space::SpaceElement,
+    output::{Mode, Output, PhysicalProperties, Scale, Subpixel},
+};
+
+use crate::utils::iced::{
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement,
+};
+
+fn output_with_scale(scale: f64) -> Output {
+    let output = Output::new(
+        String::from("TEST-2"),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: String::from("COSMIC"),
+            model: String::from("Test"),
+        },
+    );
+    let mode = Mode {
+        size: (1920, 1080).into(),
+        refresh: 60_000,
+    };
+    output.add_mode(mode);
+    output.change_current_state(
+        Some(mode),
+        None,
+        Some(Scale::Fractional(scale)),
+        Some((1920, 0).into()),
+    );
+    output
+}
+
+/// Whether the buffer of `scale` was drawn and has no redraw pending.
+fn is_prewarmed(element: &IcedElement<Label>, scale: f64) -> bool {
+    let internal = element.0.lock().unwrap();
+    internal.last_rendered_at.contains_key(&OrderedFloat(scale))
+        && internal
+            .buffers
+            .get(&OrderedFloat(scale))
+            .map_or(false, |buffer| !buffer.needs_redraw())
+}
+
+#[test]
+fn entering_an_output_draws_its_buffer() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 2.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    assert!(is_prewarmed(&element, 2.0));
+
+    let output = output_with_scale(1.5);
+    element.output_enter(&output, element.bbox());
+    assert!(is_prewarmed(&element, 1.5));
+}
+
+#[test]
+fn empty_elements_are_not_drawn() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 2.0);
+    let element = compositor.insert(Label, (0, 0), (0, 0));
+    assert!(!is_prewarmed(&element, 2.0));
+}
@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+
+use cosmic::{iced::widget::text, Element};
+use smithay::{
+    backend::renderer::{element::memory::MemoryRenderBufferRenderElement, test::DummyRenderer},
+    utils::{Point, Scale},
+};
+
+use crate::utils::iced::{
+    animation::Curve, test_helpers::HeadlessCompositor, transition::ProgramSwap, IcedElement,
+    Program, SlideDirection, SwapKind, SwapSizing, SwapTransition,
+};
+
+struct Page(&'static str);
+
+impl Program for Page {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(self.0).into()
+    }
+}
+
+fn swap(kind: SwapKind, duration: Duration) -> SwapTransition {
+    SwapTransition {
+        kind,
+        sizing: SwapSizing::Align,
+        curve: Curve::Linear,
+        duration,
+    }
+}
+
+fn render_count(element: &IcedElement<Page>) -> usize {
+    let mut renderer = DummyRenderer::new();
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_elements(&mut renderer, (0, 0).into(), Scale::from(1.0), 1.0);
+    elements.len()
+}
+
+#[test]
+fn crossfade_moves_alpha_to_the_new_content() {
+    let start = Instant::now();
+    let duration = Duration::from_millis(100);
+    let swap = ProgramSwap::new(
+        swap(SwapKind::Crossfade, duration),
+        (100, 50).into(),
+        Vec::new(),
+    );
+
+    let (old, new) = swap.params(start, (100, 50).into());
+    assert_eq!((old.alpha, new.alpha), (1.0, 0.0));
+    assert!(!swap.is_complete(start));
+
+    let end = start + duration * 2;
+    let (old, new) = swap.params(end, (100, 50).into());
+    assert_eq!((old.alpha, new.alpha), (0.0, 1.0));
+    assert!(swap.is_complete(end));
+}
+
+#[test]
+fn slide_pushes_out_the_old_content() {
+    let start = Instant::now();
+    let duration = Duration::from_millis(100);
+    let swap = ProgramSwap::new(
+        swap(SwapKind::Slide(SlideDirection::Right), duration),
+        (100, 50).into(),
+        Vec::new(),
+    );
+
+    let (old, new) = swap.params(start, (100, 50).into());
+    assert_eq!(old.offset, Point::from((0.0, 0.0)));
+    assert_eq!(new.offset, Point::from((100.0, 0.0)));
+    assert_eq!((old.alpha, new.alpha), (1.0, 1.0));
+
+    let (old, new) = swap.params(start + duration * 2, (100, 50).into());
+    assert_eq!(old.offset, Point::from((-100.0, 0.0)));
+    assert_eq!(new.offset, Point::from((0.0, 0.0)));
+}
+
+#[test]
+fn replace_program_keeps_the_element() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Page("first"), (100, 50), (0, 0));
+
+    element.replace_program(Page("second"));
+    assert_eq!(element.with_program(|page| page.0), "second");
+    assert_eq!(
+        compositor.snapshot(&element).size,
+        (100, 50).into(),
+        "size must be kept"
+    );
+    assert_eq!(render_count(&element), 1);
+}
+
+#[test]
+fn old_frame_is_shown_until_the_transition_completed() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Page("first"), (100, 50), (0, 0));
+    compositor.frame();
+
+    element.replace_program_with_transition(
+        Page("second"),
+        swap(SwapKind::Crossfade, Duration::from_millis(100)),
+    );
+    assert_eq!(element.with_program(|page| page.0), "second");
+    assert!(element.0.lock().unwrap().swap.is_some());
+    assert_eq!(render_count(&element), 2, "old frame and new content");
+
+    std::thread::sleep(Duration::from_millis(150));
+    assert_eq!(render_count(&element), 1);
+    assert!(
+        element.0.lock().unwrap().swap.is_none(),
+        "old buffers must be released"
+    );
+}
+
+#[test]
+fn replace_program_cancels_a_running_transition() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Page("first"), (100, 50), (0, 0));
+    compositor.frame();
+
+    element.replace_program_with_transition(
+        Page("second"),
+        swap(SwapKind::Crossfade, Duration::from_secs(10)),
+    );
+    element.replace_program(Page("third"));
+    assert!(element.0.lock().unwrap().swap.is_none());
+    assert_eq!(render_count(&element), 1);
+}
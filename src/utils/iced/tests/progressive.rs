@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{test_helpers::HeadlessCompositor, Overlaid, Program, ProgressivePlan},
+};
+
+#[derive(Debug, Clone)]
+struct Loaded(u32);
+
+/// A list of `stages` stages, showing how many of them are loaded.
+struct List {
+    stages: u32,
+    loaded: u32,
+}
+
+impl List {
+    fn new(stages: u32) -> List {
+        List { stages, loaded: 0 }
+    }
+}
+
+impl Program for List {
+    type Message = Loaded;
+
+    fn update(&mut self, message: Loaded, _: &LoopHandle<'static, Data>) -> Command<Loaded> {
+        self.loaded = message.0;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(format!("{} of {} loaded", self.loaded + 1, self.stages)).into()
+    }
+
+    fn progressive(&self) -> Option<ProgressivePlan> {
+        Some(ProgressivePlan::new(self.stages))
+    }
+
+    fn progressive_stage(&self, stage: u32) -> Option<Loaded> {
+        Some(Loaded(stage))
+    }
+}
+
+#[test]
+fn one_stage_is_loaded_per_loop_iteration() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(List::new(3), (200, 40), (0, 0));
+    assert_eq!(element.progressive_stage(), Some(0), "first stage is shown");
+    let first = compositor.snapshot(&element);
+
+    compositor.dispatch(Duration::ZERO);
+    assert_eq!(element.progressive_stage(), Some(1));
+    assert_eq!(element.with_program(|list| list.loaded), 1);
+    assert_ne!(compositor.snapshot(&element).pixels, first.pixels);
+
+    compositor.dispatch(Duration::ZERO);
+    assert_eq!(element.progressive_stage(), None, "all stages are loaded");
+    assert_eq!(element.with_program(|list| list.loaded), 2);
+
+    compositor.dispatch(Duration::ZERO);
+    assert_eq!(element.with_program(|list| list.loaded), 2);
+}
+
+#[test]
+fn single_stages_load_nothing() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(List::new(1), (200, 40), (0, 0));
+    assert_eq!(element.progressive_stage(), None);
+}
+
+#[test]
+fn replaced_programs_start_over() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(List::new(2), (200, 40), (0, 0));
+    compositor.dispatch(Duration::ZERO);
+    assert_eq!(element.progressive_stage(), None);
+
+    element.replace_program(List::new(2));
+    assert_eq!(element.progressive_stage(), Some(0));
+    compositor.dispatch(Duration::ZERO);
+    assert_eq!(element.with_program(|list| list.loaded), 1);
+}
+
+#[test]
+fn dropped_elements_cancel_their_stages() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(List::new(3), (200, 40), (0, 0));
+    compositor.remove(&element);
+    drop(element);
+    compositor.dispatch(Duration::ZERO);
+}
+
+#[test]
+fn combinators_load_the_base_program() {
+    let overlaid = Overlaid::new(List::new(3), List::new(5), false);
+    assert_eq!(overlaid.progressive(), Some(ProgressivePlan::new(3)));
+    assert!(overlaid.progressive_stage(1).is_some());
+}
@@ -0,0 +1,127 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use smithay::reexports::calloop::EventLoop;
+
+use super::{assert_goldens, single_golden};
+use crate::{
+    state::Data,
+    utils::iced::{
+        golden::GoldenPrograms, prompt, IcedElement, PromptAction, PromptHandle, PromptMessage,
+        PromptProgram, PromptResult, PromptSpec, ShellRequest,
+    },
+};
+
+fn spec(timeout: Option<(Duration, PromptAction)>) -> PromptSpec {
+    PromptSpec {
+        title: String::from("Log out?"),
+        body: String::from("Unsaved changes will be lost."),
+        confirm_label: String::from("Log out"),
+        cancel_label: String::from("Cancel"),
+        default_action: PromptAction::Confirm,
+        timeout,
+    }
+}
+
+struct Setup {
+    element: IcedElement<PromptProgram>,
+    handle: PromptHandle,
+    closes: Arc<Mutex<usize>>,
+    // keeps the element's sources alive
+    _event_loop: EventLoop<'static, Data>,
+}
+
+fn setup(spec: PromptSpec) -> Setup {
+    let event_loop = EventLoop::try_new().expect("Failed to create event loop");
+    let (element, handle) = prompt(spec, event_loop.handle());
+    let closes = Arc::new(Mutex::new(0));
+    let counter = closes.clone();
+    element.set_shell_request_handler(move |request, _| {
+        if let ShellRequest::CloseElement = request {
+            *counter.lock().unwrap() += 1;
+        }
+    });
+    Setup {
+        element,
+        handle,
+        closes,
+        _event_loop: event_loop,
+    }
+}
+
+#[test]
+fn answer_resolves_and_closes_once() {
+    let setup = setup(spec(None));
+    assert_eq!(setup.handle.result(), None);
+
+    setup.element.queue_message(PromptMessage::Confirm);
+    assert_eq!(setup.handle.result(), Some(PromptResult::Confirmed));
+    assert_eq!(*setup.closes.lock().unwrap(), 1);
+
+    setup.element.queue_message(PromptMessage::Cancel);
+    assert_eq!(setup.handle.result(), Some(PromptResult::Confirmed));
+    assert_eq!(*setup.closes.lock().unwrap(), 1);
+}
+
+#[test]
+fn accept_triggers_the_default_action() {
+    let setup = setup(spec(None));
+    setup.element.queue_message(PromptMessage::Accept);
+    assert_eq!(setup.handle.result(), Some(PromptResult::Confirmed));
+}
+
+#[test]
+fn countdown_applies_the_timeout_action() {
+    // partial seconds are rounded up
+    let setup = setup(spec(Some((
+        Duration::from_millis(1500),
+        PromptAction::Cancel,
+    ))));
+
+    setup.element.queue_message(PromptMessage::Tick);
+    assert_eq!(setup.handle.result(), None);
+    setup.element.queue_message(PromptMessage::Tick);
+    assert_eq!(
+        setup.handle.result(),
+        Some(PromptResult::TimedOut(PromptAction::Cancel))
+    );
+    assert_eq!(*setup.closes.lock().unwrap(), 1);
+}
+
+#[test]
+fn dropping_the_element_cancels() {
+    let Setup {
+        element,
+        handle,
+        _event_loop,
+        ..
+    } = setup(spec(None));
+    let resolved = Arc::new(Mutex::new(None));
+    let result = resolved.clone();
+    handle.on_resolved(move |r| *result.lock().unwrap() = Some(r));
+
+    drop(element);
+    assert_eq!(*resolved.lock().unwrap(), Some(PromptResult::Cancelled));
+}
+
+#[test]
+fn callbacks_of_resolved_prompts_run_immediately() {
+    let setup = setup(spec(None));
+    setup.element.queue_message(PromptMessage::Cancel);
+
+    let resolved = Arc::new(Mutex::new(None));
+    let result = resolved.clone();
+    setup
+        .handle
+        .on_resolved(move |r| *result.lock().unwrap() = Some(r));
+    assert_eq!(*resolved.lock().unwrap(), Some(PromptResult::Cancelled));
+}
+
+#[test]
+fn prompt_golden() {
+    let mut programs = GoldenPrograms::new();
+    programs.register("prompt", PromptProgram::sample_state);
+    assert_goldens(&programs, &single_golden(1.0, (420, 180)));
+}
@@ -0,0 +1,165 @@
+use std::{os::unix::net::UnixStream, thread, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::iced::{
+    confinement::seccomp_filter,
+    proxy::{encode_frame, serve, FrameDecoder, FrameError, MAX_FRAME_LEN},
+    test_helpers::HeadlessCompositor,
+    IcedElement, IcedElementProxy, ProxyMessage, SandboxedProgram, ViewNode,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Message {
+    Increment,
+}
+
+#[derive(Default)]
+struct Counter {
+    count: u32,
+}
+
+impl SandboxedProgram for Counter {
+    type Message = Message;
+
+    fn update(&mut self, message: Self::Message) {
+        let Message::Increment = message;
+        self.count += 1;
+    }
+
+    fn view(&self) -> ViewNode<Self::Message> {
+        ViewNode::Column {
+            children: vec![
+                ViewNode::Text {
+                    content: self.count.to_string(),
+                    size: None,
+                },
+                ViewNode::Button {
+                    child: Box::new(ViewNode::Text {
+                        content: String::from("+"),
+                        size: None,
+                    }),
+                    on_press: Some(Message::Increment),
+                },
+            ],
+            spacing: 4,
+            padding: 0,
+        }
+    }
+}
+
+type Proxy = IcedElementProxy<Counter>;
+
+fn shown_count(view: Option<&ViewNode<Message>>) -> Option<String> {
+    let Some(ViewNode::Column { children, .. }) = view else { return None };
+    match children.first() {
+        Some(ViewNode::Text { content, .. }) => Some(content.clone()),
+        _ => None,
+    }
+}
+
+#[test]
+fn frames_are_decoded_from_partial_reads() {
+    let frame = encode_frame(&Counter { count: 7 }.view()).unwrap();
+    let mut decoder = FrameDecoder::default();
+    let (first, second) = frame.split_at(frame.len() / 2);
+
+    decoder.push(first);
+    assert!(decoder.next_frame::<ViewNode<Message>>().is_none());
+    decoder.push(second);
+    decoder.push(&frame);
+    for _ in 0..2 {
+        let view = decoder.next_frame::<ViewNode<Message>>().unwrap().unwrap();
+        assert_eq!(shown_count(Some(&view)).as_deref(), Some("7"));
+    }
+    assert!(decoder.next_frame::<ViewNode<Message>>().is_none());
+}
+
+#[test]
+fn oversized_frames_are_rejected() {
+    let mut decoder = FrameDecoder::default();
+    decoder.push(&(MAX_FRAME_LEN as u32 + 1).to_le_bytes());
+    assert!(matches!(
+        decoder.next_frame::<ViewNode<Message>>(),
+        Some(Err(FrameError::TooLarge(_)))
+    ));
+}
+
+#[test]
+fn malformed_frames_are_rejected() {
+    let mut decoder = FrameDecoder::default();
+    decoder.push(&5u32.to_le_bytes());
+    decoder.push(b"{\"ty");
+    decoder.push(b"p");
+    assert!(matches!(
+        decoder.next_frame::<ViewNode<Message>>(),
+        Some(Err(FrameError::Malformed(_)))
+    ));
+}
+
+#[test]
+fn views_are_tagged_json() {
+    let view = ViewNode::<Message>::Space {
+        width: 4,
+        height: 2,
+    };
+    assert_eq!(
+        serde_json::to_string(&view).unwrap(),
+        r#"{"type":"space","width":4,"height":2}"#
+    );
+}
+
+#[test]
+fn seccomp_filter_compiles() {
+    assert!(seccomp_filter().is_ok());
+}
+
+/// Runs `Counter` in a thread, standing in for a confined worker.
+fn proxied_counter() -> (HeadlessCompositor<Proxy>, IcedElement<Proxy>) {
+    let (socket, worker_socket) = UnixStream::pair().unwrap();
+    thread::spawn(move || serve(Counter::default(), worker_socket));
+
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let proxy = IcedElementProxy::with_socket(socket).unwrap();
+    let element = compositor.insert(proxy, (200, 80), (0, 0));
+    (compositor, element)
+}
+
+/// Dispatches until `done` holds for the proxy, or gives up after a second.
+fn dispatch_until(
+    compositor: &mut HeadlessCompositor<Proxy>,
+    element: &IcedElement<Proxy>,
+    done: impl Fn(&Proxy) -> bool,
+) -> bool {
+    for _ in 0..100 {
+        compositor.dispatch(Duration::from_millis(10));
+        compositor.settle();
+        if element.with_program(&done) {
+            return true;
+        }
+    }
+    false
+}
+
+#[test]
+fn worker_views_are_rendered_and_messages_forwarded() {
+    let (mut compositor, element) = proxied_counter();
+    assert!(dispatch_until(&mut compositor, &element, |p| {
+        shown_count(p.current_view()).as_deref() == Some("0")
+    }));
+
+    element.queue_message(ProxyMessage::Message(Message::Increment));
+    element.queue_message(ProxyMessage::Message(Message::Increment));
+    assert!(dispatch_until(&mut compositor, &element, |p| {
+        shown_count(p.current_view()).as_deref() == Some("2")
+    }));
+}
+
+#[test]
+fn closed_workers_are_replaced_by_a_placeholder() {
+    let (socket, worker_socket) = UnixStream::pair().unwrap();
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Proxy::with_socket(socket).unwrap(), (200, 80), (0, 0));
+    drop(worker_socket);
+    assert!(dispatch_until(&mut compositor, &element, |p| p.has_exited()));
+}
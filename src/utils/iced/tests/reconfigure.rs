@@ -0,0 +1,88 @@
+use smithay::{
+    output::Scale,
+    utils::{Rectangle, Size},
+};
+
+use crate::utils::iced::{
+    reconfigure_output,
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement, Reconfigure, VirtualTargetId,
+};
+
+/// Scales of the element's buffers with their sizes, ordered by scale.
+fn buffers(element: &IcedElement<Label>) -> Vec<(f64, (i32, i32))> {
+    let internal = element.0.lock().unwrap();
+    let mut buffers = internal
+        .buffers
+        .iter()
+        .map(|(scale, buffer)| (scale.0, (buffer.size().w, buffer.size().h)))
+        .collect::<Vec<_>>();
+    buffers.sort_by(|a, b| a.0.total_cmp(&b.0));
+    buffers
+}
+
+fn layouts(element: &IcedElement<Label>) -> u64 {
+    element.0.lock().unwrap().state.layouts()
+}
+
+#[test]
+fn size_and_scales_are_applied_at_once() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+    let before = layouts(&element);
+
+    element.reconfigure(Reconfigure {
+        size: Some((120, 60).into()),
+        scales: Some(vec![1.5, 2.0]),
+    });
+    assert_eq!(buffers(&element), vec![(1.5, (180, 90)), (2.0, (240, 120))]);
+    assert_eq!(layouts(&element), before + 1, "laid out once");
+}
+
+#[test]
+fn unchanged_configuration_is_not_laid_out() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+    let before = layouts(&element);
+
+    element.reconfigure(Reconfigure {
+        size: Some((100, 50).into()),
+        scales: Some(vec![1.0]),
+    });
+    element.reconfigure(Reconfigure::default());
+    assert_eq!(layouts(&element), before);
+    assert_eq!(buffers(&element), vec![(1.0, (100, 50))]);
+}
+
+#[test]
+fn buffers_of_virtual_targets_are_kept() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+    element.add_virtual_target(
+        VirtualTargetId(7),
+        Rectangle::from_loc_and_size((0, 0), (400, 200)),
+        3.0,
+    );
+
+    element.reconfigure(Reconfigure {
+        scales: Some(vec![2.0]),
+        ..Default::default()
+    });
+    assert_eq!(
+        buffers(&element),
+        vec![(2.0, (200, 100)), (3.0, (300, 150))]
+    );
+}
+
+#[test]
+fn output_scale_changes_replace_the_buffers() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+
+    compositor
+        .output()
+        .change_current_state(None, None, Some(Scale::Fractional(2.0)), None);
+    reconfigure_output(compositor.output());
+    assert_eq!(buffers(&element), vec![(2.0, (200, 100))]);
+    assert_eq!(element.0.lock().unwrap().size, Size::from((100, 50)));
+}
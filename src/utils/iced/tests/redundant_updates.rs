@@ -0,0 +1,139 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use iced_softbuffer::native::raqote::{DrawOptions, DrawTarget};
+use ordered_float::OrderedFloat;
+use smithay::reexports::calloop::LoopHandle;
+
+use super::{assert_goldens, single_golden};
+use crate::{
+    state::Data,
+    utils::iced::{
+        golden::GoldenPrograms, test_helpers::HeadlessCompositor, HookPalette, IcedElement,
+        PaletteRole, Program,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+enum Message {
+    /// Changes bookkeeping only, the view stays the same
+    Touch,
+    Increment,
+}
+
+#[derive(Default)]
+struct Counter {
+    count: u32,
+    touched: u32,
+}
+
+impl Program for Counter {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        match message {
+            Message::Touch => self.touched += 1,
+            Message::Increment => self.count += 1,
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(format!("Count: {}", self.count)).into()
+    }
+}
+
+/// Same view as `Counter`, but draws a hook underneath.
+#[derive(Default)]
+struct HookedCounter(Counter);
+
+impl Program for HookedCounter {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        self.0.update(message, loop_handle)
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        self.0.view()
+    }
+
+    fn themed_background(&self, target: &mut DrawTarget<&mut [u32]>, palette: &HookPalette) {
+        target.fill_rect(
+            0.0,
+            0.0,
+            4.0,
+            4.0,
+            &palette.source(PaletteRole::Accent),
+            &DrawOptions::new(),
+        );
+    }
+
+    fn uses_hooks(&self) -> bool {
+        true
+    }
+}
+
+fn needs_redraw<P: Program + Send + 'static>(element: &IcedElement<P>) -> bool {
+    let internal = element.0.lock().unwrap();
+    internal
+        .buffers
+        .get(&OrderedFloat(1.0))
+        .map_or(true, |buffer| buffer.needs_redraw())
+}
+
+#[test]
+fn unchanged_views_are_not_redrawn() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (120, 30), (0, 0));
+    assert!(!needs_redraw(&element));
+
+    element.queue_message(Message::Touch);
+    compositor.settle();
+    assert_eq!(element.redundant_updates(), 1);
+    assert!(!needs_redraw(&element));
+
+    element.queue_message(Message::Increment);
+    compositor.settle();
+    assert_eq!(element.redundant_updates(), 1);
+    assert!(needs_redraw(&element));
+}
+
+#[test]
+fn forced_redraws_bypass_the_view_check() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (120, 30), (0, 0));
+
+    element.force_update();
+    assert!(needs_redraw(&element));
+    assert_eq!(element.redundant_updates(), 0);
+}
+
+#[test]
+fn programs_with_hooks_always_redraw() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(HookedCounter::default(), (120, 30), (0, 0));
+    assert!(!needs_redraw(&element));
+
+    element.queue_message(Message::Touch);
+    compositor.settle();
+    assert_eq!(element.redundant_updates(), 0);
+    assert!(needs_redraw(&element));
+}
+
+#[test]
+fn skipped_updates_show_the_latest_view() {
+    let mut programs = GoldenPrograms::new();
+    programs.register_configured("redundant-updates", Counter::default, |element| {
+        for message in [Message::Touch, Message::Increment, Message::Touch] {
+            element.queue_message(message);
+        }
+    });
+    assert_goldens(&programs, &single_golden(1.0, (120, 30)));
+}
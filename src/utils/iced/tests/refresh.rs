@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::{
+    output::{Mode, Output, PhysicalProperties, Subpixel},
+    reexports::calloop::LoopHandle,
+};
+
+use super::{assert_goldens, single_golden};
+use crate::{
+    state::Data,
+    utils::iced::{golden::GoldenPrograms, test_helpers::HeadlessCompositor, Program, RefreshInfo},
+};
+
+/// Shows the refresh rate it was notified about.
+#[derive(Default)]
+struct RefreshRate {
+    changes: Vec<RefreshInfo>,
+}
+
+impl Program for RefreshRate {
+    type Message = RefreshInfo;
+
+    fn update(&mut self, info: RefreshInfo, _: &LoopHandle<'static, Data>) -> Command<RefreshInfo> {
+        self.changes.push(info);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        let label = match self.changes.last() {
+            Some(info) => format!(
+                "{:.0} Hz{}",
+                1.0 / info.interval.as_secs_f64(),
+                if info.vrr { " (VRR)" } else { "" }
+            ),
+            None => String::from("Unknown"),
+        };
+        text(label).size(24).into()
+    }
+
+    fn refresh_changed(&mut self, info: &RefreshInfo) -> Option<Self::Message> {
+        Some(*info)
+    }
+}
+
+fn output(name: &str, refresh: i32) -> Output {
+    let output = Output::new(
+        name.to_string(),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: String::from("COSMIC"),
+            model: String::from("Test"),
+        },
+    );
+    let mode = Mode {
+        size: (1920, 1080).into(),
+        refresh,
+    };
+    output.add_mode(mode);
+    output.change_current_state(Some(mode), None, None, None);
+    output
+}
+
+fn hz(hz: u64) -> RefreshInfo {
+    RefreshInfo {
+        interval: Duration::from_nanos(1_000_000_000 / hz),
+        vrr: false,
+    }
+}
+
+#[test]
+fn refresh_info_follows_the_current_mode() {
+    let info = RefreshInfo::for_output(&output("TEST-1", 144_000), true).unwrap();
+    assert_eq!(info.interval, Duration::from_nanos(6_944_444));
+    assert!(info.vrr);
+    assert_eq!(RefreshInfo::for_output(&output("TEST-2", 0), false), None);
+}
+
+#[test]
+fn entering_an_output_notifies_its_refresh_rate() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(RefreshRate::default(), (100, 30), (0, 0));
+    compositor.settle();
+    assert_eq!(
+        element.with_program(|p| p.changes.clone()),
+        vec![RefreshInfo::for_output(compositor.output(), false).unwrap()]
+    );
+}
+
+#[test]
+fn only_changes_of_the_fastest_output_are_notified() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(RefreshRate::default(), (100, 30), (0, 0));
+    compositor.settle();
+    let fast = output("FAST-1", 144_000);
+
+    element.set_refresh_info(&fast, hz(144));
+    // slower than the fastest output
+    element.set_refresh_info(compositor.output(), hz(30));
+    element.set_refresh_info(&fast, hz(144));
+    element.set_refresh_info(&fast, hz(120));
+    compositor.settle();
+
+    let changes = element.with_program(|p| p.changes.clone());
+    assert_eq!(changes[1..], [hz(144), hz(120)]);
+    assert_eq!(element.refresh_info(), Some(hz(120)));
+}
+
+#[test]
+fn refresh_rate_golden() {
+    let mut programs = GoldenPrograms::new();
+    programs.register_configured("refresh-rate", RefreshRate::default, |element| {
+        let output = output("GOLDEN-1", 120_000);
+        element.set_refresh_info(&output, RefreshInfo::for_output(&output, true).unwrap());
+    });
+    assert_goldens(&programs, &single_golden(1.0, (160, 40)));
+}
@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use cosmic::{iced::widget::text, Element};
+use iced_softbuffer::native::raqote::DrawTarget;
+use ordered_float::OrderedFloat;
+use smithay::{
+    backend::renderer::{element::memory::MemoryRenderBufferRenderElement, test::DummyRenderer},
+    utils::Scale,
+};
+
+use crate::utils::iced::{test_helpers::HeadlessCompositor, IcedElement, Program, RenderError};
+
+/// Panics while drawing its background, if `failing`.
+#[derive(Default)]
+struct Flaky {
+    failing: AtomicBool,
+}
+
+impl Program for Flaky {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Flaky").into()
+    }
+
+    fn background(&self, _: &mut DrawTarget<&mut [u32]>) {
+        if self.failing.load(Ordering::SeqCst) {
+            panic!("background failed");
+        }
+    }
+}
+
+fn set_failing(element: &IcedElement<Flaky>, failing: bool) {
+    element.with_program(|program| program.failing.store(failing, Ordering::SeqCst));
+}
+
+fn render(element: &IcedElement<Flaky>) -> usize {
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> = element.render_elements(
+        &mut DummyRenderer::new(),
+        (0, 0).into(),
+        Scale::from(1.0),
+        1.0,
+    );
+    elements.len()
+}
+
+fn needs_redraw(element: &IcedElement<Flaky>) -> bool {
+    element.0.lock().unwrap().buffers[&OrderedFloat(1.0)].needs_redraw()
+}
+
+#[test]
+fn small_buffers_are_reported() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Flaky::default(), (10, 10), (0, 0));
+    let mut internal = element.0.lock().unwrap();
+    let result = internal
+        .rasterizer()
+        .rasterize(&mut [0; 4], (10, 10).into(), 1.0, None);
+    assert_eq!(
+        result,
+        Err(RenderError::BufferTooSmall {
+            expected: 400,
+            actual: 4,
+        })
+    );
+}
+
+#[test]
+fn panics_are_reported() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Flaky::default(), (10, 10), (0, 0));
+    set_failing(&element, true);
+    let mut internal = element.0.lock().unwrap();
+    let mut pixels = [0; 400];
+    let result = internal
+        .rasterizer()
+        .rasterize(&mut pixels, (10, 10).into(), 1.0, None);
+    assert_eq!(
+        result,
+        Err(RenderError::Panicked(String::from("background failed")))
+    );
+}
+
+#[test]
+fn failed_frames_are_skipped_and_redrawn() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Flaky::default(), (100, 40), (0, 0));
+    assert_eq!(render(&element), 1);
+
+    set_failing(&element, true);
+    // not the bare mutex, which would keep the cached frame
+    for buffer in element.lock().buffers.values_mut() {
+        buffer.mark_dirty();
+    }
+    compositor.frame();
+    assert_eq!(render(&element), 0);
+    assert!(needs_redraw(&element), "stays dirty");
+
+    set_failing(&element, false);
+    compositor.frame();
+    assert_eq!(render(&element), 1);
+    assert!(!needs_redraw(&element));
+}
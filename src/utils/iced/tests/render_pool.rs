@@ -0,0 +1,106 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ordered_float::OrderedFloat;
+use smithay::{
+    backend::renderer::{element::memory::MemoryRenderBufferRenderElement, test::DummyRenderer},
+    desktop::space::SpaceElement,
+    utils::{Rectangle, Scale},
+};
+
+use crate::utils::iced::{
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement,
+};
+
+fn pool() -> Arc<rayon::ThreadPool> {
+    Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap(),
+    )
+}
+
+/// Dispatches the event loop until the job of the element's render pool finished.
+fn wait_for_job(compositor: &mut HeadlessCompositor<Label>, element: &IcedElement<Label>) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while element.0.lock().unwrap().render_job_pending {
+        assert!(Instant::now() < deadline, "render job didn't finish");
+        compositor.dispatch(Duration::from_millis(5));
+    }
+}
+
+fn needs_redraw(element: &IcedElement<Label>) -> bool {
+    element.0.lock().unwrap().buffers[&OrderedFloat(1.0)].needs_redraw()
+}
+
+fn render(element: &IcedElement<Label>) -> usize {
+    let mut renderer = DummyRenderer::new();
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_elements(&mut renderer, (0, 0).into(), Scale::from(1.0), 1.0);
+    elements.len()
+}
+
+fn pooled_element(compositor: &HeadlessCompositor<Label>) -> IcedElement<Label> {
+    let element = IcedElement::new_with_pool(Label, (100, 50), compositor.handle(), pool());
+    element.output_enter(
+        compositor.output(),
+        Rectangle::from_loc_and_size((0, 0), (100, 50)),
+    );
+    element
+}
+
+#[test]
+fn pool_draws_the_next_frame() {
+    let mut compositor = HeadlessCompositor::<Label>::new((400, 200), 1.0);
+    let element = pooled_element(&compositor);
+
+    // the first frame is drawn ahead of time
+    wait_for_job(&mut compositor, &element);
+    assert!(!needs_redraw(&element));
+
+    for buffer in element.0.lock().unwrap().buffers.values_mut() {
+        buffer.mark_dirty();
+    }
+    assert!(needs_redraw(&element));
+    // the previous frame is presented, while the pool draws the next one
+    assert_eq!(render(&element), 1);
+    assert!(element.0.lock().unwrap().render_job_pending);
+    // only one job is in flight
+    assert_eq!(render(&element), 1);
+    wait_for_job(&mut compositor, &element);
+    assert!(!needs_redraw(&element));
+}
+
+#[test]
+fn content_changed_during_a_job_is_drawn_again() {
+    let mut compositor = HeadlessCompositor::<Label>::new((400, 200), 1.0);
+    let element = pooled_element(&compositor);
+    wait_for_job(&mut compositor, &element);
+
+    for buffer in element.0.lock().unwrap().buffers.values_mut() {
+        buffer.mark_dirty();
+    }
+    assert_eq!(render(&element), 1);
+    element.resize((120, 50).into());
+    wait_for_job(&mut compositor, &element);
+    assert!(needs_redraw(&element));
+}
+
+#[test]
+fn pending_jobs_dont_keep_elements_alive() {
+    let mut compositor = HeadlessCompositor::<Label>::new((400, 200), 1.0);
+    let element = pooled_element(&compositor);
+    assert!(element.0.lock().unwrap().render_job_pending);
+
+    let weak = Arc::downgrade(&element.0);
+    drop(element);
+    assert!(weak.upgrade().is_none());
+    // the finished job finds nobody to hand its content to
+    for _ in 0..10 {
+        compositor.dispatch(Duration::from_millis(5));
+    }
+}
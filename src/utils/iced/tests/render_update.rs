@@ -0,0 +1,71 @@
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::{
+    backend::renderer::{element::memory::MemoryRenderBufferRenderElement, test::DummyRenderer},
+    reexports::calloop::LoopHandle,
+    utils::Scale,
+};
+
+use crate::{
+    state::Data,
+    utils::iced::{test_helpers::HeadlessCompositor, IcedElement, Program},
+};
+
+struct Counter(u32);
+
+impl Program for Counter {
+    type Message = u32;
+
+    fn update(&mut self, message: u32, _: &LoopHandle<'static, Data>) -> Command<u32> {
+        self.0 = message;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(self.0.to_string()).into()
+    }
+}
+
+#[test]
+fn pending_messages_are_applied_before_rendering() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter(0), (100, 40), (0, 0));
+    let before = compositor.snapshot(&element);
+
+    // arrives without the event loop being dispatched
+    assert!(element.message_sender().send(7));
+    assert_eq!(element.with_program(|counter| counter.0), 0);
+
+    let mut renderer = DummyRenderer::new();
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_elements(&mut renderer, (0, 0).into(), Scale::from(1.0), 1.0);
+    assert_eq!(elements.len(), 1);
+    assert_eq!(element.with_program(|counter| counter.0), 7);
+    assert_ne!(compositor.snapshot(&element).pixels, before.pixels);
+}
+
+#[test]
+fn batches_render_the_same_state() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let panel = compositor.insert(Counter(0), (100, 40), (0, 0));
+    let popup = compositor.insert(Counter(0), (100, 40), (0, 50));
+
+    // the shared state changes for both, without the event loop being dispatched
+    assert!(panel.message_sender().send(3));
+    assert!(popup.message_sender().send(3));
+
+    let mut renderer = DummyRenderer::new();
+    let elements = IcedElement::render_batch(
+        &[&panel, &popup],
+        &mut renderer,
+        &[(0, 0).into(), (0, 50).into()],
+        Scale::from(1.0),
+        1.0,
+    );
+    assert_eq!(elements.len(), 2);
+    assert_eq!(panel.with_program(|counter| counter.0), 3);
+    assert_eq!(popup.with_program(|counter| counter.0), 3);
+    assert_eq!(
+        compositor.snapshot(&panel).pixels,
+        compositor.snapshot(&popup).pixels
+    );
+}
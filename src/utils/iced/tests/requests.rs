@@ -0,0 +1,120 @@
+use std::sync::{Arc, Mutex};
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+
+use crate::utils::iced::{
+    test_helpers::IcedElementTestHarness, Program, RequestMeta, ShellRequest, UpdateContext,
+};
+
+/// Forwards every message as a request.
+struct Requester;
+
+impl Program for Requester {
+    type Message = ShellRequest;
+
+    fn update_with_context(
+        &mut self,
+        request: Self::Message,
+        ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        ctx.request(request);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Requester").into()
+    }
+}
+
+type Received = Arc<Mutex<Vec<(ShellRequest, RequestMeta)>>>;
+
+fn setup() -> (IcedElementTestHarness<Requester>, Received) {
+    let harness = IcedElementTestHarness::new(Requester, (100, 20));
+    let received = Received::default();
+    let sink = received.clone();
+    harness
+        .element()
+        .set_shell_request_handler(move |request, meta| sink.lock().unwrap().push((request, meta)));
+    (harness, received)
+}
+
+#[test]
+fn requests_reach_the_handler_with_the_element_name() {
+    let (harness, received) = setup();
+    harness.element().set_name("launcher");
+
+    harness
+        .element()
+        .queue_message(ShellRequest::Spawn(String::from("cosmic-term")));
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert!(matches!(&received[0].0, ShellRequest::Spawn(command) if command == "cosmic-term"));
+    assert_eq!(received[0].1.element.as_deref(), Some("launcher"));
+}
+
+#[test]
+fn restricted_elements_only_issue_allowed_requests() {
+    let (harness, received) = setup();
+    harness.element().set_restricted(true);
+
+    harness
+        .element()
+        .queue_message(ShellRequest::SwitchWorkspace(1));
+    harness
+        .element()
+        .queue_message(ShellRequest::Spawn(String::from("cosmic-term")));
+    harness.element().queue_message(ShellRequest::CloseElement);
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert!(matches!(received[0].0, ShellRequest::CloseElement));
+}
+
+#[test]
+fn lifting_the_restriction_allows_all_requests() {
+    let (harness, received) = setup();
+    harness.element().set_restricted(true);
+    harness.element().set_restricted(false);
+
+    harness
+        .element()
+        .queue_message(ShellRequest::SwitchWorkspace(2));
+
+    let received = received.lock().unwrap();
+    assert!(matches!(
+        received[..],
+        [(ShellRequest::SwitchWorkspace(2), _)]
+    ));
+}
+
+#[test]
+fn requests_without_handler_are_dropped() {
+    let harness = IcedElementTestHarness::new(Requester, (100, 20));
+    harness
+        .element()
+        .queue_message(ShellRequest::SwitchWorkspace(1));
+
+    let received = Received::default();
+    let sink = received.clone();
+    harness
+        .element()
+        .set_shell_request_handler(move |request, meta| sink.lock().unwrap().push((request, meta)));
+    harness.element().queue_message(ShellRequest::CloseElement);
+
+    let received = received.lock().unwrap();
+    assert!(matches!(received[..], [(ShellRequest::CloseElement, _)]));
+}
+
+#[test]
+fn allowed_when_restricted() {
+    assert!(ShellRequest::CloseElement.allowed_when_restricted());
+    assert!(ShellRequest::MoveElement((10, 10).into()).allowed_when_restricted());
+    assert!(!ShellRequest::SwitchWorkspace(0).allowed_when_restricted());
+    assert!(!ShellRequest::Spawn(String::new()).allowed_when_restricted());
+    assert!(!ShellRequest::SetOutputEnabled {
+        output: String::from("eDP-1"),
+        enabled: false,
+    }
+    .allowed_when_restricted());
+}
@@ -0,0 +1,193 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use cosmic::{
+    iced::widget::{text, Column},
+    iced_native::Command,
+    Element,
+};
+use smithay::{reexports::calloop::EventLoop, utils::Size};
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        IcedElement, Program, SandboxLimits, SandboxViolation, Sandboxed, UpdateContext,
+    },
+};
+
+#[derive(Debug, Clone)]
+enum Message {
+    Increment,
+    Sleep(Duration),
+    Panic,
+    /// Shows that many labels
+    Grow(usize),
+}
+
+/// A configurable applet, as if driven by untrusted configuration.
+#[derive(Default)]
+struct Applet {
+    // the program isn't accessible through `Sandboxed`
+    count: Arc<AtomicUsize>,
+    labels: usize,
+}
+
+impl Program for Applet {
+    type Message = Message;
+
+    fn update_with_context(
+        &mut self,
+        message: Self::Message,
+        _ctx: &mut UpdateContext<'_>,
+    ) -> Command<Self::Message> {
+        match message {
+            Message::Increment => {
+                self.count.fetch_add(1, Ordering::SeqCst);
+            }
+            Message::Sleep(duration) => std::thread::sleep(duration),
+            Message::Panic => panic!("Misconfigured applet"),
+            Message::Grow(labels) => self.labels = labels,
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Column::with_children((0..self.labels).map(|_| text("Label").into()).collect()).into()
+    }
+}
+
+struct Setup {
+    element: IcedElement<Sandboxed<Applet>>,
+    count: Arc<AtomicUsize>,
+    // keeps the element's sources alive
+    _event_loop: EventLoop<'static, Data>,
+}
+
+fn sandboxed(limits: SandboxLimits) -> Setup {
+    let event_loop = EventLoop::try_new().unwrap();
+    let applet = Applet::default();
+    let count = applet.count.clone();
+    let element = IcedElement::new_sandboxed(applet, limits, (100, 40), event_loop.handle());
+    Setup {
+        element,
+        count,
+        _event_loop: event_loop,
+    }
+}
+
+#[test]
+fn sizes_are_clamped() {
+    let limits = SandboxLimits {
+        max_size: Size::from((200, 100)),
+        ..SandboxLimits::default()
+    };
+    let event_loop = EventLoop::<Data>::try_new().unwrap();
+    let element =
+        IcedElement::new_sandboxed(Applet::default(), limits, (500, 50), event_loop.handle());
+    assert_eq!(element.0.lock().unwrap().size, (200, 50).into());
+
+    element.resize((150, 5000).into());
+    assert_eq!(element.0.lock().unwrap().size, (150, 100).into());
+}
+
+#[test]
+fn oversized_views_degrade_the_element() {
+    let Setup {
+        element,
+        _event_loop,
+        ..
+    } = sandboxed(SandboxLimits {
+        max_widgets: 10,
+        ..SandboxLimits::default()
+    });
+
+    element.queue_message(Message::Grow(5));
+    assert!(!element.is_degraded());
+    element.queue_message(Message::Grow(20));
+    assert!(element.is_degraded());
+    // the column and its labels
+    assert_eq!(
+        element.sandbox_violations(),
+        vec![SandboxViolation::TooManyWidgets(21)]
+    );
+}
+
+#[test]
+fn panicking_updates_degrade_the_element() {
+    let Setup {
+        element,
+        count,
+        _event_loop,
+    } = sandboxed(SandboxLimits::default());
+
+    element.queue_message(Message::Panic);
+    assert!(element.is_degraded());
+    assert_eq!(
+        element.sandbox_violations(),
+        vec![SandboxViolation::Panicked]
+    );
+
+    // degraded programs don't receive messages anymore
+    element.queue_message(Message::Increment);
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn slow_updates_degrade_the_element() {
+    let Setup {
+        element,
+        _event_loop,
+        ..
+    } = sandboxed(SandboxLimits {
+        max_update_time: Duration::from_millis(1),
+        ..SandboxLimits::default()
+    });
+
+    element.queue_message(Message::Sleep(Duration::from_millis(10)));
+    assert!(element.is_degraded());
+    assert!(matches!(
+        element.sandbox_violations()[..],
+        [SandboxViolation::SlowUpdate(elapsed)] if elapsed >= Duration::from_millis(10)
+    ));
+}
+
+#[test]
+fn message_floods_are_dropped_and_reported_once() {
+    let Setup {
+        element,
+        count,
+        _event_loop,
+    } = sandboxed(SandboxLimits {
+        max_messages_per_second: 3,
+        ..SandboxLimits::default()
+    });
+
+    for _ in 0..6 {
+        element.queue_message(Message::Increment);
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+    assert_eq!(
+        element.sandbox_violations(),
+        vec![SandboxViolation::MessageFlood]
+    );
+    // floods don't degrade the element
+    assert!(!element.is_degraded());
+}
+
+#[test]
+fn slow_frames_throttle_redraws() {
+    let limits = SandboxLimits {
+        max_draw_time: Duration::from_millis(10),
+        ..SandboxLimits::default()
+    };
+
+    assert_eq!(limits.throttle(Duration::from_millis(5)), None);
+    let before = Instant::now();
+    let until = limits.throttle(Duration::from_millis(20)).unwrap();
+    assert!(until >= before + Duration::from_millis(80));
+}
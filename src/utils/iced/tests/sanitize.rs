@@ -0,0 +1,119 @@
+use std::borrow::Cow;
+
+use cosmic::iced_native::{Point as IcedPoint, Rectangle as IcedRectangle, Vector};
+use iced_graphics::Primitive;
+use iced_softbuffer::native::raqote::DrawTarget;
+
+use crate::utils::iced::sanitize::{draw_markers, limit, sanitize, SanitizeReport};
+
+fn clip(x: f32, y: f32, width: f32, height: f32) -> Primitive {
+    Primitive::Clip {
+        bounds: IcedRectangle {
+            x,
+            y,
+            width,
+            height,
+        },
+        content: Box::new(Primitive::None),
+    }
+}
+
+fn clip_bounds(primitive: &Primitive) -> IcedRectangle {
+    match primitive {
+        Primitive::Clip { bounds, .. } => *bounds,
+        primitive => panic!("expected a clip, got {:?}", primitive),
+    }
+}
+
+const LIMIT: f32 = 1600.0;
+
+#[test]
+fn limit_scales_with_the_element() {
+    assert_eq!(limit(100, 40), LIMIT);
+    assert_eq!(limit(0, 0), 16.0);
+}
+
+#[test]
+fn valid_streams_are_borrowed() {
+    let primitive = Primitive::Group {
+        primitives: vec![clip(0.0, 0.0, 100.0, 40.0), clip(-10.0, 5.0, 20.0, 0.0)],
+    };
+    let mut report = SanitizeReport::default();
+    assert!(matches!(
+        sanitize(&primitive, LIMIT, &mut report),
+        Some(Cow::Borrowed(_))
+    ));
+    assert_eq!(report.sanitized(), 0);
+}
+
+#[test]
+fn invalid_bounds_are_dropped() {
+    let primitive = Primitive::Group {
+        primitives: vec![
+            clip(f32::NAN, 5.0, 10.0, 10.0),
+            clip(0.0, 0.0, f32::INFINITY, 10.0),
+            clip(0.0, 0.0, 1.0e6, 10.0),
+            clip(0.0, 0.0, 100.0, 40.0),
+        ],
+    };
+    let mut report = SanitizeReport::default();
+    let sanitized = sanitize(&primitive, LIMIT, &mut report).unwrap();
+    let Primitive::Group { primitives } = sanitized.as_ref() else {
+        panic!("expected a group")
+    };
+    assert_eq!(primitives.len(), 1);
+    assert_eq!(clip_bounds(&primitives[0]).width, 100.0);
+    assert_eq!(report.dropped, 3);
+    assert_eq!(report.clamped, 0);
+    // non-finite coordinates are marked at the origin
+    assert_eq!(report.markers[0], IcedPoint::new(0.0, 5.0));
+}
+
+#[test]
+fn negative_sizes_are_clamped() {
+    let mut report = SanitizeReport::default();
+    let sanitized = sanitize(&clip(10.0, 10.0, -0.5, 20.0), LIMIT, &mut report).unwrap();
+    let bounds = clip_bounds(&sanitized);
+    assert_eq!((bounds.x, bounds.width, bounds.height), (10.0, 0.0, 20.0));
+    assert_eq!(report.clamped, 1);
+    assert_eq!(report.dropped, 0);
+}
+
+#[test]
+fn invalid_translations_are_dropped() {
+    let primitive = Primitive::Translate {
+        translation: Vector::new(f32::NAN, 0.0),
+        content: Box::new(clip(0.0, 0.0, 10.0, 10.0)),
+    };
+    let mut report = SanitizeReport::default();
+    assert!(sanitize(&primitive, LIMIT, &mut report).is_none());
+    assert_eq!(report.dropped, 1);
+}
+
+#[test]
+fn markers_include_translations() {
+    let primitive = Primitive::Translate {
+        translation: Vector::new(10.0, 20.0),
+        content: Box::new(Primitive::Group {
+            primitives: vec![clip(5.0, f32::NAN, 10.0, 10.0), Primitive::None],
+        }),
+    };
+    let mut report = SanitizeReport::default();
+    let sanitized = sanitize(&primitive, LIMIT, &mut report).unwrap();
+    assert!(matches!(sanitized.as_ref(), Primitive::Translate { .. }));
+    assert_eq!(report.markers, vec![IcedPoint::new(15.0, 20.0)]);
+}
+
+#[test]
+fn markers_are_drawn_at_scale() {
+    let mut pixels = vec![0u32; 40 * 40];
+    let mut target = DrawTarget::from_backing(40, 40, &mut pixels[..]);
+    draw_markers(&mut target, &[IcedPoint::new(10.0, 10.0)], 2.0);
+    drop(target);
+    assert_eq!(
+        pixels[20 * 40 + 20],
+        0xffff00ff,
+        "magenta at the scaled position"
+    );
+    assert_eq!(pixels[5 * 40 + 30], 0);
+}
@@ -0,0 +1,28 @@
+use smithay::{
+    backend::renderer::{
+        element::{memory::MemoryRenderBufferRenderElement, AsRenderElements},
+        test::DummyRenderer,
+    },
+    utils::{Scale, Size},
+};
+
+use crate::utils::iced::test_helpers::{HeadlessCompositor, Label};
+
+#[test]
+fn tiny_element_at_tiny_scale_renders_nothing() {
+    let mut compositor = HeadlessCompositor::new((1920, 1080), 0.1);
+    let element = compositor.insert(Label, (1, 1), (0, 0));
+    compositor.frame();
+
+    let mut renderer = DummyRenderer::new();
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_elements(&mut renderer, (0, 0).into(), Scale::from(0.1), 1.0);
+    assert!(
+        elements.is_empty(),
+        "a buffer rounded down to nothing isn't drawn"
+    );
+
+    let snapshot = compositor.snapshot(&element);
+    assert_eq!(snapshot.size, Size::from((0, 0)));
+    assert!(snapshot.pixels.is_empty());
+}
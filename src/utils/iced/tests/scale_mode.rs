@@ -0,0 +1,72 @@
+use cosmic::{iced::widget::text, Element};
+use iced_softbuffer::native::raqote::{DrawOptions, DrawTarget, SolidSource, Source};
+use smithay::utils::Size;
+
+use crate::utils::iced::{
+    scale::downsample_bilinear, test_helpers::IcedElementTestHarness, Program, ScaleMode,
+};
+
+const RED: u32 = 0xffff0000;
+
+/// Opaque red behind a label, rendered in the given mode.
+struct Swatch(ScaleMode);
+
+impl Program for Swatch {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Swatch").into()
+    }
+
+    fn background(&self, target: &mut DrawTarget<&mut [u32]>) {
+        let (w, h) = (target.width() as f32, target.height() as f32);
+        target.fill_rect(
+            0.0,
+            0.0,
+            w,
+            h,
+            &Source::Solid(SolidSource::from_unpremultiplied_argb(255, 255, 0, 0)),
+            &DrawOptions::new(),
+        );
+    }
+
+    fn scale_mode(&self) -> ScaleMode {
+        self.0
+    }
+}
+
+#[test]
+fn render_scale_of_modes() {
+    assert_eq!(ScaleMode::Exact.render_scale(1.5), 1.5);
+    assert_eq!(ScaleMode::CeilToInteger.render_scale(1.25), 2.0);
+    assert_eq!(ScaleMode::CeilToInteger.render_scale(2.0), 2.0);
+}
+
+#[test]
+fn downsampling_averages_neighbours() {
+    let src = [0xff000000, 0xffffffff, 0xffffffff, 0xff000000];
+    let mut dst = [0u32; 1];
+    downsample_bilinear(&src, Size::from((2, 2)), &mut dst, Size::from((1, 1)));
+    assert_eq!(dst[0], 0xff808080);
+}
+
+#[test]
+fn downsampling_to_the_same_size_copies() {
+    let src = [1, 2, 3, 4, 5, 6];
+    let mut dst = [0u32; 6];
+    downsample_bilinear(&src, Size::from((3, 2)), &mut dst, Size::from((3, 2)));
+    assert_eq!(dst, src);
+}
+
+#[test]
+fn integer_scale_is_downsampled_to_the_output_size() {
+    let exact = IcedElementTestHarness::new(Swatch(ScaleMode::Exact), (100, 40)).snapshot(1.5);
+    let ceiled =
+        IcedElementTestHarness::new(Swatch(ScaleMode::CeilToInteger), (100, 40)).snapshot(1.5);
+
+    assert_eq!(ceiled.size, exact.size);
+    assert_eq!(ceiled.size, Size::from((150, 60)));
+    // solid areas stay solid
+    assert_eq!(ceiled.pixel(140, 55), Some(RED));
+    assert_eq!(exact.pixel(140, 55), Some(RED));
+}
@@ -0,0 +1,139 @@
+use cosmic::{iced::widget::text, Element};
+use smithay::utils::{Point, Rectangle};
+
+use crate::utils::iced::{
+    scroll::route, Conditional, Overlaid, Program, ScrollRegion, Split, SplitDirection,
+    UnmatchedScroll,
+};
+
+/// A label declaring fixed scroll regions.
+struct Regions {
+    regions: Vec<ScrollRegion>,
+    unmatched: UnmatchedScroll,
+}
+
+impl Regions {
+    fn new(regions: &[(f64, f64, f64, f64)], unmatched: UnmatchedScroll) -> Regions {
+        Regions {
+            regions: regions
+                .iter()
+                .map(|&(x, y, w, h)| region(x, y, w, h))
+                .collect(),
+            unmatched,
+        }
+    }
+}
+
+impl Program for Regions {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Regions").into()
+    }
+
+    fn scroll_regions(&self) -> Vec<ScrollRegion> {
+        self.regions.clone()
+    }
+
+    fn unmatched_scroll(&self) -> UnmatchedScroll {
+        self.unmatched
+    }
+}
+
+fn region(x: f64, y: f64, w: f64, h: f64) -> ScrollRegion {
+    ScrollRegion {
+        bounds: Rectangle::from_loc_and_size((x, y), (w, h)),
+    }
+}
+
+fn point(x: f64, y: f64) -> Point<f64, smithay::utils::Logical> {
+    Point::from((x, y))
+}
+
+#[test]
+fn events_inside_a_region_keep_their_position() {
+    let regions = [
+        region(0.0, 0.0, 100.0, 100.0),
+        region(100.0, 0.0, 100.0, 100.0),
+    ];
+    for unmatched in [
+        UnmatchedScroll::Pass,
+        UnmatchedScroll::Region(1),
+        UnmatchedScroll::Drop,
+    ] {
+        assert_eq!(
+            route(&regions, unmatched, point(150.0, 20.0)),
+            Some(point(150.0, 20.0))
+        );
+    }
+}
+
+#[test]
+fn without_regions_every_event_passes() {
+    assert_eq!(
+        route(&[], UnmatchedScroll::Drop, point(10.0, 10.0)),
+        Some(point(10.0, 10.0))
+    );
+}
+
+#[test]
+fn unmatched_events_follow_the_policy() {
+    let regions = [
+        region(0.0, 0.0, 100.0, 50.0),
+        region(0.0, 100.0, 200.0, 50.0),
+    ];
+    let outside = point(150.0, 20.0);
+
+    assert_eq!(
+        route(&regions, UnmatchedScroll::Pass, outside),
+        Some(outside)
+    );
+    assert_eq!(route(&regions, UnmatchedScroll::Drop, outside), None);
+    // delivered at the center of the region
+    assert_eq!(
+        route(&regions, UnmatchedScroll::Region(1), outside),
+        Some(point(100.0, 125.0))
+    );
+    // an unknown region drops the event
+    assert_eq!(route(&regions, UnmatchedScroll::Region(2), outside), None);
+}
+
+#[test]
+fn overlaid_combines_regions_base_first() {
+    let overlaid = Overlaid::new(
+        Regions::new(&[(0.0, 0.0, 10.0, 10.0)], UnmatchedScroll::Drop),
+        Regions::new(&[(20.0, 0.0, 10.0, 10.0)], UnmatchedScroll::Pass),
+        true,
+    );
+
+    assert_eq!(
+        overlaid.scroll_regions(),
+        vec![region(0.0, 0.0, 10.0, 10.0), region(20.0, 0.0, 10.0, 10.0)]
+    );
+    assert_eq!(overlaid.unmatched_scroll(), UnmatchedScroll::Drop);
+}
+
+#[test]
+fn hidden_conditional_has_no_regions() {
+    let program = || Regions::new(&[(0.0, 0.0, 10.0, 10.0)], UnmatchedScroll::Drop);
+
+    let shown = Conditional::new(program(), true, |shown| *shown);
+    assert_eq!(shown.scroll_regions(), vec![region(0.0, 0.0, 10.0, 10.0)]);
+
+    let hidden = Conditional::new(program(), false, |shown| *shown);
+    assert!(hidden.scroll_regions().is_empty());
+    assert_eq!(hidden.unmatched_scroll(), UnmatchedScroll::Drop);
+}
+
+#[test]
+fn split_doesnt_forward_regions() {
+    let split = Split::new(
+        Regions::new(&[(0.0, 0.0, 10.0, 10.0)], UnmatchedScroll::Drop),
+        Regions::new(&[(0.0, 0.0, 10.0, 10.0)], UnmatchedScroll::Drop),
+        SplitDirection::Horizontal,
+        0.5,
+    );
+
+    assert!(split.scroll_regions().is_empty());
+    assert_eq!(split.unmatched_scroll(), UnmatchedScroll::Pass);
+}
@@ -0,0 +1,84 @@
+use iced_softbuffer::native::raqote::DrawTarget;
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::utils::iced::hover::{draw_indicators, SeatHovers};
+
+fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size((x, y), (w, h))
+}
+
+fn point(x: f64, y: f64) -> Point<f64, Logical> {
+    Point::from((x, y))
+}
+
+#[test]
+fn first_seat_becomes_primary() {
+    let mut hovers = SeatHovers::default();
+    assert!(hovers.claim(0));
+    assert!(!hovers.claim(1));
+    assert_eq!(hovers.primary(), Some(0));
+}
+
+#[test]
+fn secondary_seats_damage_their_indicators() {
+    let mut hovers = SeatHovers::default();
+    hovers.claim(0);
+    let regions = [rect(40, 0, 40, 20)];
+
+    // a dot around the cursor
+    assert_eq!(
+        hovers.moved(1, point(10.0, 10.0), &regions),
+        vec![rect(4, 4, 12, 12)]
+    );
+    assert!(hovers.moved(1, point(10.0, 10.0), &regions).is_empty());
+    // and the outline of the press region under it
+    assert_eq!(
+        hovers.moved(1, point(50.0, 10.0), &regions),
+        vec![rect(4, 4, 12, 12), rect(40, 0, 40, 20)]
+    );
+    assert_eq!(hovers.indicators().len(), 1);
+
+    assert_eq!(hovers.left(1), vec![rect(40, 0, 40, 20)]);
+    assert!(hovers.indicators().is_empty());
+    assert!(hovers.left(1).is_empty());
+}
+
+#[test]
+fn previous_primary_seats_get_an_indicator() {
+    let mut hovers = SeatHovers::default();
+    hovers.claim(0);
+    hovers.moved(1, point(10.0, 10.0), &[]);
+
+    let damage = hovers.set_primary(1, Some(point(30.0, 30.0)), &[]);
+    assert_eq!(damage, vec![rect(4, 4, 12, 12), rect(24, 24, 12, 12)]);
+    assert_eq!(hovers.primary(), Some(1));
+    assert_eq!(hovers.indicators().len(), 1);
+}
+
+#[test]
+fn pinned_primary_seats_stay_primary() {
+    let mut hovers = SeatHovers::default();
+    hovers.set_primary(0, None, &[]);
+    hovers.pin(true);
+    hovers.primary_left();
+    assert!(hovers.claim(0));
+    assert!(!hovers.claim(1));
+
+    hovers.pin(false);
+    hovers.primary_left();
+    assert!(hovers.claim(1), "the next seat becomes primary");
+}
+
+#[test]
+fn indicators_are_drawn_at_scale() {
+    let mut hovers = SeatHovers::default();
+    hovers.claim(0);
+    hovers.moved(1, point(10.0, 10.0), &[]);
+
+    let mut pixels = vec![0u32; 40 * 40];
+    let mut target = DrawTarget::from_backing(40, 40, &mut pixels[..]);
+    draw_indicators(&mut target, hovers.indicators(), 2.0);
+    drop(target);
+    assert_ne!(pixels[20 * 40 + 20], 0, "dot at the scaled position");
+    assert_eq!(pixels[5 * 40 + 5], 0);
+}
@@ -0,0 +1,180 @@
+use cosmic::{
+    iced::widget::{text_input, Column},
+    iced_native::{
+        keyboard::{KeyCode, Modifiers},
+        widget, Command,
+    },
+    Element,
+};
+use smithay::{
+    backend::renderer::{
+        element::{memory::MemoryRenderBufferRenderElement, AsRenderElements},
+        test::DummyRenderer,
+    },
+    reexports::calloop::LoopHandle,
+    utils::Scale,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        test_helpers::HeadlessCompositor, ContentPurpose, FocusTarget, IcedElement, Program,
+    },
+};
+
+const USER: &str = "user";
+const PASSWORD: &str = "password";
+
+#[derive(Debug, Clone)]
+enum Message {
+    User(String),
+    Password(String),
+}
+
+/// User name field above a password field, each 40 high.
+#[derive(Default)]
+struct Login {
+    user: String,
+    password: Zeroizing<String>,
+    initial_focus: Option<&'static str>,
+}
+
+impl Program for Login {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        match message {
+            Message::User(user) => self.user = user,
+            Message::Password(password) => self.password = Zeroizing::new(password),
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        Column::with_children(vec![
+            text_input("User", &self.user, Message::User)
+                .id(text_input::Id::new(USER))
+                .into(),
+            text_input("Password", &self.password, Message::Password)
+                .id(text_input::Id::new(PASSWORD))
+                .password()
+                .into(),
+        ])
+        .into()
+    }
+
+    fn initial_focus(&self) -> Option<FocusTarget> {
+        self.initial_focus
+            .map(|id| FocusTarget::Widget(widget::Id::new(id)))
+    }
+
+    fn secure_fields(&self) -> Vec<widget::Id> {
+        vec![widget::Id::new(PASSWORD)]
+    }
+}
+
+fn login(
+    compositor: &mut HeadlessCompositor<Login>,
+    initial_focus: Option<&'static str>,
+) -> IcedElement<Login> {
+    let login = Login {
+        initial_focus,
+        ..Login::default()
+    };
+    let element = compositor.insert(login, (200, 80), (0, 0));
+    compositor.settle();
+    element
+}
+
+/// Renders and presents a frame, showing all traced input so far.
+fn present(compositor: &mut HeadlessCompositor<Login>, element: &IcedElement<Login>) {
+    let mut renderer = DummyRenderer::new();
+    for _ in 0..2 {
+        let _: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+            element.render_elements(&mut renderer, (0, 0).into(), Scale::from(1.0), 1.0);
+        compositor.frame();
+    }
+}
+
+#[test]
+fn input_during_secure_entry_isnt_traced() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = login(&mut compositor, None);
+
+    compositor.pointer_enter(&element, (10.0, 60.0));
+    compositor.click(&element);
+    assert!(element.is_secure_entry_active());
+    compositor.pointer_motion(&element, (20.0, 60.0));
+    compositor.click(&element);
+    present(&mut compositor, &element);
+    assert!(element.trace_recent(usize::MAX).is_empty());
+}
+
+#[test]
+fn input_outside_of_secure_fields_is_traced() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = login(&mut compositor, None);
+
+    compositor.pointer_enter(&element, (10.0, 60.0));
+    compositor.click(&element);
+    assert!(element.is_secure_entry_active());
+
+    // focusing the user name field ends secure entry
+    compositor.pointer_motion(&element, (10.0, 20.0));
+    compositor.click(&element);
+    assert!(!element.is_secure_entry_active());
+    compositor.pointer_motion(&element, (20.0, 20.0));
+    present(&mut compositor, &element);
+    assert!(!element.trace_recent(usize::MAX).is_empty());
+}
+
+#[test]
+fn tabbing_into_the_password_field_starts_secure_entry() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = login(&mut compositor, Some(USER));
+    assert!(!element.is_secure_entry_active());
+
+    compositor.key_press(&element, KeyCode::Tab, Modifiers::empty());
+    assert!(element.is_secure_entry_active());
+    compositor.key_press(&element, KeyCode::Tab, Modifiers::SHIFT);
+    assert!(!element.is_secure_entry_active());
+}
+
+#[test]
+fn initially_focused_password_field_starts_secure_entry() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = login(&mut compositor, Some(PASSWORD));
+    assert!(element.is_secure_entry_active());
+    assert_eq!(element.input_method_purpose(), ContentPurpose::Password);
+}
+
+#[test]
+fn secure_entry_follows_keyboard_focus_of_the_element() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = login(&mut compositor, Some(PASSWORD));
+    compositor.keyboard_enter(&element);
+    assert!(element.is_secure_entry_active());
+
+    compositor.keyboard_leave(&element);
+    assert!(!element.is_secure_entry_active());
+    assert_eq!(element.input_method_purpose(), ContentPurpose::Normal);
+    // the password field kept its focus
+    compositor.keyboard_enter(&element);
+    assert!(element.is_secure_entry_active());
+}
+
+#[test]
+fn typed_passwords_are_redacted_from_debug_output() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = login(&mut compositor, Some(PASSWORD));
+    element.queue_message(Message::Password("hunter2".into()));
+    assert_eq!(element.with_program(|p| p.password.to_string()), "hunter2");
+
+    let internal = element.0.lock().unwrap();
+    assert!(!format!("{:?}", *internal).contains("hunter2"));
+}
@@ -0,0 +1,273 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, Weak,
+};
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::{
+    desktop::space::SpaceElement,
+    input::keyboard::{
+        keysyms::{KEY_a, KEY_F35},
+        ModifiersState,
+    },
+    reexports::calloop::LoopHandle,
+};
+
+use crate::{
+    config::{KeyModifiers, KeyPattern},
+    state::Data,
+    utils::iced::{
+        registry::RegisteredElement, shortcut_registry, shortcuts::ShortcutTable,
+        test_helpers::HeadlessCompositor, IcedElement, Program, ShortcutClaim, ShortcutPriority,
+        ShortcutRegistry,
+    },
+};
+
+/// Claims `pattern` while `claiming` and logs its name once triggered.
+struct Overlay {
+    name: &'static str,
+    pattern: KeyPattern,
+    claiming: Arc<AtomicBool>,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Program for Overlay {
+    type Message = ();
+
+    fn update(&mut self, _: (), _: &LoopHandle<'static, Data>) -> Command<()> {
+        self.log.lock().unwrap().push(self.name);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(self.name).into()
+    }
+
+    fn shortcuts(&self) -> Vec<(KeyPattern, Self::Message)> {
+        if self.claiming.load(Ordering::SeqCst) {
+            vec![(self.pattern.clone(), ())]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+fn ctrl() -> ModifiersState {
+    ModifiersState {
+        ctrl: true,
+        ..Default::default()
+    }
+}
+
+fn ctrl_a() -> KeyPattern {
+    KeyPattern {
+        modifiers: KeyModifiers {
+            ctrl: true,
+            ..Default::default()
+        },
+        key: KEY_a,
+    }
+}
+
+struct Setup {
+    compositor: HeadlessCompositor<Overlay>,
+    registry: ShortcutRegistry,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Setup {
+    fn new() -> Setup {
+        Setup {
+            compositor: HeadlessCompositor::new((400, 200), 1.0),
+            registry: ShortcutRegistry::default(),
+            log: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// An unmapped element, whose table is only published to the local registry.
+    fn element(
+        &self,
+        name: &'static str,
+        priority: ShortcutPriority,
+        mapped_at: Option<u64>,
+    ) -> (IcedElement<Overlay>, Arc<AtomicBool>) {
+        let claiming = Arc::new(AtomicBool::new(true));
+        let element = IcedElement::new(
+            Overlay {
+                name,
+                pattern: ctrl_a(),
+                claiming: claiming.clone(),
+                log: self.log.clone(),
+            },
+            (100, 40),
+            self.compositor.handle(),
+        );
+        self.set_table(&element, name, priority, mapped_at, false);
+        (element, claiming)
+    }
+
+    fn set_table(
+        &self,
+        element: &IcedElement<Overlay>,
+        name: &'static str,
+        priority: ShortcutPriority,
+        mapped_at: Option<u64>,
+        restricted: bool,
+    ) {
+        self.registry.set_table(
+            Arc::as_ptr(&element.0) as usize,
+            ShortcutTable {
+                element: Arc::downgrade(&element.0) as Weak<dyn RegisteredElement>,
+                name: Some(String::from(name)),
+                priority,
+                patterns: vec![ctrl_a()],
+                mapped_at,
+                restricted,
+            },
+        );
+    }
+
+    fn route(&self) -> Option<&'static str> {
+        self.log.lock().unwrap().clear();
+        self.registry
+            .route(&ctrl(), &[KEY_a])
+            .then(|| self.log.lock().unwrap()[0])
+    }
+}
+
+fn claim(element: &str, priority: ShortcutPriority) -> ShortcutClaim {
+    ShortcutClaim {
+        element: String::from(element),
+        priority,
+    }
+}
+
+#[test]
+fn higher_priorities_win() {
+    let setup = Setup::new();
+    let (_prompt, _) = setup.element("prompt", ShortcutPriority::Modal, Some(1));
+    let (_osd, _) = setup.element("osd", ShortcutPriority::Ambient, Some(2));
+
+    assert_eq!(setup.route(), Some("prompt"));
+    let dump = setup.registry.dump();
+    assert_eq!(dump.len(), 1);
+    assert_eq!(dump[0].pattern, ctrl_a());
+    assert_eq!(dump[0].owner, claim("prompt", ShortcutPriority::Modal));
+    assert_eq!(
+        dump[0].shadowed,
+        vec![claim("osd", ShortcutPriority::Ambient)]
+    );
+}
+
+#[test]
+fn ties_go_to_the_most_recently_mapped() {
+    let setup = Setup::new();
+    let (_older, _) = setup.element("older", ShortcutPriority::Ambient, Some(1));
+    let (_newer, _) = setup.element("newer", ShortcutPriority::Ambient, Some(2));
+
+    assert_eq!(setup.route(), Some("newer"));
+}
+
+#[test]
+fn unmapped_and_restricted_elements_are_skipped() {
+    let setup = Setup::new();
+    let (_unmapped, _) = setup.element("unmapped", ShortcutPriority::Modal, None);
+    let (restricted, _) = setup.element("restricted", ShortcutPriority::Modal, Some(2));
+    setup.set_table(
+        &restricted,
+        "restricted",
+        ShortcutPriority::Modal,
+        Some(2),
+        true,
+    );
+    assert_eq!(setup.route(), None);
+    assert!(setup.registry.dump().is_empty());
+
+    let (_osd, _) = setup.element("osd", ShortcutPriority::Ambient, Some(1));
+    assert_eq!(setup.route(), Some("osd"));
+}
+
+#[test]
+fn stale_claims_fall_through() {
+    let setup = Setup::new();
+    let (_osd, _) = setup.element("osd", ShortcutPriority::Ambient, Some(1));
+    let (_prompt, claiming) = setup.element("prompt", ShortcutPriority::Modal, Some(2));
+
+    // the prompt stopped claiming the combination, but its table wasn't published yet
+    claiming.store(false, Ordering::SeqCst);
+    assert_eq!(setup.route(), Some("osd"));
+}
+
+#[test]
+fn other_combinations_are_ignored() {
+    let setup = Setup::new();
+    let (_osd, _) = setup.element("osd", ShortcutPriority::Ambient, Some(1));
+
+    assert!(!setup.registry.route(&ModifiersState::default(), &[KEY_a]));
+    assert!(!setup.registry.route(&ctrl(), &[KEY_F35]));
+}
+
+// the registry of live elements is global, so a single test uses it
+#[test]
+fn elements_publish_their_shortcuts() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let pattern = KeyPattern {
+        modifiers: KeyModifiers {
+            ctrl: true,
+            alt: true,
+            logo: true,
+            ..Default::default()
+        },
+        key: KEY_F35,
+    };
+    let modifiers = ModifiersState {
+        ctrl: true,
+        alt: true,
+        logo: true,
+        ..Default::default()
+    };
+    let overlay = Overlay {
+        name: "overlay",
+        pattern: pattern.clone(),
+        claiming: Arc::new(AtomicBool::new(true)),
+        log: log.clone(),
+    };
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let owned_by_overlay = || {
+        shortcut_registry()
+            .dump()
+            .iter()
+            .any(|owner| owner.pattern == pattern && owner.owner.element == "overlay")
+    };
+
+    let element = compositor.insert(overlay, (100, 40), (0, 0));
+    element.set_name("overlay");
+    assert!(owned_by_overlay());
+    assert!(shortcut_registry().route(&modifiers, &[KEY_F35]));
+    assert_eq!(*log.lock().unwrap(), vec!["overlay"]);
+
+    // activated elements are at least focused
+    element.set_activate(true);
+    let priority = shortcut_registry()
+        .dump()
+        .into_iter()
+        .find(|owner| owner.pattern == pattern)
+        .map(|owner| owner.owner.priority);
+    assert_eq!(priority, Some(ShortcutPriority::Focused));
+
+    element.set_restricted(true);
+    assert!(!owned_by_overlay());
+    assert!(!shortcut_registry().route(&modifiers, &[KEY_F35]));
+    element.set_restricted(false);
+
+    compositor.remove(&element);
+    assert!(
+        !owned_by_overlay(),
+        "unmapped elements don't take shortcuts"
+    );
+    drop(element);
+    assert!(!shortcut_registry()
+        .dump()
+        .iter()
+        .any(|owner| owner.pattern == pattern));
+}
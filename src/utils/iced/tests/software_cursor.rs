@@ -0,0 +1,132 @@
+use cosmic::{iced::widget::text, Element};
+use smithay::{
+    backend::renderer::{
+        element::{memory::MemoryRenderBufferRenderElement, Element as _},
+        test::DummyRenderer,
+        utils::CommitCounter,
+    },
+    desktop::space::SpaceElement,
+    output::{Mode, Output, PhysicalProperties, Subpixel},
+    utils::{Logical, Physical, Rectangle, Scale},
+};
+
+use super::{assert_goldens, single_golden};
+use crate::utils::iced::{
+    cursor_moved, golden::GoldenPrograms, test_helpers::HeadlessCompositor, IcedElement, Program,
+};
+
+struct Label;
+
+impl Program for Label {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Below the cursor").into()
+    }
+}
+
+fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size((x, y), (w, h))
+}
+
+fn render(element: &IcedElement<Label>) -> MemoryRenderBufferRenderElement<DummyRenderer> {
+    let mut elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> = element
+        .render_elements(
+            &mut DummyRenderer::new(),
+            (0, 0).into(),
+            Scale::from(1.0),
+            1.0,
+        );
+    assert_eq!(elements.len(), 1);
+    elements.remove(0)
+}
+
+fn damage_since(
+    element: &IcedElement<Label>,
+    commit: CommitCounter,
+) -> Vec<Rectangle<i32, Physical>> {
+    render(element).damage_since(Scale::from(1.0), Some(commit))
+}
+
+#[test]
+fn moving_cursors_damage_the_elements_below() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (100, 50));
+    element.set_double_buffered(false);
+    let content = compositor.snapshot(&element);
+    let commit = render(&element).current_commit();
+
+    // still cursors don't damage anything
+    let footprint = rect(90, 60, 24, 24);
+    cursor_moved(compositor.output(), Some(footprint), Some(footprint));
+    compositor.frame();
+    assert!(damage_since(&element, commit).is_empty());
+
+    cursor_moved(
+        compositor.output(),
+        Some(footprint),
+        Some(rect(300, 150, 24, 24)),
+    );
+    compositor.frame();
+    assert_eq!(
+        damage_since(&element, commit),
+        vec![Rectangle::from_loc_and_size((0, 10), (14, 24))]
+    );
+    assert_eq!(compositor.snapshot(&element).pixels, content.pixels);
+}
+
+#[test]
+fn cursors_on_other_outputs_are_ignored() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (100, 50));
+    element.set_double_buffered(false);
+    let commit = render(&element).current_commit();
+
+    let other = Output::new(
+        String::from("TEST-2"),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: String::from("COSMIC"),
+            model: String::from("Test"),
+        },
+    );
+    cursor_moved(&other, None, Some(rect(100, 50, 100, 40)));
+    compositor.frame();
+    assert!(damage_since(&element, commit).is_empty());
+}
+
+#[test]
+fn cursor_damage_golden() {
+    let mut programs = GoldenPrograms::new();
+    programs.register_configured(
+        "cursor-damage",
+        || Label,
+        |element| {
+            let output = Output::new(
+                String::from("GOLDEN"),
+                PhysicalProperties {
+                    size: (0, 0).into(),
+                    subpixel: Subpixel::Unknown,
+                    make: String::from("COSMIC"),
+                    model: String::from("Golden"),
+                },
+            );
+            let mode = Mode {
+                size: (1920, 1080).into(),
+                refresh: 60_000,
+            };
+            output.add_mode(mode);
+            output.change_current_state(Some(mode), None, None, Some((0, 0).into()));
+            element.output_enter(&output, element.bbox());
+            element.set_location((0, 0).into());
+            // the area below the cursor is composited again, but never drawn differently
+            cursor_moved(
+                &output,
+                Some(rect(0, 0, 24, 24)),
+                Some(rect(60, 10, 24, 24)),
+            );
+        },
+    );
+    assert_goldens(&programs, &single_golden(1.0, (160, 40)));
+}
@@ -0,0 +1,25 @@
+use ordered_float::OrderedFloat;
+use smithay::{
+    backend::renderer::{element::memory::MemoryRenderBufferRenderElement, test::DummyRenderer},
+    utils::{Scale, Size},
+};
+
+use crate::utils::iced::test_helpers::{IcedElementTestHarness, Label};
+
+#[test]
+fn element_outside_of_a_space_renders_at_any_scale() {
+    let harness = IcedElementTestHarness::new(Label, (100, 50));
+    let element = harness.element();
+
+    let mut renderer = DummyRenderer::new();
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_standalone(&mut renderer, (0, 0).into(), Scale::from(2.0), 1.0);
+    assert!(!elements.is_empty());
+
+    let internal = element.0.lock().unwrap();
+    let buffer = internal
+        .buffers
+        .get(&OrderedFloat(2.0))
+        .expect("buffer created on demand");
+    assert_eq!(buffer.size(), Size::from((200, 100)));
+}
@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use cosmic::{
+    iced::widget::text,
+    iced_native::{futures::stream, Command},
+    Element,
+};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{test_helpers::HeadlessCompositor, IcedElement, Program, Subscription},
+};
+
+const EVERY: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Clone)]
+enum Message {
+    Tick,
+    Item(u32),
+    Enable(bool),
+}
+
+#[derive(Default)]
+struct Clock {
+    ticking: bool,
+    streaming: bool,
+    ticks: usize,
+    items: Vec<u32>,
+}
+
+impl Program for Clock {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        match message {
+            Message::Tick => self.ticks += 1,
+            Message::Item(item) => self.items.push(item),
+            Message::Enable(ticking) => self.ticking = ticking,
+        }
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(self.ticks.to_string()).into()
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription<Self::Message>> {
+        let mut subscriptions = Vec::new();
+        if self.ticking {
+            subscriptions.push(Subscription::interval("tick", EVERY, || Message::Tick));
+        }
+        if self.streaming {
+            subscriptions
+                .push(Subscription::stream("items", stream::iter(1..=3)).map(Message::Item));
+        }
+        subscriptions
+    }
+}
+
+fn running(element: &IcedElement<Clock>) -> usize {
+    element.0.lock().unwrap().subscriptions.len()
+}
+
+/// Lets a few intervals pass and applies their messages.
+fn run(compositor: &mut HeadlessCompositor<Clock>) {
+    std::thread::sleep(EVERY * 3);
+    compositor.dispatch(Duration::ZERO);
+    compositor.settle();
+}
+
+#[test]
+fn subscriptions_start_and_stop_with_the_program_state() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Clock::default(), (100, 50), (0, 0));
+    assert_eq!(running(&element), 0);
+
+    element.queue_message(Message::Enable(true));
+    compositor.settle();
+    assert_eq!(running(&element), 1);
+    run(&mut compositor);
+    assert!(element.with_program(|p| p.ticks) > 0);
+
+    element.queue_message(Message::Enable(false));
+    compositor.settle();
+    assert_eq!(running(&element), 0);
+    let ticks = element.with_program(|p| p.ticks);
+    run(&mut compositor);
+    assert_eq!(element.with_program(|p| p.ticks), ticks);
+}
+
+#[test]
+fn returning_a_subscription_again_keeps_it_running() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(
+        Clock {
+            ticking: true,
+            ..Clock::default()
+        },
+        (100, 50),
+        (0, 0),
+    );
+    let token = element.0.lock().unwrap().subscriptions[0].1;
+
+    element.queue_message(Message::Enable(true));
+    compositor.settle();
+    assert_eq!(element.0.lock().unwrap().subscriptions[0].1, token);
+}
+
+#[test]
+fn stream_items_are_delivered_in_order() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(
+        Clock {
+            streaming: true,
+            ..Clock::default()
+        },
+        (100, 50),
+        (0, 0),
+    );
+
+    run(&mut compositor);
+    assert_eq!(element.with_program(|p| p.items.clone()), vec![1, 2, 3]);
+}
+
+#[test]
+fn subscriptions_are_identified_by_their_key() {
+    let a = Subscription::interval("tick", EVERY, || ());
+    let b = Subscription::interval("tick", EVERY * 2, || ());
+    let c = Subscription::interval("tock", EVERY, || ());
+    assert_eq!(a.id(), b.id());
+    assert_ne!(a.id(), c.id());
+    assert_eq!(a.map(|()| 1).id(), b.id());
+}
@@ -0,0 +1,42 @@
+use cosmic::{iced::widget::text, Element};
+use smithay::backend::input::ButtonState;
+
+use crate::utils::iced::{test_helpers::HeadlessCompositor, Program, RingEventSource};
+
+/// Brush settings of a painting tool, controlled with the pad.
+#[derive(Default)]
+struct Brush {
+    size: f64,
+    eraser: bool,
+}
+
+impl Program for Brush {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text(format!("{} {}", self.size, self.eraser)).into()
+    }
+
+    fn on_pad_button(&mut self, button: u32, state: ButtonState) {
+        if button == 0 && state == ButtonState::Pressed {
+            self.eraser = !self.eraser;
+        }
+    }
+
+    fn on_pad_ring(&mut self, _source: RingEventSource, degrees: f64) {
+        self.size = degrees / 10.0;
+    }
+}
+
+#[test]
+fn pad_events_change_the_program() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Brush::default(), (100, 50), (0, 0));
+
+    compositor.pad_button(&element, 0, ButtonState::Pressed);
+    compositor.pad_button(&element, 0, ButtonState::Released);
+    compositor.pad_ring(&element, 90.0);
+
+    assert!(element.with_program(|brush| brush.eraser));
+    assert_eq!(element.with_program(|brush| brush.size), 9.0);
+}
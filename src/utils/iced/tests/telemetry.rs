@@ -0,0 +1,111 @@
+use std::{
+    io::Read,
+    os::unix::net::UnixStream,
+    time::{Duration, Instant},
+};
+
+use cosmic::{iced::widget::text, Element};
+
+use crate::utils::iced::{
+    init_telemetry_socket, telemetry, test_helpers::HeadlessCompositor, FrameTelemetry, Program,
+};
+
+fn frame(element_id: u64, name: Option<&str>) -> FrameTelemetry {
+    FrameTelemetry {
+        element_id,
+        name: name.map(String::from),
+        update: Duration::from_micros(120),
+        draw: Duration::from_micros(3400),
+        upload: Duration::from_micros(560),
+        buffer_size: (200, 100).into(),
+        skipped: false,
+        sanitized: 2,
+    }
+}
+
+/// Decodes the records of `bytes`, as documented in `telemetry`.
+fn decode(mut bytes: &[u8]) -> Vec<FrameTelemetry> {
+    fn take<'a>(bytes: &mut &'a [u8], len: usize) -> &'a [u8] {
+        let (head, tail) = bytes.split_at(len);
+        *bytes = tail;
+        head
+    }
+    let u64 = |bytes: &mut &[u8]| u64::from_le_bytes(take(bytes, 8).try_into().unwrap());
+    let i32 = |bytes: &mut &[u8]| i32::from_le_bytes(take(bytes, 4).try_into().unwrap());
+
+    let mut records = Vec::new();
+    while !bytes.is_empty() {
+        let len = u32::from_le_bytes(take(&mut bytes, 4).try_into().unwrap()) as usize;
+        let mut record = take(&mut bytes, len);
+        let element_id = u64(&mut record);
+        let update = Duration::from_micros(u64(&mut record));
+        let draw = Duration::from_micros(u64(&mut record));
+        let upload = Duration::from_micros(u64(&mut record));
+        let buffer_size = (i32(&mut record), i32(&mut record)).into();
+        let skipped = take(&mut record, 1)[0] == 1;
+        let sanitized = u32::from_le_bytes(take(&mut record, 4).try_into().unwrap());
+        let name_len = u16::from_le_bytes(take(&mut record, 2).try_into().unwrap()) as usize;
+        let name = String::from_utf8(take(&mut record, name_len).to_vec()).unwrap();
+        assert!(record.is_empty(), "Record is longer than its fields");
+        records.push(FrameTelemetry {
+            element_id,
+            name: (!name.is_empty()).then_some(name),
+            update,
+            draw,
+            upload,
+            buffer_size,
+            skipped,
+            sanitized,
+        });
+    }
+    records
+}
+
+#[test]
+fn records_roundtrip_through_the_wire_format() {
+    let frames = [frame(1, Some("panel")), frame(2, None)];
+    let mut bytes = Vec::new();
+    for frame in &frames {
+        frame.encode(&mut bytes);
+    }
+    assert_eq!(decode(&bytes), frames);
+}
+
+#[test]
+fn connected_clients_receive_records() {
+    struct Label;
+
+    impl Program for Label {
+        type Message = ();
+
+        fn view(&self) -> Element<'_, Self::Message> {
+            text("Label").into()
+        }
+    }
+
+    let mut compositor = HeadlessCompositor::<Label>::new((400, 200), 1.0);
+    let path = std::env::temp_dir().join(format!("cosmic-comp-telemetry-{}", std::process::id()));
+    init_telemetry_socket(&compositor.handle(), &path).unwrap();
+    assert!(!telemetry::is_enabled());
+
+    let mut client = UnixStream::connect(&path).unwrap();
+    compositor.dispatch(Duration::ZERO);
+    assert!(telemetry::is_enabled());
+
+    // other tests may render elements meanwhile, ours is found by its id
+    let ours = frame(u64::MAX - 479, Some("telemetry-test"));
+    telemetry::record(ours.clone());
+    // until the records were flushed at least once
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(300) {
+        compositor.dispatch(Duration::from_millis(50));
+    }
+
+    client
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let mut bytes = vec![0; 64 * 1024];
+    let len = client.read(&mut bytes).unwrap();
+    assert!(decode(&bytes[..len]).contains(&ours));
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,40 @@
+use cosmic::Theme;
+use smithay::{
+    backend::renderer::{
+        element::{memory::MemoryRenderBufferRenderElement, Element as _, Id},
+        test::DummyRenderer,
+        utils::CommitCounter,
+    },
+    utils::Scale,
+};
+
+use crate::utils::iced::{
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement,
+};
+
+/// Id and commit of the rendered buffer, which decide whether it is uploaded again.
+fn render(element: &IcedElement<Label>, renderer: &mut DummyRenderer) -> (Id, CommitCounter) {
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_elements(renderer, (0, 0).into(), Scale::from(1.0), 1.0);
+    assert_eq!(elements.len(), 1);
+    (elements[0].id().clone(), elements[0].current_commit())
+}
+
+#[test]
+fn unchanged_buffers_are_not_uploaded_again() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    element.set_double_buffered(false);
+    let mut renderer = DummyRenderer::new();
+
+    let first = render(&element, &mut renderer);
+    compositor.frame();
+    assert_eq!(render(&element, &mut renderer), first);
+
+    element.set_theme(Theme::light());
+    compositor.frame();
+    let (id, commit) = render(&element, &mut renderer);
+    assert_eq!(id, first.0, "the same buffer is kept");
+    assert_ne!(commit, first.1, "its damage is uploaded");
+}
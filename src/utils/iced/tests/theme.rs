@@ -0,0 +1,86 @@
+use cosmic::Theme;
+use ordered_float::OrderedFloat;
+
+use crate::utils::iced::{
+    registry::{DefaultChanges, RegisteredElement},
+    test_helpers::{HeadlessCompositor, IcedElementTestHarness, Label},
+    theme, IcedElement,
+};
+
+fn needs_redraw(element: &IcedElement<Label>, scale: f64) -> bool {
+    let internal = element.0.lock().unwrap();
+    internal
+        .buffers
+        .get(&OrderedFloat(scale))
+        .map_or(true, |buffer| buffer.needs_redraw())
+}
+
+#[test]
+fn widgets_follow_a_new_theme() {
+    let harness = IcedElementTestHarness::new(Label, (100, 30));
+    let dark = harness.snapshot(1.0);
+
+    harness.set_theme(Theme::light());
+    let light = harness.snapshot(1.0);
+    assert_ne!(dark, light);
+
+    harness.set_theme(Theme::dark());
+    assert_eq!(harness.snapshot(1.0), dark);
+}
+
+#[test]
+fn setting_a_theme_redraws_programs_without_hooks() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 30), (0, 0));
+    assert!(!needs_redraw(&element, 1.0));
+
+    element.set_theme(Theme::light());
+    assert!(needs_redraw(&element, 1.0));
+    let internal = element.0.lock().unwrap();
+    assert!(internal.theme_overridden);
+    assert!(theme::same_colors(&internal.theme, &Theme::light()));
+}
+
+/// Makes `element` look out of date with the default theme, without overriding it.
+fn drift_from_default(element: &IcedElement<Label>) {
+    let mut internal = element.lock();
+    internal.theme = Theme::light();
+}
+
+#[test]
+fn themes_compare_by_their_colors() {
+    assert!(theme::same_colors(&Theme::dark(), &Theme::dark()));
+    assert!(!theme::same_colors(&Theme::dark(), &Theme::light()));
+    // the default is dark, storing it again changes nothing
+    assert!(!theme::store_default(Theme::dark()));
+}
+
+#[test]
+fn elements_follow_a_changed_default() {
+    let harness = IcedElementTestHarness::new(Label, (100, 30));
+    let element = harness.element();
+    let dark = harness.snapshot(1.0);
+
+    drift_from_default(element);
+    element.0.defaults_changed(DefaultChanges::THEME);
+    let internal = element.0.lock().unwrap();
+    assert!(theme::same_colors(&internal.theme, &theme::default_theme()));
+    drop(internal);
+    assert_eq!(harness.snapshot(1.0), dark);
+}
+
+#[test]
+fn overridden_themes_ignore_the_default_until_refreshed() {
+    let harness = IcedElementTestHarness::new(Label, (100, 30));
+    let element = harness.element();
+    let dark = harness.snapshot(1.0);
+
+    element.set_theme(Theme::light());
+    let light = harness.snapshot(1.0);
+    element.0.defaults_changed(DefaultChanges::THEME);
+    assert_eq!(harness.snapshot(1.0), light);
+
+    element.refresh_theme();
+    assert!(!element.0.lock().unwrap().theme_overridden);
+    assert_eq!(harness.snapshot(1.0), dark);
+}
@@ -0,0 +1,102 @@
+use cosmic::{
+    iced::{
+        widget::{button, text},
+        Length,
+    },
+    iced_native::Command,
+    Element,
+};
+use smithay::{backend::input::TouchSlot, reexports::calloop::LoopHandle};
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        test_helpers::{HeadlessCompositor, IcedElementTestHarness},
+        Program,
+    },
+};
+
+#[derive(Debug, Clone)]
+enum Message {
+    Pressed,
+}
+
+#[derive(Default)]
+struct Counter {
+    presses: usize,
+}
+
+impl Program for Counter {
+    type Message = Message;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        let Message::Pressed = message;
+        self.presses += 1;
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        button(text("Press"))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .on_press(Message::Pressed)
+            .into()
+    }
+}
+
+fn presses(harness: &IcedElementTestHarness<Counter>) -> usize {
+    harness.element().with_program(|p| p.presses)
+}
+
+#[test]
+fn tap_presses_button() {
+    let harness = IcedElementTestHarness::new(Counter::default(), (100, 40));
+    let slot = TouchSlot::from(Some(0));
+
+    harness.element().touch_down(slot, (50.0, 20.0).into());
+    assert_eq!(
+        presses(&harness),
+        0,
+        "buttons fire once the finger is lifted"
+    );
+    harness.element().touch_up(slot);
+    assert_eq!(presses(&harness), 1);
+}
+
+#[test]
+fn finger_lifted_outside_doesnt_press_button() {
+    let harness = IcedElementTestHarness::new(Counter::default(), (100, 40));
+    let slot = TouchSlot::from(Some(0));
+
+    harness.element().touch_down(slot, (50.0, 20.0).into());
+    harness.element().touch_motion(slot, (150.0, 20.0).into());
+    harness.element().touch_up(slot);
+    assert_eq!(presses(&harness), 0);
+}
+
+#[test]
+fn cancelled_touch_doesnt_press_button() {
+    let harness = IcedElementTestHarness::new(Counter::default(), (100, 40));
+    let slot = TouchSlot::from(Some(0));
+
+    harness.element().touch_down(slot, (50.0, 20.0).into());
+    harness.element().touch_cancel();
+    harness.element().touch_up(slot);
+    assert_eq!(presses(&harness), 0);
+}
+
+#[test]
+fn tap_through_the_touch_target_presses_button() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Counter::default(), (100, 40), (0, 0));
+    let slot = TouchSlot::from(Some(0));
+
+    compositor.touch_down(&element, slot, (50.0, 20.0));
+    compositor.touch_up(&element, slot);
+    compositor.settle();
+    assert_eq!(element.with_program(|p| p.presses), 1);
+}
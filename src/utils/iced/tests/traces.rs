@@ -0,0 +1,114 @@
+use std::time::{Duration, Instant};
+
+use smithay::{
+    backend::renderer::{element::memory::MemoryRenderBufferRenderElement, test::DummyRenderer},
+    utils::Scale,
+};
+
+use crate::utils::iced::{
+    test_helpers::{HeadlessCompositor, Label},
+    trace::{Deferral, EventTrace, EventTracer, LatencyStats, TraceStage, TracedInput},
+};
+
+fn trace_with_latency(queued: Instant, millis: u64) -> EventTrace {
+    EventTrace {
+        id: millis,
+        input: TracedInput::Motion,
+        serial: None,
+        queued,
+        updated: Some(queued),
+        rasterized: Some(queued),
+        imported: Some(queued + Duration::from_millis(millis)),
+        deferrals: Vec::new(),
+    }
+}
+
+#[test]
+fn traces_pass_all_stages() {
+    let mut tracer = EventTracer::default();
+    tracer.queued(TracedInput::Motion, None);
+    tracer.update_deferred(Deferral::AwaitingFrame);
+    tracer.update_deferred(Deferral::AwaitingFrame);
+    let updated = Instant::now();
+    tracer.updated(updated);
+    tracer.queued(TracedInput::Axis, None);
+    tracer.raster_deferred(Deferral::RateLimited);
+    let rasterized = Instant::now();
+    tracer.rasterized(rasterized);
+    tracer.imported(Instant::now());
+
+    // the axis event missed the update
+    let recent = tracer.recent(10);
+    assert_eq!(recent.len(), 1);
+    let trace = &recent[0];
+    assert_eq!(trace.input, TracedInput::Motion);
+    assert_eq!(trace.updated, Some(updated));
+    assert_eq!(trace.rasterized, Some(rasterized));
+    assert!(trace.latency().is_some());
+    assert_eq!(
+        trace.deferrals,
+        vec![
+            (TraceStage::Update, Deferral::AwaitingFrame),
+            (TraceStage::Raster, Deferral::RateLimited),
+        ]
+    );
+
+    tracer.updated(Instant::now());
+    tracer.imported(Instant::now());
+    let recent = tracer.recent(10);
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[1].input, TracedInput::Axis);
+    assert_eq!(recent[1].rasterized, None, "no draw after its update");
+    assert_eq!(tracer.recent(1), recent[1..]);
+}
+
+#[test]
+fn suspended_tracers_drop_pending_traces() {
+    let mut tracer = EventTracer::default();
+    tracer.queued(TracedInput::Button, None);
+    tracer.set_suspended(true);
+    tracer.queued(TracedInput::Button, None);
+    tracer.set_suspended(false);
+    tracer.updated(Instant::now());
+    tracer.imported(Instant::now());
+    assert!(tracer.recent(10).is_empty());
+}
+
+#[test]
+fn latency_percentiles() {
+    let now = Instant::now();
+    let traces = (1..=100)
+        .map(|millis| trace_with_latency(now, millis))
+        .collect::<Vec<_>>();
+    let stats = LatencyStats::from_traces(&traces);
+    assert_eq!(stats.count, 100);
+    assert_eq!(stats.min, Duration::from_millis(1));
+    assert_eq!(stats.p50, Duration::from_millis(50));
+    assert_eq!(stats.p90, Duration::from_millis(90));
+    assert_eq!(stats.p99, Duration::from_millis(99));
+    assert_eq!(stats.max, Duration::from_millis(100));
+    assert_eq!(LatencyStats::from_traces(&[]), LatencyStats::default());
+}
+
+#[test]
+fn pointer_motion_is_traced_until_shown() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    compositor.pointer_enter(&element, (10.0, 10.0));
+    compositor.pointer_motion(&element, (20.0, 10.0));
+    compositor.settle();
+    assert!(element.trace_recent(10).is_empty(), "not shown yet");
+
+    let _: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> = element.render_elements(
+        &mut DummyRenderer::new(),
+        (0, 0).into(),
+        Scale::from(1.0),
+        1.0,
+    );
+    let traces = element.trace_recent(10);
+    assert!(!traces.is_empty());
+    assert!(traces
+        .iter()
+        .all(|trace| trace.input == TracedInput::Motion && trace.imported.is_some()));
+    assert_eq!(element.input_latency().count, traces.len());
+}
@@ -0,0 +1,126 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::utils::iced::{
+    animation::Curve,
+    test_helpers::{HeadlessCompositor, Label},
+    transition::{Phase, SpaceTransition, TransitionParams},
+    IcedElement, SlideDirection, TransitionKind, TransitionSpec,
+};
+
+fn spec(kind: TransitionKind) -> TransitionSpec {
+    TransitionSpec {
+        kind,
+        curve: Curve::Linear,
+        duration: Duration::from_millis(100),
+    }
+}
+
+fn slide(direction: SlideDirection) -> TransitionSpec {
+    spec(TransitionKind::Slide {
+        direction,
+        distance: 100,
+    })
+}
+
+fn params(element: &IcedElement<Label>) -> Option<TransitionParams> {
+    element
+        .0
+        .lock()
+        .unwrap()
+        .transition
+        .as_ref()
+        .map(SpaceTransition::params)
+}
+
+fn counter() -> (Arc<AtomicUsize>, impl FnOnce() + Send + 'static) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let clone = count.clone();
+    (count, move || {
+        clone.fetch_add(1, Ordering::SeqCst);
+    })
+}
+
+#[test]
+fn transitions_map_progress_to_render_parameters() {
+    let mut slide = SpaceTransition::new(Phase::Enter, slide(SlideDirection::Up), None, None);
+    slide.set_progress(0.25);
+    assert_eq!(slide.params().offset, (0.0, -75.0).into());
+    assert_eq!(slide.params().alpha, 1.0);
+
+    let mut fade = SpaceTransition::new(Phase::Exit, spec(TransitionKind::Fade), None, None);
+    fade.set_progress(0.25);
+    assert_eq!(fade.params().alpha, 0.75);
+
+    let mut scale =
+        SpaceTransition::new(Phase::Enter, spec(TransitionKind::Scale(0.5)), None, None);
+    scale.set_progress(0.0);
+    assert_eq!(scale.params().scale, 0.5);
+    assert_eq!(scale.params().alpha, 0.0);
+    scale.set_progress(1.0);
+    assert!(scale.is_complete());
+    assert_eq!(scale.params(), TransitionParams::default());
+}
+
+#[test]
+fn enter_direction_can_be_overridden() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    element.set_space_transition(Some(slide(SlideDirection::Left)), None);
+
+    element.begin_enter_transition(Some(SlideDirection::Right));
+    element.set_transition_progress(0.5);
+    assert_eq!(params(&element).unwrap().offset, (50.0, 0.0).into());
+
+    // completed enter transitions are dropped
+    element.set_transition_progress(1.0);
+    assert_eq!(params(&element), None);
+}
+
+#[test]
+fn exit_reverses_a_running_enter() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    let fade = spec(TransitionKind::Fade);
+    element.set_space_transition(Some(fade), Some(fade));
+
+    element.begin_enter_transition(None);
+    element.set_transition_progress(0.6);
+    element.begin_exit_transition(None, || {});
+    // continues from the current visibility
+    assert_eq!(params(&element).unwrap().alpha, 0.6);
+}
+
+#[test]
+fn exit_completion_is_called_once_from_the_event_loop() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+    element.set_space_transition(None, Some(spec(TransitionKind::Fade)));
+
+    let (count, on_complete) = counter();
+    element.begin_exit_transition(None, on_complete);
+    element.set_transition_progress(1.0);
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+
+    compositor.dispatch(Duration::ZERO);
+    compositor.dispatch(Duration::ZERO);
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+    // stays hidden until unmapped or entered again
+    assert_eq!(params(&element).unwrap().alpha, 0.0);
+}
+
+#[test]
+fn exit_without_transition_completes_right_away() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 40), (0, 0));
+
+    let (count, on_complete) = counter();
+    element.begin_exit_transition(None, on_complete);
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+    assert_eq!(params(&element), None);
+}
@@ -0,0 +1,81 @@
+use cosmic::Element;
+use smithay::utils::Point;
+
+use super::{assert_goldens, single_golden};
+use crate::utils::iced::{
+    golden::GoldenPrograms, test_helpers::IcedElementTestHarness, truncating_text, Program,
+    TruncatePolicy,
+};
+
+const PATH: &str = "~/Documents/work/project/src/main.rs";
+
+/// A path, truncated according to its policy.
+struct Path {
+    content: &'static str,
+    policy: TruncatePolicy,
+}
+
+impl Program for Path {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        truncating_text(self.content, self.policy).size(16).into()
+    }
+}
+
+fn path(policy: TruncatePolicy) -> Path {
+    Path {
+        content: PATH,
+        policy,
+    }
+}
+
+#[test]
+fn truncated_texts_offer_their_full_text() {
+    let harness = IcedElementTestHarness::new(path(TruncatePolicy::Middle), (120, 30));
+    let element = harness.element();
+    assert_eq!(
+        element.full_text_at(Point::from((10.0, 10.0))).as_deref(),
+        Some(PATH)
+    );
+    assert_eq!(element.full_text_at(Point::from((10.0, 40.0))), None);
+}
+
+#[test]
+fn fitting_texts_are_not_reported() {
+    let harness = IcedElementTestHarness::new(
+        Path {
+            content: "main.rs",
+            policy: TruncatePolicy::End,
+        },
+        (120, 30),
+    );
+    assert_eq!(
+        harness.element().full_text_at(Point::from((10.0, 10.0))),
+        None
+    );
+}
+
+#[test]
+fn relayouts_truncate_again() {
+    let harness = IcedElementTestHarness::new(path(TruncatePolicy::End), (120, 30));
+    let element = harness.element();
+    element.resize((600, 30).into());
+    assert_eq!(element.full_text_at(Point::from((10.0, 10.0))), None);
+    element.resize((80, 30).into());
+    assert_eq!(
+        element.full_text_at(Point::from((10.0, 10.0))).as_deref(),
+        Some(PATH)
+    );
+}
+
+#[test]
+fn truncation_policies_golden() {
+    let mut programs = GoldenPrograms::new();
+    programs.register("truncate-end", || path(TruncatePolicy::End));
+    programs.register("truncate-middle", || path(TruncatePolicy::Middle));
+    programs.register("truncate-start", || {
+        path(TruncatePolicy::StartPreserveExtension)
+    });
+    assert_goldens(&programs, &single_golden(1.0, (160, 30)));
+}
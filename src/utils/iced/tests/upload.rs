@@ -0,0 +1,35 @@
+use smithay::{
+    backend::renderer::{
+        element::{memory::MemoryRenderBufferRenderElement, AsRenderElements},
+        test::DummyRenderer,
+    },
+    utils::Scale,
+};
+
+use crate::utils::iced::{
+    test_helpers::{HeadlessCompositor, Label},
+    upload_failed, MAX_UPLOAD_FAILURES,
+};
+
+#[test]
+fn failed_uploads_are_rasterized_again_until_giving_up() {
+    let mut failures = 0;
+    let retries = (0..MAX_UPLOAD_FAILURES + 2)
+        .map(|_| upload_failed(&mut failures, 1.0, &"lost context"))
+        .collect::<Vec<_>>();
+    assert_eq!(retries, vec![true, true, false, false, false]);
+    assert_eq!(failures, MAX_UPLOAD_FAILURES + 2);
+}
+
+#[test]
+fn successful_upload_resets_the_failures() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (0, 0));
+    element.0.lock().unwrap().upload_failures = MAX_UPLOAD_FAILURES;
+
+    let mut renderer = DummyRenderer::new();
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_elements(&mut renderer, (0, 0).into(), Scale::from(1.0), 1.0);
+    assert_eq!(elements.len(), 1);
+    assert_eq!(element.0.lock().unwrap().upload_failures, 0);
+}
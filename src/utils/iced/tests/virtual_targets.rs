@@ -0,0 +1,126 @@
+use smithay::{
+    backend::renderer::{
+        element::{memory::MemoryRenderBufferRenderElement, Element as _},
+        test::DummyRenderer,
+    },
+    utils::{Rectangle, Scale, Size},
+};
+
+use crate::utils::iced::{
+    test_helpers::{HeadlessCompositor, Label},
+    IcedElement, VirtualTargetId,
+};
+
+const SCREENCAST: VirtualTargetId = VirtualTargetId(1);
+
+/// Scales of the element's buffers and their number of buffers, ordered by scale.
+fn buffers(element: &IcedElement<Label>) -> Vec<(f64, u8)> {
+    let internal = element.0.lock().unwrap();
+    let mut buffers = internal
+        .buffers
+        .iter()
+        .map(|(scale, buffer)| (scale.0, buffer.buffer_count()))
+        .collect::<Vec<_>>();
+    buffers.sort_by(|a, b| a.0.total_cmp(&b.0));
+    buffers
+}
+
+fn setup() -> (HeadlessCompositor<Label>, IcedElement<Label>) {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Label, (100, 50), (150, 100));
+    (compositor, element)
+}
+
+#[test]
+fn virtual_target_of_another_scale_gets_its_own_buffer() {
+    let (_compositor, element) = setup();
+
+    element.add_virtual_target(
+        SCREENCAST,
+        Rectangle::from_loc_and_size((100, 100), (400, 300)),
+        2.0,
+    );
+    assert_eq!(buffers(&element), vec![(1.0, 1), (2.0, 1)]);
+
+    element.remove_virtual_target(SCREENCAST);
+    assert_eq!(buffers(&element), vec![(1.0, 1)]);
+}
+
+#[test]
+fn virtual_target_sharing_the_scale_of_an_output_shares_its_buffer() {
+    let (_compositor, element) = setup();
+
+    element.add_virtual_target(
+        SCREENCAST,
+        Rectangle::from_loc_and_size((100, 100), (400, 300)),
+        1.0,
+    );
+    assert_eq!(
+        buffers(&element),
+        vec![(1.0, 2)],
+        "the shared buffer is double buffered"
+    );
+}
+
+#[test]
+fn virtual_render_is_relative_to_the_virtual_output() {
+    let (_compositor, element) = setup();
+    element.add_virtual_target(
+        SCREENCAST,
+        Rectangle::from_loc_and_size((100, 100), (400, 300)),
+        2.0,
+    );
+
+    let mut renderer = DummyRenderer::new();
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_for_virtual(&mut renderer, SCREENCAST, (150, 100).into());
+    assert_eq!(elements.len(), 1);
+    assert_eq!(
+        elements[0].geometry(Scale::from(2.0)),
+        Rectangle::from_loc_and_size((100, 0), (200, 100))
+    );
+}
+
+#[test]
+fn unknown_virtual_targets_render_nothing() {
+    let (_compositor, element) = setup();
+
+    let mut renderer = DummyRenderer::new();
+    let elements: Vec<MemoryRenderBufferRenderElement<DummyRenderer>> =
+        element.render_for_virtual(&mut renderer, SCREENCAST, (150, 100).into());
+    assert!(elements.is_empty());
+    assert!(element.capture_virtual(SCREENCAST).is_none());
+}
+
+#[test]
+fn capture_rasterizes_at_the_virtual_scale() {
+    let (_compositor, element) = setup();
+    element.add_virtual_target(
+        SCREENCAST,
+        Rectangle::from_loc_and_size((0, 0), (400, 300)),
+        2.0,
+    );
+
+    let (size, pixels) = element.capture_virtual(SCREENCAST).unwrap();
+    assert_eq!(size, Size::from((200, 100)));
+    assert_eq!(pixels.len(), 200 * 100);
+    assert!(pixels.iter().any(|pixel| *pixel != 0), "the label is drawn");
+}
+
+#[test]
+fn capture_matches_the_rendered_content() {
+    let (compositor, element) = setup();
+    element.add_virtual_target(
+        SCREENCAST,
+        Rectangle::from_loc_and_size((0, 0), (400, 300)),
+        1.0,
+    );
+
+    let (size, pixels) = element.capture_virtual(SCREENCAST).unwrap();
+    let snapshot = compositor.snapshot(&element);
+    assert_eq!(size, snapshot.size);
+    assert_eq!(pixels, snapshot.pixels);
+
+    element.remove_virtual_target(SCREENCAST);
+    assert!(element.capture_virtual(SCREENCAST).is_none());
+}
@@ -0,0 +1,60 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{test_helpers::HeadlessCompositor, Program},
+};
+
+/// Records the messages it receives.
+#[derive(Default)]
+struct Log(Arc<Mutex<Vec<u32>>>);
+
+impl Program for Log {
+    type Message = u32;
+
+    fn update(&mut self, message: u32, _: &LoopHandle<'static, Data>) -> Command<u32> {
+        self.0.lock().unwrap().push(message);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Log").into()
+    }
+}
+
+#[test]
+fn messages_from_other_threads_wake_the_element() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Log(log.clone()), (100, 40), (0, 0));
+    let sender = element.message_sender();
+    std::thread::spawn(move || {
+        assert!(sender.send(1));
+        assert!(sender.send(2));
+    })
+    .join()
+    .unwrap();
+    assert!(log.lock().unwrap().is_empty(), "delivered on dispatch");
+
+    // no input or frame is needed to process them
+    compositor.dispatch(Duration::from_millis(100));
+    assert_eq!(*log.lock().unwrap(), vec![1, 2]);
+}
+
+#[test]
+fn senders_of_dropped_elements_fail() {
+    let mut compositor = HeadlessCompositor::new((400, 200), 1.0);
+    let element = compositor.insert(Log::default(), (100, 40), (0, 0));
+    let sender = element.message_sender();
+    assert!(sender.clone().send(1));
+
+    compositor.remove(&element);
+    drop(element);
+    assert!(!sender.send(2));
+}
@@ -0,0 +1,112 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use cosmic::{iced::widget::text, iced_native::Command, Element};
+use smithay::reexports::calloop::LoopHandle;
+
+use crate::{
+    state::Data,
+    utils::iced::{
+        test_helpers::IcedElementTestHarness,
+        window_feed::{window_feed, FocusedWindowInfo, WindowFeed},
+        Program,
+    },
+};
+
+/// Records the focused windows it was told about.
+#[derive(Default)]
+struct Dock {
+    windows: Vec<FocusedWindowInfo>,
+}
+
+impl Program for Dock {
+    type Message = FocusedWindowInfo;
+
+    fn update(
+        &mut self,
+        info: Self::Message,
+        _loop_handle: &LoopHandle<'static, Data>,
+    ) -> Command<Self::Message> {
+        self.windows.push(info);
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Dock").into()
+    }
+}
+
+fn window(app_id: &str, title: &str) -> FocusedWindowInfo {
+    FocusedWindowInfo {
+        app_id: String::from(app_id),
+        title: String::from(title),
+        ..Default::default()
+    }
+}
+
+fn seen(harness: &IcedElementTestHarness<Dock>) -> Vec<FocusedWindowInfo> {
+    harness.element().with_program(|p| p.windows.clone())
+}
+
+#[test]
+fn feed_delivers_distinct_changes_until_the_binding_is_gone() {
+    let feed = WindowFeed::default();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    feed.bind(Arc::new(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst) < 2
+    }));
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        1,
+        "the current info is delivered on bind"
+    );
+
+    feed.publish(window("org.cosmic.Term", "Terminal"));
+    feed.publish(window("org.cosmic.Term", "Terminal"));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    // the binding reported itself gone and is pruned
+    feed.publish(window("org.cosmic.Files", "Files"));
+    feed.publish(window("org.cosmic.Edit", "Editor"));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    assert_eq!(feed.current(), window("org.cosmic.Edit", "Editor"));
+}
+
+#[test]
+fn redacted_info_only_has_the_app_id() {
+    let mut info = window("org.cosmic.Term", "secret.txt");
+    info.maximized = true;
+    assert_eq!(info.redacted(), window("org.cosmic.Term", ""));
+}
+
+// the only test publishing to the global feed, to not race with others
+#[test]
+fn bound_elements_receive_the_focused_window() {
+    let full = IcedElementTestHarness::new(Dock::default(), (100, 40));
+    full.element().bind_window_feed(FocusedWindowInfo::clone);
+    let restricted = IcedElementTestHarness::new(Dock::default(), (100, 40));
+    restricted.element().set_restricted(true);
+    restricted
+        .element()
+        .bind_window_feed(FocusedWindowInfo::clone);
+    assert_eq!(seen(&full), vec![window_feed().current()]);
+
+    window_feed().publish(window("org.cosmic.FeedTest", "First"));
+    window_feed().publish(window("org.cosmic.FeedTest", "Second"));
+
+    assert_eq!(
+        &seen(&full)[1..],
+        &[
+            window("org.cosmic.FeedTest", "First"),
+            window("org.cosmic.FeedTest", "Second")
+        ]
+    );
+    // title changes are invisible to restricted elements
+    assert_eq!(
+        &seen(&restricted)[1..],
+        &[window("org.cosmic.FeedTest", "")]
+    );
+}
@@ -0,0 +1,42 @@
+use cosmic::{iced::widget::text, Element};
+use smithay::desktop::space::{RenderZindex, SpaceElement};
+
+use crate::utils::iced::{test_helpers::IcedElementTestHarness, Program};
+
+struct Overlay(Option<u8>);
+
+impl Program for Overlay {
+    type Message = ();
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        text("Overlay").into()
+    }
+
+    fn z_index(&self) -> u8 {
+        self.0.unwrap_or(RenderZindex::Shell as u8)
+    }
+}
+
+#[test]
+fn elements_default_to_the_shell_layer() {
+    let harness = IcedElementTestHarness::new(Overlay(None), (100, 20));
+    assert_eq!(harness.element().z_index(), RenderZindex::Shell as u8);
+}
+
+#[test]
+fn programs_pick_their_z_index() {
+    let harness =
+        IcedElementTestHarness::new(Overlay(Some(RenderZindex::Overlay as u8)), (100, 20));
+    assert_eq!(harness.element().z_index(), RenderZindex::Overlay as u8);
+}
+
+#[test]
+fn overridden_z_index_wins_until_reset() {
+    let harness = IcedElementTestHarness::new(Overlay(Some(RenderZindex::Top as u8)), (100, 20));
+    let element = harness.element();
+
+    element.set_z_index(RenderZindex::Bottom as u8);
+    assert_eq!(element.z_index(), RenderZindex::Bottom as u8);
+    element.reset_z_index();
+    assert_eq!(element.z_index(), RenderZindex::Top as u8);
+}
@@ -0,0 +1,33 @@
+//! The default theme of all `IcedElement`s, following the desktop's light or dark theme.
+//!
+//! Themes don't implement `PartialEq`, so they are compared by the colors elements use.
+
+use std::sync::Mutex;
+
+use cosmic::{iced_native::Color, Theme};
+
+use super::{lock_global, palette::HookPalette};
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_THEME: Mutex<Theme> = Mutex::new(Theme::dark());
+}
+
+pub(super) fn default_theme() -> Theme {
+    lock_global(&DEFAULT_THEME).clone()
+}
+
+/// Stores the global default, returns false if it didn't change.
+pub(super) fn store_default(theme: Theme) -> bool {
+    let mut default = lock_global(&DEFAULT_THEME);
+    if same_colors(&default, &theme) {
+        return false;
+    }
+    *default = theme;
+    true
+}
+
+/// Whether elements look the same with either theme.
+pub(super) fn same_colors(a: &Theme, b: &Theme) -> bool {
+    let text_color = |theme: &Theme| -> Color { theme.cosmic().on_bg_color().into() };
+    HookPalette::new(a) == HookPalette::new(b) && text_color(a) == text_color(b)
+}
@@ -0,0 +1,64 @@
+//! Conversion of touch points into iced touch events.
+//!
+//! Slots are reused by the device as soon as a finger is lifted, so every touch point gets its
+//! own finger id instead. Widgets tracking a finger never confuse it with a later one.
+
+use std::collections::HashMap;
+
+use cosmic::iced_native::{
+    touch::{Event as TouchEvent, Finger},
+    Point as IcedPoint,
+};
+use smithay::{
+    backend::input::TouchSlot,
+    utils::{Logical, Point},
+};
+
+#[derive(Debug, Default)]
+pub(super) struct TouchPoints {
+    next_finger: u64,
+    /// Finger and last position of every slot currently touching the element
+    active: HashMap<TouchSlot, (Finger, IcedPoint)>,
+}
+
+fn position(location: Point<f64, Logical>) -> IcedPoint {
+    IcedPoint::new(location.x as f32, location.y as f32)
+}
+
+impl TouchPoints {
+    pub fn down(&mut self, slot: TouchSlot, location: Point<f64, Logical>) -> Vec<TouchEvent> {
+        let mut events = Vec::with_capacity(2);
+        // a missed up of the same slot
+        if let Some((id, position)) = self.active.remove(&slot) {
+            events.push(TouchEvent::FingerLost { id, position });
+        }
+        self.next_finger += 1;
+        let id = Finger(self.next_finger);
+        let position = position(location);
+        self.active.insert(slot, (id, position));
+        events.push(TouchEvent::FingerPressed { id, position });
+        events
+    }
+
+    pub fn motion(&mut self, slot: TouchSlot, location: Point<f64, Logical>) -> Option<TouchEvent> {
+        let (id, last) = self.active.get_mut(&slot)?;
+        *last = position(location);
+        Some(TouchEvent::FingerMoved {
+            id: *id,
+            position: *last,
+        })
+    }
+
+    pub fn up(&mut self, slot: TouchSlot) -> Option<TouchEvent> {
+        let (id, position) = self.active.remove(&slot)?;
+        Some(TouchEvent::FingerLifted { id, position })
+    }
+
+    /// Loses all fingers, e.g. once the compositor took over a gesture.
+    pub fn cancel(&mut self) -> Vec<TouchEvent> {
+        self.active
+            .drain()
+            .map(|(_, (id, position))| TouchEvent::FingerLost { id, position })
+            .collect()
+    }
+}
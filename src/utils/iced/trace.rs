@@ -0,0 +1,215 @@
+//! End-to-end traces of input events, from being queued until the frame showing their effect.
+//!
+//! Every traced event gets an id and timestamps for the stages it passes:
+//! - `queued` once delivered to the element,
+//! - `updated` by the update consuming it, all events queued in the meantime share that update,
+//! - `rasterized` by the first draw after that update, `None` if the update didn't change the
+//!   element's content,
+//! - `imported` by the render showing the result.
+//!
+//! Stages postponed e.g. by `Program::max_fps` are annotated with the reason, see `Deferral`.
+//! Completed traces are kept in a bounded ring per element, see `IcedElement::trace_recent`.
+//! Nothing is traced during secure entry, as the timing of input reveals what is typed.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use smithay::utils::Serial;
+
+/// Completed traces kept per element
+const TRACE_CAPACITY: usize = 128;
+/// Traced events not yet shown, older ones are dropped once exceeded
+const PENDING_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TracedInput {
+    Motion,
+    Button,
+    Axis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceStage {
+    Update,
+    Raster,
+}
+
+/// Why a stage didn't immediately follow the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deferral {
+    /// Updates are merged while waiting for the frame callback of the last frame
+    AwaitingFrame,
+    /// Bursts of high-frequency input are coalesced into one update
+    Coalesced,
+    /// `Program::max_fps`
+    RateLimited,
+    /// Sandboxed element over its draw budget
+    Throttled,
+    /// Drawn by a job of the element's render pool, shown by a later render
+    RenderPool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventTrace {
+    pub id: u64,
+    pub input: TracedInput,
+    /// Serial of the event, if it had one
+    pub serial: Option<Serial>,
+    pub queued: Instant,
+    pub updated: Option<Instant>,
+    pub rasterized: Option<Instant>,
+    pub imported: Option<Instant>,
+    /// Postponed stages in order, a stage may be postponed repeatedly for different reasons
+    pub deferrals: Vec<(TraceStage, Deferral)>,
+}
+
+impl EventTrace {
+    /// Time from queuing the event until the frame showing its effect was imported.
+    pub fn latency(&self) -> Option<Duration> {
+        Some(self.imported?.duration_since(self.queued))
+    }
+
+    fn defer(&mut self, stage: TraceStage, reason: Deferral) {
+        if self.deferrals.last() != Some(&(stage, reason)) {
+            self.deferrals.push((stage, reason));
+        }
+    }
+}
+
+/// Distribution of input-to-import latencies, see `IcedElement::input_latency`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencyStats {
+    pub count: usize,
+    pub min: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    pub fn from_traces<'a>(traces: impl IntoIterator<Item = &'a EventTrace>) -> LatencyStats {
+        let mut latencies = traces
+            .into_iter()
+            .filter_map(EventTrace::latency)
+            .collect::<Vec<_>>();
+        if latencies.is_empty() {
+            return LatencyStats::default();
+        }
+        latencies.sort_unstable();
+        let percentile = |p: usize| latencies[(latencies.len() - 1) * p / 100];
+        LatencyStats {
+            count: latencies.len(),
+            min: latencies[0],
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: latencies[latencies.len() - 1],
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct EventTracer {
+    next_id: u64,
+    /// Traces not yet imported, oldest first
+    pending: VecDeque<EventTrace>,
+    completed: VecDeque<EventTrace>,
+    suspended: bool,
+}
+
+impl EventTracer {
+    /// Stops or resumes tracing, dropping the traces not yet shown when stopped.
+    pub fn set_suspended(&mut self, suspended: bool) {
+        if suspended {
+            self.pending.clear();
+        }
+        self.suspended = suspended;
+    }
+
+    pub fn queued(&mut self, input: TracedInput, serial: Option<Serial>) {
+        if self.suspended {
+            return;
+        }
+        self.next_id += 1;
+        if self.pending.len() == PENDING_CAPACITY {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(EventTrace {
+            id: self.next_id,
+            input,
+            serial,
+            queued: Instant::now(),
+            updated: None,
+            rasterized: None,
+            imported: None,
+            deferrals: Vec::new(),
+        });
+    }
+
+    /// The update of all queued events was postponed.
+    pub fn update_deferred(&mut self, reason: Deferral) {
+        for trace in self.awaiting_update() {
+            trace.defer(TraceStage::Update, reason);
+        }
+    }
+
+    pub fn updated(&mut self, now: Instant) {
+        for trace in self.awaiting_update() {
+            trace.updated = Some(now);
+        }
+    }
+
+    /// The draw after the last update was postponed.
+    pub fn raster_deferred(&mut self, reason: Deferral) {
+        for trace in self.awaiting_raster() {
+            trace.defer(TraceStage::Raster, reason);
+        }
+    }
+
+    pub fn rasterized(&mut self, now: Instant) {
+        for trace in self.awaiting_raster() {
+            trace.rasterized = Some(now);
+        }
+    }
+
+    /// A frame showing all updates so far was imported.
+    pub fn imported(&mut self, now: Instant) {
+        while self
+            .pending
+            .front()
+            .map_or(false, |trace| trace.updated.is_some())
+        {
+            let mut trace = self.pending.pop_front().unwrap();
+            trace.imported = Some(now);
+            if self.completed.len() == TRACE_CAPACITY {
+                self.completed.pop_front();
+            }
+            self.completed.push_back(trace);
+        }
+    }
+
+    /// Up to `n` of the most recently completed traces, oldest first.
+    pub fn recent(&self, n: usize) -> Vec<EventTrace> {
+        let skip = self.completed.len().saturating_sub(n);
+        self.completed.iter().skip(skip).cloned().collect()
+    }
+
+    pub fn latency(&self) -> LatencyStats {
+        LatencyStats::from_traces(&self.completed)
+    }
+
+    fn awaiting_update(&mut self) -> impl Iterator<Item = &mut EventTrace> {
+        self.pending
+            .iter_mut()
+            .filter(|trace| trace.updated.is_none())
+    }
+
+    fn awaiting_raster(&mut self) -> impl Iterator<Item = &mut EventTrace> {
+        self.pending
+            .iter_mut()
+            .filter(|trace| trace.updated.is_some() && trace.rasterized.is_none())
+    }
+}
@@ -0,0 +1,292 @@
+//! Enter/exit transitions of elements moving between spaces, e.g. on workspace switches,
+//! and transitions between programs swapped at runtime.
+//!
+//! Transitions only change the parameters of the render element (location, alpha, size),
+//! the element's buffers are never redrawn for them. Their timing is a `Curve`, see
+//! `animation`.
+
+use std::time::{Duration, Instant};
+
+use smithay::{
+    backend::renderer::element::memory::MemoryRenderBuffer,
+    utils::{Logical, Point, Size},
+};
+
+use super::animation::{Animation, Curve};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlideDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionKind {
+    /// Slides in from (or out to) `direction` over `distance` logical pixels
+    Slide {
+        direction: SlideDirection,
+        distance: i32,
+    },
+    Fade,
+    /// Scales from (or to) the given factor around the element's center
+    Scale(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionSpec {
+    pub kind: TransitionKind,
+    /// Easing when driven by the element's own clock, see `IcedElement::set_transition_progress`
+    pub curve: Curve,
+    /// Duration of bezier curves, springs run until they settle
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Phase {
+    Enter,
+    Exit,
+}
+
+impl Phase {
+    /// Visibility once complete
+    fn target(self) -> f32 {
+        match self {
+            Phase::Enter => 1.0,
+            Phase::Exit => 0.0,
+        }
+    }
+}
+
+/// Render element parameters of a running transition.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct TransitionParams {
+    pub offset: Point<f64, Logical>,
+    pub alpha: f32,
+    pub scale: f64,
+}
+
+impl Default for TransitionParams {
+    fn default() -> Self {
+        TransitionParams {
+            offset: Point::from((0.0, 0.0)),
+            alpha: 1.0,
+            scale: 1.0,
+        }
+    }
+}
+
+/// Source of a transition's visibility, from `0.0` hidden to `1.0` fully shown.
+#[derive(Debug, Clone, Copy)]
+enum Clock {
+    /// Animated by the element, at its last tick
+    Internal {
+        visibility: Animation,
+        now: Instant,
+    },
+    External(f32),
+}
+
+pub(super) struct SpaceTransition {
+    pub phase: Phase,
+    spec: TransitionSpec,
+    clock: Clock,
+    on_complete: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl std::fmt::Debug for SpaceTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpaceTransition")
+            .field("phase", &self.phase)
+            .field("spec", &self.spec)
+            .field("clock", &self.clock)
+            .field("on_complete", &self.on_complete.is_some())
+            .finish()
+    }
+}
+
+impl SpaceTransition {
+    /// Starts a transition, continuing from the visual state of a `previous` one.
+    ///
+    /// A running animation is retargeted, keeping the velocity of springs.
+    pub fn new(
+        phase: Phase,
+        spec: TransitionSpec,
+        previous: Option<SpaceTransition>,
+        on_complete: Option<Box<dyn FnOnce() + Send>>,
+    ) -> SpaceTransition {
+        let now = Instant::now();
+        let target = phase.target();
+        let clock = match previous.map(|previous| previous.clock) {
+            Some(Clock::Internal { mut visibility, .. }) => {
+                visibility.retarget_with(spec.curve, spec.duration, target, now);
+                Clock::Internal { visibility, now }
+            }
+            Some(Clock::External(visibility)) => Clock::External(visibility),
+            None => Clock::Internal {
+                visibility: Animation::new(spec.curve, spec.duration, 1.0 - target, target, now),
+                now,
+            },
+        };
+        SpaceTransition {
+            phase,
+            spec,
+            clock,
+            on_complete,
+        }
+    }
+
+    /// Switches to externally driven timing and sets the progress.
+    pub fn set_progress(&mut self, progress: f32) {
+        let progress = progress.clamp(0.0, 1.0);
+        self.clock = Clock::External(match self.phase {
+            Phase::Enter => progress,
+            Phase::Exit => 1.0 - progress,
+        });
+    }
+
+    /// Advances the internal clock, if the transition isn't driven externally.
+    pub fn tick(&mut self, now: Instant) {
+        if let Clock::Internal { now: last_tick, .. } = &mut self.clock {
+            *last_tick = now;
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        match self.clock {
+            Clock::Internal { visibility, now } => visibility.is_complete(now),
+            Clock::External(visibility) => visibility == self.phase.target(),
+        }
+    }
+
+    pub fn take_on_complete(&mut self) -> Option<Box<dyn FnOnce() + Send>> {
+        self.on_complete.take()
+    }
+
+    pub fn params(&self) -> TransitionParams {
+        // springs may overshoot, moving the element past its position
+        let visibility = match self.clock {
+            Clock::Internal { visibility, now } => visibility.value(now),
+            Clock::External(visibility) => visibility,
+        };
+        let remaining = (1.0 - visibility) as f64;
+        let mut params = TransitionParams::default();
+        match self.spec.kind {
+            TransitionKind::Slide {
+                direction,
+                distance,
+            } => {
+                let distance = distance as f64 * remaining;
+                params.offset = match direction {
+                    SlideDirection::Left => (-distance, 0.0),
+                    SlideDirection::Right => (distance, 0.0),
+                    SlideDirection::Up => (0.0, -distance),
+                    SlideDirection::Down => (0.0, distance),
+                }
+                .into();
+            }
+            TransitionKind::Fade => params.alpha = visibility.clamp(0.0, 1.0),
+            TransitionKind::Scale(from) => {
+                params.scale = 1.0 + (from - 1.0) * remaining;
+                params.alpha = visibility.clamp(0.0, 1.0);
+            }
+        }
+        params
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapKind {
+    Crossfade,
+    /// The new content slides in from `direction`, pushing out the old content
+    Slide(SlideDirection),
+}
+
+/// How the old content is shown, if its size differs from the new content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapSizing {
+    /// Keeps its size, anchored at the top-left corner
+    Align,
+    /// Scaled to the size of the new content
+    Stretch,
+}
+
+/// Transition between the old and new program, see `IcedElement::replace_program_with_transition`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapTransition {
+    pub kind: SwapKind,
+    pub sizing: SwapSizing,
+    pub curve: Curve,
+    /// Duration of bezier curves, springs run until they settle
+    pub duration: Duration,
+}
+
+/// A running program swap, holding the last buffers of the old program.
+pub(super) struct ProgramSwap {
+    pub spec: SwapTransition,
+    /// Logical size of the old content
+    pub old_size: Size<i32, Logical>,
+    pub old_buffers: Vec<(f64, MemoryRenderBuffer)>,
+    progress: Animation,
+}
+
+impl std::fmt::Debug for ProgramSwap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgramSwap")
+            .field("spec", &self.spec)
+            .field("old_size", &self.old_size)
+            .field("progress", &self.progress)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProgramSwap {
+    pub fn new(
+        spec: SwapTransition,
+        old_size: Size<i32, Logical>,
+        old_buffers: Vec<(f64, MemoryRenderBuffer)>,
+    ) -> ProgramSwap {
+        ProgramSwap {
+            spec,
+            old_size,
+            old_buffers,
+            progress: Animation::new(spec.curve, spec.duration, 0.0, 1.0, Instant::now()),
+        }
+    }
+
+    pub fn is_complete(&self, now: Instant) -> bool {
+        self.progress.is_complete(now)
+    }
+
+    /// Render element parameters of the old and the new content, in that order.
+    pub fn params(
+        &self,
+        now: Instant,
+        size: Size<i32, Logical>,
+    ) -> (TransitionParams, TransitionParams) {
+        let progress = self.progress.value(now);
+        let mut old = TransitionParams::default();
+        let mut new = TransitionParams::default();
+        match self.spec.kind {
+            SwapKind::Crossfade => {
+                let progress = progress.clamp(0.0, 1.0);
+                old.alpha = 1.0 - progress;
+                new.alpha = progress;
+            }
+            SwapKind::Slide(direction) => {
+                let size = size.to_f64();
+                let (x, y) = match direction {
+                    SlideDirection::Left => (-size.w, 0.0),
+                    SlideDirection::Right => (size.w, 0.0),
+                    SlideDirection::Up => (0.0, -size.h),
+                    SlideDirection::Down => (0.0, size.h),
+                };
+                let progress = progress as f64;
+                new.offset = Point::from((x, y)).upscale(1.0 - progress);
+                old.offset = Point::from((-x, -y)).upscale(progress);
+            }
+        }
+        (old, new)
+    }
+}
@@ -0,0 +1,248 @@
+//! Text truncated to its layout bounds, see `truncating_text`.
+//!
+//! Widths are measured with the renderer's own `measure`, i.e. the same font metrics and shaping
+//! used to draw the text, at draw time against the current layout bounds. So relayouts and font
+//! changes re-truncate, and the ellipsized text never exceeds its bounds.
+//!
+//! Truncated texts are reported to the element drawing them, which offers their full text for
+//! tooltips via `IcedElement::full_text_at`. Texts fitting their bounds are not reported.
+//!
+//! Truncation operates on the logical order of characters, so right-to-left text loses its logical
+//! end (visually its left side) with `TruncatePolicy::End`.
+
+use std::cell::RefCell;
+
+use cosmic::iced_native::{
+    alignment::{Horizontal, Vertical},
+    layout, renderer,
+    text::{self, Text},
+    widget::Tree,
+    Color, Layout, Length, Point, Rectangle, Size, Widget,
+};
+use smithay::utils::{Logical, Rectangle as SmithayRectangle};
+
+const ELLIPSIS: &str = "…";
+
+/// Which part of a text is replaced by an ellipsis, if it doesn't fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncatePolicy {
+    /// "~/Documents/work/proj…"
+    #[default]
+    End,
+    /// "~/Documents/…/src/main.rs"
+    Middle,
+    /// "…/project/src/main.rs", never cutting into the extension of the last path component
+    StartPreserveExtension,
+}
+
+/// Full text of a truncated text, and where it was drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct TruncatedText {
+    pub bounds: SmithayRectangle<i32, Logical>,
+    pub full: String,
+}
+
+thread_local! {
+    static TRUNCATED: RefCell<Option<Vec<TruncatedText>>> = RefCell::new(None);
+}
+
+/// Runs `draw`, collecting all texts truncated while it runs.
+pub(super) fn collect<T>(draw: impl FnOnce() -> T) -> (T, Vec<TruncatedText>) {
+    let previous = TRUNCATED.with(|t| t.borrow_mut().replace(Vec::new()));
+    let result = draw();
+    let truncated = TRUNCATED.with(|t| std::mem::replace(&mut *t.borrow_mut(), previous));
+    (result, truncated.unwrap_or_default())
+}
+
+fn report(bounds: Rectangle, full: &str) {
+    TRUNCATED.with(|t| {
+        if let Some(truncated) = t.borrow_mut().as_mut() {
+            truncated.push(TruncatedText {
+                bounds: SmithayRectangle::from_loc_and_size(
+                    (bounds.x.floor() as i32, bounds.y.floor() as i32),
+                    (bounds.width.ceil() as i32, bounds.height.ceil() as i32),
+                ),
+                full: full.to_owned(),
+            });
+        }
+    });
+}
+
+/// Byte offset of the start of the `n`th character of `text`.
+fn offset_of(text: &str, n: usize) -> usize {
+    text.char_indices().nth(n).map_or(text.len(), |(i, _)| i)
+}
+
+/// `full` with all but `keep` characters replaced by an ellipsis according to `policy`.
+fn ellipsize(full: &str, chars: usize, keep: usize, policy: TruncatePolicy) -> String {
+    match policy {
+        TruncatePolicy::End => format!("{}{}", &full[..offset_of(full, keep)], ELLIPSIS),
+        TruncatePolicy::Middle => {
+            let head = (keep + 1) / 2;
+            format!(
+                "{}{}{}",
+                &full[..offset_of(full, head)],
+                ELLIPSIS,
+                &full[offset_of(full, chars - (keep - head))..]
+            )
+        }
+        TruncatePolicy::StartPreserveExtension => {
+            format!("{}{}", ELLIPSIS, &full[offset_of(full, chars - keep)..])
+        }
+    }
+}
+
+/// Number of trailing characters `StartPreserveExtension` has to keep.
+fn extension_chars(full: &str) -> usize {
+    let name = full.rsplit('/').next().unwrap_or(full);
+    match name.rfind('.') {
+        // hidden files like ".bashrc" don't have an extension
+        Some(0) | None => 0,
+        Some(dot) => name[dot..].chars().count(),
+    }
+}
+
+/// `full`, truncated according to `policy` to fit into `max_width` as measured by `measure`.
+///
+/// Returns `None` if `full` fits as is.
+fn truncate(
+    full: &str,
+    policy: TruncatePolicy,
+    max_width: f32,
+    measure: impl Fn(&str) -> f32,
+) -> Option<String> {
+    if measure(full) <= max_width {
+        return None;
+    }
+    let chars = full.chars().count();
+    let min_keep = match policy {
+        TruncatePolicy::StartPreserveExtension => extension_chars(full),
+        _ => 0,
+    };
+    // fewest characters kept, if even those don't fit only the ellipsis is left
+    let fits = |keep: usize| measure(&ellipsize(full, chars, keep, policy)) <= max_width;
+    if !fits(min_keep) {
+        return Some(String::from(ELLIPSIS));
+    }
+    // widths only grow with the number of kept characters
+    let (mut low, mut high) = (min_keep, chars.saturating_sub(1));
+    while low < high {
+        let mid = (low + high + 1) / 2;
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Some(ellipsize(full, chars, low, policy))
+}
+
+/// Single line of text, truncated with an ellipsis according to `policy` if it doesn't fit.
+pub struct TruncatingText<Renderer: text::Renderer> {
+    full: String,
+    policy: TruncatePolicy,
+    size: Option<u16>,
+    font: Renderer::Font,
+    color: Option<Color>,
+    width: Length,
+}
+
+/// Single line of text truncated to its bounds, see `TruncatingText`.
+pub fn truncating_text<Renderer: text::Renderer>(
+    full: &str,
+    policy: TruncatePolicy,
+) -> TruncatingText<Renderer> {
+    TruncatingText {
+        full: full.to_owned(),
+        policy,
+        size: None,
+        font: Default::default(),
+        color: None,
+        width: Length::Fill,
+    }
+}
+
+impl<Renderer: text::Renderer> TruncatingText<Renderer> {
+    pub fn size(mut self, size: u16) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn font(mut self, font: impl Into<Renderer::Font>) -> Self {
+        self.font = font.into();
+        self
+    }
+
+    /// Defaults to the text color of the style the text is drawn with.
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn width(mut self, width: Length) -> Self {
+        self.width = width;
+        self
+    }
+
+    fn measure(&self, renderer: &Renderer, content: &str) -> (f32, f32) {
+        let size = self.size.unwrap_or_else(|| renderer.default_size());
+        renderer.measure(content, size, self.font.clone(), Size::INFINITY)
+    }
+}
+
+impl<Message, Renderer: text::Renderer> Widget<Message, Renderer> for TruncatingText<Renderer> {
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(self.width).height(Length::Shrink);
+        let (width, height) = self.measure(renderer, &self.full);
+        layout::Node::new(limits.resolve(Size::new(width, height)))
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let truncated = truncate(&self.full, self.policy, bounds.width, |content| {
+            self.measure(renderer, content).0
+        });
+        if truncated.is_some() {
+            report(bounds, &self.full);
+        }
+        renderer.fill_text(Text {
+            content: truncated.as_deref().unwrap_or(&self.full),
+            bounds: Rectangle {
+                y: bounds.center_y(),
+                ..bounds
+            },
+            size: self.size.unwrap_or_else(|| renderer.default_size()) as f32,
+            color: self.color.unwrap_or(style.text_color),
+            font: self.font.clone(),
+            horizontal_alignment: Horizontal::Left,
+            vertical_alignment: Vertical::Center,
+        });
+    }
+}
+
+impl<'a, Message, Renderer> From<TruncatingText<Renderer>>
+    for cosmic::iced::Element<'a, Message, Renderer>
+where
+    Renderer: text::Renderer + 'a,
+{
+    fn from(text: TruncatingText<Renderer>) -> Self {
+        cosmic::iced::Element::new(text)
+    }
+}
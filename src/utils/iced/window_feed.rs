@@ -0,0 +1,79 @@
+//! Metadata of the focused toplevel, published once by the compositor for all bound elements.
+
+use std::sync::{Arc, Mutex};
+
+use smithay::output::Output;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FocusedWindowInfo {
+    pub app_id: String,
+    pub title: String,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    pub output: Option<Output>,
+}
+
+impl FocusedWindowInfo {
+    /// The part of the info restricted elements may see.
+    pub fn redacted(&self) -> FocusedWindowInfo {
+        FocusedWindowInfo {
+            app_id: self.app_id.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Delivers an info to a bound element, returns false once the element is gone.
+pub(super) type Binding = Arc<dyn Fn(&FocusedWindowInfo) -> bool + Send + Sync>;
+
+#[derive(Default)]
+pub struct WindowFeed {
+    current: Mutex<FocusedWindowInfo>,
+    bindings: Mutex<Vec<Binding>>,
+}
+
+lazy_static::lazy_static! {
+    static ref WINDOW_FEED: WindowFeed = WindowFeed::default();
+}
+
+/// The compositor-wide feed of the focused window.
+pub fn window_feed() -> &'static WindowFeed {
+    &WINDOW_FEED
+}
+
+impl WindowFeed {
+    /// Publishes the focused window, or `FocusedWindowInfo::default()` if nothing is focused.
+    pub fn publish(&self, info: FocusedWindowInfo) {
+        {
+            let mut current = self.current.lock().unwrap();
+            if *current == info {
+                return;
+            }
+            *current = info.clone();
+        }
+        // bindings call into elements, which may publish themselves
+        let bindings = self.bindings.lock().unwrap().clone();
+        let mut gone = Vec::new();
+        for binding in bindings {
+            if !binding(&info) {
+                gone.push(binding);
+            }
+        }
+        if !gone.is_empty() {
+            self.bindings
+                .lock()
+                .unwrap()
+                .retain(|binding| !gone.iter().any(|gone| Arc::ptr_eq(gone, binding)));
+        }
+    }
+
+    pub fn current(&self) -> FocusedWindowInfo {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Adds a binding and delivers the current info to it.
+    pub(super) fn bind(&self, binding: Binding) {
+        self.bindings.lock().unwrap().push(binding.clone());
+        binding(&self.current());
+    }
+}